@@ -0,0 +1,46 @@
+## Navigation
+nav-home = Start
+nav-packages = Pakete
+nav-security = Sicherheit
+nav-analytics = Statistiken
+nav-ecosystems = Ökosysteme
+nav-subscriptions = Abonnements
+nav-api = API
+nav-login = Anmelden
+nav-logout = Abmelden
+nav-get-started = Loslegen
+nav-logged-out = Erfolgreich abgemeldet
+nav-theme-light = Zum hellen Design wechseln
+nav-theme-dark = Zum dunklen Design wechseln
+nav-language = Sprache
+
+## Offline banner
+offline-age-under-minute = weniger als einer Minute
+offline-age-minutes = { $count ->
+    [one] einer Minute
+   *[other] { $count } Minuten
+}
+offline-age-hours = { $count ->
+    [one] einer Stunde
+   *[other] { $count } Stunden
+}
+offline-stale = Server nicht erreichbar. Zwischengespeicherte Daten von vor { $age }.
+offline-clear-cache = Cache leeren
+
+## Subscriptions
+subscriptions-title = Meine Abonnements
+subscriptions-subtitle = Verwalte die Pakete, denen du folgst, um Updates zu erhalten
+subscriptions-desktop-title = Desktop-Benachrichtigungen
+subscriptions-desktop-toggle = Updates zu Paketen mit aktivierten Benachrichtigungen als Systembenachrichtigung anzeigen
+subscriptions-profile-title = Öffentliches Profil
+subscriptions-profile-public = Jeder kann mein Profil sehen
+subscriptions-profile-show-follows = Die Pakete anzeigen, denen ich folge
+subscriptions-profile-view = Mein Profil ansehen
+subscriptions-empty-title = Noch keine Abonnements
+subscriptions-empty-body = Folge Paketen, um über neue Versionen und Änderungen informiert zu werden
+subscriptions-browse = Pakete durchsuchen
+subscriptions-view-repository = Repository ansehen
+subscriptions-email-notifications = E-Mail-Benachrichtigungen
+subscriptions-unsubscribe = Abbestellen
+subscriptions-unsubscribed = { $package } abbestellt
+subscriptions-undo = Rückgängig
@@ -0,0 +1,46 @@
+## Navigation
+nav-home = Home
+nav-packages = Packages
+nav-security = Security
+nav-analytics = Analytics
+nav-ecosystems = Ecosystems
+nav-subscriptions = Subscriptions
+nav-api = API
+nav-login = Login
+nav-logout = Logout
+nav-get-started = Get Started
+nav-logged-out = Logged out successfully
+nav-theme-light = Switch to light theme
+nav-theme-dark = Switch to dark theme
+nav-language = Language
+
+## Offline banner
+offline-age-under-minute = less than a minute
+offline-age-minutes = { $count ->
+    [one] 1 minute
+   *[other] { $count } minutes
+}
+offline-age-hours = { $count ->
+    [one] 1 hour
+   *[other] { $count } hours
+}
+offline-stale = Can't reach the server. Showing cached data from { $age } ago.
+offline-clear-cache = Clear cache
+
+## Subscriptions
+subscriptions-title = My Subscriptions
+subscriptions-subtitle = Manage packages you're following to receive updates
+subscriptions-desktop-title = Desktop Notifications
+subscriptions-desktop-toggle = Show updates to packages with notifications turned on as system notifications
+subscriptions-profile-title = Public Profile
+subscriptions-profile-public = Anyone can view my profile
+subscriptions-profile-show-follows = Show the packages I follow
+subscriptions-profile-view = View my profile
+subscriptions-empty-title = No Subscriptions Yet
+subscriptions-empty-body = Start following packages to get updates on new versions and changes
+subscriptions-browse = Browse Packages
+subscriptions-view-repository = View Repository
+subscriptions-email-notifications = Email Notifications
+subscriptions-unsubscribe = Unsubscribe
+subscriptions-unsubscribed = Unsubscribed from { $package }
+subscriptions-undo = Undo
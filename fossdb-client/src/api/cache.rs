@@ -0,0 +1,66 @@
+// Response cache kept in localStorage so recently viewed data survives network failures
+use chrono::{DateTime, Duration, Utc};
+use dioxus::prelude::*;
+use gloo_storage::{LocalStorage, Storage};
+use serde::{Deserialize, Serialize};
+
+const KEY_PREFIX: &str = "api_cache:";
+
+/// When the oldest cached response currently on screen was fetched, if the
+/// last request fell back to the cache
+pub static STALE_SINCE: GlobalSignal<Option<DateTime<Utc>>> = Signal::global(|| None);
+
+#[derive(Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub stored_at: DateTime<Utc>,
+    pub body: serde_json::Value,
+}
+
+impl CacheEntry {
+    pub fn is_fresh(&self, ttl: Duration) -> bool {
+        Utc::now() - self.stored_at < ttl
+    }
+}
+
+pub fn get(path: &str) -> Option<CacheEntry> {
+    LocalStorage::get(format!("{}{}", KEY_PREFIX, path)).ok()
+}
+
+pub fn put(path: &str, body: &serde_json::Value) {
+    let key = format!("{}{}", KEY_PREFIX, path);
+    let entry = CacheEntry {
+        stored_at: Utc::now(),
+        body: body.clone(),
+    };
+
+    // Storage is full, so drop every cached response and try once more
+    if LocalStorage::set(&key, &entry).is_err() {
+        clear();
+        let _ = LocalStorage::set(&key, &entry);
+    }
+}
+
+/// Remove every cached response, leaving other localStorage keys alone
+pub fn clear() {
+    let storage = LocalStorage::raw();
+    let keys: Vec<String> = (0..storage.length().unwrap_or(0))
+        .filter_map(|i| storage.key(i).ok().flatten())
+        .filter(|key| key.starts_with(KEY_PREFIX))
+        .collect();
+    for key in keys {
+        let _ = storage.remove_item(&key);
+    }
+}
+
+/// Record whether the data being shown came from the network or the cache
+pub fn mark_stale(stored_at: Option<DateTime<Utc>>) {
+    let current = *STALE_SINCE.peek();
+    let next = match (current, stored_at) {
+        (_, None) => None,
+        (Some(current), Some(stored_at)) => Some(current.min(stored_at)),
+        (None, Some(stored_at)) => Some(stored_at),
+    };
+    if next != current {
+        *STALE_SINCE.write() = next;
+    }
+}
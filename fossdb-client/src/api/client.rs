@@ -1,4 +1,6 @@
+use super::cache;
 use super::types::*;
+use chrono::Duration;
 use gloo_console::log;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
@@ -38,12 +40,20 @@ impl ApiClient {
         self
     }
 
-    async fn request<T: DeserializeOwned>(
-        &self,
-        method: &str,
-        path: &str,
-        body: Option<String>,
-    ) -> Result<T> {
+    /// Full URL of an API path, suitable for sharing outside the app
+    pub fn absolute_url(&self, path: &str) -> String {
+        let origin = if self.base_url.starts_with('/') {
+            web_sys::window()
+                .and_then(|w| w.location().origin().ok())
+                .unwrap_or_default()
+        } else {
+            String::new()
+        };
+        format!("{}{}{}", origin, self.base_url, path)
+    }
+
+    /// Send a request, failing only when the server can't be reached
+    async fn send(&self, method: &str, path: &str, body: Option<String>) -> Result<Response> {
         let url = format!("{}{}", self.base_url, path);
 
         let mut opts = RequestInit::new();
@@ -66,8 +76,10 @@ impl ApiClient {
 
         let window = web_sys::window().ok_or_else(|| JsValue::from_str("No window"))?;
         let resp_value = JsFuture::from(window.fetch_with_request(&request)).await?;
-        let resp: Response = resp_value.dyn_into()?;
+        resp_value.dyn_into()
+    }
 
+    async fn parse<T: DeserializeOwned>(resp: Response) -> Result<T> {
         if !resp.ok() {
             return Err(JsValue::from_str(&format!("HTTP error: {}", resp.status())));
         }
@@ -81,6 +93,44 @@ impl ApiClient {
             .map_err(|e| JsValue::from_str(&format!("Deserialization error: {:?}", e)))
     }
 
+    async fn request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<String>,
+    ) -> Result<T> {
+        Self::parse(self.send(method, path, body).await?).await
+    }
+
+    /// GET through the response cache. Fresh entries skip the network, and
+    /// expired ones are still served if the server can't be reached.
+    async fn cached_get<T: DeserializeOwned>(&self, path: &str, ttl: Duration) -> Result<T> {
+        let cached = cache::get(path);
+        if let Some(entry) = cached.as_ref().filter(|entry| entry.is_fresh(ttl))
+            && let Ok(value) = serde_json::from_value(entry.body.clone())
+        {
+            return Ok(value);
+        }
+
+        let resp = match (self.send("GET", path, None).await, cached) {
+            (Ok(resp), _) if resp.status() < 500 => resp,
+            (Ok(resp), None) => return Self::parse(resp).await,
+            (Err(e), None) => return Err(e),
+            (_, Some(entry)) => {
+                log!(format!("Serving cached response for {}", path));
+                cache::mark_stale(Some(entry.stored_at));
+                return serde_json::from_value(entry.body)
+                    .map_err(|e| JsValue::from_str(&format!("Deserialization error: {}", e)));
+            }
+        };
+
+        let body: serde_json::Value = Self::parse(resp).await?;
+        cache::put(path, &body);
+        cache::mark_stale(None);
+        serde_json::from_value(body)
+            .map_err(|e| JsValue::from_str(&format!("Deserialization error: {}", e)))
+    }
+
     pub async fn login(&self, email: String, password: String) -> Result<AuthResponse> {
         let body = serde_json::to_string(&LoginRequest { email, password })
             .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))?;
@@ -109,6 +159,7 @@ impl ApiClient {
     pub async fn get_packages(
         &self,
         search: Option<String>,
+        sort: Option<String>,
         page: u32,
         limit: u32,
     ) -> Result<PackagesResponse> {
@@ -116,48 +167,98 @@ impl ApiClient {
         if let Some(query) = search {
             path.push_str(&format!("&search={}", query));
         }
+        if let Some(sort) = sort {
+            path.push_str(&format!("&sort={}", sort));
+        }
+        self.cached_get(&path, Duration::minutes(5)).await
+    }
+
+    pub async fn autocomplete_packages(&self, prefix: &str) -> Result<Vec<PackageSuggestion>> {
+        let path = format!(
+            "/packages/autocomplete?q={}&limit=10",
+            js_sys::encode_uri_component(prefix)
+        );
         self.request("GET", &path, None).await
     }
 
-    pub async fn get_package(&self, id: &str) -> Result<Package> {
-        self.request("GET", &format!("/packages/{}", id), None)
+    pub async fn get_package(&self, id: &str) -> Result<PackageResponse> {
+        self.cached_get(&format!("/packages/{}", id), Duration::minutes(10))
             .await
     }
 
+    pub async fn compare_packages(&self, ids: &[u64]) -> Result<Vec<PackageComparison>> {
+        let ids: Vec<String> = ids.iter().map(u64::to_string).collect();
+        self.cached_get(
+            &format!("/packages/compare?ids={}", ids.join(",")),
+            Duration::minutes(10),
+        )
+        .await
+    }
+
     pub async fn get_package_versions(&self, id: &str) -> Result<Vec<PackageVersion>> {
-        self.request("GET", &format!("/packages/{}/versions", id), None)
+        self.cached_get(&format!("/packages/{}/versions", id), Duration::minutes(10))
             .await
     }
 
-    pub async fn get_package_subscribers(&self, id: &str) -> Result<usize> {
-        #[derive(serde::Deserialize)]
-        struct SubscriberCount {
-            count: usize,
+    pub async fn get_dependency_graph(
+        &self,
+        id: u64,
+        version: Option<&str>,
+    ) -> Result<DependencyGraph> {
+        let mut path = format!("/packages/{}/dependencies", id);
+        if let Some(version) = version {
+            path.push_str(&format!("?version={}", version));
         }
-        let result: SubscriberCount = self
-            .request("GET", &format!("/packages/{}/subscribers", id), None)
-            .await?;
-        Ok(result.count)
+        self.request("GET", &path, None).await
+    }
+
+    pub async fn get_package_activity(
+        &self,
+        id: u64,
+        offset: usize,
+        limit: usize,
+    ) -> Result<TimelineResponse> {
+        self.request(
+            "GET",
+            &format!("/packages/{}/activity?offset={}&limit={}", id, offset, limit),
+            None,
+        )
+        .await
+    }
+
+    pub async fn get_package_vulnerabilities(&self, id: u64) -> Result<VulnerabilitiesResponse> {
+        self.request(
+            "GET",
+            &format!("/vulnerabilities?package_id={}&limit=100", id),
+            None,
+        )
+        .await
     }
 
     pub async fn get_subscriptions(&self) -> Result<Vec<SubscriptionResponse>> {
-        self.request("GET", "/users/subscriptions", None).await
+        let response: SubscriptionsResponse =
+            self.request("GET", "/users/subscriptions", None).await?;
+        Ok(response.subscriptions)
     }
 
     pub async fn subscribe(&self, package_name: String) -> Result<()> {
         let body = serde_json::to_string(&SubscriptionRequest { package_name })
             .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))?;
-        self.request("POST", "/users/subscriptions", Some(body))
-            .await
+        let _: SubscriptionsResponse = self
+            .request("POST", "/users/subscriptions", Some(body))
+            .await?;
+        Ok(())
     }
 
     pub async fn unsubscribe(&self, package_name: &str) -> Result<()> {
-        self.request(
-            "DELETE",
-            &format!("/users/subscriptions/{}", package_name),
-            None,
-        )
-        .await
+        let _: SubscriptionsResponse = self
+            .request(
+                "DELETE",
+                &format!("/users/subscriptions/{}", package_name),
+                None,
+            )
+            .await?;
+        Ok(())
     }
 
     pub async fn toggle_notifications(&self, package_name: &str, enabled: bool) -> Result<()> {
@@ -169,12 +270,33 @@ impl ApiClient {
             notifications_enabled: enabled,
         })
         .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))?;
-        self.request(
-            "PUT",
-            &format!("/users/subscriptions/{}/notifications", package_name),
-            Some(body),
-        )
-        .await
+        let _: SubscriptionsResponse = self
+            .request(
+                "PUT",
+                &format!("/users/subscriptions/{}/notifications", package_name),
+                Some(body),
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_public_profile(&self, username: &str) -> Result<PublicProfile> {
+        let path = format!("/users/{}", js_sys::encode_uri_component(username));
+        self.request("GET", &path, None).await
+    }
+
+    pub async fn get_profile_settings(&self) -> Result<ProfileSettings> {
+        self.request("GET", "/users/settings/profile", None).await
+    }
+
+    pub async fn update_profile_settings(
+        &self,
+        settings: &ProfileSettings,
+    ) -> Result<ProfileSettings> {
+        let body = serde_json::to_string(settings)
+            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))?;
+        self.request("PUT", "/users/settings/profile", Some(body))
+            .await
     }
 
     pub async fn get_timeline(&self, offset: usize, limit: usize) -> Result<TimelineResponse> {
@@ -185,6 +307,42 @@ impl ApiClient {
         )
         .await
     }
+
+    pub async fn get_vulnerabilities(
+        &self,
+        severity: Option<String>,
+        page: u32,
+        limit: u32,
+    ) -> Result<VulnerabilitiesResponse> {
+        let mut path = format!("/vulnerabilities?page={}&limit={}", page, limit);
+        if let Some(severity) = severity {
+            path.push_str(&format!("&severity={}", severity));
+        }
+        self.request("GET", &path, None).await
+    }
+
+    pub async fn get_vulnerability(&self, id: u64) -> Result<VulnerabilityDetail> {
+        self.request("GET", &format!("/vulnerabilities/{}", id), None)
+            .await
+    }
+
+    pub async fn get_analytics(&self) -> Result<AnalyticsResponse> {
+        self.request("GET", "/analytics", None).await
+    }
+
+    pub async fn list_platforms(&self) -> Result<Vec<PlatformSummary>> {
+        self.cached_get("/platforms", Duration::minutes(10)).await
+    }
+
+    pub async fn get_platform_stats(&self, name: &str) -> Result<PlatformStats> {
+        self.cached_get(&format!("/platforms/{}/stats", name), Duration::minutes(10))
+            .await
+    }
+
+    pub async fn get_growth(&self, period: &str) -> Result<Vec<GrowthPoint>> {
+        self.request("GET", &format!("/analytics/growth?period={}", period), None)
+            .await
+    }
 }
 
 impl Default for ApiClient {
@@ -1,4 +1,6 @@
+pub mod cache;
 pub mod client;
+pub mod prefetch;
 pub mod types;
 
 pub use client::ApiClient;
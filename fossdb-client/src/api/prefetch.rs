@@ -0,0 +1,75 @@
+// Data needed for a page's first render. Fullstack builds load it on the
+// server and send it along with the rendered page, so hydration doesn't
+// fetch it again.
+use super::types::*;
+use dioxus::prelude::*;
+use std::future::Future;
+
+#[cfg(not(feature = "server"))]
+use super::ApiClient;
+
+/// Load data before rendering, suspending until it's ready
+#[cfg(feature = "fullstack")]
+pub fn use_prefetch<T, F, M>(future: impl FnMut() -> F + 'static) -> Result<Resource<T>, RenderError>
+where
+    F: Future<Output = T> + 'static,
+    T: dioxus::fullstack::Transportable<M>,
+    M: 'static,
+{
+    use_server_future(future)
+}
+
+/// Load data before rendering, suspending until it's ready
+#[cfg(not(feature = "fullstack"))]
+pub fn use_prefetch<T, F>(future: impl FnMut() -> F + 'static) -> Result<Resource<T>, RenderError>
+where
+    F: Future<Output = T> + 'static,
+    T: 'static,
+{
+    let resource = use_resource(future);
+    resource.suspend()?;
+    Ok(resource)
+}
+
+/// API client used while rendering on the server
+#[cfg(feature = "server")]
+fn server_client() -> fossdb_sdk::ApiClient {
+    fossdb_sdk::ApiClient::new(
+        std::env::var("FOSSDB_API_URL").unwrap_or_else(|_| "http://localhost:3000/api".to_string()),
+    )
+}
+
+pub async fn get_package(id: String) -> Option<PackageResponse> {
+    #[cfg(feature = "server")]
+    {
+        server_client().get_package(id.parse().ok()?).await.ok()
+    }
+
+    #[cfg(not(feature = "server"))]
+    {
+        ApiClient::new().get_package(&id).await.ok()
+    }
+}
+
+pub async fn get_packages(
+    search: Option<String>,
+    sort: Option<String>,
+    page: u32,
+    limit: u32,
+) -> Option<PackagesResponse> {
+    #[cfg(feature = "server")]
+    {
+        server_client()
+            .get_packages(search.as_deref(), sort.as_deref(), page, limit)
+            .await
+            .ok()
+    }
+
+    #[cfg(not(feature = "server"))]
+    {
+        ApiClient::new()
+            .get_packages(search, sort, page, limit)
+            .await
+            .ok()
+    }
+}
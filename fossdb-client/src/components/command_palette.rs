@@ -0,0 +1,233 @@
+use crate::api::types::Package;
+use crate::api::ApiClient;
+use crate::components::{use_comparison, Modal};
+use crate::hooks::{use_auth, use_notifications, LocalStorage, StorageKey};
+use crate::Route;
+use dioxus::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+const MAX_RECENT_PACKAGES: usize = 8;
+const MAX_RESULTS: usize = 10;
+
+/// Remember a visited package so the palette can offer it later
+pub fn remember_package(package: &Package) {
+    let mut recent = recent_packages();
+    recent.retain(|p| p.id != package.id);
+    recent.insert(0, package.clone());
+    recent.truncate(MAX_RECENT_PACKAGES);
+    let _ = LocalStorage::set(StorageKey::RecentPackages, &recent);
+}
+
+fn recent_packages() -> Vec<Package> {
+    LocalStorage::get(StorageKey::RecentPackages).unwrap_or_default()
+}
+
+/// Score `text` against `query` as a case-insensitive subsequence, favouring
+/// consecutive characters and matches at word starts. None if it doesn't match.
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous = None;
+
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (position..text.len()).find(|&i| text[i] == q)?;
+        score += 1;
+        if previous == Some(found.wrapping_sub(1)) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        score -= (found - position) as i32;
+        previous = Some(found);
+        position = found + 1;
+    }
+
+    Some(score)
+}
+
+#[derive(Clone, PartialEq)]
+enum Command {
+    Navigate(&'static str, Route),
+    Open(Package),
+    Subscribe(Package),
+    Compare(Package),
+    CopyApiUrl(Package),
+}
+
+impl Command {
+    fn label(&self) -> String {
+        match self {
+            Command::Navigate(name, _) => format!("Go to {}", name),
+            Command::Open(p) => p.name.clone(),
+            Command::Subscribe(p) => format!("Subscribe to {}", p.name),
+            Command::Compare(p) => format!("Compare {}", p.name),
+            Command::CopyApiUrl(p) => format!("Copy API URL for {}", p.name),
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Command::Navigate(..) => "Page",
+            Command::Open(_) => "Recent",
+            _ => "Action",
+        }
+    }
+}
+
+#[component]
+pub fn CommandPalette(show: Signal<bool>) -> Element {
+    let nav = navigator();
+    let auth = use_auth();
+    let mut comparison = use_comparison();
+    let notif = use_notifications();
+    let mut query = use_signal(String::new);
+    let mut selected = use_signal(|| 0usize);
+
+    if !show() {
+        return rsx! {};
+    }
+
+    let is_authenticated = auth.is_authenticated();
+    let mut commands = vec![
+        Command::Navigate("Home", Route::Home {}),
+        Command::Navigate("Packages", Route::Packages { query: Default::default() }),
+        Command::Navigate("Security", Route::Security {}),
+        Command::Navigate("Analytics", Route::Analytics {}),
+        Command::Navigate("API Docs", Route::ApiDocs {}),
+    ];
+    if is_authenticated {
+        commands.push(Command::Navigate("Subscriptions", Route::Subscriptions {}));
+    }
+    for package in recent_packages() {
+        commands.push(Command::Open(package.clone()));
+        if is_authenticated {
+            commands.push(Command::Subscribe(package.clone()));
+        }
+        commands.push(Command::Compare(package.clone()));
+        commands.push(Command::CopyApiUrl(package));
+    }
+
+    let mut matches: Vec<(i32, Command)> = commands
+        .into_iter()
+        .filter_map(|c| fuzzy_score(&query(), &c.label()).map(|score| (score, c)))
+        .collect();
+    matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    let matches: Vec<Command> = matches
+        .into_iter()
+        .take(MAX_RESULTS)
+        .map(|(_, c)| c)
+        .collect();
+    let current = selected().min(matches.len().saturating_sub(1));
+
+    let mut close = move || {
+        show.set(false);
+        query.set(String::new());
+        selected.set(0);
+    };
+
+    let mut run = move |command: Command| {
+        close();
+        let mut notif = notif;
+        match command {
+            Command::Navigate(_, route) => {
+                nav.push(route);
+            }
+            Command::Open(package) => {
+                nav.push(Route::PackageDetail {
+                    id: package.id.to_string(),
+                });
+            }
+            Command::Subscribe(package) => {
+                let token = auth.token();
+                spawn(async move {
+                    let client = ApiClient::new().with_token(token);
+                    if client.subscribe(package.name.clone()).await.is_ok() {
+                        notif.success(format!("Subscribed to {}", package.name));
+                    } else {
+                        notif.error("Failed to subscribe".to_string());
+                    }
+                });
+            }
+            Command::Compare(package) => comparison.add(package),
+            Command::CopyApiUrl(package) => {
+                let url = ApiClient::new().absolute_url(&format!("/packages/{}", package.id));
+                spawn(async move {
+                    let Some(window) = web_sys::window() else {
+                        return;
+                    };
+                    let promise = window.navigator().clipboard().write_text(&url);
+                    if JsFuture::from(promise).await.is_ok() {
+                        notif.success("API URL copied".to_string());
+                    } else {
+                        notif.error("Failed to copy to clipboard".to_string());
+                    }
+                });
+            }
+        }
+    };
+
+    let keyboard_matches = matches.clone();
+    let no_matches = matches.is_empty();
+
+    rsx! {
+        Modal {
+            title: "Command palette",
+            class: "bg-gray-800 rounded-xl w-full max-w-xl shadow-2xl border border-gray-700 overflow-hidden",
+            top: true,
+            on_close: move |_| close(),
+            input {
+                class: "w-full px-4 py-3 bg-gray-800 text-gray-100 border-b border-gray-700 focus:outline-none",
+                placeholder: "Search pages, recent packages and actions...",
+                value: "{query}",
+                onmounted: move |evt| async move {
+                    let _ = evt.set_focus(true).await;
+                },
+                oninput: move |evt| {
+                    query.set(evt.value());
+                    selected.set(0);
+                },
+                onkeydown: move |evt| match evt.key() {
+                    Key::ArrowDown => {
+                        evt.prevent_default();
+                        if current + 1 < keyboard_matches.len() {
+                            selected.set(current + 1);
+                        }
+                    }
+                    Key::ArrowUp => {
+                        evt.prevent_default();
+                        selected.set(current.saturating_sub(1));
+                    }
+                    Key::Enter => {
+                        if let Some(command) = keyboard_matches.get(current) {
+                            run(command.clone());
+                        }
+                    }
+                    _ => {}
+                },
+            }
+            ul { class: "max-h-96 overflow-y-auto py-2",
+                for (i, command) in matches.into_iter().enumerate() {
+                    li {
+                        class: if i == current {
+                            "flex justify-between items-center px-4 py-2 bg-gray-700 cursor-pointer"
+                        } else {
+                            "flex justify-between items-center px-4 py-2 hover:bg-gray-700 cursor-pointer"
+                        },
+                        onmouseenter: move |_| selected.set(i),
+                        onclick: {
+                            let command = command.clone();
+                            move |_| run(command.clone())
+                        },
+                        span { class: "text-gray-100", "{command.label()}" }
+                        span { class: "text-xs text-gray-400", "{command.kind()}" }
+                    }
+                }
+                if no_matches {
+                    li { class: "px-4 py-2 text-gray-400 text-sm", "No matching commands" }
+                }
+            }
+        }
+    }
+}
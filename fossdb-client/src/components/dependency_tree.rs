@@ -0,0 +1,162 @@
+use crate::api::types::{DependencyEdge, DependencyGraph, DependencyNode};
+use crate::api::ApiClient;
+use crate::Route;
+use dioxus::prelude::*;
+
+/// Direct dependencies of a node paired with the edges that reach them
+fn children(graph: &DependencyGraph) -> Vec<(DependencyEdge, DependencyNode)> {
+    graph
+        .edges
+        .iter()
+        .filter(|edge| edge.from == graph.root)
+        .filter_map(|edge| {
+            graph
+                .nodes
+                .get(edge.to)
+                .map(|node| (edge.clone(), node.clone()))
+        })
+        .collect()
+}
+
+/// Dependency tree of a package, loading each transitive level on demand
+#[component]
+pub fn DependencyTree(package_id: u64) -> Element {
+    let mut graph = use_signal(|| None::<DependencyGraph>);
+    let mut loading = use_signal(|| true);
+
+    use_effect(move || {
+        spawn(async move {
+            loading.set(true);
+            if let Ok(g) = ApiClient::new().get_dependency_graph(package_id, None).await {
+                graph.set(Some(g));
+            }
+            loading.set(false);
+        });
+    });
+
+    rsx! {
+        div { class: "bg-gray-800 rounded-2xl shadow-xl p-8 border border-gray-700",
+            h2 { class: "text-2xl font-bold text-gray-100 mb-6", "Dependencies" }
+            if loading() {
+                div { class: "text-gray-400", "Loading..." }
+            } else if let Some(g) = graph() {
+                if let Some(root) = g.nodes.get(g.root) {
+                    if !root.vulnerabilities.is_empty() {
+                        p { class: "text-sm text-red-400 mb-4",
+                            "This version is affected by {root.vulnerabilities.len()} known vulnerabilities"
+                        }
+                    }
+                }
+                DependencyList { entries: children(&g), ancestors: vec![package_id] }
+            } else {
+                div { class: "text-gray-400", "No dependency information available" }
+            }
+        }
+    }
+}
+
+#[component]
+fn DependencyList(entries: Vec<(DependencyEdge, DependencyNode)>, ancestors: Vec<u64>) -> Element {
+    if entries.is_empty() {
+        return rsx! {
+            div { class: "text-sm text-gray-500 py-1", "No dependencies" }
+        };
+    }
+
+    rsx! {
+        ul { class: "space-y-1",
+            for (edge, node) in entries {
+                DependencyItem { key: "{node.id}-{node.name}", edge, node, ancestors: ancestors.clone() }
+            }
+        }
+    }
+}
+
+#[component]
+fn DependencyItem(edge: DependencyEdge, node: DependencyNode, ancestors: Vec<u64>) -> Element {
+    let mut expanded = use_signal(|| false);
+    let mut children_of = use_signal(|| None::<Vec<(DependencyEdge, DependencyNode)>>);
+
+    // Unresolved nodes and cycles back to an ancestor can't be expanded
+    let expandable = node.version.is_some()
+        && node
+            .package_id
+            .is_some_and(|id| !ancestors.contains(&id));
+    let vulnerable = !node.vulnerabilities.is_empty();
+
+    let package_id = node.package_id;
+    let version = node.version.clone();
+    let toggle = move |_| {
+        expanded.set(!expanded());
+        if !expanded() || children_of().is_some() {
+            return;
+        }
+        let (Some(id), Some(version)) = (package_id, version.clone()) else {
+            return;
+        };
+        spawn(async move {
+            if let Ok(g) = ApiClient::new()
+                .get_dependency_graph(id, Some(&version))
+                .await
+            {
+                children_of.set(Some(children(&g)));
+            }
+        });
+    };
+
+    let mut child_ancestors = ancestors.clone();
+    child_ancestors.extend(node.package_id);
+
+    rsx! {
+        li {
+            div {
+                class: if vulnerable {
+                    "flex items-center gap-2 px-2 py-1 rounded bg-red-900/40 border border-red-700"
+                } else {
+                    "flex items-center gap-2 px-2 py-1 rounded hover:bg-gray-700"
+                },
+                if expandable {
+                    button {
+                        class: "w-5 text-gray-400 hover:text-gray-100",
+                        onclick: toggle,
+                        if expanded() { "▾" } else { "▸" }
+                    }
+                } else {
+                    span { class: "w-5" }
+                }
+                if let Some(id) = node.package_id {
+                    Link {
+                        to: Route::PackageDetail { id: id.to_string() },
+                        class: "text-blue-400 hover:text-blue-300 font-medium",
+                        "{node.name}"
+                    }
+                } else {
+                    span { class: "text-gray-300 font-medium", "{node.name}" }
+                }
+                span { class: "text-sm text-gray-400 font-mono",
+                    "{node.version.as_deref().unwrap_or(&edge.version_requirement)}"
+                }
+                if edge.dependency_type != "normal" {
+                    span { class: "px-2 text-xs bg-gray-700 text-gray-300 rounded", "{edge.dependency_type}" }
+                }
+                if edge.optional {
+                    span { class: "px-2 text-xs bg-gray-700 text-gray-300 rounded", "optional" }
+                }
+                if vulnerable {
+                    span { class: "px-2 text-xs bg-red-600 text-white rounded",
+                        "{node.vulnerabilities.len()} vulnerable"
+                    }
+                }
+            }
+            if expanded() {
+                div { class: "ml-6 mt-1 border-l border-gray-700 pl-2",
+                    if let Some(entries) = children_of() {
+                        DependencyList { entries, ancestors: child_ancestors }
+                    } else {
+                        div { class: "text-sm text-gray-500 py-1", "Loading..." }
+                    }
+                }
+            }
+        }
+    }
+}
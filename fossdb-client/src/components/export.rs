@@ -0,0 +1,103 @@
+use crate::hooks::use_notifications;
+use dioxus::prelude::*;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Blob, BlobPropertyBag, HtmlAnchorElement, Url};
+
+/// A table to export, with every cell already formatted as text
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExportTable {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl ExportTable {
+    /// RFC 4180 CSV, quoting only the cells that need it
+    pub fn to_csv(&self) -> String {
+        let line = |cells: &[String]| {
+            cells
+                .iter()
+                .map(|cell| {
+                    if cell.contains([',', '"', '\n', '\r']) {
+                        format!("\"{}\"", cell.replace('"', "\"\""))
+                    } else {
+                        cell.clone()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        std::iter::once(line(&self.headers))
+            .chain(self.rows.iter().map(|row| line(row)))
+            .map(|line| line + "\r\n")
+            .collect()
+    }
+
+    /// A GitHub flavored Markdown table, for pasting into issues and docs
+    pub fn to_markdown(&self) -> String {
+        let line = |cells: &[String]| {
+            let cells: Vec<String> = cells
+                .iter()
+                .map(|cell| cell.replace('|', "\\|").replace(['\r', '\n'], " "))
+                .collect();
+            format!("| {} |\n", cells.join(" | "))
+        };
+        let divider = vec!["---".to_string(); self.headers.len()];
+        std::iter::once(line(&self.headers))
+            .chain(std::iter::once(line(&divider)))
+            .chain(self.rows.iter().map(|row| line(row)))
+            .collect()
+    }
+}
+
+/// Save `content` as a file through the browser's download prompt
+fn download(filename: &str, mime: &str, content: &str) -> Result<(), JsValue> {
+    let document = web_sys::window()
+        .and_then(|w| w.document())
+        .ok_or_else(|| JsValue::from_str("No document"))?;
+
+    let parts = js_sys::Array::of1(&JsValue::from_str(content));
+    let options = BlobPropertyBag::new();
+    options.set_type(mime);
+    let blob = Blob::new_with_str_sequence_and_options(&parts, &options)?;
+    let url = Url::create_object_url_with_blob(&blob)?;
+
+    let link: HtmlAnchorElement = document.create_element("a")?.unchecked_into();
+    link.set_href(&url);
+    link.set_download(filename);
+    link.click();
+    Url::revoke_object_url(&url)
+}
+
+/// Buttons that download `table` as `<filename>.csv` or `<filename>.md`
+#[component]
+pub fn ExportButtons(filename: String, table: ExportTable) -> Element {
+    let notif = use_notifications();
+
+    let export = move |extension: &'static str| {
+        let mut notif = notif;
+        let (mime, content) = match extension {
+            "csv" => ("text/csv", table.to_csv()),
+            _ => ("text/markdown", table.to_markdown()),
+        };
+        if download(&format!("{}.{}", filename, extension), mime, &content).is_err() {
+            notif.error("Failed to export".to_string());
+        }
+    };
+    let export_csv = export.clone();
+
+    rsx! {
+        div { class: "flex items-center space-x-2 text-sm",
+            span { class: "text-gray-400", "Export" }
+            button {
+                class: "px-3 py-1 bg-gray-700 text-gray-300 rounded hover:bg-gray-600 transition-colors",
+                onclick: move |_| export_csv("csv"),
+                "CSV"
+            }
+            button {
+                class: "px-3 py-1 bg-gray-700 text-gray-300 rounded hover:bg-gray-600 transition-colors",
+                onclick: move |_| export("md"),
+                "Markdown"
+            }
+        }
+    }
+}
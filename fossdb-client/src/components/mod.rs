@@ -1,13 +1,23 @@
 pub mod buttons;
 pub mod cards;
+pub mod command_palette;
 pub mod comparison;
+pub mod dependency_tree;
+pub mod export;
+pub mod modal;
 pub mod modals;
 pub mod navigation;
 pub mod notifications;
+pub mod offline;
 
 pub use buttons::Button;
 pub use cards::PackageCard;
+pub use command_palette::{remember_package, CommandPalette};
 pub use comparison::{use_comparison, ComparisonBar, ComparisonState};
+pub use dependency_tree::DependencyTree;
+pub use export::{ExportButtons, ExportTable};
+pub use modal::Modal;
 pub use modals::{LoginModal, RegisterModal};
 pub use navigation::Navigation;
 pub use notifications::NotificationContainer;
+pub use offline::StaleBanner;
@@ -0,0 +1,122 @@
+use dioxus::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use wasm_bindgen::JsCast;
+use web_sys::HtmlElement;
+
+/// Elements Tab can move focus to
+const FOCUSABLE: &str = "a[href], button:not([disabled]), input:not([disabled]), \
+    select:not([disabled]), textarea:not([disabled]), [tabindex]:not([tabindex='-1'])";
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+fn focusable_in(dialog_id: &str) -> Vec<HtmlElement> {
+    let Some(dialog) = web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.get_element_by_id(dialog_id))
+    else {
+        return Vec::new();
+    };
+    let Ok(nodes) = dialog.query_selector_all(FOCUSABLE) else {
+        return Vec::new();
+    };
+    (0..nodes.length())
+        .filter_map(|i| nodes.item(i))
+        .filter_map(|node| node.dyn_into::<HtmlElement>().ok())
+        .collect()
+}
+
+fn active_element() -> Option<web_sys::Element> {
+    web_sys::window()?.document()?.active_element()
+}
+
+/// A dialog over the page. Focus moves into it when it opens, Tab cycles
+/// through its controls instead of the page behind, Escape or a click on the
+/// backdrop closes it, and focus returns to where it was when it closes.
+#[component]
+pub fn Modal(
+    /// The dialog's accessible name, usually the same as its heading
+    title: String,
+    /// Classes of the dialog's panel
+    class: &'static str,
+    /// Position the dialog near the top of the screen instead of the middle
+    #[props(default)]
+    top: bool,
+    on_close: EventHandler<()>,
+    children: Element,
+) -> Element {
+    let id = use_hook(|| NEXT_ID.fetch_add(1, Ordering::Relaxed));
+    let dialog_id = format!("modal-{}", id);
+
+    let previous_focus = use_hook(active_element);
+    use_drop(move || {
+        if let Some(element) = previous_focus.and_then(|e| e.dyn_into::<HtmlElement>().ok()) {
+            let _ = element.focus();
+        }
+    });
+
+    let backdrop_class = if top {
+        "fixed inset-0 bg-black/50 backdrop-blur-sm flex items-start justify-center z-50 pt-24 px-4"
+    } else {
+        "fixed inset-0 bg-black/50 backdrop-blur-sm flex items-center justify-center z-50 p-4"
+    };
+    let mounted_id = dialog_id.clone();
+    let keydown_id = dialog_id.clone();
+
+    rsx! {
+        div {
+            class: backdrop_class,
+            onclick: move |_| on_close.call(()),
+            div {
+                id: "{dialog_id}",
+                class,
+                role: "dialog",
+                aria_modal: "true",
+                aria_label: "{title}",
+                tabindex: "-1",
+                onclick: move |evt| evt.stop_propagation(),
+                onmounted: move |evt| {
+                    let dialog_id = mounted_id.clone();
+                    async move {
+                        // Leave focus alone if a child already took it
+                        let inside = active_element().and_then(|e| e.closest(&format!("#{}", dialog_id)).ok().flatten());
+                        if inside.is_some() {
+                            return;
+                        }
+                        match focusable_in(&dialog_id).first() {
+                            Some(first) => {
+                                let _ = first.focus();
+                            }
+                            None => {
+                                let _ = evt.set_focus(true).await;
+                            }
+                        }
+                    }
+                },
+                onkeydown: move |evt| match evt.key() {
+                    Key::Escape => {
+                        evt.stop_propagation();
+                        on_close.call(());
+                    }
+                    Key::Tab => {
+                        let focusable = focusable_in(&keydown_id);
+                        let (Some(first), Some(last)) = (focusable.first(), focusable.last()) else {
+                            evt.prevent_default();
+                            return;
+                        };
+                        let active = active_element();
+                        let at = |element: &HtmlElement| active.as_ref() == Some(element.unchecked_ref());
+                        if evt.modifiers().shift() && at(first) {
+                            evt.prevent_default();
+                            let _ = last.focus();
+                        } else if !evt.modifiers().shift() && at(last) {
+                            evt.prevent_default();
+                            let _ = first.focus();
+                        }
+                    }
+                    _ => {}
+                },
+                {children}
+            }
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::api::ApiClient;
+use crate::components::Modal;
 use crate::hooks::{use_auth, use_notifications};
 use dioxus::prelude::*;
 
@@ -81,8 +82,10 @@ pub fn LoginModal(show: Signal<bool>) -> Element {
     }
 
     rsx! {
-        div { class: "fixed inset-0 bg-black/50 backdrop-blur-sm flex items-center justify-center z-50 p-4",
-            onclick: move |_| {
+        Modal {
+            title: "Welcome Back",
+            class: "bg-gray-800 rounded-2xl w-full max-w-md shadow-2xl border border-gray-700",
+            on_close: move |_| {
                 show.set(false);
                 email.set(String::new());
                 password.set(String::new());
@@ -90,92 +93,91 @@ pub fn LoginModal(show: Signal<bool>) -> Element {
                 form_error.set(None);
                 email_touched.set(false);
             },
-            div { class: "bg-gray-800 rounded-2xl w-full max-w-md shadow-2xl border border-gray-700",
-                onclick: move |evt| evt.stop_propagation(),
-                div { class: "p-8",
-                    h3 { class: "text-2xl font-bold text-gray-100 mb-6 text-center", "Welcome Back" }
-
-                    form {
-                        onsubmit: handle_submit,
-
-                        div { class: "mb-4",
-                            label { class: "block text-gray-300 font-medium mb-2", "Email" }
-                            input {
-                                r#type: "email",
-                                class: if email_error().is_some() {
-                                    "w-full p-3 bg-gray-700 border-2 border-red-500 rounded-lg focus:ring-2 focus:ring-red-400 text-gray-100 placeholder-gray-400"
-                                } else {
-                                    "w-full p-3 bg-gray-700 border border-gray-600 rounded-lg focus:ring-2 focus:ring-blue-400 focus:border-blue-400 text-gray-100 placeholder-gray-400"
-                                },
-                                placeholder: "Enter your email address",
-                                value: "{email}",
-                                oninput: move |evt| {
-                                    email.set(evt.value());
-                                    if email_touched() {
-                                        validate_email();
-                                    }
-                                    if form_error().is_some() {
-                                        form_error.set(None);
-                                    }
-                                },
-                                onblur: move |_| {
-                                    email_touched.set(true);
+            div { class: "p-8",
+                h3 { class: "text-2xl font-bold text-gray-100 mb-6 text-center", "Welcome Back" }
+
+                form {
+                    onsubmit: handle_submit,
+
+                    div { class: "mb-4",
+                        label { r#for: "login-email", class: "block text-gray-300 font-medium mb-2", "Email" }
+                        input {
+                            id: "login-email",
+                            r#type: "email",
+                            class: if email_error().is_some() {
+                                "w-full p-3 bg-gray-700 border-2 border-red-500 rounded-lg focus:ring-2 focus:ring-red-400 text-gray-100 placeholder-gray-400"
+                            } else {
+                                "w-full p-3 bg-gray-700 border border-gray-600 rounded-lg focus:ring-2 focus:ring-blue-400 focus:border-blue-400 text-gray-100 placeholder-gray-400"
+                            },
+                            placeholder: "Enter your email address",
+                            value: "{email}",
+                            oninput: move |evt| {
+                                email.set(evt.value());
+                                if email_touched() {
                                     validate_email();
-                                },
-                                required: true
-                            }
-                            if let Some(err) = email_error() {
-                                div { class: "mt-1 text-sm text-red-400", "{err}" }
-                            }
+                                }
+                                if form_error().is_some() {
+                                    form_error.set(None);
+                                }
+                            },
+                            onblur: move |_| {
+                                email_touched.set(true);
+                                validate_email();
+                            },
+                            required: true
+                        }
+                        if let Some(err) = email_error() {
+                            div { class: "mt-1 text-sm text-red-400", "{err}" }
                         }
+                    }
 
-                        div { class: "mb-6",
-                            label { class: "block text-gray-300 font-medium mb-2", "Password" }
-                            input {
-                                r#type: "password",
-                                class: "w-full p-3 bg-gray-700 border border-gray-600 rounded-lg focus:ring-2 focus:ring-blue-400 focus:border-blue-400 text-gray-100 placeholder-gray-400",
-                                placeholder: "Enter your password",
-                                value: "{password}",
-                                oninput: move |evt| {
-                                    password.set(evt.value());
-                                    if form_error().is_some() {
-                                        form_error.set(None);
-                                    }
-                                },
-                                required: true
-                            }
+                    div { class: "mb-6",
+                        label { r#for: "login-password", class: "block text-gray-300 font-medium mb-2", "Password" }
+                        input {
+                            id: "login-password",
+                            r#type: "password",
+                            class: "w-full p-3 bg-gray-700 border border-gray-600 rounded-lg focus:ring-2 focus:ring-blue-400 focus:border-blue-400 text-gray-100 placeholder-gray-400",
+                            placeholder: "Enter your password",
+                            value: "{password}",
+                            oninput: move |evt| {
+                                password.set(evt.value());
+                                if form_error().is_some() {
+                                    form_error.set(None);
+                                }
+                            },
+                            required: true
                         }
+                    }
 
-                        if let Some(err) = form_error() {
-                            div { class: "mb-4 p-3 bg-red-500/10 border border-red-500/20 rounded-lg animate-shake",
-                                div { class: "flex items-center space-x-3",
-                                    svg { class: "w-5 h-5 text-red-400 flex-shrink-0", fill: "none", stroke: "currentColor", view_box: "0 0 24 24",
-                                        path { stroke_linecap: "round", stroke_linejoin: "round", stroke_width: "2", d: "M12 8v4m0 4h.01M21 12a9 9 0 11-18 0 9 9 0 0118 0z" }
-                                    }
-                                    div { class: "text-sm text-red-300", "{err}" }
+                    if let Some(err) = form_error() {
+                        div { class: "mb-4 p-3 bg-red-500/10 border border-red-500/20 rounded-lg animate-shake",
+                            div { class: "flex items-center space-x-3",
+                                svg { class: "w-5 h-5 text-red-400 flex-shrink-0", fill: "none", stroke: "currentColor", view_box: "0 0 24 24",
+                                    path { stroke_linecap: "round", stroke_linejoin: "round", stroke_width: "2", d: "M12 8v4m0 4h.01M21 12a9 9 0 11-18 0 9 9 0 0118 0z" }
                                 }
+                                div { class: "text-sm text-red-300", "{err}" }
                             }
                         }
+                    }
 
-                        div { class: "flex gap-3",
-                            button {
-                                r#type: "submit",
-                                class: "flex-1 bg-gradient-to-r from-blue-500 to-purple-600 text-white p-3 rounded-lg font-medium hover:from-blue-600 hover:to-purple-700 transition-all",
-                                "Login"
-                            }
-                            button {
-                                r#type: "button",
-                                class: "flex-1 bg-gray-600 text-gray-200 p-3 rounded-lg font-medium hover:bg-gray-500 transition-all",
-                                onclick: move |_| {
-                                    show.set(false);
-                                    email.set(String::new());
-                                    password.set(String::new());
-                                    email_error.set(None);
-                                    form_error.set(None);
-                                    email_touched.set(false);
-                                },
-                                "Cancel"
-                            }
+                    div { class: "flex gap-3",
+                        button {
+                            r#type: "submit",
+                            class: "flex-1 bg-gradient-to-r from-blue-500 to-purple-600 text-white p-3 rounded-lg font-medium hover:from-blue-600 hover:to-purple-700 transition-all",
+                            "Login"
+                        }
+                        button {
+                            r#type: "button",
+                            class: "flex-1 bg-gray-600 text-gray-200 p-3 rounded-lg font-medium hover:bg-gray-500 transition-all",
+                            onclick: move |_| {
+                                show.set(false);
+                                email.set(String::new());
+                                password.set(String::new());
+                                email_error.set(None);
+                                form_error.set(None);
+                                email_touched.set(false);
+                            },
+                            "Cancel"
                         }
                     }
                 }
@@ -301,8 +303,10 @@ pub fn RegisterModal(show: Signal<bool>) -> Element {
     }
 
     rsx! {
-        div { class: "fixed inset-0 bg-black/50 backdrop-blur-sm flex items-center justify-center z-50 p-4",
-            onclick: move |_| {
+        Modal {
+            title: "Join FossDB",
+            class: "bg-gray-800 rounded-2xl w-full max-w-md shadow-2xl border border-gray-700",
+            on_close: move |_| {
                 show.set(false);
                 username.set(String::new());
                 email.set(String::new());
@@ -315,144 +319,144 @@ pub fn RegisterModal(show: Signal<bool>) -> Element {
                 email_touched.set(false);
                 password_touched.set(false);
             },
-            div { class: "bg-gray-800 rounded-2xl w-full max-w-md shadow-2xl border border-gray-700",
-                onclick: move |evt| evt.stop_propagation(),
-                div { class: "p-8",
-                    h3 { class: "text-2xl font-bold text-gray-100 mb-6 text-center", "Join FossDB" }
-
-                    form {
-                        onsubmit: handle_submit,
-
-                        div { class: "mb-4",
-                            label { class: "block text-gray-300 font-medium mb-2", "Username" }
-                            input {
-                                r#type: "text",
-                                class: if username_error().is_some() {
-                                    "w-full p-3 bg-gray-700 border-2 border-red-500 rounded-lg focus:ring-2 focus:ring-red-400 text-gray-100 placeholder-gray-400"
-                                } else {
-                                    "w-full p-3 bg-gray-700 border border-gray-600 rounded-lg focus:ring-2 focus:ring-blue-400 focus:border-blue-400 text-gray-100 placeholder-gray-400"
-                                },
-                                placeholder: "Choose a username (min. 3 characters)",
-                                value: "{username}",
-                                oninput: move |evt| {
-                                    username.set(evt.value());
-                                    if username_touched() {
-                                        validate_username();
-                                    }
-                                    if form_error().is_some() {
-                                        form_error.set(None);
-                                    }
-                                },
-                                onblur: move |_| {
-                                    username_touched.set(true);
+            div { class: "p-8",
+                h3 { class: "text-2xl font-bold text-gray-100 mb-6 text-center", "Join FossDB" }
+
+                form {
+                    onsubmit: handle_submit,
+
+                    div { class: "mb-4",
+                        label { r#for: "register-username", class: "block text-gray-300 font-medium mb-2", "Username" }
+                        input {
+                            id: "register-username",
+                            r#type: "text",
+                            class: if username_error().is_some() {
+                                "w-full p-3 bg-gray-700 border-2 border-red-500 rounded-lg focus:ring-2 focus:ring-red-400 text-gray-100 placeholder-gray-400"
+                            } else {
+                                "w-full p-3 bg-gray-700 border border-gray-600 rounded-lg focus:ring-2 focus:ring-blue-400 focus:border-blue-400 text-gray-100 placeholder-gray-400"
+                            },
+                            placeholder: "Choose a username (min. 3 characters)",
+                            value: "{username}",
+                            oninput: move |evt| {
+                                username.set(evt.value());
+                                if username_touched() {
                                     validate_username();
-                                },
-                                minlength: 3,
-                                required: true
-                            }
-                            if let Some(err) = username_error() {
-                                div { class: "mt-1 text-sm text-red-400", "{err}" }
-                            }
+                                }
+                                if form_error().is_some() {
+                                    form_error.set(None);
+                                }
+                            },
+                            onblur: move |_| {
+                                username_touched.set(true);
+                                validate_username();
+                            },
+                            minlength: 3,
+                            required: true
                         }
+                        if let Some(err) = username_error() {
+                            div { class: "mt-1 text-sm text-red-400", "{err}" }
+                        }
+                    }
 
-                        div { class: "mb-4",
-                            label { class: "block text-gray-300 font-medium mb-2", "Email" }
-                            input {
-                                r#type: "email",
-                                class: if email_error().is_some() {
-                                    "w-full p-3 bg-gray-700 border-2 border-red-500 rounded-lg focus:ring-2 focus:ring-red-400 text-gray-100 placeholder-gray-400"
-                                } else {
-                                    "w-full p-3 bg-gray-700 border border-gray-600 rounded-lg focus:ring-2 focus:ring-blue-400 focus:border-blue-400 text-gray-100 placeholder-gray-400"
-                                },
-                                placeholder: "Enter your email address",
-                                value: "{email}",
-                                oninput: move |evt| {
-                                    email.set(evt.value());
-                                    if email_touched() {
-                                        validate_email_fn();
-                                    }
-                                    if form_error().is_some() {
-                                        form_error.set(None);
-                                    }
-                                },
-                                onblur: move |_| {
-                                    email_touched.set(true);
+                    div { class: "mb-4",
+                        label { r#for: "register-email", class: "block text-gray-300 font-medium mb-2", "Email" }
+                        input {
+                            id: "register-email",
+                            r#type: "email",
+                            class: if email_error().is_some() {
+                                "w-full p-3 bg-gray-700 border-2 border-red-500 rounded-lg focus:ring-2 focus:ring-red-400 text-gray-100 placeholder-gray-400"
+                            } else {
+                                "w-full p-3 bg-gray-700 border border-gray-600 rounded-lg focus:ring-2 focus:ring-blue-400 focus:border-blue-400 text-gray-100 placeholder-gray-400"
+                            },
+                            placeholder: "Enter your email address",
+                            value: "{email}",
+                            oninput: move |evt| {
+                                email.set(evt.value());
+                                if email_touched() {
                                     validate_email_fn();
-                                },
-                                required: true
-                            }
-                            if let Some(err) = email_error() {
-                                div { class: "mt-1 text-sm text-red-400", "{err}" }
-                            }
+                                }
+                                if form_error().is_some() {
+                                    form_error.set(None);
+                                }
+                            },
+                            onblur: move |_| {
+                                email_touched.set(true);
+                                validate_email_fn();
+                            },
+                            required: true
+                        }
+                        if let Some(err) = email_error() {
+                            div { class: "mt-1 text-sm text-red-400", "{err}" }
                         }
+                    }
 
-                        div { class: "mb-6",
-                            label { class: "block text-gray-300 font-medium mb-2", "Password" }
-                            input {
-                                r#type: "password",
-                                class: if password_error().is_some() {
-                                    "w-full p-3 bg-gray-700 border-2 border-red-500 rounded-lg focus:ring-2 focus:ring-red-400 text-gray-100 placeholder-gray-400"
-                                } else {
-                                    "w-full p-3 bg-gray-700 border border-gray-600 rounded-lg focus:ring-2 focus:ring-blue-400 focus:border-blue-400 text-gray-100 placeholder-gray-400"
-                                },
-                                placeholder: "Create a password (min. 6 characters)",
-                                value: "{password}",
-                                oninput: move |evt| {
-                                    password.set(evt.value());
-                                    if password_touched() {
-                                        validate_password();
-                                    }
-                                    if form_error().is_some() {
-                                        form_error.set(None);
-                                    }
-                                },
-                                onblur: move |_| {
-                                    password_touched.set(true);
+                    div { class: "mb-6",
+                        label { r#for: "register-password", class: "block text-gray-300 font-medium mb-2", "Password" }
+                        input {
+                            id: "register-password",
+                            r#type: "password",
+                            class: if password_error().is_some() {
+                                "w-full p-3 bg-gray-700 border-2 border-red-500 rounded-lg focus:ring-2 focus:ring-red-400 text-gray-100 placeholder-gray-400"
+                            } else {
+                                "w-full p-3 bg-gray-700 border border-gray-600 rounded-lg focus:ring-2 focus:ring-blue-400 focus:border-blue-400 text-gray-100 placeholder-gray-400"
+                            },
+                            placeholder: "Create a password (min. 6 characters)",
+                            value: "{password}",
+                            oninput: move |evt| {
+                                password.set(evt.value());
+                                if password_touched() {
                                     validate_password();
-                                },
-                                minlength: 6,
-                                required: true
-                            }
-                            if let Some(err) = password_error() {
-                                div { class: "mt-1 text-sm text-red-400", "{err}" }
-                            }
+                                }
+                                if form_error().is_some() {
+                                    form_error.set(None);
+                                }
+                            },
+                            onblur: move |_| {
+                                password_touched.set(true);
+                                validate_password();
+                            },
+                            minlength: 6,
+                            required: true
                         }
+                        if let Some(err) = password_error() {
+                            div { class: "mt-1 text-sm text-red-400", "{err}" }
+                        }
+                    }
 
-                        if let Some(err) = form_error() {
-                            div { class: "mb-4 p-3 bg-red-500/10 border border-red-500/20 rounded-lg animate-shake",
-                                div { class: "flex items-center space-x-3",
-                                    svg { class: "w-5 h-5 text-red-400 flex-shrink-0", fill: "none", stroke: "currentColor", view_box: "0 0 24 24",
-                                        path { stroke_linecap: "round", stroke_linejoin: "round", stroke_width: "2", d: "M12 8v4m0 4h.01M21 12a9 9 0 11-18 0 9 9 0 0118 0z" }
-                                    }
-                                    div { class: "text-sm text-red-300", "{err}" }
+                    if let Some(err) = form_error() {
+                        div { class: "mb-4 p-3 bg-red-500/10 border border-red-500/20 rounded-lg animate-shake",
+                            div { class: "flex items-center space-x-3",
+                                svg { class: "w-5 h-5 text-red-400 flex-shrink-0", fill: "none", stroke: "currentColor", view_box: "0 0 24 24",
+                                    path { stroke_linecap: "round", stroke_linejoin: "round", stroke_width: "2", d: "M12 8v4m0 4h.01M21 12a9 9 0 11-18 0 9 9 0 0118 0z" }
                                 }
+                                div { class: "text-sm text-red-300", "{err}" }
                             }
                         }
+                    }
 
-                        div { class: "flex gap-3",
-                            button {
-                                r#type: "submit",
-                                class: "flex-1 bg-gradient-to-r from-green-500 to-emerald-600 text-white p-3 rounded-lg font-medium hover:from-green-600 hover:to-emerald-700 transition-all",
-                                "Register"
-                            }
-                            button {
-                                r#type: "button",
-                                class: "flex-1 bg-gray-600 text-gray-200 p-3 rounded-lg font-medium hover:bg-gray-500 transition-all",
-                                onclick: move |_| {
-                                    show.set(false);
-                                    username.set(String::new());
-                                    email.set(String::new());
-                                    password.set(String::new());
-                                    username_error.set(None);
-                                    email_error.set(None);
-                                    password_error.set(None);
-                                    form_error.set(None);
-                                    username_touched.set(false);
-                                    email_touched.set(false);
-                                    password_touched.set(false);
-                                },
-                                "Cancel"
-                            }
+                    div { class: "flex gap-3",
+                        button {
+                            r#type: "submit",
+                            class: "flex-1 bg-gradient-to-r from-green-500 to-emerald-600 text-white p-3 rounded-lg font-medium hover:from-green-600 hover:to-emerald-700 transition-all",
+                            "Register"
+                        }
+                        button {
+                            r#type: "button",
+                            class: "flex-1 bg-gray-600 text-gray-200 p-3 rounded-lg font-medium hover:bg-gray-500 transition-all",
+                            onclick: move |_| {
+                                show.set(false);
+                                username.set(String::new());
+                                email.set(String::new());
+                                password.set(String::new());
+                                username_error.set(None);
+                                email_error.set(None);
+                                password_error.set(None);
+                                form_error.set(None);
+                                username_touched.set(false);
+                                email_touched.set(false);
+                                password_touched.set(false);
+                            },
+                            "Cancel"
                         }
                     }
                 }
@@ -1,5 +1,7 @@
 use crate::components::modals::{LoginModal, RegisterModal};
-use crate::hooks::{use_auth, use_scroll_direction, ScrollDirection};
+use crate::hooks::{
+    use_auth, use_locale, use_scroll_direction, use_theme, Locale, ScrollDirection, Theme,
+};
 use crate::Route;
 use dioxus::prelude::*;
 
@@ -11,6 +13,8 @@ pub fn Navigation() -> Element {
     let mut register_modal_open = use_signal(|| false);
     let scroll_direction = use_scroll_direction();
     let notif = crate::hooks::use_notifications();
+    let mut theme = use_theme();
+    let locale = use_locale();
 
     let is_authenticated = auth.is_authenticated();
     let username = auth.user().as_ref().map(|u| u.username.clone());
@@ -22,6 +26,18 @@ pub fn Navigation() -> Element {
         "backdrop-blur-md bg-gray-900/80 border-b border-gray-700 sticky top-0 z-50 transform translate-y-0 transition-transform duration-300"
     };
 
+    // Icon for the theme the toggle switches to
+    let (theme_label, theme_icon) = match theme.theme() {
+        Theme::Dark => (
+            locale.t("nav-theme-light"),
+            "M12 3v1m0 16v1m9-9h-1M4 12H3m15.364 6.364l-.707-.707M6.343 6.343l-.707-.707m12.728 0l-.707.707M6.343 17.657l-.707.707M16 12a4 4 0 11-8 0 4 4 0 018 0z",
+        ),
+        Theme::Light => (
+            locale.t("nav-theme-dark"),
+            "M20.354 15.354A9 9 0 018.646 3.646 9.003 9.003 0 0012 21a9.003 9.003 0 008.354-5.646z",
+        ),
+    };
+
     rsx! {
         LoginModal { show: login_modal_open }
         RegisterModal { show: register_modal_open }
@@ -48,20 +64,40 @@ pub fn Navigation() -> Element {
                     // Desktop Navigation
                     div { class: "hidden md:flex items-center space-x-8",
                         Link { to: Route::Home {}, class: "nav-link text-gray-300 hover:text-blue-400 font-medium transition-colors",
-                            "Home"
+                            {locale.t("nav-home")}
+                        }
+                        Link { to: Route::Packages { query: Default::default() }, class: "nav-link text-gray-300 hover:text-blue-400 font-medium transition-colors",
+                            {locale.t("nav-packages")}
                         }
-                        Link { to: Route::Packages {}, class: "nav-link text-gray-300 hover:text-blue-400 font-medium transition-colors",
-                            "Packages"
+                        Link { to: Route::Security {}, class: "nav-link text-gray-300 hover:text-blue-400 font-medium transition-colors",
+                            {locale.t("nav-security")}
+                        }
+                        Link { to: Route::Analytics {}, class: "nav-link text-gray-300 hover:text-blue-400 font-medium transition-colors",
+                            {locale.t("nav-analytics")}
+                        }
+                        Link { to: Route::Ecosystems {}, class: "nav-link text-gray-300 hover:text-blue-400 font-medium transition-colors",
+                            {locale.t("nav-ecosystems")}
                         }
                         if is_authenticated {
                             Link { to: Route::Subscriptions {}, class: "nav-link text-gray-300 hover:text-blue-400 font-medium transition-colors",
-                                "Subscriptions"
+                                {locale.t("nav-subscriptions")}
                             }
                         }
                         Link { to: Route::ApiDocs {}, class: "nav-link text-gray-300 hover:text-blue-400 font-medium transition-colors",
-                            "API"
+                            {locale.t("nav-api")}
                         }
 
+                        button {
+                            class: "p-2 rounded-lg text-gray-300 hover:bg-gray-700 transition-colors",
+                            title: "{theme_label}",
+                            aria_label: "{theme_label}",
+                            onclick: move |_| theme.toggle(),
+                            svg { class: "w-5 h-5", fill: "none", stroke: "currentColor", view_box: "0 0 24 24",
+                                path { stroke_linecap: "round", stroke_linejoin: "round", stroke_width: "2", d: "{theme_icon}" }
+                            }
+                        }
+                        LocaleSelect { class: "bg-transparent text-gray-300 text-sm rounded-lg border border-gray-700 px-2 py-1" }
+
                         // Auth buttons
                         if is_authenticated {
                             div { class: "flex items-center space-x-3",
@@ -73,9 +109,9 @@ pub fn Navigation() -> Element {
                                     onclick: move |_| {
                                         auth.logout();
                                         let mut notif_copy = notif;
-                                        notif_copy.success("Logged out successfully".to_string());
+                                        notif_copy.success(locale.t("nav-logged-out"));
                                     },
-                                    "Logout"
+                                    {locale.t("nav-logout")}
                                 }
                             }
                         } else {
@@ -83,12 +119,12 @@ pub fn Navigation() -> Element {
                                 button {
                                     class: "px-4 py-2 text-blue-400 hover:bg-gray-700 rounded-lg font-medium transition-all",
                                     onclick: move |_| login_modal_open.set(true),
-                                    "Login"
+                                    {locale.t("nav-login")}
                                 }
                                 button {
                                     class: "px-6 py-2 bg-gradient-to-r from-blue-500 to-purple-600 text-white rounded-lg font-medium hover:from-blue-600 hover:to-purple-700 transition-all shadow-lg hover:shadow-xl",
                                     onclick: move |_| register_modal_open.set(true),
-                                    "Get Started"
+                                    {locale.t("nav-get-started")}
                                 }
                             }
                         }
@@ -109,30 +145,46 @@ pub fn Navigation() -> Element {
                     div { class: "md:hidden mt-4 pb-4 border-t border-gray-600",
                         div { class: "flex flex-col space-y-3 pt-4",
                             Link { to: Route::Home {}, class: "nav-link text-gray-300 hover:text-blue-400 font-medium",
-                                "Home"
+                                {locale.t("nav-home")}
+                            }
+                            Link { to: Route::Packages { query: Default::default() }, class: "nav-link text-gray-300 hover:text-blue-400 font-medium",
+                                {locale.t("nav-packages")}
+                            }
+                            Link { to: Route::Security {}, class: "nav-link text-gray-300 hover:text-blue-400 font-medium",
+                                {locale.t("nav-security")}
+                            }
+                            Link { to: Route::Analytics {}, class: "nav-link text-gray-300 hover:text-blue-400 font-medium",
+                                {locale.t("nav-analytics")}
                             }
-                            Link { to: Route::Packages {}, class: "nav-link text-gray-300 hover:text-blue-400 font-medium",
-                                "Packages"
+                            Link { to: Route::Ecosystems {}, class: "nav-link text-gray-300 hover:text-blue-400 font-medium",
+                                {locale.t("nav-ecosystems")}
                             }
                             if is_authenticated {
                                 Link { to: Route::Subscriptions {}, class: "nav-link text-gray-300 hover:text-blue-400 font-medium",
-                                    "Subscriptions"
+                                    {locale.t("nav-subscriptions")}
                                 }
                             }
                             Link { to: Route::ApiDocs {}, class: "nav-link text-gray-300 hover:text-blue-400 font-medium",
-                                "API"
+                                {locale.t("nav-api")}
                             }
 
+                            button {
+                                class: "text-left text-gray-300 font-medium",
+                                onclick: move |_| theme.toggle(),
+                                "{theme_label}"
+                            }
+                            LocaleSelect { class: "bg-transparent text-gray-300 font-medium w-fit" }
+
                             if !is_authenticated {
                                 button {
                                     class: "text-left text-blue-400 font-medium",
                                     onclick: move |_| login_modal_open.set(true),
-                                    "Login"
+                                    {locale.t("nav-login")}
                                 }
                                 button {
                                     class: "text-left px-4 py-2 bg-gradient-to-r from-blue-500 to-purple-600 text-white rounded-lg font-medium w-fit",
                                     onclick: move |_| register_modal_open.set(true),
-                                    "Get Started"
+                                    {locale.t("nav-get-started")}
                                 }
                             }
                         }
@@ -142,3 +194,25 @@ pub fn Navigation() -> Element {
         }
     }
 }
+
+/// Switches the UI language, remembering the choice
+#[component]
+fn LocaleSelect(class: &'static str) -> Element {
+    let mut locale = use_locale();
+    let current = locale.locale();
+
+    rsx! {
+        select {
+            class,
+            aria_label: locale.t("nav-language"),
+            onchange: move |evt| {
+                if let Some(selected) = Locale::from_tag(&evt.value()) {
+                    locale.set(selected);
+                }
+            },
+            for option in Locale::ALL {
+                option { value: option.tag(), selected: option == current, {option.name()} }
+            }
+        }
+    }
+}
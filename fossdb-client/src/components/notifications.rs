@@ -1,18 +1,34 @@
-use crate::hooks::{Notification, NotificationState, NotificationType};
+use crate::hooks::{use_notifications, Notification, NotificationState, NotificationType};
 use dioxus::prelude::*;
 
+/// Toasts shown at once, the rest queue up behind them
+const MAX_VISIBLE: usize = 3;
+
 #[component]
 pub fn NotificationContainer() -> Element {
     let state = use_context::<Signal<NotificationState>>();
+    let mut notif = use_notifications();
     let notifications = state.read().notifications.clone();
+    let overflow = notifications.len().saturating_sub(MAX_VISIBLE);
 
     rsx! {
-        div { class: "fixed top-4 right-4 z-50 space-y-2",
-            for (index, notification) in notifications.iter().enumerate() {
+        div {
+            class: "fixed top-4 right-4 z-50 flex flex-col items-end space-y-2",
+            aria_live: "polite",
+            for notification in notifications.into_iter().take(MAX_VISIBLE) {
                 NotificationToast {
                     key: "{notification.id}",
                     notification: notification.clone(),
-                    index: index
+                }
+            }
+            if overflow > 0 {
+                div { class: "flex items-center space-x-3 px-4 py-2 rounded-lg bg-gray-800 text-gray-300 text-sm shadow-lg border border-gray-700",
+                    span { "+{overflow} more" }
+                    button {
+                        class: "text-blue-400 hover:text-blue-300",
+                        onclick: move |_| notif.dismiss_all(),
+                        "Dismiss all"
+                    }
                 }
             }
         }
@@ -20,22 +36,54 @@ pub fn NotificationContainer() -> Element {
 }
 
 #[component]
-fn NotificationToast(notification: Notification, index: usize) -> Element {
+fn NotificationToast(notification: Notification) -> Element {
+    let mut notif = use_notifications();
+    let id = notification.id;
+
+    // The timer starts once the toast is on screen rather than when it was
+    // queued, and stops if it's dismissed first
+    let duration = notification.duration();
+    use_hook(move || {
+        spawn(async move {
+            gloo_timers::future::sleep(duration).await;
+            notif.dismiss(id);
+        })
+    });
+
     let color_class = match notification.notification_type {
         NotificationType::Success => "bg-green-500 text-white",
         NotificationType::Error => "bg-red-500 text-white",
         NotificationType::Info => "bg-blue-500 text-white",
         NotificationType::Warning => "bg-yellow-500 text-black",
     };
+    let role = match notification.notification_type {
+        NotificationType::Error | NotificationType::Warning => "alert",
+        NotificationType::Success | NotificationType::Info => "status",
+    };
 
     let animation_class = "animate-slideIn";
-    let top_offset = index * 72;
 
     rsx! {
         div {
-            class: "px-6 py-4 rounded-lg shadow-lg transition-all duration-300 {color_class} {animation_class}",
-            style: "margin-top: {top_offset}px;",
-            "{notification.message}"
+            class: "flex items-center space-x-4 px-6 py-4 rounded-lg shadow-lg transition-all duration-300 {color_class} {animation_class}",
+            role,
+            span { "{notification.message}" }
+            if let Some(action) = notification.action {
+                button {
+                    class: "font-semibold underline underline-offset-2 hover:opacity-80",
+                    onclick: move |_| {
+                        action.run();
+                        notif.dismiss(id);
+                    },
+                    "{action.label}"
+                }
+            }
+            button {
+                class: "opacity-70 hover:opacity-100",
+                aria_label: "Dismiss",
+                onclick: move |_| notif.dismiss(id),
+                "✕"
+            }
         }
     }
 }
@@ -0,0 +1,42 @@
+use crate::api::cache::{self, STALE_SINCE};
+use crate::hooks::use_locale;
+use chrono::Utc;
+use dioxus::prelude::*;
+use fluent_bundle::FluentArgs;
+
+/// Warns that the page is showing cached data because the server is unreachable
+#[component]
+pub fn StaleBanner() -> Element {
+    let locale = use_locale();
+    let Some(stored_at) = STALE_SINCE() else {
+        return rsx! {};
+    };
+
+    let minutes = (Utc::now() - stored_at).num_minutes();
+    let count = |count: i64| {
+        let mut args = FluentArgs::new();
+        args.set("count", count);
+        args
+    };
+    let age = match minutes {
+        0 => locale.t("offline-age-under-minute"),
+        m if m < 60 => locale.t_args("offline-age-minutes", &count(m)),
+        m => locale.t_args("offline-age-hours", &count(m / 60)),
+    };
+    let mut args = FluentArgs::new();
+    args.set("age", age);
+
+    rsx! {
+        div { class: "bg-yellow-500 text-black px-6 py-2 text-sm flex justify-between items-center",
+            span { {locale.t_args("offline-stale", &args)} }
+            button {
+                class: "underline",
+                onclick: move |_| {
+                    cache::mark_stale(None);
+                    cache::clear();
+                },
+                {locale.t("offline-clear-cache")}
+            }
+        }
+    }
+}
@@ -2,14 +2,24 @@ use crate::api::{types::*, ApiClient};
 use crate::hooks::storage::{LocalStorage, StorageKey};
 use dioxus::prelude::*;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct AuthState {
     pub token: Option<String>,
     pub user: Option<UserResponse>,
 }
 
-impl Default for AuthState {
-    fn default() -> Self {
+impl AuthState {
+    /// Session to start rendering with, see [`LocalStorage::initial`]
+    pub fn initial() -> Self {
+        if cfg!(feature = "fullstack") {
+            Self::default()
+        } else {
+            Self::stored()
+        }
+    }
+
+    /// The session saved in this browser
+    pub fn stored() -> Self {
         let token: Option<String> = LocalStorage::get(StorageKey::AuthToken);
         let user: Option<UserResponse> = LocalStorage::get(StorageKey::UserData);
 
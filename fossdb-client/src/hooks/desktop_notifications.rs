@@ -0,0 +1,28 @@
+use crate::api::types::TimelineEvent;
+use crate::hooks::{LocalStorage, StorageKey};
+
+/// Whether timeline events should be shown as OS notifications, on unless
+/// turned off in the settings
+pub fn desktop_notifications_enabled() -> bool {
+    cfg!(feature = "desktop")
+        && LocalStorage::get(StorageKey::DesktopNotifications).unwrap_or(true)
+}
+
+pub fn set_desktop_notifications_enabled(enabled: bool) {
+    let _ = LocalStorage::set(StorageKey::DesktopNotifications, &enabled);
+}
+
+/// Show an event as an OS notification. Does nothing outside desktop builds.
+pub fn notify_desktop(event: &TimelineEvent) {
+    #[cfg(feature = "desktop")]
+    if let Err(e) = notify_rust::Notification::new()
+        .appname("FossDB")
+        .summary(&event.package_name)
+        .body(&event.message)
+        .show()
+    {
+        dioxus_logger::tracing::warn!("Failed to show desktop notification: {}", e);
+    }
+    #[cfg(not(feature = "desktop"))]
+    let _ = event;
+}
@@ -0,0 +1,34 @@
+use dioxus::prelude::*;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{IntersectionObserver, IntersectionObserverEntry};
+
+/// Track whether an element is in the viewport.
+///
+/// Returns the visibility signal and an `onmounted` handler to attach to the
+/// element being watched.
+pub fn use_intersection_observer() -> (Signal<bool>, impl Fn(MountedEvent) + Copy) {
+    let mut visible = use_signal(|| false);
+
+    let observe = move |event: MountedEvent| {
+        let Some(element) = event.data().downcast::<web_sys::Element>().cloned() else {
+            return;
+        };
+
+        let closure = Closure::wrap(Box::new(move |entries: js_sys::Array| {
+            let intersecting = entries
+                .iter()
+                .filter_map(|entry| entry.dyn_into::<IntersectionObserverEntry>().ok())
+                .any(|entry| entry.is_intersecting());
+            visible.set(intersecting);
+        }) as Box<dyn FnMut(js_sys::Array)>);
+
+        if let Ok(observer) = IntersectionObserver::new(closure.as_ref().unchecked_ref()) {
+            observer.observe(&element);
+        }
+
+        closure.forget();
+    };
+
+    (visible, observe)
+}
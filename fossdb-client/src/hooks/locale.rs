@@ -0,0 +1,150 @@
+use crate::hooks::storage::{LocalStorage, StorageKey};
+use dioxus::prelude::*;
+use fluent_bundle::{FluentArgs, FluentBundle, FluentResource};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Languages the UI is translated into. Messages live in `locales/<tag>.ftl`,
+/// and any missing from a translation fall back to English.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Locale {
+    English,
+    German,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::English, Locale::German];
+
+    /// BCP 47 tag, also the name of the message file
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Locale::English => "en-US",
+            Locale::German => "de",
+        }
+    }
+
+    /// The language's name in itself, for the switcher
+    pub fn name(&self) -> &'static str {
+        match self {
+            Locale::English => "English",
+            Locale::German => "Deutsch",
+        }
+    }
+
+    fn messages(&self) -> &'static str {
+        match self {
+            Locale::English => include_str!("../../locales/en-US.ftl"),
+            Locale::German => include_str!("../../locales/de.ftl"),
+        }
+    }
+
+    /// The locale whose tag matches `tag`, or whose language does
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag.split(['-', '_']).next().unwrap_or(tag);
+        Self::ALL
+            .into_iter()
+            .find(|l| l.tag().eq_ignore_ascii_case(tag))
+            .or_else(|| {
+                Self::ALL.into_iter().find(|l| {
+                    l.tag()
+                        .split('-')
+                        .next()
+                        .is_some_and(|own| own.eq_ignore_ascii_case(language))
+                })
+            })
+    }
+
+    /// The saved preference, or the browser's language on first load
+    pub fn stored() -> Self {
+        LocalStorage::get(StorageKey::Locale).unwrap_or_else(Self::detect)
+    }
+
+    /// Locale to start rendering with, see [`LocalStorage::initial`]
+    pub fn initial() -> Self {
+        if cfg!(feature = "fullstack") {
+            Locale::English
+        } else {
+            Self::stored()
+        }
+    }
+
+    /// First of the browser's preferred languages that has a translation
+    fn detect() -> Self {
+        let Some(navigator) = web_sys::window().map(|w| w.navigator()) else {
+            return Locale::English;
+        };
+        navigator
+            .languages()
+            .iter()
+            .filter_map(|language| language.as_string())
+            .chain(navigator.language())
+            .find_map(|tag| Self::from_tag(&tag))
+            .unwrap_or(Locale::English)
+    }
+}
+
+thread_local! {
+    static BUNDLES: RefCell<HashMap<Locale, FluentBundle<FluentResource>>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Format `id` in `locale`, or in English when the translation lacks it.
+/// Unknown ids render as themselves so they're easy to spot.
+fn format(locale: Locale, id: &str, args: Option<&FluentArgs>) -> String {
+    BUNDLES.with(|bundles| {
+        let mut bundles = bundles.borrow_mut();
+        [locale, Locale::English]
+            .into_iter()
+            .find_map(|locale| {
+                let bundle = bundles.entry(locale).or_insert_with(|| bundle(locale));
+                let pattern = bundle.get_message(id)?.value()?;
+                let mut errors = Vec::new();
+                Some(bundle.format_pattern(pattern, args, &mut errors).into_owned())
+            })
+            .unwrap_or_else(|| id.to_string())
+    })
+}
+
+fn bundle(locale: Locale) -> FluentBundle<FluentResource> {
+    let mut bundle = FluentBundle::new(vec![locale.tag().parse().expect("valid language tag")]);
+    // Keep interpolated values free of Unicode isolation marks, which show
+    // up as stray characters in some fonts
+    bundle.set_use_isolating(false);
+    let resource = FluentResource::try_new(locale.messages().to_string())
+        .unwrap_or_else(|(resource, _)| resource);
+    let _ = bundle.add_resource(resource);
+    bundle
+}
+
+#[derive(Copy, Clone)]
+pub struct LocaleContext {
+    locale: Signal<Locale>,
+}
+
+impl LocaleContext {
+    pub fn locale(&self) -> Locale {
+        *self.locale.read()
+    }
+
+    pub fn set(&mut self, locale: Locale) {
+        let _ = LocalStorage::set(StorageKey::Locale, &locale);
+        self.locale.set(locale);
+    }
+
+    /// The message `id` in the current locale
+    pub fn t(&self, id: &str) -> String {
+        format(self.locale(), id, None)
+    }
+
+    /// The message `id` with its `{ $name }` placeables filled in
+    pub fn t_args(&self, id: &str, args: &FluentArgs) -> String {
+        format(self.locale(), id, Some(args))
+    }
+}
+
+pub fn use_locale() -> LocaleContext {
+    let locale = use_context::<Signal<Locale>>();
+
+    LocaleContext { locale }
+}
@@ -1,15 +1,28 @@
 pub mod auth;
+pub mod desktop_notifications;
+pub mod intersection;
 pub mod keyboard;
+pub mod locale;
 pub mod notifications;
 pub mod scroll;
 pub mod storage;
+pub mod theme;
 pub mod time_ago;
 pub mod websocket;
 
 pub use auth::{use_auth, AuthState};
+pub use desktop_notifications::{
+    desktop_notifications_enabled, notify_desktop, set_desktop_notifications_enabled,
+};
+pub use intersection::use_intersection_observer;
 pub use keyboard::{use_keyboard_shortcut, KeyPress};
-pub use notifications::{use_notifications, Notification, NotificationState, NotificationType};
+pub use locale::{use_locale, Locale};
+pub use notifications::{
+    use_notifications, Notification, NotificationAction, NotificationContext, NotificationState,
+    NotificationType,
+};
 pub use scroll::{use_scroll_direction, ScrollDirection};
 pub use storage::{LocalStorage, StorageKey};
+pub use theme::{use_theme, Theme};
 pub use time_ago::use_time_ago;
 pub use websocket::use_websocket;
@@ -1,5 +1,12 @@
 use dioxus::prelude::*;
 use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Notifications waiting beyond this many are dropped, oldest first
+const MAX_QUEUED: usize = 20;
 
 #[derive(Clone, PartialEq, Debug)]
 pub enum NotificationType {
@@ -9,13 +16,79 @@ pub enum NotificationType {
     Warning,
 }
 
+impl NotificationType {
+    /// How long a toast stays on screen, longer for ones that need reading
+    pub fn duration(&self) -> Duration {
+        match self {
+            NotificationType::Success | NotificationType::Info => Duration::from_secs(4),
+            NotificationType::Warning => Duration::from_secs(5),
+            NotificationType::Error => Duration::from_secs(6),
+        }
+    }
+}
+
+/// A button on a toast, like "Undo"
+#[derive(Clone)]
+pub struct NotificationAction {
+    pub label: String,
+    run: Rc<dyn Fn()>,
+}
+
+impl NotificationAction {
+    /// An action that starts `task` when clicked. The task keeps running
+    /// after the toast is dismissed.
+    pub fn new<F, Fut>(label: impl Into<String>, task: F) -> Self
+    where
+        F: Fn() -> Fut + 'static,
+        Fut: Future<Output = ()> + 'static,
+    {
+        Self {
+            label: label.into(),
+            run: Rc::new(move || {
+                dioxus::core::spawn_forever(task());
+            }),
+        }
+    }
+
+    pub fn run(&self) {
+        (self.run)();
+    }
+}
+
+impl PartialEq for NotificationAction {
+    fn eq(&self, other: &Self) -> bool {
+        self.label == other.label && Rc::ptr_eq(&self.run, &other.run)
+    }
+}
+
+impl fmt::Debug for NotificationAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NotificationAction")
+            .field("label", &self.label)
+            .finish_non_exhaustive()
+    }
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct Notification {
     pub id: usize,
     pub message: String,
     pub notification_type: NotificationType,
+    pub action: Option<NotificationAction>,
 }
 
+impl Notification {
+    /// Toasts with an action stay up longer so there's time to use it
+    pub fn duration(&self) -> Duration {
+        match self.action {
+            Some(_) => Duration::from_secs(8),
+            None => self.notification_type.duration(),
+        }
+    }
+}
+
+/// Notifications in the order they were shown. The container displays the
+/// first few and the rest wait their turn.
 #[derive(Clone, PartialEq)]
 pub struct NotificationState {
     pub notifications: VecDeque<Notification>,
@@ -38,28 +111,46 @@ pub struct NotificationContext {
 
 impl NotificationContext {
     pub fn show(&mut self, message: String, notification_type: NotificationType) {
-        let id = {
-            let mut state = self.state.write();
-            let id = state.next_id;
-            state.next_id += 1;
-
-            state.notifications.push_back(Notification {
-                id,
-                message,
-                notification_type,
-            });
-
-            if state.notifications.len() > 3 {
-                state.notifications.pop_front();
-            }
-            id
-        };
-
-        let mut state_clone = self.state;
-        spawn(async move {
-            gloo_timers::future::sleep(std::time::Duration::from_secs(3)).await;
-            state_clone.write().notifications.retain(|n| n.id != id);
+        self.push(message, notification_type, None);
+    }
+
+    pub fn show_with_action(
+        &mut self,
+        message: String,
+        notification_type: NotificationType,
+        action: NotificationAction,
+    ) {
+        self.push(message, notification_type, Some(action));
+    }
+
+    fn push(
+        &mut self,
+        message: String,
+        notification_type: NotificationType,
+        action: Option<NotificationAction>,
+    ) {
+        let mut state = self.state.write();
+        let id = state.next_id;
+        state.next_id += 1;
+
+        state.notifications.push_back(Notification {
+            id,
+            message,
+            notification_type,
+            action,
         });
+
+        if state.notifications.len() > MAX_QUEUED {
+            state.notifications.pop_front();
+        }
+    }
+
+    pub fn dismiss(&mut self, id: usize) {
+        self.state.write().notifications.retain(|n| n.id != id);
+    }
+
+    pub fn dismiss_all(&mut self) {
+        self.state.write().notifications.clear();
     }
 
     pub fn success(&mut self, message: String) {
@@ -6,6 +6,11 @@ pub enum StorageKey {
     UserData,
     Subscriptions,
     ViewMode,
+    Theme,
+    RecentPackages,
+    InfiniteScroll,
+    DesktopNotifications,
+    Locale,
 }
 
 impl StorageKey {
@@ -15,6 +20,11 @@ impl StorageKey {
             StorageKey::UserData => "user_data",
             StorageKey::Subscriptions => "subscriptions",
             StorageKey::ViewMode => "view_mode",
+            StorageKey::Theme => "theme",
+            StorageKey::RecentPackages => "recent_packages",
+            StorageKey::InfiniteScroll => "infinite_scroll",
+            StorageKey::DesktopNotifications => "desktop_notifications",
+            StorageKey::Locale => "locale",
         }
     }
 }
@@ -26,6 +36,16 @@ impl LocalStorage {
         GlooLocalStorage::get(key.as_str()).ok()
     }
 
+    /// Value to start rendering with. Fullstack builds start from the default
+    /// so hydration matches the server, and restore stored values once mounted.
+    pub fn initial<T: for<'de> Deserialize<'de>>(key: StorageKey) -> Option<T> {
+        if cfg!(feature = "fullstack") {
+            None
+        } else {
+            Self::get(key)
+        }
+    }
+
     pub fn set<T: Serialize>(
         key: StorageKey,
         value: &T,
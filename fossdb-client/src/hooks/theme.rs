@@ -0,0 +1,71 @@
+use crate::hooks::storage::{LocalStorage, StorageKey};
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Theme {
+    Light,
+    Dark,
+}
+
+impl Theme {
+    /// The saved preference, or the system color scheme on first load
+    pub fn stored() -> Self {
+        LocalStorage::get(StorageKey::Theme).unwrap_or_else(Self::system)
+    }
+
+    /// Theme to start rendering with, see [`LocalStorage::initial`]
+    pub fn initial() -> Self {
+        if cfg!(feature = "fullstack") {
+            Theme::Dark
+        } else {
+            Self::stored()
+        }
+    }
+
+    fn system() -> Self {
+        let prefers_light = web_sys::window()
+            .and_then(|w| w.match_media("(prefers-color-scheme: light)").ok().flatten())
+            .is_some_and(|query| query.matches());
+
+        if prefers_light {
+            Theme::Light
+        } else {
+            Theme::Dark
+        }
+    }
+
+    /// Class selecting this theme's CSS variables in styles.css
+    pub fn class(&self) -> &'static str {
+        match self {
+            Theme::Light => "theme-light",
+            Theme::Dark => "theme-dark",
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct ThemeContext {
+    theme: Signal<Theme>,
+}
+
+impl ThemeContext {
+    pub fn theme(&self) -> Theme {
+        *self.theme.read()
+    }
+
+    pub fn toggle(&mut self) {
+        let next = match self.theme() {
+            Theme::Light => Theme::Dark,
+            Theme::Dark => Theme::Light,
+        };
+        let _ = LocalStorage::set(StorageKey::Theme, &next);
+        self.theme.set(next);
+    }
+}
+
+pub fn use_theme() -> ThemeContext {
+    let theme = use_context::<Signal<Theme>>();
+
+    ThemeContext { theme }
+}
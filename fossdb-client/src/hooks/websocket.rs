@@ -1,5 +1,5 @@
 use dioxus::prelude::*;
-use futures::StreamExt;
+use futures::{SinkExt, StreamExt};
 use gloo_net::websocket::{futures::WebSocket, Message};
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -27,6 +27,9 @@ where
         let on_message = on_message.clone();
 
         spawn(async move {
+            // Latest position in the server's event stream, used to catch up after reconnecting
+            let mut resume_token: Option<String> = None;
+
             loop {
                 state.write().reconnecting = true;
 
@@ -35,9 +38,24 @@ where
                         state.write().connected = true;
                         state.write().reconnecting = false;
 
+                        if let Some(token) = &resume_token {
+                            let resume = serde_json::json!({ "type": "Resume", "token": token });
+                            let _ = ws.send(Message::Text(resume.to_string())).await;
+                        }
+
                         while let Some(msg) = ws.next().await {
                             match msg {
                                 Ok(Message::Text(text)) => {
+                                    if let Ok(value) =
+                                        serde_json::from_str::<serde_json::Value>(&text)
+                                    {
+                                        if let Some(token) = value["resume_token"].as_str() {
+                                            resume_token = Some(token.to_string());
+                                        } else if value["type"] == "ResyncRequired" {
+                                            // The server can't tell what was missed
+                                            resume_token = None;
+                                        }
+                                    }
                                     if let Ok(data) = serde_json::from_str::<T>(&text) {
                                         (on_message.borrow_mut())(data);
                                     }
@@ -6,9 +6,12 @@ mod pages;
 use dioxus::prelude::*;
 use dioxus_logger::tracing::{info, Level};
 
-use components::{ComparisonBar, Navigation, NotificationContainer};
+use components::{CommandPalette, ComparisonBar, Navigation, NotificationContainer, StaleBanner};
 use hooks::{use_keyboard_shortcut, KeyPress};
-use pages::{ApiDocs, Home, PackageDetail, Packages, Subscriptions};
+use pages::{
+    Analytics, ApiDocs, Compare, Ecosystems, Home, PackageDetail, Packages, PackagesQuery, Profile,
+    Security, Subscriptions,
+};
 
 #[derive(Clone, Routable, Debug, PartialEq)]
 #[rustfmt::skip]
@@ -16,12 +19,22 @@ enum Route {
     #[layout(Layout)]
         #[route("/")]
         Home {},
-        #[route("/packages")]
-        Packages {},
+        #[route("/packages?:..query")]
+        Packages { query: PackagesQuery },
         #[route("/packages/:id")]
         PackageDetail { id: String },
+        #[route("/compare?:ids")]
+        Compare { ids: String },
+        #[route("/security")]
+        Security {},
+        #[route("/analytics")]
+        Analytics {},
+        #[route("/ecosystems")]
+        Ecosystems {},
         #[route("/subscriptions")]
         Subscriptions {},
+        #[route("/users/:username")]
+        Profile { username: String },
         #[route("/api")]
         ApiDocs {},
 }
@@ -51,7 +64,9 @@ fn Layout() -> Element {
             alt: false,
         },
         move || {
-            nav.push(Route::Packages {});
+            nav.push(Route::Packages {
+                query: PackagesQuery::default(),
+            });
         },
     );
 
@@ -67,20 +82,50 @@ fn Layout() -> Element {
         },
     );
 
+    let palette_open = use_signal(|| false);
+    use_keyboard_shortcut(
+        KeyPress {
+            key: "k",
+            ctrl: true,
+            shift: false,
+            alt: false,
+        },
+        move || {
+            let mut open = palette_open;
+            open.set(!open());
+        },
+    );
+
     rsx! {
+        CommandPalette { show: palette_open }
         Navigation {}
+        StaleBanner {}
         NotificationContainer {}
         ComparisonBar {}
-        Outlet::<Route> {}
+        SuspenseBoundary {
+            fallback: |_| rsx! {
+                div { class: "flex justify-center py-12",
+                    div { class: "animate-spin rounded-full h-12 w-12 border-b-2 border-blue-500" }
+                }
+            },
+            Outlet::<Route> {}
+        }
     }
 }
 
 #[component]
 pub fn App() -> Element {
     // Provide all context providers
-    use_context_provider(|| Signal::new(hooks::auth::AuthState::default()));
+    use_context_provider(|| Signal::new(hooks::AuthState::initial()));
     use_context_provider(|| Signal::new(hooks::NotificationState::default()));
     use_context_provider(|| Signal::new(components::ComparisonState::default()));
+    let theme = use_context_provider(|| Signal::new(hooks::Theme::initial()));
+    let locale = use_context_provider(|| Signal::new(hooks::Locale::initial()));
+
+    #[cfg(feature = "fullstack")]
+    let session = use_restored_session();
+    #[cfg(not(feature = "fullstack"))]
+    let session = 0;
 
     rsx! {
         document::Link { rel: "stylesheet", href: "https://cdn.tailwindcss.com" }
@@ -90,12 +135,36 @@ pub fn App() -> Element {
         }
         style { {include_str!("styles.css")} }
 
-        div { class: "bg-gradient-to-br from-gray-900 to-gray-800 min-h-screen text-white",
-            Router::<Route> {}
+        div { class: "{theme().class()} app-background min-h-screen", lang: "{locale().tag()}",
+            Router::<Route> { key: "{session}" }
         }
     }
 }
 
+/// Server-rendered pages hydrate with the signed-out, dark, English state the
+/// server saw. Once mounted, the stored session, theme and locale are
+/// restored, and the returned key changes when the routes must be rebuilt for
+/// a signed-in user.
+#[cfg(feature = "fullstack")]
+fn use_restored_session() -> Signal<u32> {
+    let mut auth = use_context::<Signal<hooks::AuthState>>();
+    let mut theme = use_context::<Signal<hooks::Theme>>();
+    let mut locale = use_context::<Signal<hooks::Locale>>();
+    let mut session = use_signal(|| 0u32);
+
+    use_effect(move || {
+        theme.set(hooks::Theme::stored());
+        locale.set(hooks::Locale::stored());
+        let stored = hooks::AuthState::stored();
+        if stored != *auth.peek() {
+            auth.set(stored);
+            session += 1;
+        }
+    });
+
+    session
+}
+
 pub fn launch() {
     dioxus_logger::init(Level::INFO).expect("failed to init logger");
     dioxus::launch(App);
@@ -0,0 +1,249 @@
+use crate::api::types::*;
+use crate::api::ApiClient;
+use dioxus::prelude::*;
+
+const CHART_WIDTH: f64 = 600.0;
+const CHART_HEIGHT: f64 = 200.0;
+const BAR_COLORS: [&str; 6] = ["#60a5fa", "#a78bfa", "#34d399", "#fbbf24", "#f87171", "#9ca3af"];
+
+#[component]
+pub fn Analytics() -> Element {
+    let mut analytics = use_signal(|| None::<AnalyticsResponse>);
+    let mut growth = use_signal(Vec::<GrowthPoint>::new);
+    let mut period = use_signal(|| "month".to_string());
+    let mut loading = use_signal(|| true);
+
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(response) = ApiClient::new().get_analytics().await {
+                analytics.set(Some(response));
+            }
+            loading.set(false);
+        });
+    });
+
+    // Reload growth history whenever the period changes
+    use_effect(move || {
+        let selected = period();
+        spawn(async move {
+            if let Ok(points) = ApiClient::new().get_growth(&selected).await {
+                growth.set(points);
+            }
+        });
+    });
+
+    rsx! {
+        main { class: "min-h-screen bg-gray-900 py-12",
+            div { class: "container mx-auto px-6",
+                div { class: "text-center mb-12",
+                    h1 { class: "text-4xl md:text-5xl font-bold text-gray-100 mb-6", "Analytics" }
+                    p { class: "text-xl text-gray-300 max-w-3xl mx-auto",
+                        "Trends across every package tracked by this instance"
+                    }
+                }
+
+                if loading() {
+                    div { class: "flex justify-center py-12",
+                        div { class: "animate-spin rounded-full h-12 w-12 border-b-2 border-blue-500" }
+                    }
+                } else if let Some(data) = analytics() {
+                    div { class: "max-w-5xl mx-auto space-y-8",
+                        div { class: "grid grid-cols-1 md:grid-cols-3 gap-4",
+                            StatCard { label: "Packages", value: data.total_packages, color: "text-blue-400" }
+                            StatCard { label: "Languages", value: data.programming_languages, color: "text-purple-400" }
+                            StatCard { label: "Updated This Week", value: data.weekly_updates, color: "text-green-400" }
+                        }
+
+                        div { class: "bg-gray-800 rounded-xl p-6 border border-gray-700",
+                            div { class: "flex justify-between items-center mb-4",
+                                h2 { class: "text-xl font-bold text-gray-100", "Growth" }
+                                select {
+                                    class: "px-3 py-2 bg-gray-700 border border-gray-600 rounded-lg text-gray-100",
+                                    value: "{period}",
+                                    onchange: move |evt| period.set(evt.value()),
+                                    option { value: "week", "Past Week" }
+                                    option { value: "month", "Past Month" }
+                                    option { value: "year", "Past Year" }
+                                }
+                            }
+                            GrowthChart { points: growth() }
+                        }
+
+                        div { class: "grid grid-cols-1 md:grid-cols-2 gap-8",
+                            DistributionChart {
+                                title: "Languages",
+                                items: data
+                                    .language_distribution
+                                    .iter()
+                                    .map(|s| (s.language.clone(), s.count, s.percentage))
+                                    .collect::<Vec<_>>(),
+                            }
+                            DistributionChart {
+                                title: "Licenses",
+                                items: data
+                                    .license_distribution
+                                    .iter()
+                                    .map(|s| (s.license.clone(), s.count, s.percentage))
+                                    .collect::<Vec<_>>(),
+                            }
+                        }
+
+                        SecurityOverview { stats: data.security_overview.clone() }
+                    }
+                } else {
+                    div { class: "text-center py-12",
+                        p { class: "text-gray-400 text-lg", "Analytics are unavailable" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn StatCard(label: &'static str, value: u64, color: &'static str) -> Element {
+    rsx! {
+        div { class: "bg-gray-800 rounded-xl p-6 border border-gray-700",
+            div { class: "text-gray-400 text-sm", "{label}" }
+            div { class: "text-3xl font-bold {color}", "{value}" }
+        }
+    }
+}
+
+/// Horizontal bars for the most common entries, with the rest grouped as "Other"
+#[component]
+fn DistributionChart(title: &'static str, items: Vec<(String, u64, f32)>) -> Element {
+    let mut rows: Vec<(String, u64, f32)> = items.iter().take(5).cloned().collect();
+    if items.len() > 5 {
+        let (count, percentage) = items[5..]
+            .iter()
+            .fold((0, 0.0), |(c, p), (_, count, percentage)| (c + count, p + percentage));
+        rows.push(("Other".to_string(), count, percentage));
+    }
+    let row_height = 36.0;
+    let height = row_height * rows.len() as f64;
+
+    rsx! {
+        div { class: "bg-gray-800 rounded-xl p-6 border border-gray-700",
+            h2 { class: "text-xl font-bold text-gray-100 mb-4", "{title}" }
+            if rows.is_empty() {
+                p { class: "text-gray-400", "No data yet" }
+            } else {
+                svg {
+                    class: "w-full",
+                    view_box: "0 0 {CHART_WIDTH} {height}",
+                    for (i, (name, count, percentage)) in rows.into_iter().enumerate() {
+                        {
+                            let y = i as f64 * row_height;
+                            let width = (CHART_WIDTH - 160.0) * (percentage as f64 / 100.0);
+                            let color = BAR_COLORS[i % BAR_COLORS.len()];
+                            rsx! {
+                                text { x: "0", y: "{y + 22.0}", fill: "#d1d5db", font_size: "14", "{name}" }
+                                rect { x: "120", y: "{y + 8.0}", width: "{width.max(2.0)}", height: "20", rx: "4", fill: "{color}" }
+                                text { x: "{128.0 + width}", y: "{y + 22.0}", fill: "#9ca3af", font_size: "12",
+                                    "{count} ({percentage:.1}%)"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Cumulative package count as a line over per-bucket additions
+#[component]
+fn GrowthChart(points: Vec<GrowthPoint>) -> Element {
+    if points.is_empty() {
+        return rsx! {
+            p { class: "text-gray-400", "No data yet" }
+        };
+    }
+
+    let max_total = points.iter().map(|p| p.cumulative_total).max().unwrap_or(0).max(1) as f64;
+    let max_added = points.iter().map(|p| p.packages_added).max().unwrap_or(0).max(1) as f64;
+    let step = CHART_WIDTH / points.len() as f64;
+    let line = points
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let x = step * (i as f64 + 0.5);
+            let y = CHART_HEIGHT - CHART_HEIGHT * (p.cumulative_total as f64 / max_total);
+            format!("{:.1},{:.1}", x, y)
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    let first = points.first().map(|p| p.date.clone()).unwrap_or_default();
+    let last = points.last().map(|p| p.date.clone()).unwrap_or_default();
+
+    rsx! {
+        svg {
+            class: "w-full",
+            view_box: "0 0 {CHART_WIDTH} {CHART_HEIGHT + 24.0}",
+            for (i, point) in points.iter().enumerate() {
+                {
+                    let height = (CHART_HEIGHT / 2.0) * (point.packages_added as f64 / max_added);
+                    rsx! {
+                        rect {
+                            x: "{step * i as f64 + step * 0.2}",
+                            y: "{CHART_HEIGHT - height}",
+                            width: "{step * 0.6}",
+                            height: "{height}",
+                            fill: "#4b5563",
+                            title { "{point.date}: {point.packages_added} added" }
+                        }
+                    }
+                }
+            }
+            polyline { points: "{line}", fill: "none", stroke: "#60a5fa", stroke_width: "2" }
+            text { x: "0", y: "{CHART_HEIGHT + 18.0}", fill: "#9ca3af", font_size: "12", "{first}" }
+            text { x: "{CHART_WIDTH}", y: "{CHART_HEIGHT + 18.0}", fill: "#9ca3af", font_size: "12", text_anchor: "end", "{last}" }
+        }
+        p { class: "text-sm text-gray-400 mt-2",
+            "Line: total packages ({max_total}). Bars: packages added per interval."
+        }
+    }
+}
+
+#[component]
+fn SecurityOverview(stats: SecurityStats) -> Element {
+    let total = (stats.clean_packages + stats.minor_issues + stats.critical_vulnerabilities).max(1) as f64;
+    let segments = [
+        ("Clean", stats.clean_packages, "#34d399"),
+        ("Minor Issues", stats.minor_issues, "#fbbf24"),
+        ("Critical", stats.critical_vulnerabilities, "#f87171"),
+    ];
+    let mut offset = 0.0;
+
+    rsx! {
+        div { class: "bg-gray-800 rounded-xl p-6 border border-gray-700",
+            h2 { class: "text-xl font-bold text-gray-100 mb-4", "Security Overview" }
+            svg {
+                class: "w-full",
+                view_box: "0 0 {CHART_WIDTH} 24",
+                for (label, count, color) in segments {
+                    {
+                        let x = offset;
+                        let width = CHART_WIDTH * (count as f64 / total);
+                        offset += width;
+                        rsx! {
+                            rect { x: "{x}", y: "0", width: "{width}", height: "24", fill: "{color}",
+                                title { "{label}: {count}" }
+                            }
+                        }
+                    }
+                }
+            }
+            div { class: "flex flex-wrap gap-6 mt-4",
+                for (label, count, color) in segments {
+                    div { class: "flex items-center gap-2",
+                        span { class: "inline-block w-3 h-3 rounded", style: "background-color: {color}" }
+                        span { class: "text-gray-300 text-sm", "{label}: {count}" }
+                    }
+                }
+                span { class: "text-gray-400 text-sm", "Scan coverage: {stats.scan_coverage:.0}%" }
+            }
+        }
+    }
+}
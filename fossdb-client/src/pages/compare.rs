@@ -0,0 +1,259 @@
+use crate::api::types::PackageComparison;
+use crate::api::ApiClient;
+use crate::components::{ExportButtons, ExportTable};
+use crate::hooks::use_notifications;
+use dioxus::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+/// Package IDs in the `ids` query parameter, skipping any that aren't numbers
+pub fn parse_ids(ids: &str) -> Vec<u64> {
+    ids.split(',').filter_map(|id| id.trim().parse().ok()).collect()
+}
+
+pub fn format_ids(ids: &[u64]) -> String {
+    ids.iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn or_na(value: Option<String>) -> String {
+    value.unwrap_or_else(|| "N/A".to_string())
+}
+
+fn last_release(c: &PackageComparison) -> String {
+    or_na(c.latest_release.map(|date| date.format("%Y-%m-%d").to_string()))
+}
+
+fn release_frequency(c: &PackageComparison) -> String {
+    or_na(c.release_interval_days.map(|days| format!("Every {} days", days)))
+}
+
+/// The comparison with packages as columns, as it's shown
+fn export_table(compared: &[PackageComparison]) -> ExportTable {
+    let row = |label: &str, cell: &dyn Fn(&PackageComparison) -> String| {
+        std::iter::once(label.to_string())
+            .chain(compared.iter().map(cell))
+            .collect()
+    };
+    ExportTable {
+        headers: std::iter::once("Property".to_string())
+            .chain(compared.iter().map(|c| c.package.name.clone()))
+            .collect(),
+        rows: vec![
+            row("Latest version", &|c| or_na(c.latest_version.clone())),
+            row("Last release", &last_release),
+            row("Releases in the last year", &|c| c.releases_last_year.to_string()),
+            row("Release frequency", &release_frequency),
+            row("Dependencies", &|c| c.dependency_count.to_string()),
+            row("Open vulnerabilities", &|c| c.open_vulnerabilities.to_string()),
+            row("Health score", &|c| format!("{}/100", c.health_score)),
+            row("Subscribers", &|c| c.subscriber_count.to_string()),
+            row("License", &|c| or_na(c.package.license.clone())),
+        ],
+    }
+}
+
+fn health_class(score: u32) -> &'static str {
+    match score {
+        80.. => "text-green-400",
+        50..=79 => "text-yellow-400",
+        _ => "text-red-400",
+    }
+}
+
+/// Packages side by side. The URL holds the package IDs so a comparison can
+/// be shared.
+#[component]
+pub fn Compare(ids: String) -> Element {
+    let mut packages = use_signal(Vec::<PackageComparison>::new);
+    let mut loading = use_signal(|| true);
+    let mut error = use_signal(|| None::<String>);
+    let notif = use_notifications();
+
+    use_effect(use_reactive!(|ids| {
+        spawn(async move {
+            loading.set(true);
+            let parsed = parse_ids(&ids);
+            if parsed.is_empty() {
+                packages.set(Vec::new());
+                error.set(None);
+            } else {
+                match ApiClient::new().compare_packages(&parsed).await {
+                    Ok(compared) => {
+                        packages.set(compared);
+                        error.set(None);
+                    }
+                    Err(e) => {
+                        packages.set(Vec::new());
+                        error.set(Some(
+                            e.as_string()
+                                .unwrap_or_else(|| "Failed to compare packages".to_string()),
+                        ));
+                    }
+                }
+            }
+            loading.set(false);
+        });
+    }));
+
+    let copy_link = move |_| {
+        let mut notif = notif;
+        spawn(async move {
+            let Some(window) = web_sys::window() else {
+                return;
+            };
+            let Ok(url) = window.location().href() else {
+                return;
+            };
+            let promise = window.navigator().clipboard().write_text(&url);
+            if JsFuture::from(promise).await.is_ok() {
+                notif.success("Link copied".to_string());
+            } else {
+                notif.error("Failed to copy to clipboard".to_string());
+            }
+        });
+    };
+
+    let compared = packages();
+    let current_ids: Vec<u64> = compared.iter().map(|c| c.package.id).collect();
+    let best_health = compared.iter().map(|c| c.health_score).max();
+
+    rsx! {
+        document::Title { "Compare Packages - FossDB" }
+        main { class: "min-h-screen bg-gray-900 py-12",
+            div { class: "container mx-auto px-6",
+                div { class: "flex justify-between items-center mb-8",
+                    h1 { class: "text-4xl font-bold text-gray-100", "Package Comparison" }
+                    if !compared.is_empty() {
+                        div { class: "flex items-center space-x-4",
+                            ExportButtons { filename: "comparison", table: export_table(&compared) }
+                            button {
+                                class: "px-4 py-2 bg-gray-700 text-gray-200 rounded-lg hover:bg-gray-600 transition-colors",
+                                onclick: copy_link,
+                                "Copy link"
+                            }
+                        }
+                    }
+                }
+
+                if loading() {
+                    div { class: "flex justify-center py-12",
+                        div { class: "animate-spin rounded-full h-12 w-12 border-b-2 border-blue-500" }
+                    }
+                } else if let Some(err) = error() {
+                    div { class: "bg-red-900/30 border border-red-700 text-red-300 rounded-xl p-6", "{err}" }
+                } else if compared.is_empty() {
+                    div { class: "text-center py-12",
+                        p { class: "text-gray-400 mb-6", "Add packages to the comparison from the packages list" }
+                        Link {
+                            to: crate::Route::Packages { query: Default::default() },
+                            class: "inline-block px-6 py-3 bg-blue-600 hover:bg-blue-700 text-white rounded-lg transition-colors",
+                            "Browse Packages"
+                        }
+                    }
+                } else {
+                    div { class: "overflow-x-auto bg-gray-800 rounded-2xl border border-gray-700",
+                        table { class: "w-full",
+                            thead {
+                                tr { class: "border-b border-gray-700",
+                                    th { class: "text-left p-4 text-gray-400 font-medium", scope: "col", "Property" }
+                                    for comparison in compared.iter() {
+                                        th { key: "{comparison.package.id}", class: "text-left p-4", scope: "col",
+                                            div { class: "flex items-center justify-between space-x-2",
+                                                Link {
+                                                    to: crate::Route::PackageDetail { id: comparison.package.id.to_string() },
+                                                    class: "text-gray-100 font-bold hover:text-blue-400",
+                                                    "{comparison.package.name}"
+                                                }
+                                                Link {
+                                                    to: crate::Route::Compare {
+                                                        ids: format_ids(
+                                                            &current_ids
+                                                                .iter()
+                                                                .copied()
+                                                                .filter(|id| *id != comparison.package.id)
+                                                                .collect::<Vec<_>>(),
+                                                        ),
+                                                    },
+                                                    class: "text-gray-500 hover:text-red-400 text-sm",
+                                                    aria_label: "Remove {comparison.package.name} from comparison",
+                                                    "✕"
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                            tbody {
+                                CompareRow { label: "Latest version",
+                                    for c in compared.iter() {
+                                        td { class: "p-4 text-gray-200", {or_na(c.latest_version.clone())} }
+                                    }
+                                }
+                                CompareRow { label: "Last release",
+                                    for c in compared.iter() {
+                                        td { class: "p-4 text-gray-200", {last_release(c)} }
+                                    }
+                                }
+                                CompareRow { label: "Releases in the last year",
+                                    for c in compared.iter() {
+                                        td { class: "p-4 text-gray-200", "{c.releases_last_year}" }
+                                    }
+                                }
+                                CompareRow { label: "Release frequency",
+                                    for c in compared.iter() {
+                                        td { class: "p-4 text-gray-200", {release_frequency(c)} }
+                                    }
+                                }
+                                CompareRow { label: "Dependencies",
+                                    for c in compared.iter() {
+                                        td { class: "p-4 text-gray-200", "{c.dependency_count}" }
+                                    }
+                                }
+                                CompareRow { label: "Open vulnerabilities",
+                                    for c in compared.iter() {
+                                        td {
+                                            class: if c.open_vulnerabilities > 0 { "p-4 text-red-400 font-semibold" } else { "p-4 text-green-400" },
+                                            "{c.open_vulnerabilities}"
+                                        }
+                                    }
+                                }
+                                CompareRow { label: "Health score",
+                                    for c in compared.iter() {
+                                        td { class: "p-4 font-semibold {health_class(c.health_score)}",
+                                            "{c.health_score}/100"
+                                            if compared.len() > 1 && Some(c.health_score) == best_health {
+                                                span { class: "ml-2 text-xs text-gray-400 font-normal", "best" }
+                                            }
+                                        }
+                                    }
+                                }
+                                CompareRow { label: "Subscribers",
+                                    for c in compared.iter() {
+                                        td { class: "p-4 text-gray-200", "{c.subscriber_count}" }
+                                    }
+                                }
+                                CompareRow { label: "License",
+                                    for c in compared.iter() {
+                                        td { class: "p-4 text-gray-200", {or_na(c.package.license.clone())} }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn CompareRow(label: &'static str, children: Element) -> Element {
+    rsx! {
+        tr { class: "border-b border-gray-700 last:border-0",
+            th { class: "text-left p-4 text-gray-400 font-normal", scope: "row", "{label}" }
+            {children}
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use crate::api::types::*;
+use crate::api::ApiClient;
+use dioxus::prelude::*;
+
+/// Registries side by side: how many packages each has, how often they
+/// release and how many advisories affect them
+#[component]
+pub fn Ecosystems() -> Element {
+    let mut platforms = use_signal(Vec::<PlatformSummary>::new);
+    let mut selected = use_signal(|| None::<String>);
+    let mut stats = use_signal(|| None::<PlatformStats>);
+    let mut loading = use_signal(|| true);
+
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(list) = ApiClient::new().list_platforms().await {
+                if selected.peek().is_none() {
+                    selected.set(list.first().map(|p| p.name.clone()));
+                }
+                platforms.set(list);
+            }
+            loading.set(false);
+        });
+    });
+
+    // Load the selected registry's stats whenever the selection changes
+    use_effect(move || {
+        let Some(name) = selected() else {
+            return;
+        };
+        spawn(async move {
+            stats.set(ApiClient::new().get_platform_stats(&name).await.ok());
+        });
+    });
+
+    rsx! {
+        document::Title { "Ecosystems - FossDB" }
+        main { class: "min-h-screen bg-gray-900 py-12",
+            div { class: "container mx-auto px-6",
+                div { class: "text-center mb-12",
+                    h1 { class: "text-4xl md:text-5xl font-bold text-gray-100 mb-6", "Ecosystems" }
+                    p { class: "text-xl text-gray-300 max-w-3xl mx-auto",
+                        "How the registries this instance collects from compare"
+                    }
+                }
+
+                if loading() {
+                    div { class: "flex justify-center py-12",
+                        div { class: "animate-spin rounded-full h-12 w-12 border-b-2 border-blue-500" }
+                    }
+                } else if platforms().is_empty() {
+                    p { class: "text-center text-gray-400", "No packages have been collected yet" }
+                } else {
+                    div { class: "max-w-5xl mx-auto space-y-8",
+                        div { class: "flex flex-wrap gap-2",
+                            for platform in platforms() {
+                                button {
+                                    key: "{platform.name}",
+                                    class: if selected().as_deref() == Some(platform.name.as_str()) {
+                                        "px-4 py-2 rounded-full bg-blue-600 text-white"
+                                    } else {
+                                        "px-4 py-2 rounded-full bg-gray-800 text-gray-300 border border-gray-700 hover:bg-gray-700"
+                                    },
+                                    aria_pressed: selected().as_deref() == Some(platform.name.as_str()),
+                                    onclick: {
+                                        let name = platform.name.clone();
+                                        move |_| selected.set(Some(name.clone()))
+                                    },
+                                    "{platform.name} "
+                                    span { class: "opacity-70", "({platform.package_count})" }
+                                }
+                            }
+                        }
+
+                        if let Some(stats) = stats() {
+                            PlatformDetails { stats }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn PlatformDetails(stats: PlatformStats) -> Element {
+    let tiles = [
+        ("Packages", stats.package_count.to_string(), "text-blue-400"),
+        (
+            "Releases in 30 Days",
+            stats.releases_last_30_days.to_string(),
+            "text-green-400",
+        ),
+        (
+            "Releases per Package per Year",
+            format!("{:.1}", stats.releases_per_package_per_year),
+            "text-purple-400",
+        ),
+        (
+            "Vulnerable Packages",
+            stats.vulnerable_packages.to_string(),
+            "text-yellow-400",
+        ),
+        (
+            "Advisories per 1000 Packages",
+            format!("{:.1}", stats.vulnerabilities_per_thousand),
+            "text-red-400",
+        ),
+    ];
+
+    rsx! {
+        div { class: "grid grid-cols-2 md:grid-cols-5 gap-4",
+            for (label, value, color) in tiles {
+                div { class: "bg-gray-800 rounded-xl p-6 border border-gray-700",
+                    div { class: "text-gray-400 text-sm", "{label}" }
+                    div { class: "text-3xl font-bold {color}", "{value}" }
+                }
+            }
+        }
+
+        div { class: "bg-gray-800 rounded-xl p-6 border border-gray-700",
+            h2 { class: "text-xl font-bold text-gray-100 mb-4", "Top Packages on {stats.name}" }
+            ol { class: "space-y-2",
+                for (i, package) in stats.top_packages.iter().enumerate() {
+                    li { key: "{package.id}", class: "flex items-baseline space-x-3",
+                        span { class: "text-gray-500 w-6 text-right", "{i + 1}." }
+                        Link {
+                            to: crate::Route::PackageDetail { id: package.id.to_string() },
+                            class: "text-blue-400 hover:underline font-medium",
+                            "{package.name}"
+                        }
+                        if let Some(description) = &package.description {
+                            span { class: "text-gray-400 text-sm truncate", "{description}" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
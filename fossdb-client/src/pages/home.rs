@@ -1,6 +1,9 @@
 use crate::api::types::*;
+use crate::api::types::collector_status::{CollectorState, CollectorSummary};
 use crate::api::ApiClient;
-use crate::hooks::{use_auth, use_time_ago, use_websocket};
+use crate::hooks::{
+    desktop_notifications_enabled, notify_desktop, use_auth, use_time_ago, use_websocket,
+};
 use dioxus::prelude::*;
 use std::collections::HashSet;
 
@@ -15,6 +18,8 @@ pub fn Home() -> Element {
     let mut timeline_total = use_signal(|| 0);
     let mut timeline_loading = use_signal(|| false);
     let mut displayed_event_ids = use_signal(|| HashSet::<u64>::new());
+    // Followed packages with notifications turned on
+    let mut notified_packages = use_signal(HashSet::<String>::new);
 
     let token = auth.token();
     let is_authenticated = auth.is_authenticated();
@@ -41,8 +46,17 @@ pub fn Home() -> Element {
                     timeline_total.set(timeline.total);
                     timeline_offset.set(20);
                 }
+                if let Ok(subscriptions) = client.get_subscriptions().await {
+                    notified_packages.set(
+                        subscriptions
+                            .into_iter()
+                            .filter(|s| s.notifications_enabled)
+                            .map(|s| s.package_name)
+                            .collect(),
+                    );
+                }
             } else {
-                if let Ok(response) = client.get_packages(None, 1, 6).await {
+                if let Ok(response) = client.get_packages(None, None, 1, 6).await {
                     latest_packages.set(response.packages);
                 }
             }
@@ -66,17 +80,37 @@ pub fn Home() -> Element {
             )
         };
 
+        // Personal events for packages the user gets notifications for
+        let notify = move |event: &TimelineEvent| {
+            if event.user_id.is_some()
+                && desktop_notifications_enabled()
+                && notified_packages.read().contains(&event.package_name)
+            {
+                notify_desktop(event);
+            }
+        };
+
         use_websocket::<WebSocketMessage, _>(ws_url, move |msg: WebSocketMessage| {
             match msg {
                 WebSocketMessage::TimelineEvent { event } => {
                     let event_id = event.id;
                     if !displayed_event_ids.read().contains(&event_id) {
                         displayed_event_ids.write().insert(event_id);
+                        notify(&event);
                         timeline_events.write().insert(0, event);
                     }
                 }
+                WebSocketMessage::TimelineEvents { events, .. } => {
+                    for event in events {
+                        if !displayed_event_ids.read().contains(&event.id) {
+                            displayed_event_ids.write().insert(event.id);
+                            notify(&event);
+                            timeline_events.write().insert(0, event);
+                        }
+                    }
+                }
                 _ => {
-                    // Ignore other message types (Ping, Pong, Auth)
+                    // Ignore other message types (Ping, Pong, Auth, Resumed, ResyncRequired)
                 }
             }
         });
@@ -117,7 +151,7 @@ pub fn Home() -> Element {
             // Hero Section
             section { class: "hero-gradient relative overflow-hidden",
                 div { class: "container mx-auto px-6 py-20 relative z-10",
-                    div { class: "text-white max-w-4xl",
+                    div { class: "max-w-4xl",
                         h1 { class: "text-4xl md:text-6xl font-bold mb-4 leading-tight",
                             "FossDB"
                         }
@@ -147,6 +181,23 @@ pub fn Home() -> Element {
                                     div { class: "text-2xl font-bold text-red-400", "{db_stats.total_vulnerabilities}" }
                                 }
                             }
+                            // Live operational state
+                            div { class: "flex flex-wrap justify-center gap-x-6 gap-y-2 mb-8 text-sm text-gray-400",
+                                span { "{db_stats.websocket_clients} watching live" }
+                                span { "{db_stats.email_queue} emails queued" }
+                                span { "{db_stats.collector_queues.pending_moderation} awaiting review" }
+                                span { "{db_stats.collector_queues.quarantined} quarantined" }
+                                for rate in db_stats.upstream_rates.iter() {
+                                    span { key: "{rate.host}", "{rate.host}: {rate.requests_per_second} req/s" }
+                                }
+                            }
+                            if !db_stats.collectors.is_empty() {
+                                div { class: "flex flex-wrap justify-center gap-3 mb-8",
+                                    for collector in db_stats.collectors.iter() {
+                                        CollectorBadge { key: "{collector.name}", collector: collector.clone() }
+                                    }
+                                }
+                            }
                         }
                     }
                 }
@@ -209,7 +260,7 @@ pub fn Home() -> Element {
 }
 
 #[component]
-fn TimelineEventCard(event: TimelineEvent) -> Element {
+pub(crate) fn TimelineEventCard(event: TimelineEvent) -> Element {
     let time_ago = use_time_ago(event.created_at);
 
     let (icon_class, icon_color) = match event.event_type {
@@ -220,6 +271,14 @@ fn TimelineEventCard(event: TimelineEvent) -> Element {
             "text-red-400",
         ),
         TimelineEventType::PackageUpdated => ("M13 16h-1v-4h-1m1-4h.01M21 12a9 9 0 11-18 0 9 9 0 0118 0z", "text-gray-400"),
+        TimelineEventType::MaintainerChanged => (
+            "M16 7a4 4 0 11-8 0 4 4 0 018 0zM12 14a7 7 0 00-7 7h14a7 7 0 00-7-7z",
+            "text-yellow-400",
+        ),
+        TimelineEventType::PackageDeprecated => (
+            "M5 8h14M5 8a2 2 0 110-4h14a2 2 0 110 4M5 8v10a2 2 0 002 2h10a2 2 0 002-2V8m-9 4h4",
+            "text-orange-400",
+        ),
     };
 
     rsx! {
@@ -255,3 +314,31 @@ fn TimelineEventCard(event: TimelineEvent) -> Element {
         }
     }
 }
+
+/// A collector's state, with when it last succeeded and runs next
+#[component]
+fn CollectorBadge(collector: CollectorSummary) -> Element {
+    let (label, color) = match collector.state {
+        CollectorState::Running => ("running", "text-green-400"),
+        CollectorState::Sleeping => ("sleeping", "text-blue-400"),
+        CollectorState::Idle => ("idle", "text-gray-400"),
+        CollectorState::Failed => ("failed", "text-red-400"),
+        CollectorState::Unavailable => ("unavailable", "text-yellow-400"),
+    };
+    let mut details = Vec::new();
+    if let Some(at) = collector.last_success_at {
+        details.push(format!("last success {}", at.format("%Y-%m-%d %H:%M UTC")));
+    }
+    if let Some(at) = collector.next_run_at {
+        details.push(format!("next run {}", at.format("%Y-%m-%d %H:%M UTC")));
+    }
+    let details = details.join(", ");
+
+    rsx! {
+        div { class: "bg-gray-800/50 rounded-lg px-3 py-2 border border-gray-700 text-sm",
+            title: "{details}",
+            span { class: "text-gray-200 font-medium", "{collector.name}" }
+            span { class: "ml-2 {color}", "{label}" }
+        }
+    }
+}
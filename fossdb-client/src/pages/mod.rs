@@ -1,11 +1,21 @@
+pub mod analytics;
 pub mod api_docs;
+pub mod compare;
+pub mod ecosystems;
 pub mod home;
 pub mod package_detail;
 pub mod packages;
+pub mod profile;
+pub mod security;
 pub mod subscriptions;
 
+pub use analytics::Analytics;
 pub use api_docs::ApiDocs;
+pub use compare::Compare;
+pub use ecosystems::Ecosystems;
 pub use home::Home;
 pub use package_detail::PackageDetail;
-pub use packages::Packages;
+pub use packages::{Packages, PackagesQuery};
+pub use profile::Profile;
+pub use security::Security;
 pub use subscriptions::Subscriptions;
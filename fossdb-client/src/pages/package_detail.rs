@@ -1,96 +1,93 @@
+use crate::api::prefetch::{self, use_prefetch};
 use crate::api::{types::*, ApiClient};
-use crate::hooks::{use_auth, use_notifications};
+use crate::components::{remember_package, DependencyTree};
+use crate::hooks::{use_auth, use_notifications, NotificationAction, NotificationType};
+use crate::pages::home::TimelineEventCard;
+use crate::pages::security::VulnerabilityCard;
 use dioxus::prelude::*;
 
+const ACTIVITY_PAGE_SIZE: usize = 20;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Tab {
+    Overview,
+    Versions,
+    Dependencies,
+    Security,
+    Activity,
+}
+
+impl Tab {
+    const ALL: [Tab; 5] = [
+        Tab::Overview,
+        Tab::Versions,
+        Tab::Dependencies,
+        Tab::Security,
+        Tab::Activity,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Tab::Overview => "Overview",
+            Tab::Versions => "Versions",
+            Tab::Dependencies => "Dependencies",
+            Tab::Security => "Security",
+            Tab::Activity => "Activity",
+        }
+    }
+}
+
 #[component]
 pub fn PackageDetail(id: String) -> Element {
     let auth = use_auth();
-    let mut package = use_signal(|| None::<Package>);
-    let mut versions = use_signal(|| Vec::<PackageVersion>::new());
-    let mut displayed_versions = use_signal(|| Vec::<PackageVersion>::new());
-    let mut subscribers = use_signal(|| 0usize);
-    let mut loading = use_signal(|| true);
+    let prefetch_id = id.clone();
+    // Fullstack builds render the package on the server so the page can be indexed
+    let response = use_prefetch(move || prefetch::get_package(prefetch_id.clone()))?;
+    let loaded = response().flatten();
+    let package = loaded.as_ref().map(|r| r.package.clone());
+    let replacement = loaded.as_ref().and_then(|r| r.replacement.clone());
+    let mut subscribers =
+        use_signal(|| loaded.as_ref().map_or(0, |r| r.subscriber_count as usize));
     let mut is_subscribed = use_signal(|| false);
     let mut notifications_enabled = use_signal(|| false);
-    let mut page_size = use_signal(|| 10);
-    let mut current_page = use_signal(|| 0);
+    let mut active_tab = use_signal(|| Tab::Overview);
+    // Tabs stay mounted once opened so their data is only fetched the first time
+    let mut opened_tabs = use_signal(|| vec![Tab::Overview]);
 
     let token = auth.token();
     let package_id = id.clone();
     let is_authenticated = auth.is_authenticated();
 
-    // Load package data
+    // Browser-only work once the package has loaded
     let token_for_effect = token.clone();
-    let package_id_for_effect = package_id.clone();
 
     use_effect(move || {
+        let Some(Some(response)) = response() else {
+            return;
+        };
+        remember_package(&response.package);
+
+        // Check if user is subscribed
+        if !is_authenticated {
+            return;
+        }
         let token_clone = token_for_effect.clone();
-        let pkg_id = package_id_for_effect.clone();
         spawn(async move {
-            let client = ApiClient::new().with_token(token_clone.clone());
-
-            if let Ok(pkg) = client.get_package(&pkg_id).await {
-                package.set(Some(pkg));
-            }
-
-            if let Ok(vers) = client.get_package_versions(&pkg_id).await {
-                versions.set(vers.clone());
-                let end = (page_size() as usize).min(vers.len());
-                displayed_versions.set(vers[0..end].to_vec());
-            }
-
-            if let Ok(count) = client.get_package_subscribers(&pkg_id).await {
-                subscribers.set(count);
-            }
-
-            // Check if user is subscribed
-            if is_authenticated {
-                if let Ok(subs) = client.get_subscriptions().await {
-                    if let Some(pkg) = package() {
-                        if let Some(sub) = subs.iter().find(|s| s.package_name == pkg.name) {
-                            is_subscribed.set(true);
-                            notifications_enabled.set(sub.notifications_enabled);
-                        }
-                    }
-                }
+            let client = ApiClient::new().with_token(token_clone);
+            if let Ok(subs) = client.get_subscriptions().await
+                && let Some(sub) = subs.iter().find(|s| s.package_name == response.package.name)
+            {
+                is_subscribed.set(true);
+                notifications_enabled.set(sub.notifications_enabled);
             }
-
-            loading.set(false);
         });
     });
 
-    let mut update_pagination = move || {
-        let page = current_page();
-        let size = page_size();
-        let all_vers = versions();
-        let start = (page * size) as usize;
-        let end = ((page + 1) * size) as usize;
-        let end = end.min(all_vers.len());
-
-        if start < all_vers.len() {
-            displayed_versions.set(all_vers[start..end].to_vec());
-        }
-    };
-
-    let mut change_page_size = move |new_size: i32| {
-        page_size.set(new_size);
-        current_page.set(0);
-        update_pagination();
-    };
-
-    let next_page = move |_| {
-        let total_pages = (versions().len() as f32 / page_size() as f32).ceil() as i32;
-        if current_page() < total_pages - 1 {
-            current_page.set(current_page() + 1);
-            update_pagination();
-        }
-    };
-
-    let prev_page = move |_| {
-        if current_page() > 0 {
-            current_page.set(current_page() - 1);
-            update_pagination();
+    let mut select_tab = move |tab: Tab| {
+        if !opened_tabs().contains(&tab) {
+            opened_tabs.write().push(tab);
         }
+        active_tab.set(tab);
     };
 
     let notif = use_notifications();
@@ -104,15 +101,39 @@ pub fn PackageDetail(id: String) -> Element {
         let pkg_id = package_id_for_subscribe.clone();
 
         spawn(async move {
-            let client = ApiClient::new().with_token(token_clone);
+            let client = ApiClient::new().with_token(token_clone.clone());
 
             if is_subscribed() {
                 // Unsubscribe
                 if let Ok(_) = client.unsubscribe(&pkg_id).await {
                     is_subscribed.set(false);
-                    notif_copy.success("Unsubscribed successfully".to_string());
                     // Decrement subscriber count
                     subscribers.set(subscribers().saturating_sub(1));
+                    let was_notified = notifications_enabled();
+                    let token = token_clone.clone();
+                    notif_copy.show_with_action(
+                        "Unsubscribed successfully".to_string(),
+                        NotificationType::Success,
+                        NotificationAction::new("Undo", move || {
+                            let client = ApiClient::new().with_token(token.clone());
+                            let pkg_id = pkg_id.clone();
+                            async move {
+                                if client.subscribe(pkg_id.clone()).await.is_err() {
+                                    return;
+                                }
+                                if !was_notified {
+                                    let _ = client.toggle_notifications(&pkg_id, false).await;
+                                }
+                                // The page may have been left in the meantime
+                                if let Ok(mut subscribed) = is_subscribed.try_write() {
+                                    *subscribed = true;
+                                }
+                                if let Ok(mut count) = subscribers.try_write() {
+                                    *count += 1;
+                                }
+                            }
+                        }),
+                    );
                 } else {
                     notif_copy.error("Failed to unsubscribe".to_string());
                 }
@@ -157,8 +178,6 @@ pub fn PackageDetail(id: String) -> Element {
         });
     };
 
-    let total_pages = (versions().len() as f32 / page_size() as f32).ceil() as i32;
-
     rsx! {
         main { class: "min-h-screen bg-gray-900 py-12",
             div { class: "container mx-auto px-6",
@@ -166,7 +185,7 @@ pub fn PackageDetail(id: String) -> Element {
                     // Back Button
                     div { class: "mb-6",
                         Link {
-                            to: crate::Route::Packages {},
+                            to: crate::Route::Packages { query: Default::default() },
                             class: "flex items-center space-x-2 text-gray-400 hover:text-blue-400 transition-colors",
                             svg { class: "w-5 h-5", fill: "none", stroke: "currentColor", view_box: "0 0 24 24",
                                 path { stroke_linecap: "round", stroke_linejoin: "round", stroke_width: "2", d: "M15 19l-7-7 7-7" }
@@ -175,11 +194,12 @@ pub fn PackageDetail(id: String) -> Element {
                         }
                     }
 
-                    if loading() {
-                        div { class: "flex justify-center py-12",
-                            div { class: "animate-spin rounded-full h-12 w-12 border-b-2 border-blue-500" }
+                    if let Some(pkg) = package {
+                        document::Title { "{pkg.name} - FossDB" }
+                        if let Some(description) = &pkg.description {
+                            document::Meta { name: "description", content: "{description}" }
                         }
-                    } else if let Some(pkg) = package() {
+
                         // Package Header
                         div { class: "bg-gray-800 rounded-2xl shadow-xl p-8 mb-6 border border-gray-700",
                             div { class: "flex justify-between items-start mb-4",
@@ -192,24 +212,27 @@ pub fn PackageDetail(id: String) -> Element {
                             }
 
                             if let Some(description) = &pkg.description {
-                                p { class: "text-gray-300 text-lg mb-6", "{description}" }
+                                p { class: "text-gray-300 text-lg", "{description}" }
                             }
 
-                            div { class: "flex flex-wrap gap-4",
-                                if let Some(homepage) = &pkg.homepage {
-                                    a {
-                                        href: "{homepage}",
-                                        target: "_blank",
-                                        class: "px-4 py-2 bg-blue-600 hover:bg-blue-700 text-white rounded-lg transition-colors",
-                                        "Homepage"
+                            if pkg.is_inactive() {
+                                if let Some(status) = &pkg.status {
+                                    p { class: "mt-4 px-4 py-3 bg-orange-900/40 text-orange-300 border border-orange-700 rounded-lg",
+                                        "This package is marked {status}. Consider an alternative for new projects."
                                     }
                                 }
-                                if let Some(repository) = &pkg.repository {
-                                    a {
-                                        href: "{repository}",
-                                        target: "_blank",
-                                        class: "px-4 py-2 bg-gray-700 hover:bg-gray-600 text-white rounded-lg transition-colors",
-                                        "Repository"
+                            }
+                            if let Some(successor) = replacement {
+                                div { class: "mt-4 px-4 py-3 bg-blue-900/40 text-blue-200 border border-blue-700 rounded-lg",
+                                    "Consider "
+                                    Link {
+                                        to: crate::Route::PackageDetail { id: successor.package_id.to_string() },
+                                        class: "font-semibold text-blue-400 hover:text-blue-300",
+                                        "{successor.name}"
+                                    }
+                                    " instead."
+                                    if let Some(note) = &successor.note {
+                                        p { class: "mt-1 text-sm text-blue-300", "{note}" }
                                     }
                                 }
                             }
@@ -217,71 +240,33 @@ pub fn PackageDetail(id: String) -> Element {
 
                         // Package Details Grid
                         div { class: "grid grid-cols-1 lg:grid-cols-3 gap-6",
-                            // Main Content - Versions with Pagination
+                            // Main Content - Tabs
                             div { class: "lg:col-span-2",
-                                div { class: "bg-gray-800 rounded-2xl shadow-xl p-8 border border-gray-700",
-                                    div { class: "flex justify-between items-center mb-6",
-                                        h2 { class: "text-2xl font-bold text-gray-100", "Versions" }
-
-                                        // Page size selector
-                                        div { class: "flex items-center space-x-2",
-                                            span { class: "text-sm text-gray-400", "Show:" }
-                                            select {
-                                                class: "p-2 bg-gray-700 border border-gray-600 rounded text-gray-100 text-sm",
-                                                value: "{page_size()}",
-                                                onchange: move |evt| {
-                                                    if let Ok(size) = evt.value().parse::<i32>() {
-                                                        change_page_size(size);
-                                                    }
-                                                },
-                                                option { value: "5", "5" }
-                                                option { value: "10", "10" }
-                                                option { value: "25", "25" }
-                                                option { value: "50", "50" }
-                                                option { value: "100", "All" }
-                                            }
+                                div { class: "flex flex-wrap gap-2 mb-6 border-b border-gray-700",
+                                    for tab in Tab::ALL {
+                                        button {
+                                            key: "{tab.label()}",
+                                            class: if active_tab() == tab {
+                                                "px-4 py-2 -mb-px border-b-2 border-blue-500 text-blue-400 font-medium"
+                                            } else {
+                                                "px-4 py-2 -mb-px border-b-2 border-transparent text-gray-400 hover:text-gray-100"
+                                            },
+                                            onclick: move |_| select_tab(tab),
+                                            "{tab.label()}"
                                         }
                                     }
+                                }
 
-                                    div { class: "space-y-2",
-                                        for version in displayed_versions().iter() {
-                                            div { key: "{version.id}", class: "flex justify-between items-center p-4 bg-gray-700 rounded-lg",
-                                                div {
-                                                    div { class: "font-semibold text-gray-100", "{version.version}" }
-                                                    div { class: "text-sm text-gray-400",
-                                                        "Released: {version.release_date.format(\"%Y-%m-%d\")}"
-                                                    }
-                                                }
-                                            }
-                                        }
-
-                                        if displayed_versions().is_empty() {
-                                            div { class: "text-center py-8 text-gray-400",
-                                                "No versions available"
-                                            }
-                                        }
-                                    }
-
-                                    // Pagination controls
-                                    if total_pages > 1 {
-                                        div { class: "flex justify-between items-center mt-6 pt-4 border-t border-gray-700",
-                                            button {
-                                                class: "px-4 py-2 bg-gray-700 hover:bg-gray-600 text-white rounded disabled:opacity-50 disabled:cursor-not-allowed",
-                                                disabled: current_page() == 0,
-                                                onclick: prev_page,
-                                                "Previous"
-                                            }
-
-                                            span { class: "text-gray-400",
-                                                "Page {current_page() + 1} of {total_pages}"
-                                            }
-
-                                            button {
-                                                class: "px-4 py-2 bg-gray-700 hover:bg-gray-600 text-white rounded disabled:opacity-50 disabled:cursor-not-allowed",
-                                                disabled: current_page() >= total_pages - 1,
-                                                onclick: next_page,
-                                                "Next"
-                                            }
+                                for tab in opened_tabs() {
+                                    div {
+                                        key: "{tab.label()}",
+                                        class: if active_tab() != tab { "hidden" },
+                                        match tab {
+                                            Tab::Overview => rsx! { OverviewTab { package: pkg.clone() } },
+                                            Tab::Versions => rsx! { VersionsTab { package_id: pkg.id } },
+                                            Tab::Dependencies => rsx! { DependencyTree { package_id: pkg.id } },
+                                            Tab::Security => rsx! { SecurityTab { package_id: pkg.id } },
+                                            Tab::Activity => rsx! { ActivityTab { package_id: pkg.id } },
                                         }
                                     }
                                 }
@@ -361,3 +346,238 @@ pub fn PackageDetail(id: String) -> Element {
         }
     }
 }
+
+#[component]
+fn OverviewTab(package: Package) -> Element {
+    rsx! {
+        div { class: "bg-gray-800 rounded-2xl shadow-xl p-8 border border-gray-700 space-y-6",
+            h2 { class: "text-2xl font-bold text-gray-100", "About" }
+
+            if let Some(description) = &package.description {
+                p { class: "text-gray-300 whitespace-pre-line", "{description}" }
+            } else {
+                p { class: "text-gray-400", "No description provided" }
+            }
+
+            if !package.tags.is_empty() {
+                div { class: "flex flex-wrap gap-2",
+                    for tag in package.tags.iter() {
+                        span { key: "{tag}", class: "px-3 py-1 bg-gray-700 text-gray-300 rounded-full text-sm", "{tag}" }
+                    }
+                }
+            }
+
+            div { class: "flex flex-wrap gap-4",
+                if let Some(homepage) = &package.homepage {
+                    a {
+                        href: "{homepage}",
+                        target: "_blank",
+                        class: "px-4 py-2 bg-blue-600 hover:bg-blue-700 text-white rounded-lg transition-colors",
+                        "Homepage"
+                    }
+                }
+                if let Some(repository) = &package.repository {
+                    a {
+                        href: "{repository}",
+                        target: "_blank",
+                        class: "px-4 py-2 bg-gray-700 hover:bg-gray-600 text-white rounded-lg transition-colors",
+                        "Repository"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn VersionsTab(package_id: u64) -> Element {
+    let mut versions = use_signal(Vec::<PackageVersion>::new);
+    let mut loading = use_signal(|| true);
+    let mut page_size = use_signal(|| 10);
+    let mut current_page = use_signal(|| 0);
+
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(vers) = ApiClient::new()
+                .get_package_versions(&package_id.to_string())
+                .await
+            {
+                versions.set(vers);
+            }
+            loading.set(false);
+        });
+    });
+
+    let total_pages = (versions().len() as f32 / page_size() as f32).ceil() as i32;
+    let start = (current_page() * page_size()) as usize;
+    let displayed_versions: Vec<PackageVersion> = versions()
+        .into_iter()
+        .skip(start)
+        .take(page_size() as usize)
+        .collect();
+
+    rsx! {
+        div { class: "bg-gray-800 rounded-2xl shadow-xl p-8 border border-gray-700",
+            div { class: "flex justify-between items-center mb-6",
+                h2 { class: "text-2xl font-bold text-gray-100", "Versions" }
+
+                // Page size selector
+                div { class: "flex items-center space-x-2",
+                    span { class: "text-sm text-gray-400", "Show:" }
+                    select {
+                        class: "p-2 bg-gray-700 border border-gray-600 rounded text-gray-100 text-sm",
+                        value: "{page_size()}",
+                        onchange: move |evt| {
+                            if let Ok(size) = evt.value().parse::<i32>() {
+                                page_size.set(size);
+                                current_page.set(0);
+                            }
+                        },
+                        option { value: "5", "5" }
+                        option { value: "10", "10" }
+                        option { value: "25", "25" }
+                        option { value: "50", "50" }
+                        option { value: "100", "All" }
+                    }
+                }
+            }
+
+            if loading() {
+                div { class: "text-gray-400", "Loading..." }
+            } else {
+                div { class: "space-y-2",
+                    for version in displayed_versions.iter() {
+                        div { key: "{version.id}", class: "flex justify-between items-center p-4 bg-gray-700 rounded-lg",
+                            div {
+                                div { class: "font-semibold text-gray-100", "{version.version}" }
+                                div { class: "text-sm text-gray-400",
+                                    "Released: {version.release_date.format(\"%Y-%m-%d\")}"
+                                }
+                            }
+                            if !version.vulnerabilities.is_empty() {
+                                span { class: "px-2 py-1 text-xs font-medium rounded bg-red-900/50 text-red-300 border border-red-700",
+                                    if version.vulnerabilities.len() == 1 {
+                                        "1 advisory"
+                                    } else {
+                                        "{version.vulnerabilities.len()} advisories"
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    if displayed_versions.is_empty() {
+                        div { class: "text-center py-8 text-gray-400",
+                            "No versions available"
+                        }
+                    }
+                }
+            }
+
+            // Pagination controls
+            if total_pages > 1 {
+                div { class: "flex justify-between items-center mt-6 pt-4 border-t border-gray-700",
+                    button {
+                        class: "px-4 py-2 bg-gray-700 hover:bg-gray-600 text-white rounded disabled:opacity-50 disabled:cursor-not-allowed",
+                        disabled: current_page() == 0,
+                        onclick: move |_| current_page.set(current_page() - 1),
+                        "Previous"
+                    }
+
+                    span { class: "text-gray-400",
+                        "Page {current_page() + 1} of {total_pages}"
+                    }
+
+                    button {
+                        class: "px-4 py-2 bg-gray-700 hover:bg-gray-600 text-white rounded disabled:opacity-50 disabled:cursor-not-allowed",
+                        disabled: current_page() >= total_pages - 1,
+                        onclick: move |_| current_page.set(current_page() + 1),
+                        "Next"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SecurityTab(package_id: u64) -> Element {
+    let mut vulnerabilities = use_signal(|| None::<Vec<Vulnerability>>);
+
+    use_effect(move || {
+        spawn(async move {
+            let found = ApiClient::new()
+                .get_package_vulnerabilities(package_id)
+                .await
+                .map(|response| response.vulnerabilities)
+                .unwrap_or_default();
+            vulnerabilities.set(Some(found));
+        });
+    });
+
+    rsx! {
+        div { class: "space-y-4",
+            match vulnerabilities() {
+                None => rsx! {
+                    div { class: "text-gray-400", "Loading..." }
+                },
+                Some(list) if list.is_empty() => rsx! {
+                    div { class: "bg-gray-800 rounded-2xl shadow-xl p-8 border border-gray-700 text-gray-400",
+                        "No known vulnerabilities affect this package"
+                    }
+                },
+                Some(list) => rsx! {
+                    for vulnerability in list {
+                        VulnerabilityCard { key: "{vulnerability.id}", vulnerability, affects_subscription: false }
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[component]
+fn ActivityTab(package_id: u64) -> Element {
+    let mut events = use_signal(Vec::<TimelineEvent>::new);
+    let mut total = use_signal(|| 0usize);
+    let mut loading = use_signal(|| true);
+
+    let mut load_more = move || {
+        loading.set(true);
+        let offset = events.peek().len();
+        spawn(async move {
+            if let Ok(response) = ApiClient::new()
+                .get_package_activity(package_id, offset, ACTIVITY_PAGE_SIZE)
+                .await
+            {
+                total.set(response.total);
+                events.write().extend(response.events);
+            }
+            loading.set(false);
+        });
+    };
+
+    use_hook(&mut load_more);
+
+    rsx! {
+        div { class: "space-y-4",
+            for event in events() {
+                TimelineEventCard { key: "{event.id}", event }
+            }
+
+            if loading() {
+                div { class: "text-gray-400", "Loading..." }
+            } else if events().is_empty() {
+                div { class: "bg-gray-800 rounded-2xl shadow-xl p-8 border border-gray-700 text-gray-400",
+                    "No activity recorded yet"
+                }
+            } else if events().len() < total() {
+                button {
+                    class: "w-full px-4 py-2 bg-gray-700 hover:bg-gray-600 text-white rounded-lg transition-colors",
+                    onclick: move |_| load_more(),
+                    "Load more"
+                }
+            }
+        }
+    }
+}
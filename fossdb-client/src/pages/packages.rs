@@ -1,16 +1,19 @@
-use crate::api::types::Package;
+use crate::api::prefetch::{self, use_prefetch};
+use crate::api::types::{Package, PackageSuggestion};
 use crate::api::ApiClient;
-use crate::components::{use_comparison, PackageCard};
-use crate::hooks::{use_auth, LocalStorage, StorageKey};
+use crate::components::{ExportButtons, ExportTable, PackageCard};
+use crate::hooks::{use_intersection_observer, LocalStorage, StorageKey};
 use dioxus::prelude::*;
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct PackageFilters {
     pub search: String,
     pub category: String,
     pub language: String,
     pub license: String,
     pub date_range: String,
+    /// `active` to hide unmaintained, archived and deprecated packages
+    pub status: String,
     pub sort: String,
 }
 
@@ -22,89 +25,335 @@ impl Default for PackageFilters {
             language: String::new(),
             license: String::new(),
             date_range: String::new(),
-            sort: "name".to_string(),
+            status: String::new(),
+            sort: "rank".to_string(),
         }
     }
 }
 
+/// Packages page state kept in the URL, so searches can be shared and
+/// reloads or back/forward navigation return to the same place
+#[derive(Clone, Debug, PartialEq)]
+pub struct PackagesQuery {
+    /// Current page, or the number of pages loaded in infinite scroll mode
+    pub page: u32,
+    pub filters: PackageFilters,
+}
+
+impl Default for PackagesQuery {
+    fn default() -> Self {
+        Self {
+            page: 1,
+            filters: PackageFilters::default(),
+        }
+    }
+}
+
+// Routes are also parsed while rendering on the server, so these can't use the browser's
+fn decode(value: &str) -> String {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut i = 0;
+    while i < value.len() {
+        let escaped = value
+            .get(i..i + 3)
+            .filter(|s| s.starts_with('%') && s[1..].bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|s| u8::from_str_radix(&s[1..], 16).ok());
+        match (escaped, value.as_bytes()[i]) {
+            (Some(byte), _) => {
+                bytes.push(byte);
+                i += 3;
+                continue;
+            }
+            (None, b'+') => bytes.push(b' '),
+            (None, byte) => bytes.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Escape like `encodeURIComponent`
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' => (byte as char).to_string(),
+            b'-' | b'_' | b'.' | b'!' | b'~' | b'*' | b'\'' | b'(' | b')' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+impl From<&str> for PackagesQuery {
+    fn from(query: &str) -> Self {
+        let mut parsed = Self::default();
+        for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+            let value = decode(value);
+            match key {
+                "page" => parsed.page = value.parse().unwrap_or(1).max(1),
+                "q" => parsed.filters.search = value,
+                "category" => parsed.filters.category = value,
+                "language" => parsed.filters.language = value,
+                "license" => parsed.filters.license = value,
+                "date" => parsed.filters.date_range = value,
+                "status" => parsed.filters.status = value,
+                "sort" => parsed.filters.sort = value,
+                _ => {}
+            }
+        }
+        parsed
+    }
+}
+
+impl std::fmt::Display for PackagesQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let defaults = PackageFilters::default();
+        let page = self.page.to_string();
+        let params = [
+            ("q", &self.filters.search, &defaults.search),
+            ("category", &self.filters.category, &defaults.category),
+            ("language", &self.filters.language, &defaults.language),
+            ("license", &self.filters.license, &defaults.license),
+            ("date", &self.filters.date_range, &defaults.date_range),
+            ("status", &self.filters.status, &defaults.status),
+            ("sort", &self.filters.sort, &defaults.sort),
+            ("page", &page, &"1".to_string()),
+        ];
+
+        // Only parameters that differ from the defaults are written
+        let mut separator = "";
+        for (key, value, default) in params {
+            if value != default {
+                write!(f, "{}{}={}", separator, key, encode(value))?;
+                separator = "&";
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The loaded packages, one row each, for exporting
+fn export_table(packages: &[Package]) -> ExportTable {
+    let text = |value: &Option<String>| value.clone().unwrap_or_default();
+    ExportTable {
+        headers: [
+            "Name",
+            "Description",
+            "Language",
+            "License",
+            "Homepage",
+            "Repository",
+            "Updated",
+        ]
+        .map(str::to_string)
+        .to_vec(),
+        rows: packages
+            .iter()
+            .map(|p| {
+                vec![
+                    p.name.clone(),
+                    text(&p.description),
+                    text(&p.language),
+                    text(&p.license),
+                    text(&p.homepage),
+                    text(&p.repository),
+                    p.updated_at.format("%Y-%m-%d").to_string(),
+                ]
+            })
+            .collect(),
+    }
+}
+
+/// Fetch one page of packages, sorted by the server, with the client-side
+/// filters applied
+async fn fetch_page(
+    filter_state: PackageFilters,
+    page: u32,
+    page_size: u32,
+) -> Option<(Vec<Package>, usize)> {
+    let query = if filter_state.search.is_empty() {
+        None
+    } else {
+        Some(filter_state.search.clone())
+    };
+
+    let sort = Some(filter_state.sort.clone());
+    let response = prefetch::get_packages(query, sort, page, page_size).await?;
+    let mut pkg_list = response.packages;
+
+    // Apply client-side filters
+    if !filter_state.language.is_empty() {
+        pkg_list.retain(|p| {
+            p.language
+                .as_ref()
+                .map(|l| l.eq_ignore_ascii_case(&filter_state.language))
+                .unwrap_or(false)
+        });
+    }
+    if !filter_state.license.is_empty() {
+        pkg_list.retain(|p| {
+            p.license
+                .as_ref()
+                .map(|l| l.eq_ignore_ascii_case(&filter_state.license))
+                .unwrap_or(false)
+        });
+    }
+    if filter_state.status == "active" {
+        pkg_list.retain(|p| !p.is_inactive());
+    }
+
+    Some((pkg_list, response.total))
+}
+
 #[component]
-pub fn Packages() -> Element {
-    let auth = use_auth();
-    let mut packages = use_signal(|| Vec::<Package>::new());
-    let mut filters = use_signal(PackageFilters::default);
-    let mut loading = use_signal(|| true);
-    let mut show_advanced = use_signal(|| false);
-    let mut current_page = use_signal(|| 1u32);
-    let mut total_packages = use_signal(|| 0usize);
-    let mut total_pages = use_signal(|| 0u32);
+pub fn Packages(query: PackagesQuery) -> Element {
     let page_size = 30u32;
+    // Fullstack builds render the first page on the server so the listing can be indexed
+    let (initial_filters, initial_page) = (query.filters.clone(), query.page);
+    let prefetched =
+        use_prefetch(move || fetch_page(initial_filters.clone(), initial_page, page_size))?;
+    let prefetched = prefetched().flatten();
+
+    let nav = navigator();
+    let mut packages = use_signal(|| {
+        prefetched
+            .as_ref()
+            .map(|(list, _)| list.clone())
+            .unwrap_or_default()
+    });
+    // Filters being edited, and the ones applied from the URL
+    let mut filters = use_signal(|| query.filters.clone());
+    let mut applied = use_signal(|| query.filters.clone());
+    let mut loading = use_signal(|| prefetched.is_none());
+    let mut use_prefetched = use_signal(|| prefetched.is_some());
+    let mut loading_more = use_signal(|| false);
+    let mut show_advanced = use_signal(|| false);
+    let mut current_page = use_signal(|| query.page);
+    let mut loaded_pages = use_signal(|| 0u32);
+    let total = prefetched.as_ref().map_or(0, |(_, total)| *total);
+    let mut total_packages = use_signal(|| total);
+    let mut total_pages = use_signal(|| ((total as f64) / (page_size as f64)).ceil() as u32);
 
     // Load view mode from localStorage
     let mut view_mode = use_signal(|| {
-        LocalStorage::get::<String>(StorageKey::ViewMode).unwrap_or_else(|| "grid".to_string())
+        LocalStorage::initial::<String>(StorageKey::ViewMode).unwrap_or_else(|| "grid".to_string())
+    });
+    let mut infinite_scroll =
+        use_signal(|| LocalStorage::initial::<bool>(StorageKey::InfiniteScroll).unwrap_or(false));
+
+    #[cfg(feature = "fullstack")]
+    use_effect(move || {
+        if let Some(mode) = LocalStorage::get::<String>(StorageKey::ViewMode) {
+            view_mode.set(mode);
+        }
+        if let Some(enabled) = LocalStorage::get::<bool>(StorageKey::InfiniteScroll) {
+            infinite_scroll.set(enabled);
+        }
     });
 
-    let token = auth.token();
     let mut search_trigger = use_signal(|| 0);
+    let mut suggestions = use_signal(Vec::<PackageSuggestion>::new);
+
+    // Follow the URL when it changes through searches or back/forward navigation
+    use_effect(use_reactive!(|query| {
+        if *applied.peek() != query.filters {
+            loaded_pages.set(0);
+            filters.set(query.filters.clone());
+            applied.set(query.filters.clone());
+        }
+        if *current_page.peek() != query.page {
+            current_page.set(query.page);
+        }
+    }));
 
-    // Search effect - runs when search_trigger or current_page changes
+    // Search effect - runs when search_trigger, the applied filters or current_page change.
+    // In infinite scroll mode, pages after those already loaded are appended.
     use_effect(move || {
         let _ = search_trigger(); // Subscribe to changes
         let page = current_page();
-        let filter_state = filters();
-        let token_clone = token.clone();
+        let infinite = infinite_scroll();
+        let filter_state = applied();
+        let loaded = *loaded_pages.peek();
+
+        // The page rendered with was loaded before the first render
+        if std::mem::take(&mut *use_prefetched.write()) && (!infinite || page == 1) {
+            loaded_pages.set(if infinite { page } else { 0 });
+            return;
+        }
 
         spawn(async move {
-            loading.set(true);
-            let client = ApiClient::new().with_token(token_clone);
-
-            let query = if filter_state.search.is_empty() {
-                None
-            } else {
-                Some(filter_state.search.clone())
+            let appending = infinite && loaded > 0 && page > loaded;
+            let first = match (infinite, appending) {
+                (false, _) => page,
+                (true, true) => loaded + 1,
+                (true, false) => 1,
             };
+            if appending {
+                loading_more.set(true);
+            } else {
+                loading.set(true);
+            }
 
-            if let Ok(response) = client.get_packages(query, page, page_size).await {
-                let mut pkg_list = response.packages;
-
-                // Apply client-side filters
-                if !filter_state.language.is_empty() {
-                    pkg_list.retain(|p| {
-                        p.language
-                            .as_ref()
-                            .map(|l| l.eq_ignore_ascii_case(&filter_state.language))
-                            .unwrap_or(false)
-                    });
-                }
-                if !filter_state.license.is_empty() {
-                    pkg_list.retain(|p| {
-                        p.license
-                            .as_ref()
-                            .map(|l| l.eq_ignore_ascii_case(&filter_state.license))
-                            .unwrap_or(false)
-                    });
-                }
-
-                // Apply sorting
-                match filter_state.sort.as_str() {
-                    "name" => pkg_list.sort_by(|a, b| a.name.cmp(&b.name)),
-                    "-created_at" => pkg_list.sort_by(|a, b| b.created_at.cmp(&a.created_at)),
-                    "created_at" => pkg_list.sort_by(|a, b| a.created_at.cmp(&b.created_at)),
-                    "-updated_at" => pkg_list.sort_by(|a, b| b.updated_at.cmp(&a.updated_at)),
-                    _ => {}
+            let mut pkg_list = if appending { packages() } else { Vec::new() };
+            for p in first..=page {
+                if let Some((batch, total)) = fetch_page(filter_state.clone(), p, page_size).await {
+                    pkg_list.extend(batch);
+                    total_packages.set(total);
+                    total_pages.set(((total as f64) / (page_size as f64)).ceil() as u32);
                 }
-
-                packages.set(pkg_list);
-                total_packages.set(response.total);
-                total_pages.set(((response.total as f64) / (page_size as f64)).ceil() as u32);
             }
+
+            packages.set(pkg_list);
+            loaded_pages.set(if infinite { page } else { 0 });
             loading.set(false);
+            loading_more.set(false);
         });
     });
 
+    // Load the next page when the end of the list scrolls into view
+    let (end_visible, observe_end) = use_intersection_observer();
+    use_effect(move || {
+        let page = current_page();
+        if end_visible()
+            && infinite_scroll()
+            && !loading()
+            && !loading_more()
+            && page < total_pages()
+        {
+            // Replace rather than push so history isn't flooded while scrolling
+            nav.replace(crate::Route::Packages {
+                query: PackagesQuery {
+                    page: page + 1,
+                    filters: applied(),
+                },
+            });
+        }
+    });
+
+    let go_to_page = move |page: u32| {
+        nav.push(crate::Route::Packages {
+            query: PackagesQuery {
+                page,
+                filters: applied(),
+            },
+        });
+    };
+
+    // Searching goes through the URL so results can be bookmarked and shared
     let mut perform_search = move || {
-        current_page.set(1); // Reset to first page when searching
-        search_trigger.set(search_trigger() + 1);
+        loaded_pages.set(0);
+        if current_page() == 1 && applied() == filters() {
+            search_trigger.set(search_trigger() + 1);
+        } else {
+            nav.push(crate::Route::Packages {
+                query: PackagesQuery {
+                    page: 1, // Reset to first page when searching
+                    filters: filters(),
+                },
+            });
+        }
     };
 
     let mut set_view_mode = move |mode: &str| {
@@ -112,12 +361,20 @@ pub fn Packages() -> Element {
         let _ = LocalStorage::set(StorageKey::ViewMode, &mode.to_string());
     };
 
+    let toggle_infinite_scroll = move |_| {
+        let enabled = !infinite_scroll();
+        let _ = LocalStorage::set(StorageKey::InfiniteScroll, &enabled);
+        infinite_scroll.set(enabled);
+        perform_search();
+    };
+
     let mut clear_filters = move |_| {
         filters.set(PackageFilters::default());
         perform_search();
     };
 
     rsx! {
+        document::Title { "Packages - FossDB" }
         main { class: "min-h-screen bg-gray-900 py-12",
             div { class: "container mx-auto px-6",
                 div { class: "text-center mb-12",
@@ -151,8 +408,22 @@ pub fn Packages() -> Element {
                                     placeholder: "Search packages, libraries, frameworks...",
                                     class: "w-full pl-12 pr-4 py-3 bg-gray-700 border border-gray-600 rounded-lg focus:ring-2 focus:ring-blue-400 focus:border-blue-400 text-gray-100 placeholder-gray-400",
                                     value: "{filters().search}",
+                                    list: "package-suggestions",
                                     oninput: move |evt| {
-                                        filters.write().search = evt.value();
+                                        let prefix = evt.value();
+                                        filters.write().search = prefix.clone();
+                                        if prefix.len() < 2 {
+                                            suggestions.set(Vec::new());
+                                            return;
+                                        }
+                                        spawn(async move {
+                                            if let Ok(found) = ApiClient::new().autocomplete_packages(&prefix).await {
+                                                // Responses can arrive after the input has moved on
+                                                if filters.peek().search == prefix {
+                                                    suggestions.set(found);
+                                                }
+                                            }
+                                        });
                                     },
                                     onkeydown: move |evt| {
                                         if evt.key() == Key::Enter {
@@ -160,6 +431,17 @@ pub fn Packages() -> Element {
                                         }
                                     }
                                 }
+                                datalist { id: "package-suggestions",
+                                    for suggestion in suggestions() {
+                                        option {
+                                            key: "{suggestion.name}",
+                                            value: "{suggestion.name}",
+                                            if let Some(platform) = &suggestion.platform {
+                                                "{platform}"
+                                            }
+                                        }
+                                    }
+                                }
                             }
                         }
 
@@ -172,6 +454,7 @@ pub fn Packages() -> Element {
                                     filters.write().sort = evt.value();
                                     perform_search();
                                 },
+                                option { value: "rank", "Most Popular" }
                                 option { value: "name", "Name (A-Z)" }
                                 option { value: "-created_at", "Newest First" }
                                 option { value: "created_at", "Oldest First" }
@@ -205,6 +488,14 @@ pub fn Packages() -> Element {
                                         path { stroke_linecap: "round", stroke_linejoin: "round", stroke_width: "2", d: "M4 6h16M4 10h16M4 14h16M4 18h16" }
                                     }
                                 }
+                                button {
+                                    class: if infinite_scroll() { "bg-blue-500 text-white px-3 py-3 transition-colors" } else { "bg-gray-700 text-gray-300 px-3 py-3 transition-colors" },
+                                    title: "Infinite scroll",
+                                    onclick: toggle_infinite_scroll,
+                                    svg { class: "w-4 h-4", fill: "none", stroke: "currentColor", view_box: "0 0 24 24",
+                                        path { stroke_linecap: "round", stroke_linejoin: "round", stroke_width: "2", d: "M19 14l-7 7m0 0l-7-7m7 7V3" }
+                                    }
+                                }
                             }
                         }
                     }
@@ -212,7 +503,7 @@ pub fn Packages() -> Element {
                     // Advanced Filters
                     if show_advanced() {
                         div { class: "border-t border-gray-700 pt-6",
-                            div { class: "grid grid-cols-1 md:grid-cols-2 lg:grid-cols-4 gap-4",
+                            div { class: "grid grid-cols-1 md:grid-cols-2 lg:grid-cols-5 gap-4",
                                 div {
                                     label { class: "block text-sm font-medium text-gray-300 mb-2", "Category" }
                                     select {
@@ -288,6 +579,20 @@ pub fn Packages() -> Element {
                                         option { value: "year", "Past Year" }
                                     }
                                 }
+
+                                div {
+                                    label { class: "block text-sm font-medium text-gray-300 mb-2", "Status" }
+                                    select {
+                                        class: "w-full p-3 bg-gray-700 border border-gray-600 rounded-lg focus:ring-2 focus:ring-blue-400 focus:border-blue-400 text-gray-100",
+                                        value: "{filters().status}",
+                                        onchange: move |evt| {
+                                            filters.write().status = evt.value();
+                                            perform_search();
+                                        },
+                                        option { value: "", "Any Status" }
+                                        option { value: "active", "Maintained Only" }
+                                    }
+                                }
                             }
 
                             div { class: "flex justify-between items-center mt-6 pt-4 border-t border-gray-700",
@@ -310,6 +615,11 @@ pub fn Packages() -> Element {
                         div { class: "animate-spin rounded-full h-12 w-12 border-b-2 border-blue-500" }
                     }
                 } else {
+                    if !packages().is_empty() {
+                        div { class: "flex justify-end mb-4",
+                            ExportButtons { filename: "packages", table: export_table(&packages()) }
+                        }
+                    }
                     div {
                         class: if view_mode() == "grid" {
                             "grid grid-cols-1 md:grid-cols-2 lg:grid-cols-3 gap-6"
@@ -328,16 +638,23 @@ pub fn Packages() -> Element {
                     }
                 }
 
+                // Infinite scroll sentinel
+                if infinite_scroll() && !loading() && current_page() < total_pages() {
+                    div { class: "flex justify-center py-8",
+                        onmounted: observe_end,
+                        if loading_more() {
+                            div { class: "animate-spin rounded-full h-8 w-8 border-b-2 border-blue-500" }
+                        }
+                    }
+                }
+
                 // Pagination controls
-                if !loading() && total_pages() > 1 {
+                if !infinite_scroll() && !loading() && total_pages() > 1 {
                     div { class: "flex justify-center items-center gap-2 mt-8",
                         button {
                             class: "px-4 py-2 bg-gray-700 text-white rounded-lg hover:bg-gray-600 transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
                             disabled: current_page() == 1,
-                            onclick: move |_| {
-                                current_page.set(current_page() - 1);
-                                search_trigger.set(search_trigger() + 1);
-                            },
+                            onclick: move |_| go_to_page(current_page() - 1),
                             "Previous"
                         }
 
@@ -352,10 +669,7 @@ pub fn Packages() -> Element {
                                         } else {
                                             "px-3 py-2 bg-gray-700 text-white rounded-lg hover:bg-gray-600 transition-colors"
                                         },
-                                        onclick: move |_| {
-                                            current_page.set(page_num);
-                                            search_trigger.set(search_trigger() + 1);
-                                        },
+                                        onclick: move |_| go_to_page(page_num),
                                         "{page_num}"
                                     }
                                 }
@@ -365,10 +679,7 @@ pub fn Packages() -> Element {
                         button {
                             class: "px-4 py-2 bg-gray-700 text-white rounded-lg hover:bg-gray-600 transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
                             disabled: current_page() >= total_pages(),
-                            onclick: move |_| {
-                                current_page.set(current_page() + 1);
-                                search_trigger.set(search_trigger() + 1);
-                            },
+                            onclick: move |_| go_to_page(current_page() + 1),
                             "Next"
                         }
 
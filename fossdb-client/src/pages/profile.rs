@@ -0,0 +1,85 @@
+use crate::api::types::PublicProfile;
+use crate::api::ApiClient;
+use crate::pages::home::TimelineEventCard;
+use crate::pages::packages::{PackageFilters, PackagesQuery};
+use dioxus::prelude::*;
+
+#[component]
+pub fn Profile(username: String) -> Element {
+    let mut profile = use_signal(|| None::<PublicProfile>);
+    let mut loading = use_signal(|| true);
+
+    use_effect(use_reactive!(|username| {
+        spawn(async move {
+            loading.set(true);
+            profile.set(ApiClient::new().get_public_profile(&username).await.ok());
+            loading.set(false);
+        });
+    }));
+
+    rsx! {
+        document::Title { "{username} - FossDB" }
+        main { class: "min-h-screen bg-gray-900 py-12",
+            div { class: "container mx-auto px-6 max-w-4xl",
+                if loading() {
+                    div { class: "flex justify-center py-12",
+                        div { class: "animate-spin rounded-full h-12 w-12 border-b-2 border-blue-500" }
+                    }
+                } else if let Some(profile) = profile() {
+                    div { class: "bg-gray-800 rounded-2xl p-8 border border-gray-700 mb-8",
+                        h1 { class: "text-4xl font-bold text-gray-100 mb-2", "{profile.username}" }
+                        p { class: "text-gray-400",
+                            "Member since {profile.created_at.format(\"%B %Y\")}"
+                        }
+                    }
+
+                    if let Some(subscriptions) = &profile.subscriptions {
+                        div { class: "mb-8",
+                            h2 { class: "text-2xl font-semibold text-gray-100 mb-4", "Following" }
+                            if subscriptions.is_empty() {
+                                p { class: "text-gray-400", "Not following any packages yet" }
+                            }
+                            div { class: "flex flex-wrap gap-2",
+                                for name in subscriptions.iter() {
+                                    Link {
+                                        key: "{name}",
+                                        to: crate::Route::Packages {
+                                            query: PackagesQuery {
+                                                page: 1,
+                                                filters: PackageFilters {
+                                                    search: name.clone(),
+                                                    ..Default::default()
+                                                },
+                                            },
+                                        },
+                                        class: "px-3 py-1 bg-gray-700 text-gray-300 rounded-full text-sm hover:bg-gray-600 transition-colors",
+                                        "{name}"
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    if let Some(activity) = &profile.activity {
+                        div {
+                            h2 { class: "text-2xl font-semibold text-gray-100 mb-4", "Recent Activity" }
+                            if activity.is_empty() {
+                                p { class: "text-gray-400", "No recent activity" }
+                            }
+                            div { class: "space-y-4",
+                                for event in activity.iter() {
+                                    TimelineEventCard { event: event.clone() }
+                                }
+                            }
+                        }
+                    }
+                } else {
+                    div { class: "text-center py-12",
+                        h1 { class: "text-2xl font-semibold text-gray-300 mb-2", "Profile Not Found" }
+                        p { class: "text-gray-400", "This user doesn't exist or keeps their profile private" }
+                    }
+                }
+            }
+        }
+    }
+}
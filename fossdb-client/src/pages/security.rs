@@ -0,0 +1,238 @@
+use crate::api::types::*;
+use crate::api::ApiClient;
+use crate::hooks::{use_auth, use_time_ago};
+use crate::Route;
+use dioxus::prelude::*;
+use std::collections::HashSet;
+
+#[component]
+pub fn Security() -> Element {
+    let auth = use_auth();
+    let mut vulnerabilities = use_signal(Vec::<Vulnerability>::new);
+    let mut subscribed_ids = use_signal(HashSet::<u64>::new);
+    let mut severity = use_signal(String::new);
+    let mut loading = use_signal(|| true);
+    let mut current_page = use_signal(|| 1u32);
+    let mut total_pages = use_signal(|| 0u32);
+    let page_size = 20u32;
+
+    let token = auth.token();
+
+    // Subscribed packages are highlighted in the list
+    let token_for_subscriptions = token.clone();
+    use_effect(move || {
+        let token_clone = token_for_subscriptions.clone();
+        spawn(async move {
+            if let Some(t) = token_clone {
+                let client = ApiClient::new().with_token(Some(t));
+                if let Ok(subs) = client.get_subscriptions().await {
+                    subscribed_ids.set(
+                        subs.iter()
+                            .filter_map(|s| s.package.as_ref().map(|p| p.id))
+                            .collect(),
+                    );
+                }
+            }
+        });
+    });
+
+    // Reload whenever the filter or page changes
+    use_effect(move || {
+        let page = current_page();
+        let filter = severity();
+        let token_clone = token.clone();
+
+        spawn(async move {
+            loading.set(true);
+            let client = ApiClient::new().with_token(token_clone);
+            let filter = (!filter.is_empty()).then_some(filter);
+
+            if let Ok(response) = client.get_vulnerabilities(filter, page, page_size).await {
+                total_pages.set((response.total as u32).div_ceil(page_size));
+                vulnerabilities.set(response.vulnerabilities);
+            }
+            loading.set(false);
+        });
+    });
+
+    rsx! {
+        main { class: "min-h-screen bg-gray-900 py-12",
+            div { class: "container mx-auto px-6",
+                div { class: "text-center mb-12",
+                    h1 { class: "text-4xl md:text-5xl font-bold text-gray-100 mb-6", "Security Advisories" }
+                    p { class: "text-xl text-gray-300 max-w-3xl mx-auto",
+                        "Known vulnerabilities affecting tracked packages"
+                    }
+                }
+
+                div { class: "max-w-4xl mx-auto",
+                    div { class: "flex flex-wrap gap-2 mb-8",
+                        for (value, label) in [("", "All"), ("critical", "Critical"), ("high", "High"), ("medium", "Medium"), ("low", "Low")] {
+                            button {
+                                class: if severity() == value {
+                                    "px-4 py-2 bg-blue-600 text-white rounded-lg"
+                                } else {
+                                    "px-4 py-2 bg-gray-700 text-white rounded-lg hover:bg-gray-600 transition-colors"
+                                },
+                                onclick: move |_| {
+                                    severity.set(value.to_string());
+                                    current_page.set(1);
+                                },
+                                "{label}"
+                            }
+                        }
+                    }
+
+                    if loading() {
+                        div { class: "flex justify-center py-12",
+                            div { class: "animate-spin rounded-full h-12 w-12 border-b-2 border-blue-500" }
+                        }
+                    } else if vulnerabilities().is_empty() {
+                        div { class: "text-center py-12",
+                            p { class: "text-gray-400 text-lg", "No vulnerabilities found" }
+                        }
+                    } else {
+                        div { class: "space-y-4",
+                            for vulnerability in vulnerabilities().iter() {
+                                {
+                                    let affects_subscription = vulnerability
+                                        .affected_packages
+                                        .iter()
+                                        .any(|a| subscribed_ids.read().contains(&a.package_id));
+                                    rsx! {
+                                        VulnerabilityCard {
+                                            key: "{vulnerability.id}",
+                                            vulnerability: vulnerability.clone(),
+                                            affects_subscription,
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    // Pagination controls
+                    if !loading() && total_pages() > 1 {
+                        div { class: "flex justify-center items-center gap-4 mt-8",
+                            button {
+                                class: "px-4 py-2 bg-gray-700 text-white rounded-lg hover:bg-gray-600 transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
+                                disabled: current_page() == 1,
+                                onclick: move |_| current_page.set(current_page() - 1),
+                                "Previous"
+                            }
+                            span { class: "text-gray-400", "Page {current_page()} of {total_pages()}" }
+                            button {
+                                class: "px-4 py-2 bg-gray-700 text-white rounded-lg hover:bg-gray-600 transition-colors disabled:opacity-50 disabled:cursor-not-allowed",
+                                disabled: current_page() >= total_pages(),
+                                onclick: move |_| current_page.set(current_page() + 1),
+                                "Next"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub(crate) fn VulnerabilityCard(vulnerability: Vulnerability, affects_subscription: bool) -> Element {
+    let time_ago = use_time_ago(vulnerability.discovered_at);
+    let mut detail = use_signal(|| None::<VulnerabilityDetail>);
+    let mut expanded = use_signal(|| false);
+
+    let badge_class = match vulnerability.severity {
+        VulnerabilitySeverity::Critical => "bg-red-600 text-white",
+        VulnerabilitySeverity::High => "bg-orange-500 text-white",
+        VulnerabilitySeverity::Medium => "bg-yellow-500 text-gray-900",
+        VulnerabilitySeverity::Low => "bg-gray-600 text-gray-100",
+    };
+    let border_class = if affects_subscription {
+        "border-red-500"
+    } else {
+        "border-gray-700"
+    };
+
+    // Package names and resolved versions are only fetched when expanded
+    let id = vulnerability.id;
+    let toggle = move |_| {
+        expanded.set(!expanded());
+        if expanded() && detail().is_none() {
+            spawn(async move {
+                if let Ok(d) = ApiClient::new().get_vulnerability(id).await {
+                    detail.set(Some(d));
+                }
+            });
+        }
+    };
+
+    rsx! {
+        div { class: "bg-gray-800 rounded-xl p-6 border {border_class}",
+            div { class: "flex justify-between items-start gap-4",
+                div { class: "flex-1",
+                    div { class: "flex items-center gap-2 mb-2",
+                        span { class: "px-2 py-1 rounded text-xs font-semibold {badge_class}",
+                            "{vulnerability.severity:?}"
+                        }
+                        if let Some(cve_id) = &vulnerability.cve_id {
+                            span { class: "text-sm text-gray-400 font-mono", "{cve_id}" }
+                        }
+                        if affects_subscription {
+                            span { class: "text-xs text-red-400 font-medium", "Affects your subscriptions" }
+                        }
+                    }
+                    h3 { class: "text-xl font-bold text-gray-100 mb-2", "{vulnerability.title}" }
+                    p { class: "text-gray-400 text-sm", "{vulnerability.description}" }
+                    if let Some(fixed_in) = &vulnerability.fixed_in {
+                        p { class: "text-sm text-green-400 mt-2", "Fixed in: {fixed_in}" }
+                    }
+                }
+                div { class: "text-sm text-gray-500 text-right",
+                    div { "{time_ago()}" }
+                    if let Some(score) = vulnerability.cvss_score {
+                        div { class: "mt-1", "CVSS {score:.1}" }
+                    }
+                }
+            }
+
+            button {
+                class: "mt-4 text-sm text-blue-400 hover:text-blue-300",
+                onclick: toggle,
+                if expanded() {
+                    "Hide affected packages"
+                } else {
+                    "Show {vulnerability.affected_packages.len()} affected package(s)"
+                }
+            }
+
+            if expanded() {
+                div { class: "mt-4 space-y-3",
+                    if let Some(d) = detail() {
+                        for affected in d.affected_packages {
+                            div { key: "{affected.package_id}", class: "bg-gray-900 rounded-lg p-4",
+                                Link {
+                                    to: Route::PackageDetail { id: affected.package_id.to_string() },
+                                    class: "text-blue-400 hover:text-blue-300 font-medium",
+                                    "{affected.package_name}"
+                                }
+                                span { class: "ml-2 text-sm text-gray-400 font-mono", "{affected.version_range}" }
+                                if !affected.affected_versions.is_empty() {
+                                    p { class: "text-sm text-red-400 mt-1",
+                                        "Affected: {affected.affected_versions.join(\", \")}"
+                                    }
+                                }
+                                if !affected.fixed_versions.is_empty() {
+                                    p { class: "text-sm text-green-400 mt-1",
+                                        "Fixed: {affected.fixed_versions.join(\", \")}"
+                                    }
+                                }
+                            }
+                        }
+                    } else {
+                        p { class: "text-gray-400 text-sm", "Loading..." }
+                    }
+                }
+            }
+        }
+    }
+}
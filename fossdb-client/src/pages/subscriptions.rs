@@ -1,10 +1,19 @@
-use crate::api::{types::SubscriptionResponse, ApiClient};
-use crate::hooks::use_auth;
+use crate::api::{
+    types::{ProfileSettings, SubscriptionResponse},
+    ApiClient,
+};
+use crate::hooks::{
+    desktop_notifications_enabled, set_desktop_notifications_enabled, use_auth, use_locale,
+    use_notifications, NotificationAction, NotificationType,
+};
 use dioxus::prelude::*;
+use fluent_bundle::FluentArgs;
 
 #[component]
 pub fn Subscriptions() -> Element {
     let auth = use_auth();
+    let locale = use_locale();
+    let notif = use_notifications();
     let mut subscriptions = use_signal(|| Vec::<SubscriptionResponse>::new());
     let mut loading = use_signal(|| true);
 
@@ -23,19 +32,108 @@ pub fn Subscriptions() -> Element {
         });
     });
 
+    // Public profile settings, once loaded
+    let mut profile = use_signal(|| None::<ProfileSettings>);
+    let token = auth.token();
+    use_effect(move || {
+        let token_clone = token.clone();
+        spawn(async move {
+            if let Some(t) = token_clone {
+                let client = ApiClient::new().with_token(Some(t));
+                profile.set(client.get_profile_settings().await.ok());
+            }
+        });
+    });
+
     let auth_token = auth.token();
+    let save_profile = move |settings: ProfileSettings| {
+        let token = auth.token();
+        spawn(async move {
+            if let Some(t) = token {
+                let client = ApiClient::new().with_token(Some(t));
+                if let Ok(saved) = client.update_profile_settings(&settings).await {
+                    profile.set(Some(saved));
+                }
+            }
+        });
+    };
+    let username = auth.user().map(|user| user.username).unwrap_or_default();
+    let mut desktop_notifications = use_signal(desktop_notifications_enabled);
 
     rsx! {
         main { class: "min-h-screen bg-gray-900 py-12",
             div { class: "container mx-auto px-6",
                 div { class: "text-center mb-12",
-                    h1 { class: "text-4xl md:text-5xl font-bold text-gray-100 mb-6", "My Subscriptions" }
+                    h1 { class: "text-4xl md:text-5xl font-bold text-gray-100 mb-6", {locale.t("subscriptions-title")} }
                     p { class: "text-xl text-gray-300 max-w-3xl mx-auto",
-                        "Manage packages you're following to receive updates"
+                        {locale.t("subscriptions-subtitle")}
                     }
                 }
 
                 div { class: "max-w-4xl mx-auto",
+                    if cfg!(feature = "desktop") {
+                        div { class: "bg-gray-800 rounded-xl p-6 border border-gray-700 mb-8",
+                            h2 { class: "text-xl font-bold text-gray-100 mb-4", {locale.t("subscriptions-desktop-title")} }
+                            label { class: "flex items-center space-x-2 cursor-pointer",
+                                input {
+                                    r#type: "checkbox",
+                                    class: "w-4 h-4 text-blue-600 bg-gray-700 border-gray-600 rounded focus:ring-blue-500",
+                                    checked: desktop_notifications(),
+                                    onchange: move |evt: Event<FormData>| {
+                                        set_desktop_notifications_enabled(evt.checked());
+                                        desktop_notifications.set(evt.checked());
+                                    }
+                                }
+                                span { class: "text-sm text-gray-300",
+                                    {locale.t("subscriptions-desktop-toggle")}
+                                }
+                            }
+                        }
+                    }
+                    if let Some(settings) = profile() {
+                        div { class: "bg-gray-800 rounded-xl p-6 border border-gray-700 mb-8",
+                            h2 { class: "text-xl font-bold text-gray-100 mb-4", {locale.t("subscriptions-profile-title")} }
+                            div { class: "flex flex-col space-y-3",
+                                label { class: "flex items-center space-x-2 cursor-pointer",
+                                    input {
+                                        r#type: "checkbox",
+                                        class: "w-4 h-4 text-blue-600 bg-gray-700 border-gray-600 rounded focus:ring-blue-500",
+                                        checked: settings.public,
+                                        onchange: {
+                                            let settings = settings.clone();
+                                            move |evt: Event<FormData>| {
+                                                save_profile(ProfileSettings { public: evt.checked(), ..settings.clone() });
+                                            }
+                                        }
+                                    }
+                                    span { class: "text-sm text-gray-300", {locale.t("subscriptions-profile-public")} }
+                                }
+                                label { class: "flex items-center space-x-2 cursor-pointer",
+                                    input {
+                                        r#type: "checkbox",
+                                        class: "w-4 h-4 text-blue-600 bg-gray-700 border-gray-600 rounded focus:ring-blue-500",
+                                        checked: settings.show_subscriptions,
+                                        disabled: !settings.public,
+                                        onchange: {
+                                            let settings = settings.clone();
+                                            move |evt: Event<FormData>| {
+                                                save_profile(ProfileSettings { show_subscriptions: evt.checked(), ..settings.clone() });
+                                            }
+                                        }
+                                    }
+                                    span { class: "text-sm text-gray-300", {locale.t("subscriptions-profile-show-follows")} }
+                                }
+                                if settings.public {
+                                    Link {
+                                        to: crate::Route::Profile { username: username.clone() },
+                                        class: "text-blue-400 hover:text-blue-300 text-sm",
+                                        {locale.t("subscriptions-profile-view")}
+                                    }
+                                }
+                            }
+                        }
+                    }
+
                     if loading() {
                         div { class: "flex justify-center py-12",
                             div { class: "animate-spin rounded-full h-12 w-12 border-b-2 border-blue-500" }
@@ -46,12 +144,12 @@ pub fn Subscriptions() -> Element {
                                 svg { class: "w-16 h-16 text-gray-600 mx-auto mb-4", fill: "none", stroke: "currentColor", view_box: "0 0 24 24",
                                     path { stroke_linecap: "round", stroke_linejoin: "round", stroke_width: "2", d: "M20 13V6a2 2 0 00-2-2H6a2 2 0 00-2 2v7m16 0v5a2 2 0 01-2 2H6a2 2 0 01-2-2v-5m16 0h-2.586a1 1 0 00-.707.293l-2.414 2.414a1 1 0 01-.707.293h-3.172a1 1 0 01-.707-.293l-2.414-2.414A1 1 0 006.586 13H4" }
                                 }
-                                h3 { class: "text-xl font-semibold text-gray-300 mb-2", "No Subscriptions Yet" }
-                                p { class: "text-gray-400 mb-6", "Start following packages to get updates on new versions and changes" }
+                                h3 { class: "text-xl font-semibold text-gray-300 mb-2", {locale.t("subscriptions-empty-title")} }
+                                p { class: "text-gray-400 mb-6", {locale.t("subscriptions-empty-body")} }
                                 Link {
-                                    to: crate::Route::Packages {},
+                                    to: crate::Route::Packages { query: Default::default() },
                                     class: "inline-block px-6 py-3 bg-blue-600 hover:bg-blue-700 text-white rounded-lg transition-colors",
-                                    "Browse Packages"
+                                    {locale.t("subscriptions-browse")}
                                 }
                             }
                         }
@@ -61,7 +159,7 @@ pub fn Subscriptions() -> Element {
                                 {
                                     let pkg_name = sub.package_name.clone();
                                     let pkg_name_toggle = sub.package_name.clone();
-                                    let pkg_name_unsub = sub.package_name.clone();
+                                    let sub_unsub = sub.clone();
                                     let notif_enabled = sub.notifications_enabled;
                                     let token_toggle = auth_token.clone();
                                     let token_unsub = auth_token.clone();
@@ -81,7 +179,7 @@ pub fn Subscriptions() -> Element {
                                                                 href: "{repository}",
                                                                 target: "_blank",
                                                                 class: "text-blue-400 hover:text-blue-300 text-sm",
-                                                                "View Repository"
+                                                                {locale.t("subscriptions-view-repository")}
                                                             }
                                                         }
                                                     }
@@ -104,23 +202,34 @@ pub fn Subscriptions() -> Element {
                                                                 });
                                                             }
                                                         }
-                                                        span { class: "text-sm text-gray-300", "Email Notifications" }
+                                                        span { class: "text-sm text-gray-300", {locale.t("subscriptions-email-notifications")} }
                                                     }
                                                     button {
                                                         class: "px-4 py-2 bg-red-500 hover:bg-red-600 text-white rounded-lg transition-colors",
                                                         onclick: move |_| {
-                                                            let pkg = pkg_name_unsub.clone();
+                                                            let sub = sub_unsub.clone();
                                                             let token = token_unsub.clone();
+                                                            let mut notif = notif;
+                                                            let locale = locale;
                                                             spawn(async move {
                                                                 if let Some(t) = token {
-                                                                    let client = ApiClient::new().with_token(Some(t));
-                                                                    if client.unsubscribe(&pkg).await.is_ok() {
-                                                                        subscriptions.write().retain(|s| s.package_name != pkg);
+                                                                    let client = ApiClient::new().with_token(Some(t.clone()));
+                                                                    if client.unsubscribe(&sub.package_name).await.is_ok() {
+                                                                        subscriptions.write().retain(|s| s.package_name != sub.package_name);
+                                                                        let mut args = FluentArgs::new();
+                                                                        args.set("package", sub.package_name.clone());
+                                                                        notif.show_with_action(
+                                                                            locale.t_args("subscriptions-unsubscribed", &args),
+                                                                            NotificationType::Success,
+                                                                            NotificationAction::new(locale.t("subscriptions-undo"), move || {
+                                                                                resubscribe(t.clone(), sub.clone(), subscriptions)
+                                                                            }),
+                                                                        );
                                                                     }
                                                                 }
                                                             });
                                                         },
-                                                        "Unsubscribe"
+                                                        {locale.t("subscriptions-unsubscribe")}
                                                     }
                                                 }
                                             }
@@ -135,3 +244,23 @@ pub fn Subscriptions() -> Element {
         }
     }
 }
+
+/// Follow a package again after an unsubscribe was undone, keeping its
+/// notification setting
+async fn resubscribe(
+    token: String,
+    sub: SubscriptionResponse,
+    mut subscriptions: Signal<Vec<SubscriptionResponse>>,
+) {
+    let client = ApiClient::new().with_token(Some(token));
+    if client.subscribe(sub.package_name.clone()).await.is_err() {
+        return;
+    }
+    if !sub.notifications_enabled {
+        let _ = client.toggle_notifications(&sub.package_name, false).await;
+    }
+    // The page may have been left in the meantime
+    if let Ok(mut subscriptions) = subscriptions.try_write() {
+        subscriptions.push(sub);
+    }
+}
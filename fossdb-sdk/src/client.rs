@@ -0,0 +1,493 @@
+use crate::error::{Error, Result};
+use crate::types::*;
+use reqwest::{Method, RequestBuilder};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Client for a FossDB server's `/api` endpoints
+#[derive(Clone, Debug)]
+pub struct ApiClient {
+    http: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+}
+
+impl ApiClient {
+    /// Create a client for the API at `base_url`, e.g. `http://localhost:3000/api`
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            token: None,
+        }
+    }
+
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token;
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let builder = self
+            .http
+            .request(method, format!("{}{}", self.base_url, path));
+        match &self.token {
+            Some(token) => builder.bearer_auth(token),
+            None => builder,
+        }
+    }
+
+    async fn send<T: DeserializeOwned>(builder: RequestBuilder) -> Result<T> {
+        let resp = builder.send().await?;
+        if !resp.status().is_success() {
+            return Err(Error::from_response(&resp));
+        }
+        Ok(resp.json().await?)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        Self::send(self.request(Method::GET, path)).await
+    }
+
+    async fn post<B: Serialize, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<T> {
+        Self::send(self.request(Method::POST, path).json(body)).await
+    }
+
+    pub async fn login(&self, email: String, password: String) -> Result<AuthResponse> {
+        self.post("/auth/login", &LoginRequest { email, password })
+            .await
+    }
+
+    pub async fn register(
+        &self,
+        username: String,
+        email: String,
+        password: String,
+    ) -> Result<AuthResponse> {
+        let body = RegisterRequest {
+            username,
+            email,
+            password,
+        };
+        self.post("/auth/register", &body).await
+    }
+
+    pub async fn get_stats(&self) -> Result<DatabaseStats> {
+        self.get("/stats").await
+    }
+
+    /// Rate limit tiers, for pacing requests before hitting a 429
+    pub async fn get_limits(&self) -> Result<RateLimitsResponse> {
+        self.get("/limits").await
+    }
+
+    /// Languages packages can be filtered by, with how many have each
+    pub async fn get_languages(&self) -> Result<Vec<FilterOption>> {
+        self.get("/meta/languages").await
+    }
+
+    /// Licenses packages can be filtered by, with how many have each
+    pub async fn get_licenses(&self) -> Result<Vec<FilterOption>> {
+        self.get("/meta/licenses").await
+    }
+
+    /// Registries packages are collected from, largest first
+    pub async fn list_platforms(&self) -> Result<Vec<PlatformSummary>> {
+        self.get("/platforms").await
+    }
+
+    pub async fn get_platform_stats(&self, name: &str) -> Result<PlatformStats> {
+        self.get(&format!("/platforms/{}/stats", name)).await
+    }
+
+    fn packages_request(
+        &self,
+        search: Option<&str>,
+        sort: Option<&str>,
+        page: u32,
+        limit: u32,
+    ) -> RequestBuilder {
+        let mut builder = self
+            .request(Method::GET, "/packages")
+            .query(&[("page", page), ("limit", limit)]);
+        if let Some(search) = search {
+            builder = builder.query(&[("search", search)]);
+        }
+        if let Some(sort) = sort {
+            builder = builder.query(&[("sort", sort)]);
+        }
+        builder
+    }
+
+    /// `sort` is a comma-separated list of fields such as `rank,-updated_at`,
+    /// by rank when None
+    pub async fn get_packages(
+        &self,
+        search: Option<&str>,
+        sort: Option<&str>,
+        page: u32,
+        limit: u32,
+    ) -> Result<PackagesResponse> {
+        Self::send(self.packages_request(search, sort, page, limit)).await
+    }
+
+    /// Up to `limit` packages whose name starts with `prefix`
+    pub async fn autocomplete_packages(
+        &self,
+        prefix: &str,
+        limit: u32,
+    ) -> Result<Vec<PackageSuggestion>> {
+        let builder = self
+            .request(Method::GET, "/packages/autocomplete")
+            .query(&[("q", prefix)])
+            .query(&[("limit", limit)]);
+        Self::send(builder).await
+    }
+
+    pub async fn get_package(&self, id: u64) -> Result<PackageResponse> {
+        self.get(&format!("/packages/{}", id)).await
+    }
+
+    /// Release, dependency and security figures for `ids` side by side
+    pub async fn compare_packages(&self, ids: &[u64]) -> Result<Vec<PackageComparison>> {
+        let ids: Vec<String> = ids.iter().map(u64::to_string).collect();
+        self.get(&format!("/packages/compare?ids={}", ids.join(","))).await
+    }
+
+    pub async fn get_package_versions(&self, id: u64) -> Result<Vec<PackageVersion>> {
+        self.get(&format!("/packages/{}/versions", id)).await
+    }
+
+    pub async fn get_dependency_graph(
+        &self,
+        id: u64,
+        version: Option<&str>,
+    ) -> Result<DependencyGraph> {
+        let builder = self.request(Method::GET, &format!("/packages/{}/dependencies", id));
+        match version {
+            Some(version) => Self::send(builder.query(&[("version", version)])).await,
+            None => Self::send(builder).await,
+        }
+    }
+
+    pub async fn get_package_activity(
+        &self,
+        id: u64,
+        offset: usize,
+        limit: usize,
+    ) -> Result<TimelineResponse> {
+        let builder = self
+            .request(Method::GET, &format!("/packages/{}/activity", id))
+            .query(&[("offset", offset), ("limit", limit)]);
+        Self::send(builder).await
+    }
+
+    /// Releases per week over the past `years`, oldest first
+    pub async fn get_package_activity_heatmap(
+        &self,
+        id: u64,
+        years: u32,
+    ) -> Result<Vec<ActivityWeek>> {
+        let builder = self
+            .request(Method::GET, &format!("/packages/{}/activity/heatmap", id))
+            .query(&[("years", years)]);
+        Self::send(builder).await
+    }
+
+    /// How many of the authenticated user's timeline events are unread
+    pub async fn get_unread_count(&self) -> Result<TimelineReadState> {
+        self.get("/users/timeline/unread").await
+    }
+
+    /// Mark timeline events up to `event_id` read, or all of them when None
+    pub async fn mark_timeline_read(&self, event_id: Option<u64>) -> Result<TimelineReadState> {
+        self.post("/users/timeline/read", &serde_json::json!({ "event_id": event_id }))
+            .await
+    }
+
+    pub async fn get_subscriptions(&self) -> Result<Vec<SubscriptionResponse>> {
+        let response: SubscriptionsResponse = self.get("/users/subscriptions").await?;
+        Ok(response.subscriptions)
+    }
+
+    /// Subscribe to a package, returning the updated subscription list
+    pub async fn subscribe(&self, package_name: String) -> Result<Vec<SubscriptionResponse>> {
+        let response: SubscriptionsResponse = self
+            .post(
+                "/users/subscriptions",
+                &SubscriptionRequest { package_name },
+            )
+            .await?;
+        Ok(response.subscriptions)
+    }
+
+    /// Unsubscribe from a package, returning the updated subscription list
+    pub async fn unsubscribe(&self, package_name: &str) -> Result<Vec<SubscriptionResponse>> {
+        let builder = self.request(
+            Method::DELETE,
+            &format!("/users/subscriptions/{}", package_name),
+        );
+        let response: SubscriptionsResponse = Self::send(builder).await?;
+        Ok(response.subscriptions)
+    }
+
+    pub async fn toggle_notifications(
+        &self,
+        package_name: &str,
+        enabled: bool,
+    ) -> Result<Vec<SubscriptionResponse>> {
+        let builder = self
+            .request(
+                Method::PUT,
+                &format!("/users/subscriptions/{}/notifications", package_name),
+            )
+            .json(&serde_json::json!({ "notifications_enabled": enabled }));
+        let response: SubscriptionsResponse = Self::send(builder).await?;
+        Ok(response.subscriptions)
+    }
+
+    pub async fn get_public_profile(&self, username: &str) -> Result<PublicProfile> {
+        self.get(&format!("/users/{}", username)).await
+    }
+
+    pub async fn get_profile_settings(&self) -> Result<ProfileSettings> {
+        self.get("/users/settings/profile").await
+    }
+
+    pub async fn update_profile_settings(
+        &self,
+        settings: &ProfileSettings,
+    ) -> Result<ProfileSettings> {
+        Self::send(
+            self.request(Method::PUT, "/users/settings/profile")
+                .json(settings),
+        )
+        .await
+    }
+
+    pub async fn get_timeline_preferences(&self) -> Result<TimelinePreferences> {
+        self.get("/users/settings/timeline").await
+    }
+
+    pub async fn update_timeline_preferences(
+        &self,
+        preferences: &TimelinePreferences,
+    ) -> Result<TimelinePreferences> {
+        Self::send(
+            self.request(Method::PUT, "/users/settings/timeline")
+                .json(preferences),
+        )
+        .await
+    }
+
+    pub async fn get_projects(&self) -> Result<Vec<Project>> {
+        self.get("/projects").await
+    }
+
+    pub async fn get_project(&self, id: u64) -> Result<Project> {
+        self.get(&format!("/projects/{}", id)).await
+    }
+
+    /// Track the dependencies resolved in a Cargo.lock, package-lock.json or
+    /// pinned requirements.txt
+    pub async fn create_project(&self, name: String, lockfile: String) -> Result<ProjectResponse> {
+        self.post("/projects", &ProjectRequest { name, lockfile })
+            .await
+    }
+
+    /// Replace a project's lockfile, returning what changed since the last one
+    pub async fn update_project_lockfile(
+        &self,
+        id: u64,
+        lockfile: String,
+    ) -> Result<ProjectResponse> {
+        Self::send(
+            self.request(Method::PUT, &format!("/projects/{}/lockfile", id))
+                .json(&LockfileRequest { lockfile }),
+        )
+        .await
+    }
+
+    pub async fn delete_project(&self, id: u64) -> Result<()> {
+        let resp = self
+            .request(Method::DELETE, &format!("/projects/{}", id))
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            return Err(Error::from_response(&resp));
+        }
+        Ok(())
+    }
+
+    pub async fn get_project_sync(&self, id: u64) -> Result<ProjectSync> {
+        self.get(&format!("/projects/{}/sync", id)).await
+    }
+
+    /// Keep a project in step with a lockfile in a repository the GitHub App
+    /// is installed on
+    pub async fn set_project_sync(
+        &self,
+        id: u64,
+        request: &ProjectSyncRequest,
+    ) -> Result<ProjectSync> {
+        Self::send(
+            self.request(Method::PUT, &format!("/projects/{}/sync", id))
+                .json(request),
+        )
+        .await
+    }
+
+    pub async fn get_github_installations(&self) -> Result<Vec<GithubInstallation>> {
+        self.get("/github/installations").await
+    }
+
+    /// Connect an installation with the code GitHub passed to the app's setup URL
+    pub async fn add_github_installation(
+        &self,
+        installation_id: u64,
+        code: String,
+    ) -> Result<GithubInstallation> {
+        let body = GithubInstallationRequest {
+            installation_id,
+            code,
+        };
+        self.post("/github/installations", &body).await
+    }
+
+    /// Events for the authenticated user's subscriptions
+    pub async fn get_timeline(&self, offset: usize, limit: usize) -> Result<TimelineResponse> {
+        let builder = self
+            .request(Method::GET, "/users/timeline")
+            .query(&[("offset", offset), ("limit", limit)]);
+        Self::send(builder).await
+    }
+
+    pub async fn get_vulnerabilities(
+        &self,
+        severity: Option<&str>,
+        page: u32,
+        limit: u32,
+    ) -> Result<VulnerabilitiesResponse> {
+        let builder = self
+            .request(Method::GET, "/vulnerabilities")
+            .query(&[("page", page), ("limit", limit)]);
+        match severity {
+            Some(severity) => Self::send(builder.query(&[("severity", severity)])).await,
+            None => Self::send(builder).await,
+        }
+    }
+
+    pub async fn get_vulnerability(&self, id: u64) -> Result<VulnerabilityDetail> {
+        self.get(&format!("/vulnerabilities/{}", id)).await
+    }
+
+    /// Dismiss an advisory everywhere, or only for `request.project_id`
+    pub async fn acknowledge_vulnerability(
+        &self,
+        id: u64,
+        request: &AdvisoryAckRequest,
+    ) -> Result<AdvisoryAck> {
+        self.post(&format!("/vulnerabilities/{}/ack", id), request)
+            .await
+    }
+
+    pub async fn unacknowledge_vulnerability(
+        &self,
+        id: u64,
+        project_id: Option<u64>,
+    ) -> Result<()> {
+        let mut builder = self.request(Method::DELETE, &format!("/vulnerabilities/{}/ack", id));
+        if let Some(project_id) = project_id {
+            builder = builder.query(&[("project_id", project_id)]);
+        }
+        let resp = builder.send().await?;
+        if !resp.status().is_success() {
+            return Err(Error::from_response(&resp));
+        }
+        Ok(())
+    }
+
+    /// Recommend `request.replacement_id` in place of package `id`
+    pub async fn set_package_replacement(
+        &self,
+        id: u64,
+        request: &PackageReplacementRequest,
+    ) -> Result<Successor> {
+        Self::send(
+            self.request(Method::PUT, &format!("/packages/{}/replacement", id))
+                .json(request),
+        )
+        .await
+    }
+
+    pub async fn delete_package_replacement(&self, id: u64) -> Result<()> {
+        let resp = self
+            .request(Method::DELETE, &format!("/packages/{}/replacement", id))
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            return Err(Error::from_response(&resp));
+        }
+        Ok(())
+    }
+
+    pub async fn list_advisory_acks(&self) -> Result<Vec<AdvisoryAck>> {
+        self.get("/users/acknowledgements").await
+    }
+
+    pub async fn get_analytics(&self) -> Result<AnalyticsResponse> {
+        self.get("/analytics").await
+    }
+
+    pub async fn get_growth(&self, period: &str) -> Result<Vec<GrowthPoint>> {
+        let builder = self
+            .request(Method::GET, "/analytics/growth")
+            .query(&[("period", period)]);
+        Self::send(builder).await
+    }
+
+    /// Activity from `from` to `to` (`YYYY-MM-DD`, inclusive) in buckets of
+    /// `granularity`: `day`, `week` or `month`
+    pub async fn get_rollups(
+        &self,
+        from: &str,
+        to: &str,
+        granularity: &str,
+    ) -> Result<Vec<RollupPoint>> {
+        let builder = self
+            .request(Method::GET, "/analytics/rollups")
+            .query(&[("from", from), ("to", to), ("granularity", granularity)]);
+        Self::send(builder).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_is_encoded() {
+        let client = ApiClient::new("http://localhost:3000/api/");
+        let request = client
+            .packages_request(Some("foo bar&baz"), Some("rank,-updated_at"), 2, 20)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            request.url().as_str(),
+            "http://localhost:3000/api/packages?page=2&limit=20&search=foo+bar%26baz&sort=rank%2C-updated_at"
+        );
+    }
+
+    #[test]
+    fn test_token_is_sent_as_bearer() {
+        let client = ApiClient::new("http://localhost:3000/api").with_token(Some("abc".into()));
+        let request = client.request(Method::GET, "/stats").build().unwrap();
+
+        assert_eq!(request.headers()["authorization"], "Bearer abc");
+    }
+}
@@ -0,0 +1,72 @@
+use reqwest::StatusCode;
+use std::fmt;
+use std::time::Duration;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The server couldn't be reached or sent a body that didn't parse
+    Request(reqwest::Error),
+    /// The token is missing, expired or was rejected
+    Unauthorized,
+    NotFound,
+    /// Too many requests, with how long the server asked us to wait
+    RateLimited(Option<Duration>),
+    /// Any other error status
+    Status(StatusCode),
+}
+
+impl Error {
+    pub(crate) fn from_response(resp: &reqwest::Response) -> Self {
+        match resp.status() {
+            StatusCode::UNAUTHORIZED => Error::Unauthorized,
+            StatusCode::NOT_FOUND => Error::NotFound,
+            StatusCode::TOO_MANY_REQUESTS => Error::RateLimited(
+                resp.headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse().ok())
+                    .map(Duration::from_secs),
+            ),
+            status => Error::Status(status),
+        }
+    }
+
+    /// Whether the same request might succeed if sent again later
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::Request(e) => e.is_request() || e.is_timeout(),
+            Error::RateLimited(_) => true,
+            Error::Status(status) => status.is_server_error(),
+            Error::Unauthorized | Error::NotFound => false,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Request(e) => write!(f, "request failed: {}", e),
+            Error::Unauthorized => write!(f, "not authorized"),
+            Error::NotFound => write!(f, "not found"),
+            Error::RateLimited(_) => write!(f, "rate limited"),
+            Error::Status(status) => write!(f, "server returned {}", status),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Request(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Request(e)
+    }
+}
@@ -0,0 +1,18 @@
+//! Typed client for the FossDB HTTP API.
+//!
+//! Requests and responses use the same types as the server, re-exported from
+//! [`types`], so the two can't drift apart. Works natively and on wasm.
+//!
+//! [`ApiClient`] maps one method to one endpoint. [`FossdbClient`] builds on it
+//! with retries, token refresh and paginated streams.
+
+mod client;
+mod error;
+mod retry;
+mod sdk;
+
+pub use client::ApiClient;
+pub use error::{Error, Result};
+pub use fossdb as types;
+pub use retry::RetryPolicy;
+pub use sdk::FossdbClient;
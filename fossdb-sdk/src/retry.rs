@@ -0,0 +1,94 @@
+use crate::error::Error;
+use std::time::Duration;
+
+/// How often and how patiently to retry requests that failed transiently
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Attempts after the first, so 0 disables retrying
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before retry number `attempt` (starting at 0), or None to give up.
+    /// Uses the server's Retry-After when given, otherwise exponential backoff
+    /// with full jitter so many clients don't retry in lockstep.
+    pub fn delay(&self, attempt: u32, error: &Error) -> Option<Duration> {
+        if attempt >= self.max_retries || !error.is_transient() {
+            return None;
+        }
+        if let Error::RateLimited(Some(retry_after)) = error {
+            return Some(*retry_after);
+        }
+
+        let ceiling = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        Some(ceiling.mul_f64(fastrand::f64()))
+    }
+}
+
+pub(crate) async fn sleep(duration: Duration) {
+    #[cfg(target_arch = "wasm32")]
+    gloo_timers::future::sleep(duration).await;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    tokio::time::sleep(duration).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+
+    #[test]
+    fn test_delay_is_capped_and_jittered() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+        };
+        let error = Error::Status(StatusCode::BAD_GATEWAY);
+
+        for attempt in 0..10 {
+            let delay = policy.delay(attempt, &error).unwrap();
+            assert!(delay <= Duration::from_secs(1));
+        }
+        assert!(policy.delay(10, &error).is_none());
+    }
+
+    #[test]
+    fn test_only_transient_errors_are_retried() {
+        let policy = RetryPolicy::default();
+
+        assert!(policy.delay(0, &Error::NotFound).is_none());
+        assert!(
+            policy
+                .delay(0, &Error::Status(StatusCode::BAD_REQUEST))
+                .is_none()
+        );
+        assert_eq!(
+            policy.delay(0, &Error::RateLimited(Some(Duration::from_secs(3)))),
+            Some(Duration::from_secs(3))
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use crate::client::ApiClient;
+use crate::error::{Error, Result};
+use crate::retry::{RetryPolicy, sleep};
+use crate::types::*;
+use futures::stream::{self, Stream, TryStreamExt};
+use std::future::Future;
+use std::sync::RwLock;
+
+/// Largest page the server returns
+const PAGE_SIZE: u32 = 100;
+
+/// High level client that retries transient failures, logs in again when its
+/// token expires and pages through listings as streams
+pub struct FossdbClient {
+    api: ApiClient,
+    token: RwLock<Option<String>>,
+    credentials: RwLock<Option<(String, String)>>,
+    retry: RetryPolicy,
+}
+
+impl FossdbClient {
+    /// Create a client for the API at `base_url`, e.g. `http://localhost:3000/api`
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            api: ApiClient::new(base_url),
+            token: RwLock::new(None),
+            credentials: RwLock::new(None),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    pub fn with_token(self, token: Option<String>) -> Self {
+        *self.token.write().unwrap() = token;
+        self
+    }
+
+    /// Log in with these credentials whenever the token is missing or rejected
+    pub fn with_credentials(self, email: String, password: String) -> Self {
+        *self.credentials.write().unwrap() = Some((email, password));
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// The low level client, carrying the current token
+    pub fn api(&self) -> ApiClient {
+        self.api
+            .clone()
+            .with_token(self.token.read().unwrap().clone())
+    }
+
+    /// Log in and keep the credentials to refresh the token later
+    pub async fn login(&self, email: String, password: String) -> Result<User> {
+        let auth = self.api.login(email.clone(), password.clone()).await?;
+        *self.token.write().unwrap() = Some(auth.token);
+        *self.credentials.write().unwrap() = Some((email, password));
+        Ok(auth.user)
+    }
+
+    async fn refresh_token(&self) -> Result<()> {
+        let Some((email, password)) = self.credentials.read().unwrap().clone() else {
+            return Err(Error::Unauthorized);
+        };
+        let auth = self.api.login(email, password).await?;
+        *self.token.write().unwrap() = Some(auth.token);
+        Ok(())
+    }
+
+    /// Run a request against the low level client, retrying transient failures
+    /// and refreshing the token once if it was rejected
+    pub async fn call<T, F, Fut>(&self, request: F) -> Result<T>
+    where
+        F: Fn(ApiClient) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+        let mut refreshed = false;
+        loop {
+            let error = match request(self.api()).await {
+                Ok(value) => return Ok(value),
+                Err(e) => e,
+            };
+
+            if matches!(error, Error::Unauthorized) && !refreshed {
+                refreshed = true;
+                if self.credentials.read().unwrap().is_some() {
+                    self.refresh_token().await?;
+                    continue;
+                }
+            }
+
+            let delay = self.retry.delay(attempt, &error).ok_or(error)?;
+            attempt += 1;
+            sleep(delay).await;
+        }
+    }
+
+    pub async fn get_stats(&self) -> Result<DatabaseStats> {
+        self.call(|api| async move { api.get_stats().await }).await
+    }
+
+    pub async fn get_package(&self, id: u64) -> Result<PackageResponse> {
+        self.call(|api| async move { api.get_package(id).await })
+            .await
+    }
+
+    pub async fn get_subscriptions(&self) -> Result<Vec<SubscriptionResponse>> {
+        self.call(|api| async move { api.get_subscriptions().await })
+            .await
+    }
+
+    pub async fn subscribe(&self, package_name: &str) -> Result<Vec<SubscriptionResponse>> {
+        self.call(|api| async move { api.subscribe(package_name.to_string()).await })
+            .await
+    }
+
+    pub async fn unsubscribe(&self, package_name: &str) -> Result<Vec<SubscriptionResponse>> {
+        self.call(|api| async move { api.unsubscribe(package_name).await })
+            .await
+    }
+
+    /// Every package matching `search`, fetched a page at a time as the stream
+    /// is polled
+    pub fn packages<'a>(
+        &'a self,
+        search: Option<&'a str>,
+    ) -> impl Stream<Item = Result<Package>> + 'a {
+        stream::try_unfold(Some(1), move |page| async move {
+            let Some(page) = page else {
+                return Ok::<_, Error>(None);
+            };
+            let response = self
+                .call(|api| async move { api.get_packages(search, None, page, PAGE_SIZE).await })
+                .await?;
+
+            let fetched = page as usize * PAGE_SIZE as usize;
+            let next =
+                (fetched < response.total && !response.packages.is_empty()).then_some(page + 1);
+            Ok(Some((response.packages, next)))
+        })
+        .map_ok(|packages| stream::iter(packages.into_iter().map(Ok)))
+        .try_flatten()
+    }
+
+    /// Every event in the authenticated user's timeline, newest first
+    pub fn timeline(&self) -> impl Stream<Item = Result<TimelineEvent>> + '_ {
+        let limit = PAGE_SIZE as usize;
+        stream::try_unfold(Some(0), move |offset| async move {
+            let Some(offset) = offset else {
+                return Ok::<_, Error>(None);
+            };
+            let response = self
+                .call(|api| async move { api.get_timeline(offset, limit).await })
+                .await?;
+
+            let fetched = offset + response.events.len();
+            let next = (fetched < response.total && !response.events.is_empty()).then_some(fetched);
+            Ok(Some((response.events, next)))
+        })
+        .map_ok(|events| stream::iter(events.into_iter().map(Ok)))
+        .try_flatten()
+    }
+}
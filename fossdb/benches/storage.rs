@@ -0,0 +1,118 @@
+//! Storage and search hot paths against large synthetic datasets.
+//!
+//! Run with `cargo bench -p fossdb --features test-support`.
+use axum::Router;
+use axum::routing::get;
+use chrono::Utc;
+use criterion::{BatchSize, BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use std::sync::Arc;
+
+use fossdb::db::Database;
+use fossdb::fixtures::{self, TestClient};
+use fossdb::storage::Storage;
+use fossdb::websocket::TimelineBroadcaster;
+use fossdb::{EventType, Package, TimelineEvent, timeline};
+
+const PACKAGES: usize = 10_000;
+const TAGS: &[&str] = &["async", "cli", "database", "parser", "web", "crypto"];
+
+fn packages(prefix: &str, count: usize) -> Vec<Package> {
+    (0..count)
+        .map(|i| Package {
+            tags: vec!["rust".to_string(), TAGS[i % TAGS.len()].to_string()],
+            ..fixtures::package(&format!("{}-{}", prefix, i))
+        })
+        .collect()
+}
+
+fn insert_packages(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert_packages");
+    for batch in [100, 1_000] {
+        group.throughput(Throughput::Elements(batch as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(batch), &batch, |b, &batch| {
+            b.iter_batched(
+                || (Database::in_memory().unwrap(), packages("batch", batch)),
+                |(db, packages)| db.insert_packages(packages).unwrap(),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn list_packages(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let db = Database::in_memory().unwrap();
+    db.insert_packages(packages("package", PACKAGES)).unwrap();
+    let client = TestClient::new(
+        Router::new()
+            .route("/api/packages", get(fossdb::handlers::packages::list_packages))
+            .with_state(fixtures::app_state(Arc::new(db))),
+    );
+
+    let mut group = c.benchmark_group("list_packages");
+    for (name, uri) in [
+        ("first_page", "/api/packages"),
+        ("last_page", "/api/packages?page=100&limit=100"),
+        ("search", "/api/packages?search=package-99"),
+        ("search_miss", "/api/packages?search=nothing-matches"),
+        ("tag", "/api/packages?tag=parser"),
+    ] {
+        group.bench_function(name, |b| b.iter(|| runtime.block_on(client.get(uri))));
+    }
+    group.finish();
+}
+
+/// A package followed by `subscribers` users, and a release of it
+fn release(subscribers: usize) -> (Database, TimelineEvent) {
+    let db = Database::in_memory().unwrap();
+    let package = db.insert_package(fixtures::package("popular")).unwrap();
+    for i in 0..subscribers {
+        db.insert_user(fixtures::user(&format!("user{}", i), &["popular"]))
+            .unwrap();
+    }
+    let event = TimelineEvent {
+        id: 0,
+        package_id: package.id,
+        user_id: None,
+        event_type: EventType::NewRelease,
+        package_name: package.name,
+        version: Some("1.0.0".to_string()),
+        message: "popular 1.0.0 released".to_string(),
+        metadata: None,
+        created_at: Utc::now(),
+        notified_at: None,
+    };
+    (db, event)
+}
+
+fn subscription_fan_out(c: &mut Criterion) {
+    let broadcaster = TimelineBroadcaster::new();
+
+    let mut group = c.benchmark_group("subscription_fan_out");
+    group.sample_size(10);
+    for subscribers in [100, 1_000] {
+        group.throughput(Throughput::Elements(subscribers as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(subscribers),
+            &subscribers,
+            |b, &subscribers| {
+                // A fresh timeline each time, since the duplicate check
+                // scans the package's history
+                b.iter_batched(
+                    || release(subscribers),
+                    |(db, event)| timeline::publish(&db, &broadcaster, event).unwrap(),
+                    BatchSize::PerIteration,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(20);
+    targets = insert_packages, list_packages, subscription_fan_out
+}
+criterion_main!(benches);
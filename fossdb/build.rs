@@ -0,0 +1,14 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    {
+        // Use the bundled protoc so builds don't need one installed
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("no bundled protoc");
+        // SAFETY: build scripts are single-threaded
+        unsafe { std::env::set_var("PROTOC", protoc) };
+        println!("cargo:rerun-if-changed=proto/fossdb.proto");
+        tonic_prost_build::configure()
+            .build_client(false)
+            .compile_protos(&["proto/fossdb.proto"], &["proto"])
+            .expect("failed to compile protos");
+    }
+}
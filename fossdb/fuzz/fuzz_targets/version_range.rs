@@ -0,0 +1,19 @@
+// Version and range strings come straight from registries and users
+#![no_main]
+
+use fossdb::version_range::{highest_matching, matches, parse_range, parse_version};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &str| {
+    // Split the input so ranges and versions are fuzzed against each other
+    let (range, version) = data.split_once('\n').unwrap_or((data, data));
+
+    if let Some(parsed) = parse_version(version) {
+        assert_eq!(parse_version(&parsed.to_string()), Some(parsed));
+    }
+    if let Some(parsed) = parse_range(range) {
+        assert_eq!(parse_range(&parsed.to_string()), Some(parsed));
+    }
+    let _ = matches(range, version);
+    let _ = highest_matching(range, version.split(','));
+});
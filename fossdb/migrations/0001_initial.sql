@@ -0,0 +1,61 @@
+-- Records are stored whole as JSON, with the columns they're looked up by
+-- pulled out alongside, mirroring the embedded database's secondary keys.
+
+CREATE TABLE packages (
+    id BIGSERIAL PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    data JSONB NOT NULL
+);
+
+CREATE TABLE package_versions (
+    id BIGSERIAL PRIMARY KEY,
+    package_id BIGINT NOT NULL,
+    data JSONB NOT NULL
+);
+CREATE INDEX package_versions_package_id ON package_versions (package_id);
+
+CREATE TABLE users (
+    id BIGSERIAL PRIMARY KEY,
+    email TEXT NOT NULL UNIQUE,
+    username TEXT NOT NULL UNIQUE,
+    data JSONB NOT NULL
+);
+
+-- Derived from users.data, rewritten whenever a user is saved
+CREATE TABLE subscriptions (
+    user_id BIGINT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    package_name TEXT NOT NULL,
+    notifications_enabled BOOLEAN NOT NULL,
+    PRIMARY KEY (user_id, package_name)
+);
+CREATE INDEX subscriptions_package_name ON subscriptions (package_name);
+
+CREATE TABLE vulnerabilities (
+    id BIGSERIAL PRIMARY KEY,
+    data JSONB NOT NULL
+);
+
+CREATE TABLE timeline_events (
+    id BIGSERIAL PRIMARY KEY,
+    package_id BIGINT NOT NULL,
+    user_id BIGINT,
+    awaits_notification BOOLEAN NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    data JSONB NOT NULL
+);
+CREATE INDEX timeline_events_package_id ON timeline_events (package_id);
+CREATE INDEX timeline_events_user_id ON timeline_events (user_id);
+CREATE INDEX timeline_events_created_at ON timeline_events (created_at);
+CREATE INDEX timeline_events_pending ON timeline_events (id) WHERE awaits_notification;
+
+-- Lets every server replica pick up releases stored by any collector
+CREATE FUNCTION notify_package_version_insert() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('package_versions', NEW.id::text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER package_versions_notify
+    AFTER INSERT ON package_versions
+    FOR EACH ROW EXECUTE FUNCTION notify_package_version_insert();
@@ -0,0 +1,7 @@
+-- Work claimed by one process at a time, such as a collector run
+
+CREATE TABLE leases (
+    name TEXT PRIMARY KEY,
+    holder TEXT NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL
+);
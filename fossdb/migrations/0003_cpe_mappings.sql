@@ -0,0 +1,9 @@
+-- Which package each NVD vendor:product refers to
+
+CREATE TABLE cpe_mappings (
+    id BIGSERIAL PRIMARY KEY,
+    vendor_product TEXT NOT NULL UNIQUE,
+    package_id BIGINT NOT NULL,
+    data JSONB NOT NULL
+);
+CREATE INDEX cpe_mappings_package_id ON cpe_mappings (package_id);
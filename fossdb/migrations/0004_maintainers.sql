@@ -0,0 +1,14 @@
+-- People who maintain packages, matched across registries by handle
+
+CREATE TABLE maintainers (
+    id BIGSERIAL PRIMARY KEY,
+    handle TEXT NOT NULL UNIQUE,
+    data JSONB NOT NULL
+);
+
+CREATE TABLE package_maintainers (
+    package_id BIGINT NOT NULL,
+    maintainer_id BIGINT NOT NULL REFERENCES maintainers (id) ON DELETE CASCADE,
+    PRIMARY KEY (package_id, maintainer_id)
+);
+CREATE INDEX package_maintainers_maintainer_id ON package_maintainers (maintainer_id);
@@ -0,0 +1,8 @@
+-- Former names of renamed packages
+
+CREATE TABLE package_aliases (
+    name TEXT PRIMARY KEY,
+    package_id BIGINT NOT NULL,
+    data JSONB NOT NULL
+);
+CREATE INDEX package_aliases_package_id ON package_aliases (package_id);
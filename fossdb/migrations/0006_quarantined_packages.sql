@@ -0,0 +1,7 @@
+-- Collected packages rejected by the license policy, awaiting review
+
+CREATE TABLE quarantined_packages (
+    id BIGSERIAL PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    data JSONB NOT NULL
+);
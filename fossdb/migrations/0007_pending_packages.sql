@@ -0,0 +1,6 @@
+-- Collected packages hidden from the public API until a moderator approves them
+
+CREATE TABLE pending_packages (
+    package_id BIGINT PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL
+);
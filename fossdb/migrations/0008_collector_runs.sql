@@ -0,0 +1,9 @@
+-- Outcome and counters of each background collector run
+
+CREATE TABLE collector_runs (
+    id BIGSERIAL PRIMARY KEY,
+    collector TEXT NOT NULL,
+    data JSONB NOT NULL
+);
+
+CREATE INDEX collector_runs_collector ON collector_runs (collector);
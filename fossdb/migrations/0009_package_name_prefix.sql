@@ -0,0 +1,4 @@
+-- Prefix matches on package names, which the unique index can't serve under
+-- a non-C collation
+
+CREATE INDEX packages_name_prefix ON packages (name text_pattern_ops);
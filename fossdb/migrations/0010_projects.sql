@@ -0,0 +1,15 @@
+-- Dependency sets users track, resolved from their lockfiles
+
+CREATE TABLE projects (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    data JSONB NOT NULL
+);
+CREATE INDEX projects_user_id ON projects (user_id);
+
+CREATE TABLE project_dependencies (
+    project_id BIGINT NOT NULL REFERENCES projects (id) ON DELETE CASCADE,
+    package_name TEXT NOT NULL,
+    PRIMARY KEY (project_id, package_name)
+);
+CREATE INDEX project_dependencies_package_name ON project_dependencies (package_name);
@@ -0,0 +1,17 @@
+-- GitHub App installations users connected, and projects kept in step with a
+-- lockfile in one of their repositories
+
+CREATE TABLE github_installations (
+    id BIGINT PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    data JSONB NOT NULL
+);
+CREATE INDEX github_installations_user_id ON github_installations (user_id);
+
+CREATE TABLE project_syncs (
+    project_id BIGINT PRIMARY KEY REFERENCES projects (id) ON DELETE CASCADE,
+    installation_id BIGINT NOT NULL REFERENCES github_installations (id) ON DELETE CASCADE,
+    repository TEXT NOT NULL,
+    data JSONB NOT NULL
+);
+CREATE INDEX project_syncs_repository ON project_syncs (repository);
@@ -0,0 +1,11 @@
+-- Security advisories users marked as dealt with, everywhere or for one of
+-- their projects
+
+CREATE TABLE advisory_acks (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    vulnerability_id BIGINT NOT NULL REFERENCES vulnerabilities (id) ON DELETE CASCADE,
+    project_id BIGINT REFERENCES projects (id) ON DELETE CASCADE,
+    data JSONB NOT NULL
+);
+CREATE INDEX advisory_acks_user_id ON advisory_acks (user_id);
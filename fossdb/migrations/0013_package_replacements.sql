@@ -0,0 +1,8 @@
+-- The successor users of a deprecated package are pointed to
+
+CREATE TABLE package_replacements (
+    package_id BIGINT PRIMARY KEY,
+    replacement_id BIGINT NOT NULL,
+    data JSONB NOT NULL
+);
+CREATE INDEX package_replacements_replacement_id ON package_replacements (replacement_id);
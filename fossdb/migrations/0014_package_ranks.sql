@@ -0,0 +1,8 @@
+-- How prominent each package is, recomputed in the background
+
+CREATE TABLE package_ranks (
+    package_id BIGINT PRIMARY KEY,
+    score INTEGER NOT NULL,
+    data JSONB NOT NULL
+);
+CREATE INDEX package_ranks_score ON package_ranks (score DESC);
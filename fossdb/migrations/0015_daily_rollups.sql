@@ -0,0 +1,9 @@
+-- Daily activity across the catalog, kept up to date from timeline events
+-- and recounted in the background
+
+CREATE TABLE daily_rollups (
+    date TEXT PRIMARY KEY,
+    packages_added BIGINT NOT NULL,
+    versions_released BIGINT NOT NULL,
+    vulnerabilities_published BIGINT NOT NULL
+);
@@ -0,0 +1,6 @@
+-- When each user was last seen, for expiring inactive accounts
+
+CREATE TABLE account_activity (
+    user_id BIGINT PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    data JSONB NOT NULL
+);
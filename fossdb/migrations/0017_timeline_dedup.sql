@@ -0,0 +1,6 @@
+-- Looks up an event's duplicates directly instead of loading the package's
+-- whole timeline
+
+CREATE INDEX timeline_events_dedup
+    ON timeline_events (package_id, (data->>'event_type'), (data->>'version'), user_id)
+    WHERE data->>'version' IS NOT NULL;
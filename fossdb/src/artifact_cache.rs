@@ -0,0 +1,143 @@
+// Pull-through cache for package artifacts, verified against published checksums
+use anyhow::{Context, Result, bail};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::io::AsyncWriteExt;
+
+/// Outcome of fetching an artifact from upstream
+#[derive(Debug)]
+pub enum Fetched {
+    /// Stored in the cache at this path
+    Verified(PathBuf),
+    /// Upstream served content with a different SHA-256 digest
+    Mismatch(String),
+}
+
+pub struct ArtifactCache {
+    dir: PathBuf,
+    client: reqwest::Client,
+    max_size: u64,
+    next_temp: AtomicU64,
+}
+
+/// Reject path components that could escape the cache directory
+fn safe_component(component: &str) -> Option<&str> {
+    let valid = !component.is_empty()
+        && component != "."
+        && component != ".."
+        && !component.contains(['/', '\\', '\0']);
+    valid.then_some(component)
+}
+
+/// Whether a normalized checksum is a SHA-256 digest we can verify
+pub fn is_sha256(checksum: &str) -> bool {
+    checksum.len() == 64 && checksum.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+impl ArtifactCache {
+    pub fn new(dir: impl Into<PathBuf>, client: reqwest::Client, max_size_mb: u64) -> Self {
+        Self {
+            dir: dir.into(),
+            client,
+            max_size: max_size_mb * 1024 * 1024,
+            next_temp: AtomicU64::new(0),
+        }
+    }
+
+    /// Location of an artifact in the cache, or None if a component is unsafe
+    pub fn path(&self, platform: &str, name: &str, version: &str) -> Option<PathBuf> {
+        Some(
+            self.dir
+                .join(safe_component(platform)?)
+                .join(safe_component(name)?)
+                .join(safe_component(version)?),
+        )
+    }
+
+    /// Download `url` to `path`, keeping it only if its SHA-256 matches `expected`.
+    ///
+    /// Downloads go to a temporary file first so concurrent requests for the
+    /// same artifact never observe a partial or unverified file.
+    pub async fn fetch(&self, url: &str, path: &Path, expected: &str) -> Result<Fetched> {
+        let parent = path.parent().context("Artifact path has no parent")?;
+        let file_name = path.file_name().context("Artifact path has no file name")?;
+        tokio::fs::create_dir_all(parent).await?;
+
+        let temp = parent.join(format!(
+            ".{}.{}.{}.part",
+            file_name.to_string_lossy(),
+            std::process::id(),
+            self.next_temp.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let result = self.download(url, &temp).await;
+        let digest = match result {
+            Ok(digest) => digest,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&temp).await;
+                return Err(e);
+            }
+        };
+
+        if digest != expected {
+            tokio::fs::remove_file(&temp).await?;
+            return Ok(Fetched::Mismatch(digest));
+        }
+
+        tokio::fs::rename(&temp, path).await?;
+        Ok(Fetched::Verified(path.to_path_buf()))
+    }
+
+    /// Stream `url` into `dest`, returning the hex SHA-256 of the content
+    async fn download(&self, url: &str, dest: &Path) -> Result<String> {
+        let mut response = self.client.get(url).send().await?.error_for_status()?;
+        if response
+            .content_length()
+            .is_some_and(|length| length > self.max_size)
+        {
+            bail!("Artifact at {} exceeds the size limit", url);
+        }
+
+        let mut file = tokio::fs::File::create(dest).await?;
+        let mut hasher = Sha256::new();
+        let mut size = 0u64;
+
+        while let Some(chunk) = response.chunk().await? {
+            size += chunk.len() as u64;
+            if size > self.max_size {
+                bail!("Artifact at {} exceeds the size limit", url);
+            }
+            hasher.update(&chunk);
+            file.write_all(&chunk).await?;
+        }
+        file.flush().await?;
+
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_rejects_traversal() {
+        let cache = ArtifactCache::new("/var/cache/fossdb", reqwest::Client::new(), 1);
+
+        assert_eq!(
+            cache.path("crates.io", "serde", "1.0.0"),
+            Some(PathBuf::from("/var/cache/fossdb/crates.io/serde/1.0.0"))
+        );
+        assert!(cache.path("crates.io", "..", "1.0.0").is_none());
+        assert!(cache.path("crates.io", "serde", "../../etc").is_none());
+        assert!(cache.path("", "serde", "1.0.0").is_none());
+    }
+
+    #[test]
+    fn test_is_sha256() {
+        assert!(is_sha256(&"a".repeat(64)));
+        assert!(!is_sha256(&"a".repeat(40)));
+        assert!(!is_sha256(&"z".repeat(64)));
+    }
+}
@@ -4,11 +4,42 @@ use chrono::{Duration, Utc};
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
 use serde::{Deserialize, Serialize};
 
+use crate::Role;
+use crate::config::Config;
+use crate::storage::Storage;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
     pub username: String,
     pub exp: usize,
+    /// The user's stored role when the token was issued
+    #[serde(default)]
+    pub role: Role,
+}
+
+impl Claims {
+    pub fn is_admin(&self, config: &Config) -> bool {
+        self.role == Role::Admin || config.admin_usernames.contains(&self.username)
+    }
+
+    /// Whether the user may moderate, which admins always can
+    pub fn is_moderator(&self, config: &Config) -> bool {
+        self.role >= Role::Moderator || config.is_moderator(&self.username)
+    }
+
+    /// The claims with the user's stored name and role, so role changes apply
+    /// to tokens issued before them. None if the user no longer exists.
+    pub fn current(&self, db: &dyn Storage) -> Result<Option<Claims>> {
+        let Ok(user_id) = self.sub.parse::<u64>() else {
+            return Ok(None);
+        };
+        Ok(db.get_user(user_id)?.map(|user| Claims {
+            username: user.username,
+            role: user.role,
+            ..self.clone()
+        }))
+    }
 }
 
 pub fn hash_password(password: &str) -> Result<String> {
@@ -21,7 +52,7 @@ pub fn verify_password(password: &str, hash: &str) -> Result<bool> {
     Ok(valid)
 }
 
-pub fn create_jwt(user_id: &str, username: &str) -> Result<String> {
+pub fn create_jwt(user_id: &str, username: &str, role: Role) -> Result<String> {
     let expiration = Utc::now()
         .checked_add_signed(Duration::days(7))
         .expect("valid timestamp")
@@ -31,6 +62,7 @@ pub fn create_jwt(user_id: &str, username: &str) -> Result<String> {
         sub: user_id.to_owned(),
         username: username.to_owned(),
         exp: expiration,
+        role,
     };
 
     let config = crate::config::Config::from_env();
@@ -53,3 +85,30 @@ pub fn verify_jwt(token: &str) -> Result<Claims> {
     )?;
     Ok(token_data.claims)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::fixtures;
+
+    #[test]
+    fn test_current_claims() {
+        let db = Database::in_memory().unwrap();
+        let seeded = fixtures::seed(&db).unwrap();
+        let claims = Claims {
+            sub: seeded.alice.id.to_string(),
+            username: seeded.alice.username.clone(),
+            exp: usize::MAX,
+            role: Role::Moderator,
+        };
+
+        // Demoted after the token was issued
+        let current = claims.current(&db).unwrap().unwrap();
+        assert_eq!(current.role, Role::User);
+        assert_eq!(current.exp, usize::MAX);
+
+        db.delete_user(seeded.alice.id).unwrap();
+        assert!(claims.current(&db).unwrap().is_none());
+    }
+}
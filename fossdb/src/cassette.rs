@@ -0,0 +1,222 @@
+//! VCR-style recording of upstream HTTP responses, so collector tests can
+//! replay real API traffic without network access.
+//!
+//! A cassette is a JSON file of interactions. Tests open one with
+//! [`Cassette::load`], which replays it unless `FOSSDB_CASSETTE=record` is set,
+//! in which case requests go to the network and the file is rewritten.
+use anyhow::{Context, Result};
+use reqwest::{Response, Url};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Query parameters that carry credentials and are never written to disk
+const REDACTED_PARAMS: &[&str] = &["api_key", "access_token", "token", "key"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Record,
+    Replay,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    pub url: String,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    pub body: String,
+}
+
+pub struct Cassette {
+    path: PathBuf,
+    mode: Mode,
+    interactions: Mutex<Vec<Interaction>>,
+    /// Replay position, so repeated requests get successive responses
+    played: Mutex<Vec<bool>>,
+}
+
+impl Cassette {
+    /// Open `path` in the mode selected by `FOSSDB_CASSETTE`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        match std::env::var("FOSSDB_CASSETTE").as_deref() {
+            Ok("record") => Ok(Self::record(path)),
+            _ => Self::replay(path),
+        }
+    }
+
+    /// Start an empty cassette that is written to `path` as requests complete
+    pub fn record(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            mode: Mode::Record,
+            interactions: Mutex::new(Vec::new()),
+            played: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn replay(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let interactions: Vec<Interaction> = serde_json::from_slice(
+            &std::fs::read(path)
+                .with_context(|| format!("Failed to read cassette {}", path.display()))?,
+        )?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            mode: Mode::Replay,
+            played: Mutex::new(vec![false; interactions.len()]),
+            interactions: Mutex::new(interactions),
+        })
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// The recorded response to a request. Requests with no recording get a
+    /// 501 so the miss shows up in the test rather than on the network.
+    pub fn play(&self, method: &str, url: &str) -> Response {
+        let url = redact(url);
+        let interactions = self.interactions.lock().unwrap();
+        let mut played = self.played.lock().unwrap();
+
+        let found = interactions
+            .iter()
+            .enumerate()
+            .find(|(i, interaction)| {
+                !played[*i] && interaction.method == method && interaction.url == url
+            })
+            .map(|(i, _)| i);
+        let Some(index) = found else {
+            tracing::error!("No recorded response for {} {} in {}", method, url, self.path.display());
+            return http::Response::builder()
+                .status(501)
+                .body(format!("No recorded response for {} {}", method, url))
+                .unwrap()
+                .into();
+        };
+        played[index] = true;
+
+        let interaction = &interactions[index];
+        let mut response = http::Response::builder().status(interaction.status);
+        if let Some(content_type) = &interaction.content_type {
+            response = response.header(http::header::CONTENT_TYPE, content_type);
+        }
+        response.body(interaction.body.clone()).unwrap().into()
+    }
+
+    /// Store a live response and hand back an equivalent one to the caller
+    pub async fn capture(
+        &self,
+        method: &str,
+        url: &str,
+        response: Response,
+    ) -> Result<Response, reqwest::Error> {
+        let status = response.status().as_u16();
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let body = response.text().await?;
+
+        let interaction = Interaction {
+            method: method.to_string(),
+            url: redact(url),
+            status,
+            content_type: content_type.clone(),
+            body: body.clone(),
+        };
+        if let Err(e) = self.save(interaction) {
+            tracing::error!("Failed to write cassette {}: {}", self.path.display(), e);
+        }
+
+        let mut response = http::Response::builder().status(status);
+        if let Some(content_type) = content_type {
+            response = response.header(http::header::CONTENT_TYPE, content_type);
+        }
+        Ok(response.body(body).unwrap().into())
+    }
+
+    fn save(&self, interaction: Interaction) -> Result<()> {
+        let mut interactions = self.interactions.lock().unwrap();
+        interactions.push(interaction);
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_vec_pretty(&*interactions)?)?;
+        Ok(())
+    }
+}
+
+/// Replace the values of credential parameters in `url`
+fn redact(url: &str) -> String {
+    let Ok(mut parsed) = Url::parse(url) else {
+        return url.to_string();
+    };
+    if !parsed
+        .query_pairs()
+        .any(|(name, _)| REDACTED_PARAMS.contains(&name.as_ref()))
+    {
+        return url.to_string();
+    }
+
+    let pairs: Vec<(String, String)> = parsed
+        .query_pairs()
+        .map(|(name, value)| {
+            if REDACTED_PARAMS.contains(&name.as_ref()) {
+                (name.into_owned(), "REDACTED".to_string())
+            } else {
+                (name.into_owned(), value.into_owned())
+            }
+        })
+        .collect();
+    parsed.query_pairs_mut().clear().extend_pairs(pairs);
+    parsed.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact() {
+        assert_eq!(
+            redact("https://libraries.io/api/platforms?api_key=secret&page=2"),
+            "https://libraries.io/api/platforms?api_key=REDACTED&page=2"
+        );
+        assert_eq!(
+            redact("https://index.crates.io/se/rd/serde"),
+            "https://index.crates.io/se/rd/serde"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replay_in_order() {
+        let path = std::env::temp_dir().join(format!("fossdb-cassette-{}.json", std::process::id()));
+        let interaction = |body: &str| Interaction {
+            method: "GET".to_string(),
+            url: "https://example.com/?api_key=REDACTED".to_string(),
+            status: 200,
+            content_type: Some("application/json".to_string()),
+            body: body.to_string(),
+        };
+        std::fs::write(
+            &path,
+            serde_json::to_vec(&[interaction("1"), interaction("2")]).unwrap(),
+        )
+        .unwrap();
+
+        let cassette = Cassette::replay(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        for expected in ["1", "2"] {
+            let response = cassette.play("GET", "https://example.com/?api_key=other");
+            assert_eq!(response.text().await.unwrap(), expected);
+        }
+        assert_eq!(
+            cassette.play("GET", "https://example.com/").status().as_u16(),
+            501
+        );
+    }
+}
@@ -2,6 +2,9 @@ use governor::{Quota, RateLimiter};
 use reqwest::{Client, RequestBuilder, Response};
 use std::num::NonZeroU32;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::collector_status::CollectorStatusRegistry;
 
 /// A wrapper around reqwest::Client that applies rate limiting to all requests.
 #[derive(Clone)]
@@ -27,6 +30,7 @@ impl RateLimitedClient {
     ///
     /// # Examples
     /// ```
+    /// # use fossdb::client::RateLimitedClient;
     /// let client = reqwest::Client::new();
     /// let limited = RateLimitedClient::new(client, 10); // 10 req/s
     /// ```
@@ -140,6 +144,10 @@ pub struct AdaptiveRateLimitedClient {
     >,
     config: Arc<AdaptiveConfig>,
     current_rate: Arc<tokio::sync::RwLock<u32>>,
+    rate_limit_hits: Arc<AtomicU64>,
+    statuses: Option<Arc<CollectorStatusRegistry>>,
+    #[cfg(feature = "http-cassettes")]
+    cassette: Option<Arc<crate::cassette::Cassette>>,
 }
 
 impl AdaptiveRateLimitedClient {
@@ -153,9 +161,28 @@ impl AdaptiveRateLimitedClient {
             limiter,
             config: Arc::new(config),
             current_rate,
+            rate_limit_hits: Arc::new(AtomicU64::new(0)),
+            statuses: None,
+            #[cfg(feature = "http-cassettes")]
+            cassette: None,
         }
     }
 
+    /// Publish the current rate for each host requested to `statuses`
+    pub fn with_statuses(mut self, statuses: Arc<CollectorStatusRegistry>) -> Self {
+        self.statuses = Some(statuses);
+        self
+    }
+
+    /// Record responses to, or replay them from, `cassette`. Replayed requests
+    /// skip the rate limiter. Requests made through the `*_builder` methods are
+    /// not covered.
+    #[cfg(feature = "http-cassettes")]
+    pub fn with_cassette(mut self, cassette: Arc<crate::cassette::Cassette>) -> Self {
+        self.cassette = Some(cassette);
+        self
+    }
+
     /// Create an adaptive client with default configuration.
     ///
     /// Starts at 10 req/s and adjusts between 1-100 req/s.
@@ -172,24 +199,50 @@ impl AdaptiveRateLimitedClient {
 
     /// Execute a GET request with adaptive rate limiting.
     pub async fn get(&self, url: &str) -> Result<Response, reqwest::Error> {
+        #[cfg(feature = "http-cassettes")]
+        if let Some(cassette) = &self.cassette
+            && cassette.mode() == crate::cassette::Mode::Replay
+        {
+            let response = cassette.play("GET", url);
+            self.report_result(url, response.status().as_u16()).await;
+            return Ok(response);
+        }
+
         {
             let limiter = self.limiter.read().await;
             limiter.until_ready().await;
         }
         let response = self.client.get(url).send().await?;
-        self.report_result(response.status().as_u16()).await;
+        self.report_result(url, response.status().as_u16()).await;
+        #[cfg(feature = "http-cassettes")]
+        if let Some(cassette) = &self.cassette {
+            return cassette.capture("GET", url, response).await;
+        }
         Ok(response)
     }
 
     /// Execute a POST request with adaptive rate limiting.
     #[allow(dead_code)]
     pub async fn post(&self, url: &str) -> Result<Response, reqwest::Error> {
+        #[cfg(feature = "http-cassettes")]
+        if let Some(cassette) = &self.cassette
+            && cassette.mode() == crate::cassette::Mode::Replay
+        {
+            let response = cassette.play("POST", url);
+            self.report_result(url, response.status().as_u16()).await;
+            return Ok(response);
+        }
+
         {
             let limiter = self.limiter.read().await;
             limiter.until_ready().await;
         }
         let response = self.client.post(url).send().await?;
-        self.report_result(response.status().as_u16()).await;
+        self.report_result(url, response.status().as_u16()).await;
+        #[cfg(feature = "http-cassettes")]
+        if let Some(cassette) = &self.cassette {
+            return cassette.capture("POST", url, response).await;
+        }
         Ok(response)
     }
 
@@ -221,11 +274,22 @@ impl AdaptiveRateLimitedClient {
     /// Use this after manually sending a request created with `*_builder` methods.
     #[allow(dead_code)]
     pub async fn report_response(&self, response: &Response) {
-        self.report_result(response.status().as_u16()).await;
+        self.report_result(response.url().as_str(), response.status().as_u16())
+            .await;
+    }
+
+    /// Number of 429 responses since the last call
+    #[allow(dead_code)]
+    pub fn take_rate_limit_hits(&self) -> u64 {
+        self.rate_limit_hits.swap(0, Ordering::Relaxed)
     }
 
-    /// Report the result of an API request to adjust the rate limit.
-    async fn report_result(&self, status_code: u16) {
+    /// Report the result of an API request to `url` to adjust the rate limit.
+    async fn report_result(&self, url: &str, status_code: u16) {
+        if status_code == 429 {
+            self.rate_limit_hits.fetch_add(1, Ordering::Relaxed);
+        }
+
         let mut current_rate = self.current_rate.write().await;
         let old_rate = *current_rate;
 
@@ -263,6 +327,13 @@ impl AdaptiveRateLimitedClient {
                 _ => {}
             }
         }
+
+        if let Some(statuses) = &self.statuses
+            && let Ok(url) = reqwest::Url::parse(url)
+            && let Some(host) = url.host_str()
+        {
+            statuses.set_upstream_rate(host, new_rate);
+        }
     }
 }
 
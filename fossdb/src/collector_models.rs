@@ -1,9 +1,20 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::license_policy::LicensePolicy;
 
 // Re-export types for consistency
 pub use crate::Dependency;
 
+/// Rules for which collected packages enter the catalog
+#[derive(Debug, Clone, Default)]
+pub struct Admission {
+    pub licenses: LicensePolicy,
+    /// Hold new packages for a moderator to approve before they're public
+    pub review_new_packages: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CollectedPackage {
     pub name: String,
@@ -18,6 +29,9 @@ pub struct CollectedPackage {
     pub status: Option<String>,
     pub dependents_count: Option<u32>,
     pub rank: Option<u32>,
+    /// Stars of the source repository
+    #[serde(default)]
+    pub stars: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,8 +44,68 @@ pub struct CollectedVersion {
     pub changelog: Option<String>,
 }
 
+/// Returned by collectors that can't run in this environment, e.g. when a
+/// required binary is not installed
+#[derive(Debug)]
+pub struct CollectorUnavailable(pub String);
+
+impl std::fmt::Display for CollectorUnavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "collector unavailable: {}", self.0)
+    }
+}
+
+impl std::error::Error for CollectorUnavailable {}
+
+/// Counters a collector bumps during a run. They're kept even if the run
+/// fails partway through.
+#[derive(Debug, Default)]
+pub struct RunReport {
+    pub packages_found: AtomicU64,
+    pub packages_added: AtomicU64,
+    pub packages_updated: AtomicU64,
+    pub versions_added: AtomicU64,
+    pub errors: AtomicU64,
+    pub rate_limit_hits: AtomicU64,
+}
+
+impl RunReport {
+    fn add(counter: &AtomicU64, n: usize) {
+        counter.fetch_add(n as u64, Ordering::Relaxed);
+    }
+
+    pub fn found(&self, n: usize) {
+        Self::add(&self.packages_found, n);
+    }
+
+    pub fn added(&self, n: usize) {
+        Self::add(&self.packages_added, n);
+    }
+
+    pub fn updated(&self, n: usize) {
+        Self::add(&self.packages_updated, n);
+    }
+
+    pub fn versions(&self, n: usize) {
+        Self::add(&self.versions_added, n);
+    }
+
+    pub fn error(&self) {
+        Self::add(&self.errors, 1);
+    }
+
+    pub fn rate_limited(&self, n: u64) {
+        self.rate_limit_hits.fetch_add(n, Ordering::Relaxed);
+    }
+}
+
 #[async_trait::async_trait]
 pub trait Collector: Send + Sync {
     fn name(&self) -> &str;
-    async fn collect(&self, db: std::sync::Arc<crate::db::Database>) -> anyhow::Result<()>;
+    async fn collect(
+        &self,
+        db: std::sync::Arc<dyn crate::storage::Storage>,
+        broadcaster: &dyn crate::timeline::Broadcast,
+        report: &RunReport,
+    ) -> anyhow::Result<()>;
 }
@@ -0,0 +1,179 @@
+// Persisted collector runs, checked against earlier ones so a collector that
+// quietly stops finding packages gets noticed
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::collector_models::{CollectorUnavailable, RunReport};
+use crate::storage::Storage;
+use crate::{CollectorRun, RunOutcome};
+
+/// Earlier runs a new run is compared against
+const HISTORY: usize = 10;
+/// Error rates below this are never flagged
+const MIN_ERROR_RATE: f64 = 0.1;
+/// Fewer rate limit hits than this are never flagged
+const MIN_RATE_LIMIT_HITS: u64 = 10;
+/// Consecutive failures before a collector is flagged
+const FAILURE_STREAK: usize = 3;
+
+/// Save a finished run along with anything unusual about it
+pub fn record(
+    db: &dyn Storage,
+    collector: &str,
+    started_at: DateTime<Utc>,
+    report: &RunReport,
+    result: &anyhow::Result<()>,
+) -> Result<CollectorRun> {
+    let finished_at = Utc::now();
+    let (outcome, error) = match result {
+        Ok(()) => (RunOutcome::Succeeded, None),
+        Err(e) if e.is::<CollectorUnavailable>() => (RunOutcome::Unavailable, Some(format!("{:#}", e))),
+        Err(e) => (RunOutcome::Failed, Some(format!("{:#}", e))),
+    };
+    let count = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+
+    let mut run = CollectorRun {
+        id: 0,
+        collector: collector.to_string(),
+        started_at,
+        finished_at,
+        duration_ms: (finished_at - started_at).num_milliseconds().max(0) as u64,
+        outcome,
+        error,
+        packages_found: count(&report.packages_found),
+        packages_added: count(&report.packages_added),
+        packages_updated: count(&report.packages_updated),
+        versions_added: count(&report.versions_added),
+        errors: count(&report.errors),
+        rate_limit_hits: count(&report.rate_limit_hits),
+        anomalies: Vec::new(),
+    };
+
+    let history = db.get_collector_runs(collector)?;
+    run.anomalies = detect_anomalies(&run, &history[history.len().saturating_sub(HISTORY)..]);
+    for anomaly in &run.anomalies {
+        tracing::warn!("Collector {} anomaly: {}", collector, anomaly);
+    }
+
+    db.insert_collector_run(run)
+}
+
+fn error_rate(run: &CollectorRun) -> f64 {
+    run.errors as f64 / run.packages_found.max(run.errors).max(1) as f64
+}
+
+fn median(mut values: Vec<u64>) -> Option<u64> {
+    values.sort_unstable();
+    values.get(values.len() / 2).copied()
+}
+
+/// What looks wrong about `run` given the runs before it, oldest first
+pub fn detect_anomalies(run: &CollectorRun, previous: &[CollectorRun]) -> Vec<String> {
+    let mut anomalies = Vec::new();
+
+    if run.outcome == RunOutcome::Succeeded {
+        let usual = median(
+            previous
+                .iter()
+                .filter(|r| r.outcome == RunOutcome::Succeeded)
+                .map(|r| r.packages_found)
+                .collect(),
+        );
+        match usual {
+            _ if run.packages_found == 0 => anomalies.push("no packages found".to_string()),
+            Some(usual) if run.packages_found * 2 < usual => anomalies.push(format!(
+                "found {} packages, usually {}",
+                run.packages_found, usual
+            )),
+            _ => {}
+        }
+    }
+
+    let rate = error_rate(run);
+    let usual_rate = previous.iter().map(error_rate).sum::<f64>() / previous.len().max(1) as f64;
+    if rate >= MIN_ERROR_RATE && rate > usual_rate * 2.0 {
+        anomalies.push(format!(
+            "error rate {:.0}%, usually {:.0}%",
+            rate * 100.0,
+            usual_rate * 100.0
+        ));
+    }
+
+    let usual_hits = median(previous.iter().map(|r| r.rate_limit_hits).collect()).unwrap_or(0);
+    if run.rate_limit_hits >= MIN_RATE_LIMIT_HITS && run.rate_limit_hits > usual_hits * 2 {
+        anomalies.push(format!(
+            "rate limited {} times, usually {}",
+            run.rate_limit_hits, usual_hits
+        ));
+    }
+
+    if run.outcome == RunOutcome::Failed {
+        let streak = 1 + previous
+            .iter()
+            .rev()
+            .take_while(|r| r.outcome == RunOutcome::Failed)
+            .count();
+        if streak >= FAILURE_STREAK {
+            anomalies.push(format!("failed {} times in a row", streak));
+        }
+    }
+
+    anomalies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(outcome: RunOutcome, found: u64, errors: u64) -> CollectorRun {
+        let now = Utc::now();
+        CollectorRun {
+            id: 0,
+            collector: "nixpkgs".to_string(),
+            started_at: now,
+            finished_at: now,
+            duration_ms: 0,
+            outcome,
+            error: None,
+            packages_found: found,
+            packages_added: 0,
+            packages_updated: 0,
+            versions_added: 0,
+            errors,
+            rate_limit_hits: 0,
+            anomalies: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_detect_anomalies() {
+        let history = vec![
+            run(RunOutcome::Succeeded, 100, 1),
+            run(RunOutcome::Succeeded, 120, 0),
+            run(RunOutcome::Succeeded, 110, 2),
+        ];
+        assert!(detect_anomalies(&run(RunOutcome::Succeeded, 105, 1), &history).is_empty());
+        assert_eq!(
+            detect_anomalies(&run(RunOutcome::Succeeded, 0, 0), &history),
+            vec!["no packages found"]
+        );
+        assert_eq!(
+            detect_anomalies(&run(RunOutcome::Succeeded, 40, 20), &history),
+            vec!["found 40 packages, usually 110", "error rate 50%, usually 1%"]
+        );
+
+        // A first run has nothing to compare against but can still be empty
+        assert_eq!(
+            detect_anomalies(&run(RunOutcome::Succeeded, 0, 0), &[]),
+            vec!["no packages found"]
+        );
+
+        let failures = vec![run(RunOutcome::Failed, 0, 0), run(RunOutcome::Failed, 0, 0)];
+        assert_eq!(
+            detect_anomalies(&run(RunOutcome::Failed, 0, 0), &failures),
+            vec!["failed 3 times in a row"]
+        );
+        assert!(detect_anomalies(&run(RunOutcome::Unavailable, 0, 0), &failures).is_empty());
+    }
+}
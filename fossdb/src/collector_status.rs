@@ -0,0 +1,230 @@
+// Run state of background collectors, reported through the admin API
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CollectorState {
+    Idle,
+    Running,
+    /// Waiting for its next scheduled run
+    Sleeping,
+    Failed,
+    /// A required external tool or credential is missing
+    Unavailable,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CollectorStatus {
+    pub name: String,
+    pub state: CollectorState,
+    pub last_started_at: Option<DateTime<Utc>>,
+    pub last_finished_at: Option<DateTime<Utc>>,
+    pub last_success_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub consecutive_failures: u32,
+    /// Anomalies flagged in the last recorded run
+    pub anomalies: Vec<String>,
+    #[serde(default)]
+    pub next_run_at: Option<DateTime<Utc>>,
+}
+
+/// The part of a collector's status shown to everyone
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CollectorSummary {
+    pub name: String,
+    pub state: CollectorState,
+    pub last_success_at: Option<DateTime<Utc>>,
+    pub next_run_at: Option<DateTime<Utc>>,
+}
+
+impl CollectorStatus {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            state: CollectorState::Idle,
+            last_started_at: None,
+            last_finished_at: None,
+            last_success_at: None,
+            last_error: None,
+            consecutive_failures: 0,
+            anomalies: Vec::new(),
+            next_run_at: None,
+        }
+    }
+}
+
+/// Shared record of every collector's most recent run
+#[derive(Default)]
+pub struct CollectorStatusRegistry {
+    statuses: RwLock<BTreeMap<String, CollectorStatus>>,
+    /// Current requests per second allowed to each upstream host
+    upstream_rates: RwLock<BTreeMap<String, u32>>,
+}
+
+impl CollectorStatusRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn update(&self, name: &str, f: impl FnOnce(&mut CollectorStatus)) {
+        let mut statuses = self.statuses.write().unwrap();
+        f(statuses
+            .entry(name.to_string())
+            .or_insert_with(|| CollectorStatus::new(name)));
+    }
+
+    pub fn register(&self, name: &str) {
+        self.update(name, |_| {});
+    }
+
+    pub fn started(&self, name: &str) {
+        self.update(name, |status| {
+            status.state = CollectorState::Running;
+            status.last_started_at = Some(Utc::now());
+            status.next_run_at = None;
+        });
+    }
+
+    pub fn succeeded(&self, name: &str) {
+        self.update(name, |status| {
+            let now = Utc::now();
+            status.state = CollectorState::Idle;
+            status.last_finished_at = Some(now);
+            status.last_success_at = Some(now);
+            status.last_error = None;
+            status.consecutive_failures = 0;
+        });
+    }
+
+    /// The run was left to another process
+    pub fn skipped(&self, name: &str) {
+        self.update(name, |status| status.state = CollectorState::Idle);
+    }
+
+    pub fn failed(&self, name: &str, error: String, unavailable: bool) {
+        self.update(name, |status| {
+            status.state = if unavailable {
+                CollectorState::Unavailable
+            } else {
+                CollectorState::Failed
+            };
+            status.last_finished_at = Some(Utc::now());
+            status.last_error = Some(error);
+            status.consecutive_failures += 1;
+        });
+    }
+
+    pub fn flagged(&self, name: &str, anomalies: Vec<String>) {
+        self.update(name, |status| status.anomalies = anomalies);
+    }
+
+    /// The collector won't run again until `at`. A failed run stays visible
+    /// as such while it waits.
+    pub fn scheduled(&self, name: &str, at: DateTime<Utc>) {
+        self.update(name, |status| {
+            if status.state == CollectorState::Idle {
+                status.state = CollectorState::Sleeping;
+            }
+            status.next_run_at = Some(at);
+        });
+    }
+
+    /// Statuses of all registered collectors, sorted by name
+    pub fn snapshot(&self) -> Vec<CollectorStatus> {
+        self.statuses.read().unwrap().values().cloned().collect()
+    }
+
+    /// Public summaries of all registered collectors, sorted by name
+    pub fn summaries(&self) -> Vec<CollectorSummary> {
+        self.statuses
+            .read()
+            .unwrap()
+            .values()
+            .map(|status| CollectorSummary {
+                name: status.name.clone(),
+                state: status.state,
+                last_success_at: status.last_success_at,
+                next_run_at: status.next_run_at,
+            })
+            .collect()
+    }
+
+    /// Names of the collectors currently running
+    pub fn running(&self) -> Vec<String> {
+        self.statuses
+            .read()
+            .unwrap()
+            .values()
+            .filter(|status| status.state == CollectorState::Running)
+            .map(|status| status.name.clone())
+            .collect()
+    }
+
+    /// Record the rate an adaptive client settled on for `host`
+    pub fn set_upstream_rate(&self, host: &str, requests_per_second: u32) {
+        let mut rates = self.upstream_rates.write().unwrap();
+        if rates.get(host) != Some(&requests_per_second) {
+            rates.insert(host.to_string(), requests_per_second);
+        }
+    }
+
+    /// Current rate per upstream host, sorted by host
+    pub fn upstream_rates(&self) -> Vec<(String, u32)> {
+        self.upstream_rates
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(host, rate)| (host.clone(), *rate))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_tracks_runs() {
+        let registry = CollectorStatusRegistry::new();
+        registry.register("nixpkgs");
+        registry.started("crates.io");
+
+        registry.failed("nixpkgs", "nix-env not found".to_string(), true);
+        registry.failed("nixpkgs", "nix-env not found".to_string(), true);
+
+        let statuses = registry.snapshot();
+        assert_eq!(statuses[0].name, "crates.io");
+        assert_eq!(statuses[0].state, CollectorState::Running);
+        assert_eq!(statuses[1].state, CollectorState::Unavailable);
+        assert_eq!(statuses[1].consecutive_failures, 2);
+
+        registry.succeeded("nixpkgs");
+        let nixpkgs = &registry.snapshot()[1];
+        assert_eq!(nixpkgs.state, CollectorState::Idle);
+        assert_eq!(nixpkgs.consecutive_failures, 0);
+        assert!(nixpkgs.last_error.is_none());
+        assert_eq!(registry.running(), vec!["crates.io".to_string()]);
+
+        let next_run = Utc::now() + chrono::Duration::hours(6);
+        registry.scheduled("nixpkgs", next_run);
+        registry.failed("crates.io", "timed out".to_string(), false);
+        registry.scheduled("crates.io", next_run);
+        let summaries = registry.summaries();
+        assert_eq!(summaries[0].state, CollectorState::Failed);
+        assert_eq!(summaries[1].state, CollectorState::Sleeping);
+        assert_eq!(summaries[1].next_run_at, Some(next_run));
+        assert!(summaries[1].last_success_at.is_some());
+        registry.started("nixpkgs");
+        assert_eq!(registry.summaries()[1].next_run_at, None);
+
+        registry.set_upstream_rate("libraries.io", 30);
+        registry.set_upstream_rate("libraries.io", 15);
+        assert_eq!(
+            registry.upstream_rates(),
+            vec![("libraries.io".to_string(), 15)]
+        );
+    }
+}
@@ -1,20 +1,56 @@
 use anyhow::Result;
 use async_trait::async_trait;
 use crates_io_api::{AsyncClient, Sort};
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use crate::collector_models::Collector;
+use crate::collector_models::{Admission, Collector, RunReport};
 use crate::collectors::helpers;
+use crate::timeline::Broadcast;
+
+/// Convert crates.io dependency records, which name the depended-on crate in `crate_id`
+fn convert_dependencies(dependencies: &[crates_io_api::Dependency]) -> Vec<crate::Dependency> {
+    dependencies
+        .iter()
+        .map(|d| crate::Dependency {
+            name: d.crate_id.clone(),
+            version_requirement: d.req.clone(),
+            dependency_type: d.kind.clone(),
+            optional: d.optional,
+        })
+        .collect()
+}
+
+/// Path of a crate's file in the sparse registry index
+fn sparse_index_path(name: &str) -> String {
+    let name = name.to_lowercase();
+    match name.len() {
+        1 => format!("1/{}", name),
+        2 => format!("2/{}", name),
+        3 => format!("3/{}/{}", &name[..1], name),
+        _ => format!("{}/{}/{}", &name[..2], &name[2..4], name),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct IndexEntry {
+    vers: String,
+    cksum: String,
+}
 
 pub struct CratesIoCollector {
     client: Arc<AsyncClient>,
+    index_client: reqwest::Client,
+    admission: Admission,
 }
 
 impl CratesIoCollector {
-    pub fn new(_client: reqwest::Client) -> Self {
+    pub fn new(index_client: reqwest::Client, admission: Admission) -> Self {
         // crates_io_api handles rate limiting internally (1 req/s)
         // We don't need our custom rate limiting for this collector
         Self {
+            index_client,
+            admission,
             client: Arc::new(
                 AsyncClient::new(
                     "fossdb (https://github.com/fossable/fossdb)",
@@ -24,6 +60,35 @@ impl CratesIoCollector {
             ),
         }
     }
+
+    /// SHA-256 checksums of every published version, from the sparse index.
+    ///
+    /// The API doesn't expose checksums, and the index is served from a CDN
+    /// so it doesn't count against the API rate limit.
+    async fn fetch_checksums(&self, name: &str) -> HashMap<String, String> {
+        let url = format!("https://index.crates.io/{}", sparse_index_path(name));
+        let body = match self.index_client.get(&url).send().await {
+            Ok(response) if response.status().is_success() => response.text().await,
+            Ok(response) => {
+                tracing::warn!("Index lookup for {} returned {}", name, response.status());
+                return HashMap::new();
+            }
+            Err(e) => Err(e),
+        };
+
+        match body {
+            // One JSON object per published version
+            Ok(body) => body
+                .lines()
+                .filter_map(|line| serde_json::from_str::<IndexEntry>(line).ok())
+                .map(|entry| (entry.vers, entry.cksum))
+                .collect(),
+            Err(e) => {
+                tracing::warn!("Failed to fetch index entry for {}: {}", name, e);
+                HashMap::new()
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -32,11 +97,19 @@ impl Collector for CratesIoCollector {
         "crates.io"
     }
 
-    async fn collect(&self, db: Arc<crate::db::Database>) -> Result<()> {
+    async fn collect(
+        &self,
+        db: Arc<dyn crate::storage::Storage>,
+        broadcaster: &dyn Broadcast,
+        report: &RunReport,
+    ) -> Result<()> {
         use crate::{Package, PackageVersion};
         use chrono::Utc;
         use std::collections::HashSet;
 
+        let mut typosquats = helpers::TyposquatGuard::load(&*db)?;
+        let mut admission = helpers::AdmissionGate::load(&*db, self.admission.clone())?;
+
         // In debug mode, limit to 5 packages total
         let mut packages_processed = 0;
         let max_packages = if cfg!(debug_assertions) { 5 } else { usize::MAX };
@@ -57,13 +130,19 @@ impl Collector for CratesIoCollector {
                 crates_page.crates.len(),
                 page
             );
+            report.found(crates_page.crates.len());
 
             // For each crate, check if we need to update it
             for krate in &crates_page.crates {
                 let crate_name = krate.name.clone();
 
                 // Check if package already exists
-                match db.get_package_by_name(&crate_name) {
+                let existing = if db.may_contain_package(&crate_name) {
+                    db.get_package_by_name(&crate_name)
+                } else {
+                    Ok(None)
+                };
+                match existing {
                     Ok(Some(existing_package)) => {
                         // Package exists - check if it has been updated since we last scraped
                         // Use the updated_at field from the search result to avoid unnecessary API calls
@@ -88,12 +167,30 @@ impl Collector for CratesIoCollector {
 
                         match self.client.full_crate(&crate_name, false).await {
                             Ok(full_crate) => {
+                                if let (Some(old), Some(new)) =
+                                    (&existing_package.repository, &full_crate.repository)
+                                    && helpers::normalize_url(old) != helpers::normalize_url(new)
+                                {
+                                    helpers::record_repository_move(
+                                        &*db,
+                                        broadcaster,
+                                        &existing_package,
+                                        old,
+                                        new,
+                                    )?;
+                                    let repository = full_crate.repository.clone();
+                                    db.modify_package(existing_package.id, &mut |p| {
+                                        p.repository = repository.clone()
+                                    })?;
+                                }
+
                                 let existing_versions =
                                     db.get_versions_by_package(existing_package.id)?;
                                 let existing_version_nums: HashSet<String> = existing_versions
                                     .iter()
                                     .map(|v| v.version.clone())
                                     .collect();
+                                let checksums = self.fetch_checksums(&crate_name).await;
 
                                 let now = Utc::now();
 
@@ -116,11 +213,13 @@ impl Collector for CratesIoCollector {
                                                 "https://crates.io{}",
                                                 v.dl_path
                                             )),
-                                            checksum: None,
-                                            dependencies: Vec::new(),
+                                            checksum: checksums.get(&v.num).cloned(),
+                                            dependencies: convert_dependencies(&v.dependencies),
                                             vulnerabilities: Vec::new(),
                                             changelog: None,
                                             created_at: now,
+                                            license: v.license.clone(),
+                                            size: v.crate_size,
                                         };
 
                                         // Save version - timeline events will be created automatically by the database listener
@@ -130,14 +229,19 @@ impl Collector for CratesIoCollector {
                                                 v.num,
                                                 crate_name
                                             );
+                                            report.versions(1);
+                                        } else {
+                                            report.error();
                                         }
                                     }
                                 }
 
                                 // Update the package's updated_at timestamp
-                                let mut updated_package = existing_package.clone();
-                                updated_package.updated_at = krate.updated_at;
-                                if let Err(e) = db.update_package(updated_package) {
+                                report.updated(1);
+                                if let Err(e) = db.modify_package(existing_package.id, &mut |p: &mut Package| {
+                                    p.updated_at = krate.updated_at;
+                                }) {
+                                    report.error();
                                     tracing::error!(
                                         "Failed to update package {} timestamp: {}",
                                         crate_name,
@@ -151,6 +255,7 @@ impl Collector for CratesIoCollector {
                                     crate_name,
                                     e
                                 );
+                                report.error();
                             }
                         }
                         continue;
@@ -169,21 +274,7 @@ impl Collector for CratesIoCollector {
                                     .first()
                                     .and_then(|v| v.license.clone());
 
-                                // Skip packages with non-free licenses
-                                if let Some(ref lic) = license {
-                                    if !helpers::is_free_license(lic) {
-                                        tracing::info!(
-                                            "Skipping package {} with non-free license: {}",
-                                            crate_name_for_log,
-                                            lic
-                                        );
-                                        continue;
-                                    }
-                                } else {
-                                    tracing::info!(
-                                        "Skipping package {} with no license information",
-                                        crate_name_for_log
-                                    );
+                                if !admission.admit(&crate_name_for_log, "crates.io", license.as_deref()) {
                                     continue;
                                 }
 
@@ -208,6 +299,11 @@ impl Collector for CratesIoCollector {
                                 match db.insert_package(package) {
                                     Ok(saved_package) => {
                                         tracing::info!("Saved package: {}", saved_package.name);
+                                        report.added(1);
+                                        admission.hold_new(&*db, std::slice::from_ref(&saved_package))?;
+                                        typosquats.check(&*db, broadcaster, &saved_package);
+                                        let checksums =
+                                            self.fetch_checksums(&saved_package.name).await;
 
                                         // Save versions (up to 10 non-yanked versions)
                                         for v in full_crate
@@ -225,11 +321,15 @@ impl Collector for CratesIoCollector {
                                                     "https://crates.io{}",
                                                     v.dl_path
                                                 )),
-                                                checksum: None,
-                                                dependencies: Vec::new(), // Could fetch dependencies if needed
+                                                checksum: checksums.get(&v.num).cloned(),
+                                                dependencies: convert_dependencies(
+                                                    &v.dependencies,
+                                                ),
                                                 vulnerabilities: Vec::new(),
                                                 changelog: None,
                                                 created_at: now,
+                                                license: v.license.clone(),
+                                                size: v.crate_size,
                                             };
 
                                             if let Err(e) = db.insert_version(version) {
@@ -239,12 +339,14 @@ impl Collector for CratesIoCollector {
                                                     saved_package.name,
                                                     e
                                                 );
+                                                report.error();
                                             } else {
                                                 tracing::debug!(
                                                     "Saved version {} for package {}",
                                                     v.num,
                                                     saved_package.name
                                                 );
+                                                report.versions(1);
                                             }
                                         }
                                     }
@@ -254,6 +356,7 @@ impl Collector for CratesIoCollector {
                                             full_crate.name,
                                             e
                                         );
+                                        report.error();
                                     }
                                 }
                             }
@@ -263,11 +366,13 @@ impl Collector for CratesIoCollector {
                                     crate_name_for_log,
                                     e
                                 );
+                                report.error();
                             }
                         }
                     }
                     Err(e) => {
                         tracing::error!("Failed to check if package {} exists: {}", crate_name, e);
+                        report.error();
                     }
                 }
 
@@ -277,10 +382,12 @@ impl Collector for CratesIoCollector {
                     if cfg!(debug_assertions) {
                         tracing::info!("Debug mode: Reached limit of {} packages, stopping collection", max_packages);
                     }
+                    admission.flush(&*db)?;
                     return Ok(());
                 }
             }
 
+            admission.flush(&*db)?;
             if crates_page.crates.len() < 100 {
                 break;
             }
@@ -289,3 +396,16 @@ impl Collector for CratesIoCollector {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparse_index_path() {
+        assert_eq!(sparse_index_path("a"), "1/a");
+        assert_eq!(sparse_index_path("cc"), "2/cc");
+        assert_eq!(sparse_index_path("syn"), "3/s/syn");
+        assert_eq!(sparse_index_path("Serde_JSON"), "se/rd/serde_json");
+    }
+}
@@ -0,0 +1,466 @@
+// Backfill from the crates.io database dump (https://crates.io/data-access)
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Deserializer, de::DeserializeOwned};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufReader, Write};
+use std::path::Path;
+
+use crate::collector_models::Admission;
+use crate::collectors::helpers;
+use crate::storage::Storage;
+use crate::{Dependency, Maintainer, Package, PackageVersion, ranking};
+
+pub const DUMP_URL: &str = "https://static.crates.io/db-dump.tar.gz";
+
+const PLATFORM: &str = "crates.io";
+
+/// Number of rows written per database transaction
+const BATCH_SIZE: usize = 1000;
+
+#[derive(Debug, Default)]
+pub struct BackfillStats {
+    pub packages_created: usize,
+    pub packages_updated: usize,
+    pub versions_created: usize,
+    pub dependencies: usize,
+    pub maintainers: usize,
+    pub skipped_license: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrateRow {
+    id: u64,
+    name: String,
+    description: Option<String>,
+    homepage: Option<String>,
+    repository: Option<String>,
+    #[serde(deserialize_with = "timestamp")]
+    created_at: DateTime<Utc>,
+    #[serde(deserialize_with = "timestamp")]
+    updated_at: DateTime<Utc>,
+    /// Only in dumps from before downloads moved to their own table
+    #[serde(default)]
+    downloads: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VersionRow {
+    id: u64,
+    crate_id: u64,
+    num: String,
+    license: Option<String>,
+    checksum: Option<String>,
+    #[serde(default)]
+    crate_size: Option<u64>,
+    #[serde(deserialize_with = "timestamp")]
+    created_at: DateTime<Utc>,
+    #[serde(deserialize_with = "flag")]
+    yanked: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct DependencyRow {
+    version_id: u64,
+    /// The crate being depended on
+    crate_id: u64,
+    req: String,
+    #[serde(deserialize_with = "flag")]
+    optional: bool,
+    kind: u8,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrateOwnerRow {
+    crate_id: u64,
+    owner_id: u64,
+    /// 0 for users, 1 for GitHub teams
+    owner_kind: u8,
+}
+
+#[derive(Debug, Deserialize)]
+struct UserRow {
+    id: u64,
+    gh_login: String,
+    name: Option<String>,
+}
+
+/// Postgres text timestamps, with or without an offset
+fn timestamp<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    DateTime::parse_from_str(&value, "%Y-%m-%d %H:%M:%S%.f%#z")
+        .map(|t| t.with_timezone(&Utc))
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(&value, "%Y-%m-%d %H:%M:%S%.f").map(|t| t.and_utc())
+        })
+        .map_err(serde::de::Error::custom)
+}
+
+/// Postgres text booleans (`t`/`f`)
+fn flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    Ok(matches!(String::deserialize(deserializer)?.as_str(), "t" | "true"))
+}
+
+fn dependency_kind(kind: u8) -> &'static str {
+    match kind {
+        1 => "build",
+        2 => "dev",
+        _ => "normal",
+    }
+}
+
+/// Stream the crates.io dump to `dest`
+pub async fn download_dump(client: &reqwest::Client, dest: &Path) -> Result<()> {
+    tracing::info!("Downloading {} to {}", DUMP_URL, dest.display());
+
+    let mut response = client.get(DUMP_URL).send().await?.error_for_status()?;
+    let mut file = File::create(dest)
+        .with_context(|| format!("Failed to create {}", dest.display()))?;
+
+    let mut written = 0u64;
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk)?;
+        written += chunk.len() as u64;
+    }
+    file.flush()?;
+
+    tracing::info!("Downloaded {} MiB", written / (1024 * 1024));
+    Ok(())
+}
+
+/// Stream every row of `data/<table>.csv` from the dump tarball.
+///
+/// The tarball is read sequentially, so each table costs one pass over it.
+fn for_each_row<T, F>(dump: &Path, table: &str, mut f: F) -> Result<()>
+where
+    T: DeserializeOwned,
+    F: FnMut(T) -> Result<()>,
+{
+    let file = File::open(dump).with_context(|| format!("Failed to open {}", dump.display()))?;
+    let mut archive = tar::Archive::new(GzDecoder::new(BufReader::new(file)));
+    let suffix = format!("data/{}.csv", table);
+
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if !entry.path()?.to_string_lossy().ends_with(&suffix) {
+            continue;
+        }
+
+        let mut reader = csv::Reader::from_reader(entry);
+        for row in reader.deserialize() {
+            f(row.with_context(|| format!("Invalid row in {}", suffix))?)?;
+        }
+        return Ok(());
+    }
+
+    anyhow::bail!("{} not found in crates.io dump", suffix)
+}
+
+/// Import every crate, version and dependency from a downloaded dump.
+///
+/// Existing crates.io packages are updated in place and only unknown
+/// versions are inserted, so the backfill can be re-run safely.
+pub fn backfill(db: &dyn Storage, dump: &Path, admission: &Admission) -> Result<BackfillStats> {
+    let mut stats = BackfillStats::default();
+
+    tracing::info!("Reading crates...");
+    let mut crates: HashMap<u64, CrateRow> = HashMap::new();
+    for_each_row(dump, "crates", |row: CrateRow| {
+        crates.insert(row.id, row);
+        Ok(())
+    })?;
+
+    // The package license is taken from the newest non-yanked version
+    tracing::info!("Reading licenses for {} crates...", crates.len());
+    let mut licenses: HashMap<u64, (DateTime<Utc>, Option<String>)> = HashMap::new();
+    for_each_row(dump, "versions", |row: VersionRow| {
+        if !row.yanked {
+            let newest = licenses.entry(row.crate_id).or_insert((row.created_at, None));
+            if row.created_at >= newest.0 {
+                *newest = (row.created_at, row.license);
+            }
+        }
+        Ok(())
+    })?;
+
+    tracing::info!("Upserting packages...");
+    let mut existing: HashMap<String, Package> = db
+        .get_all_packages()?
+        .into_iter()
+        .map(|p| (p.name.clone(), p))
+        .collect();
+    let mut typosquats = helpers::TyposquatGuard::load(db)?;
+    let mut gate = helpers::AdmissionGate::load(db, admission.clone())?;
+
+    // crates.io crate ID -> package ID
+    let mut package_ids: HashMap<u64, u64> = HashMap::new();
+    let mut crate_ids: Vec<&u64> = crates.keys().collect();
+    crate_ids.sort();
+
+    for batch in crate_ids.chunks(BATCH_SIZE) {
+        let mut new_packages = Vec::new();
+        let mut new_crate_ids = Vec::new();
+        let mut changed_packages = Vec::new();
+
+        for &&crate_id in batch {
+            let row = &crates[&crate_id];
+            let license = licenses.get(&crate_id).and_then(|(_, l)| l.clone());
+            if !gate.admit(&row.name, PLATFORM, license.as_deref()) {
+                stats.skipped_license += 1;
+                continue;
+            }
+
+            match existing.remove(&row.name) {
+                // Names are unique across platforms, so leave other sources alone
+                Some(package) if package.platform.as_deref() != Some(PLATFORM) => {}
+                Some(mut package) => {
+                    package_ids.insert(crate_id, package.id);
+                    if row.updated_at > package.updated_at {
+                        if let (Some(old), Some(new)) = (&package.repository, &row.repository) {
+                            helpers::record_repository_move(db, &(), &package, old, new)?;
+                        }
+                        package.description = row.description.clone();
+                        package.homepage = row.homepage.clone();
+                        package.repository = row.repository.clone();
+                        package.license = license;
+                        package.updated_at = row.updated_at;
+                        changed_packages.push(package);
+                    }
+                }
+                None => {
+                    new_packages.push(Package {
+                        id: 0,
+                        name: row.name.clone(),
+                        description: row.description.clone(),
+                        homepage: row.homepage.clone(),
+                        repository: row.repository.clone(),
+                        license,
+                        tags: vec!["rust".to_string(), "crate".to_string()],
+                        created_at: row.created_at,
+                        updated_at: row.updated_at,
+                        platform: Some(PLATFORM.to_string()),
+                        language: Some("rust".to_string()),
+                        status: None,
+                        dependents_count: None,
+                        rank: None,
+                    });
+                    new_crate_ids.push(crate_id);
+                }
+            }
+        }
+
+        stats.packages_updated += changed_packages.len();
+        db.update_packages(changed_packages)?;
+
+        let saved_packages = db.insert_packages(new_packages)?;
+        gate.hold_new(db, &saved_packages)?;
+        for (package, crate_id) in saved_packages.iter().zip(new_crate_ids) {
+            typosquats.check(db, &(), package);
+            package_ids.insert(crate_id, package.id);
+            stats.packages_created += 1;
+        }
+        gate.flush(db)?;
+
+        let downloads = batch
+            .iter()
+            .filter_map(|crate_id| {
+                let package_id = *package_ids.get(crate_id)?;
+                Some((package_id, crates[crate_id].downloads, None))
+            })
+            .collect();
+        ranking::record_popularity(db, downloads)?;
+    }
+    drop(licenses);
+
+    tracing::info!("Inserting versions...");
+    let known_package_ids: HashSet<u64> = package_ids.values().copied().collect();
+    let mut known_versions: HashSet<(u64, String)> = db
+        .get_all_versions()?
+        .into_iter()
+        .filter(|v| known_package_ids.contains(&v.package_id))
+        .map(|v| (v.package_id, v.version))
+        .collect();
+
+    // crates.io version ID -> our version ID, only for versions inserted now
+    let mut version_ids: HashMap<u64, u64> = HashMap::new();
+    let mut pending: Vec<(u64, PackageVersion)> = Vec::new();
+    let now = Utc::now();
+
+    let mut flush_versions = |pending: &mut Vec<(u64, PackageVersion)>| -> Result<()> {
+        let (dump_ids, versions): (Vec<u64>, Vec<PackageVersion>) = pending.drain(..).unzip();
+        for (dump_id, saved) in dump_ids.into_iter().zip(db.insert_versions(versions)?) {
+            version_ids.insert(dump_id, saved.id);
+        }
+        Ok(())
+    };
+
+    for_each_row(dump, "versions", |row: VersionRow| {
+        let Some(&package_id) = package_ids.get(&row.crate_id) else {
+            return Ok(());
+        };
+        if row.yanked || !known_versions.insert((package_id, row.num.clone())) {
+            return Ok(());
+        }
+
+        let crate_name = &crates[&row.crate_id].name;
+        pending.push((
+            row.id,
+            PackageVersion {
+                id: 0,
+                package_id,
+                download_url: Some(format!(
+                    "https://crates.io/api/v1/crates/{}/{}/download",
+                    crate_name, row.num
+                )),
+                version: row.num,
+                release_date: row.created_at,
+                checksum: row.checksum.filter(|c| !c.is_empty()),
+                dependencies: Vec::new(),
+                vulnerabilities: Vec::new(),
+                changelog: None,
+                created_at: now,
+                license: row.license.filter(|l| !l.is_empty()),
+                size: row.crate_size,
+            },
+        ));
+
+        if pending.len() >= BATCH_SIZE {
+            flush_versions(&mut pending)?;
+        }
+        Ok(())
+    })?;
+    flush_versions(&mut pending)?;
+    stats.versions_created = version_ids.len();
+    drop(known_versions);
+
+    // Dependencies are attached to the versions inserted above. Rows for a
+    // version are published together, so they're grouped as they stream past.
+    tracing::info!("Attaching dependencies...");
+    let mut grouped: HashMap<u64, Vec<Dependency>> = HashMap::new();
+
+    let flush_dependencies = |grouped: &mut HashMap<u64, Vec<Dependency>>| -> Result<usize> {
+        let mut versions = Vec::with_capacity(grouped.len());
+        let mut count = 0;
+        for (version_id, dependencies) in grouped.drain() {
+            if let Some(mut version) = db.get_version(version_id)? {
+                count += dependencies.len();
+                version.dependencies.extend(dependencies);
+                versions.push(version);
+            }
+        }
+        db.update_versions(versions)?;
+        Ok(count)
+    };
+
+    for_each_row(dump, "dependencies", |row: DependencyRow| {
+        let (Some(&version_id), Some(dependency)) =
+            (version_ids.get(&row.version_id), crates.get(&row.crate_id))
+        else {
+            return Ok(());
+        };
+
+        grouped.entry(version_id).or_default().push(Dependency {
+            name: dependency.name.clone(),
+            version_requirement: row.req,
+            dependency_type: dependency_kind(row.kind).to_string(),
+            optional: row.optional,
+        });
+
+        if grouped.len() >= BATCH_SIZE {
+            stats.dependencies += flush_dependencies(&mut grouped)?;
+        }
+        Ok(())
+    })?;
+    stats.dependencies += flush_dependencies(&mut grouped)?;
+    drop(grouped);
+
+    // Team owners are skipped, only people are tracked as maintainers
+    tracing::info!("Attaching maintainers...");
+    let mut owners: HashMap<u64, Vec<u64>> = HashMap::new();
+    for_each_row(dump, "crate_owners", |row: CrateOwnerRow| {
+        if row.owner_kind == 0
+            && let Some(&package_id) = package_ids.get(&row.crate_id)
+        {
+            owners.entry(package_id).or_default().push(row.owner_id);
+        }
+        Ok(())
+    })?;
+
+    let owner_ids: HashSet<u64> = owners.values().flatten().copied().collect();
+    let mut users = Vec::new();
+    for_each_row(dump, "users", |row: UserRow| {
+        if owner_ids.contains(&row.id) {
+            users.push(row);
+        }
+        Ok(())
+    })?;
+
+    // crates.io user ID -> maintainer ID
+    let mut maintainer_ids: HashMap<u64, u64> = HashMap::new();
+    for batch in users.chunks(BATCH_SIZE) {
+        let maintainers = batch
+            .iter()
+            .map(|user| Maintainer {
+                id: 0,
+                handle: Maintainer::handle_for(PLATFORM, Some(&user.gh_login), &user.gh_login),
+                name: user.name.clone().filter(|n| !n.is_empty()),
+                github: Some(user.gh_login.clone()),
+                created_at: now,
+            })
+            .collect();
+        for (user, saved) in batch.iter().zip(db.upsert_maintainers(maintainers)?) {
+            maintainer_ids.insert(user.id, saved.id);
+        }
+    }
+    stats.maintainers = maintainer_ids.len();
+
+    let links: Vec<(u64, Vec<u64>)> = owners
+        .into_iter()
+        .map(|(package_id, users)| {
+            let ids = users.iter().filter_map(|u| maintainer_ids.get(u).copied()).collect();
+            (package_id, ids)
+        })
+        .collect();
+    for batch in links.chunks(BATCH_SIZE) {
+        helpers::update_maintainers(db, &(), batch.to_vec())?;
+    }
+
+    tracing::info!(
+        "crates.io backfill completed: {} new packages, {} updated, {} new versions, {} dependencies, {} maintainers, {} skipped for licensing",
+        stats.packages_created,
+        stats.packages_updated,
+        stats.versions_created,
+        stats.dependencies,
+        stats.maintainers,
+        stats.skipped_license
+    );
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dump_rows() {
+        let data = "\
+checksum,crate_id,created_at,id,license,num,yanked
+abc123,7,2024-01-02 03:04:05.678901+00,42,MIT OR Apache-2.0,1.0.0,f
+,7,2024-02-01 00:00:00,43,,1.0.1,t
+";
+        let rows: Vec<VersionRow> = csv::Reader::from_reader(data.as_bytes())
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(rows[0].crate_id, 7);
+        assert_eq!(rows[0].checksum.as_deref(), Some("abc123"));
+        assert_eq!(rows[0].created_at.to_rfc3339(), "2024-01-02T03:04:05.678901+00:00");
+        assert!(!rows[0].yanked);
+        assert!(rows[1].yanked);
+        assert_eq!(rows[1].license, None);
+        assert_eq!(rows[1].checksum, None);
+    }
+}
@@ -1,14 +1,22 @@
 use anyhow::Result;
 use chrono::{DateTime, Utc};
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::sync::Arc;
 
-use crate::db::Database;
-use crate::{Package, PackageVersion};
+use crate::collector_models::Admission;
+pub use crate::duplicates::normalize_url;
+use crate::license_policy::{LicensePolicy, Verdict};
+use crate::storage::Storage;
+use crate::timeline::{self, Broadcast};
+use crate::typosquat::NameIndex;
+use crate::{
+    EventMetadata, EventType, Package, PackageAlias, PackageVersion, QuarantinedPackage,
+    TimelineEvent, User,
+};
 
 /// Helper for checking and inserting new versions for an existing package
 pub async fn check_and_insert_new_versions<F>(
-    db: &Arc<Database>,
+    db: &Arc<dyn Storage>,
     package_id: u64,
     package_name: &str,
     new_versions: Vec<VersionData>,
@@ -52,7 +60,7 @@ where
 
 /// Helper for creating a new package and its versions
 pub async fn insert_package_with_versions<F>(
-    db: &Arc<Database>,
+    db: &Arc<dyn Storage>,
     package: Package,
     versions: Vec<VersionData>,
     create_version: F,
@@ -124,71 +132,322 @@ impl VersionData {
     }
 }
 
-/// Check if a license string represents a free/open source license
-/// Returns true if the license is free/open source, false if proprietary or unknown
-pub fn is_free_license(license: &str) -> bool {
-    // Normalize license string: lowercase and remove common separators
-    let normalized = license.to_lowercase();
+/// Flags newly ingested packages whose names closely mimic existing ones.
+///
+/// Load once per collection run; names are added as packages are checked.
+pub struct TyposquatGuard {
+    index: NameIndex,
+}
 
-    // List of known free/open source licenses and their variations
-    // Based on OSI-approved licenses and FSF free software licenses
-    let free_licenses = [
-        // Permissive licenses
-        "mit", "apache", "apache-2.0", "apache 2.0", "bsd", "isc", "cc0",
-        "unlicense", "wtfpl", "0bsd", "bsl-1.0", "ncsa", "zlib", "x11",
+impl TyposquatGuard {
+    pub fn load(db: &dyn Storage) -> Result<Self> {
+        Ok(Self {
+            index: NameIndex::load(db)?,
+        })
+    }
 
-        // Copyleft licenses
-        "gpl", "lgpl", "agpl", "mpl", "epl", "cpl", "cddl", "cecill",
-        "eupl", "osl", "afl", "artistic",
-
-        // Creative Commons free licenses
-        "cc-by", "cc-by-sa",
-
-        // Public domain
-        "public domain", "publicdomain", "unlicensed",
-    ];
-
-    // Known non-free keywords
-    let non_free_keywords = [
-        "proprietary", "commercial", "private", "closed",
-        "all rights reserved", "copyright only",
-        // Non-free Creative Commons licenses
-        "cc-by-nd", "cc-by-nc",
-    ];
-
-    // Check for non-free keywords first
-    for keyword in &non_free_keywords {
-        if normalized.contains(keyword) {
-            return false;
+    /// Publish a SecurityAlert event if `package` looks like a typosquat of a
+    /// known package on the same platform
+    pub fn check(&mut self, db: &dyn Storage, broadcaster: &dyn Broadcast, package: &Package) {
+        let similar = self
+            .index
+            .find_similar(&package.name, package.platform.as_deref());
+
+        if let Some(target) = similar.first() {
+            tracing::info!(
+                "Package {} looks like a typosquat of {}",
+                package.name,
+                target.name
+            );
+
+            let event = TimelineEvent {
+                id: 0,
+                package_id: package.id,
+                user_id: None,
+                event_type: EventType::SecurityAlert,
+                package_name: package.name.clone(),
+                version: None,
+                message: format!("Possible typosquat of {}", target.name),
+                metadata: Some(EventMetadata::Typosquat { similar }.to_json()),
+                created_at: Utc::now(),
+                notified_at: None,
+            };
+
+            if let Err(e) = publish(db, broadcaster, event) {
+                tracing::error!(
+                    "Failed to record typosquat alert for {}: {}",
+                    package.name,
+                    e
+                );
+            }
         }
+
+        self.index
+            .insert(package.name.clone(), package.platform.clone());
     }
+}
 
-    // Check if it matches any known free license
-    for free_license in &free_licenses {
-        if normalized.contains(free_license) {
-            return true;
+/// Replace the maintainers of each package, publishing a MaintainerChanged
+/// event for packages whose known maintainers changed. Packages seen for the
+/// first time get no event. Returns the number of packages that changed.
+pub fn update_maintainers(
+    db: &dyn Storage,
+    broadcaster: &dyn Broadcast,
+    links: Vec<(u64, Vec<u64>)>,
+) -> Result<usize> {
+    let mut changed = Vec::new();
+    let mut events = Vec::new();
+
+    for (package_id, maintainer_ids) in links {
+        let old: BTreeSet<u64> = db
+            .get_package_maintainers(package_id)?
+            .iter()
+            .map(|m| m.id)
+            .collect();
+        let new: BTreeSet<u64> = maintainer_ids.iter().copied().collect();
+        if old == new {
+            continue;
         }
-    }
 
-    // Handle SPDX-style "OR" expressions - if any part is free, consider it free
-    if normalized.contains(" or ") || normalized.contains("/") {
-        let parts: Vec<&str> = normalized.split(&[' ', '/', '|'][..]).collect();
-        for part in parts {
-            let part = part.trim();
-            if part.is_empty() || part == "or" {
-                continue;
-            }
-            for free_license in &free_licenses {
-                if part.contains(free_license) {
-                    return true;
+        if !old.is_empty()
+            && let Some(package) = db.get_package(package_id)?
+        {
+            let handles = |ids: Vec<&u64>| -> Result<Vec<String>> {
+                let mut handles = Vec::new();
+                for &id in ids {
+                    if let Some(maintainer) = db.get_maintainer(id)? {
+                        handles.push(maintainer.handle);
+                    }
                 }
+                Ok(handles)
+            };
+            let added = handles(new.difference(&old).collect())?;
+            let removed = handles(old.difference(&new).collect())?;
+
+            let mut changes = Vec::new();
+            if !added.is_empty() {
+                changes.push(format!("added {}", added.join(", ")));
+            }
+            if !removed.is_empty() {
+                changes.push(format!("removed {}", removed.join(", ")));
             }
+            events.push(TimelineEvent {
+                id: 0,
+                package_id,
+                user_id: None,
+                event_type: EventType::MaintainerChanged,
+                package_name: package.name,
+                version: None,
+                message: format!("Maintainers changed: {}", changes.join("; ")),
+                metadata: Some(EventMetadata::MaintainersChanged { added, removed }.to_json()),
+                created_at: Utc::now(),
+                notified_at: None,
+            });
+        }
+        changed.push((package_id, maintainer_ids));
+    }
+
+    let count = changed.len();
+    db.set_package_maintainers(changed)?;
+    for event in events {
+        tracing::info!("{} for {}", event.message, event.package_name);
+        publish(db, broadcaster, event)?;
+    }
+    Ok(count)
+}
+
+/// Pair packages that disappeared from a registry with new names published
+/// under the same project URL. Only unambiguous pairs are returned, keyed by
+/// the new name.
+pub fn match_renames<'a>(
+    missing: impl IntoIterator<Item = (&'a Package, &'a str)>,
+    new: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> HashMap<String, Package> {
+    let mut missing_by_url: HashMap<String, Vec<&Package>> = HashMap::new();
+    for (package, url) in missing {
+        missing_by_url.entry(normalize_url(url)).or_default().push(package);
+    }
+    let mut new_by_url: HashMap<String, Vec<&str>> = HashMap::new();
+    for (name, url) in new {
+        new_by_url.entry(normalize_url(url)).or_default().push(name);
+    }
+
+    missing_by_url
+        .into_iter()
+        .filter_map(|(url, old)| match (old.as_slice(), new_by_url.get(&url)?.as_slice()) {
+            ([old], [new]) => Some((new.to_string(), (*old).clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Record `event` on its package's timeline and fan it out to subscribers.
+/// Packages awaiting moderation aren't public yet, so only the record is kept.
+fn publish(db: &dyn Storage, broadcaster: &dyn Broadcast, event: TimelineEvent) -> Result<()> {
+    if db.insert_timeline_event(event.clone())?.is_none()
+        || db.is_package_pending(event.package_id)?
+    {
+        return Ok(());
+    }
+    timeline::publish(db, broadcaster, event)?;
+    Ok(())
+}
+
+fn package_event(package: &Package, message: String, metadata: EventMetadata) -> TimelineEvent {
+    TimelineEvent {
+        id: 0,
+        package_id: package.id,
+        user_id: None,
+        event_type: EventType::PackageUpdated,
+        package_name: package.name.clone(),
+        version: None,
+        message,
+        metadata: Some(metadata.to_json()),
+        created_at: Utc::now(),
+        notified_at: None,
+    }
+}
+
+/// Rename a package, keeping its old name as an alias and moving existing
+/// subscriptions over to the new name
+pub fn rename_package(
+    db: &dyn Storage,
+    broadcaster: &dyn Broadcast,
+    mut package: Package,
+    new_name: &str,
+) -> Result<Package> {
+    let old_name = std::mem::replace(&mut package.name, new_name.to_string());
+    package.updated_at = Utc::now();
+    tracing::info!("Package {} was renamed to {}", old_name, new_name);
+
+    db.insert_package_alias(PackageAlias {
+        name: old_name.clone(),
+        package_id: package.id,
+        created_at: Utc::now(),
+    })?;
+    db.update_package(package.clone())?;
+
+    for user in db.get_all_users()? {
+        if user.subscriptions.iter().any(|s| s.package_name == old_name) {
+            db.modify_user(user.id, &mut |user: &mut User| {
+                if user.subscriptions.iter().any(|s| s.package_name == new_name) {
+                    user.subscriptions.retain(|s| s.package_name != old_name);
+                }
+                for subscription in &mut user.subscriptions {
+                    if subscription.package_name == old_name {
+                        subscription.package_name = new_name.to_string();
+                    }
+                }
+            })?;
         }
     }
 
-    // If unknown, log it and reject for safety
-    tracing::warn!("Unknown license, treating as non-free: {}", license);
-    false
+    let event = package_event(
+        &package,
+        format!("Renamed from {} to {}", old_name, new_name),
+        EventMetadata::Renamed {
+            renamed_from: old_name,
+        },
+    );
+    publish(db, broadcaster, event)?;
+    Ok(package)
+}
+
+/// Record that a package's repository moved, which usually means it was
+/// transferred to a new owner. Does nothing if only the URL's form changed.
+pub fn record_repository_move(
+    db: &dyn Storage,
+    broadcaster: &dyn Broadcast,
+    package: &Package,
+    old: &str,
+    new: &str,
+) -> Result<()> {
+    if normalize_url(old) == normalize_url(new) {
+        return Ok(());
+    }
+    tracing::info!("Repository of {} moved from {} to {}", package.name, old, new);
+    let event = package_event(
+        package,
+        format!("Repository moved from {} to {}", old, new),
+        EventMetadata::RepositoryMoved {
+            repository_from: old.to_string(),
+            repository_to: new.to_string(),
+        },
+    );
+    publish(db, broadcaster, event)
+}
+
+/// Check if a license string represents a free/open source license under
+/// the default policy. Unknown licenses are treated as non-free.
+pub fn is_free_license(license: &str) -> bool {
+    LicensePolicy::default().verdict(Some(license)) == Verdict::Allowed
+}
+
+/// Applies the admission rules to collected packages, quarantining those the
+/// license policy rejects for admins to review. Load once per collection run.
+pub struct AdmissionGate {
+    policy: LicensePolicy,
+    review: bool,
+    /// Quarantined packages an admin let in anyway
+    admitted: HashSet<String>,
+    pending: Vec<QuarantinedPackage>,
+}
+
+impl AdmissionGate {
+    pub fn load(db: &dyn Storage, admission: Admission) -> Result<Self> {
+        let admitted = db
+            .get_all_quarantined_packages()?
+            .into_iter()
+            .filter(|q| q.admitted)
+            .map(|q| q.name)
+            .collect();
+        Ok(Self {
+            policy: admission.licenses,
+            review: admission.review_new_packages,
+            admitted,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Whether a package may be collected. Rejected packages are queued for
+    /// quarantine until the next [`Self::flush`].
+    pub fn admit(&mut self, name: &str, platform: &str, license: Option<&str>) -> bool {
+        let Some(reason) = self.policy.rejection(license) else {
+            return true;
+        };
+        if self.admitted.contains(name) {
+            return true;
+        }
+
+        tracing::info!("Quarantining package {}: {}", name, reason);
+        let now = Utc::now();
+        self.pending.push(QuarantinedPackage {
+            id: 0,
+            name: name.to_string(),
+            platform: Some(platform.to_string()),
+            license: license.map(str::to_string),
+            reason,
+            admitted: false,
+            first_seen: now,
+            last_seen: now,
+        });
+        false
+    }
+
+    /// Store queued rejections, returning how many there were
+    pub fn flush(&mut self, db: &dyn Storage) -> Result<usize> {
+        let count = self.pending.len();
+        db.quarantine_packages(std::mem::take(&mut self.pending))?;
+        Ok(count)
+    }
+
+    /// Hold just-inserted packages for moderation when reviews are enabled
+    pub fn hold_new(&self, db: &dyn Storage, packages: &[Package]) -> Result<()> {
+        if self.review && !packages.is_empty() {
+            db.hold_packages(packages.iter().map(|p| p.id).collect())?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -226,4 +485,124 @@ mod tests {
         assert!(!is_free_license("CustomLicense"));
         assert!(!is_free_license(""));
     }
+
+    #[test]
+    fn test_update_maintainers_records_changes() {
+        use crate::Maintainer;
+        use crate::db::Database;
+
+        let db = Database::in_memory().unwrap();
+        let package = db
+            .insert_package(crate::fixtures::package("left-pad"))
+            .unwrap();
+        let subscriber = db
+            .insert_user(crate::fixtures::user("alice", &["left-pad"]))
+            .unwrap();
+        let maintainer = |handle: &str| Maintainer {
+            id: 0,
+            handle: handle.to_string(),
+            name: None,
+            github: None,
+            created_at: Utc::now(),
+        };
+        let saved = db
+            .upsert_maintainers(vec![maintainer("alice"), maintainer("mallory")])
+            .unwrap();
+        let (alice, mallory) = (saved[0].id, saved[1].id);
+
+        // First sighting and unchanged lists are quiet
+        assert_eq!(
+            update_maintainers(&db, &(), vec![(package.id, vec![alice])]).unwrap(),
+            1
+        );
+        assert_eq!(
+            update_maintainers(&db, &(), vec![(package.id, vec![alice])]).unwrap(),
+            0
+        );
+        assert!(db.get_timeline_by_package(package.id).unwrap().is_empty());
+
+        update_maintainers(&db, &(), vec![(package.id, vec![mallory])]).unwrap();
+        let events = db.get_timeline_by_package(package.id).unwrap();
+        // The package's own record and the subscriber's copy
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().any(|e| e.user_id == Some(subscriber.id)));
+        assert_eq!(events[0].event_type, EventType::MaintainerChanged);
+        assert_eq!(events[0].message, "Maintainers changed: added mallory; removed alice");
+        assert_eq!(db.get_packages_by_maintainer(mallory).unwrap().len(), 1);
+        assert!(db.get_packages_by_maintainer(alice).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_renames_keep_old_names_resolving() {
+        use crate::db::Database;
+
+        let db = Database::in_memory().unwrap();
+        let package = |name: &str, homepage: &str| Package {
+            homepage: Some(homepage.to_string()),
+            repository: None,
+            ..crate::fixtures::package(name)
+        };
+        let old = db.insert_package(package("gnome-foo", "https://foo.org/")).unwrap();
+        let other = package("bar", "https://bar.org");
+
+        let renames = match_renames(
+            [(&old, "https://foo.org/"), (&other, "https://bar.org")],
+            [("foo", "http://www.foo.org"), ("bar2", "https://bar.org"), ("bar3", "https://bar.org")],
+        );
+        // bar's successor is ambiguous
+        assert_eq!(renames.len(), 1);
+        let renamed = rename_package(&db, &(), renames["foo"].clone(), "foo").unwrap();
+
+        assert_eq!(renamed.id, old.id);
+        assert_eq!(db.find_package("gnome-foo").unwrap().unwrap().name, "foo");
+        assert_eq!(db.find_package("foo").unwrap().unwrap().id, old.id);
+        assert!(db.find_package("gnome").unwrap().is_none());
+        let events = db.get_timeline_by_package(old.id).unwrap();
+        assert_eq!(events[0].message, "Renamed from gnome-foo to foo");
+    }
+
+    #[test]
+    fn test_license_gate_quarantines_until_admitted() {
+        use crate::db::Database;
+
+        let db = Database::in_memory().unwrap();
+
+        let mut gate = AdmissionGate::load(&db, Admission::default()).unwrap();
+        assert!(gate.admit("free", "nixpkgs", Some("MIT")));
+        assert!(!gate.admit("unfree", "nixpkgs", Some("Proprietary")));
+        assert_eq!(gate.flush(&db).unwrap(), 1);
+
+        let mut quarantined = db.get_all_quarantined_packages().unwrap().remove(0);
+        assert_eq!(quarantined.name, "unfree");
+        quarantined.admitted = true;
+        db.update_quarantined_package(quarantined.clone()).unwrap();
+
+        // Seeing it again keeps the admin's decision
+        let mut gate = AdmissionGate::load(&db, Admission::default()).unwrap();
+        assert!(gate.admit("unfree", "nixpkgs", Some("Proprietary")));
+        assert!(!gate.admit("other", "nixpkgs", None));
+        gate.flush(&db).unwrap();
+        assert_eq!(db.get_all_quarantined_packages().unwrap().len(), 2);
+        assert!(db.get_quarantined_package(quarantined.id).unwrap().unwrap().admitted);
+    }
+
+    #[test]
+    fn test_reviewed_packages_stay_hidden_until_approved() {
+        use crate::db::Database;
+
+        let db = Database::in_memory().unwrap();
+        let package = db.insert_package(crate::fixtures::package("fresh")).unwrap();
+
+        let admission = Admission {
+            review_new_packages: true,
+            ..Default::default()
+        };
+        let gate = AdmissionGate::load(&db, admission).unwrap();
+        gate.hold_new(&db, std::slice::from_ref(&package)).unwrap();
+        assert!(db.published(Some(package.clone())).unwrap().is_none());
+
+        assert_eq!(db.approve_packages(vec![package.id, 999]).unwrap(), 1);
+        assert!(db.published(Some(package)).unwrap().is_some());
+        assert!(db.get_pending_packages().unwrap().is_empty());
+    }
 }
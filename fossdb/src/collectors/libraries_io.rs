@@ -6,12 +6,18 @@ use serde::Deserialize;
 use std::sync::Arc;
 
 use crate::client::{AdaptiveConfig, AdaptiveRateLimitedClient};
-use crate::collector_models::{CollectedPackage, CollectedVersion, Collector, Dependency};
+use crate::collector_models::{
+    Admission, CollectedPackage, CollectedVersion, Collector, Dependency, RunReport,
+};
+use crate::collector_status::CollectorStatusRegistry;
 use crate::collectors::helpers;
+use crate::ranking;
+use crate::timeline::Broadcast;
 
 pub struct LibrariesIoCollector {
     client: AdaptiveRateLimitedClient,
     api_key: String,
+    admission: Admission,
 }
 
 #[derive(Debug, Deserialize)]
@@ -30,6 +36,7 @@ struct LibrariesIoProject {
     #[allow(dead_code)]
     dependent_repositories_count: Option<u32>,
     rank: Option<u32>,
+    stars: Option<u32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -54,6 +61,12 @@ struct LibrariesIoDependency {
     outdated: Option<bool>,
 }
 
+/// The project, with the dependencies of the requested version
+#[derive(Debug, Deserialize)]
+struct LibrariesIoDependencies {
+    dependencies: Vec<LibrariesIoDependency>,
+}
+
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
 struct LibrariesIoPlatform {
@@ -65,7 +78,7 @@ struct LibrariesIoPlatform {
 }
 
 impl LibrariesIoCollector {
-    pub fn new(client: Client, api_key: String) -> Self {
+    pub fn new(client: Client, api_key: String, admission: Admission) -> Self {
         // libraries.io has a 60 req/min rate limit for authenticated requests
         // Start conservative and let it adapt
         let config = AdaptiveConfig {
@@ -77,9 +90,23 @@ impl LibrariesIoCollector {
         Self {
             client: adaptive_client,
             api_key,
+            admission,
         }
     }
 
+    /// Publish the adapted request rate to `statuses`
+    pub fn with_statuses(mut self, statuses: Arc<CollectorStatusRegistry>) -> Self {
+        self.client = self.client.with_statuses(statuses);
+        self
+    }
+
+    /// Replay libraries.io responses from `cassette`, or record them to it
+    #[cfg(feature = "http-cassettes")]
+    pub fn with_cassette(mut self, cassette: Arc<crate::cassette::Cassette>) -> Self {
+        self.client = self.client.with_cassette(cassette);
+        self
+    }
+
     async fn get_platforms(&self) -> Result<Vec<LibrariesIoPlatform>> {
         let url = format!(
             "https://libraries.io/api/platforms?api_key={}",
@@ -104,7 +131,10 @@ impl LibrariesIoCollector {
         );
 
         let response = self.client.get(&url).await?;
-        let dependencies: Vec<LibrariesIoDependency> = response.json().await.unwrap_or_default();
+        let dependencies = match response.json::<LibrariesIoDependencies>().await {
+            Ok(project) => project.dependencies,
+            Err(_) => Vec::new(),
+        };
 
         let deps = dependencies
             .into_iter()
@@ -142,6 +172,7 @@ impl LibrariesIoCollector {
     async fn scrape_platform(
         &self,
         platform: &LibrariesIoPlatform,
+        admission: &mut helpers::AdmissionGate,
     ) -> Result<Vec<CollectedPackage>> {
         let mut packages = Vec::new();
 
@@ -201,21 +232,11 @@ impl LibrariesIoCollector {
                     tags.push(format!("status:{}", status.to_lowercase()));
                 }
 
-                // Skip packages with non-free licenses
-                if let Some(ref lic) = project_details.licenses {
-                    if !helpers::is_free_license(lic) {
-                        tracing::info!(
-                            "Skipping package {} with non-free license: {}",
-                            project_details.name,
-                            lic
-                        );
-                        continue;
-                    }
-                } else {
-                    tracing::info!(
-                        "Skipping package {} with no license information",
-                        project_details.name
-                    );
+                if !admission.admit(
+                    &project_details.name,
+                    &project_details.platform,
+                    project_details.licenses.as_deref(),
+                ) {
                     continue;
                 }
 
@@ -232,6 +253,7 @@ impl LibrariesIoCollector {
                     status: project_details.status,
                     dependents_count: project_details.dependents_count,
                     rank: project_details.rank,
+                    stars: project_details.stars,
                 };
 
                 packages.push(package);
@@ -248,10 +270,33 @@ impl Collector for LibrariesIoCollector {
         "libraries.io"
     }
 
-    async fn collect(&self, db: Arc<crate::db::Database>) -> Result<()> {
+    async fn collect(
+        &self,
+        db: Arc<dyn crate::storage::Storage>,
+        broadcaster: &dyn Broadcast,
+        report: &RunReport,
+    ) -> Result<()> {
+        // Count 429s from this run only
+        self.client.take_rate_limit_hits();
+        let result = self.collect_platforms(db, broadcaster, report).await;
+        report.rate_limited(self.client.take_rate_limit_hits());
+        result
+    }
+}
+
+impl LibrariesIoCollector {
+    async fn collect_platforms(
+        &self,
+        db: Arc<dyn crate::storage::Storage>,
+        broadcaster: &dyn Broadcast,
+        report: &RunReport,
+    ) -> Result<()> {
         use crate::{Package, PackageVersion};
         use std::collections::HashSet;
 
+        let mut typosquats = helpers::TyposquatGuard::load(&*db)?;
+        let mut admission = helpers::AdmissionGate::load(&*db, self.admission.clone())?;
+
         // In debug mode, limit to 5 packages total
         let mut packages_processed = 0;
         let max_packages = if cfg!(debug_assertions) { 5 } else { usize::MAX };
@@ -274,19 +319,34 @@ impl Collector for LibrariesIoCollector {
             if priority_platforms.contains(&platform.name.as_str()) {
                 tracing::info!("Scraping libraries.io platform: {}", platform.name);
 
-                match self.scrape_platform(&platform).await {
+                let scraped = self.scrape_platform(&platform, &mut admission).await;
+                admission.flush(&*db)?;
+                match scraped {
                     Ok(packages) => {
                         tracing::info!(
                             "Found {} packages from platform {}",
                             packages.len(),
                             platform.name
                         );
+                        report.found(packages.len());
 
                         // Save each package to the database
                         for package_data in packages {
                             // Check if package already exists
-                            match db.get_package_by_name(&package_data.name) {
+                            let existing = if db.may_contain_package(&package_data.name) {
+                                db.get_package_by_name(&package_data.name)
+                            } else {
+                                Ok(None)
+                            };
+                            match existing {
                                 Ok(Some(existing_package)) => {
+                                    if let Err(e) = ranking::record_popularity(
+                                        &*db,
+                                        vec![(existing_package.id, None, package_data.stars)],
+                                    ) {
+                                        tracing::warn!("Failed to record stars of {}: {}", package_data.name, e);
+                                    }
+
                                     // Package exists - check for new versions
                                     tracing::debug!(
                                         "Package {} exists, checking for new versions",
@@ -302,6 +362,7 @@ impl Collector for LibrariesIoCollector {
                                                     package_data.name,
                                                     e
                                                 );
+                                                report.error();
                                                 continue;
                                             }
                                         };
@@ -333,6 +394,8 @@ impl Collector for LibrariesIoCollector {
                                                 vulnerabilities: Vec::new(),
                                                 changelog: version_data.changelog,
                                                 created_at: now,
+                                                license: None,
+                                                size: None,
                                             };
 
                                             // Timeline events will be created automatically by the database listener
@@ -342,6 +405,9 @@ impl Collector for LibrariesIoCollector {
                                                     version_data.version,
                                                     package_data.name
                                                 );
+                                                report.versions(1);
+                                            } else {
+                                                report.error();
                                             }
                                         }
                                     }
@@ -371,6 +437,15 @@ impl Collector for LibrariesIoCollector {
                                     match db.insert_package(package) {
                                         Ok(saved_package) => {
                                             tracing::info!("Saved package: {}", saved_package.name);
+                                            report.added(1);
+                                            admission.hold_new(&*db, std::slice::from_ref(&saved_package))?;
+                                            typosquats.check(&*db, broadcaster, &saved_package);
+                                            if let Err(e) = ranking::record_popularity(
+                                                &*db,
+                                                vec![(saved_package.id, None, package_data.stars)],
+                                            ) {
+                                                tracing::warn!("Failed to record stars of {}: {}", saved_package.name, e);
+                                            }
 
                                             // Save versions
                                             for version_data in package_data.versions {
@@ -385,6 +460,8 @@ impl Collector for LibrariesIoCollector {
                                                     vulnerabilities: Vec::new(),
                                                     changelog: version_data.changelog,
                                                     created_at: now,
+                                                    license: None,
+                                                    size: None,
                                                 };
 
                                                 if let Err(e) = db.insert_version(version) {
@@ -394,12 +471,14 @@ impl Collector for LibrariesIoCollector {
                                                         saved_package.name,
                                                         e
                                                     );
+                                                    report.error();
                                                 } else {
                                                     tracing::debug!(
                                                         "Saved version {} for package {}",
                                                         version_data.version,
                                                         saved_package.name
                                                     );
+                                                    report.versions(1);
                                                 }
                                             }
                                         }
@@ -409,6 +488,7 @@ impl Collector for LibrariesIoCollector {
                                                 package_data.name,
                                                 e
                                             );
+                                            report.error();
                                         }
                                     }
                                 }
@@ -418,6 +498,7 @@ impl Collector for LibrariesIoCollector {
                                         package_data.name,
                                         e
                                     );
+                                    report.error();
                                 }
                             }
 
@@ -433,6 +514,7 @@ impl Collector for LibrariesIoCollector {
                     }
                     Err(e) => {
                         tracing::warn!("Failed to scrape platform {}: {}", platform.name, e);
+                        report.error();
                     }
                 }
             }
@@ -441,3 +523,48 @@ impl Collector for LibrariesIoCollector {
         Ok(())
     }
 }
+
+#[cfg(all(test, feature = "http-cassettes"))]
+mod tests {
+    use super::*;
+    use crate::cassette::Cassette;
+    use crate::db::Database;
+    use crate::storage::Storage;
+    use std::sync::atomic::Ordering;
+
+    #[tokio::test]
+    async fn test_collect_from_cassette() {
+        let cassette = Cassette::load(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/cassettes/libraries_io.json"
+        ))
+        .unwrap();
+        let api_key = std::env::var("LIBRARIES_IO_API_KEY").unwrap_or_default();
+        let collector = LibrariesIoCollector::new(Client::new(), api_key, Admission::default())
+            .with_cassette(Arc::new(cassette));
+
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        let report = RunReport::default();
+        collector.collect(db.clone(), &(), &report).await.unwrap();
+
+        let chalk = db.get_package_by_name("chalk").unwrap().unwrap();
+        assert_eq!(chalk.license.as_deref(), Some("MIT"));
+        assert_eq!(chalk.tags, ["npm", "libraries.io", "javascript"]);
+        let versions = db.get_versions_by_package(chalk.id).unwrap();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].version, "4.1.2");
+        let dependencies: Vec<_> = versions[0]
+            .dependencies
+            .iter()
+            .map(|d| (d.name.as_str(), d.version_requirement.as_str()))
+            .collect();
+        assert_eq!(
+            dependencies,
+            [("ansi-styles", "^4.1.0"), ("supports-color", "^7.1.0")]
+        );
+
+        // The 404 project is skipped
+        assert!(db.get_package_by_name("removed-pkg").unwrap().is_none());
+        assert_eq!(report.packages_found.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,397 @@
+// Backfill from the libraries.io open data dump (https://libraries.io/data)
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Deserializer, de::DeserializeOwned};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::collector_models::Admission;
+use crate::collectors::helpers;
+use crate::storage::Storage;
+use crate::{Dependency, Package, PackageVersion};
+
+/// Number of rows written per database transaction
+const BATCH_SIZE: usize = 1000;
+
+#[derive(Debug, Default)]
+pub struct BackfillStats {
+    pub packages_created: usize,
+    pub packages_updated: usize,
+    pub versions_created: usize,
+    pub dependencies: usize,
+    pub skipped_license: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProjectRow {
+    #[serde(rename = "ID")]
+    id: u64,
+    #[serde(rename = "Platform")]
+    platform: String,
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Created Timestamp", deserialize_with = "timestamp")]
+    created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "Updated Timestamp", deserialize_with = "timestamp")]
+    updated_at: Option<DateTime<Utc>>,
+    #[serde(rename = "Description")]
+    description: Option<String>,
+    #[serde(rename = "Homepage URL")]
+    homepage: Option<String>,
+    #[serde(rename = "Licenses")]
+    licenses: Option<String>,
+    #[serde(rename = "Repository URL")]
+    repository: Option<String>,
+    #[serde(rename = "SourceRank")]
+    rank: Option<u32>,
+    #[serde(rename = "Dependent Projects Count")]
+    dependents_count: Option<u32>,
+    #[serde(rename = "Language")]
+    language: Option<String>,
+    #[serde(rename = "Status")]
+    status: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VersionRow {
+    #[serde(rename = "ID")]
+    id: u64,
+    #[serde(rename = "Project ID")]
+    project_id: u64,
+    #[serde(rename = "Number")]
+    number: String,
+    #[serde(rename = "Published Timestamp", deserialize_with = "timestamp")]
+    published_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DependencyRow {
+    #[serde(rename = "Version ID")]
+    version_id: u64,
+    #[serde(rename = "Dependency Name")]
+    name: String,
+    #[serde(rename = "Dependency Kind")]
+    kind: Option<String>,
+    #[serde(rename = "Optional Dependency", default)]
+    optional: bool,
+    #[serde(rename = "Dependency Requirements")]
+    requirements: Option<String>,
+}
+
+/// Timestamps like `2015-04-08 16:03:54 UTC`, empty when unknown
+fn timestamp<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error> {
+    let Some(value) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    let value = value.trim_end_matches(" UTC");
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f")
+        .map(|t| Some(t.and_utc()))
+        .map_err(serde::de::Error::custom)
+}
+
+/// Map the registry-specific kinds onto our dependency types
+fn dependency_kind(kind: Option<&str>) -> &'static str {
+    let kind = kind.unwrap_or_default().to_lowercase();
+    if kind.contains("dev") || kind.contains("test") {
+        "dev"
+    } else if kind.contains("build") {
+        "build"
+    } else {
+        "normal"
+    }
+}
+
+/// Locate a table in the dump directory, e.g. `projects-1.6.0-2020-01-12.csv`
+fn find_table(dir: &Path, table: &str) -> Result<PathBuf> {
+    let prefix = format!("{}-", table);
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if name.starts_with(&prefix) && name.ends_with(".csv") {
+            return Ok(path);
+        }
+    }
+    anyhow::bail!("No {}-*.csv in {}", table, dir.display())
+}
+
+/// Stream every row of a table from the dump directory
+fn for_each_row<T, F>(dir: &Path, table: &str, mut f: F) -> Result<()>
+where
+    T: DeserializeOwned,
+    F: FnMut(T) -> Result<()>,
+{
+    let path = find_table(dir, table)?;
+    let mut reader = csv::Reader::from_path(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    for row in reader.deserialize() {
+        f(row.with_context(|| format!("Invalid row in {}", path.display()))?)?;
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct ProjectBatch {
+    new: Vec<Package>,
+    /// libraries.io project ID of each package in `new`
+    new_project_ids: Vec<u64>,
+    changed: Vec<Package>,
+}
+
+impl ProjectBatch {
+    fn flush(
+        &mut self,
+        db: &dyn Storage,
+        gate: &mut helpers::AdmissionGate,
+        typosquats: &mut helpers::TyposquatGuard,
+        package_ids: &mut HashMap<u64, u64>,
+        stats: &mut BackfillStats,
+    ) -> Result<()> {
+        stats.packages_updated += self.changed.len();
+        db.update_packages(std::mem::take(&mut self.changed))?;
+
+        let saved = db.insert_packages(std::mem::take(&mut self.new))?;
+        gate.hold_new(db, &saved)?;
+        for (package, project_id) in saved.iter().zip(self.new_project_ids.drain(..)) {
+            typosquats.check(db, &(), package);
+            package_ids.insert(project_id, package.id);
+            stats.packages_created += 1;
+        }
+        gate.flush(db)?;
+        Ok(())
+    }
+}
+
+/// Import projects, versions and dependencies from an extracted dump.
+///
+/// Only `platforms` are imported (case-insensitive), or every platform if
+/// it's empty. Like the crates.io backfill, re-running it is safe.
+pub fn backfill(
+    db: &dyn Storage,
+    dir: &Path,
+    platforms: &[String],
+    admission: &Admission,
+) -> Result<BackfillStats> {
+    let mut stats = BackfillStats::default();
+    let wanted = |platform: &str| platforms.is_empty() || platforms.iter().any(|p| p.eq_ignore_ascii_case(platform));
+
+    tracing::info!("Upserting projects...");
+    let mut existing: HashMap<String, Package> = db
+        .get_all_packages()?
+        .into_iter()
+        .map(|p| (p.name.clone(), p))
+        .collect();
+    let mut typosquats = helpers::TyposquatGuard::load(db)?;
+    let mut gate = helpers::AdmissionGate::load(db, admission.clone())?;
+
+    // libraries.io project ID -> package ID
+    let mut package_ids: HashMap<u64, u64> = HashMap::new();
+    let mut batch = ProjectBatch::default();
+    let now = Utc::now();
+
+    for_each_row(dir, "projects", |row: ProjectRow| {
+        if !wanted(&row.platform) {
+            return Ok(());
+        }
+        if !gate.admit(&row.name, &row.platform, row.licenses.as_deref()) {
+            stats.skipped_license += 1;
+            return Ok(());
+        }
+
+        let updated_at = row.updated_at.or(row.created_at).unwrap_or(now);
+        match existing.remove(&row.name) {
+            // Names are unique across platforms, so leave other sources alone
+            Some(package) if package.platform.as_deref() != Some(row.platform.as_str()) => {}
+            Some(mut package) => {
+                package_ids.insert(row.id, package.id);
+                if updated_at > package.updated_at {
+                    if let (Some(old), Some(new)) = (&package.repository, &row.repository) {
+                        helpers::record_repository_move(db, &(), &package, old, new)?;
+                    }
+                    package.description = row.description;
+                    package.homepage = row.homepage;
+                    package.repository = row.repository;
+                    package.license = row.licenses;
+                    package.status = row.status;
+                    package.dependents_count = row.dependents_count;
+                    package.rank = row.rank;
+                    package.updated_at = updated_at;
+                    batch.changed.push(package);
+                }
+            }
+            None => {
+                let mut tags = vec![row.platform.to_lowercase(), "libraries.io".to_string()];
+                if let Some(language) = &row.language {
+                    tags.push(language.to_lowercase());
+                }
+                if let Some(status) = &row.status {
+                    tags.push(format!("status:{}", status.to_lowercase()));
+                }
+
+                batch.new.push(Package {
+                    id: 0,
+                    name: row.name,
+                    description: row.description,
+                    homepage: row.homepage,
+                    repository: row.repository,
+                    license: row.licenses,
+                    tags,
+                    created_at: row.created_at.unwrap_or(now),
+                    updated_at,
+                    platform: Some(row.platform),
+                    language: row.language,
+                    status: row.status,
+                    dependents_count: row.dependents_count,
+                    rank: row.rank,
+                });
+                batch.new_project_ids.push(row.id);
+            }
+        }
+
+        if batch.new.len() + batch.changed.len() >= BATCH_SIZE {
+            batch.flush(db, &mut gate, &mut typosquats, &mut package_ids, &mut stats)?;
+        }
+        Ok(())
+    })?;
+    batch.flush(db, &mut gate, &mut typosquats, &mut package_ids, &mut stats)?;
+    drop(existing);
+
+    tracing::info!("Inserting versions...");
+    let known_package_ids: HashSet<u64> = package_ids.values().copied().collect();
+    let mut known_versions: HashSet<(u64, String)> = db
+        .get_all_versions()?
+        .into_iter()
+        .filter(|v| known_package_ids.contains(&v.package_id))
+        .map(|v| (v.package_id, v.version))
+        .collect();
+
+    // libraries.io version ID -> our version ID, only for versions inserted now
+    let mut version_ids: HashMap<u64, u64> = HashMap::new();
+    let mut pending: Vec<(u64, PackageVersion)> = Vec::new();
+
+    let mut flush_versions = |pending: &mut Vec<(u64, PackageVersion)>| -> Result<()> {
+        let (dump_ids, versions): (Vec<u64>, Vec<PackageVersion>) = pending.drain(..).unzip();
+        for (dump_id, saved) in dump_ids.into_iter().zip(db.insert_versions(versions)?) {
+            version_ids.insert(dump_id, saved.id);
+        }
+        Ok(())
+    };
+
+    for_each_row(dir, "versions", |row: VersionRow| {
+        let Some(&package_id) = package_ids.get(&row.project_id) else {
+            return Ok(());
+        };
+        if !known_versions.insert((package_id, row.number.clone())) {
+            return Ok(());
+        }
+
+        pending.push((
+            row.id,
+            PackageVersion {
+                id: 0,
+                package_id,
+                version: row.number,
+                release_date: row.published_at.unwrap_or(now),
+                download_url: None,
+                checksum: None,
+                dependencies: Vec::new(),
+                vulnerabilities: Vec::new(),
+                changelog: None,
+                created_at: now,
+                license: None,
+                size: None,
+            },
+        ));
+
+        if pending.len() >= BATCH_SIZE {
+            flush_versions(&mut pending)?;
+        }
+        Ok(())
+    })?;
+    flush_versions(&mut pending)?;
+    stats.versions_created = version_ids.len();
+    drop(known_versions);
+
+    // Dependencies are attached to the versions inserted above, grouped as
+    // they stream past since rows for a version are adjacent
+    tracing::info!("Attaching dependencies...");
+    let mut grouped: HashMap<u64, Vec<Dependency>> = HashMap::new();
+
+    let flush_dependencies = |grouped: &mut HashMap<u64, Vec<Dependency>>| -> Result<usize> {
+        let mut versions = Vec::with_capacity(grouped.len());
+        let mut count = 0;
+        for (version_id, dependencies) in grouped.drain() {
+            if let Some(mut version) = db.get_version(version_id)? {
+                count += dependencies.len();
+                version.dependencies.extend(dependencies);
+                versions.push(version);
+            }
+        }
+        db.update_versions(versions)?;
+        Ok(count)
+    };
+
+    for_each_row(dir, "dependencies", |row: DependencyRow| {
+        let Some(&version_id) = version_ids.get(&row.version_id) else {
+            return Ok(());
+        };
+
+        grouped.entry(version_id).or_default().push(Dependency {
+            name: row.name,
+            version_requirement: row.requirements.unwrap_or_else(|| "*".to_string()),
+            dependency_type: dependency_kind(row.kind.as_deref()).to_string(),
+            optional: row.optional,
+        });
+
+        if grouped.len() >= BATCH_SIZE {
+            stats.dependencies += flush_dependencies(&mut grouped)?;
+        }
+        Ok(())
+    })?;
+    stats.dependencies += flush_dependencies(&mut grouped)?;
+
+    tracing::info!(
+        "libraries.io backfill completed: {} new packages, {} updated, {} new versions, {} dependencies, {} skipped for licensing",
+        stats.packages_created,
+        stats.packages_updated,
+        stats.versions_created,
+        stats.dependencies,
+        stats.skipped_license
+    );
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dump_rows() {
+        let data = "\
+ID,Platform,Name,Created Timestamp,Updated Timestamp,Description,Keywords,Homepage URL,Licenses,Repository URL,Versions Count,SourceRank,Latest Release Publish Timestamp,Latest Release Number,Package Manager ID,Dependent Projects Count,Language,Status,Last synced Timestamp,Dependent Repositories Count,Repository ID
+1,NPM,left-pad,2014-03-21 08:16:36 UTC,,String left pad,\"pad,string\",,WTFPL,https://github.com/stevemao/left-pad,12,14,2017-04-19 20:39:41 UTC,1.3.0,,3940,JavaScript,Deprecated,,,
+";
+        let rows: Vec<ProjectRow> = csv::Reader::from_reader(data.as_bytes())
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(rows[0].name, "left-pad");
+        assert_eq!(rows[0].created_at.unwrap().to_rfc3339(), "2014-03-21T08:16:36+00:00");
+        assert_eq!(rows[0].updated_at, None);
+        assert_eq!(rows[0].homepage, None);
+        assert_eq!(rows[0].rank, Some(14));
+        assert_eq!(rows[0].status.as_deref(), Some("Deprecated"));
+
+        let data = "\
+ID,Platform,Project Name,Project ID,Version Number,Version ID,Dependency Name,Dependency Platform,Dependency Kind,Optional Dependency,Dependency Requirements,Dependency Project ID
+9,NPM,left-pad,1,1.3.0,7,tape,NPM,Development,false,*,2
+";
+        let rows: Vec<DependencyRow> = csv::Reader::from_reader(data.as_bytes())
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(rows[0].version_id, 7);
+        assert!(!rows[0].optional);
+        assert_eq!(dependency_kind(rows[0].kind.as_deref()), "dev");
+    }
+}
@@ -2,8 +2,12 @@ pub mod helpers;
 
 #[cfg(feature = "collector-rust")]
 pub mod crates_io;
-#[cfg(feature = "collector-rust")]
+#[cfg(feature = "collector-crates-io-dump")]
+pub mod crates_io_dump;
+#[cfg(feature = "collector-libraries-io")]
 pub mod libraries_io;
+#[cfg(feature = "collector-libraries-io-dump")]
+pub mod libraries_io_dump;
 #[cfg(feature = "collector-nixpkgs")]
 pub mod nixpkgs;
 // pub mod npm;
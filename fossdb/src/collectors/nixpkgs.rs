@@ -1,44 +1,72 @@
 use anyhow::{Context, Result};
 use async_trait::async_trait;
+use chrono::Utc;
 use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::process::Stdio;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
 use tokio::process::Command;
+use tokio::sync::Semaphore;
 
-use crate::collector_models::Collector;
+use crate::collector_models::{Admission, Collector, CollectorUnavailable, RunReport};
 use crate::collectors::helpers;
+use crate::storage::Storage;
+use crate::timeline::Broadcast;
+use crate::{Maintainer, Package, PackageVersion};
 
-#[derive(Debug, Deserialize)]
-struct NixSearchResult {
-    #[serde(flatten)]
-    packages: std::collections::HashMap<String, NixPackageInfo>,
-}
+const PLATFORM: &str = "nixpkgs";
 
-#[derive(Debug, Deserialize)]
-struct NixPackageInfo {
-    pname: Option<String>,
-    version: Option<String>,
-    description: Option<String>,
-}
+/// Number of packages written per database transaction
+const BATCH_SIZE: usize = 1000;
 
+/// One entry of `nix-env -qa --json --meta`, keyed by attribute path
 #[derive(Debug, Deserialize)]
-struct NixPackageMeta {
+struct NixEnvPackage {
+    name: Option<String>,
+    pname: Option<String>,
     version: Option<String>,
+    #[serde(default)]
     meta: NixMetaInfo,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 struct NixMetaInfo {
     description: Option<String>,
-    homepage: Option<String>,
+    homepage: Option<NixHomepage>,
     license: Option<NixLicense>,
     changelog: Option<String>,
+    #[serde(default)]
+    maintainers: Vec<NixMaintainer>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct NixMaintainer {
+    name: Option<String>,
+    github: Option<String>,
+}
+
+impl NixMaintainer {
+    fn handle(&self) -> Option<String> {
+        let name = self.name.as_deref().or(self.github.as_deref())?;
+        Some(Maintainer::handle_for(PLATFORM, self.github.as_deref(), name))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum NixHomepage {
+    Single(String),
+    Multiple(Vec<String>),
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(untagged)]
 enum NixLicense {
+    Name(String),
     Single(NixLicenseInfo),
-    Multiple(Vec<NixLicenseInfo>),
+    Multiple(Vec<NixLicense>),
 }
 
 #[derive(Debug, Deserialize)]
@@ -51,69 +79,290 @@ struct NixLicenseInfo {
     spdx_id: Option<String>,
 }
 
-pub struct NixpkgsCollector {}
+impl NixLicense {
+    fn names(&self) -> Vec<String> {
+        match self {
+            NixLicense::Name(name) => vec![name.clone()],
+            NixLicense::Single(l) => l
+                .spdx_id
+                .clone()
+                .or_else(|| l.short_name.clone())
+                .or_else(|| l.full_name.clone())
+                .into_iter()
+                .collect(),
+            NixLicense::Multiple(licenses) => licenses.iter().flat_map(NixLicense::names).collect(),
+        }
+    }
 
-impl NixpkgsCollector {
-    async fn run_nix_command(&self, args: &[&str]) -> Result<String> {
-        let output = Command::new("nix")
-            .args(args)
-            .output()
-            .await
-            .context("Failed to execute nix command")?;
+    /// Join multiple licenses with " OR "
+    fn to_expression(&self) -> Option<String> {
+        let names = self.names();
+        (!names.is_empty()).then(|| names.join(" OR "))
+    }
+}
+
+/// A package as it appears in the current nixpkgs snapshot
+#[derive(Debug, Clone, PartialEq)]
+struct NixPackage {
+    name: String,
+    version: Option<String>,
+    description: Option<String>,
+    homepage: Option<String>,
+    license: Option<String>,
+    changelog: Option<String>,
+    maintainers: Vec<NixMaintainer>,
+}
+
+/// Attribute paths nested deeper in the package set are usually variants
+/// (e.g. `python312Packages.foo`), so prefer the shallowest one
+fn attr_priority(attr_path: &str) -> (usize, usize, &str) {
+    (attr_path.matches('.').count(), attr_path.len(), attr_path)
+}
+
+/// Parse a `nix-env -qa --json --meta` dump into one entry per package name
+fn parse_dump(dump: &[u8]) -> Result<Vec<NixPackage>> {
+    let entries: HashMap<String, NixEnvPackage> =
+        serde_json::from_slice(dump).context("Failed to parse nix-env output")?;
+
+    let mut attr_paths: Vec<&String> = entries.keys().collect();
+    attr_paths.sort_by_key(|attr_path| attr_priority(attr_path));
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("nix command failed: {}", stderr);
+    let mut seen = HashSet::new();
+    let mut packages = Vec::new();
+
+    for attr_path in attr_paths {
+        let entry = &entries[attr_path];
+        let name = entry
+            .pname
+            .clone()
+            .or_else(|| entry.name.clone())
+            // Fall back to the last attribute path component
+            .unwrap_or_else(|| attr_path.rsplit('.').next().unwrap_or(attr_path).to_string());
+
+        if !seen.insert(name.clone()) {
+            continue;
         }
 
-        Ok(String::from_utf8(output.stdout)?)
+        packages.push(NixPackage {
+            name,
+            version: entry.version.clone().filter(|v| !v.is_empty()),
+            description: entry.meta.description.clone(),
+            homepage: entry.meta.homepage.as_ref().and_then(|h| match h {
+                NixHomepage::Single(url) => Some(url.clone()),
+                NixHomepage::Multiple(urls) => urls.first().cloned(),
+            }),
+            license: entry.meta.license.as_ref().and_then(NixLicense::to_expression),
+            changelog: entry.meta.changelog.clone(),
+            maintainers: entry.meta.maintainers.clone(),
+        });
+    }
+
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(packages)
+}
+
+fn new_version(package_id: u64, nix_package: &NixPackage, version: &str) -> PackageVersion {
+    let now = Utc::now();
+    PackageVersion {
+        id: 0,
+        package_id,
+        version: version.to_string(),
+        release_date: now, // We don't have exact release dates from nix
+        download_url: None,
+        checksum: None,
+        dependencies: Vec::new(),
+        vulnerabilities: Vec::new(),
+        changelog: nix_package.changelog.clone(),
+        created_at: now,
+        license: None,
+        size: None,
+    }
+}
+
+/// Record who maintains each package, replacing the previous lists
+fn save_maintainers(
+    db: &dyn Storage,
+    broadcaster: &dyn Broadcast,
+    packages: &[(u64, &NixPackage)],
+) -> Result<()> {
+    let now = Utc::now();
+    let mut maintainers: HashMap<String, Maintainer> = HashMap::new();
+    for maintainer in packages.iter().flat_map(|(_, p)| &p.maintainers) {
+        if let Some(handle) = maintainer.handle() {
+            maintainers.entry(handle.clone()).or_insert_with(|| Maintainer {
+                id: 0,
+                handle,
+                name: maintainer.name.clone(),
+                github: maintainer.github.clone(),
+                created_at: now,
+            });
+        }
     }
 
-    async fn search_packages(&self) -> Result<Vec<(String, NixPackageInfo)>> {
-        tracing::info!("Searching nixpkgs for packages...");
+    let ids: HashMap<String, u64> = db
+        .upsert_maintainers(maintainers.into_values().collect())?
+        .into_iter()
+        .map(|m| (m.handle, m.id))
+        .collect();
+    let links = packages
+        .iter()
+        .map(|(package_id, nix_package)| {
+            let maintainer_ids = nix_package
+                .maintainers
+                .iter()
+                .filter_map(|m| ids.get(&m.handle()?).copied())
+                .collect();
+            (*package_id, maintainer_ids)
+        })
+        .collect();
+    helpers::update_maintainers(db, broadcaster, links)?;
+    Ok(())
+}
 
-        // Search for all packages using nix search with JSON output
-        let output = self
-            .run_nix_command(&["search", "nixpkgs", "^", "--json"])
-            .await?;
+/// Resource limits for nix subprocesses
+#[derive(Debug, Clone)]
+pub struct NixLimits {
+    pub timeout: Duration,
+    pub max_output_bytes: usize,
+    pub max_concurrency: usize,
+}
 
-        let search_result: NixSearchResult =
-            serde_json::from_str(&output).context("Failed to parse nix search output")?;
+impl Default for NixLimits {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(3600),
+            max_output_bytes: 1024 * 1024 * 1024,
+            max_concurrency: 1,
+        }
+    }
+}
 
-        let mut packages: Vec<(String, NixPackageInfo)> =
-            search_result.packages.into_iter().collect();
+/// Environment variables nix needs; everything else is withheld from the subprocess
+const PASSTHROUGH_ENV: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "NIX_PATH",
+    "NIX_REMOTE",
+    "NIX_CONF_DIR",
+    "NIX_SSL_CERT_FILE",
+    "SSL_CERT_FILE",
+    "XDG_CACHE_HOME",
+    "TMPDIR",
+];
+
+/// Stderr beyond this is dropped, it is only used for error messages
+const MAX_STDERR_BYTES: usize = 64 * 1024;
+
+/// Read a stream to the end, keeping at most `limit` bytes so the child never
+/// blocks on a full pipe
+async fn drain_limited(
+    reader: &mut (impl AsyncRead + Unpin),
+    buffer: &mut Vec<u8>,
+    limit: usize,
+) -> std::io::Result<()> {
+    let mut chunk = [0; 8192];
+    loop {
+        let n = reader.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        let keep = n.min(limit.saturating_sub(buffer.len()));
+        buffer.extend_from_slice(&chunk[..keep]);
+    }
+}
 
-        // Sort by attribute path for consistent ordering
-        packages.sort_by(|a, b| a.0.cmp(&b.0));
+pub struct NixpkgsCollector {
+    limits: NixLimits,
+    permits: Semaphore,
+    admission: Admission,
+}
 
-        tracing::info!("Found {} packages from nixpkgs", packages.len());
-        Ok(packages)
+impl NixpkgsCollector {
+    pub fn new(limits: NixLimits, admission: Admission) -> Self {
+        Self {
+            permits: Semaphore::new(limits.max_concurrency.max(1)),
+            limits,
+            admission,
+        }
     }
 
-    async fn get_package_details(&self, attr_path: &str) -> Result<NixPackageMeta> {
-        tracing::debug!("Fetching details for {}", attr_path);
-
-        // Build a Nix expression to get package metadata
-        let expr = format!(
-            r#"with import <nixpkgs> {{}}; let pkg = {}; in {{
-                name = pkg.pname or pkg.name;
-                version = pkg.version or null;
-                meta = pkg.meta or {{}};
-            }}"#,
-            attr_path
-                .strip_prefix("legacyPackages.x86_64-linux.")
-                .or_else(|| attr_path.strip_prefix("packages.x86_64-linux."))
-                .unwrap_or(attr_path)
-        );
+    /// Run a nix command under the configured timeout, output and concurrency limits
+    async fn run_nix(&self, program: &str, args: &[&str]) -> Result<Vec<u8>> {
+        let _permit = self.permits.acquire().await?;
 
-        let output = self
-            .run_nix_command(&["eval", "--impure", "--expr", &expr, "--json"])
-            .await?;
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .env_clear()
+            .envs(PASSTHROUGH_ENV.iter().filter_map(|key| Some((key, std::env::var_os(key)?))))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(CollectorUnavailable(format!("{} is not installed", program)).into());
+            }
+            Err(e) => return Err(e).with_context(|| format!("Failed to execute {}", program)),
+        };
+
+        let mut stdout = child.stdout.take().context("stdout was not captured")?;
+        let mut stderr = child.stderr.take().context("stderr was not captured")?;
+        let max_output = self.limits.max_output_bytes;
+
+        // Drain stderr in the background so the child never blocks on it
+        let stderr_task = tokio::spawn(async move {
+            let mut errors = Vec::new();
+            drain_limited(&mut stderr, &mut errors, MAX_STDERR_BYTES)
+                .await
+                .map(|()| errors)
+        });
+
+        let run = async {
+            let mut output = Vec::new();
+            (&mut stdout)
+                .take(max_output as u64 + 1)
+                .read_to_end(&mut output)
+                .await?;
+            if output.len() > max_output {
+                anyhow::bail!("{} output exceeded {} bytes", program, max_output);
+            }
+            let status = child.wait().await?;
+            let errors = stderr_task.await??;
+            Ok((status, output, errors))
+        };
+
+        // The child is killed on drop if we give up early
+        let (status, output, errors) = tokio::time::timeout(self.limits.timeout, run)
+            .await
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "{} timed out after {} seconds",
+                    program,
+                    self.limits.timeout.as_secs()
+                )
+            })??;
+
+        if !status.success() {
+            anyhow::bail!(
+                "{} failed ({}): {}",
+                program,
+                status,
+                String::from_utf8_lossy(&errors).trim()
+            );
+        }
 
-        let package_meta: NixPackageMeta =
-            serde_json::from_str(&output).context("Failed to parse package details")?;
+        Ok(output)
+    }
 
-        Ok(package_meta)
+    /// Evaluate the whole package set once, including metadata
+    async fn query_all(&self) -> Result<Vec<u8>> {
+        tracing::info!("Querying nixpkgs for packages...");
+        self.run_nix("nix-env", &["-f", "<nixpkgs>", "-qa", "--json", "--meta"])
+            .await
     }
 }
 
@@ -123,191 +372,217 @@ impl Collector for NixpkgsCollector {
         "nixpkgs"
     }
 
-    async fn collect(&self, db: Arc<crate::db::Database>) -> Result<()> {
-        use crate::{Package, PackageVersion};
-        use chrono::Utc;
-
+    async fn collect(
+        &self,
+        db: Arc<dyn Storage>,
+        broadcaster: &dyn Broadcast,
+        report: &RunReport,
+    ) -> Result<()> {
         tracing::info!("Starting nixpkgs collection...");
 
-        // In debug mode, limit to 5 packages total
-        let mut packages_processed = 0;
-        let max_packages = if cfg!(debug_assertions) { 5 } else { usize::MAX };
-
-        // Search for packages
-        let packages = self.search_packages().await?;
-
-        for (attr_path, search_info) in packages {
-            let package_name = search_info.pname.clone().unwrap_or_else(|| {
-                // Extract package name from attribute path if pname is not available
-                attr_path
-                    .rsplit('.')
-                    .next()
-                    .unwrap_or(&attr_path)
-                    .to_string()
-            });
+        let dump = self.query_all().await?;
+        let packages = parse_dump(&dump)?;
+        drop(dump);
+        tracing::info!("Found {} packages in nixpkgs", packages.len());
+        report.found(packages.len());
+
+        let mut typosquats = helpers::TyposquatGuard::load(&*db)?;
+        let mut admission = helpers::AdmissionGate::load(&*db, self.admission.clone())?;
+        let mut existing: HashMap<String, Package> = db
+            .get_all_packages()?
+            .into_iter()
+            .map(|p| (p.name.clone(), p))
+            .collect();
+
+        // A package missing from the snapshot may have been renamed to a new
+        // attribute with the same homepage
+        let names: HashSet<&str> = packages.iter().map(|p| p.name.as_str()).collect();
+        let mut renames = helpers::match_renames(
+            existing
+                .values()
+                .filter(|p| p.platform.as_deref() == Some(PLATFORM) && !names.contains(p.name.as_str()))
+                .filter_map(|p| Some((p, p.homepage.as_deref()?))),
+            packages
+                .iter()
+                .filter(|p| !existing.contains_key(&p.name))
+                .filter_map(|p| Some((p.name.as_str(), p.homepage.as_deref()?))),
+        );
+
+        // In debug mode, limit to 5 new packages per run
+        let max_new_packages = if cfg!(debug_assertions) { 5 } else { usize::MAX };
 
-            // Check if package already exists
-            match db.get_package_by_name(&package_name) {
-                Ok(Some(_existing_package)) => {
-                    tracing::debug!("Package {} already exists, skipping for now", package_name);
-                    // For now, skip existing packages
-                    // In the future, we could check for version updates
+        let (mut created, mut updated, mut versions_added, mut skipped) = (0, 0, 0, 0);
+
+        for batch in packages.chunks(BATCH_SIZE) {
+            let mut new_packages = Vec::new();
+            let mut new_package_sources = Vec::new();
+            let mut changed_packages = Vec::new();
+            let mut new_versions = Vec::new();
+            let mut maintained = Vec::new();
+
+            for nix_package in batch {
+                if !admission.admit(&nix_package.name, PLATFORM, nix_package.license.as_deref()) {
+                    skipped += 1;
                     continue;
                 }
-                Ok(None) => {
-                    // Package doesn't exist, fetch details and save it
-                    tracing::info!("New package discovered: {}", package_name);
-
-                    // Try to get detailed metadata
-                    let package_meta = match self.get_package_details(&attr_path).await {
-                        Ok(meta) => Some(meta),
-                        Err(e) => {
-                            tracing::warn!("Failed to fetch details for {}: {}", package_name, e);
-                            None
-                        }
-                    };
-
-                    let now = Utc::now();
-
-                    // Extract license
-                    let license = if let Some(ref meta) = package_meta {
-                        meta.meta.license.as_ref().and_then(|lic| match lic {
-                            NixLicense::Single(l) => l
-                                .spdx_id
-                                .clone()
-                                .or_else(|| l.short_name.clone())
-                                .or_else(|| l.full_name.clone()),
-                            NixLicense::Multiple(licenses) => {
-                                // Join multiple licenses with " OR "
-                                let license_str = licenses
-                                    .iter()
-                                    .filter_map(|l| {
-                                        l.spdx_id
-                                            .clone()
-                                            .or_else(|| l.short_name.clone())
-                                            .or_else(|| l.full_name.clone())
-                                    })
-                                    .collect::<Vec<_>>()
-                                    .join(" OR ");
-                                if license_str.is_empty() {
-                                    None
-                                } else {
-                                    Some(license_str)
-                                }
+
+                let known = match renames.remove(&nix_package.name) {
+                    Some(old) => Some(helpers::rename_package(
+                        &*db,
+                        broadcaster,
+                        old,
+                        &nix_package.name,
+                    )?),
+                    None => existing.remove(&nix_package.name),
+                };
+                match known {
+                    // Names are unique across platforms, so leave other sources alone
+                    Some(package) if package.platform.as_deref() != Some(PLATFORM) => {}
+                    Some(mut package) => {
+                        maintained.push((package.id, nix_package));
+                        let mut changed = false;
+                        for (field, value) in [
+                            (&mut package.description, &nix_package.description),
+                            (&mut package.homepage, &nix_package.homepage),
+                            (&mut package.license, &nix_package.license),
+                        ] {
+                            if value.is_some() && field != value {
+                                *field = value.clone();
+                                changed = true;
                             }
-                        })
-                    } else {
-                        None
-                    };
-
-                    // Extract description - prefer meta description over search description
-                    let description = package_meta
-                        .as_ref()
-                        .and_then(|m| m.meta.description.clone())
-                        .or_else(|| search_info.description.clone());
-
-                    // Extract homepage
-                    let homepage = package_meta.as_ref().and_then(|m| m.meta.homepage.clone());
-
-                    // Skip packages with non-free licenses
-                    if let Some(ref lic) = license {
-                        if !helpers::is_free_license(lic) {
-                            tracing::info!(
-                                "Skipping package {} with non-free license: {}",
-                                package_name,
-                                lic
-                            );
-                            continue;
                         }
-                    } else {
-                        tracing::info!(
-                            "Skipping package {} with no license information",
-                            package_name
-                        );
-                        continue;
-                    }
 
-                    // Create the package
-                    let package = Package {
-                        id: 0,
-                        name: package_name.clone(),
-                        description,
-                        homepage,
-                        repository: None, // Nixpkgs doesn't directly expose repository URLs
-                        license,
-                        tags: vec!["nix".to_string(), "nixpkgs".to_string()],
-                        created_at: now,
-                        updated_at: now,
-                        platform: Some("nixpkgs".to_string()),
-                        language: None,
-                        status: None,
-                        dependents_count: None,
-                        rank: None,
-                    };
-
-                    match db.insert_package(package) {
-                        Ok(saved_package) => {
-                            tracing::info!("Saved package: {}", saved_package.name);
-
-                            // Save the current version if available
-                            let version_string = package_meta
-                                .as_ref()
-                                .and_then(|m| m.version.clone())
-                                .or_else(|| search_info.version.clone());
-
-                            if let Some(version_str) = version_string {
-                                let version = PackageVersion {
-                                    id: 0,
-                                    package_id: saved_package.id,
-                                    version: version_str.clone(),
-                                    release_date: now, // We don't have exact release dates from nix
-                                    download_url: None,
-                                    checksum: None,
-                                    dependencies: Vec::new(),
-                                    vulnerabilities: Vec::new(),
-                                    changelog: package_meta
-                                        .as_ref()
-                                        .and_then(|m| m.meta.changelog.clone()),
-                                    created_at: now,
-                                };
-
-                                if let Err(e) = db.insert_version(version) {
-                                    tracing::error!(
-                                        "Failed to save version {} for package {}: {}",
-                                        version_str,
-                                        saved_package.name,
-                                        e
-                                    );
-                                } else {
-                                    tracing::debug!(
-                                        "Saved version {} for package {}",
-                                        version_str,
-                                        saved_package.name
-                                    );
-                                }
+                        if let Some(version) = &nix_package.version {
+                            let known = db.get_versions_by_package(package.id)?;
+                            if !known.iter().any(|v| &v.version == version) {
+                                tracing::info!(
+                                    "New version detected: {} {}",
+                                    package.name,
+                                    version
+                                );
+                                new_versions.push(new_version(package.id, nix_package, version));
+                                changed = true;
                             }
                         }
-                        Err(e) => {
-                            tracing::error!("Failed to save package {}: {}", package_name, e);
+
+                        if changed {
+                            package.updated_at = Utc::now();
+                            changed_packages.push(package);
                         }
                     }
-                }
-                Err(e) => {
-                    tracing::error!("Failed to check if package {} exists: {}", package_name, e);
+                    None => {
+                        if created + new_packages.len() >= max_new_packages {
+                            continue;
+                        }
+
+                        let now = Utc::now();
+                        new_packages.push(Package {
+                            id: 0,
+                            name: nix_package.name.clone(),
+                            description: nix_package.description.clone(),
+                            homepage: nix_package.homepage.clone(),
+                            repository: None, // Nixpkgs doesn't directly expose repository URLs
+                            license: nix_package.license.clone(),
+                            tags: vec!["nix".to_string(), "nixpkgs".to_string()],
+                            created_at: now,
+                            updated_at: now,
+                            platform: Some(PLATFORM.to_string()),
+                            language: None,
+                            status: None,
+                            dependents_count: None,
+                            rank: None,
+                        });
+                        new_package_sources.push(nix_package);
+                    }
                 }
             }
 
-            // Increment counter and check limit
-            packages_processed += 1;
-            if packages_processed >= max_packages {
-                if cfg!(debug_assertions) {
-                    tracing::info!("Debug mode: Reached limit of {} packages, stopping collection", max_packages);
+            let saved_packages = db.insert_packages(new_packages)?;
+            admission.hold_new(&*db, &saved_packages)?;
+            for (package, nix_package) in saved_packages.iter().zip(new_package_sources) {
+                typosquats.check(&*db, broadcaster, package);
+                maintained.push((package.id, nix_package));
+                if let Some(version) = &nix_package.version {
+                    new_versions.push(new_version(package.id, nix_package, version));
                 }
-                break;
             }
+
+            created += saved_packages.len();
+            updated += changed_packages.len();
+            versions_added += new_versions.len();
+            report.added(saved_packages.len());
+            report.updated(changed_packages.len());
+            report.versions(new_versions.len());
+
+            db.update_packages(changed_packages)?;
+            db.insert_versions(new_versions)?;
+            save_maintainers(&*db, broadcaster, &maintained)?;
+            admission.flush(&*db)?;
         }
 
-        tracing::info!("Nixpkgs collection completed");
+        tracing::info!(
+            "Nixpkgs collection completed: {} new, {} updated, {} new versions, {} skipped for licensing",
+            created,
+            updated,
+            versions_added,
+            skipped
+        );
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dump() {
+        let dump = serde_json::json!({
+            "python312Packages.requests": {
+                "name": "python3.12-requests-2.32.3",
+                "pname": "requests",
+                "version": "2.32.3",
+                "meta": { "description": "Python variant" }
+            },
+            "hello": {
+                "name": "hello-2.12.1",
+                "pname": "hello",
+                "version": "2.12.1",
+                "meta": {
+                    "description": "A program that produces a familiar, friendly greeting",
+                    "homepage": ["https://www.gnu.org/software/hello/manual/"],
+                    "license": { "spdxId": "GPL-3.0-or-later", "shortName": "gpl3Plus" },
+                    "maintainers": [
+                        { "name": "Alice", "github": "AliceDev", "githubId": 1 },
+                        { "name": "Bob", "email": "bob@example.com" }
+                    ]
+                }
+            },
+            "python3Packages.requests": {
+                "name": "python3.13-requests-2.32.3",
+                "pname": "requests",
+                "version": "2.32.3",
+                "meta": {
+                    "license": [{ "spdxId": "Apache-2.0" }, "MIT"]
+                }
+            }
+        });
+
+        let packages = parse_dump(dump.to_string().as_bytes()).unwrap();
+        assert_eq!(packages.len(), 2);
+
+        assert_eq!(packages[0].name, "hello");
+        assert_eq!(
+            packages[0].homepage.as_deref(),
+            Some("https://www.gnu.org/software/hello/manual/")
+        );
+        assert_eq!(packages[0].license.as_deref(), Some("GPL-3.0-or-later"));
+        let handles: Vec<_> = packages[0].maintainers.iter().filter_map(|m| m.handle()).collect();
+        assert_eq!(handles, ["alicedev", "nixpkgs:Bob"]);
+
+        // The shallower attribute path wins
+        assert_eq!(packages[1].name, "requests");
+        assert_eq!(packages[1].description, None);
+        assert_eq!(packages[1].license.as_deref(), Some("Apache-2.0 OR MIT"));
+    }
+}
@@ -1,13 +1,27 @@
+#[cfg(feature = "collector")]
+use crate::collector_models::Admission;
+#[cfg(feature = "collector")]
+use crate::license_policy::LicensePolicy;
+use crate::retention::RetentionPolicy;
 use std::env;
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub database_path: String,
+    /// Use this Postgres database instead of the embedded one at `database_path`
+    pub database_url: Option<String>,
     pub jwt_secret: String,
     #[allow(dead_code)]
     pub server_port: u16,
+    /// Port of the gRPC server, when built with the grpc feature
+    pub grpc_port: u16,
+    /// Serve admin endpoints on this address, e.g. `127.0.0.1:3001`, instead
+    /// of alongside the public API
+    pub admin_listen_addr: Option<String>,
     pub libraries_io_api_key: Option<String>,
     pub collector_interval_hours: u64,
+    /// Identifies this process when claiming collector work shared with others
+    pub instance_id: String,
     pub timeline_retention_days: u64,
     pub smtp_host: String,
     pub smtp_port: u16,
@@ -16,6 +30,69 @@ pub struct Config {
     pub smtp_from_address: String,
     pub smtp_from_name: String,
     pub email_enabled: bool,
+    /// How email is delivered: smtp, sendmail, file, ses or mailgun
+    pub email_transport: String,
+    pub sendmail_command: String,
+    /// Where the file transport writes `.eml` files
+    pub email_output_dir: String,
+    pub aws_region: String,
+    pub aws_access_key_id: Option<String>,
+    pub aws_secret_access_key: Option<String>,
+    /// `https://api.eu.mailgun.net` for domains in the EU region
+    pub mailgun_api_url: String,
+    pub mailgun_domain: Option<String>,
+    pub mailgun_api_key: Option<String>,
+    pub cors_allowed_origins: Vec<String>,
+    pub cors_allowed_methods: Vec<String>,
+    pub cors_allow_credentials: bool,
+    pub idempotency_ttl_seconds: u64,
+    /// Requests with larger bodies are rejected with 413
+    pub max_request_body_kb: usize,
+    /// Requests per minute per IP without a token, 0 for no limit
+    pub rate_limit_anonymous_per_minute: u32,
+    /// Requests per minute per signed in user, 0 for no limit
+    pub rate_limit_authenticated_per_minute: u32,
+    /// Count anonymous requests by `X-Forwarded-For`, only safe behind a proxy
+    pub rate_limit_trust_forwarded: bool,
+    pub admin_usernames: Vec<String>,
+    pub moderator_usernames: Vec<String>,
+    pub nvd_api_key: Option<String>,
+    /// Used when prefetching metadata for submitted packages
+    pub github_token: Option<String>,
+    /// GitHub App that syncs projects from repository lockfiles, with the
+    /// github-app feature
+    pub github_app_id: Option<u64>,
+    /// PEM file with the app's private key
+    pub github_app_private_key_path: Option<String>,
+    pub github_app_client_id: Option<String>,
+    pub github_app_client_secret: Option<String>,
+    pub github_webhook_secret: Option<String>,
+    pub enrichment_interval_hours: u64,
+    pub nix_timeout_seconds: u64,
+    pub nix_max_output_mb: usize,
+    pub nix_max_concurrency: usize,
+    /// Licenses collectors admit, empty for the built-in list of free licenses
+    pub license_allow: Vec<String>,
+    pub license_deny: Vec<String>,
+    /// Admit packages whose license is missing or unrecognized
+    pub license_accept_unknown: bool,
+    /// Hide newly collected packages until a moderator approves them
+    pub review_new_packages: bool,
+    pub artifact_cache_dir: String,
+    pub artifact_max_size_mb: u64,
+    pub ws_flush_interval_ms: u64,
+    /// `nats://` or `mqtt://` broker that global timeline events are published to
+    pub event_broker_url: Option<String>,
+    pub event_topic_prefix: String,
+    /// Months an account can go unused before it's expired, 0 to keep
+    /// accounts forever
+    pub inactive_account_months: u64,
+    /// Days between warning a user and expiring their account
+    pub inactive_account_grace_days: u64,
+    /// `anonymize` or `delete`
+    pub inactive_account_action: String,
+    /// Keep client IPs out of memory and off the record, see `/api/health`
+    pub privacy_mode: bool,
 }
 
 impl Config {
@@ -27,16 +104,26 @@ impl Config {
 
         Self {
             database_path: env::var("DATABASE_PATH").unwrap_or_else(|_| "./foss.db".to_string()),
+            database_url: env::var("DATABASE_URL").ok().filter(|url| !url.is_empty()),
             jwt_secret,
             server_port: env::var("SERVER_PORT")
                 .unwrap_or_else(|_| "3000".to_string())
                 .parse()
                 .unwrap_or(3000),
+            grpc_port: env::var("GRPC_PORT")
+                .unwrap_or_else(|_| "50051".to_string())
+                .parse()
+                .unwrap_or(50051),
+            admin_listen_addr: env::var("ADMIN_LISTEN_ADDR").ok().filter(|addr| !addr.is_empty()),
             libraries_io_api_key: env::var("LIBRARIES_IO_API_KEY").ok(),
             collector_interval_hours: env::var("COLLECTOR_INTERVAL_HOURS")
                 .unwrap_or_else(|_| "1".to_string())
                 .parse()
                 .unwrap_or(1),
+            instance_id: env::var("INSTANCE_ID").unwrap_or_else(|_| {
+                let host = env::var("HOSTNAME").unwrap_or_else(|_| "localhost".to_string());
+                format!("{}-{}", host, std::process::id())
+            }),
             timeline_retention_days: env::var("TIMELINE_RETENTION_DAYS")
                 .unwrap_or_else(|_| "90".to_string())
                 .parse()
@@ -55,6 +142,165 @@ impl Config {
                 .unwrap_or_else(|_| "false".to_string())
                 .parse()
                 .unwrap_or(false),
+            email_transport: env::var("EMAIL_TRANSPORT")
+                .unwrap_or_else(|_| "smtp".to_string())
+                .to_lowercase(),
+            sendmail_command: env::var("SENDMAIL_COMMAND")
+                .unwrap_or_else(|_| "sendmail".to_string()),
+            email_output_dir: env::var("EMAIL_OUTPUT_DIR")
+                .unwrap_or_else(|_| "./data/emails".to_string()),
+            aws_region: env::var("AWS_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+            aws_access_key_id: env::var("AWS_ACCESS_KEY_ID").ok().filter(|key| !key.is_empty()),
+            aws_secret_access_key: env::var("AWS_SECRET_ACCESS_KEY")
+                .ok()
+                .filter(|key| !key.is_empty()),
+            mailgun_api_url: env::var("MAILGUN_API_URL")
+                .unwrap_or_else(|_| "https://api.mailgun.net".to_string()),
+            mailgun_domain: env::var("MAILGUN_DOMAIN").ok().filter(|domain| !domain.is_empty()),
+            mailgun_api_key: env::var("MAILGUN_API_KEY").ok().filter(|key| !key.is_empty()),
+            cors_allowed_origins: env_list("CORS_ALLOWED_ORIGINS", "http://localhost:8080"),
+            cors_allowed_methods: env_list("CORS_ALLOWED_METHODS", "GET,POST,PUT,DELETE"),
+            cors_allow_credentials: env::var("CORS_ALLOW_CREDENTIALS")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            idempotency_ttl_seconds: env::var("IDEMPOTENCY_TTL_SECONDS")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()
+                .unwrap_or(3600),
+            max_request_body_kb: env::var("MAX_REQUEST_BODY_KB")
+                .unwrap_or_else(|_| "1024".to_string())
+                .parse()
+                .unwrap_or(1024),
+            rate_limit_anonymous_per_minute: env::var("RATE_LIMIT_ANONYMOUS_PER_MINUTE")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .unwrap_or(60),
+            rate_limit_authenticated_per_minute: env::var("RATE_LIMIT_AUTHENTICATED_PER_MINUTE")
+                .unwrap_or_else(|_| "600".to_string())
+                .parse()
+                .unwrap_or(600),
+            rate_limit_trust_forwarded: env::var("RATE_LIMIT_TRUST_FORWARDED")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            admin_usernames: env_list("ADMIN_USERNAMES", ""),
+            moderator_usernames: env_list("MODERATOR_USERNAMES", ""),
+            nvd_api_key: env::var("NVD_API_KEY").ok(),
+            github_token: env::var("GITHUB_TOKEN").ok().filter(|token| !token.is_empty()),
+            github_app_id: env::var("GITHUB_APP_ID").ok().and_then(|id| id.parse().ok()),
+            github_app_private_key_path: env::var("GITHUB_APP_PRIVATE_KEY_PATH")
+                .ok()
+                .filter(|path| !path.is_empty()),
+            github_app_client_id: env::var("GITHUB_APP_CLIENT_ID")
+                .ok()
+                .filter(|id| !id.is_empty()),
+            github_app_client_secret: env::var("GITHUB_APP_CLIENT_SECRET")
+                .ok()
+                .filter(|secret| !secret.is_empty()),
+            github_webhook_secret: env::var("GITHUB_WEBHOOK_SECRET")
+                .ok()
+                .filter(|secret| !secret.is_empty()),
+            enrichment_interval_hours: env::var("ENRICHMENT_INTERVAL_HOURS")
+                .unwrap_or_else(|_| "24".to_string())
+                .parse()
+                .unwrap_or(24),
+            nix_timeout_seconds: env::var("NIX_TIMEOUT_SECONDS")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()
+                .unwrap_or(3600),
+            nix_max_output_mb: env::var("NIX_MAX_OUTPUT_MB")
+                .unwrap_or_else(|_| "1024".to_string())
+                .parse()
+                .unwrap_or(1024),
+            nix_max_concurrency: env::var("NIX_MAX_CONCURRENCY")
+                .unwrap_or_else(|_| "1".to_string())
+                .parse()
+                .unwrap_or(1),
+            license_allow: env_list("LICENSE_ALLOW", ""),
+            license_deny: env_list("LICENSE_DENY", ""),
+            license_accept_unknown: env::var("LICENSE_ACCEPT_UNKNOWN")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            review_new_packages: env::var("REVIEW_NEW_PACKAGES")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            artifact_cache_dir: env::var("ARTIFACT_CACHE_DIR")
+                .unwrap_or_else(|_| "./data/artifacts".to_string()),
+            artifact_max_size_mb: env::var("ARTIFACT_MAX_SIZE_MB")
+                .unwrap_or_else(|_| "512".to_string())
+                .parse()
+                .unwrap_or(512),
+            ws_flush_interval_ms: env::var("WS_FLUSH_INTERVAL_MS")
+                .unwrap_or_else(|_| "250".to_string())
+                .parse()
+                .unwrap_or(250),
+            event_broker_url: env::var("EVENT_BROKER_URL").ok().filter(|url| !url.is_empty()),
+            event_topic_prefix: env::var("EVENT_TOPIC_PREFIX")
+                .unwrap_or_else(|_| "fossdb.events".to_string()),
+            inactive_account_months: env::var("INACTIVE_ACCOUNT_MONTHS")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .unwrap_or(0),
+            inactive_account_grace_days: env::var("INACTIVE_ACCOUNT_GRACE_DAYS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .unwrap_or(30),
+            inactive_account_action: env::var("INACTIVE_ACCOUNT_ACTION")
+                .unwrap_or_else(|_| "anonymize".to_string()),
+            privacy_mode: env::var("PRIVACY_MODE")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
         }
     }
+
+    /// Whether a user may moderate, which admins always can
+    pub fn is_moderator(&self, username: &str) -> bool {
+        self.moderator_usernames.iter().any(|u| u == username)
+            || self.admin_usernames.iter().any(|u| u == username)
+    }
+
+    /// What collectors let into the catalog
+    #[cfg(feature = "collector")]
+    pub fn admission(&self) -> Admission {
+        Admission {
+            licenses: LicensePolicy {
+                allow: self.license_allow.clone(),
+                deny: self.license_deny.clone(),
+                accept_unknown: self.license_accept_unknown,
+            },
+            review_new_packages: self.review_new_packages,
+        }
+    }
+
+    /// How unused accounts are expired, if at all
+    pub fn retention_policy(&self) -> anyhow::Result<Option<RetentionPolicy>> {
+        if self.inactive_account_months == 0 {
+            return Ok(None);
+        }
+        Ok(Some(RetentionPolicy {
+            inactive_after: chrono::Duration::days(30 * self.inactive_account_months as i64),
+            grace_period: chrono::Duration::days(self.inactive_account_grace_days as i64),
+            action: self.inactive_account_action.parse()?,
+            exempt_usernames: self
+                .admin_usernames
+                .iter()
+                .chain(&self.moderator_usernames)
+                .cloned()
+                .collect(),
+        }))
+    }
+}
+
+/// Read a comma-separated list from the environment
+fn env_list(key: &str, default: &str) -> Vec<String> {
+    env::var(key)
+        .unwrap_or_else(|_| default.to_string())
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
 }
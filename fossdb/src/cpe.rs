@@ -0,0 +1,195 @@
+// Linking NVD's CPE product names to packages, so CVEs published against a
+// CPE can be attached to the right records
+#[cfg(feature = "db")]
+use anyhow::Result;
+
+#[cfg(feature = "db")]
+use crate::storage::Storage;
+#[cfg(feature = "db")]
+use crate::{CpeMapping, CpeMappingSource};
+
+/// The parts of a CPE name that identify a product
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cpe {
+    /// `a` for applications, `o` for operating systems, `h` for hardware
+    pub part: String,
+    pub vendor: String,
+    pub product: String,
+    pub version: Option<String>,
+}
+
+impl Cpe {
+    /// Parse a CPE 2.3 formatted string (`cpe:2.3:a:vendor:product:...`) or a
+    /// CPE 2.2 URI (`cpe:/a:vendor:product:...`)
+    pub fn parse(cpe: &str) -> Option<Self> {
+        let fields: Vec<String> = if let Some(rest) = cpe.strip_prefix("cpe:2.3:") {
+            split_escaped(rest)
+        } else if let Some(rest) = cpe.strip_prefix("cpe:/") {
+            rest.split(':').map(str::to_string).collect()
+        } else {
+            return None;
+        };
+
+        let field = |i: usize| {
+            fields
+                .get(i)
+                .map(|f| f.to_lowercase())
+                .filter(|f| !f.is_empty() && f != "*" && f != "-")
+        };
+        Some(Self {
+            part: field(0)?,
+            vendor: field(1)?,
+            product: field(2)?,
+            version: field(3),
+        })
+    }
+
+    /// Key mappings are stored under, ignoring version and edition
+    pub fn vendor_product(&self) -> String {
+        format!("{}:{}", self.vendor, self.product)
+    }
+
+    /// Package names this product is likely published under, best first
+    pub fn candidate_names(&self) -> Vec<String> {
+        let mut names = vec![self.product.clone()];
+        for name in [self.product.replace('_', "-"), self.product.replace('-', "_")] {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+}
+
+/// Split a CPE 2.3 string on unescaped colons, dropping the escapes
+fn split_escaped(cpe: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = cpe.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => fields.last_mut().unwrap().extend(chars.next()),
+            ':' => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+/// Whether a vendor name plausibly refers to the package's project, based on
+/// where the package is hosted
+#[cfg(feature = "db")]
+fn vendor_matches(vendor: &str, package: &crate::Package) -> bool {
+    let vendor = vendor.trim_end_matches("_project").replace('_', "-");
+    [&package.repository, &package.homepage]
+        .into_iter()
+        .flatten()
+        .any(|url| url.to_lowercase().contains(&vendor))
+}
+
+/// Find the package a CPE refers to. Stored mappings win, otherwise a package
+/// named after the product is guessed and remembered as a heuristic mapping
+/// for admins to review. None if nothing matches or the guess is ambiguous.
+#[cfg(feature = "db")]
+pub fn resolve(db: &dyn Storage, cpe: &str) -> Result<Option<u64>> {
+    let Some(cpe) = Cpe::parse(cpe) else {
+        return Ok(None);
+    };
+    let vendor_product = cpe.vendor_product();
+    if let Some(mapping) = db.get_cpe_mapping(&vendor_product)? {
+        return Ok(Some(mapping.package_id));
+    }
+
+    let mut candidates = Vec::new();
+    for name in cpe.candidate_names() {
+        if let Some(package) = db.get_package_by_name(&name)? {
+            candidates.push(package);
+        }
+    }
+    if candidates.len() > 1 {
+        candidates.retain(|package| vendor_matches(&cpe.vendor, package));
+    }
+    let [package] = candidates.as_slice() else {
+        return Ok(None);
+    };
+
+    db.insert_cpe_mapping(CpeMapping {
+        id: 0,
+        vendor_product,
+        package_id: package.id,
+        source: CpeMappingSource::Heuristic,
+        created_at: chrono::Utc::now(),
+    })?;
+    Ok(Some(package.id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpe_formats() {
+        let cpe = Cpe::parse("cpe:2.3:a:serde-rs:serde_json:1.0.0:*:*:*:*:rust:*:*").unwrap();
+        assert_eq!(cpe.part, "a");
+        assert_eq!(cpe.vendor_product(), "serde-rs:serde_json");
+        assert_eq!(cpe.version.as_deref(), Some("1.0.0"));
+        assert_eq!(cpe.candidate_names(), ["serde_json", "serde-json"]);
+
+        let uri = Cpe::parse("cpe:/a:Nodejs:Node.js").unwrap();
+        assert_eq!(uri.vendor_product(), "nodejs:node.js");
+        assert_eq!(uri.version, None);
+
+        let escaped = Cpe::parse(r"cpe:2.3:a:acme:foo\:bar:*:*:*:*:*:*:*:*").unwrap();
+        assert_eq!(escaped.product, "foo:bar");
+        assert_eq!(escaped.version, None);
+
+        assert_eq!(Cpe::parse("cpe:2.3:a:*:serde"), None);
+        assert_eq!(Cpe::parse("pkg:cargo/serde"), None);
+    }
+
+    #[cfg(feature = "db")]
+    #[test]
+    fn test_resolve_remembers_guesses() {
+        use crate::Package;
+        use crate::db::Database;
+        use chrono::Utc;
+
+        let db = Database::in_memory().unwrap();
+        let package = |name: &str| Package {
+            id: 0,
+            name: name.to_string(),
+            description: None,
+            homepage: None,
+            repository: None,
+            license: None,
+            tags: vec![],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            platform: None,
+            language: None,
+            status: None,
+            dependents_count: None,
+            rank: None,
+        };
+        let openssl = db.insert_package(package("openssl")).unwrap();
+        let other = db.insert_package(package("openssl-sys")).unwrap();
+
+        let cpe = "cpe:2.3:a:openssl:openssl:3.0.0:*:*:*:*:*:*:*";
+        assert_eq!(resolve(&db, cpe).unwrap(), Some(openssl.id));
+        let mapping = db.get_cpe_mapping("openssl:openssl").unwrap().unwrap();
+        assert_eq!(mapping.source, CpeMappingSource::Heuristic);
+
+        // Curated mappings replace the guess
+        db.insert_cpe_mapping(CpeMapping {
+            id: 0,
+            vendor_product: "openssl:openssl".to_string(),
+            package_id: other.id,
+            source: CpeMappingSource::Curated,
+            created_at: Utc::now(),
+        })
+        .unwrap();
+        assert_eq!(resolve(&db, cpe).unwrap(), Some(other.id));
+        assert_eq!(db.get_all_cpe_mappings().unwrap().len(), 1);
+
+        assert_eq!(resolve(&db, "cpe:2.3:a:acme:unknown").unwrap(), None);
+    }
+}
@@ -0,0 +1,397 @@
+//! Import packages or versions from arbitrary CSV files, using a TOML mapping
+//! from record fields to CSV columns.
+//!
+//! ```toml
+//! target = "packages"
+//! list_separator = ";"
+//!
+//! [columns]
+//! name = "Component"
+//! license = "License"
+//! tags = "Labels"
+//!
+//! [defaults]
+//! platform = "npm"
+//! ```
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+
+use crate::storage::Storage;
+use crate::{Package, PackageVersion};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Target {
+    Packages,
+    Versions,
+}
+
+impl Target {
+    fn fields(self) -> &'static [&'static str] {
+        match self {
+            Target::Packages => &[
+                "name",
+                "description",
+                "homepage",
+                "repository",
+                "license",
+                "tags",
+                "platform",
+                "language",
+                "status",
+                "dependents_count",
+                "rank",
+                "created_at",
+            ],
+            Target::Versions => &[
+                "package",
+                "version",
+                "release_date",
+                "download_url",
+                "checksum",
+                "changelog",
+                "license",
+                "size",
+            ],
+        }
+    }
+
+    fn required(self) -> &'static [&'static str] {
+        match self {
+            Target::Packages => &["name"],
+            Target::Versions => &["package", "version", "release_date"],
+        }
+    }
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+fn default_list_separator() -> String {
+    ";".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Mapping {
+    pub target: Target,
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+    /// Separates items in list fields such as `tags`
+    #[serde(default = "default_list_separator")]
+    pub list_separator: String,
+    /// `chrono` format for dates that aren't RFC 3339, e.g. `%d/%m/%Y`
+    pub date_format: Option<String>,
+    /// Record field to CSV column header
+    #[serde(default)]
+    pub columns: HashMap<String, String>,
+    /// Values for fields that no column provides, or that are empty in a row
+    #[serde(default)]
+    pub defaults: HashMap<String, String>,
+}
+
+impl Mapping {
+    pub fn from_toml(text: &str) -> Result<Self> {
+        let mapping: Mapping = toml::from_str(text)?;
+        let fields = mapping.target.fields();
+        for field in mapping.columns.keys().chain(mapping.defaults.keys()) {
+            if !fields.contains(&field.as_str()) {
+                bail!(
+                    "Unknown field '{}' for {:?}. Valid fields: {}",
+                    field,
+                    mapping.target,
+                    fields.join(", ")
+                );
+            }
+        }
+        for field in mapping.target.required() {
+            if !mapping.columns.contains_key(*field) && !mapping.defaults.contains_key(*field) {
+                bail!("Required field '{}' has no column or default", field);
+            }
+        }
+        if !mapping.delimiter.is_ascii() {
+            bail!("Delimiter must be an ASCII character");
+        }
+        Ok(mapping)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RowError {
+    /// Line in the CSV file, counting the header as line 1
+    pub line: u64,
+    pub message: String,
+}
+
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub imported: usize,
+    /// Rows describing records that already exist
+    pub skipped: usize,
+    pub errors: Vec<RowError>,
+}
+
+/// A CSV row viewed through the mapping
+struct Row<'a> {
+    mapping: &'a Mapping,
+    header: &'a HashMap<String, usize>,
+    record: &'a csv::StringRecord,
+}
+
+impl Row<'_> {
+    fn get(&self, field: &str) -> Option<String> {
+        self.mapping
+            .columns
+            .get(field)
+            .and_then(|column| self.record.get(self.header[column]))
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .or_else(|| self.mapping.defaults.get(field).map(String::as_str))
+            .map(str::to_string)
+    }
+
+    fn required(&self, field: &str) -> Result<String> {
+        self.get(field)
+            .with_context(|| format!("'{}' is required", field))
+    }
+
+    fn list(&self, field: &str) -> Vec<String> {
+        self.get(field)
+            .map(|value| {
+                value
+                    .split(self.mapping.list_separator.as_str())
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn number<T: std::str::FromStr>(&self, field: &str) -> Result<Option<T>> {
+        self.get(field)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("'{}' is not a valid number: {}", field, value))
+            })
+            .transpose()
+    }
+
+    fn date(&self, field: &str) -> Result<Option<DateTime<Utc>>> {
+        let Some(value) = self.get(field) else {
+            return Ok(None);
+        };
+        if let Ok(date) = DateTime::parse_from_rfc3339(&value) {
+            return Ok(Some(date.with_timezone(&Utc)));
+        }
+        let format = self.mapping.date_format.as_deref().unwrap_or("%Y-%m-%d");
+        NaiveDateTime::parse_from_str(&value, format)
+            .or_else(|_| NaiveDate::parse_from_str(&value, format).map(|d| d.and_time(Default::default())))
+            .map(|date| Some(date.and_utc()))
+            .map_err(|_| anyhow::anyhow!("'{}' is not a valid date: {}", field, value))
+    }
+}
+
+fn package(row: &Row) -> Result<Package> {
+    let now = Utc::now();
+    let created_at = row.date("created_at")?.unwrap_or(now);
+    Ok(Package {
+        id: 0,
+        name: row.required("name")?,
+        description: row.get("description"),
+        homepage: row.get("homepage"),
+        repository: row.get("repository"),
+        license: row.get("license"),
+        tags: row.list("tags"),
+        created_at,
+        updated_at: created_at,
+        platform: row.get("platform"),
+        language: row.get("language"),
+        status: row.get("status"),
+        dependents_count: row.number("dependents_count")?,
+        rank: row.number("rank")?,
+    })
+}
+
+fn version(db: &dyn Storage, row: &Row) -> Result<PackageVersion> {
+    let name = row.required("package")?;
+    let package = db
+        .find_package(&name)?
+        .with_context(|| format!("Unknown package: {}", name))?;
+    Ok(PackageVersion {
+        id: 0,
+        package_id: package.id,
+        version: row.required("version")?,
+        release_date: row.date("release_date")?.context("'release_date' is required")?,
+        download_url: row.get("download_url"),
+        checksum: row.get("checksum"),
+        dependencies: Vec::new(),
+        vulnerabilities: Vec::new(),
+        changelog: row.get("changelog"),
+        created_at: Utc::now(),
+        license: row.get("license"),
+        size: row.number("size")?,
+    })
+}
+
+/// Read every row of `input`, then insert the valid ones. Nothing is written
+/// if any row is invalid, unless `skip_invalid` is set.
+pub fn import(
+    db: &dyn Storage,
+    mapping: &Mapping,
+    input: impl Read,
+    skip_invalid: bool,
+) -> Result<ImportReport> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(mapping.delimiter as u8)
+        .flexible(true)
+        .from_reader(input);
+
+    let header: HashMap<String, usize> = reader
+        .headers()?
+        .iter()
+        .enumerate()
+        .map(|(i, column)| (column.trim().to_string(), i))
+        .collect();
+    for column in mapping.columns.values() {
+        if !header.contains_key(column) {
+            bail!("Column '{}' is not in the CSV header", column);
+        }
+    }
+
+    let mut report = ImportReport::default();
+    let mut packages = Vec::new();
+    let mut versions = Vec::new();
+    let mut seen = HashSet::new();
+    let mut existing_versions: HashMap<u64, HashSet<String>> = HashMap::new();
+
+    for (index, record) in reader.records().enumerate() {
+        // Header is line 1
+        let line = index as u64 + 2;
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                report.errors.push(RowError {
+                    line,
+                    message: e.to_string(),
+                });
+                continue;
+            }
+        };
+        let row = Row {
+            mapping,
+            header: &header,
+            record: &record,
+        };
+
+        let result = match mapping.target {
+            Target::Packages => package(&row).and_then(|package| {
+                if !seen.insert(package.name.clone())
+                    || db.get_package_by_name(&package.name)?.is_some()
+                {
+                    report.skipped += 1;
+                } else {
+                    packages.push(package);
+                }
+                Ok(())
+            }),
+            Target::Versions => version(db, &row).and_then(|version| {
+                let existing = match existing_versions.entry(version.package_id) {
+                    std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                    std::collections::hash_map::Entry::Vacant(entry) => entry.insert(
+                        db.get_versions_by_package(version.package_id)?
+                            .into_iter()
+                            .map(|v| v.version)
+                            .collect(),
+                    ),
+                };
+                if existing.insert(version.version.clone()) {
+                    versions.push(version);
+                } else {
+                    report.skipped += 1;
+                }
+                Ok(())
+            }),
+        };
+        if let Err(e) = result {
+            report.errors.push(RowError {
+                line,
+                message: e.to_string(),
+            });
+        }
+    }
+
+    if !report.errors.is_empty() && !skip_invalid {
+        return Ok(report);
+    }
+    report.imported = packages.len() + versions.len();
+    if !packages.is_empty() {
+        db.insert_packages(packages)?;
+    }
+    if !versions.is_empty() {
+        db.insert_versions(versions)?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    #[test]
+    fn test_import() {
+        let db = Database::in_memory().unwrap();
+        db.insert_package(crate::fixtures::package("serde")).unwrap();
+
+        let mapping = Mapping::from_toml(
+            r#"
+            target = "packages"
+            [columns]
+            name = "Component"
+            license = "License"
+            tags = "Labels"
+            created_at = "Added"
+            [defaults]
+            platform = "crates.io"
+            "#,
+        )
+        .unwrap();
+        let csv = "Component,License,Labels,Added\n\
+                   tokio,MIT,async; runtime,2016-08-01\n\
+                   ,MIT,,\n\
+                   serde,MIT,,\n\
+                   rand,MIT,,yesterday\n";
+
+        let report = import(&db, &mapping, csv.as_bytes(), false).unwrap();
+        assert_eq!(
+            report.errors,
+            [
+                RowError {
+                    line: 3,
+                    message: "'name' is required".to_string()
+                },
+                RowError {
+                    line: 5,
+                    message: "'created_at' is not a valid date: yesterday".to_string()
+                },
+            ]
+        );
+        assert_eq!(report.imported, 0);
+        assert!(db.get_package_by_name("tokio").unwrap().is_none());
+
+        let report = import(&db, &mapping, csv.as_bytes(), true).unwrap();
+        assert_eq!((report.imported, report.skipped), (1, 1));
+        let tokio = db.get_package_by_name("tokio").unwrap().unwrap();
+        assert_eq!(tokio.tags, ["async", "runtime"]);
+        assert_eq!(tokio.platform.as_deref(), Some("crates.io"));
+
+        assert!(Mapping::from_toml("target = \"versions\"\n[columns]\nversion = \"v\"").is_err());
+        assert!(Mapping::from_toml("target = \"packages\"\n[columns]\nnmae = \"n\"").is_err());
+    }
+}
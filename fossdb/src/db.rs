@@ -4,24 +4,14 @@ use once_cell::sync::Lazy;
 use std::sync::Arc;
 
 use crate::id_generator::IdGenerator;
+use crate::name_filter::NameFilter;
+use crate::storage::Storage;
 use crate::*;
 
 // Macro for generating insert methods
 macro_rules! impl_insert {
     ($method:ident, $type:ty, $id_gen:ident) => {
-        pub fn $method(&self, mut entity: $type) -> Result<$type> {
-            if entity.id == 0 {
-                entity.id = self.$id_gen.next();
-            }
-            let rw = self.db.rw_transaction()?;
-            rw.insert(entity.clone())?;
-            rw.commit()?;
-            Ok(entity)
-        }
-    };
-    (#[allow(dead_code)] $method:ident, $type:ty, $id_gen:ident) => {
-        #[allow(dead_code)]
-        pub fn $method(&self, mut entity: $type) -> Result<$type> {
+        fn $method(&self, mut entity: $type) -> Result<$type> {
             if entity.id == 0 {
                 entity.id = self.$id_gen.next();
             }
@@ -36,14 +26,7 @@ macro_rules! impl_insert {
 // Macro for generating get by ID methods
 macro_rules! impl_get {
     ($method:ident, $type:ty) => {
-        pub fn $method(&self, id: u64) -> Result<Option<$type>> {
-            let r = self.db.r_transaction()?;
-            Ok(r.get().primary(id)?)
-        }
-    };
-    (#[allow(dead_code)] $method:ident, $type:ty) => {
-        #[allow(dead_code)]
-        pub fn $method(&self, id: u64) -> Result<Option<$type>> {
+        fn $method(&self, id: u64) -> Result<Option<$type>> {
             let r = self.db.r_transaction()?;
             Ok(r.get().primary(id)?)
         }
@@ -53,7 +36,7 @@ macro_rules! impl_get {
 // Macro for generating get all methods
 macro_rules! impl_get_all {
     ($method:ident, $type:ty) => {
-        pub fn $method(&self) -> Result<Vec<$type>> {
+        fn $method(&self) -> Result<Vec<$type>> {
             let r = self.db.r_transaction()?;
             let all: Vec<$type> = r.scan().primary()?.all()?.collect::<Result<Vec<_>, _>>()?;
             Ok(all)
@@ -61,10 +44,23 @@ macro_rules! impl_get_all {
     };
 }
 
+// Macro for generating streaming scan methods
+macro_rules! impl_for_each {
+    ($method:ident, $type:ty) => {
+        fn $method(&self, f: &mut dyn FnMut($type) -> Result<()>) -> Result<()> {
+            let r = self.db.r_transaction()?;
+            for record in r.scan().primary()?.all()? {
+                f(record?)?;
+            }
+            Ok(())
+        }
+    };
+}
+
 // Macro for generating update methods
 macro_rules! impl_update {
     ($method:ident, $type:ty) => {
-        pub fn $method(&self, entity: $type) -> Result<()> {
+        fn $method(&self, entity: $type) -> Result<()> {
             let rw = self.db.rw_transaction()?;
             if let Some(old) = rw.get().primary::<$type>(entity.id)? {
                 rw.remove(old)?;
@@ -76,6 +72,315 @@ macro_rules! impl_update {
     };
 }
 
+// Macro for generating batch insert methods that write in a single transaction
+macro_rules! impl_insert_batch {
+    ($method:ident, $type:ty, $id_gen:ident) => {
+        fn $method(&self, entities: Vec<$type>) -> Result<Vec<$type>> {
+            let rw = self.db.rw_transaction()?;
+            let mut saved = Vec::with_capacity(entities.len());
+            for mut entity in entities {
+                if entity.id == 0 {
+                    entity.id = self.$id_gen.next();
+                }
+                rw.insert(entity.clone())?;
+                saved.push(entity);
+            }
+            rw.commit()?;
+            Ok(saved)
+        }
+    };
+}
+
+// Macro for generating batch update methods that write in a single transaction
+macro_rules! impl_update_batch {
+    ($method:ident, $type:ty) => {
+        fn $method(&self, entities: Vec<$type>) -> Result<()> {
+            let rw = self.db.rw_transaction()?;
+            for entity in entities {
+                if let Some(old) = rw.get().primary::<$type>(entity.id)? {
+                    rw.remove(old)?;
+                }
+                rw.insert(entity)?;
+            }
+            rw.commit()?;
+            Ok(())
+        }
+    };
+}
+
+// Macro for generating delete methods, returns whether the entity existed
+macro_rules! impl_delete {
+    ($method:ident, $type:ty) => {
+        fn $method(&self, id: u64) -> Result<bool> {
+            let rw = self.db.rw_transaction()?;
+            let existed = match rw.get().primary::<$type>(id)? {
+                Some(old) => {
+                    rw.remove(old)?;
+                    true
+                }
+                None => false,
+            };
+            rw.commit()?;
+            Ok(existed)
+        }
+    };
+}
+
+/// Attempts made by [`Database::modify`] before giving up on a contended record
+const MAX_MODIFY_ATTEMPTS: usize = 5;
+
+/// A record changed between being read and written
+#[derive(Debug)]
+pub struct Conflict;
+
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "record was modified concurrently")
+    }
+}
+
+impl std::error::Error for Conflict {}
+
+/// Models that support optimistic updates through [`Database::compare_and_swap`]
+pub trait Record: ToInput + Clone + PartialEq {
+    fn id(&self) -> u64;
+
+    /// Replace the stored row inside a write transaction, maintaining any
+    /// tables derived from it
+    fn replace(rw: &transaction::RwTransaction, old: Option<Self>, new: Self) -> Result<()> {
+        if let Some(old) = old {
+            rw.remove(old)?;
+        }
+        rw.insert(new)?;
+        Ok(())
+    }
+}
+
+impl Record for Package {
+    fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl Record for User {
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn replace(rw: &transaction::RwTransaction, old: Option<Self>, new: Self) -> Result<()> {
+        adjust_subscriber_counts(rw, old.as_ref(), Some(&new))?;
+        if old.as_ref().is_some_and(|old| old.notifications_enabled) && !new.notifications_enabled {
+            clear_pending_notifications(rw, new.id)?;
+        }
+        if let Some(old) = old {
+            for edge in SubscriptionEdge::from_user(&old) {
+                rw.remove(edge)?;
+            }
+            rw.remove(old)?;
+        }
+        for edge in SubscriptionEdge::from_user(&new) {
+            rw.insert(edge)?;
+        }
+        rw.insert(new)?;
+        Ok(())
+    }
+}
+
+impl Record for Project {
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn replace(rw: &transaction::RwTransaction, old: Option<Self>, new: Self) -> Result<()> {
+        if let Some(old) = old {
+            remove_project(rw, old)?;
+        }
+        for edge in ProjectDependency::from_project(&new) {
+            rw.insert(edge)?;
+        }
+        rw.insert(new)?;
+        Ok(())
+    }
+}
+
+/// Remove a project along with its dependency index entries and sync
+fn remove_project(rw: &transaction::RwTransaction, project: Project) -> Result<()> {
+    for edge in ProjectDependency::from_project(&project) {
+        rw.remove(edge)?;
+    }
+    if let Some(sync) = rw.get().primary::<ProjectSync>(project.id)? {
+        rw.remove(sync)?;
+    }
+    for ack in advisory_acks(rw, AdvisoryAckKey::user_id, project.user_id)? {
+        if ack.project_id == Some(project.id) {
+            rw.remove(ack)?;
+        }
+    }
+    rw.remove(project)?;
+    Ok(())
+}
+
+/// Remove a user's projects, GitHub installations, advisory acknowledgements
+/// and personal timeline
+fn remove_user_records(rw: &transaction::RwTransaction, user_id: u64) -> Result<()> {
+    let projects: Vec<Project> = rw
+        .scan()
+        .secondary(ProjectKey::user_id)?
+        .start_with(user_id)?
+        .collect::<Result<Vec<_>, _>>()?;
+    for project in projects {
+        remove_project(rw, project)?;
+    }
+    let installations: Vec<GithubInstallation> = rw
+        .scan()
+        .secondary(GithubInstallationKey::user_id)?
+        .start_with(user_id)?
+        .collect::<Result<Vec<_>, _>>()?;
+    for installation in installations {
+        remove_github_installation(rw, installation)?;
+    }
+    for ack in advisory_acks(rw, AdvisoryAckKey::user_id, user_id)? {
+        rw.remove(ack)?;
+    }
+    remove_personal_timeline(rw, user_id)?;
+    Ok(())
+}
+
+/// Acknowledgements by a user or of a vulnerability, depending on `key`
+fn advisory_acks(
+    rw: &transaction::RwTransaction,
+    key: AdvisoryAckKey,
+    id: u64,
+) -> Result<Vec<AdvisoryAck>> {
+    Ok(rw
+        .scan()
+        .secondary(key)?
+        .start_with(id)?
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Remove an installation along with the project syncs through it
+fn remove_github_installation(
+    rw: &transaction::RwTransaction,
+    installation: GithubInstallation,
+) -> Result<()> {
+    let syncs: Vec<ProjectSync> = rw
+        .scan()
+        .secondary(ProjectSyncKey::installation_id)?
+        .start_with(installation.id)?
+        .collect::<Result<Vec<_>, _>>()?;
+    for sync in syncs {
+        rw.remove(sync)?;
+    }
+    rw.remove(installation)?;
+    Ok(())
+}
+
+impl Record for TimelineEvent {
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn replace(rw: &transaction::RwTransaction, old: Option<Self>, new: Self) -> Result<()> {
+        if let Some(old) = old {
+            remove_timeline_event(rw, old)?;
+        }
+        insert_timeline_event(rw, new)
+    }
+}
+
+/// Whether `event` should be queued for email, which it isn't for users who
+/// turned notifications off or no longer exist
+fn queues_notification(rw: &transaction::RwTransaction, event: &TimelineEvent) -> Result<bool> {
+    let Some(user_id) = event.user_id.filter(|_| event.awaits_notification()) else {
+        return Ok(false);
+    };
+    Ok(rw
+        .get()
+        .primary::<User>(user_id)?
+        .is_some_and(|user| user.notifications_enabled))
+}
+
+/// Drop every notification still queued for a user
+fn clear_pending_notifications(rw: &transaction::RwTransaction, user_id: u64) -> Result<()> {
+    let events: Vec<TimelineEvent> = rw
+        .scan()
+        .secondary(TimelineEventKey::user_id)?
+        .start_with(Some(user_id))?
+        .collect::<Result<Vec<_>, _>>()?;
+    for event in events {
+        if let Some(pending) = rw.get().primary::<PendingNotification>(event.id)? {
+            rw.remove(pending)?;
+        }
+    }
+    Ok(())
+}
+
+/// Store an event along with its notification and dedup index entries
+fn insert_timeline_event(rw: &transaction::RwTransaction, event: TimelineEvent) -> Result<()> {
+    if queues_notification(rw, &event)? {
+        rw.insert(PendingNotification { event_id: event.id })?;
+    }
+    if let Some(key) = event.dedup_key() {
+        rw.upsert(TimelineDedup {
+            key,
+            event_id: event.id,
+        })?;
+    }
+    rw.insert(event)?;
+    Ok(())
+}
+
+/// Remove an event along with its index entries
+fn remove_timeline_event(rw: &transaction::RwTransaction, event: TimelineEvent) -> Result<()> {
+    if let Some(pending) = rw.get().primary::<PendingNotification>(event.id)? {
+        rw.remove(pending)?;
+    }
+    if let Some(key) = event.dedup_key()
+        && let Some(dedup) = rw.get().primary::<TimelineDedup>(key)?
+        && dedup.event_id == event.id
+    {
+        rw.remove(dedup)?;
+    }
+    rw.remove(event)?;
+    Ok(())
+}
+
+/// Remove every event on a user's personal timeline, returning how many
+fn remove_personal_timeline(rw: &transaction::RwTransaction, user_id: u64) -> Result<usize> {
+    let events: Vec<TimelineEvent> = rw
+        .scan()
+        .secondary(TimelineEventKey::user_id)?
+        .start_with(Some(user_id))?
+        .collect::<Result<Vec<_>, _>>()?;
+    let count = events.len();
+    for event in events {
+        remove_timeline_event(rw, event)?;
+    }
+    Ok(count)
+}
+
+/// Apply a one-off upgrade unless it's already been recorded as applied
+fn migrate_once(
+    db: &native_db::Database,
+    name: &str,
+    upgrade: impl FnOnce(&transaction::RwTransaction) -> Result<()>,
+) -> Result<()> {
+    let rw = db.rw_transaction()?;
+    if rw
+        .get()
+        .primary::<AppliedMigration>(name.to_string())?
+        .is_none()
+    {
+        upgrade(&rw)?;
+        rw.insert(AppliedMigration {
+            name: name.to_string(),
+        })?;
+    }
+    rw.commit()?;
+    Ok(())
+}
+
 // Macro for finding max ID
 macro_rules! find_max_id {
     ($tx:expr, $type:ty) => {
@@ -88,13 +393,78 @@ macro_rules! find_max_id {
     };
 }
 
+/// Apply the change in a user's subscriptions to the per-package counters
+fn adjust_subscriber_counts(
+    rw: &transaction::RwTransaction,
+    old: Option<&User>,
+    new: Option<&User>,
+) -> Result<()> {
+    let names = |user: Option<&User>| -> std::collections::HashSet<String> {
+        user.iter()
+            .flat_map(|u| u.subscriptions.iter().map(|s| s.package_name.clone()))
+            .collect()
+    };
+    let (old, new) = (names(old), names(new));
+
+    for (name, delta) in old
+        .difference(&new)
+        .map(|n| (n, -1i64))
+        .chain(new.difference(&old).map(|n| (n, 1)))
+    {
+        let current = rw.get().primary::<SubscriberCount>(name.clone())?;
+        let count = current
+            .as_ref()
+            .map_or(0, |c| c.count)
+            .saturating_add_signed(delta);
+        if let Some(current) = current {
+            rw.remove(current)?;
+        }
+        if count > 0 {
+            rw.insert(SubscriberCount {
+                package_name: name.clone(),
+                count,
+            })?;
+        }
+    }
+    Ok(())
+}
+
 static MODELS: Lazy<Models> = Lazy::new(|| {
     let mut models = Models::new();
     models.define::<Package>().unwrap();
+    models.define::<PackageVersionV1>().unwrap();
     models.define::<PackageVersion>().unwrap();
+    models.define::<UserV1>().unwrap();
+    models.define::<UserV2>().unwrap();
+    models.define::<UserV3>().unwrap();
+    models.define::<UserV4>().unwrap();
     models.define::<User>().unwrap();
+    models.define::<VulnerabilityV1>().unwrap();
+    models.define::<VulnerabilityV2>().unwrap();
     models.define::<Vulnerability>().unwrap();
     models.define::<TimelineEvent>().unwrap();
+    models.define::<PendingNotification>().unwrap();
+    models.define::<TimelineDedup>().unwrap();
+    models.define::<AppliedMigration>().unwrap();
+    models.define::<SubscriptionEdge>().unwrap();
+    models.define::<SubscriberCount>().unwrap();
+    models.define::<Lease>().unwrap();
+    models.define::<CpeMapping>().unwrap();
+    models.define::<Maintainer>().unwrap();
+    models.define::<PackageMaintainer>().unwrap();
+    models.define::<PackageAlias>().unwrap();
+    models.define::<PackageReplacement>().unwrap();
+    models.define::<PackageRank>().unwrap();
+    models.define::<DailyRollup>().unwrap();
+    models.define::<AccountActivity>().unwrap();
+    models.define::<QuarantinedPackage>().unwrap();
+    models.define::<PendingPackage>().unwrap();
+    models.define::<CollectorRun>().unwrap();
+    models.define::<Project>().unwrap();
+    models.define::<ProjectDependency>().unwrap();
+    models.define::<GithubInstallation>().unwrap();
+    models.define::<ProjectSync>().unwrap();
+    models.define::<AdvisoryAck>().unwrap();
     models
 });
 
@@ -103,15 +473,88 @@ pub struct Database {
     package_ids: Arc<IdGenerator>,
     version_ids: Arc<IdGenerator>,
     user_ids: Arc<IdGenerator>,
-    #[allow(dead_code)]
     vulnerability_ids: Arc<IdGenerator>,
     timeline_ids: Arc<IdGenerator>,
+    cpe_mapping_ids: Arc<IdGenerator>,
+    maintainer_ids: Arc<IdGenerator>,
+    quarantine_ids: Arc<IdGenerator>,
+    collector_run_ids: Arc<IdGenerator>,
+    project_ids: Arc<IdGenerator>,
+    advisory_ack_ids: Arc<IdGenerator>,
+    package_names: Arc<NameFilter>,
 }
 
 impl Database {
     pub fn new(path: &str) -> Result<Self> {
         // Open or create database using static MODELS
-        let db = Builder::new().create(&MODELS, path)?;
+        Self::open(Builder::new().create(&MODELS, path)?)
+    }
+
+    /// A database that lives only as long as this value, for tests
+    pub fn in_memory() -> Result<Self> {
+        Self::open(Builder::new().create_in_memory(&MODELS)?)
+    }
+
+    fn open(db: native_db::Database<'static>) -> Result<Self> {
+        // Upgrade any rows stored with older model versions
+        let rw = db.rw_transaction()?;
+        rw.migrate::<PackageVersion>()?;
+        rw.migrate::<Vulnerability>()?;
+        rw.migrate::<User>()?;
+        rw.commit()?;
+
+        // Rebuild the pending notification index for databases that predate
+        // it, or that still queue events for users who opted out
+        migrate_once(&db, "pending_notifications", |rw| {
+            let stale: Vec<PendingNotification> = rw
+                .scan()
+                .primary::<PendingNotification>()?
+                .all()?
+                .collect::<Result<Vec<_>, _>>()?;
+            for pending in stale {
+                rw.remove(pending)?;
+            }
+            for event in rw.scan().primary::<TimelineEvent>()?.all()? {
+                let event = event?;
+                if queues_notification(rw, &event)? {
+                    rw.insert(PendingNotification { event_id: event.id })?;
+                }
+            }
+            Ok(())
+        })?;
+
+        // Index the events already stored by their dedup key
+        migrate_once(&db, "timeline_dedup", |rw| {
+            for event in rw.scan().primary::<TimelineEvent>()?.all()? {
+                let event = event?;
+                if let Some(key) = event.dedup_key() {
+                    rw.upsert(TimelineDedup {
+                        key,
+                        event_id: event.id,
+                    })?;
+                }
+            }
+            Ok(())
+        })?;
+
+        // Likewise for the subscription index and counters
+        let rw = db.rw_transaction()?;
+        let build_edges = rw.len().primary::<SubscriptionEdge>()? == 0;
+        let build_counts = rw.len().primary::<SubscriberCount>()? == 0;
+        if build_edges || build_counts {
+            for user in rw.scan().primary::<User>()?.all()? {
+                let user = user?;
+                if build_edges {
+                    for edge in SubscriptionEdge::from_user(&user) {
+                        rw.insert(edge)?;
+                    }
+                }
+                if build_counts {
+                    adjust_subscriber_counts(&rw, None, Some(&user))?;
+                }
+            }
+        }
+        rw.commit()?;
 
         // Scan database to find highest IDs and initialize generators
         let r = db.r_transaction()?;
@@ -121,6 +564,17 @@ impl Database {
         let max_user_id = find_max_id!(r, User);
         let max_vulnerability_id = find_max_id!(r, Vulnerability);
         let max_timeline_id = find_max_id!(r, TimelineEvent);
+        let max_cpe_mapping_id = find_max_id!(r, CpeMapping);
+        let max_maintainer_id = find_max_id!(r, Maintainer);
+        let max_quarantine_id = find_max_id!(r, QuarantinedPackage);
+        let max_collector_run_id = find_max_id!(r, CollectorRun);
+        let max_project_id = find_max_id!(r, Project);
+        let max_advisory_ack_id = find_max_id!(r, AdvisoryAck);
+
+        let package_names = NameFilter::with_capacity(r.len().primary::<Package>()? as usize);
+        for package in r.scan().primary::<Package>()?.all()? {
+            package_names.insert(&package?.name);
+        }
 
         drop(r);
 
@@ -130,6 +584,12 @@ impl Database {
         let user_ids = Arc::new(IdGenerator::new(max_user_id + 1));
         let vulnerability_ids = Arc::new(IdGenerator::new(max_vulnerability_id + 1));
         let timeline_ids = Arc::new(IdGenerator::new(max_timeline_id + 1));
+        let cpe_mapping_ids = Arc::new(IdGenerator::new(max_cpe_mapping_id + 1));
+        let maintainer_ids = Arc::new(IdGenerator::new(max_maintainer_id + 1));
+        let quarantine_ids = Arc::new(IdGenerator::new(max_quarantine_id + 1));
+        let collector_run_ids = Arc::new(IdGenerator::new(max_collector_run_id + 1));
+        let project_ids = Arc::new(IdGenerator::new(max_project_id + 1));
+        let advisory_ack_ids = Arc::new(IdGenerator::new(max_advisory_ack_id + 1));
 
         Ok(Self {
             db,
@@ -138,36 +598,129 @@ impl Database {
             user_ids,
             vulnerability_ids,
             timeline_ids,
+            cpe_mapping_ids,
+            maintainer_ids,
+            quarantine_ids,
+            collector_run_ids,
+            project_ids,
+            advisory_ack_ids,
+            package_names: Arc::new(package_names),
         })
     }
 
+    // Package writes, wrapped below to keep `package_names` current
+    impl_insert!(insert_package_record, Package, package_ids);
+    impl_insert_batch!(insert_package_records, Package, package_ids);
+    impl_update_batch!(update_package_records, Package);
+
+    /// Unconditionally replace a record
+    fn write<T: Record>(&self, entity: T) -> Result<()> {
+        let rw = self.db.rw_transaction()?;
+        let old = rw.get().primary::<T>(entity.id())?;
+        T::replace(&rw, old, entity)?;
+        rw.commit()?;
+        Ok(())
+    }
+
+    /// Replace a record only if the stored copy still equals `expected`,
+    /// failing with [`Conflict`] if someone else wrote it first
+    pub fn compare_and_swap<T: Record>(&self, expected: &T, new: T) -> Result<()> {
+        let rw = self.db.rw_transaction()?;
+        let current = rw.get().primary::<T>(expected.id())?;
+        if current.as_ref() != Some(expected) {
+            return Err(Conflict.into());
+        }
+        T::replace(&rw, current, new)?;
+        rw.commit()?;
+        Ok(())
+    }
+
+    /// Apply `f` to the stored record and write it back, retrying from a fresh
+    /// read if the record changes in between. `f` may run more than once.
+    ///
+    /// Returns the updated record, or None if it doesn't exist.
+    pub fn modify<T: Record>(&self, id: u64, mut f: impl FnMut(&mut T)) -> Result<Option<T>> {
+        for _ in 0..MAX_MODIFY_ATTEMPTS {
+            let current = {
+                let r = self.db.r_transaction()?;
+                r.get().primary::<T>(id)?
+            };
+            let Some(current) = current else {
+                return Ok(None);
+            };
+
+            let mut updated = current.clone();
+            f(&mut updated);
+            if updated == current {
+                return Ok(Some(current));
+            }
+
+            match self.compare_and_swap(&current, updated.clone()) {
+                Ok(()) => return Ok(Some(updated)),
+                Err(e) if e.is::<Conflict>() => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(Conflict.into())
+    }
+}
+
+impl Storage for Database {
     // Package operations
-    impl_insert!(insert_package, Package, package_ids);
+    // Names are added to the filter before they're written, so it never
+    // misses a stored package
+    fn insert_package(&self, package: Package) -> Result<Package> {
+        self.package_names.insert(&package.name);
+        self.insert_package_record(package)
+    }
     impl_get!(get_package, Package);
 
-    pub fn get_package_by_name(&self, name: &str) -> Result<Option<Package>> {
+    fn get_package_by_name(&self, name: &str) -> Result<Option<Package>> {
         let r = self.db.r_transaction()?;
-        let results: Vec<Package> = r
+        Ok(r.get().secondary(PackageKey::name, name)?)
+    }
+
+    fn find_packages_with_prefix(&self, prefix: &str, limit: usize) -> Result<Vec<Package>> {
+        let r = self.db.r_transaction()?;
+        let packages: Vec<Package> = r
             .scan()
             .secondary(PackageKey::name)?
-            .start_with(name)?
+            .start_with(prefix)?
+            .take(limit)
             .collect::<Result<Vec<_>, _>>()?;
+        Ok(packages)
+    }
 
-        Ok(results.into_iter().next())
+    fn may_contain_package(&self, name: &str) -> bool {
+        self.package_names.may_contain(name)
     }
 
-    impl_get_all!(get_all_packages, Package);
-    impl_update!(update_package, Package);
+    impl_for_each!(for_each_package, Package);
+    fn update_package(&self, package: Package) -> Result<()> {
+        self.package_names.insert(&package.name);
+        self.write(package)
+    }
+    fn insert_packages(&self, packages: Vec<Package>) -> Result<Vec<Package>> {
+        for package in &packages {
+            self.package_names.insert(&package.name);
+        }
+        self.insert_package_records(packages)
+    }
+    fn update_packages(&self, packages: Vec<Package>) -> Result<()> {
+        for package in &packages {
+            self.package_names.insert(&package.name);
+        }
+        self.update_package_records(packages)
+    }
 
     // PackageVersion operations
     impl_insert!(insert_version, PackageVersion, version_ids);
-    impl_get!(
-        #[allow(dead_code)]
-        get_version,
-        PackageVersion
-    );
+    impl_insert_batch!(insert_versions, PackageVersion, version_ids);
+    impl_update_batch!(update_versions, PackageVersion);
+    impl_get!(get_version, PackageVersion);
 
-    pub fn get_versions_by_package(&self, package_id: u64) -> Result<Vec<PackageVersion>> {
+    fn get_versions_by_package(&self, package_id: u64) -> Result<Vec<PackageVersion>> {
         let r = self.db.r_transaction()?;
         let versions: Vec<PackageVersion> = r
             .scan()
@@ -177,63 +730,137 @@ impl Database {
         Ok(versions)
     }
 
-    impl_get_all!(get_all_versions, PackageVersion);
+    impl_for_each!(for_each_version, PackageVersion);
 
     // User operations
-    impl_insert!(insert_user, User, user_ids);
+
+    /// Insert a user along with their subscription index entries
+    fn insert_user(&self, mut user: User) -> Result<User> {
+        if user.id == 0 {
+            user.id = self.user_ids.next();
+        }
+        let rw = self.db.rw_transaction()?;
+        User::replace(&rw, None, user.clone())?;
+        rw.commit()?;
+        Ok(user)
+    }
+
     impl_get!(get_user, User);
 
-    pub fn get_user_by_email(&self, email: &str) -> Result<Option<User>> {
+    fn get_user_by_email(&self, email: &str) -> Result<Option<User>> {
         let r = self.db.r_transaction()?;
-        let results: Vec<User> = r
-            .scan()
-            .secondary(UserKey::email)?
-            .start_with(email)?
-            .collect::<Result<Vec<_>, _>>()?;
-
-        Ok(results.into_iter().next())
+        Ok(r.get().secondary(UserKey::email, email)?)
     }
 
-    #[allow(dead_code)]
-    pub fn get_user_by_username(&self, username: &str) -> Result<Option<User>> {
+    fn get_user_by_username(&self, username: &str) -> Result<Option<User>> {
         let r = self.db.r_transaction()?;
-        let results: Vec<User> = r
-            .scan()
-            .secondary(UserKey::username)?
-            .start_with(username)?
-            .collect::<Result<Vec<_>, _>>()?;
+        Ok(r.get().secondary(UserKey::username, username)?)
+    }
 
-        Ok(results.into_iter().next())
+    impl_for_each!(for_each_user, User);
+
+    /// Update a user, rewriting their subscription index entries
+    fn update_user(&self, user: User) -> Result<()> {
+        self.write(user)
     }
 
-    impl_get_all!(get_all_users, User);
-    impl_update!(update_user, User);
+    fn delete_user(&self, id: u64) -> Result<bool> {
+        let rw = self.db.rw_transaction()?;
+        let Some(user) = rw.get().primary::<User>(id)? else {
+            return Ok(false);
+        };
+        adjust_subscriber_counts(&rw, Some(&user), None)?;
+        for edge in SubscriptionEdge::from_user(&user) {
+            rw.remove(edge)?;
+        }
+        remove_user_records(&rw, id)?;
+        if let Some(activity) = rw.get().primary::<AccountActivity>(id)? {
+            rw.remove(activity)?;
+        }
+        rw.remove(user)?;
+        rw.commit()?;
+        Ok(true)
+    }
+
+    fn anonymize_user(&self, user: User) -> Result<bool> {
+        let rw = self.db.rw_transaction()?;
+        let Some(old) = rw.get().primary::<User>(user.id)? else {
+            return Ok(false);
+        };
+        remove_user_records(&rw, user.id)?;
+        User::replace(&rw, Some(old), user)?;
+        rw.commit()?;
+        Ok(true)
+    }
+
+    impl_get!(get_account_activity, AccountActivity);
+    impl_get_all!(get_all_account_activity, AccountActivity);
+
+    fn set_account_activity(&self, activity: AccountActivity) -> Result<()> {
+        let rw = self.db.rw_transaction()?;
+        rw.upsert(activity)?;
+        rw.commit()?;
+        Ok(())
+    }
 
     // Vulnerability operations
-    impl_insert!(
-        #[allow(dead_code)]
-        insert_vulnerability,
-        Vulnerability,
-        vulnerability_ids
-    );
-    impl_get!(
-        #[allow(dead_code)]
-        get_vulnerability,
-        Vulnerability
-    );
-    impl_get_all!(get_all_vulnerabilities, Vulnerability);
+    impl_insert!(insert_vulnerability, Vulnerability, vulnerability_ids);
+    impl_get!(get_vulnerability, Vulnerability);
+
+    fn get_vulnerabilities(&self, ids: &[u64]) -> Result<Vec<Vulnerability>> {
+        let r = self.db.r_transaction()?;
+        let mut vulnerabilities = Vec::new();
+        for &id in ids {
+            if let Some(vulnerability) = r.get().primary(id)? {
+                vulnerabilities.push(vulnerability);
+            }
+        }
+        Ok(vulnerabilities)
+    }
+    impl_for_each!(for_each_vulnerability, Vulnerability);
+    impl_update!(update_vulnerability, Vulnerability);
+
+    /// Delete a vulnerability along with its acknowledgements
+    fn delete_vulnerability(&self, id: u64) -> Result<bool> {
+        let rw = self.db.rw_transaction()?;
+        let Some(vulnerability) = rw.get().primary::<Vulnerability>(id)? else {
+            return Ok(false);
+        };
+        for ack in advisory_acks(&rw, AdvisoryAckKey::vulnerability_id, id)? {
+            rw.remove(ack)?;
+        }
+        rw.remove(vulnerability)?;
+        rw.commit()?;
+        Ok(true)
+    }
 
     // TimelineEvent operations
-    impl_insert!(insert_timeline_event, TimelineEvent, timeline_ids);
-    impl_get!(
-        #[allow(dead_code)]
-        get_timeline_event,
-        TimelineEvent
-    );
-    impl_get_all!(get_all_timeline_events, TimelineEvent);
-
-    #[allow(dead_code)]
-    pub fn get_timeline_by_package(&self, package_id: u64) -> Result<Vec<TimelineEvent>> {
+
+    /// Insert a timeline event unless one already exists for the same package,
+    /// event type, version and user. Returns None for duplicates.
+    ///
+    /// Events without a version aren't tied to a release and are always inserted.
+    fn insert_timeline_event(&self, mut event: TimelineEvent) -> Result<Option<TimelineEvent>> {
+        let rw = self.db.rw_transaction()?;
+
+        if let Some(key) = event.dedup_key()
+            && rw.get().primary::<TimelineDedup>(key)?.is_some()
+        {
+            return Ok(None);
+        }
+
+        if event.id == 0 {
+            event.id = self.timeline_ids.next();
+        }
+        insert_timeline_event(&rw, event.clone())?;
+        rw.commit()?;
+        Ok(Some(event))
+    }
+
+    impl_get!(get_timeline_event, TimelineEvent);
+    impl_for_each!(for_each_timeline_event, TimelineEvent);
+
+    fn get_timeline_by_package(&self, package_id: u64) -> Result<Vec<TimelineEvent>> {
         let r = self.db.r_transaction()?;
         let events: Vec<TimelineEvent> = r
             .scan()
@@ -243,9 +870,12 @@ impl Database {
         Ok(events)
     }
 
-    impl_update!(update_timeline_event, TimelineEvent);
+    /// Update an event, keeping the pending notification index in sync
+    fn update_timeline_event(&self, event: TimelineEvent) -> Result<()> {
+        self.write(event)
+    }
 
-    pub fn get_timeline_events_by_user(&self, user_id: u64) -> Result<Vec<TimelineEvent>> {
+    fn get_timeline_events_by_user(&self, user_id: u64) -> Result<Vec<TimelineEvent>> {
         let r = self.db.r_transaction()?;
         let events: Vec<TimelineEvent> = r
             .scan()
@@ -255,38 +885,214 @@ impl Database {
         Ok(events)
     }
 
-    pub fn get_pending_notifications(&self) -> Result<Vec<TimelineEvent>> {
+    fn delete_timeline_events_by_user(&self, user_id: u64) -> Result<usize> {
+        let rw = self.db.rw_transaction()?;
+        let count = remove_personal_timeline(&rw, user_id)?;
+        rw.commit()?;
+        Ok(count)
+    }
+
+    fn get_pending_notifications(&self) -> Result<Vec<TimelineEvent>> {
         let r = self.db.r_transaction()?;
-        let all_events: Vec<TimelineEvent> =
-            r.scan().primary()?.all()?.collect::<Result<Vec<_>, _>>()?;
-        // Filter in memory - native_db doesn't support complex queries
-        // For better scalability, consider adding a compound index or separate pending_notifications table
-        Ok(all_events
-            .into_iter()
-            .filter(|e| {
-                e.user_id.is_some()
-                    && e.notified_at.is_none()
-                    && e.event_type == crate::EventType::NewRelease
-            })
-            .collect())
+        let mut events = Vec::new();
+        for pending in r.scan().primary::<PendingNotification>()?.all()? {
+            if let Some(event) = r.get().primary::<TimelineEvent>(pending?.event_id)? {
+                events.push(event);
+            }
+        }
+        Ok(events)
     }
 
-    pub fn get_users_subscribed_to(&self, package_name: &str) -> Result<Vec<u64>> {
-        let all_users = self.get_all_users()?;
-        Ok(all_users
+    fn get_subscriber_count(&self, package_name: &str) -> Result<u64> {
+        let r = self.db.r_transaction()?;
+        Ok(r
+            .get()
+            .primary::<SubscriberCount>(package_name)?
+            .map_or(0, |c| c.count))
+    }
+
+    fn get_users_subscribed_to(&self, package_name: &str) -> Result<Vec<u64>> {
+        let r = self.db.r_transaction()?;
+        let mut user_ids = Vec::new();
+        // Prefix scan, so names that merely start with this one are skipped
+        for edge in r
+            .scan()
+            .secondary::<SubscriptionEdge>(SubscriptionEdgeKey::package_name)?
+            .start_with(package_name)?
+        {
+            let edge = edge?;
+            if edge.package_name == package_name && edge.notifications_enabled {
+                user_ids.push(edge.user_id);
+            }
+        }
+        Ok(user_ids)
+    }
+
+    fn insert_project(&self, mut project: Project) -> Result<Project> {
+        if project.id == 0 {
+            project.id = self.project_ids.next();
+        }
+        let rw = self.db.rw_transaction()?;
+        Project::replace(&rw, None, project.clone())?;
+        rw.commit()?;
+        Ok(project)
+    }
+
+    impl_get!(get_project, Project);
+
+    fn get_projects_by_user(&self, user_id: u64) -> Result<Vec<Project>> {
+        let r = self.db.r_transaction()?;
+        let projects: Vec<Project> = r
+            .scan()
+            .secondary(ProjectKey::user_id)?
+            .start_with(user_id)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(projects)
+    }
+
+    /// Update a project, rewriting its dependency index entries
+    fn update_project(&self, project: Project) -> Result<()> {
+        self.write(project)
+    }
+
+    fn delete_project(&self, id: u64) -> Result<bool> {
+        let rw = self.db.rw_transaction()?;
+        let Some(project) = rw.get().primary::<Project>(id)? else {
+            return Ok(false);
+        };
+        remove_project(&rw, project)?;
+        rw.commit()?;
+        Ok(true)
+    }
+
+    fn get_projects_depending_on(&self, package_name: &str) -> Result<Vec<Project>> {
+        let r = self.db.r_transaction()?;
+        let mut projects = Vec::new();
+        // Prefix scan, so names that merely start with this one are skipped
+        for edge in r
+            .scan()
+            .secondary::<ProjectDependency>(ProjectDependencyKey::package_name)?
+            .start_with(package_name)?
+        {
+            let edge = edge?;
+            if edge.package_name == package_name
+                && let Some(project) = r.get().primary::<Project>(edge.project_id)?
+            {
+                projects.push(project);
+            }
+        }
+        Ok(projects)
+    }
+
+    fn upsert_github_installation(&self, installation: GithubInstallation) -> Result<()> {
+        let rw = self.db.rw_transaction()?;
+        rw.upsert(installation)?;
+        rw.commit()?;
+        Ok(())
+    }
+
+    fn get_github_installation(&self, id: u64) -> Result<Option<GithubInstallation>> {
+        let r = self.db.r_transaction()?;
+        Ok(r.get().primary(id)?)
+    }
+
+    fn get_github_installations_by_user(&self, user_id: u64) -> Result<Vec<GithubInstallation>> {
+        let r = self.db.r_transaction()?;
+        let installations: Vec<GithubInstallation> = r
+            .scan()
+            .secondary(GithubInstallationKey::user_id)?
+            .start_with(user_id)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(installations)
+    }
+
+    fn delete_github_installation(&self, id: u64) -> Result<bool> {
+        let rw = self.db.rw_transaction()?;
+        let Some(installation) = rw.get().primary::<GithubInstallation>(id)? else {
+            return Ok(false);
+        };
+        remove_github_installation(&rw, installation)?;
+        rw.commit()?;
+        Ok(true)
+    }
+
+    fn set_project_sync(&self, sync: ProjectSync) -> Result<()> {
+        let rw = self.db.rw_transaction()?;
+        rw.upsert(sync)?;
+        rw.commit()?;
+        Ok(())
+    }
+
+    fn get_project_sync(&self, project_id: u64) -> Result<Option<ProjectSync>> {
+        let r = self.db.r_transaction()?;
+        Ok(r.get().primary(project_id)?)
+    }
+
+    fn get_project_syncs_by_repository(&self, repository: &str) -> Result<Vec<ProjectSync>> {
+        let r = self.db.r_transaction()?;
+        let mut syncs = Vec::new();
+        // Prefix scan, so repositories that merely start with this one are skipped
+        for sync in r
+            .scan()
+            .secondary::<ProjectSync>(ProjectSyncKey::repository)?
+            .start_with(repository)?
+        {
+            let sync = sync?;
+            if sync.repository == repository {
+                syncs.push(sync);
+            }
+        }
+        Ok(syncs)
+    }
+
+    fn delete_project_sync(&self, project_id: u64) -> Result<bool> {
+        let rw = self.db.rw_transaction()?;
+        let existed = match rw.get().primary::<ProjectSync>(project_id)? {
+            Some(sync) => {
+                rw.remove(sync)?;
+                true
+            }
+            None => false,
+        };
+        rw.commit()?;
+        Ok(existed)
+    }
+
+    fn acknowledge_advisory(&self, mut ack: AdvisoryAck) -> Result<AdvisoryAck> {
+        let rw = self.db.rw_transaction()?;
+        let existing = advisory_acks(&rw, AdvisoryAckKey::user_id, ack.user_id)?
             .into_iter()
-            .filter(|u| {
-                u.subscriptions
-                    .iter()
-                    .any(|s| s.package_name == package_name && s.notifications_enabled)
-            })
-            .map(|u| u.id)
-            .collect())
+            .find(|existing| {
+                existing.vulnerability_id == ack.vulnerability_id
+                    && existing.project_id == ack.project_id
+            });
+        match existing {
+            Some(existing) => {
+                ack.id = existing.id;
+                rw.remove(existing)?;
+            }
+            None => ack.id = self.advisory_ack_ids.next(),
+        }
+        rw.insert(ack.clone())?;
+        rw.commit()?;
+        Ok(ack)
     }
 
+    fn get_advisory_acks_by_user(&self, user_id: u64) -> Result<Vec<AdvisoryAck>> {
+        let r = self.db.r_transaction()?;
+        let acks: Vec<AdvisoryAck> = r
+            .scan()
+            .secondary(AdvisoryAckKey::user_id)?
+            .start_with(user_id)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(acks)
+    }
+
+    impl_delete!(delete_advisory_ack, AdvisoryAck);
+
     /// Purge timeline events older than the specified duration
     /// Returns the number of events deleted
-    pub fn purge_old_timeline_events(&self, older_than: chrono::Duration) -> Result<usize> {
+    fn purge_old_timeline_events(&self, older_than: chrono::Duration) -> Result<usize> {
         use chrono::Utc;
 
         let cutoff_time = Utc::now() - older_than;
@@ -312,7 +1118,7 @@ impl Database {
             let rw = self.db.rw_transaction()?;
 
             for event in events_to_delete {
-                rw.remove(event)?;
+                remove_timeline_event(&rw, event)?;
             }
 
             rw.commit()?;
@@ -323,4 +1129,645 @@ impl Database {
 
         Ok(delete_count)
     }
+
+    fn modify_package(&self, id: u64, f: &mut dyn FnMut(&mut Package)) -> Result<Option<Package>> {
+        self.modify(id, |package: &mut Package| {
+            f(package);
+            self.package_names.insert(&package.name);
+        })
+    }
+
+    fn insert_package_alias(&self, alias: PackageAlias) -> Result<()> {
+        let rw = self.db.rw_transaction()?;
+        rw.upsert(alias)?;
+        rw.commit()?;
+        Ok(())
+    }
+
+    fn get_package_alias(&self, name: &str) -> Result<Option<PackageAlias>> {
+        let r = self.db.r_transaction()?;
+        Ok(r.get().primary(name)?)
+    }
+
+    fn get_package_aliases(&self, package_id: u64) -> Result<Vec<PackageAlias>> {
+        let r = self.db.r_transaction()?;
+        let aliases: Vec<PackageAlias> = r
+            .scan()
+            .secondary(PackageAliasKey::package_id)?
+            .start_with(package_id)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(aliases)
+    }
+
+    fn set_package_replacement(&self, replacement: PackageReplacement) -> Result<()> {
+        let rw = self.db.rw_transaction()?;
+        rw.upsert(replacement)?;
+        rw.commit()?;
+        Ok(())
+    }
+
+    fn get_package_replacement(&self, package_id: u64) -> Result<Option<PackageReplacement>> {
+        let r = self.db.r_transaction()?;
+        Ok(r.get().primary(package_id)?)
+    }
+
+    impl_delete!(delete_package_replacement, PackageReplacement);
+
+    impl_get!(get_package_rank, PackageRank);
+    impl_get_all!(get_all_package_ranks, PackageRank);
+
+    fn update_package_ranks(&self, ranks: Vec<PackageRank>) -> Result<()> {
+        let rw = self.db.rw_transaction()?;
+        for rank in ranks {
+            rw.upsert(rank)?;
+        }
+        rw.commit()?;
+        Ok(())
+    }
+
+    fn get_daily_rollups(&self, from: &str, to: &str) -> Result<Vec<DailyRollup>> {
+        let r = self.db.r_transaction()?;
+        let rollups = r
+            .scan()
+            .primary()?
+            .range(from.to_string()..=to.to_string())?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rollups)
+    }
+
+    fn add_daily_rollups(&self, rollups: Vec<DailyRollup>) -> Result<()> {
+        let rw = self.db.rw_transaction()?;
+        for rollup in rollups {
+            let total = match rw.get().primary::<DailyRollup>(rollup.date.clone())? {
+                Some(current) => DailyRollup {
+                    packages_added: current.packages_added + rollup.packages_added,
+                    versions_released: current.versions_released + rollup.versions_released,
+                    vulnerabilities_published: current.vulnerabilities_published
+                        + rollup.vulnerabilities_published,
+                    ..current
+                },
+                None => rollup,
+            };
+            rw.upsert(total)?;
+        }
+        rw.commit()?;
+        Ok(())
+    }
+
+    fn replace_daily_rollups(&self, rollups: Vec<DailyRollup>) -> Result<()> {
+        let rw = self.db.rw_transaction()?;
+        let existing: Vec<DailyRollup> = rw
+            .scan()
+            .primary()?
+            .all()?
+            .collect::<Result<Vec<_>, _>>()?;
+        for rollup in existing {
+            rw.remove(rollup)?;
+        }
+        for rollup in rollups {
+            rw.insert(rollup)?;
+        }
+        rw.commit()?;
+        Ok(())
+    }
+
+    fn modify_user(&self, id: u64, f: &mut dyn FnMut(&mut User)) -> Result<Option<User>> {
+        self.modify(id, f)
+    }
+
+    fn insert_cpe_mapping(&self, mut mapping: CpeMapping) -> Result<CpeMapping> {
+        let rw = self.db.rw_transaction()?;
+        if let Some(existing) = rw
+            .get()
+            .secondary::<CpeMapping>(CpeMappingKey::vendor_product, mapping.vendor_product.clone())?
+        {
+            if mapping.id == 0 {
+                mapping.id = existing.id;
+            }
+            rw.remove(existing)?;
+        }
+        if mapping.id == 0 {
+            mapping.id = self.cpe_mapping_ids.next();
+        }
+        rw.insert(mapping.clone())?;
+        rw.commit()?;
+        Ok(mapping)
+    }
+
+    fn get_cpe_mapping(&self, vendor_product: &str) -> Result<Option<CpeMapping>> {
+        let r = self.db.r_transaction()?;
+        Ok(r
+            .get()
+            .secondary(CpeMappingKey::vendor_product, vendor_product)?)
+    }
+
+    impl_get_all!(get_all_cpe_mappings, CpeMapping);
+    impl_delete!(delete_cpe_mapping, CpeMapping);
+
+    fn upsert_maintainers(&self, maintainers: Vec<Maintainer>) -> Result<Vec<Maintainer>> {
+        let rw = self.db.rw_transaction()?;
+        let mut saved = Vec::with_capacity(maintainers.len());
+        for mut maintainer in maintainers {
+            match rw
+                .get()
+                .secondary::<Maintainer>(MaintainerKey::handle, maintainer.handle.clone())?
+            {
+                Some(existing) => {
+                    maintainer.id = existing.id;
+                    maintainer.created_at = existing.created_at;
+                    if maintainer != existing {
+                        rw.update(existing, maintainer.clone())?;
+                    }
+                }
+                None => {
+                    maintainer.id = self.maintainer_ids.next();
+                    rw.insert(maintainer.clone())?;
+                }
+            }
+            saved.push(maintainer);
+        }
+        rw.commit()?;
+        Ok(saved)
+    }
+
+    impl_get!(get_maintainer, Maintainer);
+
+    fn get_maintainer_by_handle(&self, handle: &str) -> Result<Option<Maintainer>> {
+        let r = self.db.r_transaction()?;
+        Ok(r.get().secondary(MaintainerKey::handle, handle)?)
+    }
+
+    impl_get_all!(get_all_maintainers, Maintainer);
+
+    fn set_package_maintainers(&self, links: Vec<(u64, Vec<u64>)>) -> Result<()> {
+        let rw = self.db.rw_transaction()?;
+        for (package_id, maintainer_ids) in links {
+            let old = rw
+                .scan()
+                .secondary::<PackageMaintainer>(PackageMaintainerKey::package_id)?
+                .start_with(package_id)?
+                .collect::<Result<Vec<_>, _>>()?;
+            for link in old {
+                rw.remove(link)?;
+            }
+            for maintainer_id in maintainer_ids {
+                rw.upsert(PackageMaintainer {
+                    package_id,
+                    maintainer_id,
+                })?;
+            }
+        }
+        rw.commit()?;
+        Ok(())
+    }
+
+    fn get_package_maintainers(&self, package_id: u64) -> Result<Vec<Maintainer>> {
+        let r = self.db.r_transaction()?;
+        let mut maintainers = Vec::new();
+        for link in r
+            .scan()
+            .secondary::<PackageMaintainer>(PackageMaintainerKey::package_id)?
+            .start_with(package_id)?
+        {
+            if let Some(maintainer) = r.get().primary(link?.maintainer_id)? {
+                maintainers.push(maintainer);
+            }
+        }
+        Ok(maintainers)
+    }
+
+    fn get_packages_by_maintainer(&self, maintainer_id: u64) -> Result<Vec<Package>> {
+        let r = self.db.r_transaction()?;
+        let mut packages = Vec::new();
+        for link in r
+            .scan()
+            .secondary::<PackageMaintainer>(PackageMaintainerKey::maintainer_id)?
+            .start_with(maintainer_id)?
+        {
+            if let Some(package) = r.get().primary(link?.package_id)? {
+                packages.push(package);
+            }
+        }
+        Ok(packages)
+    }
+
+    fn quarantine_packages(&self, packages: Vec<QuarantinedPackage>) -> Result<()> {
+        let rw = self.db.rw_transaction()?;
+        for mut package in packages {
+            match rw
+                .get()
+                .secondary::<QuarantinedPackage>(QuarantinedPackageKey::name, package.name.clone())?
+            {
+                Some(existing) => {
+                    package.id = existing.id;
+                    package.admitted = existing.admitted;
+                    package.first_seen = existing.first_seen;
+                    rw.update(existing, package)?;
+                }
+                None => {
+                    package.id = self.quarantine_ids.next();
+                    rw.insert(package)?;
+                }
+            }
+        }
+        rw.commit()?;
+        Ok(())
+    }
+
+    impl_get!(get_quarantined_package, QuarantinedPackage);
+    impl_get_all!(get_all_quarantined_packages, QuarantinedPackage);
+    impl_update!(update_quarantined_package, QuarantinedPackage);
+    impl_delete!(delete_quarantined_package, QuarantinedPackage);
+
+    fn hold_packages(&self, package_ids: Vec<u64>) -> Result<()> {
+        let rw = self.db.rw_transaction()?;
+        let now = chrono::Utc::now();
+        for package_id in package_ids {
+            rw.upsert(PendingPackage {
+                package_id,
+                created_at: now,
+            })?;
+        }
+        rw.commit()?;
+        Ok(())
+    }
+
+    fn insert_pending_package(&self, mut package: Package) -> Result<Package> {
+        self.package_names.insert(&package.name);
+        if package.id == 0 {
+            package.id = self.package_ids.next();
+        }
+        let rw = self.db.rw_transaction()?;
+        rw.insert(package.clone())?;
+        rw.upsert(PendingPackage {
+            package_id: package.id,
+            created_at: chrono::Utc::now(),
+        })?;
+        rw.commit()?;
+        Ok(package)
+    }
+
+    impl_get_all!(get_pending_packages, PendingPackage);
+
+    fn is_package_pending(&self, package_id: u64) -> Result<bool> {
+        let r = self.db.r_transaction()?;
+        Ok(r.get().primary::<PendingPackage>(package_id)?.is_some())
+    }
+
+    fn approve_packages(&self, package_ids: Vec<u64>) -> Result<usize> {
+        let rw = self.db.rw_transaction()?;
+        let mut approved = 0;
+        for package_id in package_ids {
+            if let Some(pending) = rw.get().primary::<PendingPackage>(package_id)? {
+                rw.remove(pending)?;
+                approved += 1;
+            }
+        }
+        rw.commit()?;
+        Ok(approved)
+    }
+
+    impl_insert!(insert_collector_run, CollectorRun, collector_run_ids);
+
+    fn get_collector_runs(&self, collector: &str) -> Result<Vec<CollectorRun>> {
+        let r = self.db.r_transaction()?;
+        let mut runs = Vec::new();
+        // Keys are matched by prefix
+        for run in r
+            .scan()
+            .secondary::<CollectorRun>(CollectorRunKey::collector)?
+            .start_with(collector)?
+        {
+            let run = run?;
+            if run.collector == collector {
+                runs.push(run);
+            }
+        }
+        runs.sort_by_key(|run| run.id);
+        Ok(runs)
+    }
+
+    impl_get_all!(get_all_collector_runs, CollectorRun);
+
+    fn acquire_lease(&self, name: &str, holder: &str, ttl: chrono::Duration) -> Result<bool> {
+        let now = chrono::Utc::now();
+        let rw = self.db.rw_transaction()?;
+        if let Some(current) = rw.get().primary::<Lease>(name)? {
+            if current.holder != holder && current.expires_at > now {
+                return Ok(false);
+            }
+            rw.remove(current)?;
+        }
+        rw.insert(Lease {
+            name: name.to_string(),
+            holder: holder.to_string(),
+            expires_at: now + ttl,
+        })?;
+        rw.commit()?;
+        Ok(true)
+    }
+
+    fn release_lease(&self, name: &str, holder: &str) -> Result<()> {
+        let rw = self.db.rw_transaction()?;
+        if let Some(current) = rw.get().primary::<Lease>(name)?
+            && current.holder == holder
+        {
+            rw.remove(current)?;
+        }
+        rw.commit()?;
+        Ok(())
+    }
+
+    fn cleanup_expired_leases(&self) -> Result<usize> {
+        let now = chrono::Utc::now();
+        let rw = self.db.rw_transaction()?;
+        let expired = rw
+            .scan()
+            .primary::<Lease>()?
+            .all()?
+            .filter(|lease| lease.as_ref().map_or(true, |lease| lease.expires_at <= now))
+            .collect::<Result<Vec<_>, _>>()?;
+        let count = expired.len();
+        for lease in expired {
+            rw.remove(lease)?;
+        }
+        rw.commit()?;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_lookups_match_exact_names() {
+        let db = Database::in_memory().unwrap();
+        for name in ["serde_json", "serde", "serde_derive", "tokio"] {
+            db.insert_package(fixtures::package(name)).unwrap();
+        }
+        db.insert_user(fixtures::user("alice2", &[])).unwrap();
+
+        assert_eq!(db.get_package_by_name("serde").unwrap().unwrap().name, "serde");
+        assert!(db.get_package_by_name("serde_").unwrap().is_none());
+        assert!(db.get_user_by_username("alice").unwrap().is_none());
+        assert!(db.get_user_by_email("alice").unwrap().is_none());
+
+        let names = |prefix: &str, limit: usize| -> Vec<String> {
+            db.find_packages_with_prefix(prefix, limit)
+                .unwrap()
+                .into_iter()
+                .map(|p| p.name)
+                .collect()
+        };
+        assert_eq!(names("serde", 10), ["serde", "serde_derive", "serde_json"]);
+        assert_eq!(names("serde_", 1), ["serde_derive"]);
+        assert!(names("x", 10).is_empty());
+    }
+
+    #[test]
+    fn test_insert_pending_package() {
+        let db = Database::in_memory().unwrap();
+        let package = db
+            .insert_pending_package(fixtures::package("left-pad"))
+            .unwrap();
+
+        assert!(db.is_package_pending(package.id).unwrap());
+        assert!(db.may_contain_package("left-pad"));
+        assert!(
+            db.published(db.get_package(package.id).unwrap())
+                .unwrap()
+                .is_none()
+        );
+        db.approve_packages(vec![package.id]).unwrap();
+        assert_eq!(
+            db.published(db.get_package(package.id).unwrap()).unwrap(),
+            Some(package)
+        );
+    }
+
+    fn release(package_id: u64, user_id: Option<u64>, version: &str) -> TimelineEvent {
+        TimelineEvent {
+            id: 0,
+            package_id,
+            user_id,
+            event_type: EventType::NewRelease,
+            package_name: "serde".to_string(),
+            version: Some(version.to_string()),
+            message: String::new(),
+            metadata: None,
+            created_at: chrono::Utc::now(),
+            notified_at: None,
+        }
+    }
+
+    fn pending_ids(db: &Database) -> Vec<u64> {
+        db.get_pending_notifications()
+            .unwrap()
+            .into_iter()
+            .map(|e| e.id)
+            .collect()
+    }
+
+    #[test]
+    fn test_compare_and_swap() {
+        let db = Database::in_memory().unwrap();
+        let alice = db.insert_user(fixtures::user("alice", &[])).unwrap();
+
+        let renamed = User {
+            username: "alicia".to_string(),
+            ..alice.clone()
+        };
+        db.compare_and_swap(&alice, renamed.clone()).unwrap();
+        // The stored copy no longer matches what the caller last read
+        let stale = db.compare_and_swap(&alice, alice.clone()).unwrap_err();
+        assert!(stale.is::<Conflict>());
+        assert_eq!(db.get_user(alice.id).unwrap().unwrap(), renamed);
+    }
+
+    #[test]
+    fn test_modify_retries_after_conflict() {
+        let db = Database::in_memory().unwrap();
+        let alice = db.insert_user(fixtures::user("alice", &[])).unwrap();
+
+        // Another writer changes the record while the first update is running
+        let mut attempts = 0;
+        let updated = db
+            .modify::<User>(alice.id, |user| {
+                attempts += 1;
+                if attempts == 1 {
+                    db.modify_user(alice.id, &mut |u| u.is_verified = false)
+                        .unwrap();
+                }
+                user.username = "alicia".to_string();
+            })
+            .unwrap()
+            .unwrap();
+        assert_eq!(attempts, 2);
+        assert_eq!(updated.username, "alicia");
+        // Neither write was lost
+        let stored = db.get_user(alice.id).unwrap().unwrap();
+        assert_eq!(stored, updated);
+        assert!(!stored.is_verified);
+
+        // A record that changes under every attempt gives up
+        let mut attempts = 0;
+        let contended = db.modify::<User>(alice.id, |user| {
+            attempts += 1;
+            db.modify_user(alice.id, &mut |u| {
+                u.email = format!("{}@example.com", attempts)
+            })
+            .unwrap();
+            user.username = "alice".to_string();
+        });
+        assert!(contended.unwrap_err().is::<Conflict>());
+        assert_eq!(attempts, MAX_MODIFY_ATTEMPTS);
+        assert_eq!(db.get_user(alice.id).unwrap().unwrap().username, "alicia");
+
+        assert!(db.modify::<User>(999, |_| {}).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_duplicate_events_are_skipped() {
+        let db = Database::in_memory().unwrap();
+        let alice = db.insert_user(fixtures::user("alice", &[])).unwrap();
+
+        let first = db
+            .insert_timeline_event(release(1, Some(alice.id), "1.0.0"))
+            .unwrap();
+        assert!(first.is_some());
+        assert!(
+            db.insert_timeline_event(release(1, Some(alice.id), "1.0.0"))
+                .unwrap()
+                .is_none()
+        );
+        // Any part of the key differing makes a new event
+        for event in [
+            release(1, None, "1.0.0"),
+            release(1, Some(alice.id), "1.0.1"),
+            release(2, Some(alice.id), "1.0.0"),
+            TimelineEvent {
+                event_type: EventType::SecurityAlert,
+                ..release(1, Some(alice.id), "1.0.0")
+            },
+        ] {
+            assert!(db.insert_timeline_event(event).unwrap().is_some());
+        }
+        // Events without a version are never duplicates
+        let unversioned = TimelineEvent {
+            version: None,
+            ..release(1, None, "")
+        };
+        assert!(
+            db.insert_timeline_event(unversioned.clone())
+                .unwrap()
+                .is_some()
+        );
+        assert!(db.insert_timeline_event(unversioned).unwrap().is_some());
+
+        // Removing an event frees its key
+        db.delete_timeline_events_by_user(alice.id).unwrap();
+        assert!(
+            db.insert_timeline_event(release(1, Some(alice.id), "1.0.0"))
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_pending_notification_index() {
+        let db = Database::in_memory().unwrap();
+        let alice = db.insert_user(fixtures::user("alice", &[])).unwrap();
+        let bob = db.insert_user(fixtures::user("bob", &[])).unwrap();
+
+        let mut alice_event = db
+            .insert_timeline_event(release(1, Some(alice.id), "1.0.0"))
+            .unwrap()
+            .unwrap();
+        let bob_event = db
+            .insert_timeline_event(release(1, Some(bob.id), "1.0.0"))
+            .unwrap()
+            .unwrap();
+        // Global events and alerts aren't emailed
+        db.insert_timeline_event(release(1, None, "1.0.0")).unwrap();
+        db.insert_timeline_event(TimelineEvent {
+            event_type: EventType::SecurityAlert,
+            ..release(1, Some(alice.id), "1.0.0")
+        })
+        .unwrap();
+        assert_eq!(pending_ids(&db), [alice_event.id, bob_event.id]);
+
+        alice_event.notified_at = Some(chrono::Utc::now());
+        db.update_timeline_event(alice_event).unwrap();
+        assert_eq!(pending_ids(&db), [bob_event.id]);
+
+        // Opting out drops what's queued, and nothing new is queued
+        db.modify_user(bob.id, &mut |u| u.notifications_enabled = false)
+            .unwrap();
+        assert!(pending_ids(&db).is_empty());
+        db.insert_timeline_event(release(1, Some(bob.id), "1.0.1"))
+            .unwrap();
+        assert!(pending_ids(&db).is_empty());
+
+        let event = db
+            .insert_timeline_event(release(1, Some(alice.id), "1.0.1"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(pending_ids(&db), [event.id]);
+        db.delete_user(alice.id).unwrap();
+        assert!(pending_ids(&db).is_empty());
+        // Their personal timeline goes with them
+        let events = db.get_timeline_by_package(1).unwrap();
+        assert!(events.iter().all(|e| e.user_id != Some(alice.id)));
+        assert!(events.iter().any(|e| e.user_id.is_none()));
+    }
+
+    #[test]
+    fn test_subscription_edges_and_counts() {
+        let db = Database::in_memory().unwrap();
+        let alice = db
+            .insert_user(fixtures::user("alice", &["serde", "tokio"]))
+            .unwrap();
+        let bob = db.insert_user(fixtures::user("bob", &["serde"])).unwrap();
+
+        let mut subscribers = db.get_users_subscribed_to("serde").unwrap();
+        subscribers.sort();
+        assert_eq!(subscribers, [alice.id, bob.id]);
+        assert_eq!(db.get_subscriber_count("serde").unwrap(), 2);
+        assert_eq!(db.get_subscriber_count("tokio").unwrap(), 1);
+        // Names that only share a prefix aren't matched
+        assert!(db.get_users_subscribed_to("serde_json").unwrap().is_empty());
+        assert!(db.get_users_subscribed_to("serd").unwrap().is_empty());
+
+        // Unsubscribing and subscribing elsewhere moves both edge and count
+        db.modify_user(alice.id, &mut |u| {
+            u.subscriptions.retain(|s| s.package_name != "serde");
+            u.subscriptions.push(PackageSubscription {
+                package_name: "serde_json".to_string(),
+                notifications_enabled: true,
+            });
+        })
+        .unwrap();
+        assert_eq!(db.get_users_subscribed_to("serde").unwrap(), [bob.id]);
+        assert_eq!(
+            db.get_users_subscribed_to("serde_json").unwrap(),
+            [alice.id]
+        );
+        assert_eq!(db.get_subscriber_count("serde").unwrap(), 1);
+        assert_eq!(db.get_subscriber_count("serde_json").unwrap(), 1);
+
+        // Rewriting a user unchanged doesn't count them twice
+        let alice = db.get_user(alice.id).unwrap().unwrap();
+        db.update_user(alice.clone()).unwrap();
+        assert_eq!(db.get_subscriber_count("tokio").unwrap(), 1);
+
+        db.delete_user(alice.id).unwrap();
+        db.modify_user(bob.id, &mut |u| u.subscriptions.clear())
+            .unwrap();
+        for name in ["serde", "serde_json", "tokio"] {
+            assert_eq!(db.get_subscriber_count(name).unwrap(), 0);
+            assert!(db.get_users_subscribed_to(name).unwrap().is_empty());
+        }
+    }
 }
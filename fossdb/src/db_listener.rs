@@ -4,6 +4,11 @@ use native_db::watch::Event;
 use std::sync::Arc;
 
 use crate::db::Database;
+use crate::storage::Storage;
+#[cfg(feature = "storage-postgres")]
+use crate::storage::postgres::PostgresStorage;
+use crate::timeline;
+use crate::vulnerability_links;
 use crate::{EventType, PackageVersion, TimelineEvent};
 use crate::websocket::TimelineBroadcaster;
 
@@ -56,12 +61,68 @@ async fn handle_package_version_event(
         }
     };
 
+    publish_new_version(version, &*db, &broadcaster)
+}
+
+/// Spawns a background task that listens for PackageVersion inserts made by
+/// any process sharing the Postgres database
+#[cfg(feature = "storage-postgres")]
+pub async fn spawn_postgres_version_listener(
+    db: Arc<PostgresStorage>,
+    broadcaster: Arc<TimelineBroadcaster>,
+) -> Result<()> {
+    let mut listener = sqlx::postgres::PgListener::connect_with(db.pool()).await?;
+    listener.listen("package_versions").await?;
+
+    tracing::info!("Started Postgres listener for PackageVersion events");
+
+    tokio::spawn(async move {
+        loop {
+            let notification = match listener.recv().await {
+                Ok(notification) => notification,
+                Err(e) => {
+                    tracing::error!("Error receiving Postgres notification: {}", e);
+                    break;
+                }
+            };
+            let Ok(id) = notification.payload().parse() else {
+                continue;
+            };
+            let result = db
+                .get_version(id)
+                .and_then(|version| match version {
+                    Some(version) => publish_new_version(version, &*db, &broadcaster),
+                    None => Ok(()),
+                });
+            if let Err(e) = result {
+                tracing::error!("Error handling package version event: {}", e);
+            }
+        }
+        tracing::warn!("Postgres listener for PackageVersion events stopped");
+    });
+
+    Ok(())
+}
+
+fn publish_new_version(
+    version: PackageVersion,
+    db: &dyn Storage,
+    broadcaster: &TimelineBroadcaster,
+) -> Result<()> {
     tracing::debug!(
         "Detected new PackageVersion insert: package_id={}, version={}",
         version.package_id,
         version.version
     );
 
+    if let Err(e) = vulnerability_links::link_version(db, version.clone()) {
+        tracing::error!(
+            "Failed to link {} to its advisories: {}",
+            version.version,
+            e
+        );
+    }
+
     // Get the package to retrieve its name
     let package = match db.get_package(version.package_id)? {
         Some(pkg) => pkg,
@@ -74,54 +135,12 @@ async fn handle_package_version_event(
             return Ok(());
         }
     };
-
-    let now = Utc::now();
-
-    // Create timeline events for subscribed users
-    match db.get_users_subscribed_to(&package.name) {
-        Ok(subscribed_users) => {
-            for user_id in subscribed_users {
-                let event = TimelineEvent {
-                    id: 0,
-                    package_id: package.id,
-                    user_id: Some(user_id),
-                    event_type: EventType::NewRelease,
-                    package_name: package.name.clone(),
-                    version: Some(version.version.clone()),
-                    message: format!("New version {} released", version.version),
-                    metadata: None,
-                    created_at: now,
-                    notified_at: None,
-                };
-
-                match db.insert_timeline_event(event) {
-                    Ok(saved_event) => {
-                        // Broadcast the event to connected WebSocket clients
-                        broadcaster.broadcast(saved_event);
-                        tracing::debug!(
-                            "Created timeline event for user {} for {} {}",
-                            user_id,
-                            package.name,
-                            version.version
-                        );
-                    }
-                    Err(e) => {
-                        tracing::error!(
-                            "Failed to create timeline event for user {}: {}",
-                            user_id,
-                            e
-                        );
-                    }
-                }
-            }
-        }
-        Err(e) => {
-            tracing::error!("Failed to get subscribed users for {}: {}", package.name, e);
-        }
+    // Releases of packages awaiting moderation aren't public yet
+    if db.is_package_pending(package.id)? {
+        return Ok(());
     }
 
-    // Broadcast a global event to WebSocket clients (not stored in database)
-    let global_event = TimelineEvent {
+    let event = TimelineEvent {
         id: 0,
         package_id: package.id,
         user_id: None,
@@ -130,12 +149,15 @@ async fn handle_package_version_event(
         version: Some(version.version.clone()),
         message: format!("New version {} released", version.version),
         metadata: None,
-        created_at: now,
+        created_at: Utc::now(),
         notified_at: None,
     };
 
-    // Broadcast the global event to connected WebSocket clients
-    broadcaster.broadcast(global_event);
+    // Store events for subscribed users and broadcast to WebSocket clients
+    if let Err(e) = timeline::publish(db, broadcaster, event) {
+        tracing::error!("Failed to publish timeline event for {}: {}", package.name, e);
+    }
+
     tracing::info!(
         "Broadcast global timeline event for {} {}",
         package.name,
@@ -0,0 +1,127 @@
+// Detects packages that already exist under a submitted name or repository
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::storage::Storage;
+
+/// Compare project URLs loosely: scheme, `www.`, case, a trailing slash and
+/// `.git` are ignored
+pub fn normalize_url(url: &str) -> String {
+    let url = url.trim().to_lowercase();
+    let url = url.split_once("://").map_or(url.as_str(), |(_, rest)| rest);
+    let url = url.strip_prefix("www.").unwrap_or(url);
+    url.trim_end_matches('/').trim_end_matches(".git").to_string()
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateReason {
+    Name,
+    /// The name a package had before it was renamed
+    FormerName,
+    Repository,
+}
+
+/// An existing package a submission would duplicate
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Duplicate {
+    pub package_id: u64,
+    pub name: String,
+    pub reason: DuplicateReason,
+}
+
+/// Existing packages, including those awaiting review, that a new package
+/// with this name and repository would duplicate
+pub fn find_duplicates(
+    db: &dyn Storage,
+    name: &str,
+    repository: Option<&str>,
+) -> Result<Vec<Duplicate>> {
+    let mut duplicates = Vec::new();
+    if let Some(package) = db.get_package_by_name(name)? {
+        duplicates.push(Duplicate {
+            package_id: package.id,
+            name: package.name,
+            reason: DuplicateReason::Name,
+        });
+    } else if let Some(package) = db.find_package(name)? {
+        duplicates.push(Duplicate {
+            package_id: package.id,
+            name: package.name,
+            reason: DuplicateReason::FormerName,
+        });
+    }
+
+    if let Some(repository) = repository {
+        let repository = normalize_url(repository);
+        for package in db.get_all_packages()? {
+            if package.repository.as_deref().map(normalize_url).as_ref() == Some(&repository)
+                && !duplicates.iter().any(|d| d.package_id == package.id)
+            {
+                duplicates.push(Duplicate {
+                    package_id: package.id,
+                    name: package.name,
+                    reason: DuplicateReason::Repository,
+                });
+            }
+        }
+    }
+    Ok(duplicates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::{Package, PackageAlias};
+    use chrono::Utc;
+
+    #[test]
+    fn test_find_duplicates() {
+        let db = Database::in_memory().unwrap();
+        let package = db
+            .insert_package(Package {
+                id: 0,
+                name: "serde".to_string(),
+                description: None,
+                homepage: None,
+                repository: Some("https://github.com/serde-rs/serde".to_string()),
+                license: None,
+                tags: vec![],
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                platform: None,
+                language: None,
+                status: None,
+                dependents_count: None,
+                rank: None,
+            })
+            .unwrap();
+        db.insert_package_alias(PackageAlias {
+            name: "serde-old".to_string(),
+            package_id: package.id,
+            created_at: Utc::now(),
+        })
+        .unwrap();
+
+        let reasons = |name: &str, repository: Option<&str>| -> Vec<DuplicateReason> {
+            find_duplicates(&db, name, repository)
+                .unwrap()
+                .into_iter()
+                .map(|d| d.reason)
+                .collect()
+        };
+        assert_eq!(reasons("serde", None), [DuplicateReason::Name]);
+        assert_eq!(reasons("serde-old", None), [DuplicateReason::FormerName]);
+        assert_eq!(
+            reasons("serde2", Some("http://www.GitHub.com/serde-rs/serde.git")),
+            [DuplicateReason::Repository]
+        );
+        // A package is only reported once
+        assert_eq!(
+            reasons("serde", Some("https://github.com/serde-rs/serde")),
+            [DuplicateReason::Name]
+        );
+        assert!(reasons("ser", None).is_empty());
+    }
+}
@@ -0,0 +1,249 @@
+use anyhow::Result;
+use lettre::message::Mailbox;
+use once_cell::sync::Lazy;
+use tera::{Context, Tera};
+
+use crate::config::Config;
+use crate::retention::RetentionAction;
+
+pub mod transport;
+
+pub use transport::{Email, EmailTransport};
+
+static TEMPLATES: Lazy<Tera> = Lazy::new(|| {
+    let mut tera = Tera::default();
+
+    tera.add_raw_template(
+        "new_release.html",
+        r#"
+<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <style>
+        body { font-family: Arial, sans-serif; line-height: 1.6; color: #333; }
+        .container { max-width: 600px; margin: 0 auto; padding: 20px; }
+        .header { background: #0066cc; color: white; padding: 20px; text-align: center; }
+        .content { background: #f4f4f4; padding: 20px; margin-top: 20px; }
+        .version { font-size: 24px; font-weight: bold; color: #0066cc; }
+        .footer { margin-top: 20px; font-size: 12px; color: #666; }
+        a { color: #0066cc; text-decoration: none; }
+    </style>
+</head>
+<body>
+    <div class="container">
+        <div class="header">
+            <h1>New Release Available</h1>
+        </div>
+        <div class="content">
+            <p>Hello!</p>
+            <p>A new version of <strong>{{ package_name }}</strong> has been released:</p>
+            <p class="version">{{ version }}</p>
+            <p>Released on: {{ release_date }}</p>
+            {% if description %}
+            <p>{{ description }}</p>
+            {% endif %}
+            {% if changes %}
+            <h3>What changed</h3>
+            <ul>
+                {% for change in changes %}
+                <li>{{ change }}</li>
+                {% endfor %}
+            </ul>
+            {% endif %}
+            <p><a href="{{ package_url }}">View package details</a></p>
+        </div>
+        <div class="footer">
+            <p>You're receiving this because you're subscribed to {{ package_name }}.</p>
+            <p><a href="{{ settings_url }}">Manage notification settings</a></p>
+        </div>
+    </div>
+</body>
+</html>
+"#,
+    )
+    .unwrap();
+
+    tera.add_raw_template(
+        "new_release.txt",
+        r#"
+New Release: {{ package_name }} {{ version }}
+
+A new version of {{ package_name }} has been released:
+
+Version: {{ version }}
+Released: {{ release_date }}
+
+{% if description %}
+{{ description }}
+{% endif %}
+{% if changes %}
+What changed:
+{% for change in changes %}
+  - {{ change }}
+{% endfor %}
+{% endif %}
+
+View package details: {{ package_url }}
+
+---
+You're receiving this because you're subscribed to {{ package_name }}.
+Manage settings: {{ settings_url }}
+"#,
+    )
+    .unwrap();
+
+    tera.add_raw_template(
+        "inactivity_warning.html",
+        r#"
+<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <style>
+        body { font-family: Arial, sans-serif; line-height: 1.6; color: #333; }
+        .container { max-width: 600px; margin: 0 auto; padding: 20px; }
+        .header { background: #0066cc; color: white; padding: 20px; text-align: center; }
+        .content { background: #f4f4f4; padding: 20px; margin-top: 20px; }
+        a { color: #0066cc; text-decoration: none; }
+    </style>
+</head>
+<body>
+    <div class="container">
+        <div class="header">
+            <h1>Your account is about to expire</h1>
+        </div>
+        <div class="content">
+            <p>Hello {{ username }},</p>
+            <p>You haven't signed in to FossDB for a long time. Unless you sign in
+            before {{ expires_on }}, your account will be {{ action }} along with
+            your subscriptions and timeline.</p>
+            <p><a href="{{ login_url }}">Sign in to keep your account</a></p>
+        </div>
+    </div>
+</body>
+</html>
+"#,
+    )
+    .unwrap();
+
+    tera.add_raw_template(
+        "inactivity_warning.txt",
+        r#"
+Hello {{ username }},
+
+You haven't signed in to FossDB for a long time. Unless you sign in before
+{{ expires_on }}, your account will be {{ action }} along with your
+subscriptions and timeline.
+
+Sign in to keep your account: {{ login_url }}
+"#,
+    )
+    .unwrap();
+
+    tera
+});
+
+pub struct EmailService {
+    transport: Box<dyn EmailTransport>,
+    from: Mailbox,
+    config: Config,
+}
+
+impl EmailService {
+    pub fn new(config: Config) -> Result<Self> {
+        let transport = transport::from_config(&config)?;
+        let from = format!("{} <{}>", config.smtp_from_name, config.smtp_from_address).parse()?;
+
+        Ok(Self {
+            transport,
+            from,
+            config,
+        })
+    }
+
+    pub async fn send_new_release_notification(
+        &self,
+        to_email: &str,
+        package_name: &str,
+        version: &str,
+        release_date: &str,
+        description: Option<&str>,
+        changes: &[String],
+    ) -> Result<()> {
+        if !self.config.email_enabled {
+            tracing::info!("Email disabled, skipping notification to {}", to_email);
+            return Ok(());
+        }
+
+        let mut context = Context::new();
+        context.insert("package_name", package_name);
+        context.insert("version", version);
+        context.insert("release_date", release_date);
+        context.insert("description", &description.unwrap_or(""));
+        context.insert("changes", changes);
+        context.insert(
+            "package_url",
+            &format!("https://fossdb.org/packages/{}", package_name),
+        );
+        context.insert("settings_url", "https://fossdb.org/settings");
+
+        let email = Email {
+            from: self.from.clone(),
+            to: to_email.parse()?,
+            subject: format!("New release: {} {}", package_name, version),
+            text: TEMPLATES.render("new_release.txt", &context)?,
+            html: TEMPLATES.render("new_release.html", &context)?,
+        };
+
+        self.transport.send(&email).await?;
+
+        tracing::info!(
+            "Sent notification to {} for {} {}",
+            to_email,
+            package_name,
+            version
+        );
+        Ok(())
+    }
+
+    /// Tell a user their unused account will be expired under the retention
+    /// policy
+    pub async fn send_inactivity_warning(
+        &self,
+        to_email: &str,
+        username: &str,
+        expires_on: &str,
+        action: RetentionAction,
+    ) -> Result<()> {
+        if !self.config.email_enabled {
+            tracing::info!("Email disabled, skipping inactivity warning to {}", to_email);
+            return Ok(());
+        }
+
+        let mut context = Context::new();
+        context.insert("username", username);
+        context.insert("expires_on", expires_on);
+        context.insert(
+            "action",
+            match action {
+                RetentionAction::Anonymize => "anonymized",
+                RetentionAction::Delete => "deleted",
+            },
+        );
+        context.insert("login_url", "https://fossdb.org/login");
+
+        let email = Email {
+            from: self.from.clone(),
+            to: to_email.parse()?,
+            subject: "Your FossDB account is about to expire".to_string(),
+            text: TEMPLATES.render("inactivity_warning.txt", &context)?,
+            html: TEMPLATES.render("inactivity_warning.html", &context)?,
+        };
+
+        self.transport.send(&email).await?;
+
+        tracing::info!("Sent inactivity warning to {}", to_email);
+        Ok(())
+    }
+}
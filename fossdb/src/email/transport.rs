@@ -0,0 +1,321 @@
+// Ways of handing a rendered email to a mail system
+use anyhow::{Result, bail};
+use lettre::message::{Mailbox, MultiPart};
+use lettre::{
+    AsyncFileTransport, AsyncSendmailTransport, AsyncSmtpTransport, AsyncTransport, Message,
+    Tokio1Executor, transport::smtp::authentication::Credentials,
+};
+
+use crate::config::Config;
+
+/// A rendered email, independent of how it's delivered
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub from: Mailbox,
+    pub to: Mailbox,
+    pub subject: String,
+    pub text: String,
+    pub html: String,
+}
+
+impl Email {
+    fn message(&self) -> Result<Message> {
+        Ok(Message::builder()
+            .from(self.from.clone())
+            .to(self.to.clone())
+            .subject(&self.subject)
+            .multipart(MultiPart::alternative_plain_html(
+                self.text.clone(),
+                self.html.clone(),
+            ))?)
+    }
+}
+
+#[async_trait::async_trait]
+pub trait EmailTransport: Send + Sync {
+    async fn send(&self, email: &Email) -> Result<()>;
+}
+
+/// Select the transport named by `EMAIL_TRANSPORT`
+pub fn from_config(config: &Config) -> Result<Box<dyn EmailTransport>> {
+    Ok(match config.email_transport.as_str() {
+        "smtp" => Box::new(Smtp::new(config)?),
+        "sendmail" => Box::new(Sendmail(AsyncSendmailTransport::new_with_command(
+            &config.sendmail_command,
+        ))),
+        "file" => {
+            std::fs::create_dir_all(&config.email_output_dir)?;
+            Box::new(File(AsyncFileTransport::new(&config.email_output_dir)))
+        }
+        #[cfg(feature = "email-ses")]
+        "ses" => Box::new(ses::Ses::new(config)?),
+        #[cfg(feature = "email-mailgun")]
+        "mailgun" => Box::new(mailgun::Mailgun::new(config)?),
+        other => bail!("Unsupported email transport: {}", other),
+    })
+}
+
+pub struct Smtp(AsyncSmtpTransport<Tokio1Executor>);
+
+impl Smtp {
+    pub fn new(config: &Config) -> Result<Self> {
+        let creds = Credentials::new(config.smtp_username.clone(), config.smtp_password.clone());
+        Ok(Self(
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host)?
+                .credentials(creds)
+                .port(config.smtp_port)
+                .build(),
+        ))
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailTransport for Smtp {
+    async fn send(&self, email: &Email) -> Result<()> {
+        self.0.send(email.message()?).await?;
+        Ok(())
+    }
+}
+
+/// Pipe messages to a local `sendmail` compatible binary
+pub struct Sendmail(AsyncSendmailTransport<Tokio1Executor>);
+
+#[async_trait::async_trait]
+impl EmailTransport for Sendmail {
+    async fn send(&self, email: &Email) -> Result<()> {
+        self.0.send(email.message()?).await?;
+        Ok(())
+    }
+}
+
+/// Write each message to an `.eml` file instead of sending it, for development
+pub struct File(AsyncFileTransport<Tokio1Executor>);
+
+#[async_trait::async_trait]
+impl EmailTransport for File {
+    async fn send(&self, email: &Email) -> Result<()> {
+        let id = self.0.send(email.message()?).await?;
+        tracing::info!("Wrote email to {} as {}.eml", email.to, id);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "email-ses")]
+mod ses {
+    use super::{Email, EmailTransport};
+    use crate::config::Config;
+    use anyhow::{Context, Result, bail};
+    use chrono::{DateTime, Utc};
+    use hmac::{Hmac, Mac};
+    use sha2::{Digest, Sha256};
+
+    /// Amazon SES v2 API, signed with Signature Version 4
+    pub struct Ses {
+        client: reqwest::Client,
+        region: String,
+        access_key_id: String,
+        secret_access_key: String,
+    }
+
+    impl Ses {
+        pub fn new(config: &Config) -> Result<Self> {
+            Ok(Self {
+                client: reqwest::Client::new(),
+                region: config.aws_region.clone(),
+                access_key_id: config
+                    .aws_access_key_id
+                    .clone()
+                    .context("AWS_ACCESS_KEY_ID is required for SES")?,
+                secret_access_key: config
+                    .aws_secret_access_key
+                    .clone()
+                    .context("AWS_SECRET_ACCESS_KEY is required for SES")?,
+            })
+        }
+    }
+
+    fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+        mac.update(data.as_bytes());
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    /// Signature Version 4 signature of a canonical request
+    pub(super) fn signature(
+        secret_access_key: &str,
+        time: DateTime<Utc>,
+        region: &str,
+        service: &str,
+        canonical_request: &str,
+    ) -> String {
+        let date = time.format("%Y%m%d").to_string();
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}/{}/{}/aws4_request\n{}",
+            time.format("%Y%m%dT%H%M%SZ"),
+            date,
+            region,
+            service,
+            hex::encode(Sha256::digest(canonical_request))
+        );
+
+        let key = hmac(format!("AWS4{}", secret_access_key).as_bytes(), &date);
+        let key = hmac(&key, region);
+        let key = hmac(&key, service);
+        let key = hmac(&key, "aws4_request");
+        hex::encode(hmac(&key, &string_to_sign))
+    }
+
+    #[async_trait::async_trait]
+    impl EmailTransport for Ses {
+        async fn send(&self, email: &Email) -> Result<()> {
+            let host = format!("email.{}.amazonaws.com", self.region);
+            let path = "/v2/email/outbound-emails";
+            let body = serde_json::to_string(&serde_json::json!({
+                "FromEmailAddress": email.from.to_string(),
+                "Destination": { "ToAddresses": [email.to.to_string()] },
+                "Content": {
+                    "Simple": {
+                        "Subject": { "Data": email.subject, "Charset": "UTF-8" },
+                        "Body": {
+                            "Text": { "Data": email.text, "Charset": "UTF-8" },
+                            "Html": { "Data": email.html, "Charset": "UTF-8" },
+                        },
+                    },
+                },
+            }))?;
+
+            let now = Utc::now();
+            let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+            let signed_headers = "content-type;host;x-amz-date";
+            let canonical_request = format!(
+                "POST\n{}\n\ncontent-type:application/json\nhost:{}\nx-amz-date:{}\n\n{}\n{}",
+                path,
+                host,
+                amz_date,
+                signed_headers,
+                hex::encode(Sha256::digest(&body))
+            );
+            let authorization = format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}/{}/ses/aws4_request, SignedHeaders={}, Signature={}",
+                self.access_key_id,
+                now.format("%Y%m%d"),
+                self.region,
+                signed_headers,
+                signature(&self.secret_access_key, now, &self.region, "ses", &canonical_request)
+            );
+
+            let response = self
+                .client
+                .post(format!("https://{}{}", host, path))
+                .header("content-type", "application/json")
+                .header("x-amz-date", amz_date)
+                .header("authorization", authorization)
+                .body(body)
+                .send()
+                .await?;
+            if !response.status().is_success() {
+                bail!("SES returned {}: {}", response.status(), response.text().await?);
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "email-mailgun")]
+mod mailgun {
+    use super::{Email, EmailTransport};
+    use crate::config::Config;
+    use anyhow::{Context, Result, bail};
+
+    pub struct Mailgun {
+        client: reqwest::Client,
+        api_url: String,
+        domain: String,
+        api_key: String,
+    }
+
+    impl Mailgun {
+        pub fn new(config: &Config) -> Result<Self> {
+            Ok(Self {
+                client: reqwest::Client::new(),
+                api_url: config.mailgun_api_url.trim_end_matches('/').to_string(),
+                domain: config
+                    .mailgun_domain
+                    .clone()
+                    .context("MAILGUN_DOMAIN is required for Mailgun")?,
+                api_key: config
+                    .mailgun_api_key
+                    .clone()
+                    .context("MAILGUN_API_KEY is required for Mailgun")?,
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl EmailTransport for Mailgun {
+        async fn send(&self, email: &Email) -> Result<()> {
+            let response = self
+                .client
+                .post(format!("{}/v3/{}/messages", self.api_url, self.domain))
+                .basic_auth("api", Some(&self.api_key))
+                .form(&[
+                    ("from", email.from.to_string()),
+                    ("to", email.to.to_string()),
+                    ("subject", email.subject.clone()),
+                    ("text", email.text.clone()),
+                    ("html", email.html.clone()),
+                ])
+                .send()
+                .await?;
+            if !response.status().is_success() {
+                bail!("Mailgun returned {}: {}", response.status(), response.text().await?);
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_transport() {
+        let dir = std::env::temp_dir().join(format!("fossdb-email-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let transport = File(AsyncFileTransport::new(&dir));
+
+        let email = Email {
+            from: "FossDB <noreply@fossdb.org>".parse().unwrap(),
+            to: "user@example.com".parse().unwrap(),
+            subject: "New release: serde 1.0.200".to_string(),
+            text: "serde 1.0.200 was released".to_string(),
+            html: "<p>serde 1.0.200 was released</p>".to_string(),
+        };
+        transport.send(&email).await.unwrap();
+
+        let written: Vec<_> = std::fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(written.len(), 1);
+        let contents = std::fs::read_to_string(written[0].as_ref().unwrap().path()).unwrap();
+        assert!(contents.contains("Subject: New release: serde 1.0.200"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "email-ses")]
+    #[test]
+    fn test_sigv4_signature() {
+        // get-vanilla from the AWS Signature Version 4 test suite
+        let time = "2015-08-30T12:36:00Z".parse().unwrap();
+        let canonical_request = "GET\n/\n\nhost:example.amazonaws.com\nx-amz-date:20150830T123600Z\n\nhost;x-amz-date\ne3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        assert_eq!(
+            ses::signature(
+                "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+                time,
+                "us-east-1",
+                "service",
+                canonical_request
+            ),
+            "5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+}
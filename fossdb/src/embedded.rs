@@ -0,0 +1,126 @@
+//! The package database as a library, for tools that want to query or fill
+//! it directly instead of going through the HTTP API.
+use anyhow::Result;
+use std::sync::Arc;
+
+use crate::db::Database;
+use crate::storage::Storage;
+use crate::{Package, PackageVersion, TimelineEvent, Vulnerability};
+
+/// An open FossDB database
+#[derive(Clone)]
+pub struct Fossdb {
+    db: Arc<dyn Storage>,
+}
+
+impl Fossdb {
+    /// Open the database at `path`, creating it if it doesn't exist
+    pub fn open(path: &str) -> Result<Self> {
+        Ok(Self::with_storage(Arc::new(Database::new(path)?)))
+    }
+
+    pub fn with_storage(db: Arc<dyn Storage>) -> Self {
+        Self { db }
+    }
+
+    /// The underlying storage, for anything the typed queries don't cover
+    pub fn storage(&self) -> &Arc<dyn Storage> {
+        &self.db
+    }
+
+    pub fn package(&self, id: u64) -> Result<Option<Package>> {
+        self.db.get_package(id)
+    }
+
+    pub fn package_by_name(&self, name: &str) -> Result<Option<Package>> {
+        self.db.get_package_by_name(name)
+    }
+
+    /// Packages whose name or description contains `search`, ignoring case
+    pub fn search_packages(&self, search: &str) -> Result<Vec<Package>> {
+        let mut packages = self.db.get_all_packages()?;
+        packages.retain(|p| p.matches_search(search));
+        Ok(packages)
+    }
+
+    pub fn versions(&self, package_id: u64) -> Result<Vec<PackageVersion>> {
+        self.db.get_versions_by_package(package_id)
+    }
+
+    /// Known vulnerabilities affecting any version of a package
+    pub fn vulnerabilities(&self, package_id: u64) -> Result<Vec<Vulnerability>> {
+        let mut vulnerabilities = self.db.get_all_vulnerabilities()?;
+        vulnerabilities.retain(|v| {
+            v.affected_packages
+                .iter()
+                .any(|a| a.package_id == package_id)
+        });
+        Ok(vulnerabilities)
+    }
+
+    /// Public events for a package, newest first
+    pub fn timeline(&self, package_id: u64) -> Result<Vec<TimelineEvent>> {
+        let mut events = self.db.get_timeline_by_package(package_id)?;
+        events.retain(|e| e.user_id.is_none());
+        events.sort_by_key(|e| std::cmp::Reverse(e.created_at));
+        Ok(events)
+    }
+
+    /// Run a collector once against this database, recording the run.
+    /// Events it finds are stored for subscribers without being broadcast.
+    #[cfg(feature = "collector")]
+    pub async fn collect(&self, collector: &dyn crate::collector_models::Collector) -> Result<()> {
+        let started_at = chrono::Utc::now();
+        let report = crate::collector_models::RunReport::default();
+        let result = collector.collect(self.db.clone(), &(), &report).await;
+        crate::collector_runs::record(&*self.db, collector.name(), started_at, &report, &result)?;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn package(name: &str, description: &str) -> Package {
+        Package {
+            id: 0,
+            name: name.to_string(),
+            description: Some(description.to_string()),
+            homepage: None,
+            repository: None,
+            license: None,
+            tags: vec![],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            platform: None,
+            language: None,
+            status: None,
+            dependents_count: None,
+            rank: None,
+        }
+    }
+
+    #[test]
+    fn test_search_packages() {
+        let path = std::env::temp_dir().join(format!("fossdb-embedded-{}.db", std::process::id()));
+        let fossdb = Fossdb::open(path.to_str().unwrap()).unwrap();
+        let serde = fossdb
+            .storage()
+            .insert_package(package("serde", "Serialization framework"))
+            .unwrap();
+        fossdb
+            .storage()
+            .insert_package(package("tokio", "Async runtime"))
+            .unwrap();
+
+        let found = fossdb.search_packages("SERIAL").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, serde.id);
+        assert_eq!(fossdb.package_by_name("tokio").unwrap().unwrap().name, "tokio");
+
+        drop(fossdb);
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,297 @@
+// CVSS and EPSS score enrichment for vulnerabilities with a CVE ID
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::client::RateLimitedClient;
+use crate::storage::Storage;
+
+const NVD_API_URL: &str = "https://services.nvd.nist.gov/rest/json/cves/2.0";
+const EPSS_API_URL: &str = "https://api.first.org/data/v1/epss";
+
+/// FIRST accepts up to 100 CVEs per request
+const EPSS_BATCH_SIZE: usize = 100;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CvssScore {
+    pub vector: String,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpssScore {
+    pub probability: f32,
+    pub percentile: f32,
+}
+
+#[derive(Debug, Deserialize)]
+struct NvdResponse {
+    vulnerabilities: Vec<NvdVulnerability>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NvdVulnerability {
+    cve: NvdCve,
+}
+
+#[derive(Debug, Deserialize)]
+struct NvdCve {
+    #[serde(default)]
+    metrics: HashMap<String, Vec<NvdMetric>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NvdMetric {
+    #[serde(rename = "type")]
+    metric_type: Option<String>,
+    cvss_data: NvdCvssData,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NvdCvssData {
+    vector_string: String,
+    base_score: f32,
+}
+
+#[derive(Debug, Deserialize)]
+struct EpssResponse {
+    data: Vec<EpssEntry>,
+}
+
+/// FIRST encodes scores as strings
+#[derive(Debug, Deserialize)]
+struct EpssEntry {
+    cve: String,
+    epss: String,
+    percentile: String,
+}
+
+/// Pick the newest CVSS version available, preferring NVD's primary assessment
+fn select_cvss(response: NvdResponse) -> Option<CvssScore> {
+    let mut metrics = response.vulnerabilities.into_iter().next()?.cve.metrics;
+
+    for key in ["cvssMetricV40", "cvssMetricV31", "cvssMetricV30", "cvssMetricV2"] {
+        let Some(mut candidates) = metrics.remove(key).filter(|c| !c.is_empty()) else {
+            continue;
+        };
+        let index = candidates
+            .iter()
+            .position(|m| m.metric_type.as_deref() == Some("Primary"))
+            .unwrap_or(0);
+        let metric = candidates.swap_remove(index);
+        return Some(CvssScore {
+            vector: metric.cvss_data.vector_string,
+            score: metric.cvss_data.base_score,
+        });
+    }
+
+    None
+}
+
+fn parse_epss(response: EpssResponse) -> HashMap<String, EpssScore> {
+    response
+        .data
+        .into_iter()
+        .filter_map(|entry| {
+            Some((
+                entry.cve,
+                EpssScore {
+                    probability: entry.epss.parse().ok()?,
+                    percentile: entry.percentile.parse().ok()?,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Fetches scores from NVD (CVSS) and FIRST (EPSS)
+pub struct ScoreEnricher {
+    client: reqwest::Client,
+    nvd: RateLimitedClient,
+    nvd_api_key: Option<String>,
+}
+
+impl ScoreEnricher {
+    pub fn new(client: reqwest::Client, nvd_api_key: Option<String>) -> Self {
+        // NVD allows 5 requests per 30 seconds, or 50 with an API key
+        let per_minute = if nvd_api_key.is_some() { 100 } else { 10 };
+        Self {
+            nvd: RateLimitedClient::per_minute(client.clone(), per_minute),
+            client,
+            nvd_api_key,
+        }
+    }
+
+    pub async fn fetch_cvss(&self, cve_id: &str) -> Result<Option<CvssScore>> {
+        // CVE IDs are validated on input, so they need no escaping
+        let url = format!("{}?cveId={}", NVD_API_URL, cve_id);
+        let mut request = self.nvd.get(&url).await;
+        if let Some(key) = &self.nvd_api_key {
+            request = request.header("apiKey", key);
+        }
+
+        let response: NvdResponse = request
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .with_context(|| format!("Invalid NVD response for {}", cve_id))?;
+
+        Ok(select_cvss(response))
+    }
+
+    pub async fn fetch_epss(&self, cve_ids: &[String]) -> Result<HashMap<String, EpssScore>> {
+        let mut scores = HashMap::new();
+
+        for batch in cve_ids.chunks(EPSS_BATCH_SIZE) {
+            let response: EpssResponse = self
+                .client
+                .get(format!("{}?cve={}", EPSS_API_URL, batch.join(",")))
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await
+                .context("Invalid EPSS response")?;
+            scores.extend(parse_epss(response));
+        }
+
+        Ok(scores)
+    }
+
+    /// Refresh scores for every vulnerability with a CVE ID.
+    ///
+    /// EPSS changes daily so it is always refreshed, while CVSS is only
+    /// fetched until NVD has published a score. Returns the number of
+    /// vulnerabilities updated.
+    pub async fn refresh(&self, db: &dyn Storage) -> Result<usize> {
+        let vulnerabilities: Vec<_> = db
+            .get_all_vulnerabilities()?
+            .into_iter()
+            .filter(|v| v.cve_id.is_some())
+            .collect();
+
+        let cve_ids: Vec<String> = vulnerabilities
+            .iter()
+            .filter_map(|v| v.cve_id.clone())
+            .collect();
+        let epss = self.fetch_epss(&cve_ids).await?;
+
+        let mut updated = 0;
+
+        for vulnerability in vulnerabilities {
+            let Some(cve_id) = vulnerability.cve_id.clone() else {
+                continue;
+            };
+
+            let cvss = if vulnerability.cvss_score.is_none() {
+                match self.fetch_cvss(&cve_id).await {
+                    Ok(cvss) => cvss,
+                    Err(e) => {
+                        tracing::warn!("Failed to fetch CVSS for {}: {}", cve_id, e);
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+
+            let epss = epss.get(&cve_id);
+            if cvss.is_none() && epss.is_none() {
+                continue;
+            }
+
+            // Re-read so edits made while we were fetching aren't lost
+            let Some(mut current) = db.get_vulnerability(vulnerability.id)? else {
+                continue;
+            };
+            if current.cve_id.as_deref() != Some(cve_id.as_str()) {
+                continue;
+            }
+
+            if let Some(cvss) = cvss {
+                current.cvss_vector = Some(cvss.vector);
+                current.cvss_score = Some(cvss.score);
+            }
+            if let Some(epss) = epss {
+                current.epss_score = Some(epss.probability);
+                current.epss_percentile = Some(epss.percentile);
+            }
+            current.scores_updated_at = Some(Utc::now());
+
+            db.update_vulnerability(current)?;
+            updated += 1;
+        }
+
+        Ok(updated)
+    }
+}
+
+/// Periodically refresh vulnerability scores
+pub async fn run_enrichment_loop(enricher: ScoreEnricher, db: Arc<dyn Storage>, interval_hours: u64) {
+    loop {
+        match enricher.refresh(&*db).await {
+            Ok(count) => tracing::info!("Refreshed scores for {} vulnerabilities", count),
+            Err(e) => tracing::error!("Vulnerability score enrichment failed: {}", e),
+        }
+
+        tokio::time::sleep(tokio::time::Duration::from_secs(interval_hours * 3600)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_cvss_prefers_newest_primary() {
+        let response: NvdResponse = serde_json::from_value(serde_json::json!({
+            "vulnerabilities": [{
+                "cve": {
+                    "id": "CVE-2021-44228",
+                    "metrics": {
+                        "cvssMetricV31": [
+                            {
+                                "type": "Secondary",
+                                "cvssData": { "vectorString": "CVSS:3.1/AV:N/AC:H/PR:N/UI:N/S:U/C:H/I:H/A:H", "baseScore": 8.1 }
+                            },
+                            {
+                                "type": "Primary",
+                                "cvssData": { "vectorString": "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:C/C:H/I:H/A:H", "baseScore": 10.0 }
+                            }
+                        ],
+                        "cvssMetricV2": [{
+                            "type": "Primary",
+                            "cvssData": { "vectorString": "AV:N/AC:M/Au:N/C:C/I:C/A:C", "baseScore": 9.3 }
+                        }]
+                    }
+                }
+            }]
+        }))
+        .unwrap();
+
+        let cvss = select_cvss(response).unwrap();
+        assert_eq!(cvss.score, 10.0);
+        assert!(cvss.vector.starts_with("CVSS:3.1/AV:N/AC:L"));
+    }
+
+    #[test]
+    fn test_parse_epss() {
+        let response: EpssResponse = serde_json::from_value(serde_json::json!({
+            "data": [
+                { "cve": "CVE-2021-44228", "epss": "0.944660000", "percentile": "0.999990000", "date": "2024-01-01" },
+                { "cve": "CVE-2024-0001", "epss": "not a number", "percentile": "0.1", "date": "2024-01-01" }
+            ]
+        }))
+        .unwrap();
+
+        let scores = parse_epss(response);
+        assert_eq!(scores.len(), 1);
+        assert_eq!(scores["CVE-2021-44228"].probability, 0.94466);
+    }
+}
@@ -0,0 +1,133 @@
+// Forwards global timeline events to a message broker so other systems can
+// react to releases and advisories without polling. Each event type gets its
+// own NATS subject (`<prefix>.new_release`) or MQTT topic
+// (`<prefix>/new_release`, with the prefix's dots as slashes).
+use anyhow::{Result, bail};
+use futures::StreamExt;
+use std::sync::Arc;
+
+use crate::websocket::TimelineBroadcaster;
+use crate::{EventType, TimelineEvent};
+
+pub enum EventBroker {
+    #[cfg(feature = "events-nats")]
+    Nats(async_nats::Client),
+    #[cfg(feature = "events-mqtt")]
+    Mqtt(rumqttc::AsyncClient),
+}
+
+impl EventBroker {
+    /// Connect to a `nats://` or `mqtt://` URL, naming the connection `client_id`
+    pub async fn connect(url: &str, client_id: &str) -> Result<Self> {
+        let Some((scheme, _)) = url.split_once("://") else {
+            bail!("event broker URL {} has no scheme", url);
+        };
+
+        match scheme {
+            #[cfg(feature = "events-nats")]
+            "nats" => Ok(Self::Nats(
+                async_nats::ConnectOptions::new()
+                    .name(client_id)
+                    .connect(url)
+                    .await?,
+            )),
+            #[cfg(feature = "events-mqtt")]
+            "mqtt" => Self::connect_mqtt(url, client_id),
+            _ => bail!("{}:// brokers aren't supported by this build", scheme),
+        }
+    }
+
+    #[cfg(feature = "events-mqtt")]
+    fn connect_mqtt(url: &str, client_id: &str) -> Result<Self> {
+        let address = url.trim_start_matches("mqtt://").trim_end_matches('/');
+        let (host, port) = match address.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse()?),
+            None => (address, 1883),
+        };
+
+        let mut options = rumqttc::MqttOptions::new(client_id, host, port);
+        options.set_keep_alive(std::time::Duration::from_secs(30));
+        let (client, mut event_loop) = rumqttc::AsyncClient::new(options, 64);
+
+        // The event loop does the actual I/O and reconnects after errors
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = event_loop.poll().await {
+                    tracing::warn!("MQTT connection error: {}", e);
+                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                }
+            }
+        });
+        Ok(Self::Mqtt(client))
+    }
+
+    async fn publish(&self, prefix: &str, event: &TimelineEvent) -> Result<()> {
+        let payload = serde_json::to_vec(event)?;
+        match self {
+            #[cfg(feature = "events-nats")]
+            Self::Nats(client) => {
+                let subject = format!("{}.{}", prefix, topic_name(&event.event_type));
+                client.publish(subject, payload.into()).await?;
+            }
+            #[cfg(feature = "events-mqtt")]
+            Self::Mqtt(client) => {
+                let topic = format!(
+                    "{}/{}",
+                    prefix.replace('.', "/"),
+                    topic_name(&event.event_type)
+                );
+                client
+                    .publish(topic, rumqttc::QoS::AtLeastOnce, false, payload)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Last segment of an event type's subject or topic
+fn topic_name(event_type: &EventType) -> &'static str {
+    event_type.as_str()
+}
+
+/// Publish every global timeline event to the broker. Personal copies for
+/// subscribers stay private.
+pub fn spawn_publisher(
+    broker: EventBroker,
+    prefix: String,
+    broadcaster: Arc<TimelineBroadcaster>,
+) {
+    tokio::spawn(async move {
+        loop {
+            let mut events = Box::pin(broadcaster.global_events());
+            while let Some(event) = events.next().await {
+                if let Err(e) = broker.publish(&prefix, &event).await {
+                    tracing::warn!("Failed to publish event {} to broker: {}", event.id, e);
+                }
+            }
+            // The stream ends if publishing falls behind the broadcaster
+            tracing::warn!("Event publisher fell behind, some events were not published");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topic_names_are_distinct() {
+        let names = [
+            EventType::NewRelease,
+            EventType::SecurityAlert,
+            EventType::PackageAdded,
+            EventType::PackageUpdated,
+            EventType::MaintainerChanged,
+            EventType::PackageDeprecated,
+        ]
+        .map(|event_type| topic_name(&event_type));
+        assert_eq!(names[0], "new_release");
+        let unique: std::collections::HashSet<_> = names.iter().collect();
+        assert_eq!(unique.len(), names.len());
+    }
+}
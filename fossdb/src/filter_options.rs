@@ -0,0 +1,138 @@
+// Distinct languages and licenses in the catalog, for filter UIs and for
+// rejecting filters that can't match anything
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::FilterOption;
+use crate::storage::Storage;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FilterOptions {
+    pub languages: Vec<FilterOption>,
+    pub licenses: Vec<FilterOption>,
+}
+
+impl FilterOptions {
+    /// Count the values on every published package
+    pub fn collect(db: &dyn Storage) -> Result<Self> {
+        let pending = db.pending_package_ids()?;
+        let mut languages = HashMap::new();
+        let mut licenses = HashMap::new();
+        db.for_each_package_summary(&mut |summary| {
+            if !pending.contains(&summary.id) {
+                if let Some(language) = summary.language {
+                    *languages.entry(language).or_insert(0) += 1;
+                }
+                if let Some(license) = summary.license {
+                    *licenses.entry(license).or_insert(0) += 1;
+                }
+            }
+            Ok(())
+        })?;
+        Ok(Self {
+            languages: sorted(languages),
+            licenses: sorted(licenses),
+        })
+    }
+}
+
+/// Most common first, then alphabetically
+fn sorted(counts: HashMap<String, u64>) -> Vec<FilterOption> {
+    let mut options: Vec<FilterOption> = counts
+        .into_iter()
+        .map(|(value, count)| FilterOption { value, count })
+        .collect();
+    options.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    options
+}
+
+/// Whether `value` is one of `options`, ignoring case
+pub fn contains(options: &[FilterOption], value: &str) -> bool {
+    options.iter().any(|o| o.value.eq_ignore_ascii_case(value))
+}
+
+/// Filter options recounted at most once per `ttl`, since counting scans
+/// every package
+pub struct FilterOptionsCache {
+    ttl: Duration,
+    cached: Mutex<Option<(Instant, FilterOptions)>>,
+}
+
+impl FilterOptionsCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            cached: Mutex::new(None),
+        }
+    }
+
+    pub fn get(&self, db: &dyn Storage) -> Result<FilterOptions> {
+        if let Some((counted, options)) = &*self.cached.lock().unwrap()
+            && counted.elapsed() < self.ttl
+        {
+            return Ok(options.clone());
+        }
+
+        // Count without holding the lock, a racing recount is harmless
+        let options = FilterOptions::collect(db)?;
+        *self.cached.lock().unwrap() = Some((Instant::now(), options.clone()));
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::{Package, fixtures};
+
+    fn add(db: &Database, name: &str, language: Option<&str>, license: Option<&str>) {
+        db.insert_package(Package {
+            language: language.map(str::to_string),
+            license: license.map(str::to_string),
+            ..fixtures::package(name)
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_cache() {
+        let db = Database::in_memory().unwrap();
+        add(&db, "serde", Some("Rust"), Some("MIT"));
+        add(&db, "tokio", Some("Rust"), Some("MIT"));
+        add(&db, "left-pad", Some("JavaScript"), Some("WTFPL"));
+        add(&db, "mystery", None, None);
+
+        let cache = FilterOptionsCache::new(Duration::from_secs(60));
+        let options = cache.get(&db).unwrap();
+        assert_eq!(
+            options.languages,
+            [
+                FilterOption {
+                    value: "Rust".to_string(),
+                    count: 2
+                },
+                FilterOption {
+                    value: "JavaScript".to_string(),
+                    count: 1
+                },
+            ]
+        );
+        assert!(contains(&options.licenses, "wtfpl"));
+        assert!(!contains(&options.licenses, "GPL-3.0"));
+
+        // Served from the cache until the TTL runs out
+        add(&db, "requests", Some("Python"), Some("Apache-2.0"));
+        assert_eq!(cache.get(&db).unwrap(), options);
+        assert_eq!(
+            FilterOptionsCache::new(Duration::ZERO)
+                .get(&db)
+                .unwrap()
+                .languages
+                .len(),
+            3
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! Realistic records and an in-process HTTP harness for tests. Enabled for
+//! this crate's own tests and for others with the `test-support` feature.
+use anyhow::Result;
+use chrono::{Duration, TimeZone, Utc};
+
+use crate::storage::Storage;
+use crate::{Dependency, Package, PackageSubscription, PackageVersion, ProfileSettings, Role, TimelinePreferences, User};
+
+/// A package as a collector would have stored it
+pub fn package(name: &str) -> Package {
+    let created_at = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+    Package {
+        id: 0,
+        name: name.to_string(),
+        description: Some(format!("The {} package", name)),
+        homepage: Some(format!("https://{}.rs", name)),
+        repository: Some(format!("https://github.com/{0}-rs/{0}", name)),
+        license: Some("MIT OR Apache-2.0".to_string()),
+        tags: vec!["rust".to_string(), "crate".to_string()],
+        created_at,
+        updated_at: created_at,
+        platform: Some("crates.io".to_string()),
+        language: Some("rust".to_string()),
+        status: None,
+        dependents_count: Some(0),
+        rank: Some(10),
+    }
+}
+
+pub fn version(package_id: u64, version: &str) -> PackageVersion {
+    let release_date = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    PackageVersion {
+        id: 0,
+        package_id,
+        version: version.to_string(),
+        release_date,
+        download_url: None,
+        checksum: None,
+        dependencies: Vec::new(),
+        vulnerabilities: Vec::new(),
+        changelog: None,
+        created_at: release_date,
+        license: Some("MIT OR Apache-2.0".to_string()),
+        size: Some(64 * 1024),
+    }
+}
+
+pub fn dependency(name: &str, requirement: &str) -> Dependency {
+    Dependency {
+        name: name.to_string(),
+        version_requirement: requirement.to_string(),
+        dependency_type: "normal".to_string(),
+        optional: false,
+    }
+}
+
+/// A verified user subscribed to `subscriptions`. The password hash is not
+/// a real hash, so log in through [`TestClient::as_user`] instead.
+pub fn user(username: &str, subscriptions: &[&str]) -> User {
+    User {
+        id: 0,
+        email: format!("{}@example.com", username),
+        username: username.to_string(),
+        password_hash: String::new(),
+        subscriptions: subscriptions
+            .iter()
+            .map(|name| PackageSubscription {
+                package_name: name.to_string(),
+                notifications_enabled: true,
+            })
+            .collect(),
+        created_at: Utc::now(),
+        is_verified: true,
+        notifications_enabled: true,
+        role: Role::User,
+        profile: ProfileSettings::default(),
+        last_read_event_id: None,
+        timeline: TimelinePreferences::default(),
+    }
+}
+
+/// What [`seed`] inserted
+pub struct Seeded {
+    pub serde: Package,
+    pub serde_json: Package,
+    pub tokio: Package,
+    pub versions: Vec<PackageVersion>,
+    pub alice: User,
+}
+
+/// Fill `db` with a few related packages, their releases and a subscriber
+pub fn seed(db: &dyn Storage) -> Result<Seeded> {
+    let serde = db.insert_package(package("serde"))?;
+    let serde_json = db.insert_package(Package {
+        repository: Some("https://github.com/serde-rs/json".to_string()),
+        ..package("serde_json")
+    })?;
+    let tokio = db.insert_package(Package {
+        tags: vec!["rust".to_string(), "async".to_string()],
+        ..package("tokio")
+    })?;
+
+    let mut versions = Vec::new();
+    for (offset, number) in ["1.0.0", "1.0.1", "1.1.0"].into_iter().enumerate() {
+        versions.push(PackageVersion {
+            release_date: version(0, number).release_date + Duration::days(offset as i64 * 30),
+            ..version(serde.id, number)
+        });
+    }
+    versions.push(PackageVersion {
+        dependencies: vec![dependency("serde", "^1.0")],
+        ..version(serde_json.id, "1.0.0")
+    });
+    versions.push(version(tokio.id, "1.40.0"));
+    let versions = db.insert_versions(versions)?;
+
+    let alice = db.insert_user(user("alice", &["serde", "tokio"]))?;
+
+    Ok(Seeded {
+        serde,
+        serde_json,
+        tokio,
+        versions,
+        alice,
+    })
+}
+
+#[cfg(feature = "api-server")]
+pub use harness::*;
+
+#[cfg(feature = "api-server")]
+mod harness {
+    use axum::Router;
+    use axum::body::Body;
+    use axum::http::{HeaderMap, Method, Request, StatusCode, header};
+    use serde_json::Value;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    use crate::auth::Claims;
+    use crate::storage::Storage;
+    use crate::{AppState, User};
+
+    /// Server state around `db`, with every optional service at its defaults
+    pub fn app_state(db: Arc<dyn Storage>) -> AppState {
+        AppState {
+            db,
+            broadcaster: Arc::new(crate::websocket::TimelineBroadcaster::new()),
+            idempotency: Arc::new(crate::idempotency::IdempotencyStore::new(
+                std::time::Duration::from_secs(60),
+            )),
+            collectors: Arc::new(crate::collector_status::CollectorStatusRegistry::new()),
+            rate_limits: Arc::new(crate::rate_limit::RateLimits::new(0, 0, false)),
+            filter_options: Arc::new(crate::filter_options::FilterOptionsCache::new(
+                std::time::Duration::ZERO,
+            )),
+            names: Arc::new(crate::typosquat::SharedNameIndex::new(
+                std::time::Duration::ZERO,
+            )),
+            #[cfg(feature = "artifact-cache")]
+            artifacts: Arc::new(crate::artifact_cache::ArtifactCache::new(
+                std::env::temp_dir().join("fossdb-test-artifacts"),
+                reqwest::Client::new(),
+                1,
+            )),
+            #[cfg(feature = "prefetch")]
+            prefetcher: Arc::new(crate::prefetch::Prefetcher::new(reqwest::Client::new(), None)),
+            #[cfg(feature = "github-app")]
+            github_app: None,
+        }
+    }
+
+    pub struct TestResponse {
+        pub status: StatusCode,
+        pub headers: HeaderMap,
+        /// The body parsed as JSON, or null if it isn't
+        pub body: Value,
+        /// The raw body, for responses that aren't JSON
+        pub text: String,
+    }
+
+    /// Sends requests straight to a router without opening a socket
+    pub struct TestClient {
+        router: Router,
+        claims: Option<Claims>,
+    }
+
+    impl TestClient {
+        pub fn new(router: Router) -> Self {
+            Self {
+                router,
+                claims: None,
+            }
+        }
+
+        /// Act as `user` on routes that read the authenticated user. The
+        /// auth middleware is bypassed, so leave it off the router.
+        pub fn as_user(mut self, user: &User) -> Self {
+            self.claims = Some(Claims {
+                sub: user.id.to_string(),
+                username: user.username.clone(),
+                exp: usize::MAX,
+                role: user.role,
+            });
+            self
+        }
+
+        pub async fn get(&self, uri: &str) -> TestResponse {
+            self.request(Method::GET, uri, None).await
+        }
+
+        pub async fn post(&self, uri: &str, body: Value) -> TestResponse {
+            self.request(Method::POST, uri, Some(body)).await
+        }
+
+        pub async fn delete(&self, uri: &str) -> TestResponse {
+            self.request(Method::DELETE, uri, None).await
+        }
+
+        pub async fn request(&self, method: Method, uri: &str, body: Option<Value>) -> TestResponse {
+            let mut request = Request::builder().method(method).uri(uri);
+            if body.is_some() {
+                request = request.header(header::CONTENT_TYPE, "application/json");
+            }
+            let mut request = request
+                .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+                .unwrap();
+            if let Some(claims) = &self.claims {
+                request.extensions_mut().insert(claims.clone());
+            }
+
+            let response = self.router.clone().oneshot(request).await.unwrap();
+            let status = response.status();
+            let headers = response.headers().clone();
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            TestResponse {
+                status,
+                headers,
+                body: serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+                text: String::from_utf8_lossy(&bytes).into_owned(),
+            }
+        }
+    }
+}
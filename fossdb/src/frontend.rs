@@ -0,0 +1,89 @@
+use axum::{
+    http::{HeaderMap, StatusCode, Uri, header},
+    response::{IntoResponse, Response},
+};
+use rust_embed::{EmbeddedFile, RustEmbed};
+
+/// Frontend assets produced by `dx bundle` in the fossdb-client crate.
+///
+/// In debug builds rust-embed reads the files from disk at runtime, so the
+/// frontend can be rebuilt without recompiling the server.
+#[derive(RustEmbed)]
+#[folder = "$CARGO_MANIFEST_DIR/../target/dx/fossdb-client/release/web/public"]
+#[allow_missing = true]
+struct Assets;
+
+const INDEX: &str = "index.html";
+
+/// Fallback handler that serves embedded frontend assets.
+///
+/// Unknown paths without a file extension are routed to `index.html` so the
+/// client-side router can handle them.
+pub async fn static_handler(uri: Uri, headers: HeaderMap) -> Response {
+    let path = uri.path().trim_start_matches('/');
+
+    // Never shadow API routes with the SPA
+    if path.starts_with("api/") || path.starts_with("ws/") {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let path = if path.is_empty() { INDEX } else { path };
+
+    if let Some(file) = Assets::get(path) {
+        return serve_file(path, file, &headers);
+    }
+
+    // Missing assets should 404 rather than return HTML
+    if path.rsplit('/').next().is_some_and(|name| name.contains('.')) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    match Assets::get(INDEX) {
+        Some(file) => serve_file(INDEX, file, &headers),
+        None => {
+            tracing::warn!("Frontend assets were not embedded, build fossdb-client first");
+            StatusCode::NOT_FOUND.into_response()
+        }
+    }
+}
+
+fn serve_file(path: &str, file: EmbeddedFile, headers: &HeaderMap) -> Response {
+    let etag = format!(
+        "\"{}\"",
+        file.metadata
+            .sha256_hash()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>()
+    );
+
+    if headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v == etag)
+    {
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+    }
+
+    (
+        [
+            (header::CONTENT_TYPE, file.metadata.mimetype().to_string()),
+            (header::CACHE_CONTROL, cache_control(path).to_string()),
+            (header::ETAG, etag),
+        ],
+        file.data,
+    )
+        .into_response()
+}
+
+fn cache_control(path: &str) -> &'static str {
+    if path == INDEX {
+        // Always revalidate so new deployments are picked up immediately
+        "no-cache"
+    } else if path.starts_with("assets/") {
+        // Dioxus fingerprints everything under assets/
+        "public, max-age=31536000, immutable"
+    } else {
+        "public, max-age=3600"
+    }
+}
@@ -0,0 +1,339 @@
+//! GitHub App that keeps projects in step with lockfiles in the repositories
+//! it's installed on
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::config::Config;
+use crate::storage::Storage;
+use crate::{Project, ProjectDiff, ProjectSync, projects};
+
+const API_URL: &str = "https://api.github.com";
+
+/// Claims of the JWT the app authenticates as itself with
+#[derive(Serialize)]
+struct AppClaims {
+    iat: i64,
+    exp: i64,
+    iss: String,
+}
+
+#[derive(Deserialize)]
+struct AccessToken {
+    token: String,
+}
+
+#[derive(Deserialize)]
+struct OauthResponse {
+    access_token: Option<String>,
+    error_description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct UserInstallations {
+    installations: Vec<UserInstallation>,
+}
+
+#[derive(Deserialize)]
+struct UserInstallation {
+    id: u64,
+    account: Account,
+}
+
+#[derive(Deserialize)]
+struct Account {
+    login: String,
+}
+
+/// The parts of a `push` webhook event used for syncing
+#[derive(Debug, Deserialize)]
+pub struct PushEvent {
+    #[serde(rename = "ref")]
+    pub reference: String,
+    /// Commit the branch now points at
+    pub after: String,
+    pub repository: Repository,
+    pub installation: Option<Installation>,
+    #[serde(default)]
+    pub commits: Vec<Commit>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Repository {
+    pub full_name: String,
+    pub default_branch: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Installation {
+    pub id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Commit {
+    #[serde(default)]
+    pub added: Vec<String>,
+    #[serde(default)]
+    pub modified: Vec<String>,
+}
+
+impl PushEvent {
+    /// Whether the push moved the default branch and added or changed `path`
+    pub fn touches(&self, path: &str) -> bool {
+        self.reference == format!("refs/heads/{}", self.repository.default_branch)
+            && self
+                .commits
+                .iter()
+                .any(|c| c.added.iter().chain(&c.modified).any(|p| p == path))
+    }
+}
+
+/// Whether `signature`, an `X-Hub-Signature-256` header, is the HMAC of `body`
+/// under `secret`
+pub fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let Some(expected) = signature
+        .strip_prefix("sha256=")
+        .and_then(|hex| hex::decode(hex).ok())
+    else {
+        return false;
+    };
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+pub struct GithubApp {
+    client: reqwest::Client,
+    app_id: u64,
+    private_key: EncodingKey,
+    client_id: String,
+    client_secret: String,
+    webhook_secret: String,
+}
+
+impl GithubApp {
+    pub fn new(config: &Config, client: reqwest::Client) -> Result<Self> {
+        let key_path = config
+            .github_app_private_key_path
+            .as_ref()
+            .context("GITHUB_APP_PRIVATE_KEY_PATH is required for the GitHub App")?;
+        let pem = std::fs::read(key_path)
+            .with_context(|| format!("Failed to read GitHub App private key {}", key_path))?;
+        Ok(Self {
+            client,
+            app_id: config
+                .github_app_id
+                .context("GITHUB_APP_ID is required for the GitHub App")?,
+            private_key: EncodingKey::from_rsa_pem(&pem)
+                .context("GitHub App private key is not an RSA PEM key")?,
+            client_id: config
+                .github_app_client_id
+                .clone()
+                .context("GITHUB_APP_CLIENT_ID is required for the GitHub App")?,
+            client_secret: config
+                .github_app_client_secret
+                .clone()
+                .context("GITHUB_APP_CLIENT_SECRET is required for the GitHub App")?,
+            webhook_secret: config
+                .github_webhook_secret
+                .clone()
+                .context("GITHUB_WEBHOOK_SECRET is required for the GitHub App")?,
+        })
+    }
+
+    pub fn verify_signature(&self, body: &[u8], signature: &str) -> bool {
+        verify_signature(&self.webhook_secret, body, signature)
+    }
+
+    fn api(&self, method: reqwest::Method, path: &str, token: &str) -> reqwest::RequestBuilder {
+        self.client
+            .request(method, format!("{}{}", API_URL, path))
+            .header("Accept", "application/vnd.github+json")
+            .bearer_auth(token)
+    }
+
+    /// A short-lived token for acting as the app itself
+    fn app_token(&self) -> Result<String> {
+        let now = Utc::now().timestamp();
+        let claims = AppClaims {
+            // Backdated for clock drift, GitHub allows at most ten minutes
+            iat: now - 60,
+            exp: now + 9 * 60,
+            iss: self.app_id.to_string(),
+        };
+        Ok(jsonwebtoken::encode(
+            &Header::new(Algorithm::RS256),
+            &claims,
+            &self.private_key,
+        )?)
+    }
+
+    async fn installation_token(&self, installation_id: u64) -> Result<String> {
+        let path = format!("/app/installations/{}/access_tokens", installation_id);
+        let token: AccessToken = self
+            .api(reqwest::Method::POST, &path, &self.app_token()?)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(token.token)
+    }
+
+    /// Contents of a file at the commit `reference`, or on the default branch.
+    /// None if there is no such file.
+    pub async fn fetch_file(
+        &self,
+        installation_id: u64,
+        repository: &str,
+        path: &str,
+        reference: Option<&str>,
+    ) -> Result<Option<String>> {
+        let token = self.installation_token(installation_id).await?;
+        let mut url = format!("{}/repos/{}/contents/{}", API_URL, repository, path);
+        if let Some(reference) = reference {
+            url.push_str(&format!("?ref={}", reference));
+        }
+
+        // The raw media type returns the file itself rather than base64 JSON
+        let response = self
+            .client
+            .get(url)
+            .header("Accept", "application/vnd.github.raw+json")
+            .bearer_auth(token)
+            .send()
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(response.error_for_status()?.text().await?))
+    }
+
+    /// Installations the user who authorized `code` can access, with the
+    /// login of the account each is on
+    pub async fn user_installations(&self, code: &str) -> Result<Vec<(u64, String)>> {
+        let oauth: OauthResponse = self
+            .client
+            .post("https://github.com/login/oauth/access_token")
+            .header("Accept", "application/json")
+            .json(&serde_json::json!({
+                "client_id": self.client_id,
+                "client_secret": self.client_secret,
+                "code": code,
+            }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let Some(user_token) = oauth.access_token else {
+            bail!(
+                "{}",
+                oauth
+                    .error_description
+                    .unwrap_or_else(|| "authorization failed".to_string())
+            );
+        };
+
+        let response: UserInstallations = self
+            .api(reqwest::Method::GET, "/user/installations", &user_token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(response
+            .installations
+            .into_iter()
+            .map(|i| (i.id, i.account.login))
+            .collect())
+    }
+
+    /// Replace a project's dependencies with those in its lockfile at
+    /// `reference`, or on the default branch. The outcome is recorded on the
+    /// sync either way.
+    pub async fn sync_project(
+        &self,
+        db: &dyn Storage,
+        mut sync: ProjectSync,
+        reference: Option<&str>,
+    ) -> Result<ProjectDiff> {
+        let contents = self
+            .fetch_file(
+                sync.installation_id,
+                &sync.repository,
+                &sync.path,
+                reference,
+            )
+            .await;
+        let result = contents.and_then(|contents| {
+            let contents = contents
+                .ok_or_else(|| anyhow!("{} not found in {}", sync.path, sync.repository))?;
+            let dependencies = projects::parse_lockfile(&contents)?;
+            let project = db
+                .get_project(sync.project_id)?
+                .context("project no longer exists")?;
+            let diff = projects::diff(&project.dependencies, &dependencies);
+            db.update_project(Project {
+                dependencies,
+                updated_at: Utc::now(),
+                ..project
+            })?;
+            Ok(diff)
+        });
+
+        sync.synced_at = Some(Utc::now());
+        match &result {
+            Ok(_) => {
+                sync.error = None;
+                sync.last_commit = reference.map(str::to_string);
+            }
+            Err(e) => sync.error = Some(e.to_string()),
+        }
+        db.set_project_sync(sync)?;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_signature() {
+        // Example from GitHub's webhook documentation
+        let secret = "It's a Secret to Everybody";
+        let signature = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+        assert!(verify_signature(secret, b"Hello, World!", signature));
+        assert!(!verify_signature(secret, b"Hello, World?", signature));
+        assert!(!verify_signature(secret, b"Hello, World!", "sha256=zz"));
+    }
+
+    #[test]
+    fn test_push_touches() {
+        let push: PushEvent = serde_json::from_value(serde_json::json!({
+            "ref": "refs/heads/main",
+            "after": "abc123",
+            "repository": { "full_name": "fossable/fossdb", "default_branch": "main" },
+            "installation": { "id": 42 },
+            "commits": [
+                { "added": [], "modified": ["README.md"] },
+                { "added": [], "modified": ["Cargo.lock"] },
+            ],
+        }))
+        .unwrap();
+        assert!(push.touches("Cargo.lock"));
+        assert!(!push.touches("fossdb-client/Cargo.lock"));
+
+        let branch = PushEvent {
+            reference: "refs/heads/feature".to_string(),
+            ..push
+        };
+        assert!(!branch.touches("Cargo.lock"));
+    }
+}
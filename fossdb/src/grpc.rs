@@ -0,0 +1,292 @@
+// gRPC mirror of the package read APIs, for internal services that prefer
+// protobuf to JSON and WebSockets. See proto/fossdb.proto.
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use std::pin::Pin;
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+
+use crate::handlers::packages::dependency_graph;
+use crate::storage::Storage;
+use crate::websocket::TimelineBroadcaster;
+use crate::{Dependency, DependencyGraph, EventType, Package, PackageVersion, TimelineEvent};
+
+pub mod proto {
+    tonic::include_proto!("fossdb.v1");
+}
+
+use proto::get_package_request;
+use proto::packages_server::{Packages, PackagesServer};
+
+pub struct PackagesService {
+    db: Arc<dyn Storage>,
+    broadcaster: Arc<TimelineBroadcaster>,
+}
+
+impl PackagesService {
+    pub fn new(db: Arc<dyn Storage>, broadcaster: Arc<TimelineBroadcaster>) -> Self {
+        Self { db, broadcaster }
+    }
+
+    pub fn into_server(self) -> PackagesServer<Self> {
+        PackagesServer::new(self)
+    }
+}
+
+fn internal(e: anyhow::Error) -> Status {
+    tracing::error!("gRPC request failed: {:#}", e);
+    Status::internal("internal error")
+}
+
+fn timestamp(time: DateTime<Utc>) -> Option<prost_types::Timestamp> {
+    Some(prost_types::Timestamp {
+        seconds: time.timestamp(),
+        nanos: time.timestamp_subsec_nanos() as i32,
+    })
+}
+
+fn to_proto_package(package: Package, subscriber_count: u64) -> proto::Package {
+    proto::Package {
+        id: package.id,
+        name: package.name,
+        description: package.description,
+        homepage: package.homepage,
+        repository: package.repository,
+        license: package.license,
+        tags: package.tags,
+        created_at: timestamp(package.created_at),
+        updated_at: timestamp(package.updated_at),
+        platform: package.platform,
+        language: package.language,
+        status: package.status,
+        dependents_count: package.dependents_count,
+        rank: package.rank,
+        subscriber_count,
+    }
+}
+
+impl From<Dependency> for proto::Dependency {
+    fn from(dependency: Dependency) -> Self {
+        Self {
+            name: dependency.name,
+            version_requirement: dependency.version_requirement,
+            dependency_type: dependency.dependency_type,
+            optional: dependency.optional,
+        }
+    }
+}
+
+impl From<PackageVersion> for proto::PackageVersion {
+    fn from(version: PackageVersion) -> Self {
+        Self {
+            id: version.id,
+            package_id: version.package_id,
+            version: version.version,
+            release_date: timestamp(version.release_date),
+            download_url: version.download_url,
+            checksum: version.checksum,
+            dependencies: version.dependencies.into_iter().map(Into::into).collect(),
+            vulnerabilities: version.vulnerabilities,
+            changelog: version.changelog,
+            license: version.license,
+            size: version.size,
+        }
+    }
+}
+
+impl From<DependencyGraph> for proto::DependencyGraph {
+    fn from(graph: DependencyGraph) -> Self {
+        Self {
+            root: graph.root as u64,
+            nodes: graph
+                .nodes
+                .into_iter()
+                .map(|node| proto::DependencyNode {
+                    id: node.id as u64,
+                    package_id: node.package_id,
+                    name: node.name,
+                    version: node.version,
+                    vulnerabilities: node.vulnerabilities,
+                })
+                .collect(),
+            edges: graph
+                .edges
+                .into_iter()
+                .map(|edge| proto::DependencyEdge {
+                    from: edge.from as u64,
+                    to: edge.to as u64,
+                    version_requirement: edge.version_requirement,
+                    dependency_type: edge.dependency_type,
+                    optional: edge.optional,
+                })
+                .collect(),
+            truncated: graph.truncated,
+        }
+    }
+}
+
+impl From<TimelineEvent> for proto::TimelineEvent {
+    fn from(event: TimelineEvent) -> Self {
+        let event_type = match event.event_type {
+            EventType::NewRelease => proto::EventType::NewRelease,
+            EventType::SecurityAlert => proto::EventType::SecurityAlert,
+            EventType::PackageAdded => proto::EventType::PackageAdded,
+            EventType::PackageUpdated => proto::EventType::PackageUpdated,
+            EventType::MaintainerChanged => proto::EventType::MaintainerChanged,
+            EventType::PackageDeprecated => proto::EventType::PackageDeprecated,
+        };
+        Self {
+            id: event.id,
+            package_id: event.package_id,
+            event_type: event_type.into(),
+            package_name: event.package_name,
+            version: event.version,
+            message: event.message,
+            metadata: event.metadata,
+            created_at: timestamp(event.created_at),
+        }
+    }
+}
+
+type TimelineStream = Pin<Box<dyn Stream<Item = Result<proto::TimelineEvent, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl Packages for PackagesService {
+    async fn get_package(
+        &self,
+        request: Request<proto::GetPackageRequest>,
+    ) -> Result<Response<proto::Package>, Status> {
+        let package = match request.into_inner().package {
+            Some(get_package_request::Package::Id(id)) => self.db.get_package(id),
+            Some(get_package_request::Package::Name(name)) => self.db.find_package(&name),
+            None => return Err(Status::invalid_argument("package id or name is required")),
+        }
+        .and_then(|p| self.db.published(p))
+        .map_err(internal)?
+        .ok_or_else(|| Status::not_found("package not found"))?;
+
+        let subscriber_count = self
+            .db
+            .get_subscriber_count(&package.name)
+            .map_err(internal)?;
+        Ok(Response::new(to_proto_package(package, subscriber_count)))
+    }
+
+    async fn list_versions(
+        &self,
+        request: Request<proto::ListVersionsRequest>,
+    ) -> Result<Response<proto::ListVersionsResponse>, Status> {
+        let package_id = request.into_inner().package_id;
+        self.db
+            .get_package(package_id)
+            .and_then(|p| self.db.published(p))
+            .map_err(internal)?
+            .ok_or_else(|| Status::not_found("package not found"))?;
+
+        let versions = self
+            .db
+            .get_versions_by_package(package_id)
+            .map_err(internal)?;
+        Ok(Response::new(proto::ListVersionsResponse {
+            versions: versions.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn resolve_dependencies(
+        &self,
+        request: Request<proto::ResolveDependenciesRequest>,
+    ) -> Result<Response<proto::DependencyGraph>, Status> {
+        let request = request.into_inner();
+        let depth = (request.depth as usize).clamp(1, 5);
+        let graph = dependency_graph(
+            &*self.db,
+            request.package_id,
+            request.version.as_deref(),
+            depth,
+            request.include_dev,
+        )
+        .map_err(internal)?
+        .ok_or_else(|| Status::not_found("package version not found"))?;
+        Ok(Response::new(graph.into()))
+    }
+
+    type StreamTimelineStream = TimelineStream;
+
+    async fn stream_timeline(
+        &self,
+        request: Request<proto::StreamTimelineRequest>,
+    ) -> Result<Response<Self::StreamTimelineStream>, Status> {
+        let package_id = request.into_inner().package_id;
+        let events = self
+            .broadcaster
+            .global_events()
+            .filter(move |event| {
+                std::future::ready(package_id.is_none_or(|id| id == event.package_id))
+            })
+            .map(|event| Ok(event.into()));
+        Ok(Response::new(Box::pin(events)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::fixtures::package;
+
+
+    #[tokio::test]
+    async fn test_get_package_by_name() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        db.insert_package(package("serde_json")).unwrap();
+        let serde = db.insert_package(package("serde")).unwrap();
+        let service = PackagesService::new(db, Arc::new(TimelineBroadcaster::new()));
+
+        let request = proto::GetPackageRequest {
+            package: Some(get_package_request::Package::Name("serde".to_string())),
+        };
+        let found = service.get_package(Request::new(request)).await.unwrap();
+        assert_eq!(found.get_ref().id, serde.id);
+
+        let request = proto::GetPackageRequest {
+            package: Some(get_package_request::Package::Name("tokio".to_string())),
+        };
+        let missing = service.get_package(Request::new(request)).await.unwrap_err();
+        assert_eq!(missing.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_pending_packages_are_hidden() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        let seeded = crate::fixtures::seed(&*db).unwrap();
+        db.hold_packages(vec![seeded.tokio.id]).unwrap();
+        let service = PackagesService::new(db, Arc::new(TimelineBroadcaster::new()));
+
+        let request = proto::ListVersionsRequest {
+            package_id: seeded.serde.id,
+        };
+        let listed = service.list_versions(Request::new(request)).await.unwrap();
+        assert_eq!(listed.get_ref().versions.len(), 3);
+
+        let request = proto::ListVersionsRequest {
+            package_id: seeded.tokio.id,
+        };
+        let held = service
+            .list_versions(Request::new(request))
+            .await
+            .unwrap_err();
+        assert_eq!(held.code(), tonic::Code::NotFound);
+
+        let request = proto::ResolveDependenciesRequest {
+            package_id: seeded.tokio.id,
+            version: None,
+            depth: 1,
+            include_dev: false,
+        };
+        let held = service
+            .resolve_dependencies(Request::new(request))
+            .await
+            .unwrap_err();
+        assert_eq!(held.code(), tonic::Code::NotFound);
+    }
+}
@@ -0,0 +1,211 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::collector_status::CollectorStatus;
+use crate::cpe::Cpe;
+use crate::quality::{self, PackageQuality, SourceQuality};
+use crate::validation::ValidatedJson;
+use crate::{
+    AppState, CollectorRun, CpeMapping, CpeMappingRequest, CpeMappingSource, QuarantinedPackage,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct DataQualityQuery {
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct DataQualityResponse {
+    pub total_packages: u64,
+    pub average_score: f32,
+    pub sources: Vec<SourceQuality>,
+    pub lowest_scoring: Vec<PackageQuality>,
+}
+
+pub async fn get_data_quality(
+    Query(params): Query<DataQualityQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<DataQualityResponse>, StatusCode> {
+    let packages = state
+        .db
+        .get_all_packages()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let versions = state
+        .db
+        .get_all_versions()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let with_versions: HashSet<u64> = versions.iter().map(|v| v.package_id).collect();
+
+    let mut scores: Vec<PackageQuality> = packages
+        .iter()
+        .map(|pkg| quality::score_package(pkg, with_versions.contains(&pkg.id)))
+        .collect();
+
+    let total = scores.len() as u64;
+    let average_score = if total > 0 {
+        scores.iter().map(|s| s.score as u64).sum::<u64>() as f32 / total as f32
+    } else {
+        0.0
+    };
+
+    let sources = quality::aggregate_by_source(&scores);
+
+    let limit = params.limit.unwrap_or(20).min(100);
+    scores.sort_by_key(|s| s.score);
+    scores.truncate(limit);
+
+    Ok(Json(DataQualityResponse {
+        total_packages: total,
+        average_score,
+        sources,
+        lowest_scoring: scores,
+    }))
+}
+
+/// Most recent run of every background collector
+pub async fn get_collector_status(State(state): State<AppState>) -> Json<Vec<CollectorStatus>> {
+    Json(state.collectors.snapshot())
+}
+
+pub async fn list_cpe_mappings(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<CpeMapping>>, StatusCode> {
+    state
+        .db
+        .get_all_cpe_mappings()
+        .map(Json)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Map a CPE's vendor and product to a package, overriding any guess
+pub async fn set_cpe_mapping(
+    State(state): State<AppState>,
+    ValidatedJson(payload): ValidatedJson<CpeMappingRequest>,
+) -> Result<Json<CpeMapping>, StatusCode> {
+    let cpe = Cpe::parse(&payload.cpe).ok_or(StatusCode::BAD_REQUEST)?;
+    match state.db.get_package(payload.package_id) {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+
+    state
+        .db
+        .insert_cpe_mapping(CpeMapping {
+            id: 0,
+            vendor_product: cpe.vendor_product(),
+            package_id: payload.package_id,
+            source: CpeMappingSource::Curated,
+            created_at: Utc::now(),
+        })
+        .map(Json)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+pub async fn delete_cpe_mapping(Path(id): Path<String>, State(state): State<AppState>) -> StatusCode {
+    let Ok(id) = id.parse::<u64>() else {
+        return StatusCode::BAD_REQUEST;
+    };
+
+    match state.db.delete_cpe_mapping(id) {
+        Ok(true) => StatusCode::NO_CONTENT,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CollectorRunsQuery {
+    collector: Option<String>,
+    /// Only runs with anomalies
+    #[serde(default)]
+    anomalous: bool,
+    limit: Option<usize>,
+}
+
+/// Recorded collector runs, most recent first
+pub async fn list_collector_runs(
+    Query(params): Query<CollectorRunsQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<CollectorRun>>, StatusCode> {
+    let runs = match &params.collector {
+        Some(collector) => state.db.get_collector_runs(collector),
+        None => state.db.get_all_collector_runs(),
+    }
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut runs: Vec<CollectorRun> = runs
+        .into_iter()
+        .filter(|run| !params.anomalous || !run.anomalies.is_empty())
+        .collect();
+    runs.sort_by_key(|run| std::cmp::Reverse(run.id));
+    runs.truncate(params.limit.unwrap_or(50).min(500));
+    Ok(Json(runs))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QuarantineQuery {
+    admitted: Option<bool>,
+    platform: Option<String>,
+}
+
+/// Packages collectors rejected under the license policy, most recently seen first
+pub async fn list_quarantined_packages(
+    Query(params): Query<QuarantineQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<QuarantinedPackage>>, StatusCode> {
+    let mut packages: Vec<QuarantinedPackage> = state
+        .db
+        .get_all_quarantined_packages()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .filter(|q| params.admitted.is_none_or(|admitted| q.admitted == admitted))
+        .filter(|q| params.platform.is_none() || q.platform == params.platform)
+        .collect();
+    packages.sort_by_key(|q| std::cmp::Reverse(q.last_seen));
+    Ok(Json(packages))
+}
+
+/// Let a quarantined package in on the next collection run despite its license
+pub async fn admit_quarantined_package(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<QuarantinedPackage>, StatusCode> {
+    let id = id.parse::<u64>().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let mut package = match state.db.get_quarantined_package(id) {
+        Ok(Some(package)) => package,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    package.admitted = true;
+    state
+        .db
+        .update_quarantined_package(package.clone())
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(package))
+}
+
+/// Forget a quarantined package. It's quarantined again if collectors still
+/// reject it.
+pub async fn delete_quarantined_package(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> StatusCode {
+    let Ok(id) = id.parse::<u64>() else {
+        return StatusCode::BAD_REQUEST;
+    };
+
+    match state.db.delete_quarantined_package(id) {
+        Ok(true) => StatusCode::NO_CONTENT,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
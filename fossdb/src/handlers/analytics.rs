@@ -1,69 +1,110 @@
-use crate::AppState;
-use axum::{extract::State, http::StatusCode, response::Json};
-use serde::Serialize;
-
-#[derive(Serialize)]
-pub struct DatabaseStats {
-    pub total_packages: u64,
-    pub total_versions: u64,
-    pub total_users: u64,
-    pub total_vulnerabilities: u64,
-    pub total_timeline_events: u64,
-    pub collectors_running: Vec<String>,
-}
+use crate::rollups::{self, Granularity};
+use crate::{
+    AnalyticsResponse, AppState, CollectorQueues, DatabaseStats, GrowthPoint, LanguageStats,
+    LicenseStats, RollupPoint, SecurityStats, TrendingPackage, UpstreamRate,
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::Deserialize;
 
-#[derive(Serialize)]
-pub struct AnalyticsResponse {
-    pub total_packages: u64,
-    pub programming_languages: u64,
-    pub weekly_updates: u64,
-    pub language_distribution: Vec<LanguageStats>,
-    pub license_distribution: Vec<LicenseStats>,
-    pub trending_packages: Vec<TrendingPackage>,
-    pub security_overview: SecurityStats,
-    pub growth_data: Vec<GrowthPoint>,
+/// Time window for historical analytics
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Period {
+    Week,
+    #[default]
+    Month,
+    Year,
 }
 
-#[derive(Serialize)]
-pub struct LanguageStats {
-    pub language: String,
-    pub percentage: f32,
-    pub count: u64,
+impl Period {
+    /// Width of each bucket in days and the number of buckets
+    fn buckets(self) -> (i64, i64) {
+        match self {
+            Period::Week => (1, 7),
+            Period::Month => (1, 30),
+            Period::Year => (7, 52),
+        }
+    }
 }
 
-#[derive(Serialize)]
-pub struct LicenseStats {
-    pub license: String,
-    pub percentage: f32,
-    pub count: u64,
+impl std::str::FromStr for Period {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "week" => Ok(Self::Week),
+            "month" => Ok(Self::Month),
+            "year" => Ok(Self::Year),
+            _ => Err(format!("Unknown period: {}", s)),
+        }
+    }
 }
 
-#[derive(Serialize)]
-pub struct TrendingPackage {
-    pub name: String,
-    pub description: String,
-    pub growth_percentage: f32,
-    pub category: String,
+#[derive(Debug, Deserialize)]
+pub struct PeriodQuery {
+    /// One of `week`, `month` (default) or `year`
+    period: Option<String>,
 }
 
-#[derive(Serialize)]
-pub struct SecurityStats {
-    pub clean_packages: u64,
-    pub minor_issues: u64,
-    pub critical_vulnerabilities: u64,
-    pub scan_coverage: f32,
+impl PeriodQuery {
+    fn period(&self) -> Result<Period, StatusCode> {
+        self.period
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+            .map(Option::unwrap_or_default)
+            .map_err(|_| StatusCode::BAD_REQUEST)
+    }
 }
 
-#[derive(Serialize)]
-pub struct GrowthPoint {
-    pub date: String,
-    pub packages_added: u64,
-    pub cumulative_total: u64,
+/// Bucket creation times into packages added per interval, ending today
+fn growth_history(
+    created: impl Iterator<Item = DateTime<Utc>>,
+    period: Period,
+    now: DateTime<Utc>,
+) -> Vec<GrowthPoint> {
+    let (width, count) = period.buckets();
+    let today = now.date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc();
+    let start = today - Duration::days(width * (count - 1));
+
+    let mut baseline = 0;
+    let mut added = vec![0u64; count as usize];
+    for created_at in created {
+        if created_at < start {
+            baseline += 1;
+        } else {
+            let bucket = ((created_at - start).num_days() / width).min(count - 1);
+            added[bucket as usize] += 1;
+        }
+    }
+
+    let mut cumulative_total = baseline;
+    added
+        .into_iter()
+        .enumerate()
+        .map(|(i, packages_added)| {
+            cumulative_total += packages_added;
+            GrowthPoint {
+                date: (start + Duration::days(width * i as i64))
+                    .format("%Y-%m-%d")
+                    .to_string(),
+                packages_added,
+                cumulative_total,
+            }
+        })
+        .collect()
 }
 
 pub async fn get_analytics(
+    Query(params): Query<PeriodQuery>,
     State(state): State<AppState>,
 ) -> Result<Json<AnalyticsResponse>, StatusCode> {
+    let period = params.period()?;
+
     // Fetch real data from database
     let packages = state
         .db
@@ -102,7 +143,7 @@ pub async fn get_analytics(
             count,
         })
         .collect();
-    language_distribution.sort_by(|a, b| b.count.cmp(&a.count));
+    language_distribution.sort_by_key(|s| std::cmp::Reverse(s.count));
 
     // Build license distribution
     let mut license_distribution: Vec<LicenseStats> = license_counts
@@ -117,7 +158,7 @@ pub async fn get_analytics(
             count,
         })
         .collect();
-    license_distribution.sort_by(|a, b| b.count.cmp(&a.count));
+    license_distribution.sort_by_key(|s| std::cmp::Reverse(s.count));
 
     // Calculate security stats from real vulnerabilities
     let critical_vulns = vulnerabilities
@@ -155,20 +196,75 @@ pub async fn get_analytics(
         })
         .collect();
 
+    let now = Utc::now();
     let analytics = AnalyticsResponse {
         total_packages: total,
         programming_languages: language_distribution.len() as u64,
-        weekly_updates: 0, // Would need historical tracking
+        weekly_updates: packages
+            .iter()
+            .filter(|p| p.updated_at >= now - Duration::days(7))
+            .count() as u64,
         language_distribution,
         license_distribution,
         trending_packages,
         security_overview,
-        growth_data: vec![], // Would need historical tracking
+        growth_data: growth_history(packages.iter().map(|p| p.created_at), period, now),
     };
 
     Ok(Json(analytics))
 }
 
+pub async fn get_growth(
+    Query(params): Query<PeriodQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<GrowthPoint>>, StatusCode> {
+    let period = params.period()?;
+    let packages = state
+        .db
+        .get_all_packages()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(growth_history(
+        packages.iter().map(|p| p.created_at),
+        period,
+        Utc::now(),
+    )))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RollupQuery {
+    /// First day included, 29 days before `to` by default
+    from: Option<NaiveDate>,
+    /// Last day included, today by default
+    to: Option<NaiveDate>,
+    /// One of `day` (default), `week` or `month`
+    granularity: Option<String>,
+}
+
+/// Packages added, versions released and vulnerabilities published over a
+/// range of days
+pub async fn get_rollups(
+    Query(params): Query<RollupQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<RollupPoint>>, StatusCode> {
+    let granularity: Granularity = params
+        .granularity
+        .as_deref()
+        .map(str::parse)
+        .transpose()
+        .map_err(|_| StatusCode::BAD_REQUEST)?
+        .unwrap_or_default();
+    let to = params.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = params.from.unwrap_or(to - Duration::days(29));
+    if from > to || (to - from).num_days() >= rollups::MAX_RANGE_DAYS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    rollups::series(&*state.db, from, to, granularity)
+        .map(Json)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
 pub async fn get_language_trends(
     State(state): State<AppState>,
 ) -> Result<Json<Vec<LanguageStats>>, StatusCode> {
@@ -198,7 +294,7 @@ pub async fn get_language_trends(
             count,
         })
         .collect();
-    trends.sort_by(|a, b| b.count.cmp(&a.count));
+    trends.sort_by_key(|s| std::cmp::Reverse(s.count));
 
     Ok(Json(trends))
 }
@@ -265,14 +361,177 @@ pub async fn get_db_stats(
         .get_all_timeline_events()
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    let pending = state
+        .db
+        .get_pending_packages()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let quarantined = state
+        .db
+        .get_all_quarantined_packages()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let email_queue = state
+        .db
+        .get_pending_notifications()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
     let stats = DatabaseStats {
         total_packages: packages.len() as u64,
         total_versions: versions.len() as u64,
         total_users: users.len() as u64,
         total_vulnerabilities: vulnerabilities.len() as u64,
         total_timeline_events: timeline_events.len() as u64,
-        collectors_running: vec!["crates.io".to_string()],
+        collectors_running: state.collectors.running(),
+        collectors: state.collectors.summaries(),
+        collector_queues: CollectorQueues {
+            pending_moderation: pending.len() as u64,
+            quarantined: quarantined.iter().filter(|p| !p.admitted).count() as u64,
+        },
+        upstream_rates: state
+            .collectors
+            .upstream_rates()
+            .into_iter()
+            .map(|(host, requests_per_second)| UpstreamRate {
+                host,
+                requests_per_second,
+            })
+            .collect(),
+        websocket_clients: state.broadcaster.client_count() as u64,
+        email_queue: email_queue.len() as u64,
     };
 
     Ok(Json(stats))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector_status::CollectorState;
+    use crate::db::Database;
+    use crate::fixtures::{self, TestClient};
+    use crate::storage::Storage;
+    use crate::{EventType, QuarantinedPackage, TimelineEvent};
+    use axum::{Router, routing::get};
+    use chrono::TimeZone;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_growth_history_buckets() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 10, 12, 0, 0).unwrap();
+        let created = [
+            Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2024, 3, 4, 8, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2024, 3, 10, 9, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2024, 3, 10, 11, 0, 0).unwrap(),
+        ];
+
+        let history = growth_history(created.into_iter(), Period::Week, now);
+
+        assert_eq!(history.len(), 7);
+        assert_eq!(history[0].date, "2024-03-04");
+        assert_eq!(history[0].packages_added, 1);
+        assert_eq!(history[0].cumulative_total, 2);
+        assert_eq!(history[6].date, "2024-03-10");
+        assert_eq!(history[6].packages_added, 2);
+        assert_eq!(history[6].cumulative_total, 4);
+    }
+
+    #[tokio::test]
+    async fn test_db_stats() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        let seeded = fixtures::seed(&*db).unwrap();
+        db.hold_packages(vec![seeded.tokio.id]).unwrap();
+        db.quarantine_packages(vec![QuarantinedPackage {
+            id: 0,
+            name: "leftpad".to_string(),
+            platform: None,
+            license: Some("Proprietary".to_string()),
+            reason: "license not allowed".to_string(),
+            admitted: false,
+            first_seen: Utc::now(),
+            last_seen: Utc::now(),
+        }])
+        .unwrap();
+        db.insert_timeline_event(TimelineEvent {
+            id: 0,
+            package_id: seeded.serde.id,
+            user_id: Some(seeded.alice.id),
+            event_type: EventType::NewRelease,
+            package_name: "serde".to_string(),
+            version: Some("1.1.0".to_string()),
+            message: String::new(),
+            metadata: None,
+            created_at: Utc::now(),
+            notified_at: None,
+        })
+        .unwrap();
+        let state = fixtures::app_state(db);
+        state.collectors.started("crates.io");
+        state.collectors.set_upstream_rate("libraries.io", 15);
+        let client = TestClient::new(
+            Router::new()
+                .route("/api/stats", get(get_db_stats))
+                .with_state(state),
+        );
+
+        let stats: DatabaseStats =
+            serde_json::from_value(client.get("/api/stats").await.body).unwrap();
+        assert_eq!(stats.collectors_running, vec!["crates.io".to_string()]);
+        assert_eq!(stats.collectors[0].name, "crates.io");
+        assert_eq!(stats.collectors[0].state, CollectorState::Running);
+        assert_eq!(
+            stats.collector_queues,
+            CollectorQueues {
+                pending_moderation: 1,
+                quarantined: 1,
+            }
+        );
+        assert_eq!(
+            stats.upstream_rates,
+            vec![UpstreamRate {
+                host: "libraries.io".to_string(),
+                requests_per_second: 15,
+            }]
+        );
+        assert_eq!(stats.websocket_clients, 0);
+        assert_eq!(stats.email_queue, 1);
+    }
+
+    #[tokio::test]
+    async fn test_rollups() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        fixtures::seed(&*db).unwrap();
+        rollups::rebuild(&*db).unwrap();
+        let client = TestClient::new(
+            Router::new()
+                .route("/api/analytics/rollups", get(get_rollups))
+                .with_state(fixtures::app_state(db)),
+        );
+
+        let months = client
+            .get("/api/analytics/rollups?from=2024-01-01&to=2024-12-31&granularity=month")
+            .await;
+        let months: Vec<RollupPoint> = serde_json::from_value(months.body).unwrap();
+        assert_eq!(months.len(), 12);
+        assert_eq!(months[0].date, "2024-01-01");
+        assert!(months.iter().map(|p| p.versions_released).sum::<u64>() > 0);
+        let recent = client.get("/api/analytics/rollups").await;
+        assert_eq!(recent.body.as_array().unwrap().len(), 30);
+
+        for query in [
+            "granularity=hour",
+            "from=2024-02-01&to=2024-01-01",
+            "from=1900-01-01&to=2024-01-01",
+            "from=yesterday",
+        ] {
+            let uri = format!("/api/analytics/rollups?{query}");
+            assert_eq!(client.get(&uri).await.status, StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[test]
+    fn test_period_parse() {
+        assert_eq!("Year".parse::<Period>(), Ok(Period::Year));
+        assert!("decade".parse::<Period>().is_err());
+        assert_eq!(Period::default().buckets(), (1, 30));
+    }
+}
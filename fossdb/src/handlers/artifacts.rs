@@ -0,0 +1,92 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use tokio_util::io::ReaderStream;
+
+use crate::AppState;
+use crate::artifact_cache::{self, Fetched};
+use crate::handlers::packages::{normalize_checksum, report_checksum_mismatch};
+
+/// Serve a package artifact through the local cache.
+///
+/// Only versions with a published SHA-256 checksum are served, and upstream
+/// content is verified before it is cached. A mismatch raises a SecurityAlert
+/// and is reported as a bad gateway.
+pub async fn get_artifact(
+    Path((platform, name, version)): Path<(String, String, String)>,
+    State(state): State<AppState>,
+) -> Result<Response, StatusCode> {
+    let package = match state
+        .db
+        .get_package_by_name(&name)
+        .and_then(|package| state.db.published(package))
+    {
+        Ok(Some(package)) => package,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    if package.platform.as_deref() != Some(platform.as_str()) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let package_version = state
+        .db
+        .get_versions_by_package(package.id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .find(|v| v.version == version)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let (Some(url), Some(checksum)) = (&package_version.download_url, &package_version.checksum)
+    else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let expected = normalize_checksum(checksum);
+    if !artifact_cache::is_sha256(&expected) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let path = state
+        .artifacts
+        .path(&platform, &name, &version)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+    if !tokio::fs::try_exists(&path).await.unwrap_or(false) {
+        match state.artifacts.fetch(url, &path, &expected).await {
+            Ok(Fetched::Verified(_)) => {
+                tracing::info!("Cached artifact for {} {}", name, version);
+            }
+            Ok(Fetched::Mismatch(digest)) => {
+                if let Err(e) = report_checksum_mismatch(&state, &package, &package_version, &digest)
+                {
+                    tracing::error!("Failed to record checksum mismatch for {}: {}", name, e);
+                }
+                return Err(StatusCode::BAD_GATEWAY);
+            }
+            Err(e) => {
+                tracing::warn!("Failed to fetch artifact for {} {}: {}", name, version, e);
+                return Err(StatusCode::BAD_GATEWAY);
+            }
+        }
+    }
+
+    let file = tokio::fs::File::open(&path)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+            (header::ETAG, format!("\"{}\"", expected)),
+            (
+                header::CACHE_CONTROL,
+                "public, max-age=31536000, immutable".to_string(),
+            ),
+        ],
+        Body::from_stream(ReaderStream::new(file)),
+    )
+        .into_response())
+}
@@ -1,32 +1,20 @@
-use axum::{Form, extract::State, http::StatusCode, response::Json};
+use axum::{extract::State, http::StatusCode, response::Json};
 use chrono::Utc;
-use serde::Deserialize;
 
-use crate::{AppState, auth::*, User, RegisterRequest, LoginRequest, AuthResponse};
-
-#[derive(Debug, Deserialize)]
-pub struct LoginForm {
-    pub email: String,
-    pub password: String,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct RegisterForm {
-    pub username: String,
-    pub email: String,
-    pub password: String,
-}
+use crate::retention;
+use crate::validation::{ValidatedForm, ValidatedJson};
+use crate::{AppState, auth::*, User, Role, ProfileSettings, TimelinePreferences, RegisterRequest, LoginRequest, AuthResponse};
 
 pub async fn register(
     State(state): State<AppState>,
-    Json(payload): Json<RegisterRequest>,
+    ValidatedJson(payload): ValidatedJson<RegisterRequest>,
 ) -> Result<Json<AuthResponse>, StatusCode> {
     register_user(state, payload.username, payload.email, payload.password).await
 }
 
 pub async fn register_form(
     State(state): State<AppState>,
-    Form(payload): Form<RegisterForm>,
+    ValidatedForm(payload): ValidatedForm<RegisterRequest>,
 ) -> Result<Json<AuthResponse>, StatusCode> {
     register_user(state, payload.username, payload.email, payload.password).await
 }
@@ -48,14 +36,19 @@ async fn register_user(
         created_at: Utc::now(),
         is_verified: false,
         notifications_enabled: true, // Enable notifications by default
+        role: Role::User,
+        profile: ProfileSettings::default(),
+        last_read_event_id: None,
+        timeline: TimelinePreferences::default(),
     };
 
     let user = state
         .db
         .insert_user(user)
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    record_activity(&state, user.id);
 
-    let token = create_jwt(&user.id.to_string(), &user.username)
+    let token = create_jwt(&user.id.to_string(), &user.username, user.role)
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(Json(AuthResponse { token, user }))
@@ -63,14 +56,14 @@ async fn register_user(
 
 pub async fn login(
     State(state): State<AppState>,
-    Json(payload): Json<LoginRequest>,
+    ValidatedJson(payload): ValidatedJson<LoginRequest>,
 ) -> Result<Json<AuthResponse>, StatusCode> {
     login_user(state, payload.email, payload.password).await
 }
 
 pub async fn login_form(
     State(state): State<AppState>,
-    Form(payload): Form<LoginForm>,
+    ValidatedForm(payload): ValidatedForm<LoginRequest>,
 ) -> Result<Json<AuthResponse>, StatusCode> {
     login_user(state, payload.email, payload.password).await
 }
@@ -93,9 +86,18 @@ async fn login_user(
     if !is_valid {
         return Err(StatusCode::UNAUTHORIZED);
     }
+    record_activity(&state, user.id);
 
-    let token = create_jwt(&user.id.to_string(), &user.username)
+    let token = create_jwt(&user.id.to_string(), &user.username, user.role)
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(Json(AuthResponse { token, user }))
 }
+
+/// Keep a signed in account from expiring. Failing to doesn't stop the user
+/// signing in.
+fn record_activity(state: &AppState, user_id: u64) {
+    if let Err(e) = retention::record_activity(&*state.db, user_id, Utc::now()) {
+        tracing::warn!("Failed to record activity of user {}: {}", user_id, e);
+    }
+}
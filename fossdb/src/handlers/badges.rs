@@ -0,0 +1,214 @@
+//! Shields-style SVG badges for embedding in READMEs
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use crate::handlers::vulnerabilities::affecting_version;
+use crate::storage::Storage;
+use crate::{AppState, Package, version_range};
+
+const BLUE: &str = "#007ec6";
+const GREEN: &str = "#4c1";
+const RED: &str = "#e05d44";
+const GREY: &str = "#9f9f9f";
+
+/// Badges are cheap to render but fetched on every README view, so let
+/// caches and GitHub's image proxy hold them for a while
+const CACHE_CONTROL: &str = "public, max-age=3600";
+
+/// Rough width of `text` in 11px Verdana, which is what shields.io measures
+fn text_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c {
+            'i' | 'j' | 'l' | '.' | ',' | ':' | ';' | '|' | '!' | '\'' => 3,
+            'f' | 'r' | 't' | 'I' | ' ' | '(' | ')' | '-' => 5,
+            'm' | 'w' | 'M' | 'W' => 10,
+            c if c.is_ascii_uppercase() => 8,
+            _ => 7,
+        })
+        .sum()
+}
+
+/// Escape text for use in SVG or HTML, inside elements or quoted attributes
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// A flat two-part badge with `label` on grey and `message` on `color`
+pub fn render(label: &str, message: &str, color: &str) -> String {
+    let label_width = text_width(label) + 10;
+    let message_width = text_width(message) + 10;
+    let width = label_width + message_width;
+    let (label, message) = (escape(label), escape(message));
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}"><title>{label}: {message}</title><linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient><clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath><g clip-path="url(#r)"><rect width="{label_width}" height="20" fill="#555"/><rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/><rect width="{width}" height="20" fill="url(#s)"/></g><g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11"><text x="{label_x}" y="15" fill="#010101" fill-opacity=".3">{label}</text><text x="{label_x}" y="14">{label}</text><text x="{message_x}" y="15" fill="#010101" fill-opacity=".3">{message}</text><text x="{message_x}" y="14">{message}</text></g></svg>"##,
+        label_x = label_width / 2,
+        message_x = label_width + message_width / 2,
+    )
+}
+
+/// Serve a badge, answering revalidations whose ETag still matches
+fn badge_response(svg: String, headers: &HeaderMap) -> Response {
+    let mut hasher = DefaultHasher::new();
+    svg.hash(&mut hasher);
+    let etag = format!("\"{:016x}\"", hasher.finish());
+
+    if headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v == etag)
+    {
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+    }
+
+    (
+        [
+            (header::CONTENT_TYPE, "image/svg+xml".to_string()),
+            (header::CACHE_CONTROL, CACHE_CONTROL.to_string()),
+            (header::ETAG, etag),
+        ],
+        svg,
+    )
+        .into_response()
+}
+
+/// A published package by name, following renames
+fn lookup(db: &dyn Storage, name: &str) -> anyhow::Result<Option<Package>> {
+    db.published(db.find_package(name)?)
+}
+
+/// Latest version of a package. Unknown packages get a grey badge rather than
+/// an error so READMEs don't show a broken image.
+pub async fn version_badge(
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let package = lookup(&*state.db, &name).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let svg = match package {
+        Some(package) => {
+            let versions = state
+                .db
+                .get_versions_by_package(package.id)
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            match version_range::latest_version(&versions) {
+                Some(latest) => render("version", &format!("v{}", latest), BLUE),
+                None => render("version", "none", GREY),
+            }
+        }
+        None => render("version", "not found", GREY),
+    };
+    Ok(badge_response(svg, &headers))
+}
+
+/// Count of advisories affecting a package's latest version
+pub async fn vulnerabilities_badge(
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let package = lookup(&*state.db, &name).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let Some(package) = package else {
+        return Ok(badge_response(
+            render("vulnerabilities", "not found", GREY),
+            &headers,
+        ));
+    };
+    let versions = state
+        .db
+        .get_versions_by_package(package.id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let Some(latest) = version_range::latest_version(&versions) else {
+        return Ok(badge_response(
+            render("vulnerabilities", "unknown", GREY),
+            &headers,
+        ));
+    };
+
+    let count = affecting_version(&*state.db, package.id, &latest)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .len();
+    let svg = match count {
+        0 => render("vulnerabilities", "0", GREEN),
+        count => render("vulnerabilities", &count.to_string(), RED),
+    };
+    Ok(badge_response(svg, &headers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::fixtures::{self, TestClient};
+    use crate::{AffectedPackage, Vulnerability, VulnerabilitySeverity};
+    use axum::{Router, routing::get};
+    use chrono::Utc;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_badges() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        let seeded = fixtures::seed(&*db).unwrap();
+        let router = Router::new()
+            .route("/badge/packages/{name}/version.svg", get(version_badge))
+            .route(
+                "/badge/packages/{name}/vulnerabilities.svg",
+                get(vulnerabilities_badge),
+            )
+            .with_state(fixtures::app_state(db.clone()));
+
+        let version = TestClient::new(router.clone())
+            .get("/badge/packages/serde/version.svg")
+            .await;
+        assert_eq!(version.status, StatusCode::OK);
+        assert_eq!(version.headers[header::CONTENT_TYPE], "image/svg+xml");
+        assert!(version.headers.contains_key(header::ETAG));
+        assert!(version.text.contains("v1.1.0"));
+
+        let clean = TestClient::new(router.clone())
+            .get("/badge/packages/serde/vulnerabilities.svg")
+            .await;
+        assert!(clean.text.contains(">0<"));
+
+        let advisory = |range: &str| Vulnerability {
+            id: 0,
+            cve_id: None,
+            title: "Bad".to_string(),
+            description: String::new(),
+            severity: VulnerabilitySeverity::High,
+            affected_packages: vec![AffectedPackage {
+                package_id: seeded.serde.id,
+                version_range: range.to_string(),
+            }],
+            discovered_at: Utc::now(),
+            fixed_in: None,
+            cvss_vector: None,
+            cvss_score: None,
+            epss_score: None,
+            epss_percentile: None,
+            scores_updated_at: None,
+            informational: None,
+        };
+        // Only the first affects the latest version
+        db.insert_vulnerability(advisory(">=1.0.0")).unwrap();
+        db.insert_vulnerability(advisory("<1.1.0")).unwrap();
+        let affected = TestClient::new(router.clone())
+            .get("/badge/packages/serde/vulnerabilities.svg")
+            .await;
+        assert!(affected.text.contains(">1<"));
+        assert_ne!(affected.headers[header::ETAG], clean.headers[header::ETAG]);
+
+        let missing = TestClient::new(router)
+            .get("/badge/packages/nope/version.svg")
+            .await;
+        assert_eq!(missing.status, StatusCode::OK);
+        assert!(missing.text.contains("not found"));
+    }
+}
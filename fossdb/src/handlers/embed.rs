@@ -0,0 +1,159 @@
+//! Live package cards for blogs and documentation sites to embed
+use axum::{
+    extract::{Path, State},
+    http::{StatusCode, header},
+    response::{Html, IntoResponse, Json, Response},
+};
+use chrono::Utc;
+
+use crate::handlers::badges::escape;
+use crate::handlers::vulnerabilities::affecting_version;
+use crate::storage::Storage;
+use crate::{AppState, PackageEmbed, quality, version_range};
+
+/// Embeds are public and identical for every visitor, so they can be cached
+/// briefly by browsers and CDNs in front of the embedding site
+const CACHE_CONTROL: &str = "public, max-age=600";
+
+/// Summarise a published package. None if there is no such package.
+pub(crate) fn package_embed(db: &dyn Storage, id: u64) -> anyhow::Result<Option<PackageEmbed>> {
+    let Some(package) = db.published(db.get_package(id)?)? else {
+        return Ok(None);
+    };
+    let versions = db.get_versions_by_package(id)?;
+    let latest = version_range::latest_version(&versions)
+        .and_then(|latest| versions.into_iter().find(|v| v.version == latest));
+
+    let advisories = match &latest {
+        Some(latest) => affecting_version(db, id, &latest.version)?,
+        None => Vec::new(),
+    };
+    let severities: Vec<_> = advisories.into_iter().map(|v| v.severity).collect();
+    let health_score = quality::health_score(
+        &quality::score_package(&package, latest.is_some()),
+        latest.as_ref().map(|v| v.release_date),
+        &severities,
+        Utc::now(),
+    );
+
+    Ok(Some(PackageEmbed {
+        id: package.id,
+        latest_version: latest.map(|v| v.version),
+        license: package.license,
+        health_score,
+        open_advisories: severities.len(),
+        url: package.homepage.or(package.repository),
+        name: package.name,
+        description: package.description,
+    }))
+}
+
+/// A self-contained card with inline styles, so it renders the same inside
+/// any page or iframe
+fn render_card(embed: &PackageEmbed) -> String {
+    let name = escape(&embed.name);
+    let title = match embed.url.as_deref().filter(|url| url.starts_with("http")) {
+        Some(url) => format!(
+            r#"<a href="{}" target="_blank" rel="noopener" style="color:inherit">{}</a>"#,
+            escape(url),
+            name
+        ),
+        None => name,
+    };
+    let health_color = match embed.health_score {
+        80.. => "#2e7d32",
+        50.. => "#f9a825",
+        _ => "#c62828",
+    };
+
+    let mut card = format!(
+        r#"<div class="fossdb-card" style="font-family:system-ui,sans-serif;border:1px solid #ddd;border-radius:6px;padding:12px 16px;max-width:360px"><div style="font-weight:600;font-size:16px">{}</div>"#,
+        title
+    );
+    if let Some(description) = &embed.description {
+        card.push_str(&format!(
+            r#"<div style="color:#555;font-size:13px;margin-top:4px">{}</div>"#,
+            escape(description)
+        ));
+    }
+    card.push_str(&format!(
+        r#"<div style="font-size:13px;margin-top:8px">{} &middot; {} &middot; <span style="color:{}">health {}/100</span></div></div>"#,
+        escape(embed.latest_version.as_deref().unwrap_or("no releases")),
+        escape(embed.license.as_deref().unwrap_or("unknown license")),
+        health_color,
+        embed.health_score
+    ));
+    card
+}
+
+/// `{id}.json` for the summary itself or `{id}.html` for a card to drop into
+/// a page. The router can't capture part of a segment, hence the one route.
+pub async fn get_embed(
+    Path(file): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Response, StatusCode> {
+    let (id, extension) = file.rsplit_once('.').ok_or(StatusCode::NOT_FOUND)?;
+    let id: u64 = id.parse().map_err(|_| StatusCode::NOT_FOUND)?;
+    if extension != "json" && extension != "html" {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let embed = match package_embed(&*state.db, id) {
+        Ok(Some(embed)) => embed,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    let cache = [(header::CACHE_CONTROL, CACHE_CONTROL)];
+    Ok(match extension {
+        "json" => (cache, Json(embed)).into_response(),
+        _ => (cache, Html(render_card(&embed))).into_response(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::fixtures::{self, TestClient};
+    use axum::{Router, routing::get};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_embeds() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        let seeded = fixtures::seed(&*db).unwrap();
+        let mut serde = seeded.serde.clone();
+        serde.description = Some("<script>alert(1)</script>".to_string());
+        db.update_package(serde).unwrap();
+        let router = Router::new()
+            .route("/embed/packages/{file}", get(get_embed))
+            .with_state(fixtures::app_state(db.clone()));
+
+        let json = TestClient::new(router.clone())
+            .get(&format!("/embed/packages/{}.json", seeded.serde.id))
+            .await;
+        assert_eq!(json.status, StatusCode::OK);
+        assert_eq!(json.body["name"], "serde");
+        assert_eq!(json.body["latest_version"], "1.1.0");
+        assert_eq!(json.body["open_advisories"], 0);
+        // No advisories and complete metadata, but no release in years
+        assert_eq!(json.body["health_score"], 70);
+        assert_eq!(json.headers[header::CACHE_CONTROL], CACHE_CONTROL);
+
+        let html = TestClient::new(router.clone())
+            .get(&format!("/embed/packages/{}.html", seeded.serde.id))
+            .await;
+        assert_eq!(html.status, StatusCode::OK);
+        assert!(html.text.contains("1.1.0"));
+        assert!(html.text.contains("&lt;script&gt;"));
+        assert!(!html.text.contains("<script>"));
+
+        let client = TestClient::new(router);
+        assert_eq!(
+            client.get("/embed/packages/999.json").await.status,
+            StatusCode::NOT_FOUND
+        );
+        let path = format!("/embed/packages/{}.xml", seeded.serde.id);
+        assert_eq!(client.get(&path).await.status, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,225 @@
+use axum::{
+    body::Bytes,
+    extract::{Extension, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::Utc;
+use std::sync::Arc;
+
+use crate::github_app::{GithubApp, PushEvent};
+use crate::handlers::projects::owned_project;
+use crate::validation::{ValidatedJson, ValidationErrors};
+use crate::{
+    AppState, GithubInstallation, GithubInstallationRequest, ProjectSync, ProjectSyncRequest,
+    auth::Claims,
+};
+
+fn github_app(state: &AppState) -> Result<&Arc<GithubApp>, StatusCode> {
+    state.github_app.as_ref().ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Receives events for the GitHub App, syncing projects whose lockfile was
+/// pushed to the default branch
+pub async fn webhook(State(state): State<AppState>, headers: HeaderMap, body: Bytes) -> StatusCode {
+    let app = match github_app(&state) {
+        Ok(app) => app.clone(),
+        Err(status) => return status,
+    };
+    let signature = headers
+        .get("x-hub-signature-256")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    if !app.verify_signature(&body, signature) {
+        return StatusCode::UNAUTHORIZED;
+    }
+
+    let event = headers
+        .get("x-github-event")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    match event {
+        "installation" => {
+            let Ok(payload) = serde_json::from_slice::<serde_json::Value>(&body) else {
+                return StatusCode::BAD_REQUEST;
+            };
+            if payload["action"] == "deleted"
+                && let Some(id) = payload["installation"]["id"].as_u64()
+                && let Err(e) = state.db.delete_github_installation(id)
+            {
+                tracing::error!("Failed to remove GitHub installation {}: {}", id, e);
+                return StatusCode::INTERNAL_SERVER_ERROR;
+            }
+            StatusCode::NO_CONTENT
+        }
+        "push" => {
+            let Ok(push) = serde_json::from_slice::<PushEvent>(&body) else {
+                return StatusCode::BAD_REQUEST;
+            };
+            let syncs = match state
+                .db
+                .get_project_syncs_by_repository(&push.repository.full_name)
+            {
+                Ok(syncs) => syncs,
+                Err(_) => return StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            let syncs: Vec<ProjectSync> = syncs
+                .into_iter()
+                .filter(|sync| {
+                    push.installation.as_ref().map(|i| i.id) == Some(sync.installation_id)
+                        && push.touches(&sync.path)
+                })
+                .collect();
+            if syncs.is_empty() {
+                return StatusCode::NO_CONTENT;
+            }
+
+            // GitHub gives up on slow deliveries, so fetch the lockfiles afterwards
+            tokio::spawn(async move {
+                for sync in syncs {
+                    let project_id = sync.project_id;
+                    if let Err(e) = app.sync_project(&*state.db, sync, Some(&push.after)).await {
+                        tracing::warn!("Failed to sync project {}: {}", project_id, e);
+                    }
+                }
+            });
+            StatusCode::ACCEPTED
+        }
+        _ => StatusCode::NO_CONTENT,
+    }
+}
+
+pub async fn list_installations(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<GithubInstallation>>, StatusCode> {
+    let user_id: u64 = claims.sub.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let installations = state
+        .db
+        .get_github_installations_by_user(user_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(installations))
+}
+
+/// Connect an installation, once GitHub confirms the user can access it
+pub async fn add_installation(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<GithubInstallationRequest>,
+) -> Result<Json<GithubInstallation>, StatusCode> {
+    let user_id: u64 = claims.sub.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let app = github_app(&state)?;
+
+    let accessible = app.user_installations(&payload.code).await.map_err(|e| {
+        tracing::warn!("GitHub authorization failed: {}", e);
+        StatusCode::FORBIDDEN
+    })?;
+    let (id, account) = accessible
+        .into_iter()
+        .find(|(id, _)| *id == payload.installation_id)
+        .ok_or(StatusCode::FORBIDDEN)?;
+
+    let installation = GithubInstallation {
+        id,
+        user_id,
+        account,
+        created_at: Utc::now(),
+    };
+    state
+        .db
+        .upsert_github_installation(installation.clone())
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(installation))
+}
+
+/// Disconnect an installation, stopping the project syncs through it
+pub async fn delete_installation(
+    Path(id): Path<u64>,
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> StatusCode {
+    let Ok(user_id) = claims.sub.parse::<u64>() else {
+        return StatusCode::BAD_REQUEST;
+    };
+    match state.db.get_github_installation(id) {
+        Ok(Some(installation)) if installation.user_id == user_id => {}
+        Ok(_) => return StatusCode::NOT_FOUND,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR,
+    }
+
+    match state.db.delete_github_installation(id) {
+        Ok(true) => StatusCode::NO_CONTENT,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+pub async fn get_project_sync(
+    Path(id): Path<u64>,
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ProjectSync>, StatusCode> {
+    owned_project(&state, &claims, id)?;
+    match state.db.get_project_sync(id) {
+        Ok(Some(sync)) => Ok(Json(sync)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// Follow a lockfile in a repository, syncing from its default branch now
+/// and on every push that changes it
+pub async fn set_project_sync(
+    Path(id): Path<u64>,
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ValidatedJson(payload): ValidatedJson<ProjectSyncRequest>,
+) -> Result<Json<ProjectSync>, Response> {
+    let app = github_app(&state).map_err(IntoResponse::into_response)?;
+    let project = owned_project(&state, &claims, id).map_err(IntoResponse::into_response)?;
+    match state.db.get_github_installation(payload.installation_id) {
+        Ok(Some(installation)) if installation.user_id == project.user_id => {}
+        Ok(_) => {
+            let mut errors = ValidationErrors::new();
+            errors.add("installation_id", "is not connected to your account");
+            return Err(errors.into_response());
+        }
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response()),
+    }
+
+    let sync = ProjectSync {
+        project_id: project.id,
+        installation_id: payload.installation_id,
+        repository: payload.repository,
+        path: payload.path.trim_start_matches('/').to_string(),
+        last_commit: None,
+        synced_at: None,
+        error: None,
+    };
+    // A failed first sync is recorded on the sync rather than rejected, since
+    // the lockfile may not have been pushed yet
+    if let Err(e) = app.sync_project(&*state.db, sync, None).await {
+        tracing::debug!("Initial sync of project {} failed: {}", project.id, e);
+    }
+
+    match state.db.get_project_sync(project.id) {
+        Ok(Some(sync)) => Ok(Json(sync)),
+        _ => Err(StatusCode::INTERNAL_SERVER_ERROR.into_response()),
+    }
+}
+
+pub async fn delete_project_sync(
+    Path(id): Path<u64>,
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> StatusCode {
+    if let Err(status) = owned_project(&state, &claims, id) {
+        return status;
+    }
+
+    match state.db.delete_project_sync(id) {
+        Ok(true) => StatusCode::NO_CONTENT,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
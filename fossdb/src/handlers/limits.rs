@@ -0,0 +1,56 @@
+use axum::{extract::State, http::HeaderMap, response::Json};
+
+use crate::{AppState, RateLimitsResponse};
+
+/// The rate limit tiers and which one the caller falls in
+pub async fn get_limits(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Json<RateLimitsResponse> {
+    let (tier, _) = state.rate_limits.caller(&headers, None);
+    Json(state.rate_limits.describe(tier))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::fixtures::{self, TestClient};
+    use crate::rate_limit::{self, RateLimits};
+    use crate::storage::Storage;
+    use axum::{Router, http::StatusCode, routing::get};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_anonymous_quota() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        let state = AppState {
+            rate_limits: Arc::new(RateLimits::new(2, 600, false)),
+            ..fixtures::app_state(db)
+        };
+        let router = Router::new()
+            .route("/api/limits", get(get_limits))
+            .route("/api/health", get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                rate_limit::rate_limit_middleware,
+            ))
+            .with_state(state);
+        let client = TestClient::new(router);
+
+        let first = client.get("/api/limits").await;
+        assert_eq!(first.status, StatusCode::OK);
+        assert_eq!(first.body["tier"], "anonymous");
+        assert_eq!(first.body["tiers"][0]["requests_per_minute"], 2);
+        assert_eq!(first.headers[rate_limit::LIMIT_HEADER], "2");
+        assert_eq!(first.headers[rate_limit::REMAINING_HEADER], "1");
+
+        client.get("/api/limits").await;
+        let limited = client.get("/api/limits").await;
+        assert_eq!(limited.status, StatusCode::TOO_MANY_REQUESTS);
+        assert!(limited.headers.contains_key("retry-after"));
+
+        // Health checks are never limited
+        assert_eq!(client.get("/api/health").await.status, StatusCode::OK);
+    }
+}
@@ -0,0 +1,91 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Deserialize;
+
+use crate::{AppState, Maintainer, Package};
+
+#[derive(Debug, Deserialize)]
+pub struct ListMaintainersQuery {
+    /// Matches handles, names and GitHub logins
+    search: Option<String>,
+    limit: Option<usize>,
+}
+
+pub async fn list_maintainers(
+    Query(params): Query<ListMaintainersQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<Maintainer>>, StatusCode> {
+    let limit = params.limit.unwrap_or(50).min(100);
+    let search = params.search.map(|s| s.to_lowercase());
+
+    let maintainers = state
+        .db
+        .get_all_maintainers()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .filter(|m| {
+            search.as_deref().is_none_or(|search| {
+                [Some(&m.handle), m.name.as_ref(), m.github.as_ref()]
+                    .into_iter()
+                    .flatten()
+                    .any(|field| field.to_lowercase().contains(search))
+            })
+        })
+        .take(limit)
+        .collect();
+    Ok(Json(maintainers))
+}
+
+pub async fn get_maintainer(
+    Path(id): Path<u64>,
+    State(state): State<AppState>,
+) -> Result<Json<Maintainer>, StatusCode> {
+    match state.db.get_maintainer(id) {
+        Ok(Some(maintainer)) => Ok(Json(maintainer)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// Every package maintained by one person, across registries
+pub async fn get_maintainer_packages(
+    Path(id): Path<u64>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<Package>>, StatusCode> {
+    match state.db.get_maintainer(id) {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+
+    let pending = state
+        .db
+        .pending_package_ids()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut packages = state
+        .db
+        .get_packages_by_maintainer(id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    packages.retain(|p| !pending.contains(&p.id));
+    Ok(Json(packages))
+}
+
+pub async fn get_package_maintainers(
+    Path(id): Path<u64>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<Maintainer>>, StatusCode> {
+    match state.db.get_package(id).and_then(|p| state.db.published(p)) {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+
+    state
+        .db
+        .get_package_maintainers(id)
+        .map(Json)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
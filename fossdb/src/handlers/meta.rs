@@ -0,0 +1,86 @@
+use axum::{
+    extract::State,
+    http::{StatusCode, header},
+    response::{IntoResponse, Json},
+};
+
+use crate::{AppState, FilterOption};
+
+// Matches how long the server keeps its counts
+const CACHE_CONTROL: &str = "public, max-age=300";
+
+fn options_response(options: Vec<FilterOption>) -> impl IntoResponse {
+    ([(header::CACHE_CONTROL, CACHE_CONTROL)], Json(options))
+}
+
+/// Every language a package is written in, most common first
+pub async fn get_languages(State(state): State<AppState>) -> Result<impl IntoResponse, StatusCode> {
+    let options = state
+        .filter_options
+        .get(&*state.db)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(options_response(options.languages))
+}
+
+/// Every license a package is published under, most common first
+pub async fn get_licenses(State(state): State<AppState>) -> Result<impl IntoResponse, StatusCode> {
+    let options = state
+        .filter_options
+        .get(&*state.db)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(options_response(options.licenses))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Package;
+    use crate::db::Database;
+    use crate::fixtures::{self, TestClient};
+    use crate::handlers::packages::list_packages;
+    use crate::storage::Storage;
+    use axum::{Router, routing::get};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_filter_options() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        fixtures::seed(&*db).unwrap();
+        db.insert_package(Package {
+            language: Some("javascript".to_string()),
+            license: Some("WTFPL".to_string()),
+            ..fixtures::package("left-pad")
+        })
+        .unwrap();
+        let router = Router::new()
+            .route("/api/meta/languages", get(get_languages))
+            .route("/api/meta/licenses", get(get_licenses))
+            .route("/api/packages", get(list_packages))
+            .with_state(fixtures::app_state(db));
+        let client = TestClient::new(router);
+
+        let languages = client.get("/api/meta/languages").await;
+        assert_eq!(languages.status, StatusCode::OK);
+        assert_eq!(languages.body[0]["value"], "rust");
+        assert_eq!(languages.body[1]["value"], "javascript");
+        assert_eq!(languages.body[1]["count"], 1);
+        assert_eq!(languages.headers[header::CACHE_CONTROL], CACHE_CONTROL);
+
+        let licenses = client.get("/api/meta/licenses").await;
+        assert_eq!(licenses.body.as_array().unwrap().len(), 2);
+
+        let filtered = client.get("/api/packages?language=JavaScript").await;
+        assert_eq!(filtered.body["total"], 1);
+        assert_eq!(filtered.body["packages"][0]["name"], "left-pad");
+        let filtered = client
+            .get("/api/packages?license=WTFPL&fields=summary")
+            .await;
+        assert_eq!(filtered.body["total"], 1);
+
+        // Values no package has are rejected rather than returning nothing
+        assert_eq!(
+            client.get("/api/packages?language=cobol").await.status,
+            StatusCode::BAD_REQUEST
+        );
+    }
+}
@@ -1,4 +1,18 @@
+pub mod admin;
 pub mod analytics;
+#[cfg(feature = "artifact-cache")]
+pub mod artifacts;
 pub mod auth;
+pub mod badges;
+pub mod embed;
+#[cfg(feature = "github-app")]
+pub mod github;
+pub mod limits;
+pub mod maintainers;
+pub mod meta;
+pub mod moderation;
 pub mod packages;
+pub mod platforms;
+pub mod projects;
 pub mod users;
+pub mod vulnerabilities;
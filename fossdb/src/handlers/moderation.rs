@@ -0,0 +1,67 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{AppState, ApprovePackagesRequest, Package};
+
+#[derive(Serialize)]
+pub struct PendingPackageResponse {
+    pub package: Package,
+    pub held_at: DateTime<Utc>,
+}
+
+/// Collected packages awaiting approval, oldest first
+pub async fn list_pending_packages(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<PendingPackageResponse>>, StatusCode> {
+    let pending = state
+        .db
+        .get_pending_packages()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut packages = Vec::with_capacity(pending.len());
+    for held in pending {
+        match state.db.get_package(held.package_id) {
+            Ok(Some(package)) => packages.push(PendingPackageResponse {
+                package,
+                held_at: held.created_at,
+            }),
+            Ok(None) => {}
+            Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    }
+    packages.sort_by_key(|p| p.held_at);
+    Ok(Json(packages))
+}
+
+pub async fn approve_package(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> StatusCode {
+    let Ok(id) = id.parse::<u64>() else {
+        return StatusCode::BAD_REQUEST;
+    };
+
+    match state.db.approve_packages(vec![id]) {
+        Ok(0) => StatusCode::NOT_FOUND,
+        Ok(_) => StatusCode::NO_CONTENT,
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Approve many packages at once. IDs that aren't pending are ignored.
+pub async fn approve_packages(
+    State(state): State<AppState>,
+    Json(payload): Json<ApprovePackagesRequest>,
+) -> Result<Json<Value>, StatusCode> {
+    let approved = state
+        .db
+        .approve_packages(payload.package_ids)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(serde_json::json!({ "approved": approved })))
+}
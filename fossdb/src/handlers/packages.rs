@@ -1,13 +1,34 @@
 use axum::{
-    extract::{Path, Query, State},
+    extract::{Extension, Path, Query, State},
     http::StatusCode,
-    response::Json,
+    response::{IntoResponse, Json, Response},
 };
-use chrono::Utc;
-use serde::Deserialize;
+use chrono::{DateTime, Datelike, Duration, Utc};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 
-use crate::{AppState, Package, PackageVersion, CreatePackageRequest};
+use crate::auth::Claims;
+use crate::config::Config;
+use crate::duplicates;
+use crate::filter_options;
+use crate::handlers::vulnerabilities::affecting_version;
+use crate::release_diff::{self, VersionDiff};
+use crate::storage::Storage;
+use crate::typosquat::SimilarName;
+use crate::validation::{ValidatedJson, ValidationErrors};
+use crate::{
+    ActivityWeek, AppState, ChecksumStatus, ChecksumVerification, CreatePackageRequest, DependencyEdge,
+    DependencyGraph, DependencyNode, EventMetadata, EventType, Package, PackageAlias, PackageComparison, PackageReplacement, PackageReplacementRequest, PackageResponse, PackageSuggestion, PackageSummary, PackageVersion, ReplacementSource, Successor, TimelineEvent,
+    TimelineResponse, VerifyChecksumRequest, quality, ranking, timeline, version_range,
+};
+
+/// Upper bound on graph size so deep ecosystems can't exhaust the server
+const MAX_GRAPH_NODES: usize = 500;
+
+/// Most packages that can be compared at once
+const MAX_COMPARED: usize = 4;
 
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
@@ -16,74 +37,556 @@ pub struct ListPackagesQuery {
     limit: Option<u32>,
     search: Option<String>,
     tag: Option<String>,
+    /// One of the values listed by `/api/meta/languages`
+    language: Option<String>,
+    /// One of the values listed by `/api/meta/licenses`
+    license: Option<String>,
+    /// Leave out unmaintained, archived and deprecated packages
+    #[serde(default)]
+    exclude_inactive: bool,
+    /// `summary` for [`PackageSummary`]s, or a comma-separated list of
+    /// fields to include. Whole packages when absent.
+    fields: Option<String>,
+    /// Comma-separated [`SORT_FIELDS`], each descending when prefixed with
+    /// `-`, e.g. `rank,-updated_at`. By rank when absent.
+    sort: Option<String>,
+}
+
+/// Fields `sort` can order by. `rank` puts the most prominent packages first.
+const SORT_FIELDS: &[&str] = &["rank", "name", "created_at", "updated_at"];
+
+/// One key of a `sort` parameter
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SortKey {
+    field: &'static str,
+    descending: bool,
+}
+
+impl SortKey {
+    /// `key` is `field` or `-field`, for one of [`SORT_FIELDS`]
+    fn parse(key: &str) -> Option<Self> {
+        let key = key.trim();
+        let (name, descending) = match key.strip_prefix('-') {
+            Some(name) => (name, true),
+            None => (key, false),
+        };
+        let field = SORT_FIELDS.iter().find(|f| **f == name)?;
+        Some(Self { field, descending })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum SortValue {
+    Number(i64),
+    Text(String),
+    Time(Option<DateTime<Utc>>),
+}
+
+/// A value to order by, in the direction its key asks for
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Ordered {
+    Ascending(SortValue),
+    Descending(Reverse<SortValue>),
+}
+
+/// What a package sorts by under `keys`. Times are None for summaries,
+/// which are only used when no key needs them.
+fn sort_values(
+    keys: &[SortKey],
+    score: u32,
+    name: &str,
+    times: Option<(DateTime<Utc>, DateTime<Utc>)>,
+) -> Vec<Ordered> {
+    keys.iter()
+        .map(|key| {
+            let value = match key.field {
+                "rank" => SortValue::Number(-(score as i64)),
+                "name" => SortValue::Text(name.to_lowercase()),
+                "created_at" => SortValue::Time(times.map(|(created, _)| created)),
+                _ => SortValue::Time(times.map(|(_, updated)| updated)),
+            };
+            if key.descending {
+                Ordered::Descending(Reverse(value))
+            } else {
+                Ordered::Ascending(value)
+            }
+        })
+        .collect()
 }
 
 pub async fn list_packages(
     Query(params): Query<ListPackagesQuery>,
     State(state): State<AppState>,
 ) -> Result<Json<Value>, StatusCode> {
-    match state.db.get_all_packages() {
-        Ok(mut packages) => {
-            // Filter by search term if provided
-            if let Some(search) = &params.search {
-                let search_lower = search.to_lowercase();
-                packages.retain(|pkg| {
-                    pkg.name.to_lowercase().contains(&search_lower)
-                        || pkg
-                            .description
-                            .as_ref()
-                            .map(|d| d.to_lowercase().contains(&search_lower))
-                            .unwrap_or(false)
-                });
+    let fields: Option<Vec<&str>> = match params.fields.as_deref() {
+        None => None,
+        Some("summary") => Some(PackageSummary::FIELDS.to_vec()),
+        Some(fields) => {
+            let fields: Vec<&str> = fields.split(',').map(str::trim).collect();
+            if !fields.iter().all(|f| PACKAGE_FIELDS.contains(f)) {
+                return Err(StatusCode::BAD_REQUEST);
             }
+            Some(fields)
+        }
+    };
 
-            // Filter by tag if provided
-            if let Some(tag) = &params.tag {
-                packages.retain(|pkg| pkg.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)));
-            }
+    // Reject filters no package could match, so typos don't look like empty results
+    if params.language.is_some() || params.license.is_some() {
+        let options = state
+            .filter_options
+            .get(&*state.db)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let known = |filter: &Option<String>, options: &[crate::FilterOption]| {
+            filter
+                .as_deref()
+                .is_none_or(|f| filter_options::contains(options, f))
+        };
+        if !known(&params.language, &options.languages)
+            || !known(&params.license, &options.licenses)
+        {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+    let matches_option = |filter: &Option<String>, value: &Option<String>| {
+        filter
+            .as_deref()
+            .is_none_or(|f| value.as_deref().is_some_and(|v| v.eq_ignore_ascii_case(f)))
+    };
 
-            // Apply pagination
-            let total = packages.len();
-            let limit = params.limit.unwrap_or(50).min(100) as usize;
-            let page = params.page.unwrap_or(1).max(1);
-            let offset = ((page - 1) * limit as u32) as usize;
+    let pending = state
+        .db
+        .pending_package_ids()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let limit = params.limit.unwrap_or(50).min(100) as usize;
+    let page = params.page.unwrap_or(1).max(1);
+    let offset = ((page - 1) * limit as u32) as usize;
 
-            let paginated_packages: Vec<Package> =
-                packages.into_iter().skip(offset).take(limit).collect();
+    let sort: Vec<SortKey> = match params.sort.as_deref() {
+        None => vec![SortKey::parse("rank").unwrap()],
+        Some(sort) => sort
+            .split(',')
+            .map(SortKey::parse)
+            .collect::<Option<_>>()
+            .ok_or(StatusCode::BAD_REQUEST)?,
+    };
 
-            Ok(Json(serde_json::json!({
-                "packages": paginated_packages,
-                "total": total,
-                "page": page,
-                "limit": limit
-            })))
+    // Summaries can be read without loading whole packages, as long as the
+    // filters and sort don't need anything else
+    let summaries_suffice = params.tag.is_none()
+        && !params.exclude_inactive
+        && sort.iter().all(|key| PackageSummary::FIELDS.contains(&key.field))
+        && fields
+            .as_ref()
+            .is_some_and(|fields| fields.iter().all(|f| PackageSummary::FIELDS.contains(f)));
+
+    // Sorted with the ID as a tiebreaker, holding on to no more than the
+    // page needs
+    let scores = ranking::scores(&*state.db).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut total = 0;
+    let mut sorted: BTreeMap<(Vec<Ordered>, u64), Value> = BTreeMap::new();
+    let mut keep = |id: u64, values: Vec<Ordered>, row: Value| {
+        sorted.insert((values, id), row);
+        if sorted.len() > offset + limit {
+            sorted.pop_last();
+        }
+        total += 1;
+    };
+    let score = |id: u64| scores.get(&id).copied().unwrap_or(0);
+    let result = if summaries_suffice {
+        state.db.for_each_package_summary(&mut |summary| {
+            if !pending.contains(&summary.id)
+                && params.search.as_ref().is_none_or(|s| summary.matches_search(s))
+                && matches_option(&params.language, &summary.language)
+                && matches_option(&params.license, &summary.license)
+            {
+                let values = sort_values(&sort, score(summary.id), &summary.name, None);
+                keep(summary.id, values, serde_json::to_value(summary)?);
+            }
+            Ok(())
+        })
+    } else {
+        state.db.for_each_package(&mut |package| {
+            if !pending.contains(&package.id)
+                && params.search.as_ref().is_none_or(|s| package.matches_search(s))
+                && matches_option(&params.language, &package.language)
+                && matches_option(&params.license, &package.license)
+                && !(params.exclude_inactive && package.is_inactive())
+                && params
+                    .tag
+                    .as_ref()
+                    .is_none_or(|tag| package.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+            {
+                let times = Some((package.created_at, package.updated_at));
+                let values = sort_values(&sort, score(package.id), &package.name, times);
+                keep(package.id, values, serde_json::to_value(package)?);
+            }
+            Ok(())
+        })
+    };
+    result.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut page_rows: Vec<Value> = sorted.into_values().skip(offset).collect();
+
+    if let Some(fields) = &fields {
+        for row in &mut page_rows {
+            if let Value::Object(object) = row {
+                object.retain(|key, _| fields.contains(&key.as_str()));
+            }
         }
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
+
+    Ok(Json(serde_json::json!({
+        "packages": page_rows,
+        "total": total,
+        "page": page,
+        "limit": limit
+    })))
+}
+
+/// Fields `fields` can select from
+const PACKAGE_FIELDS: &[&str] = &[
+    "id",
+    "name",
+    "description",
+    "homepage",
+    "repository",
+    "license",
+    "tags",
+    "created_at",
+    "updated_at",
+    "platform",
+    "language",
+    "status",
+    "dependents_count",
+    "rank",
+];
+
+#[derive(Debug, Deserialize)]
+pub struct AutocompleteQuery {
+    q: String,
+    limit: Option<usize>,
+}
+
+/// Names starting with `q`, for search box typeahead. Served from the name
+/// index, so the cost depends on `limit` rather than the number of packages.
+pub async fn autocomplete_packages(
+    Query(params): Query<AutocompleteQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<PackageSuggestion>>, StatusCode> {
+    if params.q.is_empty() {
+        return Ok(Json(Vec::new()));
+    }
+    let limit = params.limit.unwrap_or(10).min(50);
+
+    let pending = state
+        .db
+        .pending_package_ids()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // Fetch enough that skipping pending packages still fills the limit
+    let packages = state
+        .db
+        .find_packages_with_prefix(&params.q, limit + pending.len())
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(
+        packages
+            .into_iter()
+            .filter(|p| !pending.contains(&p.id))
+            .take(limit)
+            .map(|p| PackageSuggestion {
+                name: p.name,
+                platform: p.platform,
+            })
+            .collect(),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckNameQuery {
+    name: String,
+    platform: Option<String>,
+}
+
+/// Report whether a name is taken and which existing names it could be confused with
+pub async fn check_package_name(
+    Query(params): Query<CheckNameQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, StatusCode> {
+    let platform = params.platform.as_deref();
+    // The name index can be an hour behind, which is fine for lookalikes but
+    // not for whether the name itself is taken
+    let exists = state
+        .db
+        .get_package_by_name(&params.name)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .is_some_and(|package| platform.is_none() || package.platform.as_deref() == platform);
+    let similar = state
+        .names
+        .with(&*state.db, |index| {
+            index.find_similar(&params.name, platform)
+        })
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(serde_json::json!({
+        "name": params.name,
+        "platform": params.platform,
+        "exists": exists,
+        "similar": similar
+    })))
 }
 
 pub async fn get_package(
     Path(id): Path<String>,
     State(state): State<AppState>,
-) -> Result<Json<Package>, StatusCode> {
+) -> Result<Json<PackageResponse>, StatusCode> {
     let id = id.parse::<u64>().map_err(|_| StatusCode::BAD_REQUEST)?;
 
-    match state.db.get_package(id) {
-        Ok(Some(package)) => Ok(Json(package)),
-        Ok(None) => Err(StatusCode::NOT_FOUND),
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    let package = match state.db.get_package(id).and_then(|p| state.db.published(p)) {
+        Ok(Some(package)) => package,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    let subscriber_count = state
+        .db
+        .get_subscriber_count(&package.name)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let replacement = successor(&*state.db, package.id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(PackageResponse {
+        package,
+        subscriber_count,
+        replacement,
+    }))
+}
+
+/// The package recommended in place of `package_id`, if any
+fn successor(db: &dyn Storage, package_id: u64) -> anyhow::Result<Option<Successor>> {
+    let Some(replacement) = db.get_package_replacement(package_id)? else {
+        return Ok(None);
+    };
+    Ok(db.get_package(replacement.replacement_id)?.map(|package| Successor {
+        package_id: package.id,
+        name: package.name,
+        note: replacement.note,
+        source: replacement.source,
+    }))
+}
+
+/// Look up a package by name, following renames
+pub async fn get_package_by_name(
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<PackageResponse>, StatusCode> {
+    let package = match state.db.find_package(&name).and_then(|p| state.db.published(p)) {
+        Ok(Some(package)) => package,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    let subscriber_count = state
+        .db
+        .get_subscriber_count(&package.name)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let replacement = successor(&*state.db, package.id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(PackageResponse {
+        package,
+        subscriber_count,
+        replacement,
+    }))
+}
+
+/// Recommend a successor to a package. Curated replacements take precedence
+/// over those seeded from advisories.
+pub async fn set_package_replacement(
+    Path(id): Path<u64>,
+    State(state): State<AppState>,
+    ValidatedJson(payload): ValidatedJson<PackageReplacementRequest>,
+) -> Result<Json<Successor>, Response> {
+    let internal = |_| StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    if state.db.get_package(id).map_err(internal)?.is_none() {
+        return Err(StatusCode::NOT_FOUND.into_response());
+    }
+    let replacement = state
+        .db
+        .get_package(payload.replacement_id)
+        .map_err(internal)?
+        .filter(|package| package.id != id);
+    let Some(replacement) = replacement else {
+        let mut errors = ValidationErrors::new();
+        errors.add("replacement_id", "must be another existing package");
+        return Err(errors.into_response());
+    };
+
+    state
+        .db
+        .set_package_replacement(PackageReplacement {
+            package_id: id,
+            replacement_id: replacement.id,
+            note: payload.note.clone(),
+            source: ReplacementSource::Curated,
+            updated_at: Utc::now(),
+        })
+        .map_err(internal)?;
+
+    Ok(Json(Successor {
+        package_id: replacement.id,
+        name: replacement.name,
+        note: payload.note,
+        source: ReplacementSource::Curated,
+    }))
+}
+
+pub async fn delete_package_replacement(
+    Path(id): Path<u64>,
+    State(state): State<AppState>,
+) -> StatusCode {
+    match state.db.delete_package_replacement(id) {
+        Ok(true) => StatusCode::NO_CONTENT,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// The package with `id`, as long as it isn't awaiting moderation
+fn published_package(state: &AppState, id: u64) -> Result<Package, StatusCode> {
+    state
+        .db
+        .get_package(id)
+        .and_then(|p| state.db.published(p))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Former names of a package
+pub async fn get_package_aliases(
+    Path(id): Path<u64>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<PackageAlias>>, StatusCode> {
+    published_package(&state, id)?;
+    state
+        .db
+        .get_package_aliases(id)
+        .map(Json)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+#[derive(Serialize)]
+pub struct PackageSubmission {
+    pub package: Package,
+    /// Hidden until a moderator approves it
+    pub pending: bool,
+    /// Existing names the new one could be confused with
+    pub similar: Vec<SimilarName>,
+    /// Fields that were read from the repository rather than submitted
+    pub auto_filled: Vec<&'static str>,
+    /// The repository's latest release, recorded as the first version
+    pub latest_version: Option<PackageVersion>,
+}
+
+/// Fill in fields missing from a submission from its repository. Returns the
+/// names of the filled fields and the latest release, whose `package_id` is
+/// left for the caller to set.
+#[cfg(feature = "prefetch")]
+async fn prefetch(
+    state: &AppState,
+    payload: &mut CreatePackageRequest,
+) -> (Vec<&'static str>, Option<PackageVersion>) {
+    let mut auto_filled = Vec::new();
+    let Some(repository) = &payload.repository else {
+        return (auto_filled, None);
+    };
+    let metadata = match state.prefetcher.fetch(repository).await {
+        Ok(Some(metadata)) => metadata,
+        Ok(None) => return (auto_filled, None),
+        Err(e) => {
+            tracing::warn!("Failed to prefetch metadata from {}: {}", repository, e);
+            return (auto_filled, None);
+        }
+    };
+
+    for (field, value, fetched) in [
+        ("name", &mut payload.name, Some(metadata.name)),
+        ("description", &mut payload.description, metadata.description),
+        ("homepage", &mut payload.homepage, metadata.homepage),
+        ("license", &mut payload.license, metadata.license),
+    ] {
+        if value.is_none() && fetched.is_some() {
+            *value = fetched;
+            auto_filled.push(field);
+        }
     }
+
+    let version = metadata.latest_release.map(|release| {
+        auto_filled.push("latest_version");
+        PackageVersion {
+            id: 0,
+            package_id: 0,
+            version: release.version,
+            release_date: release.published_at,
+            download_url: None,
+            checksum: None,
+            dependencies: Vec::new(),
+            vulnerabilities: Vec::new(),
+            changelog: release.notes,
+            created_at: Utc::now(),
+            license: payload.license.clone(),
+            size: None,
+        }
+    });
+    (auto_filled, version)
+}
+
+#[cfg(not(feature = "prefetch"))]
+async fn prefetch(
+    _state: &AppState,
+    _payload: &mut CreatePackageRequest,
+) -> (Vec<&'static str>, Option<PackageVersion>) {
+    (Vec::new(), None)
 }
 
+/// Submit a package. Missing metadata, including the name, is prefetched
+/// from the repository, names and repositories already in the catalog are
+/// refused, and packages from users who can't moderate wait for review.
 pub async fn create_package(
     State(state): State<AppState>,
-    Json(payload): Json<CreatePackageRequest>,
-) -> Result<Json<Package>, StatusCode> {
-    let now = Utc::now();
+    Extension(claims): Extension<Claims>,
+    ValidatedJson(mut payload): ValidatedJson<CreatePackageRequest>,
+) -> Result<Json<PackageSubmission>, Response> {
+    let internal = |_| StatusCode::INTERNAL_SERVER_ERROR.into_response();
+
+    let (auto_filled, latest_version) = prefetch(&state, &mut payload).await;
+    let Some(name) = payload.name else {
+        let mut errors = ValidationErrors::new();
+        errors.add("name", "could not be read from the repository");
+        return Err(errors.into_response());
+    };
+
+    let duplicates =
+        duplicates::find_duplicates(&*state.db, &name, payload.repository.as_deref())
+            .map_err(internal)?;
+    if !duplicates.is_empty() {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({
+                "error": "duplicate",
+                "duplicates": duplicates,
+            })),
+        )
+            .into_response());
+    }
 
+    let similar = state
+        .names
+        .with(&*state.db, |index| index.find_similar(&name, None))
+        .map_err(internal)?;
+
+    let now = Utc::now();
     let package = Package {
         id: 0, // Will be auto-generated
-        name: payload.name,
+        name,
         description: payload.description,
         homepage: payload.homepage,
         repository: payload.repository,
@@ -98,10 +601,41 @@ pub async fn create_package(
         rank: None,
     };
 
-    match state.db.insert_package(package) {
-        Ok(package) => Ok(Json(package)),
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    let pending = !claims
+        .current(&*state.db)
+        .map_err(internal)?
+        .is_some_and(|claims| claims.is_moderator(&Config::from_env()));
+    let package = if pending {
+        state.db.insert_pending_package(package)
+    } else {
+        state.db.insert_package(package)
     }
+    .map_err(internal)?;
+    state
+        .names
+        .insert(package.name.clone(), package.platform.clone());
+
+    // Held first so a pending package's release isn't announced
+    let latest_version = match latest_version {
+        Some(version) => Some(
+            state
+                .db
+                .insert_version(PackageVersion {
+                    package_id: package.id,
+                    ..version
+                })
+                .map_err(internal)?,
+        ),
+        None => None,
+    };
+
+    Ok(Json(PackageSubmission {
+        package,
+        pending,
+        similar,
+        auto_filled,
+        latest_version,
+    }))
 }
 
 pub async fn get_package_versions(
@@ -109,6 +643,7 @@ pub async fn get_package_versions(
     State(state): State<AppState>,
 ) -> Result<Json<Vec<PackageVersion>>, StatusCode> {
     let id = id.parse::<u64>().map_err(|_| StatusCode::BAD_REQUEST)?;
+    published_package(&state, id)?;
 
     match state.db.get_versions_by_package(id) {
         Ok(versions) => Ok(Json(versions)),
@@ -116,6 +651,110 @@ pub async fn get_package_versions(
     }
 }
 
+/// What changed between two versions of a package
+pub async fn get_version_diff(
+    Path((id, from, to)): Path<(String, String, String)>,
+    State(state): State<AppState>,
+) -> Result<Json<VersionDiff>, StatusCode> {
+    let id = id.parse::<u64>().map_err(|_| StatusCode::BAD_REQUEST)?;
+    published_package(&state, id)?;
+
+    let versions = state
+        .db
+        .get_versions_by_package(id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let find = |version: &str| {
+        versions
+            .iter()
+            .find(|v| v.version == version)
+            .ok_or(StatusCode::NOT_FOUND)
+    };
+
+    Ok(Json(release_diff::diff(find(&from)?, find(&to)?)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompareQuery {
+    /// Comma-separated package IDs, in the order to show them
+    ids: String,
+}
+
+/// Release, dependency and security figures for a few packages side by side
+pub async fn compare_packages(
+    Query(params): Query<CompareQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<PackageComparison>>, StatusCode> {
+    let mut ids: Vec<u64> = Vec::new();
+    for id in params.ids.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+        let id = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    if ids.is_empty() || ids.len() > MAX_COMPARED {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    ids.into_iter()
+        .map(|id| match package_comparison(&*state.db, id) {
+            Ok(Some(comparison)) => Ok(comparison),
+            Ok(None) => Err(StatusCode::NOT_FOUND),
+            Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Json)
+}
+
+/// Compare a published package. None if there is no such package.
+fn package_comparison(db: &dyn Storage, id: u64) -> anyhow::Result<Option<PackageComparison>> {
+    let Some(package) = db.published(db.get_package(id)?)? else {
+        return Ok(None);
+    };
+    let mut versions = db.get_versions_by_package(id)?;
+    versions.sort_by_key(|v| v.release_date);
+    let latest = version_range::latest_version(&versions)
+        .and_then(|latest| versions.iter().find(|v| v.version == latest));
+
+    let severities: Vec<_> = match latest {
+        Some(latest) => affecting_version(db, id, &latest.version)?
+            .into_iter()
+            .map(|v| v.severity)
+            .collect(),
+        None => Vec::new(),
+    };
+    let now = Utc::now();
+    let health_score = quality::health_score(
+        &quality::score_package(&package, latest.is_some()),
+        latest.map(|v| v.release_date),
+        &severities,
+        now,
+    );
+
+    // The mean gap between consecutive releases is the span over the gaps
+    let release_interval_days = match (versions.first(), versions.last()) {
+        (Some(first), Some(last)) if versions.len() > 1 => Some(
+            ((last.release_date - first.release_date).num_days() / (versions.len() as i64 - 1))
+                as u32,
+        ),
+        _ => None,
+    };
+
+    Ok(Some(PackageComparison {
+        latest_version: latest.map(|v| v.version.clone()),
+        latest_release: latest.map(|v| v.release_date),
+        releases_last_year: versions
+            .iter()
+            .filter(|v| now - v.release_date <= Duration::days(365))
+            .count(),
+        release_interval_days,
+        dependency_count: latest.map_or(0, |v| v.dependencies.len()),
+        open_vulnerabilities: severities.len(),
+        health_score,
+        subscriber_count: db.get_subscriber_count(&package.name)?,
+        package,
+    }))
+}
+
 pub async fn get_package_subscriber_count(
     Path(id): Path<String>,
     State(state): State<AppState>,
@@ -123,19 +762,807 @@ pub async fn get_package_subscriber_count(
     let id = id.parse::<u64>().map_err(|_| StatusCode::BAD_REQUEST)?;
 
     // First get the package to get its name
-    let package = match state.db.get_package(id) {
+    let package = match state.db.get_package(id).and_then(|p| state.db.published(p)) {
         Ok(Some(pkg)) => pkg,
         Ok(None) => return Err(StatusCode::NOT_FOUND),
         Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
     };
 
-    // Get subscriber count
-    match state.db.get_users_subscribed_to(&package.name) {
-        Ok(subscribers) => Ok(Json(serde_json::json!({
+    match state.db.get_subscriber_count(&package.name) {
+        Ok(count) => Ok(Json(serde_json::json!({
             "package_id": id,
             "package_name": package.name,
-            "subscriber_count": subscribers.len()
+            "subscriber_count": count
         }))),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
+
+#[derive(Debug, Deserialize)]
+pub struct ActivityQuery {
+    limit: Option<usize>,
+    offset: Option<usize>,
+}
+
+/// Public timeline events for a package, newest first
+pub async fn get_package_activity(
+    Path(id): Path<String>,
+    Query(params): Query<ActivityQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<TimelineResponse>, StatusCode> {
+    let id = id.parse::<u64>().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let limit = params.limit.unwrap_or(20).min(100);
+    let offset = params.offset.unwrap_or(0);
+
+    if state
+        .db
+        .get_package(id)
+        .and_then(|p| state.db.published(p))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .is_none()
+    {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    // Personal copies of events belong to subscribers' timelines
+    let mut events: Vec<TimelineEvent> = state
+        .db
+        .get_timeline_by_package(id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .filter(|event| event.user_id.is_none())
+        .collect();
+    events.sort_by_key(|event| std::cmp::Reverse(event.created_at));
+
+    let total = events.len();
+    let events = events.into_iter().skip(offset).take(limit).collect();
+
+    Ok(Json(TimelineResponse {
+        events,
+        total,
+        limit,
+        offset,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ActivityHeatmapQuery {
+    /// How far back to go, 1 (default) to 5 years
+    years: Option<i64>,
+}
+
+/// Bucket release dates into weeks starting on Monday, oldest first and
+/// ending with the current week. Weeks without releases are included so
+/// clients can lay out a grid.
+fn weekly_releases(
+    released: impl Iterator<Item = DateTime<Utc>>,
+    years: i64,
+    now: DateTime<Utc>,
+) -> Vec<ActivityWeek> {
+    let count = 52 * years;
+    let today = now.date_naive();
+    let this_week = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    let start = this_week - Duration::weeks(count - 1);
+
+    let mut releases = vec![0u64; count as usize];
+    for release_date in released {
+        let days = (release_date.date_naive() - start).num_days();
+        if days >= 0 {
+            releases[(days / 7).min(count - 1) as usize] += 1;
+        }
+    }
+
+    releases
+        .into_iter()
+        .enumerate()
+        .map(|(i, releases)| ActivityWeek {
+            week: (start + Duration::weeks(i as i64))
+                .format("%Y-%m-%d")
+                .to_string(),
+            releases,
+        })
+        .collect()
+}
+
+/// Weekly release counts for a package's activity heatmap
+pub async fn get_package_activity_heatmap(
+    Path(id): Path<String>,
+    Query(params): Query<ActivityHeatmapQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ActivityWeek>>, StatusCode> {
+    let id = id.parse::<u64>().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let years = params.years.unwrap_or(1);
+    if !(1..=5).contains(&years) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    if state
+        .db
+        .get_package(id)
+        .and_then(|p| state.db.published(p))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .is_none()
+    {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let versions = state
+        .db
+        .get_versions_by_package(id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(weekly_releases(
+        versions.iter().map(|v| v.release_date),
+        years,
+        Utc::now(),
+    )))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DependencyGraphQuery {
+    /// Defaults to the latest known version
+    version: Option<String>,
+    /// Levels of transitive dependencies to expand, 1-5
+    depth: Option<usize>,
+    /// Include dev dependencies of the root package
+    #[serde(default)]
+    include_dev: bool,
+}
+
+pub async fn get_dependency_graph(
+    Path(id): Path<String>,
+    Query(params): Query<DependencyGraphQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<DependencyGraph>, StatusCode> {
+    let id = id.parse::<u64>().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let depth = params.depth.unwrap_or(1).clamp(1, 5);
+
+    match dependency_graph(
+        &*state.db,
+        id,
+        params.version.as_deref(),
+        depth,
+        params.include_dev,
+    ) {
+        Ok(Some(graph)) => Ok(Json(graph)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// Resolve a package version's dependencies `depth` levels deep, defaulting
+/// to its latest version. None if the package or version isn't known, or the
+/// package is awaiting moderation
+pub(crate) fn dependency_graph(
+    db: &dyn Storage,
+    id: u64,
+    version: Option<&str>,
+    depth: usize,
+    include_dev: bool,
+) -> anyhow::Result<Option<DependencyGraph>> {
+    let Some(package) = db.published(db.get_package(id)?)? else {
+        return Ok(None);
+    };
+    let versions = db.get_versions_by_package(id)?;
+
+    let wanted = match version {
+        Some(version) => version.to_string(),
+        None => match version_range::latest_version(&versions) {
+            Some(latest) => latest,
+            None => return Ok(None),
+        },
+    };
+    let Some(root_version) = versions.into_iter().find(|v| v.version == wanted) else {
+        return Ok(None);
+    };
+
+    // package ID -> (advisory ID, affected range)
+    let mut advisories: HashMap<u64, Vec<(u64, String)>> = HashMap::new();
+    for vulnerability in db.get_all_vulnerabilities()? {
+        for affected in vulnerability.affected_packages {
+            advisories
+                .entry(affected.package_id)
+                .or_default()
+                .push((vulnerability.id, affected.version_range));
+        }
+    }
+    let vulnerabilities_of = |package_id: Option<u64>, version: Option<&str>| -> Vec<u64> {
+        let (Some(package_id), Some(version)) = (package_id, version) else {
+            return Vec::new();
+        };
+        advisories
+            .get(&package_id)
+            .into_iter()
+            .flatten()
+            .filter(|(_, range)| version_range::matches(range, version) == Some(true))
+            .map(|(id, _)| *id)
+            .collect()
+    };
+
+    let mut graph = DependencyGraph {
+        root: 0,
+        nodes: vec![DependencyNode {
+            id: 0,
+            package_id: Some(package.id),
+            vulnerabilities: vulnerabilities_of(Some(package.id), Some(&root_version.version)),
+            name: package.name,
+            version: Some(root_version.version.clone()),
+        }],
+        edges: Vec::new(),
+        truncated: false,
+    };
+
+    // (name, version) -> node, so shared dependencies appear once
+    let mut seen: HashMap<(String, Option<String>), usize> = HashMap::new();
+    seen.insert(
+        (graph.nodes[0].name.clone(), graph.nodes[0].version.clone()),
+        0,
+    );
+
+    let mut queue = VecDeque::from([(0, root_version, 0)]);
+    while let Some((from, version, level)) = queue.pop_front() {
+        for dependency in &version.dependencies {
+            // Dev dependencies of dependencies never affect the root
+            if dependency.dependency_type == "dev" && (level > 0 || !include_dev) {
+                continue;
+            }
+
+            let (package_id, resolved) =
+                resolve_dependency(db, &dependency.name, &dependency.version_requirement)?;
+            let key = (
+                dependency.name.clone(),
+                resolved.as_ref().map(|v| v.version.clone()),
+            );
+
+            let to = match seen.get(&key) {
+                Some(&to) => to,
+                None => {
+                    if graph.nodes.len() >= MAX_GRAPH_NODES {
+                        graph.truncated = true;
+                        continue;
+                    }
+
+                    let to = graph.nodes.len();
+                    graph.nodes.push(DependencyNode {
+                        id: to,
+                        package_id,
+                        name: dependency.name.clone(),
+                        version: key.1.clone(),
+                        vulnerabilities: vulnerabilities_of(package_id, key.1.as_deref()),
+                    });
+                    seen.insert(key, to);
+
+                    if let Some(resolved_version) = resolved
+                        && level + 1 < depth
+                    {
+                        queue.push_back((to, resolved_version, level + 1));
+                    }
+                    to
+                }
+            };
+
+            graph.edges.push(DependencyEdge {
+                from,
+                to,
+                version_requirement: dependency.version_requirement.clone(),
+                dependency_type: dependency.dependency_type.clone(),
+                optional: dependency.optional,
+            });
+        }
+    }
+
+    Ok(Some(graph))
+}
+
+/// Find a dependency's package and its highest known version satisfying the requirement
+fn resolve_dependency(
+    db: &dyn Storage,
+    name: &str,
+    requirement: &str,
+) -> anyhow::Result<(Option<u64>, Option<PackageVersion>)> {
+    // Dependents may still use a package's old name. Pending packages are
+    // left unresolved like unknown ones.
+    let Some(package) = db.published(db.find_package(name)?)? else {
+        return Ok((None, None));
+    };
+
+    let versions = db.get_versions_by_package(package.id)?;
+    let best =
+        version_range::highest_matching(requirement, versions.iter().map(|v| v.version.as_str()))
+            .map(str::to_string);
+
+    Ok((
+        Some(package.id),
+        best.and_then(|best| versions.into_iter().find(|v| v.version == best)),
+    ))
+}
+
+/// Normalize a digest for comparison, ignoring an algorithm prefix and hex case
+pub(crate) fn normalize_checksum(checksum: &str) -> String {
+    let checksum = checksum.trim();
+    let digest = checksum
+        .split_once(':')
+        .map_or(checksum, |(_, digest)| digest);
+    if digest.chars().all(|c| c.is_ascii_hexdigit()) {
+        digest.to_ascii_lowercase()
+    } else {
+        digest.to_string()
+    }
+}
+
+/// Raise a SecurityAlert for an artifact whose hash differs from the published
+/// checksum, once per version
+pub(crate) fn report_checksum_mismatch(
+    state: &AppState,
+    package: &Package,
+    package_version: &PackageVersion,
+    provided: &str,
+) -> anyhow::Result<()> {
+    let version = &package_version.version;
+    let event = TimelineEvent {
+        id: 0,
+        package_id: package.id,
+        user_id: None,
+        event_type: EventType::SecurityAlert,
+        package_name: package.name.clone(),
+        version: Some(version.clone()),
+        message: format!(
+            "Artifact checksum mismatch reported for {} {}",
+            package.name, version
+        ),
+        metadata: Some(
+            EventMetadata::ChecksumMismatch {
+                expected: package_version.checksum.clone(),
+                provided: provided.to_string(),
+            }
+            .to_json(),
+        ),
+        created_at: Utc::now(),
+        notified_at: None,
+    };
+
+    // The global record is kept so repeat reports aren't re-alerted
+    if state.db.insert_timeline_event(event.clone())?.is_none() {
+        return Ok(());
+    }
+
+    tracing::warn!("Checksum mismatch reported for {} {}", package.name, version);
+    timeline::publish(&*state.db, &*state.broadcaster, event)?;
+    Ok(())
+}
+
+/// Compare a client-computed artifact hash with the one published by the
+/// registry. Mismatches raise alerts, so each user can only report a few an
+/// hour.
+pub async fn verify_checksum(
+    Path((id, version)): Path<(String, String)>,
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ValidatedJson(payload): ValidatedJson<VerifyChecksumRequest>,
+) -> Result<Json<ChecksumVerification>, StatusCode> {
+    let id = id.parse::<u64>().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let user_id: u64 = claims.sub.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let package = published_package(&state, id)?;
+    let package_version = state
+        .db
+        .get_versions_by_package(id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .find(|v| v.version == version)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let provided = normalize_checksum(&payload.checksum);
+    let status = match &package_version.checksum {
+        None => ChecksumStatus::Unknown,
+        Some(expected) if normalize_checksum(expected) == provided => ChecksumStatus::Match,
+        Some(_) => ChecksumStatus::Mismatch,
+    };
+
+    if status == ChecksumStatus::Mismatch {
+        if !state.rate_limits.allow_checksum_report(user_id) {
+            return Err(StatusCode::TOO_MANY_REQUESTS);
+        }
+        if let Err(e) = report_checksum_mismatch(&state, &package, &package_version, &provided) {
+            tracing::error!(
+                "Failed to record checksum mismatch for {}: {}",
+                package.name,
+                e
+            );
+        }
+    }
+
+    Ok(Json(ChecksumVerification {
+        package_id: id,
+        version,
+        expected: package_version.checksum,
+        status,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::fixtures::{self, TestClient};
+    use axum::{Router, http::Method, routing::get};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_check_package_name() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        fixtures::seed(&*db).unwrap();
+        let client = TestClient::new(
+            Router::new()
+                .route("/api/packages/check-name", get(check_package_name))
+                .with_state(fixtures::app_state(db.clone())),
+        );
+
+        let checked = client.get("/api/packages/check-name?name=serde").await;
+        assert_eq!(checked.body["exists"], true);
+        let checked = client.get("/api/packages/check-name?name=rand").await;
+        assert_eq!(checked.body["exists"], false);
+
+        // Taken as soon as it's stored, before the name index catches up
+        db.insert_package(fixtures::package("rand")).unwrap();
+        let checked = client.get("/api/packages/check-name?name=rand").await;
+        assert_eq!(checked.body["exists"], true);
+    }
+
+    #[tokio::test]
+    async fn test_pending_packages_are_hidden() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        let seeded = fixtures::seed(&*db).unwrap();
+        db.hold_packages(vec![seeded.tokio.id]).unwrap();
+        let client = TestClient::new(
+            Router::new()
+                .route("/api/packages", get(list_packages))
+                .route("/api/packages/by-name/{name}", get(get_package_by_name))
+                .route("/api/packages/{id}", get(get_package))
+                .route("/api/packages/{id}/aliases", get(get_package_aliases))
+                .route("/api/packages/{id}/dependencies", get(get_dependency_graph))
+                .route("/api/packages/{id}/versions", get(get_package_versions))
+                .route(
+                    "/api/packages/{id}/versions/{from}/diff/{to}",
+                    get(get_version_diff),
+                )
+                .route(
+                    "/api/packages/{id}/versions/{version}/verify",
+                    axum::routing::post(verify_checksum),
+                )
+                .with_state(fixtures::app_state(db)),
+        )
+        .as_user(&seeded.alice);
+
+        let listed = client.get("/api/packages?search=serde").await;
+        assert_eq!(listed.status, StatusCode::OK);
+        assert_eq!(listed.body["total"], 2);
+        assert_eq!(client.get("/api/packages?tag=async").await.body["total"], 0);
+
+        let serde = client.get("/api/packages/by-name/serde").await;
+        assert_eq!(serde.body["id"], seeded.serde.id);
+        assert_eq!(serde.body["subscriber_count"], 1);
+        let versions = client
+            .get(&format!("/api/packages/{}/versions", seeded.serde.id))
+            .await;
+        assert_eq!(versions.body.as_array().unwrap().len(), 3);
+
+        let tokio = client.get(&format!("/api/packages/{}", seeded.tokio.id)).await;
+        assert_eq!(tokio.status, StatusCode::NOT_FOUND);
+        assert_eq!(client.get("/api/packages/tokio").await.status, StatusCode::BAD_REQUEST);
+
+        // Nor can anything else about it be looked up by ID
+        for path in [
+            "aliases",
+            "dependencies",
+            "versions",
+            "versions/1.40.0/diff/1.40.0",
+        ] {
+            let held = client
+                .get(&format!("/api/packages/{}/{}", seeded.tokio.id, path))
+                .await;
+            assert_eq!(held.status, StatusCode::NOT_FOUND, "{}", path);
+        }
+        let verified = client
+            .post(
+                &format!("/api/packages/{}/versions/1.40.0/verify", seeded.tokio.id),
+                serde_json::json!({"checksum": "abcd"}),
+            )
+            .await;
+        assert_eq!(verified.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_autocomplete_packages() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        let seeded = fixtures::seed(&*db).unwrap();
+        db.hold_packages(vec![seeded.serde_json.id]).unwrap();
+        let client = TestClient::new(
+            Router::new()
+                .route("/api/packages/autocomplete", get(autocomplete_packages))
+                .with_state(fixtures::app_state(db)),
+        );
+
+        let suggestions = client.get("/api/packages/autocomplete?q=ser").await;
+        assert_eq!(
+            suggestions.body,
+            serde_json::json!([{"name": "serde", "platform": "crates.io"}])
+        );
+        let tokio = client.get("/api/packages/autocomplete?q=t&limit=1").await;
+        assert_eq!(tokio.body.as_array().unwrap().len(), 1);
+        assert_eq!(
+            client.get("/api/packages/autocomplete?q=").await.body,
+            serde_json::json!([])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_package_fields() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        let seeded = fixtures::seed(&*db).unwrap();
+        db.modify_package(seeded.serde_json.id, &mut |p| {
+            p.status = Some("Deprecated".to_string())
+        })
+        .unwrap();
+        let client = TestClient::new(
+            Router::new()
+                .route("/api/packages", get(list_packages))
+                .with_state(fixtures::app_state(db.clone())),
+        );
+
+        let summaries = client.get("/api/packages?fields=summary&search=serde&limit=1").await;
+        assert_eq!(summaries.body["total"], 2);
+        let summary: PackageSummary =
+            serde_json::from_value(summaries.body["packages"][0].clone()).unwrap();
+        assert_eq!(summary.name, "serde");
+        let active = client
+            .get("/api/packages?fields=summary&search=serde&exclude_inactive=true")
+            .await;
+        assert_eq!(active.body["total"], 1);
+
+        let projected = client.get("/api/packages?fields=name,tags&tag=async").await;
+        assert_eq!(
+            projected.body["packages"][0],
+            serde_json::json!({"name": "tokio", "tags": ["rust", "async"]})
+        );
+        assert_eq!(
+            client.get("/api/packages?fields=password").await.status,
+            StatusCode::BAD_REQUEST
+        );
+
+        // Highest ranked first, then in the order they were added
+        db.update_package_ranks(vec![crate::PackageRank {
+            package_id: seeded.tokio.id,
+            score: 500,
+            downloads: None,
+            stars: None,
+            computed_at: None,
+        }])
+        .unwrap();
+        let names = |body: &Value| -> Vec<Value> {
+            let rows = body["packages"].as_array().unwrap();
+            rows.iter().map(|row| row["name"].clone()).collect()
+        };
+        let ranked = client.get("/api/packages?fields=name").await;
+        assert_eq!(names(&ranked.body), ["tokio", "serde", "serde_json"]);
+        let second = client.get("/api/packages?fields=name&limit=1&page=2").await;
+        assert_eq!(second.body["total"], 3);
+        assert_eq!(names(&second.body), ["serde"]);
+    }
+
+    #[tokio::test]
+    async fn test_sort_packages() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        let seeded = fixtures::seed(&*db).unwrap();
+        db.modify_package(seeded.serde.id, &mut |p| {
+            p.updated_at = Utc::now() + Duration::days(1)
+        })
+        .unwrap();
+        db.update_package_ranks(vec![crate::PackageRank {
+            package_id: seeded.serde_json.id,
+            score: 500,
+            downloads: None,
+            stars: None,
+            computed_at: None,
+        }])
+        .unwrap();
+        let client = TestClient::new(
+            Router::new()
+                .route("/api/packages", get(list_packages))
+                .with_state(fixtures::app_state(db)),
+        );
+        let names = |body: &Value| -> Vec<Value> {
+            let rows = body["packages"].as_array().unwrap();
+            rows.iter().map(|row| row["name"].clone()).collect()
+        };
+
+        let by_name = client.get("/api/packages?fields=summary&sort=-name").await;
+        assert_eq!(names(&by_name.body), ["tokio", "serde_json", "serde"]);
+        let updated = client.get("/api/packages?fields=name&sort=-updated_at").await;
+        assert_eq!(names(&updated.body)[0], "serde");
+        let ranked = client.get("/api/packages?fields=name&sort=rank,-name").await;
+        assert_eq!(names(&ranked.body), ["serde_json", "tokio", "serde"]);
+        let least = client.get("/api/packages?fields=name&sort=-rank&limit=1").await;
+        assert_eq!(names(&least.body), ["serde"]);
+
+        for sort in ["password", "name,", "--name"] {
+            let uri = format!("/api/packages?sort={sort}");
+            assert_eq!(client.get(&uri).await.status, StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compare_packages() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        let seeded = fixtures::seed(&*db).unwrap();
+        db.hold_packages(vec![seeded.tokio.id]).unwrap();
+        let client = TestClient::new(
+            Router::new()
+                .route("/api/packages/compare", get(compare_packages))
+                .with_state(fixtures::app_state(db)),
+        );
+
+        let compared = client
+            .get(&format!(
+                "/api/packages/compare?ids={},{}",
+                seeded.serde_json.id, seeded.serde.id
+            ))
+            .await;
+        assert_eq!(compared.status, StatusCode::OK);
+        let [serde_json, serde] = compared.body.as_array().unwrap().as_slice() else {
+            panic!("expected two packages");
+        };
+        assert_eq!(serde_json["package"]["name"], "serde_json");
+        assert_eq!(serde_json["dependency_count"], 1);
+        assert_eq!(serde_json["release_interval_days"], Value::Null);
+        assert_eq!(serde["latest_version"], "1.1.0");
+        assert_eq!(serde["release_interval_days"], 30);
+        assert_eq!(serde["open_vulnerabilities"], 0);
+        assert_eq!(serde["health_score"], 70);
+        assert_eq!(serde["subscriber_count"], 1);
+
+        for ids in ["", "1,x", "1,2,3,4,5"] {
+            let uri = format!("/api/packages/compare?ids={}", ids);
+            assert_eq!(client.get(&uri).await.status, StatusCode::BAD_REQUEST);
+        }
+        // Pending packages can't be compared any more than viewed
+        let uri = format!(
+            "/api/packages/compare?ids={},{}",
+            seeded.serde.id, seeded.tokio.id
+        );
+        assert_eq!(client.get(&uri).await.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_package_replacement() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        let seeded = fixtures::seed(&*db).unwrap();
+        let client = TestClient::new(
+            Router::new()
+                .route("/api/packages/{id}", get(get_package))
+                .route(
+                    "/api/packages/{id}/replacement",
+                    axum::routing::put(set_package_replacement).delete(delete_package_replacement),
+                )
+                .route(
+                    "/api/vulnerabilities",
+                    axum::routing::post(crate::handlers::vulnerabilities::create_vulnerability),
+                )
+                .with_state(fixtures::app_state(db.clone())),
+        );
+        let advisory = |superseded_by: &str| {
+            serde_json::json!({
+                "cve_id": null,
+                "title": "serde_json is unmaintained",
+                "description": "Use serde instead",
+                "severity": "Low",
+                "affected_packages": [
+                    {"package_id": seeded.serde_json.id, "version_range": "*"},
+                    {"package_id": seeded.serde.id, "version_range": "*"}
+                ],
+                "fixed_in": null,
+                "informational": "unmaintained",
+                "superseded_by": superseded_by
+            })
+        };
+        let uri = format!("/api/packages/{}", seeded.serde_json.id);
+
+        // Advisories seed it, except on the successor itself
+        let created = client.post("/api/vulnerabilities", advisory("serde")).await;
+        assert_eq!(created.status, StatusCode::OK);
+        let package = client.get(&uri).await;
+        assert_eq!(package.body["replacement"]["name"], "serde");
+        assert_eq!(package.body["replacement"]["source"], "advisory");
+        assert_eq!(db.get_package_replacement(seeded.serde.id).unwrap(), None);
+        let unknown = client.post("/api/vulnerabilities", advisory("nope")).await;
+        assert_eq!(unknown.body["fields"][0]["field"], "superseded_by");
+
+        // Curated ones win over later advisories
+        let request = |replacement_id: u64| {
+            Some(serde_json::json!({"replacement_id": replacement_id, "note": "Moved"}))
+        };
+        let replacement = format!("{}/replacement", uri);
+        let set = client
+            .request(Method::PUT, &replacement, request(seeded.tokio.id))
+            .await;
+        assert_eq!(set.body["name"], "tokio");
+        client.post("/api/vulnerabilities", advisory("serde")).await;
+        let package = client.get(&uri).await;
+        assert_eq!(package.body["replacement"]["name"], "tokio");
+        assert_eq!(package.body["replacement"]["source"], "curated");
+
+        let own = client
+            .request(Method::PUT, &replacement, request(seeded.serde_json.id))
+            .await;
+        assert_eq!(own.status, StatusCode::UNPROCESSABLE_ENTITY);
+        let missing = client
+            .request(Method::PUT, "/api/packages/999/replacement", request(seeded.tokio.id))
+            .await;
+        assert_eq!(missing.status, StatusCode::NOT_FOUND);
+
+        assert_eq!(client.delete(&replacement).await.status, StatusCode::NO_CONTENT);
+        assert_eq!(client.get(&uri).await.body["replacement"], Value::Null);
+        assert_eq!(client.delete(&replacement).await.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_checksum_reports_are_limited() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        let seeded = fixtures::seed(&*db).unwrap();
+        let mut version = seeded.versions[0].clone();
+        version.checksum = Some("sha256:abcd".to_string());
+        db.update_versions(vec![version.clone()]).unwrap();
+        let client = TestClient::new(
+            Router::new()
+                .route(
+                    "/api/packages/{id}/versions/{version}/verify",
+                    axum::routing::post(verify_checksum),
+                )
+                .with_state(fixtures::app_state(db.clone())),
+        )
+        .as_user(&seeded.alice);
+        let uri = format!("/api/packages/{}/versions/1.0.0/verify", seeded.serde.id);
+
+        let matching = client
+            .post(&uri, serde_json::json!({"checksum": "ABCD"}))
+            .await;
+        assert_eq!(matching.body["status"], "match");
+        for _ in 0..crate::rate_limit::CHECKSUM_REPORTS_PER_HOUR {
+            let reported = client
+                .post(&uri, serde_json::json!({"checksum": "ffff"}))
+                .await;
+            assert_eq!(reported.body["status"], "mismatch");
+        }
+        let limited = client
+            .post(&uri, serde_json::json!({"checksum": "ffff"}))
+            .await;
+        assert_eq!(limited.status, StatusCode::TOO_MANY_REQUESTS);
+        // Repeat reports of the same version only alert once
+        let alerts = db.get_timeline_by_package(seeded.serde.id).unwrap();
+        assert_eq!(alerts.iter().filter(|e| e.user_id.is_none()).count(), 1);
+    }
+
+    #[test]
+    fn test_weekly_releases() {
+        use chrono::TimeZone;
+
+        // A Wednesday
+        let now = Utc.with_ymd_and_hms(2024, 3, 13, 12, 0, 0).unwrap();
+        let released = [
+            Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2023, 3, 20, 8, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2024, 3, 11, 0, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2024, 3, 13, 9, 0, 0).unwrap(),
+        ];
+
+        let weeks = weekly_releases(released.into_iter(), 1, now);
+
+        assert_eq!(weeks.len(), 52);
+        assert_eq!(weeks[0].week, "2023-03-20");
+        assert_eq!(weeks[0].releases, 1);
+        assert_eq!(weeks[51].week, "2024-03-11");
+        assert_eq!(weeks[51].releases, 2);
+        // Older releases fall outside the window
+        assert_eq!(weeks.iter().map(|w| w.releases).sum::<u64>(), 3);
+        assert_eq!(weekly_releases(released.into_iter(), 2, now).len(), 104);
+    }
+}
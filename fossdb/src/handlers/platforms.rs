@@ -0,0 +1,203 @@
+//! Per-registry statistics for comparing ecosystems
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, HashSet};
+
+use crate::storage::Storage;
+use crate::{AppState, PackageSummary, PlatformStats, PlatformSummary};
+
+/// Packages listed in [`PlatformStats::top_packages`]
+const TOP_PACKAGES: usize = 10;
+
+/// Every registry with published packages, largest first
+pub async fn list_platforms(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<PlatformSummary>>, StatusCode> {
+    let pending = state
+        .db
+        .pending_package_ids()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut counts: HashMap<String, u64> = HashMap::new();
+    state
+        .db
+        .for_each_package_summary(&mut |summary| {
+            if let Some(platform) = summary.platform
+                && !pending.contains(&summary.id)
+            {
+                *counts.entry(platform).or_insert(0) += 1;
+            }
+            Ok(())
+        })
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut platforms: Vec<PlatformSummary> = counts
+        .into_iter()
+        .map(|(name, package_count)| PlatformSummary {
+            name,
+            package_count,
+        })
+        .collect();
+    platforms.sort_by(|a, b| {
+        b.package_count
+            .cmp(&a.package_count)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    Ok(Json(platforms))
+}
+
+/// Package counts, release velocity, top packages and vulnerability density
+/// of one registry, matched ignoring case
+pub async fn get_platform_stats(
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<PlatformStats>, StatusCode> {
+    match platform_stats(&*state.db, &name, Utc::now()) {
+        Ok(Some(stats)) => Ok(Json(stats)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// None if no published package is on the platform
+fn platform_stats(
+    db: &dyn Storage,
+    name: &str,
+    now: DateTime<Utc>,
+) -> anyhow::Result<Option<PlatformStats>> {
+    let pending = db.pending_package_ids()?;
+    let mut packages: Vec<PackageSummary> = Vec::new();
+    db.for_each_package_summary(&mut |summary| {
+        if summary
+            .platform
+            .as_deref()
+            .is_some_and(|platform| platform.eq_ignore_ascii_case(name))
+            && !pending.contains(&summary.id)
+        {
+            packages.push(summary);
+        }
+        Ok(())
+    })?;
+    let Some(platform) = packages.first().and_then(|p| p.platform.clone()) else {
+        return Ok(None);
+    };
+    let ids: HashSet<u64> = packages.iter().map(|p| p.id).collect();
+
+    let (mut last_30_days, mut last_year) = (0u64, 0u64);
+    db.for_each_version(&mut |version| {
+        if ids.contains(&version.package_id) {
+            let age = now - version.release_date;
+            if age <= Duration::days(30) {
+                last_30_days += 1;
+            }
+            if age <= Duration::days(365) {
+                last_year += 1;
+            }
+        }
+        Ok(())
+    })?;
+
+    let mut advisories = 0u64;
+    let mut vulnerable: HashSet<u64> = HashSet::new();
+    db.for_each_vulnerability(&mut |vulnerability| {
+        let affected: Vec<u64> = vulnerability
+            .affected_packages
+            .iter()
+            .map(|a| a.package_id)
+            .filter(|id| ids.contains(id))
+            .collect();
+        if !affected.is_empty() {
+            advisories += 1;
+            vulnerable.extend(affected);
+        }
+        Ok(())
+    })?;
+
+    let package_count = packages.len() as u64;
+    packages.sort_by(|a, b| b.rank.cmp(&a.rank).then_with(|| a.name.cmp(&b.name)));
+    packages.truncate(TOP_PACKAGES);
+
+    Ok(Some(PlatformStats {
+        name: platform,
+        package_count,
+        releases_last_30_days: last_30_days,
+        releases_per_package_per_year: last_year as f32 / package_count as f32,
+        top_packages: packages,
+        vulnerable_packages: vulnerable.len() as u64,
+        vulnerabilities_per_thousand: advisories as f32 * 1000.0 / package_count as f32,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::fixtures::{self, TestClient};
+    use crate::{AffectedPackage, Package, Vulnerability, VulnerabilitySeverity};
+    use axum::{Router, routing::get};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_platforms() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        let seeded = fixtures::seed(&*db).unwrap();
+        db.insert_package(Package {
+            platform: Some("npm".to_string()),
+            ..fixtures::package("left-pad")
+        })
+        .unwrap();
+        db.update_package(Package {
+            rank: Some(20),
+            ..seeded.tokio.clone()
+        })
+        .unwrap();
+        db.insert_vulnerability(Vulnerability {
+            id: 0,
+            cve_id: None,
+            title: "Stack overflow".to_string(),
+            description: String::new(),
+            severity: VulnerabilitySeverity::High,
+            affected_packages: vec![AffectedPackage {
+                package_id: seeded.serde.id,
+                version_range: "<1.1.0".to_string(),
+            }],
+            discovered_at: Utc::now(),
+            fixed_in: None,
+            cvss_vector: None,
+            cvss_score: None,
+            epss_score: None,
+            epss_percentile: None,
+            scores_updated_at: None,
+            informational: None,
+        })
+        .unwrap();
+        let client = TestClient::new(
+            Router::new()
+                .route("/api/platforms", get(list_platforms))
+                .route("/api/platforms/{name}/stats", get(get_platform_stats))
+                .with_state(fixtures::app_state(db)),
+        );
+
+        let platforms = client.get("/api/platforms").await;
+        assert_eq!(platforms.status, StatusCode::OK);
+        assert_eq!(platforms.body[0]["name"], "crates.io");
+        assert_eq!(platforms.body[0]["package_count"], 3);
+        assert_eq!(platforms.body[1]["name"], "npm");
+
+        let stats = client.get("/api/platforms/Crates.IO/stats").await;
+        assert_eq!(stats.status, StatusCode::OK);
+        assert_eq!(stats.body["name"], "crates.io");
+        assert_eq!(stats.body["top_packages"][0]["name"], "tokio");
+        assert_eq!(stats.body["vulnerable_packages"], 1);
+        let density = stats.body["vulnerabilities_per_thousand"].as_f64().unwrap();
+        assert!((density - 333.33).abs() < 0.01);
+
+        assert_eq!(
+            client.get("/api/platforms/pypi/stats").await.status,
+            StatusCode::NOT_FOUND
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::Utc;
+
+use crate::validation::{ValidatedJson, ValidationErrors};
+use crate::{
+    AppState, LockedDependency, LockfileRequest, Project, ProjectRequest, ProjectResponse,
+    auth::Claims, projects,
+};
+
+fn parse_lockfile(lockfile: &str) -> Result<Vec<LockedDependency>, ValidationErrors> {
+    projects::parse_lockfile(lockfile).map_err(|e| {
+        let mut errors = ValidationErrors::new();
+        errors.add("lockfile", e.to_string());
+        errors
+    })
+}
+
+/// The project, unless it belongs to someone else
+pub(crate) fn owned_project(
+    state: &AppState,
+    claims: &Claims,
+    id: u64,
+) -> Result<Project, StatusCode> {
+    let user_id: u64 = claims.sub.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    match state.db.get_project(id) {
+        Ok(Some(project)) if project.user_id == user_id => Ok(project),
+        Ok(_) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+pub async fn list_projects(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<Project>>, StatusCode> {
+    let user_id: u64 = claims.sub.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let projects = state
+        .db
+        .get_projects_by_user(user_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(projects))
+}
+
+pub async fn create_project(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ValidatedJson(payload): ValidatedJson<ProjectRequest>,
+) -> Result<Json<ProjectResponse>, Response> {
+    let user_id: u64 = claims
+        .sub
+        .parse()
+        .map_err(|_| StatusCode::BAD_REQUEST.into_response())?;
+    let dependencies = parse_lockfile(&payload.lockfile).map_err(IntoResponse::into_response)?;
+
+    let now = Utc::now();
+    let project = state
+        .db
+        .insert_project(Project {
+            id: 0,
+            user_id,
+            name: payload.name.trim().to_string(),
+            dependencies,
+            created_at: now,
+            updated_at: now,
+        })
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?;
+
+    Ok(Json(ProjectResponse {
+        project,
+        diff: None,
+    }))
+}
+
+pub async fn get_project(
+    Path(id): Path<u64>,
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Project>, StatusCode> {
+    owned_project(&state, &claims, id).map(Json)
+}
+
+/// Replace a project's dependencies from a newer lockfile, reporting what changed
+pub async fn update_lockfile(
+    Path(id): Path<u64>,
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ValidatedJson(payload): ValidatedJson<LockfileRequest>,
+) -> Result<Json<ProjectResponse>, Response> {
+    let existing = owned_project(&state, &claims, id).map_err(IntoResponse::into_response)?;
+    let dependencies = parse_lockfile(&payload.lockfile).map_err(IntoResponse::into_response)?;
+
+    let diff = projects::diff(&existing.dependencies, &dependencies);
+    let project = Project {
+        dependencies,
+        updated_at: Utc::now(),
+        ..existing
+    };
+    state
+        .db
+        .update_project(project.clone())
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?;
+
+    Ok(Json(ProjectResponse {
+        project,
+        diff: Some(diff),
+    }))
+}
+
+pub async fn delete_project(
+    Path(id): Path<u64>,
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> StatusCode {
+    if let Err(status) = owned_project(&state, &claims, id) {
+        return status;
+    }
+
+    match state.db.delete_project(id) {
+        Ok(true) => StatusCode::NO_CONTENT,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::fixtures::{self, TestClient};
+    use crate::storage::Storage;
+    use crate::{EventType, TimelineEvent, timeline};
+    use axum::{
+        Router,
+        http::Method,
+        routing::{get, post, put},
+    };
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn release(package: &crate::Package, version: &str) -> TimelineEvent {
+        TimelineEvent {
+            id: 0,
+            package_id: package.id,
+            user_id: None,
+            event_type: EventType::NewRelease,
+            package_name: package.name.clone(),
+            version: Some(version.to_string()),
+            message: format!("New version {} released", version),
+            metadata: None,
+            created_at: Utc::now(),
+            notified_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_project_lockfile_updates_and_alerts() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        let seeded = fixtures::seed(&*db).unwrap();
+        let bob = db.insert_user(fixtures::user("bob", &[])).unwrap();
+        let state = fixtures::app_state(db.clone());
+        let router = Router::new()
+            .route("/api/projects", post(create_project))
+            .route("/api/projects/{id}", get(get_project))
+            .route("/api/projects/{id}/lockfile", put(update_lockfile))
+            .with_state(state.clone());
+        let client = TestClient::new(router.clone()).as_user(&bob);
+
+        let created = client
+            .post(
+                "/api/projects",
+                json!({ "name": "api", "lockfile": "serde_json==1.0.0\n" }),
+            )
+            .await;
+        assert_eq!(created.status, StatusCode::OK);
+        let id = created.body["id"].as_u64().unwrap();
+
+        let invalid = client
+            .post(
+                "/api/projects",
+                json!({ "name": "api", "lockfile": "serde>=1\n" }),
+            )
+            .await;
+        assert_eq!(invalid.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let path = format!("/api/projects/{}", id);
+        assert_eq!(
+            TestClient::new(router)
+                .as_user(&seeded.alice)
+                .get(&path)
+                .await
+                .status,
+            StatusCode::NOT_FOUND
+        );
+
+        let updated = client
+            .request(
+                Method::PUT,
+                &format!("{}/lockfile", path),
+                Some(json!({ "lockfile": "serde_json==1.0.0\nserde==1.0.0\n" })),
+            )
+            .await;
+        assert_eq!(
+            updated.body["diff"]["added"],
+            json!([{ "name": "serde", "version": "1.0.0" }])
+        );
+
+        // Only a new major version reaches bob, who isn't subscribed
+        timeline::publish(&*db, &*state.broadcaster, release(&seeded.serde, "1.2.0")).unwrap();
+        assert!(db.get_timeline_events_by_user(bob.id).unwrap().is_empty());
+        timeline::publish(&*db, &*state.broadcaster, release(&seeded.serde, "2.0.0")).unwrap();
+        let events = db.get_timeline_events_by_user(bob.id).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].version.as_deref(), Some("2.0.0"));
+    }
+}
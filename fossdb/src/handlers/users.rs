@@ -3,19 +3,33 @@ use axum::{
     http::StatusCode,
     response::Json,
 };
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::{AppState, auth::Claims, PackageSubscription};
+use std::collections::{HashMap, HashSet};
+
+use crate::handlers::projects::owned_project;
+use crate::handlers::vulnerabilities::{by_score_desc, security_alert};
+use crate::validation::{self, Validate, ValidatedJson, ValidationErrors};
+use crate::{
+    AdvisoryAck, AppState, EventMetadata, EventType, Package, PackageSubscription, ProfileSettings,
+    PublicProfile, SecurityAdvisory, SecurityReport, SubscriptionResponse, SubscriptionsResponse,
+    TimelineEvent, TimelineFilter, TimelinePreferences, TimelineReadState, User,
+    VulnerabilitySeverity, auth::Claims, version_range,
+};
 
 #[derive(Debug, Deserialize)]
 pub struct SubscriptionRequest {
     pub package_name: String,
 }
 
-#[derive(Debug, Serialize)]
-pub struct SubscriptionResponse {
-    pub subscriptions: Vec<PackageSubscription>,
+impl Validate for SubscriptionRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        validation::check_package_name(&mut errors, "package_name", &self.package_name);
+        errors.into_result()
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -33,10 +47,136 @@ pub struct NotificationSettingsResponse {
     pub notifications_enabled: bool,
 }
 
+/// Events shown on a public profile
+const PROFILE_ACTIVITY_LIMIT: usize = 20;
+
 #[derive(Debug, Deserialize)]
 pub struct TimelineQuery {
     limit: Option<usize>,
     offset: Option<usize>,
+    /// e.g. `security_alert` or `new_release`
+    event_type: Option<String>,
+    package_id: Option<u64>,
+    /// Security alerts raised for vulnerabilities of this severity
+    severity: Option<String>,
+    /// RFC 3339 time, only events at or after it are returned
+    since: Option<DateTime<Utc>>,
+    /// Interleave global highlights into a personal timeline, overriding
+    /// the user's `merge_highlights` preference
+    merge: Option<bool>,
+}
+
+impl TimelineQuery {
+    fn filter(&self) -> Result<TimelineFilter, StatusCode> {
+        let event_type = self
+            .event_type
+            .as_deref()
+            .map(str::parse::<EventType>)
+            .transpose()
+            .map_err(|_| StatusCode::BAD_REQUEST)?;
+        Ok(TimelineFilter {
+            user_id: None,
+            package_id: self.package_id,
+            // Only security alerts have a severity
+            event_type: if self.severity.is_some() {
+                Some(EventType::SecurityAlert)
+            } else {
+                event_type
+            },
+            since: self.since,
+        })
+    }
+}
+
+/// The advisory a security alert was raised for
+fn vulnerability_id(event: &TimelineEvent) -> Option<u64> {
+    match event.typed_metadata()? {
+        EventMetadata::Vulnerability {
+            vulnerability_id, ..
+        } => Some(vulnerability_id),
+        _ => None,
+    }
+}
+
+/// Severities of the vulnerabilities security alerts among `events` were
+/// raised for, by vulnerability ID. Alerts that aren't about a
+/// vulnerability, like typosquats, have none.
+fn alert_severities(
+    state: &AppState,
+    events: &[TimelineEvent],
+) -> Result<HashMap<u64, VulnerabilitySeverity>, StatusCode> {
+    let ids: Vec<u64> = events
+        .iter()
+        .filter_map(vulnerability_id)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let vulnerabilities = state
+        .db
+        .get_vulnerabilities(&ids)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(vulnerabilities
+        .into_iter()
+        .map(|vulnerability| (vulnerability.id, vulnerability.severity))
+        .collect())
+}
+
+/// How far back highlights reach when the query has no `since`
+const HIGHLIGHT_WINDOW_DAYS: i64 = 30;
+
+/// Security alerts for recent advisories of at least `threshold` severity,
+/// on any package, that aren't already among the user's `events`
+fn timeline_highlights(
+    state: &AppState,
+    filter: &TimelineFilter,
+    threshold: &VulnerabilitySeverity,
+    events: &[TimelineEvent],
+) -> Result<Vec<TimelineEvent>, StatusCode> {
+    let filter = TimelineFilter {
+        user_id: None,
+        since: Some(
+            filter
+                .since
+                .unwrap_or_else(|| Utc::now() - Duration::days(HIGHLIGHT_WINDOW_DAYS)),
+        ),
+        ..filter.clone()
+    };
+    let seen: HashSet<(u64, u64)> = events
+        .iter()
+        .filter_map(|event| Some((event.package_id, vulnerability_id(event)?)))
+        .collect();
+
+    let mut vulnerabilities = Vec::new();
+    state
+        .db
+        .for_each_vulnerability(&mut |vulnerability| {
+            if vulnerability.severity >= *threshold {
+                vulnerabilities.push(vulnerability);
+            }
+            Ok(())
+        })
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut highlights = Vec::new();
+    for vulnerability in &vulnerabilities {
+        for affected in &vulnerability.affected_packages {
+            if seen.contains(&(affected.package_id, vulnerability.id)) {
+                continue;
+            }
+            let package = state
+                .db
+                .get_package(affected.package_id)
+                .and_then(|package| state.db.published(package))
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            if let Some(package) = package {
+                let event = security_alert(vulnerability, &package, affected);
+                if filter.matches(&event) {
+                    highlights.push(event);
+                }
+            }
+        }
+    }
+    Ok(highlights)
 }
 
 pub async fn get_timeline(
@@ -44,28 +184,75 @@ pub async fn get_timeline(
     Query(params): Query<TimelineQuery>,
     claims: Option<Extension<Claims>>,
 ) -> Result<Json<Value>, StatusCode> {
+    let mut filter = params.filter()?;
+    let severity = params
+        .severity
+        .as_deref()
+        .map(str::parse::<VulnerabilitySeverity>)
+        .transpose()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
     // If user is logged in, return their personal timeline (paginated)
     // Otherwise, return the global timeline (generated dynamically from recent versions)
     let is_authenticated = claims.is_some();
     let mut db_events = if let Some(Extension(claims)) = claims {
         // User is logged in - get their personal timeline
         let user_id: u64 = claims.sub.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+        filter.user_id = Some(user_id);
 
-        state
+        let mut events = state
             .db
-            .get_timeline_events_by_user(user_id)
+            .query_timeline(&filter)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let preferences = state
+            .db
+            .get_user(user_id)
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .map(|user| user.timeline)
+            .unwrap_or_default();
+        if params.merge.unwrap_or(preferences.merge_highlights) {
+            // Highlights have no ID, so interleave by time, newest first
+            let highlights = timeline_highlights(
+                &state,
+                &filter,
+                &preferences.highlight_severity,
+                &events,
+            )?;
+            events.extend(highlights);
+            events.sort_by_key(|event| std::cmp::Reverse(event.created_at));
+        }
+        if let Some(severity) = &severity {
+            let severities = alert_severities(&state, &events)?;
+            events.retain(|event| {
+                vulnerability_id(event).and_then(|id| severities.get(&id)) == Some(severity)
+            });
+        }
+        events
+    } else if filter
+        .event_type
+        .as_ref()
+        .is_some_and(|event_type| *event_type != EventType::NewRelease)
+    {
+        // The global timeline only has releases
+        Vec::new()
     } else {
         // No user logged in - generate global timeline dynamically from recent package versions
-        use crate::{TimelineEvent, EventType};
-
-        let mut versions = state
+        let mut versions = match filter.package_id {
+            Some(package_id) => state.db.get_versions_by_package(package_id),
+            None => state.db.get_all_versions(),
+        }
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let pending = state
             .db
-            .get_all_versions()
+            .pending_package_ids()
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        versions.retain(|v| !pending.contains(&v.package_id));
+        if let Some(since) = filter.since {
+            versions.retain(|v| v.release_date >= since);
+        }
 
         // Sort by release date (most recent first)
-        versions.sort_by(|a, b| b.release_date.cmp(&a.release_date));
+        versions.sort_by_key(|v| std::cmp::Reverse(v.release_date));
 
         // Take the 50 most recent versions and convert to timeline events
         versions
@@ -114,91 +301,162 @@ pub async fn get_timeline(
     }
 }
 
-pub async fn get_subscriptions(
+#[derive(Debug, Deserialize)]
+pub struct MarkReadRequest {
+    /// Mark events up to and including this one, or every event when absent
+    pub event_id: Option<u64>,
+}
+
+fn timeline_read_state(state: &AppState, user: &User) -> Result<TimelineReadState, StatusCode> {
+    let events = state
+        .db
+        .get_timeline_events_by_user(user.id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let last_read = user.last_read_event_id.unwrap_or(0);
+    Ok(TimelineReadState {
+        last_read_event_id: user.last_read_event_id,
+        unread_count: events.iter().filter(|e| e.id > last_read).count() as u64,
+    })
+}
+
+/// How many personal timeline events haven't been read, for a badge
+pub async fn get_unread_count(
     State(state): State<AppState>,
     Extension(claims): Extension<Claims>,
-) -> Result<Json<SubscriptionResponse>, StatusCode> {
+) -> Result<Json<TimelineReadState>, StatusCode> {
     let user_id: u64 = claims.sub.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
-
     let user = state
         .db
         .get_user(user_id)
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    Ok(Json(SubscriptionResponse {
-        subscriptions: user.subscriptions,
-    }))
+    Ok(Json(timeline_read_state(&state, &user)?))
 }
 
-pub async fn add_subscription(
+/// Mark personal timeline events read. Events already read stay read, so
+/// an older `event_id` doesn't bring anything back.
+pub async fn mark_timeline_read(
     State(state): State<AppState>,
     Extension(claims): Extension<Claims>,
-    Json(payload): Json<SubscriptionRequest>,
-) -> Result<Json<SubscriptionResponse>, StatusCode> {
+    Json(payload): Json<MarkReadRequest>,
+) -> Result<Json<TimelineReadState>, StatusCode> {
     let user_id: u64 = claims.sub.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
 
-    let mut user = state
+    let read_up_to = match payload.event_id {
+        Some(event_id) => event_id,
+        None => state
+            .db
+            .get_timeline_events_by_user(user_id)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .iter()
+            .map(|e| e.id)
+            .max()
+            .unwrap_or(0),
+    };
+    let user = state
+        .db
+        .modify_user(user_id, &mut |user: &mut User| {
+            let last_read = user.last_read_event_id.unwrap_or(0).max(read_up_to);
+            user.last_read_event_id = (last_read > 0).then_some(last_read);
+        })
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(timeline_read_state(&state, &user)?))
+}
+
+/// Attach the followed packages so clients can show them without more requests
+fn subscriptions_response(
+    state: &AppState,
+    subscriptions: Vec<PackageSubscription>,
+) -> Result<Json<SubscriptionsResponse>, StatusCode> {
+    let subscriptions = subscriptions
+        .into_iter()
+        .map(|subscription| {
+            let package = state
+                .db
+                .get_package_by_name(&subscription.package_name)
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            Ok(SubscriptionResponse {
+                package_name: subscription.package_name,
+                notifications_enabled: subscription.notifications_enabled,
+                package,
+            })
+        })
+        .collect::<Result<_, StatusCode>>()?;
+
+    Ok(Json(SubscriptionsResponse { subscriptions }))
+}
+
+pub async fn get_subscriptions(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<SubscriptionsResponse>, StatusCode> {
+    let user_id: u64 = claims.sub.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let user = state
         .db
         .get_user(user_id)
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    // Verify package exists
-    if state
+    subscriptions_response(&state, user.subscriptions)
+}
+
+pub async fn add_subscription(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ValidatedJson(payload): ValidatedJson<SubscriptionRequest>,
+) -> Result<Json<SubscriptionsResponse>, StatusCode> {
+    let user_id: u64 = claims.sub.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    // Verify package exists, subscribing under its current name
+    let package_name = state
         .db
-        .get_package_by_name(&payload.package_name)
+        .find_package(&payload.package_name)
+        .and_then(|p| state.db.published(p))
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .is_none()
-    {
-        return Err(StatusCode::NOT_FOUND);
-    }
+        .ok_or(StatusCode::NOT_FOUND)?
+        .name;
 
     // Add subscription if not already subscribed
-    if !user
-        .subscriptions
-        .iter()
-        .any(|s| s.package_name == payload.package_name)
-    {
-        user.subscriptions.push(PackageSubscription {
-            package_name: payload.package_name,
-            notifications_enabled: true, // Default to enabled
-        });
-        state
-            .db
-            .update_user(user.clone())
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    }
+    let user = state
+        .db
+        .modify_user(user_id, &mut |user: &mut User| {
+            if !user
+                .subscriptions
+                .iter()
+                .any(|s| s.package_name == package_name)
+            {
+                user.subscriptions.push(PackageSubscription {
+                    package_name: package_name.clone(),
+                    notifications_enabled: true, // Default to enabled
+                });
+            }
+        })
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
 
-    Ok(Json(SubscriptionResponse {
-        subscriptions: user.subscriptions,
-    }))
+    subscriptions_response(&state, user.subscriptions)
 }
 
 pub async fn remove_subscription(
     State(state): State<AppState>,
     Extension(claims): Extension<Claims>,
     Path(package_name): Path<String>,
-) -> Result<Json<SubscriptionResponse>, StatusCode> {
+) -> Result<Json<SubscriptionsResponse>, StatusCode> {
     let user_id: u64 = claims.sub.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
 
-    let mut user = state
+    let user = state
         .db
-        .get_user(user_id)
+        .modify_user(user_id, &mut |user: &mut User| {
+            user.subscriptions.retain(|s| s.package_name != package_name);
+        })
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    user.subscriptions
-        .retain(|s| s.package_name != package_name);
-
-    state
-        .db
-        .update_user(user.clone())
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    Ok(Json(SubscriptionResponse {
-        subscriptions: user.subscriptions,
-    }))
+    subscriptions_response(&state, user.subscriptions)
 }
 
 pub async fn get_notification_settings(
@@ -225,19 +483,14 @@ pub async fn update_notification_settings(
 ) -> Result<Json<NotificationSettingsResponse>, StatusCode> {
     let user_id: u64 = claims.sub.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
 
-    let mut user = state
+    state
         .db
-        .get_user(user_id)
+        .modify_user(user_id, &mut |user: &mut User| {
+            user.notifications_enabled = payload.notifications_enabled;
+        })
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    user.notifications_enabled = payload.notifications_enabled;
-
-    state
-        .db
-        .update_user(user)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
     Ok(Json(NotificationSettingsResponse {
         notifications_enabled: payload.notifications_enabled,
     }))
@@ -248,32 +501,844 @@ pub async fn update_package_notification(
     Extension(claims): Extension<Claims>,
     Path(package_name): Path<String>,
     Json(payload): Json<UpdatePackageNotificationRequest>,
-) -> Result<Json<SubscriptionResponse>, StatusCode> {
+) -> Result<Json<SubscriptionsResponse>, StatusCode> {
     let user_id: u64 = claims.sub.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
 
-    let mut user = state
+    // Find and update the subscription
+    let user = state
         .db
-        .get_user(user_id)
+        .modify_user(user_id, &mut |user: &mut User| {
+            if let Some(subscription) = user
+                .subscriptions
+                .iter_mut()
+                .find(|s| s.package_name == package_name)
+            {
+                subscription.notifications_enabled = payload.notifications_enabled;
+            }
+        })
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    // Find and update the subscription
-    if let Some(subscription) = user
+    if !user
         .subscriptions
-        .iter_mut()
-        .find(|s| s.package_name == package_name)
+        .iter()
+        .any(|s| s.package_name == package_name)
     {
-        subscription.notifications_enabled = payload.notifications_enabled;
+        return Err(StatusCode::NOT_FOUND);
+    }
 
-        state
-            .db
-            .update_user(user.clone())
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    subscriptions_response(&state, user.subscriptions)
+}
 
-        Ok(Json(SubscriptionResponse {
-            subscriptions: user.subscriptions,
-        }))
+pub async fn get_profile_settings(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ProfileSettings>, StatusCode> {
+    let user_id: u64 = claims.sub.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let user = state
+        .db
+        .get_user(user_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(user.profile))
+}
+
+pub async fn update_profile_settings(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<ProfileSettings>,
+) -> Result<Json<ProfileSettings>, StatusCode> {
+    let user_id: u64 = claims.sub.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let user = state
+        .db
+        .modify_user(user_id, &mut |user: &mut User| {
+            user.profile = payload.clone();
+        })
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(user.profile))
+}
+
+pub async fn get_timeline_preferences(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<TimelinePreferences>, StatusCode> {
+    let user_id: u64 = claims.sub.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let user = state
+        .db
+        .get_user(user_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(user.timeline))
+}
+
+pub async fn update_timeline_preferences(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<TimelinePreferences>,
+) -> Result<Json<TimelinePreferences>, StatusCode> {
+    let user_id: u64 = claims.sub.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let user = state
+        .db
+        .modify_user(user_id, &mut |user: &mut User| {
+            user.timeline = payload.clone();
+        })
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(user.timeline))
+}
+
+/// A user's public profile. Private profiles are reported as missing, so
+/// they don't reveal that the username is taken.
+pub async fn get_public_profile(
+    Path(username): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<PublicProfile>, StatusCode> {
+    let user = state
+        .db
+        .get_user_by_username(&username)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .filter(|user| user.profile.public)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let (subscriptions, activity) = if user.profile.show_subscriptions {
+        let mut activity = Vec::new();
+        for subscription in &user.subscriptions {
+            let package = state
+                .db
+                .get_package_by_name(&subscription.package_name)
+                .and_then(|p| state.db.published(p))
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            if let Some(package) = package {
+                let events = state
+                    .db
+                    .get_timeline_by_package(package.id)
+                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+                activity.extend(events.into_iter().filter(|e| e.user_id.is_none()));
+            }
+        }
+        activity.sort_by_key(|e| std::cmp::Reverse(e.created_at));
+        activity.truncate(PROFILE_ACTIVITY_LIMIT);
+
+        let names = user.subscriptions.into_iter().map(|s| s.package_name).collect();
+        (Some(names), Some(activity))
     } else {
-        Err(StatusCode::NOT_FOUND)
+        (None, None)
+    };
+
+    Ok(Json(PublicProfile {
+        username: user.username,
+        created_at: user.created_at,
+        subscriptions,
+        activity,
+    }))
+}
+
+/// Advisories the user acknowledged, everywhere or for one of their projects
+pub async fn list_advisory_acks(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<AdvisoryAck>>, StatusCode> {
+    let user_id: u64 = claims.sub.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let acks = state
+        .db
+        .get_advisory_acks_by_user(user_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(acks))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SecurityReportQuery {
+    min_cvss: Option<f32>,
+    min_epss: Option<f32>,
+    /// Report on the locked dependencies of this project instead of the
+    /// followed packages
+    project_id: Option<u64>,
+}
+
+/// The smallest upgrade from `current` clear of every range in `ranges`
+fn upgrade_target(
+    state: &AppState,
+    package_id: u64,
+    current: &str,
+    ranges: &[&str],
+) -> Result<Option<String>, StatusCode> {
+    let versions = state
+        .db
+        .get_versions_by_package(package_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let versions = versions.iter().map(|v| v.version.as_str());
+    Ok(version_range::minimal_upgrade(current, ranges, versions).map(str::to_string))
+}
+
+pub async fn get_security_report(
+    Query(params): Query<SecurityReportQuery>,
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<SecurityReport>, StatusCode> {
+    let user_id: u64 = claims.sub.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let user = state
+        .db
+        .get_user(user_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // Resolve followed packages along with their latest known version, or
+    // the project's dependencies along with their locked versions
+    let mut followed: HashMap<u64, Vec<(Package, Option<String>)>> = HashMap::new();
+    if let Some(project_id) = params.project_id {
+        let project = owned_project(&state, &claims, project_id)?;
+        for dependency in project.dependencies {
+            if let Some(package) = state
+                .db
+                .get_package_by_name(&dependency.name)
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            {
+                followed
+                    .entry(package.id)
+                    .or_default()
+                    .push((package, Some(dependency.version)));
+            }
+        }
+    } else {
+        for subscription in &user.subscriptions {
+            if let Some(package) = state
+                .db
+                .get_package_by_name(&subscription.package_name)
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            {
+                let versions = state
+                    .db
+                    .get_versions_by_package(package.id)
+                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+                let latest = version_range::latest_version(&versions);
+                followed.insert(package.id, vec![(package, latest)]);
+            }
+        }
+    }
+    let acks = state
+        .db
+        .get_advisory_acks_by_user(user_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let vulnerabilities = state
+        .db
+        .get_all_vulnerabilities()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // Upgrades have to clear every advisory for the package, including those
+    // filtered out of the report
+    let mut ranges: HashMap<u64, Vec<&str>> = HashMap::new();
+    for vulnerability in &vulnerabilities {
+        for affected in &vulnerability.affected_packages {
+            if followed.contains_key(&affected.package_id) {
+                ranges
+                    .entry(affected.package_id)
+                    .or_default()
+                    .push(&affected.version_range);
+            }
+        }
+    }
+    let mut upgrades: HashMap<(u64, String), Option<String>> = HashMap::new();
+
+    let mut report = SecurityReport::default();
+    let mut affected_packages = HashSet::new();
+
+    for vulnerability in vulnerabilities
+        .iter()
+        .filter(|v| v.meets_scores(params.min_cvss, params.min_epss))
+    {
+        for affected in &vulnerability.affected_packages {
+            let Some(checked) = followed.get(&affected.package_id) else {
+                continue;
+            };
+            for (package, latest) in checked {
+                // An advisory is open while the checked version is still in
+                // range. Without a comparable version, trust whether a fix has
+                // been published.
+                let open = latest
+                    .as_deref()
+                    .and_then(|v| version_range::matches(&affected.version_range, v))
+                    .unwrap_or(vulnerability.fixed_in.is_none());
+                if !open {
+                    continue;
+                }
+                if acks
+                    .iter()
+                    .any(|ack| ack.covers(vulnerability.id, params.project_id))
+                {
+                    report.acknowledged += 1;
+                    continue;
+                }
+
+                let upgrade_to = match latest {
+                    Some(version) => match upgrades.get(&(package.id, version.clone())) {
+                        Some(upgrade) => upgrade.clone(),
+                        None => {
+                            let upgrade =
+                                upgrade_target(&state, package.id, version, &ranges[&package.id])?;
+                            upgrades.insert((package.id, version.clone()), upgrade.clone());
+                            upgrade
+                        }
+                    },
+                    None => None,
+                };
+
+                let advisory = SecurityAdvisory {
+                    vulnerability_id: vulnerability.id,
+                    cve_id: vulnerability.cve_id.clone(),
+                    title: vulnerability.title.clone(),
+                    severity: vulnerability.severity.clone(),
+                    package_id: package.id,
+                    package_name: package.name.clone(),
+                    version_range: affected.version_range.clone(),
+                    latest_version: latest.clone(),
+                    fixed_in: vulnerability.fixed_in.clone(),
+                    upgrade_to,
+                    cvss_score: vulnerability.cvss_score,
+                    epss_score: vulnerability.epss_score,
+                };
+
+                affected_packages.insert(package.id);
+                report.total_open += 1;
+                match vulnerability.severity {
+                    VulnerabilitySeverity::Critical => report.critical.push(advisory),
+                    VulnerabilitySeverity::High => report.high.push(advisory),
+                    VulnerabilitySeverity::Medium => report.medium.push(advisory),
+                    VulnerabilitySeverity::Low => report.low.push(advisory),
+                }
+            }
+        }
+    }
+
+    report.packages_affected = affected_packages.len();
+    for group in [
+        &mut report.critical,
+        &mut report.high,
+        &mut report.medium,
+        &mut report.low,
+    ] {
+        group.sort_by(|a, b| {
+            by_score_desc(a.epss_score, b.epss_score)
+                .then_with(|| by_score_desc(a.cvss_score, b.cvss_score))
+        });
+    }
+
+    Ok(Json(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::fixtures::{self, TestClient};
+    use crate::storage::Storage;
+    use axum::{
+        Router,
+        routing::{get, post},
+    };
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_subscribe_by_former_name() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        let seeded = fixtures::seed(&*db).unwrap();
+        // Renamed upstream, the old name still resolves
+        db.modify_package(seeded.serde_json.id, &mut |p| p.name = "json".to_string())
+            .unwrap();
+        db.insert_package_alias(crate::PackageAlias {
+            name: "serde_json".to_string(),
+            package_id: seeded.serde_json.id,
+            created_at: chrono::Utc::now(),
+        })
+        .unwrap();
+        let client = TestClient::new(
+            Router::new()
+                .route("/api/users/subscriptions", post(add_subscription))
+                .with_state(fixtures::app_state(db)),
+        )
+        .as_user(&seeded.alice);
+
+        let response = client
+            .post("/api/users/subscriptions", json!({ "package_name": "serde_json" }))
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        let names: Vec<&str> = response.body["subscriptions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| s["package_name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["serde", "tokio", "json"]);
+
+        let missing = client
+            .post("/api/users/subscriptions", json!({ "package_name": "left-pad" }))
+            .await;
+        assert_eq!(missing.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_public_profile() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        let seeded = fixtures::seed(&*db).unwrap();
+        let release = |user_id: Option<u64>| crate::TimelineEvent {
+            id: 0,
+            package_id: seeded.serde.id,
+            user_id,
+            event_type: crate::EventType::NewRelease,
+            package_name: "serde".to_string(),
+            version: Some("1.1.0".to_string()),
+            message: "New version 1.1.0 released".to_string(),
+            metadata: None,
+            created_at: chrono::Utc::now(),
+            notified_at: None,
+        };
+        db.insert_timeline_event(release(None)).unwrap();
+        db.insert_timeline_event(release(Some(seeded.alice.id))).unwrap();
+        let client = TestClient::new(
+            Router::new()
+                .route("/api/users/{username}", get(get_public_profile))
+                .with_state(fixtures::app_state(db.clone())),
+        );
+
+        assert_eq!(client.get("/api/users/alice").await.status, StatusCode::NOT_FOUND);
+
+        let set_profile = |profile: ProfileSettings| {
+            db.modify_user(seeded.alice.id, &mut |user| user.profile = profile.clone())
+                .unwrap();
+        };
+        set_profile(ProfileSettings {
+            public: true,
+            show_subscriptions: false,
+        });
+        let profile = client.get("/api/users/alice").await;
+        assert_eq!(profile.body["username"], "alice");
+        assert!(profile.body["subscriptions"].is_null());
+
+        set_profile(ProfileSettings {
+            public: true,
+            show_subscriptions: true,
+        });
+        let profile = client.get("/api/users/alice").await;
+        assert_eq!(profile.body["subscriptions"], json!(["serde", "tokio"]));
+        let activity = profile.body["activity"].as_array().unwrap();
+        assert_eq!(activity.len(), 1);
+        assert!(activity[0]["user_id"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_mark_timeline_read() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        let seeded = fixtures::seed(&*db).unwrap();
+        let bob = db.insert_user(fixtures::user("bob", &["serde"])).unwrap();
+        let mut ids = Vec::new();
+        for (user_id, version) in [
+            (seeded.alice.id, "1.0.0"),
+            (seeded.alice.id, "1.0.1"),
+            (bob.id, "1.0.1"),
+            (seeded.alice.id, "1.1.0"),
+        ] {
+            let event = db
+                .insert_timeline_event(TimelineEvent {
+                    id: 0,
+                    package_id: seeded.serde.id,
+                    user_id: Some(user_id),
+                    event_type: EventType::NewRelease,
+                    package_name: "serde".to_string(),
+                    version: Some(version.to_string()),
+                    message: String::new(),
+                    metadata: None,
+                    created_at: chrono::Utc::now(),
+                    notified_at: None,
+                })
+                .unwrap()
+                .unwrap();
+            ids.push(event.id);
+        }
+        let client = TestClient::new(
+            Router::new()
+                .route("/api/users/timeline/unread", get(get_unread_count))
+                .route("/api/users/timeline/read", post(mark_timeline_read))
+                .with_state(fixtures::app_state(db)),
+        )
+        .as_user(&seeded.alice);
+
+        let unread = client.get("/api/users/timeline/unread").await;
+        assert_eq!(unread.body["unread_count"], 3);
+        assert!(unread.body["last_read_event_id"].is_null());
+
+        let read = client
+            .post("/api/users/timeline/read", json!({ "event_id": ids[1] }))
+            .await;
+        assert_eq!(read.body["unread_count"], 1);
+        assert_eq!(read.body["last_read_event_id"], ids[1]);
+
+        // Marking an older event doesn't make newer ones unread again
+        let read = client
+            .post("/api/users/timeline/read", json!({ "event_id": ids[0] }))
+            .await;
+        assert_eq!(read.body["unread_count"], 1);
+
+        let read = client.post("/api/users/timeline/read", json!({})).await;
+        assert_eq!(read.body["unread_count"], 0);
+        assert_eq!(read.body["last_read_event_id"], ids[3]);
+    }
+
+    #[tokio::test]
+    async fn test_timeline_filters() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        let seeded = fixtures::seed(&*db).unwrap();
+        let critical = db
+            .insert_vulnerability(crate::Vulnerability {
+                id: 0,
+                cve_id: None,
+                title: "Remote code execution".to_string(),
+                description: String::new(),
+                severity: VulnerabilitySeverity::Critical,
+                affected_packages: Vec::new(),
+                discovered_at: chrono::Utc::now(),
+                fixed_in: None,
+                cvss_vector: None,
+                cvss_score: None,
+                epss_score: None,
+                epss_percentile: None,
+                scores_updated_at: None,
+                informational: None,
+            })
+            .unwrap();
+        let event = |package: &crate::Package, event_type: EventType, metadata: Option<String>| {
+            TimelineEvent {
+                id: 0,
+                package_id: package.id,
+                user_id: Some(seeded.alice.id),
+                event_type,
+                package_name: package.name.clone(),
+                version: None,
+                message: String::new(),
+                metadata,
+                created_at: chrono::Utc::now(),
+                notified_at: None,
+            }
+        };
+        let alert = EventMetadata::Vulnerability {
+            vulnerability_id: critical.id,
+            cve_id: None,
+            version_range: "*".to_string(),
+        }
+        .to_json();
+        for event in [
+            event(&seeded.serde, EventType::NewRelease, None),
+            event(&seeded.serde, EventType::SecurityAlert, Some(alert)),
+            // A typosquat alert, not about any vulnerability
+            event(&seeded.tokio, EventType::SecurityAlert, None),
+        ] {
+            db.insert_timeline_event(event).unwrap();
+        }
+        let client = TestClient::new(
+            Router::new()
+                .route("/api/users/timeline", get(get_timeline))
+                .with_state(fixtures::app_state(db)),
+        )
+        .as_user(&seeded.alice);
+
+        let total = |query: &str| {
+            let client = &client;
+            let url = format!("/api/users/timeline?{}", query);
+            async move { client.get(&url).await.body["total"].clone() }
+        };
+        assert_eq!(total("").await, 3);
+        assert_eq!(total("event_type=security_alert").await, 2);
+        assert_eq!(total(&format!("package_id={}", seeded.serde.id)).await, 2);
+        assert_eq!(total("severity=critical").await, 1);
+        assert_eq!(total("severity=low").await, 0);
+        assert_eq!(total("since=2999-01-01T00:00:00Z").await, 0);
+        assert_eq!(
+            client
+                .get("/api/users/timeline?event_type=nope")
+                .await
+                .status,
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[tokio::test]
+    async fn test_global_timeline_hides_pending_packages() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        let seeded = fixtures::seed(&*db).unwrap();
+        db.hold_packages(vec![seeded.tokio.id]).unwrap();
+        let client = TestClient::new(
+            Router::new()
+                .route("/api/users/timeline", get(get_timeline))
+                .with_state(fixtures::app_state(db)),
+        );
+
+        let events = client.get("/api/users/timeline").await.body["events"].clone();
+        let events = events.as_array().unwrap();
+        assert_eq!(events.len(), 4);
+        assert!(events.iter().all(|e| e["package_name"] != "tokio"));
+        let held = client
+            .get(&format!(
+                "/api/users/timeline?package_id={}",
+                seeded.tokio.id
+            ))
+            .await;
+        assert_eq!(held.body["events"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_timeline_highlights() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        let seeded = fixtures::seed(&*db).unwrap();
+        let vulnerability = |severity: VulnerabilitySeverity, packages: &[&crate::Package]| {
+            db.insert_vulnerability(crate::Vulnerability {
+                id: 0,
+                cve_id: None,
+                title: "Remote code execution".to_string(),
+                description: String::new(),
+                severity,
+                affected_packages: packages
+                    .iter()
+                    .map(|package| crate::AffectedPackage {
+                        package_id: package.id,
+                        version_range: "<2.0.0".to_string(),
+                    })
+                    .collect(),
+                discovered_at: chrono::Utc::now(),
+                fixed_in: None,
+                cvss_vector: None,
+                cvss_score: None,
+                epss_score: None,
+                epss_percentile: None,
+                scores_updated_at: None,
+                informational: None,
+            })
+            .unwrap()
+        };
+        let critical = vulnerability(
+            VulnerabilitySeverity::Critical,
+            &[&seeded.serde, &seeded.serde_json],
+        );
+        vulnerability(VulnerabilitySeverity::High, &[&seeded.serde_json]);
+        // Alice already has the alert for serde, her subscription
+        let affected = &critical.affected_packages[0];
+        db.insert_timeline_event(TimelineEvent {
+            user_id: Some(seeded.alice.id),
+            ..crate::handlers::vulnerabilities::security_alert(&critical, &seeded.serde, affected)
+        })
+        .unwrap();
+        let client = TestClient::new(
+            Router::new()
+                .route("/api/users/timeline", get(get_timeline))
+                .route(
+                    "/api/users/settings/timeline",
+                    axum::routing::put(update_timeline_preferences),
+                )
+                .with_state(fixtures::app_state(db)),
+        )
+        .as_user(&seeded.alice);
+
+        let names = |query: &'static str| {
+            let client = &client;
+            async move {
+                let response = client.get(&format!("/api/users/timeline?{}", query)).await;
+                response.body["events"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|e| e["package_name"].as_str().unwrap().to_string())
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(names("").await, ["serde"]);
+        assert_eq!(names("merge=true").await, ["serde", "serde_json"]);
+        assert!(names("merge=true&package_id=999").await.is_empty());
+
+        let response = client
+            .request(
+                axum::http::Method::PUT,
+                "/api/users/settings/timeline",
+                Some(json!({ "merge_highlights": true, "highlight_severity": "High" })),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(names("").await.len(), 3);
+        assert_eq!(names("merge=false").await, ["serde"]);
+    }
+
+    #[tokio::test]
+    async fn test_advisory_acks() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        let seeded = fixtures::seed(&*db).unwrap();
+        let advisory = |package: &crate::Package| {
+            db.insert_vulnerability(crate::Vulnerability {
+                id: 0,
+                cve_id: None,
+                title: "Stack overflow".to_string(),
+                description: String::new(),
+                severity: VulnerabilitySeverity::High,
+                affected_packages: vec![crate::AffectedPackage {
+                    package_id: package.id,
+                    version_range: "<2.0.0".to_string(),
+                }],
+                discovered_at: chrono::Utc::now(),
+                fixed_in: None,
+                cvss_vector: None,
+                cvss_score: None,
+                epss_score: None,
+                epss_percentile: None,
+                scores_updated_at: None,
+                informational: None,
+            })
+            .unwrap()
+        };
+        let followed = advisory(&seeded.serde);
+        let locked = advisory(&seeded.serde_json);
+        let project = db
+            .insert_project(crate::Project {
+                id: 0,
+                user_id: seeded.alice.id,
+                name: "app".to_string(),
+                dependencies: vec![crate::LockedDependency {
+                    name: "serde_json".to_string(),
+                    version: "1.0.0".to_string(),
+                }],
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            })
+            .unwrap();
+        let client = TestClient::new(
+            Router::new()
+                .route("/api/users/security-report", get(get_security_report))
+                .route("/api/users/acknowledgements", get(list_advisory_acks))
+                .route(
+                    "/api/vulnerabilities/{id}/ack",
+                    post(crate::handlers::vulnerabilities::acknowledge_vulnerability)
+                        .delete(crate::handlers::vulnerabilities::unacknowledge_vulnerability),
+                )
+                .with_state(fixtures::app_state(db)),
+        )
+        .as_user(&seeded.alice);
+        let report = |query: String| {
+            let client = &client;
+            async move {
+                let body = client
+                    .get(&format!("/api/users/security-report?{}", query))
+                    .await
+                    .body;
+                (body["total_open"].clone(), body["acknowledged"].clone())
+            }
+        };
+        let project_query = format!("project_id={}", project.id);
+        assert_eq!(report(String::new()).await, (json!(1), json!(0)));
+        assert_eq!(report(project_query.clone()).await, (json!(1), json!(0)));
+
+        let ack = client
+            .post(
+                &format!("/api/vulnerabilities/{}/ack", followed.id),
+                json!({ "note": "Not reachable" }),
+            )
+            .await;
+        assert_eq!(ack.status, StatusCode::OK);
+        assert_eq!(ack.body["note"], "Not reachable");
+        assert_eq!(report(String::new()).await, (json!(0), json!(1)));
+
+        // Acknowledged for the project only
+        let ack = client
+            .post(
+                &format!("/api/vulnerabilities/{}/ack", locked.id),
+                json!({ "project_id": project.id }),
+            )
+            .await;
+        assert_eq!(ack.status, StatusCode::OK);
+        assert_eq!(report(project_query).await, (json!(0), json!(1)));
+        let acks = client.get("/api/users/acknowledgements").await;
+        assert_eq!(acks.body.as_array().unwrap().len(), 2);
+
+        for uri in [
+            "/api/vulnerabilities/999/ack".to_string(),
+            format!("/api/vulnerabilities/{}/ack", locked.id),
+        ] {
+            let body = json!({ "project_id": 999 });
+            assert_eq!(client.post(&uri, body).await.status, StatusCode::NOT_FOUND);
+        }
+
+        let uri = format!("/api/vulnerabilities/{}/ack", followed.id);
+        assert_eq!(client.delete(&uri).await.status, StatusCode::NO_CONTENT);
+        assert_eq!(client.delete(&uri).await.status, StatusCode::NOT_FOUND);
+        assert_eq!(report(String::new()).await, (json!(1), json!(0)));
+    }
+
+    #[tokio::test]
+    async fn test_security_report_upgrades() {
+        let db: Arc<dyn Storage> = Arc::new(Database::in_memory().unwrap());
+        let seeded = fixtures::seed(&*db).unwrap();
+        for range in ["<1.0.1", ">=1.0.1, <1.1.0"] {
+            db.insert_vulnerability(crate::Vulnerability {
+                id: 0,
+                cve_id: None,
+                title: "Stack overflow".to_string(),
+                description: String::new(),
+                severity: VulnerabilitySeverity::High,
+                affected_packages: vec![crate::AffectedPackage {
+                    package_id: seeded.serde.id,
+                    version_range: range.to_string(),
+                }],
+                discovered_at: chrono::Utc::now(),
+                fixed_in: None,
+                cvss_vector: None,
+                cvss_score: None,
+                epss_score: None,
+                epss_percentile: None,
+                scores_updated_at: None,
+                informational: None,
+            })
+            .unwrap();
+        }
+        let project = db
+            .insert_project(crate::Project {
+                id: 0,
+                user_id: seeded.alice.id,
+                name: "app".to_string(),
+                dependencies: vec![crate::LockedDependency {
+                    name: "serde".to_string(),
+                    version: "1.0.0".to_string(),
+                }],
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            })
+            .unwrap();
+        let client = TestClient::new(
+            Router::new()
+                .route("/api/users/security-report", get(get_security_report))
+                .with_state(fixtures::app_state(db)),
+        )
+        .as_user(&seeded.alice);
+
+        let report = client
+            .get(&format!(
+                "/api/users/security-report?project_id={}",
+                project.id
+            ))
+            .await;
+        assert_eq!(report.status, StatusCode::OK);
+        // 1.0.1 fixes the only advisory affecting 1.0.0, but not the other
+        let high = report.body["high"].as_array().unwrap();
+        assert_eq!(high.len(), 1);
+        assert_eq!(high[0]["latest_version"], "1.0.0");
+        assert_eq!(high[0]["upgrade_to"], "1.1.0");
+
+        // The latest version is never vulnerable here, so nothing to report
+        let report = client.get("/api/users/security-report").await;
+        assert_eq!(report.body["total_open"], 0);
     }
 }
@@ -0,0 +1,536 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use crate::handlers::projects::owned_project;
+use crate::storage::Storage;
+use crate::validation::{ValidatedJson, ValidationErrors};
+use crate::{
+    AdvisoryAck, AdvisoryAckRequest, AffectedPackage, AppState, EventMetadata, EventType, Package,
+    PackageReplacement, ReplacementSource, ResolvedAffectedPackage, TimelineEvent,
+    VulnerabilitiesResponse, Vulnerability,
+    VulnerabilityDetail, VulnerabilityRequest, VulnerabilitySeverity, auth::Claims, maintenance,
+    timeline, version_range, vulnerability_links,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct ListVulnerabilitiesQuery {
+    page: Option<u32>,
+    limit: Option<u32>,
+    severity: Option<String>,
+    package_id: Option<u64>,
+    package: Option<String>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    min_cvss: Option<f32>,
+    min_epss: Option<f32>,
+    /// One of `discovered` (default), `cvss` or `epss`
+    sort: Option<String>,
+}
+
+/// Order scores highest first, with unscored entries last
+pub(crate) fn by_score_desc(a: Option<f32>, b: Option<f32>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => b.total_cmp(&a),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Advisories whose affected range includes a version of a package
+pub(crate) fn affecting_version(
+    db: &dyn Storage,
+    package_id: u64,
+    version: &str,
+) -> anyhow::Result<Vec<Vulnerability>> {
+    let mut affecting = Vec::new();
+    db.for_each_vulnerability(&mut |vulnerability| {
+        if vulnerability.affected_packages.iter().any(|affected| {
+            affected.package_id == package_id
+                && version_range::matches(&affected.version_range, version) == Some(true)
+        }) {
+            affecting.push(vulnerability);
+        }
+        Ok(())
+    })?;
+    Ok(affecting)
+}
+
+pub async fn list_vulnerabilities(
+    Query(params): Query<ListVulnerabilitiesQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<VulnerabilitiesResponse>, StatusCode> {
+    let severity = params
+        .severity
+        .as_deref()
+        .map(str::parse::<VulnerabilitySeverity>)
+        .transpose()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    // Resolve a package name filter to its ID
+    let package_id = match (&params.package, params.package_id) {
+        (Some(name), _) => match state
+            .db
+            .find_package(name)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        {
+            Some(package) => Some(package.id),
+            None => return Err(StatusCode::NOT_FOUND),
+        },
+        (None, id) => id,
+    };
+
+    let mut vulnerabilities = state
+        .db
+        .get_all_vulnerabilities()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    vulnerabilities.retain(|v| {
+        severity.as_ref().is_none_or(|s| v.severity == *s)
+            && package_id.is_none_or(|id| v.affected_packages.iter().any(|a| a.package_id == id))
+            && params.since.is_none_or(|since| v.discovered_at >= since)
+            && params.until.is_none_or(|until| v.discovered_at <= until)
+            && v.meets_scores(params.min_cvss, params.min_epss)
+    });
+
+    // Ties fall back to the most recently discovered first
+    vulnerabilities.sort_by_key(|v| std::cmp::Reverse(v.discovered_at));
+    match params.sort.as_deref() {
+        None | Some("discovered") => {}
+        Some("cvss") => vulnerabilities.sort_by(|a, b| by_score_desc(a.cvss_score, b.cvss_score)),
+        Some("epss") => vulnerabilities.sort_by(|a, b| by_score_desc(a.epss_score, b.epss_score)),
+        Some(_) => return Err(StatusCode::BAD_REQUEST),
+    }
+
+    let total = vulnerabilities.len();
+    let limit = params.limit.unwrap_or(50).clamp(1, 100);
+    let page = params.page.unwrap_or(1).max(1);
+    let offset = page.saturating_sub(1).saturating_mul(limit) as usize;
+
+    Ok(Json(VulnerabilitiesResponse {
+        vulnerabilities: vulnerabilities
+            .into_iter()
+            .skip(offset)
+            .take(limit as usize)
+            .collect(),
+        total,
+        page,
+        limit,
+    }))
+}
+
+pub async fn get_vulnerability(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<VulnerabilityDetail>, StatusCode> {
+    let id = id.parse::<u64>().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let vulnerability = match state.db.get_vulnerability(id) {
+        Ok(Some(vulnerability)) => vulnerability,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    let mut affected_packages = Vec::new();
+    for affected in &vulnerability.affected_packages {
+        // Packages may have been removed since the advisory was curated
+        let Some(package) = state
+            .db
+            .get_package(affected.package_id)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        else {
+            continue;
+        };
+
+        let versions = state
+            .db
+            .get_versions_by_package(package.id)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        affected_packages.push(resolve_versions(
+            &package,
+            &affected.version_range,
+            versions.iter().map(|v| v.version.as_str()),
+        ));
+    }
+
+    Ok(Json(VulnerabilityDetail {
+        id: vulnerability.id,
+        cve_id: vulnerability.cve_id,
+        title: vulnerability.title,
+        description: vulnerability.description,
+        severity: vulnerability.severity,
+        discovered_at: vulnerability.discovered_at,
+        fixed_in: vulnerability.fixed_in,
+        cvss_vector: vulnerability.cvss_vector,
+        cvss_score: vulnerability.cvss_score,
+        epss_score: vulnerability.epss_score,
+        epss_percentile: vulnerability.epss_percentile,
+        scores_updated_at: vulnerability.scores_updated_at,
+        informational: vulnerability.informational,
+        affected_packages,
+    }))
+}
+
+/// Bring the advisory links of every version of the `affected` packages up
+/// to date, along with their status, which informational advisories decide
+fn relink_versions<'a>(
+    state: &AppState,
+    affected: impl IntoIterator<Item = &'a AffectedPackage>,
+) {
+    let packages: HashSet<u64> = affected
+        .into_iter()
+        .map(|affected| affected.package_id)
+        .collect();
+    if let Err(e) = maintenance::sweep(&*state.db, Some(&packages), &HashMap::new(), Utc::now()) {
+        tracing::error!("Failed to update package statuses: {}", e);
+    }
+    if let Err(e) = vulnerability_links::link_packages(&*state.db, packages) {
+        tracing::error!("Failed to link versions to advisories: {}", e);
+    }
+}
+
+/// The timeline event alerting subscribers of `package` to `vulnerability`
+pub(crate) fn security_alert(
+    vulnerability: &Vulnerability,
+    package: &Package,
+    affected: &AffectedPackage,
+) -> TimelineEvent {
+    TimelineEvent {
+        id: 0,
+        package_id: package.id,
+        user_id: None,
+        event_type: EventType::SecurityAlert,
+        package_name: package.name.clone(),
+        version: None,
+        message: format!(
+            "{:?} severity vulnerability: {}",
+            vulnerability.severity, vulnerability.title
+        ),
+        metadata: Some(
+            EventMetadata::Vulnerability {
+                vulnerability_id: vulnerability.id,
+                cve_id: vulnerability.cve_id.clone(),
+                version_range: affected.version_range.clone(),
+            }
+            .to_json(),
+        ),
+        created_at: vulnerability.discovered_at,
+        notified_at: None,
+    }
+}
+
+/// Split known versions into those inside the range and newer ones outside it
+fn resolve_versions<'a>(
+    package: &Package,
+    range: &str,
+    versions: impl Iterator<Item = &'a str>,
+) -> ResolvedAffectedPackage {
+    let mut parsed: Vec<(semver::Version, &str)> = versions
+        .filter_map(|v| version_range::parse_version(v).map(|parsed| (parsed, v)))
+        .collect();
+    parsed.sort();
+
+    let affected_versions: Vec<String> = parsed
+        .iter()
+        .filter(|(_, v)| version_range::matches(range, v) == Some(true))
+        .map(|(_, v)| v.to_string())
+        .collect();
+
+    let newest_affected = parsed
+        .iter()
+        .rev()
+        .find(|(_, v)| version_range::matches(range, v) == Some(true))
+        .map(|(parsed, _)| parsed.clone());
+
+    let fixed_versions = parsed
+        .iter()
+        .filter(|(parsed, v)| {
+            version_range::matches(range, v) == Some(false)
+                && newest_affected.as_ref().is_none_or(|newest| parsed > newest)
+        })
+        .map(|(_, v)| v.to_string())
+        .collect();
+
+    ResolvedAffectedPackage {
+        package_id: package.id,
+        package_name: package.name.clone(),
+        version_range: range.to_string(),
+        affected_versions,
+        fixed_versions,
+    }
+}
+
+/// The packages an advisory request names
+struct RequestPackages {
+    affected: Vec<Package>,
+    superseded_by: Option<Package>,
+}
+
+/// Look up every affected package and the one superseding them, reporting
+/// unknown ones as field errors
+fn resolve_affected_packages(
+    db: &dyn Storage,
+    request: &VulnerabilityRequest,
+) -> anyhow::Result<Result<RequestPackages, ValidationErrors>> {
+    let mut errors = ValidationErrors::new();
+    let mut packages = Vec::new();
+
+    for (i, affected) in request.affected_packages.iter().enumerate() {
+        match db.get_package(affected.package_id)? {
+            Some(package) => packages.push(package),
+            None => errors.add(
+                &format!("affected_packages[{}].package_id", i),
+                "package does not exist",
+            ),
+        }
+    }
+    let mut superseded_by = None;
+    if let Some(name) = &request.superseded_by {
+        superseded_by = db.find_package(name)?;
+        if superseded_by.is_none() {
+            errors.add("superseded_by", "package does not exist");
+        }
+    }
+
+    Ok(errors.into_result().map(|()| RequestPackages {
+        affected: packages,
+        superseded_by,
+    }))
+}
+
+/// Point the affected packages to the one superseding them, leaving curated
+/// replacements alone
+fn seed_replacements(
+    db: &dyn Storage,
+    packages: &[Package],
+    successor: &Package,
+    title: &str,
+) -> anyhow::Result<()> {
+    for package in packages.iter().filter(|p| p.id != successor.id) {
+        if let Some(existing) = db.get_package_replacement(package.id)?
+            && existing.source == ReplacementSource::Curated
+        {
+            continue;
+        }
+        db.set_package_replacement(PackageReplacement {
+            package_id: package.id,
+            replacement_id: successor.id,
+            note: Some(title.to_string()),
+            source: ReplacementSource::Advisory,
+            updated_at: Utc::now(),
+        })?;
+    }
+    Ok(())
+}
+
+pub async fn create_vulnerability(
+    State(state): State<AppState>,
+    ValidatedJson(payload): ValidatedJson<VulnerabilityRequest>,
+) -> Result<Json<Vulnerability>, Response> {
+    let packages = resolve_affected_packages(&*state.db, &payload)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?
+        .map_err(IntoResponse::into_response)?;
+    if let Some(successor) = &packages.superseded_by {
+        seed_replacements(&*state.db, &packages.affected, successor, &payload.title)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?;
+    }
+
+    let vulnerability = Vulnerability {
+        id: 0, // Will be auto-generated
+        cve_id: payload.cve_id,
+        title: payload.title,
+        description: payload.description,
+        severity: payload.severity,
+        affected_packages: payload.affected_packages,
+        discovered_at: Utc::now(),
+        fixed_in: payload.fixed_in,
+        // Filled in by the enrichment task
+        cvss_vector: None,
+        cvss_score: None,
+        epss_score: None,
+        epss_percentile: None,
+        scores_updated_at: None,
+        informational: payload.informational,
+    };
+
+    let vulnerability = state
+        .db
+        .insert_vulnerability(vulnerability)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?;
+    relink_versions(&state, &vulnerability.affected_packages);
+
+    // Alert subscribers of every affected package
+    for (package, affected) in packages.affected.iter().zip(&vulnerability.affected_packages) {
+        let event = security_alert(&vulnerability, package, affected);
+
+        if let Err(e) = timeline::publish(&*state.db, &*state.broadcaster, event) {
+            tracing::error!(
+                "Failed to publish security alert for {}: {}",
+                package.name,
+                e
+            );
+        }
+    }
+
+    Ok(Json(vulnerability))
+}
+
+pub async fn update_vulnerability(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    ValidatedJson(payload): ValidatedJson<VulnerabilityRequest>,
+) -> Result<Json<Vulnerability>, Response> {
+    let id = id
+        .parse::<u64>()
+        .map_err(|_| StatusCode::BAD_REQUEST.into_response())?;
+
+    let existing = match state.db.get_vulnerability(id) {
+        Ok(Some(vulnerability)) => vulnerability,
+        Ok(None) => return Err(StatusCode::NOT_FOUND.into_response()),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response()),
+    };
+
+    let packages = resolve_affected_packages(&*state.db, &payload)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?
+        .map_err(IntoResponse::into_response)?;
+    if let Some(successor) = &packages.superseded_by {
+        seed_replacements(&*state.db, &packages.affected, successor, &payload.title)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?;
+    }
+
+    let previous_packages = existing.affected_packages;
+    // Scores belong to the CVE, so they're dropped if it changes
+    let same_cve = existing.cve_id == payload.cve_id;
+    let vulnerability = Vulnerability {
+        id: existing.id,
+        cve_id: payload.cve_id,
+        title: payload.title,
+        description: payload.description,
+        severity: payload.severity,
+        affected_packages: payload.affected_packages,
+        discovered_at: existing.discovered_at,
+        fixed_in: payload.fixed_in,
+        cvss_vector: existing.cvss_vector.filter(|_| same_cve),
+        cvss_score: existing.cvss_score.filter(|_| same_cve),
+        epss_score: existing.epss_score.filter(|_| same_cve),
+        epss_percentile: existing.epss_percentile.filter(|_| same_cve),
+        scores_updated_at: existing.scores_updated_at.filter(|_| same_cve),
+        informational: payload.informational,
+    };
+
+    state
+        .db
+        .update_vulnerability(vulnerability.clone())
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?;
+    // Packages dropped from the advisory are unlinked too
+    relink_versions(
+        &state,
+        previous_packages.iter().chain(&vulnerability.affected_packages),
+    );
+
+    Ok(Json(vulnerability))
+}
+
+pub async fn delete_vulnerability(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> StatusCode {
+    let Ok(id) = id.parse::<u64>() else {
+        return StatusCode::BAD_REQUEST;
+    };
+
+    let existing = match state.db.get_vulnerability(id) {
+        Ok(Some(vulnerability)) => vulnerability,
+        Ok(None) => return StatusCode::NOT_FOUND,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    match state.db.delete_vulnerability(id) {
+        Ok(true) => {
+            relink_versions(&state, &existing.affected_packages);
+            StatusCode::NO_CONTENT
+        }
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Mark an advisory as dealt with, everywhere or for one of the caller's
+/// projects, so reports and notifications leave it out. Acknowledging it
+/// again replaces the note.
+pub async fn acknowledge_vulnerability(
+    Path(id): Path<u64>,
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ValidatedJson(payload): ValidatedJson<AdvisoryAckRequest>,
+) -> Result<Json<AdvisoryAck>, StatusCode> {
+    let user_id: u64 = claims.sub.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    if state
+        .db
+        .get_vulnerability(id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .is_none()
+    {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    if let Some(project_id) = payload.project_id {
+        owned_project(&state, &claims, project_id)?;
+    }
+
+    let ack = state
+        .db
+        .acknowledge_advisory(AdvisoryAck {
+            id: 0,
+            user_id,
+            vulnerability_id: id,
+            project_id: payload.project_id,
+            note: payload
+                .note
+                .map(|note| note.trim().to_string())
+                .filter(|note| !note.is_empty()),
+            created_at: Utc::now(),
+        })
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(ack))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AcknowledgementQuery {
+    project_id: Option<u64>,
+}
+
+/// Reopen an advisory the caller acknowledged
+pub async fn unacknowledge_vulnerability(
+    Path(id): Path<u64>,
+    Query(params): Query<AcknowledgementQuery>,
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> StatusCode {
+    let Ok(user_id) = claims.sub.parse::<u64>() else {
+        return StatusCode::BAD_REQUEST;
+    };
+    let acks = match state.db.get_advisory_acks_by_user(user_id) {
+        Ok(acks) => acks,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    let Some(ack) = acks
+        .into_iter()
+        .find(|ack| ack.vulnerability_id == id && ack.project_id == params.project_id)
+    else {
+        return StatusCode::NOT_FOUND;
+    };
+
+    match state.db.delete_advisory_ack(ack.id) {
+        Ok(true) => StatusCode::NO_CONTENT,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
@@ -0,0 +1,253 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::{HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::AppState;
+
+pub const IDEMPOTENCY_KEY: &str = "idempotency-key";
+const REPLAYED_HEADER: &str = "idempotent-replayed";
+
+// Matches axum's default body limit
+const MAX_BODY_SIZE: usize = 2 * 1024 * 1024;
+
+enum Entry {
+    /// The first request with this key is still being handled
+    InFlight { fingerprint: u64, started: Instant },
+    /// The first request completed and its response can be replayed
+    Done {
+        fingerprint: u64,
+        stored: Instant,
+        status: StatusCode,
+        content_type: Option<HeaderValue>,
+        body: Bytes,
+    },
+}
+
+enum Lookup {
+    Miss,
+    InFlight,
+    Mismatch,
+    Replay(Response),
+}
+
+/// Short-lived store of responses keyed by `Idempotency-Key`
+pub struct IdempotencyStore {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl IdempotencyStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Look up a key, claiming it for this request if it is unused
+    fn begin(&self, key: &str, fingerprint: u64) -> Lookup {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+        let ttl = self.ttl;
+
+        entries.retain(|_, entry| match entry {
+            Entry::InFlight { started, .. } => now.duration_since(*started) < ttl,
+            Entry::Done { stored, .. } => now.duration_since(*stored) < ttl,
+        });
+
+        match entries.get(key) {
+            None => {
+                entries.insert(
+                    key.to_string(),
+                    Entry::InFlight {
+                        fingerprint,
+                        started: now,
+                    },
+                );
+                Lookup::Miss
+            }
+            Some(Entry::InFlight {
+                fingerprint: existing,
+                ..
+            })
+            | Some(Entry::Done {
+                fingerprint: existing,
+                ..
+            }) if *existing != fingerprint => Lookup::Mismatch,
+            Some(Entry::InFlight { .. }) => Lookup::InFlight,
+            Some(Entry::Done {
+                status,
+                content_type,
+                body,
+                ..
+            }) => {
+                let mut response = (*status, body.clone()).into_response();
+                if let Some(content_type) = content_type {
+                    response
+                        .headers_mut()
+                        .insert(header::CONTENT_TYPE, content_type.clone());
+                }
+                response
+                    .headers_mut()
+                    .insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+                Lookup::Replay(response)
+            }
+        }
+    }
+
+    fn complete(&self, key: &str, fingerprint: u64, response: &Response, body: Bytes) {
+        let mut entries = self.entries.lock().unwrap();
+
+        // Server errors are not cached so the client can retry
+        if response.status().is_server_error() {
+            entries.remove(key);
+            return;
+        }
+
+        entries.insert(
+            key.to_string(),
+            Entry::Done {
+                fingerprint,
+                stored: Instant::now(),
+                status: response.status(),
+                content_type: response.headers().get(header::CONTENT_TYPE).cloned(),
+                body,
+            },
+        );
+    }
+
+    fn abandon(&self, key: &str) {
+        self.entries.lock().unwrap().remove(key);
+    }
+}
+
+/// Replay responses for POST requests that carry a previously seen `Idempotency-Key`.
+///
+/// Keys are scoped to the request path and caller so different users can't collide.
+pub async fn idempotency_middleware(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if req.method() != Method::POST {
+        return next.run(req).await;
+    }
+
+    let Some(idempotency_key) = req
+        .headers()
+        .get(IDEMPOTENCY_KEY)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+    else {
+        return next.run(req).await;
+    };
+
+    if idempotency_key.is_empty() || idempotency_key.len() > 255 {
+        return (
+            StatusCode::BAD_REQUEST,
+            "Idempotency-Key must be between 1 and 255 characters",
+        )
+            .into_response();
+    }
+
+    let (parts, body) = req.into_parts();
+    let body = match axum::body::to_bytes(body, MAX_BODY_SIZE).await {
+        Ok(body) => body,
+        Err(_) => return StatusCode::PAYLOAD_TOO_LARGE.into_response(),
+    };
+
+    let mut caller = DefaultHasher::new();
+    parts
+        .headers
+        .get(header::AUTHORIZATION)
+        .map(|v| v.as_bytes())
+        .hash(&mut caller);
+    let key = format!(
+        "{:x}:{}:{}",
+        caller.finish(),
+        parts.uri.path(),
+        idempotency_key
+    );
+
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    let fingerprint = hasher.finish();
+
+    let store = &state.idempotency;
+    match store.begin(&key, fingerprint) {
+        Lookup::Miss => {}
+        Lookup::Replay(response) => return response,
+        Lookup::InFlight => {
+            return (
+                StatusCode::CONFLICT,
+                "A request with this Idempotency-Key is already in progress",
+            )
+                .into_response();
+        }
+        Lookup::Mismatch => {
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Idempotency-Key was already used with a different request body",
+            )
+                .into_response();
+        }
+    }
+
+    let response = next
+        .run(Request::from_parts(parts, Body::from(body)))
+        .await;
+
+    let (parts, body) = response.into_parts();
+    match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => {
+            let response = Response::from_parts(parts, Body::from(bytes.clone()));
+            store.complete(&key, fingerprint, &response, bytes);
+            response
+        }
+        Err(_) => {
+            store.abandon(&key);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_replays_and_detects_mismatch() {
+        let store = IdempotencyStore::new(Duration::from_secs(60));
+
+        assert!(matches!(store.begin("k", 1), Lookup::Miss));
+        assert!(matches!(store.begin("k", 1), Lookup::InFlight));
+
+        let response = (StatusCode::CREATED, "done").into_response();
+        store.complete("k", 1, &response, Bytes::from_static(b"done"));
+
+        match store.begin("k", 1) {
+            Lookup::Replay(response) => assert_eq!(response.status(), StatusCode::CREATED),
+            _ => panic!("expected replay"),
+        }
+        assert!(matches!(store.begin("k", 2), Lookup::Mismatch));
+    }
+
+    #[test]
+    fn test_store_forgets_server_errors() {
+        let store = IdempotencyStore::new(Duration::from_secs(60));
+
+        assert!(matches!(store.begin("k", 1), Lookup::Miss));
+        let response = StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        store.complete("k", 1, &response, Bytes::new());
+
+        assert!(matches!(store.begin("k", 1), Lookup::Miss));
+    }
+}
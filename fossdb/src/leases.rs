@@ -0,0 +1,136 @@
+// Work shared between processes through storage. Replicas and standalone
+// collectors claim a lease before running, so each run happens only once.
+use anyhow::{Result, anyhow};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::storage::Storage;
+
+/// How long a lease lasts if its holder stops renewing it
+pub const LEASE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Run `work` while holding the lease `name`, renewing it until the work
+/// finishes. None if another holder has it. The work is abandoned if the
+/// lease is taken over, since someone else is doing it by then.
+///
+/// Once the work succeeds the lease is kept until `hold_for` after it
+/// started, so other holders skip the run until it's next due. If it fails
+/// the lease is released straight away for someone else to retry.
+pub async fn run_with_lease<T, E>(
+    db: Arc<dyn Storage>,
+    name: &str,
+    holder: &str,
+    hold_for: Duration,
+    work: impl Future<Output = Result<T, E>>,
+) -> Result<Option<Result<T, E>>> {
+    let ttl = chrono::Duration::from_std(LEASE_TTL)?;
+    let started = std::time::Instant::now();
+    if !db.acquire_lease(name, holder, ttl)? {
+        return Ok(None);
+    }
+
+    tokio::pin!(work);
+    let mut renewal = tokio::time::interval(LEASE_TTL / 3);
+    // The first tick completes immediately
+    renewal.tick().await;
+
+    let result = loop {
+        tokio::select! {
+            output = &mut work => break Ok(Some(output)),
+            _ = renewal.tick() => match db.acquire_lease(name, holder, ttl) {
+                Ok(true) => {}
+                Ok(false) => break Err(anyhow!("lease {} was taken over", name)),
+                // Still held until it expires, so try again next tick
+                Err(e) => warn!("Failed to renew lease {}: {}", name, e),
+            },
+        }
+    };
+
+    let remaining = hold_for.saturating_sub(started.elapsed());
+    let held = match &result {
+        Ok(Some(Ok(_))) if !remaining.is_zero() => chrono::Duration::from_std(remaining)
+            .map_err(anyhow::Error::from)
+            .and_then(|remaining| db.acquire_lease(name, holder, remaining)),
+        // Taken over, so it isn't ours to release
+        Err(_) => return result,
+        _ => Ok(false),
+    };
+    match held {
+        Ok(true) => {}
+        Ok(false) => {
+            if let Err(e) = db.release_lease(name, holder) {
+                warn!("Failed to release lease {}: {}", name, e);
+            }
+        }
+        Err(e) => warn!("Failed to hold lease {} until the next run: {}", name, e),
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    #[test]
+    fn test_lease_exclusive_until_expiry() {
+        let db = Arc::new(Database::in_memory().unwrap());
+        let minute = chrono::Duration::minutes(1);
+
+        assert!(db.acquire_lease("nixpkgs", "a", minute).unwrap());
+        assert!(!db.acquire_lease("nixpkgs", "b", minute).unwrap());
+        assert!(db.acquire_lease("nixpkgs", "a", minute).unwrap());
+
+        // Only the holder can release it
+        db.release_lease("nixpkgs", "b").unwrap();
+        assert!(!db.acquire_lease("nixpkgs", "b", minute).unwrap());
+        db.release_lease("nixpkgs", "a").unwrap();
+        assert!(db.acquire_lease("nixpkgs", "b", minute).unwrap());
+
+        // Expired leases can be taken over or cleaned up
+        assert!(db.acquire_lease("crates-io", "a", -minute).unwrap());
+        assert!(db.acquire_lease("crates-io", "b", -minute).unwrap());
+        assert_eq!(db.cleanup_expired_leases().unwrap(), 1);
+        assert!(!db.acquire_lease("nixpkgs", "a", minute).unwrap());
+    }
+
+    async fn run(db: &Arc<Database>, name: &str, holder: &str, hold_for: Duration) -> Option<u8> {
+        run_with_lease(db.clone(), name, holder, hold_for, async { Ok::<_, ()>(1) })
+            .await
+            .unwrap()
+            .map(Result::unwrap)
+    }
+
+    #[tokio::test]
+    async fn test_run_with_lease_skips_held_work() {
+        let db = Arc::new(Database::in_memory().unwrap());
+        db.acquire_lease("nixpkgs", "other", chrono::Duration::minutes(1))
+            .unwrap();
+
+        assert_eq!(run(&db, "nixpkgs", "me", Duration::ZERO).await, None);
+        assert_eq!(run(&db, "crates-io", "me", Duration::ZERO).await, Some(1));
+        // Released once the work is done when there's nothing to hold it for
+        assert!(db.acquire_lease("crates-io", "other", chrono::Duration::minutes(1)).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_run_with_lease_holds_until_next_run() {
+        let db = Arc::new(Database::in_memory().unwrap());
+        let interval = Duration::from_secs(3600);
+
+        // A second replica running the same schedule right after skips it
+        assert_eq!(run(&db, "nixpkgs", "a", interval).await, Some(1));
+        assert_eq!(run(&db, "nixpkgs", "b", interval).await, None);
+        // The holder keeps its own schedule
+        assert_eq!(run(&db, "nixpkgs", "a", interval).await, Some(1));
+
+        // Failed runs are left for someone else to retry
+        let failed = run_with_lease(db.clone(), "crates-io", "a", interval, async {
+            Err::<(), _>(())
+        });
+        assert_eq!(failed.await.unwrap(), Some(Err(())));
+        assert_eq!(run(&db, "crates-io", "b", interval).await, Some(1));
+    }
+}
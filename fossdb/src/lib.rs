@@ -34,7 +34,7 @@ macro_rules! db_model {
     (
         // Match and filter out native_model attribute
         #[derive($($derive:ident),*)]
-        #[native_model(id = $id:expr, version = $version:expr)]
+        #[native_model(id = $id:expr, version = $version:expr $(, from = $from:ty)?)]
         #[native_db]
         $vis:vis struct $name:ident {
             $(
@@ -79,7 +79,7 @@ db_model! {
 
 db_model! {
     #[derive(Debug, Clone, Serialize, Deserialize)]
-    #[native_model(id = 2, version = 1)]
+    #[native_model(id = 2, version = 2, from = PackageVersionV1)]
     #[native_db]
     pub struct PackageVersion {
         #[primary_key]
@@ -91,13 +91,77 @@ db_model! {
         pub download_url: Option<String>,
         pub checksum: Option<String>,
         pub dependencies: Vec<Dependency>,
+        /// IDs of the advisories whose affected range includes this version,
+        /// kept up to date by `vulnerability_links`
         pub vulnerabilities: Vec<String>,
         pub changelog: Option<String>,
         pub created_at: DateTime<Utc>,
+        /// License of this release, when the registry tracks it per version
+        pub license: Option<String>,
+        /// Size of the release artifact in bytes
+        pub size: Option<u64>,
     }
 }
 
+/// PackageVersion as stored before per-version license and size were tracked
+#[cfg(feature = "db")]
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[native_model(id = 2, version = 1)]
+#[native_db]
+pub struct PackageVersionV1 {
+    #[primary_key]
+    pub id: u64,
+    #[secondary_key]
+    pub package_id: u64,
+    pub version: String,
+    pub release_date: DateTime<Utc>,
+    pub download_url: Option<String>,
+    pub checksum: Option<String>,
+    pub dependencies: Vec<Dependency>,
+    pub vulnerabilities: Vec<String>,
+    pub changelog: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[cfg(feature = "db")]
+impl From<PackageVersionV1> for PackageVersion {
+    fn from(v: PackageVersionV1) -> Self {
+        Self {
+            id: v.id,
+            package_id: v.package_id,
+            version: v.version,
+            release_date: v.release_date,
+            download_url: v.download_url,
+            checksum: v.checksum,
+            dependencies: v.dependencies,
+            vulnerabilities: v.vulnerabilities,
+            changelog: v.changelog,
+            created_at: v.created_at,
+            license: None,
+            size: None,
+        }
+    }
+}
+
+#[cfg(feature = "db")]
+impl From<PackageVersion> for PackageVersionV1 {
+    fn from(v: PackageVersion) -> Self {
+        Self {
+            id: v.id,
+            package_id: v.package_id,
+            version: v.version,
+            release_date: v.release_date,
+            download_url: v.download_url,
+            checksum: v.checksum,
+            dependencies: v.dependencies,
+            vulnerabilities: v.vulnerabilities,
+            changelog: v.changelog,
+            created_at: v.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Dependency {
     pub name: String,
     pub version_requirement: String,
@@ -113,7 +177,7 @@ pub struct PackageSubscription {
 
 db_model! {
     #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-    #[native_model(id = 3, version = 1)]
+    #[native_model(id = 3, version = 5, from = UserV4)]
     #[native_db]
     pub struct User {
         #[primary_key]
@@ -127,12 +191,355 @@ db_model! {
         pub created_at: DateTime<Utc>,
         pub is_verified: bool,
         pub notifications_enabled: bool,
+        #[serde(default)]
+        pub role: Role,
+        #[serde(default)]
+        pub profile: ProfileSettings,
+        /// Personal timeline events up to this one have been seen
+        #[serde(default)]
+        pub last_read_event_id: Option<u64>,
+        #[serde(default)]
+        pub timeline: TimelinePreferences,
+    }
+}
+
+/// What a user shares on their public profile. Nothing is, by default
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ProfileSettings {
+    /// Whether the profile can be viewed at all
+    pub public: bool,
+    /// Whether a public profile lists followed packages and their activity
+    pub show_subscriptions: bool,
+}
+
+/// How global highlights are mixed into a user's personal timeline
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TimelinePreferences {
+    /// Interleave highlights without having to ask with `merge=true`
+    pub merge_highlights: bool,
+    /// The least severe vulnerability that counts as a highlight
+    pub highlight_severity: VulnerabilitySeverity,
+}
+
+impl Default for TimelinePreferences {
+    fn default() -> Self {
+        Self {
+            merge_highlights: false,
+            highlight_severity: VulnerabilitySeverity::Critical,
+        }
+    }
+}
+
+/// Granted on top of `ADMIN_USERNAMES` and `MODERATOR_USERNAMES`, which
+/// apply whatever a user's stored role is
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "api-server", derive(clap::ValueEnum))]
+pub enum Role {
+    #[default]
+    User,
+    Moderator,
+    Admin,
+}
+
+/// User as stored before roles were kept in the database
+#[cfg(feature = "db")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[native_model(id = 3, version = 1)]
+#[native_db]
+pub struct UserV1 {
+    #[primary_key]
+    pub id: u64,
+    #[secondary_key(unique)]
+    pub email: String,
+    #[secondary_key(unique)]
+    pub username: String,
+    pub password_hash: String,
+    pub subscriptions: Vec<PackageSubscription>,
+    pub created_at: DateTime<Utc>,
+    pub is_verified: bool,
+    pub notifications_enabled: bool,
+}
+
+#[cfg(feature = "db")]
+impl From<UserV1> for UserV2 {
+    fn from(u: UserV1) -> Self {
+        Self {
+            id: u.id,
+            email: u.email,
+            username: u.username,
+            password_hash: u.password_hash,
+            subscriptions: u.subscriptions,
+            created_at: u.created_at,
+            is_verified: u.is_verified,
+            notifications_enabled: u.notifications_enabled,
+            role: Role::User,
+        }
+    }
+}
+
+#[cfg(feature = "db")]
+impl From<UserV2> for UserV1 {
+    fn from(u: UserV2) -> Self {
+        Self {
+            id: u.id,
+            email: u.email,
+            username: u.username,
+            password_hash: u.password_hash,
+            subscriptions: u.subscriptions,
+            created_at: u.created_at,
+            is_verified: u.is_verified,
+            notifications_enabled: u.notifications_enabled,
+        }
+    }
+}
+
+/// User as stored before profile settings
+#[cfg(feature = "db")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[native_model(id = 3, version = 2, from = UserV1)]
+#[native_db]
+pub struct UserV2 {
+    #[primary_key]
+    pub id: u64,
+    #[secondary_key(unique)]
+    pub email: String,
+    #[secondary_key(unique)]
+    pub username: String,
+    pub password_hash: String,
+    pub subscriptions: Vec<PackageSubscription>,
+    pub created_at: DateTime<Utc>,
+    pub is_verified: bool,
+    pub notifications_enabled: bool,
+    pub role: Role,
+}
+
+#[cfg(feature = "db")]
+impl From<UserV2> for UserV3 {
+    fn from(u: UserV2) -> Self {
+        Self {
+            id: u.id,
+            email: u.email,
+            username: u.username,
+            password_hash: u.password_hash,
+            subscriptions: u.subscriptions,
+            created_at: u.created_at,
+            is_verified: u.is_verified,
+            notifications_enabled: u.notifications_enabled,
+            role: u.role,
+            profile: ProfileSettings::default(),
+        }
+    }
+}
+
+#[cfg(feature = "db")]
+impl From<UserV3> for UserV2 {
+    fn from(u: UserV3) -> Self {
+        Self {
+            id: u.id,
+            email: u.email,
+            username: u.username,
+            password_hash: u.password_hash,
+            subscriptions: u.subscriptions,
+            created_at: u.created_at,
+            is_verified: u.is_verified,
+            notifications_enabled: u.notifications_enabled,
+            role: u.role,
+        }
+    }
+}
+
+/// User as stored before timeline read tracking
+#[cfg(feature = "db")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[native_model(id = 3, version = 3, from = UserV2)]
+#[native_db]
+pub struct UserV3 {
+    #[primary_key]
+    pub id: u64,
+    #[secondary_key(unique)]
+    pub email: String,
+    #[secondary_key(unique)]
+    pub username: String,
+    pub password_hash: String,
+    pub subscriptions: Vec<PackageSubscription>,
+    pub created_at: DateTime<Utc>,
+    pub is_verified: bool,
+    pub notifications_enabled: bool,
+    pub role: Role,
+    pub profile: ProfileSettings,
+}
+
+#[cfg(feature = "db")]
+impl From<UserV3> for UserV4 {
+    fn from(u: UserV3) -> Self {
+        Self {
+            id: u.id,
+            email: u.email,
+            username: u.username,
+            password_hash: u.password_hash,
+            subscriptions: u.subscriptions,
+            created_at: u.created_at,
+            is_verified: u.is_verified,
+            notifications_enabled: u.notifications_enabled,
+            role: u.role,
+            profile: u.profile,
+            last_read_event_id: None,
+        }
+    }
+}
+
+#[cfg(feature = "db")]
+impl From<UserV4> for UserV3 {
+    fn from(u: UserV4) -> Self {
+        Self {
+            id: u.id,
+            email: u.email,
+            username: u.username,
+            password_hash: u.password_hash,
+            subscriptions: u.subscriptions,
+            created_at: u.created_at,
+            is_verified: u.is_verified,
+            notifications_enabled: u.notifications_enabled,
+            role: u.role,
+            profile: u.profile,
+        }
+    }
+}
+
+/// User as stored before timeline preferences
+#[cfg(feature = "db")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[native_model(id = 3, version = 4, from = UserV3)]
+#[native_db]
+pub struct UserV4 {
+    #[primary_key]
+    pub id: u64,
+    #[secondary_key(unique)]
+    pub email: String,
+    #[secondary_key(unique)]
+    pub username: String,
+    pub password_hash: String,
+    pub subscriptions: Vec<PackageSubscription>,
+    pub created_at: DateTime<Utc>,
+    pub is_verified: bool,
+    pub notifications_enabled: bool,
+    pub role: Role,
+    pub profile: ProfileSettings,
+    pub last_read_event_id: Option<u64>,
+}
+
+#[cfg(feature = "db")]
+impl From<UserV4> for User {
+    fn from(u: UserV4) -> Self {
+        Self {
+            id: u.id,
+            email: u.email,
+            username: u.username,
+            password_hash: u.password_hash,
+            subscriptions: u.subscriptions,
+            created_at: u.created_at,
+            is_verified: u.is_verified,
+            notifications_enabled: u.notifications_enabled,
+            role: u.role,
+            profile: u.profile,
+            last_read_event_id: u.last_read_event_id,
+            timeline: TimelinePreferences::default(),
+        }
+    }
+}
+
+#[cfg(feature = "db")]
+impl From<User> for UserV4 {
+    fn from(u: User) -> Self {
+        Self {
+            id: u.id,
+            email: u.email,
+            username: u.username,
+            password_hash: u.password_hash,
+            subscriptions: u.subscriptions,
+            created_at: u.created_at,
+            is_verified: u.is_verified,
+            notifications_enabled: u.notifications_enabled,
+            role: u.role,
+            profile: u.profile,
+            last_read_event_id: u.last_read_event_id,
+        }
+    }
+}
+
+/// A user's subscription to a package, indexed by package for release fan-out
+#[cfg(feature = "db")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[native_model(id = 7, version = 1)]
+#[native_db(primary_key(edge_key -> (u64, String)))]
+pub struct SubscriptionEdge {
+    pub user_id: u64,
+    #[secondary_key]
+    pub package_name: String,
+    pub notifications_enabled: bool,
+}
+
+#[cfg(feature = "db")]
+impl SubscriptionEdge {
+    fn edge_key(&self) -> (u64, String) {
+        (self.user_id, self.package_name.clone())
     }
+
+    pub fn from_user(user: &User) -> impl Iterator<Item = SubscriptionEdge> + '_ {
+        user.subscriptions.iter().map(|s| SubscriptionEdge {
+            user_id: user.id,
+            package_name: s.package_name.clone(),
+            notifications_enabled: s.notifications_enabled,
+        })
+    }
+}
+
+/// Number of users subscribed to a package, kept in step with its edges
+#[cfg(feature = "db")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[native_model(id = 8, version = 1)]
+#[native_db]
+pub struct SubscriberCount {
+    #[primary_key]
+    pub package_name: String,
+    pub count: u64,
+}
+
+/// Links a package to one of its maintainers
+#[cfg(feature = "db")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[native_model(id = 12, version = 1)]
+#[native_db(primary_key(link_key -> (u64, u64)))]
+pub struct PackageMaintainer {
+    #[secondary_key]
+    pub package_id: u64,
+    #[secondary_key]
+    pub maintainer_id: u64,
+}
+
+#[cfg(feature = "db")]
+impl PackageMaintainer {
+    fn link_key(&self) -> (u64, u64) {
+        (self.package_id, self.maintainer_id)
+    }
+}
+
+/// Claim on a piece of shared work, held until `expires_at` unless renewed
+#[cfg(feature = "db")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[native_model(id = 9, version = 1)]
+#[native_db]
+pub struct Lease {
+    #[primary_key]
+    pub name: String,
+    pub holder: String,
+    pub expires_at: DateTime<Utc>,
 }
 
 db_model! {
-    #[derive(Debug, Clone, Serialize, Deserialize)]
-    #[native_model(id = 4, version = 1)]
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    #[native_model(id = 4, version = 3, from = VulnerabilityV2)]
     #[native_db]
     pub struct Vulnerability {
         #[primary_key]
@@ -144,21 +551,532 @@ db_model! {
         pub affected_packages: Vec<AffectedPackage>,
         pub discovered_at: DateTime<Utc>,
         pub fixed_in: Option<String>,
+        pub cvss_vector: Option<String>,
+        pub cvss_score: Option<f32>,
+        pub epss_score: Option<f32>,
+        pub epss_percentile: Option<f32>,
+        pub scores_updated_at: Option<DateTime<Utc>>,
+        /// Kind of a RustSec-style informational advisory, such as
+        /// `unmaintained` or `unsound`. None for vulnerabilities.
+        #[serde(default)]
+        pub informational: Option<String>,
+    }
+}
+
+impl Vulnerability {
+    /// Whether the scores reach the given minimums; unscored advisories never do
+    pub fn meets_scores(&self, min_cvss: Option<f32>, min_epss: Option<f32>) -> bool {
+        min_cvss.is_none_or(|min| self.cvss_score.is_some_and(|s| s >= min))
+            && min_epss.is_none_or(|min| self.epss_score.is_some_and(|s| s >= min))
+    }
+}
+
+/// Vulnerability as stored before CVSS and EPSS scores were tracked
+#[cfg(feature = "db")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[native_model(id = 4, version = 1)]
+#[native_db]
+pub struct VulnerabilityV1 {
+    #[primary_key]
+    pub id: u64,
+    pub cve_id: Option<String>,
+    pub title: String,
+    pub description: String,
+    pub severity: VulnerabilitySeverity,
+    pub affected_packages: Vec<AffectedPackage>,
+    pub discovered_at: DateTime<Utc>,
+    pub fixed_in: Option<String>,
+}
+
+#[cfg(feature = "db")]
+impl From<VulnerabilityV1> for VulnerabilityV2 {
+    fn from(v: VulnerabilityV1) -> Self {
+        Self {
+            id: v.id,
+            cve_id: v.cve_id,
+            title: v.title,
+            description: v.description,
+            severity: v.severity,
+            affected_packages: v.affected_packages,
+            discovered_at: v.discovered_at,
+            fixed_in: v.fixed_in,
+            cvss_vector: None,
+            cvss_score: None,
+            epss_score: None,
+            epss_percentile: None,
+            scores_updated_at: None,
+        }
+    }
+}
+
+#[cfg(feature = "db")]
+impl From<VulnerabilityV2> for VulnerabilityV1 {
+    fn from(v: VulnerabilityV2) -> Self {
+        Self {
+            id: v.id,
+            cve_id: v.cve_id,
+            title: v.title,
+            description: v.description,
+            severity: v.severity,
+            affected_packages: v.affected_packages,
+            discovered_at: v.discovered_at,
+            fixed_in: v.fixed_in,
+        }
+    }
+}
+
+/// Vulnerability as stored before informational advisories
+#[cfg(feature = "db")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[native_model(id = 4, version = 2, from = VulnerabilityV1)]
+#[native_db]
+pub struct VulnerabilityV2 {
+    #[primary_key]
+    pub id: u64,
+    pub cve_id: Option<String>,
+    pub title: String,
+    pub description: String,
+    pub severity: VulnerabilitySeverity,
+    pub affected_packages: Vec<AffectedPackage>,
+    pub discovered_at: DateTime<Utc>,
+    pub fixed_in: Option<String>,
+    pub cvss_vector: Option<String>,
+    pub cvss_score: Option<f32>,
+    pub epss_score: Option<f32>,
+    pub epss_percentile: Option<f32>,
+    pub scores_updated_at: Option<DateTime<Utc>>,
+}
+
+#[cfg(feature = "db")]
+impl From<VulnerabilityV2> for Vulnerability {
+    fn from(v: VulnerabilityV2) -> Self {
+        Self {
+            id: v.id,
+            cve_id: v.cve_id,
+            title: v.title,
+            description: v.description,
+            severity: v.severity,
+            affected_packages: v.affected_packages,
+            discovered_at: v.discovered_at,
+            fixed_in: v.fixed_in,
+            cvss_vector: v.cvss_vector,
+            cvss_score: v.cvss_score,
+            epss_score: v.epss_score,
+            epss_percentile: v.epss_percentile,
+            scores_updated_at: v.scores_updated_at,
+            informational: None,
+        }
+    }
+}
+
+#[cfg(feature = "db")]
+impl From<Vulnerability> for VulnerabilityV2 {
+    fn from(v: Vulnerability) -> Self {
+        Self {
+            id: v.id,
+            cve_id: v.cve_id,
+            title: v.title,
+            description: v.description,
+            severity: v.severity,
+            affected_packages: v.affected_packages,
+            discovered_at: v.discovered_at,
+            fixed_in: v.fixed_in,
+            cvss_vector: v.cvss_vector,
+            cvss_score: v.cvss_score,
+            epss_score: v.epss_score,
+            epss_percentile: v.epss_percentile,
+            scores_updated_at: v.scores_updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum VulnerabilitySeverity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl std::str::FromStr for VulnerabilitySeverity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "low" => Ok(Self::Low),
+            "medium" => Ok(Self::Medium),
+            "high" => Ok(Self::High),
+            "critical" => Ok(Self::Critical),
+            _ => Err(format!("Unknown severity: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AffectedPackage {
+    pub package_id: u64,
+    pub version_range: String,
+}
+
+db_model! {
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    #[native_model(id = 10, version = 1)]
+    #[native_db]
+    pub struct CpeMapping {
+        #[primary_key]
+        pub id: u64,
+        /// `vendor:product` of the CPE names this package is published under
+        #[secondary_key(unique)]
+        pub vendor_product: String,
+        #[secondary_key]
+        pub package_id: u64,
+        pub source: CpeMappingSource,
+        pub created_at: DateTime<Utc>,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CpeMappingSource {
+    /// Guessed from the package name, open to correction
+    Heuristic,
+    /// Set by an admin, never replaced by a guess
+    Curated,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CpeMappingRequest {
+    /// Any CPE 2.2 or 2.3 name for the product
+    pub cpe: String,
+    pub package_id: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovePackagesRequest {
+    pub package_ids: Vec<u64>,
+}
+
+db_model! {
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    #[native_model(id = 11, version = 1)]
+    #[native_db]
+    pub struct Maintainer {
+        #[primary_key]
+        pub id: u64,
+        /// Lowercased GitHub login when known, so accounts on different
+        /// registries meet, otherwise `<platform>:<name>`
+        #[secondary_key(unique)]
+        pub handle: String,
+        pub name: Option<String>,
+        pub github: Option<String>,
+        pub created_at: DateTime<Utc>,
+    }
+}
+
+// A former name of a package, kept so it still resolves after a rename
+db_model! {
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    #[native_model(id = 13, version = 1)]
+    #[native_db]
+    pub struct PackageAlias {
+        #[primary_key]
+        pub name: String,
+        #[secondary_key]
+        pub package_id: u64,
+        pub created_at: DateTime<Utc>,
+    }
+}
+
+// The package users of a deprecated one are pointed to
+db_model! {
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    #[native_model(id = 22, version = 1)]
+    #[native_db]
+    pub struct PackageReplacement {
+        #[primary_key]
+        pub package_id: u64,
+        #[secondary_key]
+        pub replacement_id: u64,
+        /// Why, or how to migrate
+        pub note: Option<String>,
+        pub source: ReplacementSource,
+        pub updated_at: DateTime<Utc>,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplacementSource {
+    /// From an advisory naming what supersedes the package
+    Advisory,
+    /// Set by a moderator, never replaced by an advisory
+    Curated,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageReplacementRequest {
+    pub replacement_id: u64,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// A package's recommended successor, as shown alongside it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Successor {
+    pub package_id: u64,
+    pub name: String,
+    pub note: Option<String>,
+    pub source: ReplacementSource,
+}
+
+// How prominent a package is, the default order of listings
+db_model! {
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    #[native_model(id = 23, version = 1)]
+    #[native_db]
+    pub struct PackageRank {
+        #[primary_key]
+        pub package_id: u64,
+        /// Out of [`ranking::MAX_SCORE`], recomputed by [`ranking::recompute`]
+        pub score: u32,
+        /// Reported by registries that publish them
+        pub downloads: Option<u64>,
+        /// Reported by repository hosts
+        pub stars: Option<u32>,
+        pub computed_at: Option<DateTime<Utc>>,
+    }
+}
+
+// What happened across the catalog on one day, for charts over long ranges
+db_model! {
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+    #[native_model(id = 24, version = 1)]
+    #[native_db]
+    pub struct DailyRollup {
+        /// `YYYY-MM-DD`, in UTC
+        #[primary_key]
+        pub date: String,
+        pub packages_added: u64,
+        pub versions_released: u64,
+        pub vulnerabilities_published: u64,
+    }
+}
+
+// When a user was last seen, for expiring accounts under a retention policy
+db_model! {
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    #[native_model(id = 25, version = 1)]
+    #[native_db]
+    pub struct AccountActivity {
+        #[primary_key]
+        pub user_id: u64,
+        pub last_active_at: DateTime<Utc>,
+        /// When the user was told their account would be expired
+        pub warned_at: Option<DateTime<Utc>>,
+        pub anonymized_at: Option<DateTime<Utc>>,
+    }
+}
+
+// A collected package the license policy kept out of the catalog
+db_model! {
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    #[native_model(id = 14, version = 1)]
+    #[native_db]
+    pub struct QuarantinedPackage {
+        #[primary_key]
+        pub id: u64,
+        #[secondary_key(unique)]
+        pub name: String,
+        pub platform: Option<String>,
+        pub license: Option<String>,
+        pub reason: String,
+        /// Set by an admin to collect the package despite the policy
+        pub admitted: bool,
+        pub first_seen: DateTime<Utc>,
+        pub last_seen: DateTime<Utc>,
+    }
+}
+
+// A collected package hidden from the public API until a moderator approves it
+db_model! {
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    #[native_model(id = 15, version = 1)]
+    #[native_db]
+    pub struct PendingPackage {
+        #[primary_key]
+        pub package_id: u64,
+        pub created_at: DateTime<Utc>,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunOutcome {
+    Succeeded,
+    Failed,
+    /// A required external tool or credential is missing
+    Unavailable,
+}
+
+// One background collector run, kept so silent breakage can be spotted
+db_model! {
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    #[native_model(id = 16, version = 1)]
+    #[native_db]
+    pub struct CollectorRun {
+        #[primary_key]
+        pub id: u64,
+        #[secondary_key]
+        pub collector: String,
+        pub started_at: DateTime<Utc>,
+        pub finished_at: DateTime<Utc>,
+        pub duration_ms: u64,
+        pub outcome: RunOutcome,
+        pub error: Option<String>,
+        /// Packages seen upstream, whether or not anything changed
+        pub packages_found: u64,
+        pub packages_added: u64,
+        pub packages_updated: u64,
+        pub versions_added: u64,
+        /// Packages or requests that failed without aborting the run
+        pub errors: u64,
+        pub rate_limit_hits: u64,
+        /// What looked wrong compared to previous runs
+        pub anomalies: Vec<String>,
+    }
+}
+
+// A user's set of dependencies, resolved from a lockfile they upload, so
+// alerts can follow what they actually use
+db_model! {
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    #[native_model(id = 17, version = 1)]
+    #[native_db]
+    pub struct Project {
+        #[primary_key]
+        pub id: u64,
+        #[secondary_key]
+        pub user_id: u64,
+        pub name: String,
+        /// Sorted by name, one entry per name and version
+        pub dependencies: Vec<LockedDependency>,
+        pub created_at: DateTime<Utc>,
+        pub updated_at: DateTime<Utc>,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LockedDependency {
+    pub name: String,
+    pub version: String,
+}
+
+/// Links a project to a package it depends on, for alert fan-out
+#[cfg(feature = "db")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[native_model(id = 18, version = 1)]
+#[native_db(primary_key(edge_key -> (u64, String)))]
+pub struct ProjectDependency {
+    pub project_id: u64,
+    #[secondary_key]
+    pub package_name: String,
+}
+
+#[cfg(feature = "db")]
+impl ProjectDependency {
+    fn edge_key(&self) -> (u64, String) {
+        (self.project_id, self.package_name.clone())
+    }
+
+    pub fn from_project(project: &Project) -> impl Iterator<Item = ProjectDependency> + '_ {
+        let mut names: Vec<&str> = project.dependencies.iter().map(|d| d.name.as_str()).collect();
+        names.dedup();
+        names.into_iter().map(|name| ProjectDependency {
+            project_id: project.id,
+            package_name: name.to_string(),
+        })
+    }
+}
+
+// A GitHub App installation a user connected, through which their projects
+// can follow lockfiles in the account's repositories
+db_model! {
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    #[native_model(id = 19, version = 1)]
+    #[native_db]
+    pub struct GithubInstallation {
+        /// GitHub's installation ID
+        #[primary_key]
+        pub id: u64,
+        #[secondary_key]
+        pub user_id: u64,
+        /// Login of the user or organization the app is installed on
+        pub account: String,
+        pub created_at: DateTime<Utc>,
+    }
+}
+
+// Keeps a project's dependencies in step with a lockfile in a repository
+db_model! {
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    #[native_model(id = 20, version = 1)]
+    #[native_db]
+    pub struct ProjectSync {
+        #[primary_key]
+        pub project_id: u64,
+        #[secondary_key]
+        pub installation_id: u64,
+        /// `owner/name`
+        #[secondary_key]
+        pub repository: String,
+        /// Path of the lockfile within the repository
+        pub path: String,
+        /// Commit the dependencies were last read from
+        pub last_commit: Option<String>,
+        pub synced_at: Option<DateTime<Utc>>,
+        /// Why the last sync failed, cleared by the next one that succeeds
+        pub error: Option<String>,
+    }
+}
+
+// A user marking a security advisory as dealt with, everywhere or for one of
+// their projects, so it drops out of their reports and notifications
+db_model! {
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    #[native_model(id = 21, version = 1)]
+    #[native_db]
+    pub struct AdvisoryAck {
+        #[primary_key]
+        pub id: u64,
+        #[secondary_key]
+        pub user_id: u64,
+        #[secondary_key]
+        pub vulnerability_id: u64,
+        /// The project it's acknowledged for, or None for everything the
+        /// user follows
+        pub project_id: Option<u64>,
+        /// Why, e.g. that the affected code isn't used
+        pub note: Option<String>,
+        pub created_at: DateTime<Utc>,
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub enum VulnerabilitySeverity {
-    Low,
-    Medium,
-    High,
-    Critical,
+impl AdvisoryAck {
+    /// Whether this settles `vulnerability_id` in the context of a project,
+    /// or of the user's followed packages when `project_id` is None
+    pub fn covers(&self, vulnerability_id: u64, project_id: Option<u64>) -> bool {
+        self.vulnerability_id == vulnerability_id
+            && (self.project_id.is_none() || self.project_id == project_id)
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AffectedPackage {
-    pub package_id: u64,
-    pub version_range: String,
+impl Maintainer {
+    pub fn handle_for(platform: &str, github: Option<&str>, name: &str) -> String {
+        match github {
+            Some(login) => login.to_lowercase(),
+            None => format!("{}:{}", platform, name),
+        }
+    }
 }
 
 db_model! {
@@ -176,33 +1094,379 @@ db_model! {
         pub package_name: String,
         pub version: Option<String>,
         pub message: String,
+        /// JSON of an [`EventMetadata`], see [`TimelineEvent::typed_metadata`]
         pub metadata: Option<String>,
         pub created_at: DateTime<Utc>,
         pub notified_at: Option<DateTime<Utc>>,
     }
 }
 
+impl TimelineEvent {
+    /// The event's metadata, None when it has none or it isn't recognized
+    pub fn typed_metadata(&self) -> Option<EventMetadata> {
+        EventMetadata::parse(self.metadata.as_deref()?)
+    }
+}
+
+/// Details of a timeline event beyond its message, stored in
+/// [`TimelineEvent::metadata`] as JSON tagged with a `kind`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EventMetadata {
+    /// A security alert for an advisory affecting `version_range`
+    Vulnerability {
+        vulnerability_id: u64,
+        cve_id: Option<String>,
+        version_range: String,
+    },
+    /// A security alert for a name dangerously similar to existing ones,
+    /// most similar first
+    Typosquat { similar: Vec<typosquat::SimilarName> },
+    /// A security alert for a reported artifact checksum that doesn't match
+    /// the recorded one
+    ChecksumMismatch {
+        expected: Option<String>,
+        provided: String,
+    },
+    /// Maintainer handles added to and removed from a package
+    MaintainersChanged {
+        added: Vec<String>,
+        removed: Vec<String>,
+    },
+    Renamed { renamed_from: String },
+    RepositoryMoved {
+        repository_from: String,
+        repository_to: String,
+    },
+    /// The status a package moved to and why
+    Deprecated { status: String, reason: String },
+}
+
+impl EventMetadata {
+    /// Metadata stored before it was tagged is recognized by its fields
+    pub fn parse(json: &str) -> Option<Self> {
+        let mut value: serde_json::Value = serde_json::from_str(json).ok()?;
+        if let Some(object) = value.as_object_mut()
+            && !object.contains_key("kind")
+        {
+            let kind = [
+                ("vulnerability_id", "vulnerability"),
+                ("provided", "checksum_mismatch"),
+                ("added", "maintainers_changed"),
+                ("renamed_from", "renamed"),
+                ("repository_from", "repository_moved"),
+            ]
+            .into_iter()
+            .find(|(field, _)| object.contains_key(*field))?
+            .1;
+            object.insert("kind".to_string(), kind.into());
+        } else if value.is_array() {
+            value = serde_json::json!({ "kind": "typosquat", "similar": value });
+        }
+        serde_json::from_value(value).ok()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("event metadata serializes")
+    }
+}
+
+impl Package {
+    /// Statuses of packages that shouldn't be picked for new work
+    pub const INACTIVE_STATUSES: &[&str] = &["unmaintained", "archived", "deprecated", "removed"];
+
+    /// Whether the name or description contains `search`, ignoring case
+    pub fn matches_search(&self, search: &str) -> bool {
+        name_or_description_contains(&self.name, self.description.as_deref(), search)
+    }
+
+    /// Whether the status is one of [`Self::INACTIVE_STATUSES`], ignoring case
+    pub fn is_inactive(&self) -> bool {
+        self.status.as_deref().is_some_and(|status| {
+            Self::INACTIVE_STATUSES
+                .iter()
+                .any(|inactive| status.eq_ignore_ascii_case(inactive))
+        })
+    }
+}
+
+impl PackageSummary {
+    /// Fields a summary has, in the names `Package` serializes them as
+    pub const FIELDS: &[&str] = &[
+        "id",
+        "name",
+        "description",
+        "repository",
+        "license",
+        "platform",
+        "language",
+        "rank",
+    ];
+
+    /// Whether the name or description contains `search`, ignoring case
+    pub fn matches_search(&self, search: &str) -> bool {
+        name_or_description_contains(&self.name, self.description.as_deref(), search)
+    }
+}
+
+fn name_or_description_contains(name: &str, description: Option<&str>, search: &str) -> bool {
+    let search = search.to_lowercase();
+    name.to_lowercase().contains(&search)
+        || description.is_some_and(|d| d.to_lowercase().contains(&search))
+}
+
+impl From<Package> for PackageSummary {
+    fn from(package: Package) -> Self {
+        Self {
+            id: package.id,
+            name: package.name,
+            description: package.description,
+            repository: package.repository,
+            license: package.license,
+            platform: package.platform,
+            language: package.language,
+            rank: package.rank,
+        }
+    }
+}
+
+impl TimelineEvent {
+    /// Key shared by events that describe the same thing for the same
+    /// recipient. Events without a version are never deduplicated, so they
+    /// have none.
+    pub fn dedup_key(&self) -> Option<String> {
+        let version = self.version.as_ref()?;
+        let user_id = self.user_id.map(|id| id.to_string()).unwrap_or_default();
+        Some(format!(
+            "{}:{}:{}:{}",
+            self.package_id,
+            self.event_type.as_str(),
+            user_id,
+            version
+        ))
+    }
+
+    /// Whether the event should be emailed to its recipient
+    pub fn awaits_notification(&self) -> bool {
+        self.user_id.is_some()
+            && self.notified_at.is_none()
+            && self.event_type == EventType::NewRelease
+    }
+}
+
+/// Narrows a timeline query, every field left as None matches everything
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TimelineFilter {
+    pub user_id: Option<u64>,
+    pub package_id: Option<u64>,
+    pub event_type: Option<EventType>,
+    /// Only events created at or after this time
+    pub since: Option<DateTime<Utc>>,
+}
+
+impl TimelineFilter {
+    pub fn matches(&self, event: &TimelineEvent) -> bool {
+        self.user_id.is_none_or(|id| event.user_id == Some(id))
+            && self.package_id.is_none_or(|id| event.package_id == id)
+            && self
+                .event_type
+                .as_ref()
+                .is_none_or(|event_type| &event.event_type == event_type)
+            && self.since.is_none_or(|since| event.created_at >= since)
+    }
+}
+
+/// Index of personal events still awaiting an email notification
+#[cfg(feature = "db")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[native_model(id = 6, version = 1)]
+#[native_db]
+pub struct PendingNotification {
+    #[primary_key]
+    pub event_id: u64,
+}
+
+/// Index of versioned events by [`TimelineEvent::dedup_key`], so duplicates
+/// are found without scanning the package's timeline
+#[cfg(feature = "db")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[native_model(id = 26, version = 1)]
+#[native_db]
+pub struct TimelineDedup {
+    #[primary_key]
+    pub key: String,
+    pub event_id: u64,
+}
+
+/// A one-off upgrade that has already been applied to the database
+#[cfg(feature = "db")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[native_model(id = 27, version = 1)]
+#[native_db]
+pub struct AppliedMigration {
+    #[primary_key]
+    pub name: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum EventType {
     NewRelease,
     SecurityAlert,
     PackageAdded,
     PackageUpdated,
+    /// A package gained or lost maintainers, a common supply-chain risk
+    MaintainerChanged,
+    /// A package was found unmaintained, archived or otherwise abandoned
+    PackageDeprecated,
+}
+
+impl EventType {
+    /// The name used in query parameters and event topics
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventType::NewRelease => "new_release",
+            EventType::SecurityAlert => "security_alert",
+            EventType::PackageAdded => "package_added",
+            EventType::PackageUpdated => "package_updated",
+            EventType::MaintainerChanged => "maintainer_changed",
+            EventType::PackageDeprecated => "package_deprecated",
+        }
+    }
+}
+
+impl std::str::FromStr for EventType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "new_release" => Ok(Self::NewRelease),
+            "security_alert" => Ok(Self::SecurityAlert),
+            "package_added" => Ok(Self::PackageAdded),
+            "package_updated" => Ok(Self::PackageUpdated),
+            "maintainer_changed" => Ok(Self::MaintainerChanged),
+            "package_deprecated" => Ok(Self::PackageDeprecated),
+            _ => Err(format!("Unknown event type: {}", s)),
+        }
+    }
 }
 
 // Alias for API compatibility
 pub type TimelineEventType = EventType;
 
+/// What list views show of a package, so they don't pay for tags,
+/// timestamps and the rest
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PackageSummary {
+    pub id: u64,
+    pub name: String,
+    pub description: Option<String>,
+    pub repository: Option<String>,
+    pub license: Option<String>,
+    pub platform: Option<String>,
+    pub language: Option<String>,
+    pub rank: Option<u32>,
+}
+
+/// A typeahead completion for a partially typed package name
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PackageSuggestion {
+    pub name: String,
+    pub platform: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageResponse {
+    #[serde(flatten)]
+    pub package: Package,
+    pub subscriber_count: u64,
+    /// What to use instead, when the package has a recommended successor
+    #[serde(default)]
+    pub replacement: Option<Successor>,
+}
+
+/// One class of caller and the request quota it's held to
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RateLimitTier {
+    pub name: String,
+    pub requests_per_minute: u32,
+    /// What requests are counted against: `ip`, `hashed_ip` in privacy mode,
+    /// or `user`
+    pub keyed_by: String,
+    pub description: String,
+}
+
+/// The rate limits in force, for clients to configure their backoff
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RateLimitsResponse {
+    /// Tier of the caller that asked
+    pub tier: String,
+    pub tiers: Vec<RateLimitTier>,
+    /// Response headers carrying the caller's quota, with what each means
+    pub headers: std::collections::BTreeMap<String, String>,
+}
+
+/// Summary of a package for embedding on other sites
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PackageEmbed {
+    pub id: u64,
+    pub name: String,
+    pub description: Option<String>,
+    pub latest_version: Option<String>,
+    pub license: Option<String>,
+    /// Out of 100, see [`quality::health_score`]
+    pub health_score: u32,
+    /// Advisories affecting the latest version
+    pub open_advisories: usize,
+    pub url: Option<String>,
+}
+
+/// One package's column in a side-by-side comparison
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PackageComparison {
+    pub package: Package,
+    pub latest_version: Option<String>,
+    pub latest_release: Option<DateTime<Utc>>,
+    pub releases_last_year: usize,
+    /// Mean days between consecutive releases, None with fewer than two
+    pub release_interval_days: Option<u32>,
+    /// Direct dependencies of the latest version
+    pub dependency_count: usize,
+    /// Advisories affecting the latest version
+    pub open_vulnerabilities: usize,
+    /// Out of 100, see [`quality::health_score`]
+    pub health_score: u32,
+    pub subscriber_count: u64,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CreatePackageRequest {
-    pub name: String,
+    /// Defaults to the repository's name
+    pub name: Option<String>,
     pub description: Option<String>,
     pub homepage: Option<String>,
     pub repository: Option<String>,
     pub license: Option<String>,
+    #[serde(default)]
     pub tags: Vec<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VulnerabilityRequest {
+    pub cve_id: Option<String>,
+    pub title: String,
+    pub description: String,
+    pub severity: VulnerabilitySeverity,
+    pub affected_packages: Vec<AffectedPackage>,
+    pub fixed_in: Option<String>,
+    /// See [`Vulnerability::informational`]
+    #[serde(default)]
+    pub informational: Option<String>,
+    /// Name of the package superseding the affected ones, recorded as their
+    /// replacement
+    #[serde(default)]
+    pub superseded_by: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RegisterRequest {
     pub username: String,
@@ -230,6 +1494,131 @@ pub struct DatabaseStats {
     pub total_vulnerabilities: u64,
     pub total_timeline_events: u64,
     pub collectors_running: Vec<String>,
+    /// Every registered collector, whether running or not
+    #[serde(default)]
+    pub collectors: Vec<collector_status::CollectorSummary>,
+    #[serde(default)]
+    pub collector_queues: CollectorQueues,
+    #[serde(default)]
+    pub upstream_rates: Vec<UpstreamRate>,
+    /// Clients connected to the live timeline
+    #[serde(default)]
+    pub websocket_clients: u64,
+    /// Notifications waiting to be emailed
+    #[serde(default)]
+    pub email_queue: u64,
+}
+
+/// Collected packages waiting on someone before they're published
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CollectorQueues {
+    /// Held until a moderator approves them
+    pub pending_moderation: u64,
+    /// Kept out by the license policy and not yet admitted
+    pub quarantined: u64,
+}
+
+/// The request rate an adaptive client currently allows for an upstream host
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UpstreamRate {
+    pub host: String,
+    pub requests_per_second: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AnalyticsResponse {
+    pub total_packages: u64,
+    pub programming_languages: u64,
+    pub weekly_updates: u64,
+    pub language_distribution: Vec<LanguageStats>,
+    pub license_distribution: Vec<LicenseStats>,
+    pub trending_packages: Vec<TrendingPackage>,
+    pub security_overview: SecurityStats,
+    pub growth_data: Vec<GrowthPoint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LanguageStats {
+    pub language: String,
+    pub percentage: f32,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LicenseStats {
+    pub license: String,
+    pub percentage: f32,
+    pub count: u64,
+}
+
+/// A value a package list can be filtered by, with how many packages have it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FilterOption {
+    pub value: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrendingPackage {
+    pub name: String,
+    pub description: String,
+    pub growth_percentage: f32,
+    pub category: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SecurityStats {
+    pub clean_packages: u64,
+    pub minor_issues: u64,
+    pub critical_vulnerabilities: u64,
+    pub scan_coverage: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GrowthPoint {
+    pub date: String,
+    pub packages_added: u64,
+    pub cumulative_total: u64,
+}
+
+/// Activity in the bucket starting on `date`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct RollupPoint {
+    pub date: String,
+    pub packages_added: u64,
+    pub versions_released: u64,
+    pub vulnerabilities_published: u64,
+}
+
+/// A registry packages are collected from
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PlatformSummary {
+    pub name: String,
+    pub package_count: u64,
+}
+
+/// How one registry's packages are doing, for comparing ecosystems
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PlatformStats {
+    pub name: String,
+    pub package_count: u64,
+    pub releases_last_30_days: u64,
+    /// Mean releases per package over the last year
+    pub releases_per_package_per_year: f32,
+    /// Highest ranked packages first
+    pub top_packages: Vec<PackageSummary>,
+    /// Packages with at least one advisory against them
+    pub vulnerable_packages: u64,
+    /// Advisories affecting the registry's packages, per thousand packages
+    pub vulnerabilities_per_thousand: f32,
+}
+
+/// Releases of a package in one week, a cell of its activity heatmap
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ActivityWeek {
+    /// Monday the week starts on, `YYYY-MM-DD`
+    pub week: String,
+    pub releases: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -237,13 +1626,92 @@ pub struct SubscriptionRequest {
     pub package_name: String,
 }
 
+/// A subscription with the package it follows, if that still exists
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SubscriptionResponse {
     pub package_name: String,
     pub notifications_enabled: bool,
+    #[serde(default)]
     pub package: Option<Package>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscriptionsResponse {
+    pub subscriptions: Vec<SubscriptionResponse>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectRequest {
+    pub name: String,
+    /// Contents of a Cargo.lock, package-lock.json or pinned requirements.txt
+    pub lockfile: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockfileRequest {
+    pub lockfile: String,
+}
+
+/// How a project's dependencies changed between two lockfiles
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ProjectDiff {
+    pub added: Vec<LockedDependency>,
+    pub removed: Vec<LockedDependency>,
+    pub changed: Vec<VersionChange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VersionChange {
+    pub name: String,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectResponse {
+    #[serde(flatten)]
+    pub project: Project,
+    /// Set when the lockfile replaced an earlier one
+    pub diff: Option<ProjectDiff>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GithubInstallationRequest {
+    pub installation_id: u64,
+    /// OAuth code GitHub passes to the app's setup URL, showing the user can
+    /// access the installation
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectSyncRequest {
+    pub installation_id: u64,
+    /// `owner/name`
+    pub repository: String,
+    /// Path of the lockfile within the repository
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AdvisoryAckRequest {
+    /// Acknowledge for one project only, rather than everywhere
+    #[serde(default)]
+    pub project_id: Option<u64>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// What anyone can see of a user who made their profile public
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublicProfile {
+    pub username: String,
+    pub created_at: DateTime<Utc>,
+    /// Names of followed packages, if the user shares them
+    pub subscriptions: Option<Vec<String>>,
+    /// Recent public events for followed packages, newest first
+    pub activity: Option<Vec<TimelineEvent>>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TimelineResponse {
     pub events: Vec<TimelineEvent>,
@@ -252,6 +1720,13 @@ pub struct TimelineResponse {
     pub offset: usize,
 }
 
+/// Where a user is up to in their personal timeline
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TimelineReadState {
+    pub last_read_event_id: Option<u64>,
+    pub unread_count: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct UserResponse {
     pub id: u64,
@@ -279,6 +1754,135 @@ pub struct PackagesResponse {
     pub limit: u32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VulnerabilitiesResponse {
+    pub vulnerabilities: Vec<Vulnerability>,
+    pub total: usize,
+    pub page: u32,
+    pub limit: u32,
+}
+
+/// An affected package with its known versions split by the advisory's range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedAffectedPackage {
+    pub package_id: u64,
+    pub package_name: String,
+    pub version_range: String,
+    pub affected_versions: Vec<String>,
+    pub fixed_versions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VulnerabilityDetail {
+    pub id: u64,
+    pub cve_id: Option<String>,
+    pub title: String,
+    pub description: String,
+    pub severity: VulnerabilitySeverity,
+    pub discovered_at: DateTime<Utc>,
+    pub fixed_in: Option<String>,
+    pub cvss_vector: Option<String>,
+    pub cvss_score: Option<f32>,
+    pub epss_score: Option<f32>,
+    pub epss_percentile: Option<f32>,
+    pub scores_updated_at: Option<DateTime<Utc>>,
+    pub informational: Option<String>,
+    pub affected_packages: Vec<ResolvedAffectedPackage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyChecksumRequest {
+    /// Hex digest, optionally prefixed with its algorithm (`sha256:...`)
+    pub checksum: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecksumStatus {
+    Match,
+    Mismatch,
+    /// The registry didn't publish a checksum for this version
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChecksumVerification {
+    pub package_id: u64,
+    pub version: String,
+    pub expected: Option<String>,
+    pub status: ChecksumStatus,
+}
+
+/// A package version in a dependency graph. Nodes without a resolved
+/// `version` are not expanded further.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DependencyNode {
+    pub id: usize,
+    pub package_id: Option<u64>,
+    pub name: String,
+    pub version: Option<String>,
+    /// Advisories whose range includes the resolved version
+    #[serde(default)]
+    pub vulnerabilities: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DependencyEdge {
+    pub from: usize,
+    pub to: usize,
+    pub version_requirement: String,
+    pub dependency_type: String,
+    pub optional: bool,
+}
+
+/// Dependencies of a package version, resolved to the highest matching known versions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyGraph {
+    pub root: usize,
+    pub nodes: Vec<DependencyNode>,
+    pub edges: Vec<DependencyEdge>,
+    /// Set when the node limit stopped the graph from being fully expanded
+    pub truncated: bool,
+}
+
+/// An advisory that still affects the latest known version of a followed
+/// package, or the locked version of a project dependency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityAdvisory {
+    pub vulnerability_id: u64,
+    pub cve_id: Option<String>,
+    pub title: String,
+    pub severity: VulnerabilitySeverity,
+    pub package_id: u64,
+    pub package_name: String,
+    pub version_range: String,
+    /// The version checked against the range
+    pub latest_version: Option<String>,
+    pub fixed_in: Option<String>,
+    /// The lowest known release above the checked version that no advisory
+    /// for the package affects
+    #[serde(default)]
+    pub upgrade_to: Option<String>,
+    pub cvss_score: Option<f32>,
+    pub epss_score: Option<f32>,
+}
+
+/// Open advisories for a user's subscriptions, grouped by severity.
+///
+/// Each group is ordered by exploit likelihood (EPSS), then CVSS score.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SecurityReport {
+    pub total_open: usize,
+    pub packages_affected: usize,
+    /// Open advisories left out because the user acknowledged them
+    #[serde(default)]
+    pub acknowledged: usize,
+    pub critical: Vec<SecurityAdvisory>,
+    pub high: Vec<SecurityAdvisory>,
+    pub medium: Vec<SecurityAdvisory>,
+    pub low: Vec<SecurityAdvisory>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum WebSocketMessage {
@@ -286,25 +1890,97 @@ pub enum WebSocketMessage {
     Ping,
     Pong,
     TimelineEvent { event: TimelineEvent },
+    /// Events coalesced by the server, oldest first
+    TimelineEvents {
+        events: Vec<TimelineEvent>,
+        /// Send back in `Resume` after reconnecting to receive missed events
+        #[serde(default)]
+        resume_token: Option<String>,
+    },
+    Resume { token: String },
+    /// Sent after replaying every event missed since the resume token
+    Resumed,
+    /// The resume token is from another server run or older than the events
+    /// kept for replay, so the client has to reload its timeline instead
+    ResyncRequired,
 }
 
 // Conditionally compile modules based on features
+#[cfg(feature = "artifact-cache")]
+pub mod artifact_cache;
 #[cfg(feature = "api-server")]
 pub mod auth;
-#[cfg(feature = "api-server")]
+#[cfg(feature = "http-cassettes")]
+pub mod cassette;
+#[cfg(any(feature = "api-server", feature = "collector-libraries-io"))]
 pub mod client;
 #[cfg(feature = "api-server")]
 pub mod config;
-#[cfg(feature = "api-server")]
+pub mod cpe;
+#[cfg(feature = "import-csv")]
+pub mod csv_import;
+#[cfg(feature = "db")]
 pub mod db;
+#[cfg(feature = "db")]
+pub mod embedded;
+#[cfg(feature = "db")]
+pub mod name_filter;
+#[cfg(feature = "db")]
+pub mod storage;
+#[cfg(feature = "systemd")]
+pub mod systemd;
+#[cfg(feature = "db")]
+pub use embedded::Fossdb;
 #[cfg(feature = "api-server")]
 pub mod db_listener;
+#[cfg(feature = "db")]
+pub mod duplicates;
+#[cfg(feature = "enrichment")]
+pub mod enrichment;
+#[cfg(feature = "db")]
+pub mod filter_options;
+#[cfg(all(feature = "db", any(test, feature = "test-support")))]
+pub mod fixtures;
+#[cfg(feature = "github-app")]
+pub mod github_app;
+#[cfg(feature = "embed-frontend")]
+pub mod frontend;
+#[cfg(any(feature = "events-nats", feature = "events-mqtt"))]
+pub mod events;
+#[cfg(feature = "grpc")]
+pub mod grpc;
 #[cfg(feature = "api-server")]
 pub mod handlers;
 #[cfg(feature = "api-server")]
+pub mod idempotency;
+#[cfg(feature = "db")]
 pub mod id_generator;
+#[cfg(feature = "collector")]
+pub mod leases;
+#[cfg(feature = "db")]
+pub mod maintenance;
+#[cfg(feature = "db")]
+pub mod ranking;
+#[cfg(feature = "db")]
+pub mod retention;
 #[cfg(feature = "api-server")]
 pub mod middleware;
+#[cfg(feature = "prefetch")]
+pub mod prefetch;
+#[cfg(feature = "db")]
+pub mod projects;
+#[cfg(feature = "db")]
+pub mod query;
+#[cfg(feature = "api-server")]
+pub mod rate_limit;
+#[cfg(feature = "api-server")]
+pub mod rollups;
+#[cfg(feature = "db")]
+pub mod timeline;
+#[cfg(feature = "api-server")]
+pub mod validation;
+#[cfg(feature = "db")]
+pub mod vulnerability_links;
 #[cfg(feature = "api-server")]
 pub mod websocket;
 
@@ -312,10 +1988,29 @@ pub mod websocket;
 #[cfg(feature = "api-server")]
 #[derive(Clone)]
 pub struct AppState {
-    pub db: std::sync::Arc<db::Database>,
+    pub db: std::sync::Arc<dyn storage::Storage>,
     pub broadcaster: std::sync::Arc<websocket::TimelineBroadcaster>,
+    pub idempotency: std::sync::Arc<idempotency::IdempotencyStore>,
+    pub collectors: std::sync::Arc<collector_status::CollectorStatusRegistry>,
+    pub rate_limits: std::sync::Arc<rate_limit::RateLimits>,
+    pub filter_options: std::sync::Arc<filter_options::FilterOptionsCache>,
+    pub names: std::sync::Arc<typosquat::SharedNameIndex>,
+    #[cfg(feature = "artifact-cache")]
+    pub artifacts: std::sync::Arc<artifact_cache::ArtifactCache>,
+    #[cfg(feature = "prefetch")]
+    pub prefetcher: std::sync::Arc<prefetch::Prefetcher>,
+    /// None unless the app is configured
+    #[cfg(feature = "github-app")]
+    pub github_app: Option<std::sync::Arc<github_app::GithubApp>>,
 }
 
+pub mod collector_status;
+pub mod license_policy;
+pub mod quality;
+pub mod release_diff;
+pub mod typosquat;
+pub mod version_range;
+
 #[cfg(feature = "email")]
 pub mod email;
 
@@ -325,4 +2020,6 @@ pub mod notifications;
 #[cfg(feature = "collector")]
 pub mod collector_models;
 #[cfg(feature = "collector")]
+pub mod collector_runs;
+#[cfg(feature = "collector")]
 pub mod collectors;
@@ -0,0 +1,219 @@
+// Which licenses collectors admit into the catalog. License strings are
+// evaluated as SPDX expressions, with `/` accepted as a legacy OR.
+
+/// Licenses admitted when no allowlist is configured, matched as substrings
+/// of each license in an expression
+const FREE_LICENSES: &[&str] = &[
+    // Permissive licenses
+    "mit", "apache", "apache-2.0", "apache 2.0", "bsd", "isc", "cc0",
+    "unlicense", "wtfpl", "0bsd", "bsl-1.0", "ncsa", "zlib", "x11",
+
+    // Copyleft licenses
+    "gpl", "lgpl", "agpl", "mpl", "epl", "cpl", "cddl", "cecill",
+    "eupl", "osl", "afl", "artistic",
+
+    // Creative Commons free licenses
+    "cc-by", "cc-by-sa",
+
+    // Public domain
+    "public domain", "publicdomain", "unlicensed",
+];
+
+/// Always rejected unless allowlisted, checked before [`FREE_LICENSES`]
+const NON_FREE_KEYWORDS: &[&str] = &[
+    "proprietary", "commercial", "private", "closed",
+    "all rights reserved", "copyright only",
+    // Non-free Creative Commons licenses
+    "cc-by-nd", "cc-by-nc",
+];
+
+/// How a license or expression fares against the policy, worst first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verdict {
+    Denied,
+    Unknown,
+    Allowed,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LicensePolicy {
+    /// If set, only these licenses are admitted (case-insensitive SPDX IDs)
+    pub allow: Vec<String>,
+    /// Licenses that are never admitted
+    pub deny: Vec<String>,
+    /// Admit packages whose license is missing or unrecognized
+    pub accept_unknown: bool,
+}
+
+impl LicensePolicy {
+    fn license_verdict(&self, license: &str) -> Verdict {
+        let listed = |list: &[String]| list.iter().any(|l| l.eq_ignore_ascii_case(license));
+        if listed(&self.deny) {
+            return Verdict::Denied;
+        }
+        if !self.allow.is_empty() {
+            return if listed(&self.allow) { Verdict::Allowed } else { Verdict::Denied };
+        }
+
+        let license = license.to_lowercase();
+        if NON_FREE_KEYWORDS.iter().any(|k| license.contains(k)) {
+            Verdict::Denied
+        } else if FREE_LICENSES.iter().any(|l| license.contains(l)) {
+            Verdict::Allowed
+        } else {
+            Verdict::Unknown
+        }
+    }
+
+    /// Evaluate a license expression. A choice (`OR`) is as good as its best
+    /// option and a combination (`AND`) as bad as its worst part.
+    pub fn verdict(&self, license: Option<&str>) -> Verdict {
+        let tokens = license.map(tokenize).unwrap_or_default();
+        let mut parser = Parser { tokens: &tokens, pos: 0 };
+        match parser.or_expr(self) {
+            Some(verdict) if parser.pos == tokens.len() => verdict,
+            _ => Verdict::Unknown,
+        }
+    }
+
+    /// Why a license isn't admitted, or None if it is
+    pub fn rejection(&self, license: Option<&str>) -> Option<String> {
+        match (self.verdict(license), license) {
+            (Verdict::Allowed, _) => None,
+            (Verdict::Unknown, _) if self.accept_unknown => None,
+            (Verdict::Unknown, None) => Some("no license information".to_string()),
+            (Verdict::Unknown, Some(license)) => Some(format!("unrecognized license: {}", license)),
+            (Verdict::Denied, Some(license)) => Some(format!("license not allowed: {}", license)),
+            (Verdict::Denied, None) => Some("license not allowed".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    License(String),
+    And,
+    Or,
+    With,
+    Open,
+    Close,
+}
+
+/// Split an expression into tokens. Consecutive words that aren't operators
+/// form one license, so free-form names like "Public Domain" survive.
+fn tokenize(expression: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut words: Vec<&str> = Vec::new();
+    let flush = |words: &mut Vec<&str>, tokens: &mut Vec<Token>| {
+        if !words.is_empty() {
+            tokens.push(Token::License(words.join(" ")));
+            words.clear();
+        }
+    };
+
+    let spaced = expression.replace('(', " ( ").replace(')', " ) ").replace('/', " / ");
+    for word in spaced.split_whitespace() {
+        let operator = match word.to_ascii_uppercase().as_str() {
+            "AND" => Some(Token::And),
+            "OR" | "/" => Some(Token::Or),
+            "WITH" => Some(Token::With),
+            "(" => Some(Token::Open),
+            ")" => Some(Token::Close),
+            _ => None,
+        };
+        match operator {
+            Some(operator) => {
+                flush(&mut words, &mut tokens);
+                tokens.push(operator);
+            }
+            None => words.push(word),
+        }
+    }
+    flush(&mut words, &mut tokens);
+    tokens
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn next_is(&mut self, token: &Token) -> bool {
+        let matches = self.tokens.get(self.pos) == Some(token);
+        if matches {
+            self.pos += 1;
+        }
+        matches
+    }
+
+    fn or_expr(&mut self, policy: &LicensePolicy) -> Option<Verdict> {
+        let mut verdict = self.and_expr(policy)?;
+        while self.next_is(&Token::Or) {
+            verdict = verdict.max(self.and_expr(policy)?);
+        }
+        Some(verdict)
+    }
+
+    fn and_expr(&mut self, policy: &LicensePolicy) -> Option<Verdict> {
+        let mut verdict = self.term(policy)?;
+        while self.next_is(&Token::And) {
+            verdict = verdict.min(self.term(policy)?);
+        }
+        Some(verdict)
+    }
+
+    fn term(&mut self, policy: &LicensePolicy) -> Option<Verdict> {
+        if self.next_is(&Token::Open) {
+            let verdict = self.or_expr(policy)?;
+            return self.next_is(&Token::Close).then_some(verdict);
+        }
+        let Some(Token::License(license)) = self.tokens.get(self.pos) else {
+            return None;
+        };
+        self.pos += 1;
+        // Exceptions only grant extra permissions
+        if self.next_is(&Token::With) {
+            let Some(Token::License(_)) = self.tokens.get(self.pos) else {
+                return None;
+            };
+            self.pos += 1;
+        }
+        Some(policy.license_verdict(license))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expressions() {
+        let policy = LicensePolicy::default();
+        assert_eq!(policy.verdict(Some("MIT OR Apache-2.0")), Verdict::Allowed);
+        assert_eq!(policy.verdict(Some("GPL-2.0/GPL-3.0")), Verdict::Allowed);
+        assert_eq!(policy.verdict(Some("(MIT AND Proprietary) OR BSD-3-Clause")), Verdict::Allowed);
+        assert_eq!(policy.verdict(Some("MIT AND Proprietary")), Verdict::Denied);
+        assert_eq!(policy.verdict(Some("GPL-2.0 WITH Classpath-exception-2.0")), Verdict::Allowed);
+        assert_eq!(policy.verdict(Some("Public Domain")), Verdict::Allowed);
+        assert_eq!(policy.verdict(Some("CustomLicense")), Verdict::Unknown);
+        assert_eq!(policy.verdict(Some("MIT AND (Apache-2.0")), Verdict::Unknown);
+        assert_eq!(policy.verdict(None), Verdict::Unknown);
+        assert!(policy.rejection(None).is_some());
+    }
+
+    #[test]
+    fn test_configured_lists() {
+        let policy = LicensePolicy {
+            allow: vec!["MIT".to_string(), "Apache-2.0".to_string()],
+            deny: vec!["apache-2.0".to_string()],
+            accept_unknown: true,
+        };
+        assert_eq!(policy.verdict(Some("mit")), Verdict::Allowed);
+        assert_eq!(policy.verdict(Some("Apache-2.0")), Verdict::Denied);
+        assert_eq!(policy.verdict(Some("GPL-3.0")), Verdict::Denied);
+        assert_eq!(policy.rejection(Some("Apache-2.0 OR MIT")), None);
+        // Missing licenses count as unknown
+        assert_eq!(policy.rejection(None), None);
+    }
+}
@@ -1,6 +1,7 @@
 use anyhow::Result;
 use axum::{
     Router,
+    extract::{DefaultBodyLimit, State},
     response::Json,
     routing::{get, post},
 };
@@ -11,12 +12,18 @@ use std::{
     path::{Path, PathBuf},
     sync::Arc,
 };
-use tower_http::cors::CorsLayer;
-use tracing::{error, info};
+use tower_http::{
+    cors::{Any, CorsLayer},
+    limit::RequestBodyLimitLayer,
+};
+use tracing::{error, info, warn};
 
 // Import from the library
-use fossdb::{AppState, config::Config, db::Database, handlers, middleware};
-use fossdb::{Package, PackageVersion, User, Vulnerability, TimelineEvent};
+use fossdb::{AppState, config::Config, db::Database, handlers, idempotency, middleware, storage::Storage};
+#[cfg(feature = "storage-postgres")]
+use fossdb::storage::postgres::PostgresStorage;
+use fossdb::{Package, PackageVersion, Role, User, Vulnerability, TimelineEvent};
+use fossdb::query::{Condition, Query};
 
 #[cfg(feature = "email")]
 use fossdb::{email, notifications};
@@ -37,6 +44,10 @@ struct Args {
     /// Disable background collectors (only for serve command)
     #[arg(long, default_value_t = false)]
     no_collectors: bool,
+
+    /// Development mode: allow requests from any origin (only for serve command)
+    #[arg(long, default_value_t = false)]
+    dev: bool,
 }
 
 #[derive(clap::Subcommand, Debug)]
@@ -47,6 +58,10 @@ enum Commands {
         /// Disable background collectors
         #[arg(long, default_value_t = false)]
         no_collectors: bool,
+
+        /// Development mode: allow requests from any origin
+        #[arg(long, default_value_t = false)]
+        dev: bool,
     },
     /// Export database tables to JSON files
     #[cfg(feature = "db")]
@@ -59,7 +74,7 @@ enum Commands {
         #[arg(short, long)]
         table: Option<String>,
     },
-    /// Import database table from JSON file
+    /// Import database table from JSON file, or packages and versions from CSV
     #[cfg(feature = "db")]
     Import {
         /// Input file path (e.g., packages.json)
@@ -69,6 +84,141 @@ enum Commands {
         /// Merge with existing data instead of replacing
         #[arg(long, default_value_t = false)]
         merge: bool,
+
+        #[arg(long, value_enum, default_value_t = ImportFormat::Json)]
+        format: ImportFormat,
+
+        /// TOML file mapping CSV columns to fields (required for CSV)
+        #[cfg(feature = "import-csv")]
+        #[arg(long, required_if_eq("format", "csv"))]
+        map: Option<PathBuf>,
+
+        /// Import the valid CSV rows even if others have errors
+        #[cfg(feature = "import-csv")]
+        #[arg(long, default_value_t = false)]
+        skip_invalid: bool,
+
+        /// Don't ask before replacing existing data
+        #[arg(short, long, default_value_t = false)]
+        yes: bool,
+
+        /// Snapshot the database before writing to it
+        #[arg(long, default_value_t = false)]
+        backup_first: bool,
+    },
+    /// Filter and sort a table of the local database without starting the server
+    #[cfg(feature = "db")]
+    Query {
+        /// Table to read (packages, versions, users, vulnerabilities, timeline_events)
+        #[arg(short, long)]
+        table: String,
+
+        /// Condition such as `language=rust`, `rank>=10` or `name~serde`.
+        /// Operators are =, !=, <, <=, >, >= and ~ (contains). Repeat to
+        /// require all of them.
+        #[arg(short = 'w', long = "where")]
+        conditions: Vec<Condition>,
+
+        /// Field to sort by, prefixed with `-` for descending
+        #[arg(short, long, allow_hyphen_values = true)]
+        sort: Option<String>,
+
+        #[arg(short, long)]
+        limit: Option<usize>,
+
+        #[arg(short, long, value_enum, default_value_t = QueryFormat::Table)]
+        format: QueryFormat,
+
+        /// Columns for table output, e.g. `name,rank`
+        #[arg(short, long, value_delimiter = ',')]
+        columns: Vec<String>,
+    },
+    /// Manage accounts directly in the database, e.g. to bootstrap an admin
+    #[cfg(feature = "db")]
+    User {
+        #[command(subcommand)]
+        command: UserCommand,
+    },
+    /// Bulk load a package registry from its public data dump
+    #[cfg(any(feature = "collector-crates-io-dump", feature = "collector-libraries-io-dump"))]
+    Backfill {
+        #[command(subcommand)]
+        source: BackfillSource,
+    },
+}
+
+/// Passwords are prompted for on a terminal, or read as a line from stdin
+#[cfg(feature = "db")]
+#[derive(clap::Subcommand, Debug)]
+enum UserCommand {
+    /// Create a verified account
+    Create {
+        #[arg(long)]
+        username: String,
+        #[arg(long)]
+        email: String,
+        #[arg(long, value_enum, default_value_t = Role::User)]
+        role: Role,
+    },
+    List,
+    /// Change a user's role. Users listed in `ADMIN_USERNAMES` or
+    /// `MODERATOR_USERNAMES` keep those rights regardless.
+    SetRole {
+        username: String,
+        #[arg(value_enum)]
+        role: Role,
+    },
+    ResetPassword { username: String },
+    /// Delete an account and its subscriptions
+    Delete {
+        username: String,
+        /// Don't ask for confirmation
+        #[arg(short, long, default_value_t = false)]
+        yes: bool,
+    },
+}
+
+#[cfg(feature = "db")]
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum ImportFormat {
+    /// A table written by `fossdb export`
+    Json,
+    #[cfg(feature = "import-csv")]
+    Csv,
+}
+
+#[cfg(feature = "db")]
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum QueryFormat {
+    Table,
+    /// One JSON record per line
+    Json,
+}
+
+#[cfg(any(feature = "collector-crates-io-dump", feature = "collector-libraries-io-dump"))]
+#[derive(clap::Subcommand, Debug)]
+enum BackfillSource {
+    /// Import every crate from the crates.io database dump
+    #[cfg(feature = "collector-crates-io-dump")]
+    CratesIo {
+        /// Use an already downloaded db-dump.tar.gz instead of fetching it
+        #[arg(long)]
+        dump: Option<PathBuf>,
+
+        /// Keep the downloaded dump after importing
+        #[arg(long, default_value_t = false)]
+        keep_dump: bool,
+    },
+    /// Import projects, versions and dependencies from the libraries.io open data dump
+    #[cfg(feature = "collector-libraries-io-dump")]
+    LibrariesIo {
+        /// Directory containing the extracted CSV files
+        #[arg(long)]
+        path: PathBuf,
+
+        /// Only import these platforms, e.g. `--platform npm --platform pypi`
+        #[arg(long = "platform")]
+        platforms: Vec<String>,
     },
 }
 
@@ -88,16 +238,58 @@ async fn main() -> Result<()> {
             return export_database(&config, output_dir, table).await;
         }
         #[cfg(feature = "db")]
-        Some(Commands::Import { input, merge }) => {
-            return import_database(&config, input, merge).await;
+        #[cfg(feature = "import-csv")]
+        Some(Commands::Import {
+            input,
+            format: ImportFormat::Csv,
+            map,
+            skip_invalid,
+            backup_first,
+            ..
+        }) => {
+            return import_csv(&config, input, map.unwrap(), skip_invalid, backup_first).await;
+        }
+        #[cfg(feature = "db")]
+        Some(Commands::Import {
+            input,
+            merge,
+            yes,
+            backup_first,
+            ..
+        }) => {
+            return import_database(&config, input, merge, yes, backup_first).await;
+        }
+        #[cfg(feature = "db")]
+        Some(Commands::Query {
+            table,
+            conditions,
+            sort,
+            limit,
+            format,
+            columns,
+        }) => {
+            let query = Query {
+                conditions,
+                sort,
+                limit,
+            };
+            return query_table(&config, &table, &query, format, &columns).await;
+        }
+        #[cfg(feature = "db")]
+        Some(Commands::User { command }) => {
+            return manage_users(&config, command).await;
+        }
+        #[cfg(any(feature = "collector-crates-io-dump", feature = "collector-libraries-io-dump"))]
+        Some(Commands::Backfill { source }) => {
+            return backfill(&config, source).await;
         }
         #[cfg(feature = "api-server")]
-        Some(Commands::Serve { no_collectors }) => {
-            return start_server(config, no_collectors).await;
+        Some(Commands::Serve { no_collectors, dev }) => {
+            return start_server(config, no_collectors, dev).await;
         }
         None => {
             #[cfg(feature = "api-server")]
-            return start_server(config, args.no_collectors).await;
+            return start_server(config, args.no_collectors, args.dev).await;
             #[cfg(not(feature = "api-server"))]
             {
                 std::future::pending::<()>().await;
@@ -107,10 +299,78 @@ async fn main() -> Result<()> {
     }
 }
 
-async fn start_server(config: Config, no_collectors: bool) -> Result<()> {
-    // Initialize native_db
-    let db = Database::new(&config.database_path)?;
-    let db = Arc::new(db);
+/// Where data is kept: the embedded database, or Postgres when `DATABASE_URL`
+/// is set and support is compiled in
+enum Backend {
+    Embedded(Arc<Database>),
+    #[cfg(feature = "storage-postgres")]
+    Postgres(Arc<PostgresStorage>),
+}
+
+impl Backend {
+    async fn open(config: &Config) -> Result<Self> {
+        #[cfg(feature = "storage-postgres")]
+        if let Some(url) = &config.database_url {
+            return Ok(Backend::Postgres(Arc::new(PostgresStorage::connect(url).await?)));
+        }
+        #[cfg(not(feature = "storage-postgres"))]
+        if config.database_url.is_some() {
+            warn!("DATABASE_URL is set but Postgres support isn't compiled in, using DATABASE_PATH");
+        }
+
+        Ok(Backend::Embedded(Arc::new(Database::new(&config.database_path)?)))
+    }
+
+    /// Snapshot the database before `table` is written to. The embedded
+    /// database is copied whole, Postgres has `table` exported as JSON.
+    #[cfg_attr(not(feature = "storage-postgres"), allow(unused_variables))]
+    fn backup(&self, config: &Config, table: &str) -> Result<PathBuf> {
+        let timestamp = chrono::Utc::now().format("%Y%m%dT%H%M%S");
+        match self {
+            Backend::Embedded(_) => {
+                let path = PathBuf::from(format!("{}.{}.bak", config.database_path, timestamp));
+                std::fs::copy(&config.database_path, &path)?;
+                Ok(path)
+            }
+            #[cfg(feature = "storage-postgres")]
+            Backend::Postgres(db) => {
+                let dir = PathBuf::from(format!("fossdb-backup-{}", timestamp));
+                std::fs::create_dir_all(&dir)?;
+                export_named(&**db, table, &dir)?;
+                Ok(dir)
+            }
+        }
+    }
+
+    fn storage(&self) -> Arc<dyn Storage> {
+        match self {
+            Backend::Embedded(db) => db.clone(),
+            #[cfg(feature = "storage-postgres")]
+            Backend::Postgres(db) => db.clone(),
+        }
+    }
+
+    /// Create timeline events for new versions as they're stored
+    #[cfg(feature = "collector")]
+    async fn spawn_version_listener(
+        &self,
+        broadcaster: Arc<websocket::TimelineBroadcaster>,
+    ) -> Result<()> {
+        match self {
+            Backend::Embedded(db) => {
+                fossdb::db_listener::spawn_package_version_listener(db.clone(), broadcaster)
+            }
+            #[cfg(feature = "storage-postgres")]
+            Backend::Postgres(db) => {
+                fossdb::db_listener::spawn_postgres_version_listener(db.clone(), broadcaster).await
+            }
+        }
+    }
+}
+
+async fn start_server(config: Config, no_collectors: bool, dev: bool) -> Result<()> {
+    let backend = Backend::open(&config).await?;
+    let db = backend.storage();
 
     // Log database statistics
     let num_packages = db.get_all_packages()?.len();
@@ -128,22 +388,142 @@ async fn start_server(config: Config, no_collectors: bool) -> Result<()> {
 
     // Initialize timeline broadcaster
     let broadcaster = Arc::new(websocket::TimelineBroadcaster::new());
+    websocket::spawn_flusher(
+        broadcaster.clone(),
+        std::time::Duration::from_millis(config.ws_flush_interval_ms.max(1)),
+    );
+
+    // Daily activity for analytics, recounted before anything new is
+    // published and counted live from then on
+    match fossdb::rollups::rebuild(&*db) {
+        Ok(days) => info!("Counted activity on {} days", days),
+        Err(e) => error!("Failed to count daily activity: {}", e),
+    }
+    fossdb::rollups::spawn_maintainer(db.clone(), broadcaster.clone());
 
     // Initialize database listener for automatic timeline event creation
     #[cfg(feature = "collector")]
-    if !no_collectors {
-        if let Err(e) =
-            fossdb::db_listener::spawn_package_version_listener(db.clone(), broadcaster.clone())
-        {
-            error!("Failed to initialize database listener: {}", e);
-        }
+    if !no_collectors
+        && let Err(e) = backend.spawn_version_listener(broadcaster.clone()).await
+    {
+        error!("Failed to initialize database listener: {}", e);
     }
 
     let state = AppState {
         db: db.clone(),
         broadcaster: broadcaster.clone(),
+        idempotency: Arc::new(idempotency::IdempotencyStore::new(
+            std::time::Duration::from_secs(config.idempotency_ttl_seconds),
+        )),
+        collectors: Arc::new(fossdb::collector_status::CollectorStatusRegistry::new()),
+        rate_limits: Arc::new(
+            fossdb::rate_limit::RateLimits::new(
+                config.rate_limit_anonymous_per_minute,
+                config.rate_limit_authenticated_per_minute,
+                config.rate_limit_trust_forwarded,
+            )
+            .with_privacy_mode(config.privacy_mode),
+        ),
+        filter_options: Arc::new(fossdb::filter_options::FilterOptionsCache::new(
+            std::time::Duration::from_secs(300),
+        )),
+        names: Arc::new(fossdb::typosquat::SharedNameIndex::new(
+            std::time::Duration::from_secs(3600),
+        )),
+        #[cfg(feature = "artifact-cache")]
+        artifacts: Arc::new(fossdb::artifact_cache::ArtifactCache::new(
+            &config.artifact_cache_dir,
+            reqwest::Client::builder().user_agent("fossdb").build()?,
+            config.artifact_max_size_mb,
+        )),
+        #[cfg(feature = "prefetch")]
+        prefetcher: Arc::new(fossdb::prefetch::Prefetcher::new(
+            reqwest::Client::builder().user_agent("fossdb").build()?,
+            config.github_token.clone(),
+        )),
+        #[cfg(feature = "github-app")]
+        github_app: match config.github_app_id {
+            Some(_) => Some(Arc::new(fossdb::github_app::GithubApp::new(
+                &config,
+                reqwest::Client::builder().user_agent("fossdb").build()?,
+            )?)),
+            None => None,
+        },
     };
 
+    // Forget idle callers so the rate limiters don't grow without bound
+    {
+        let rate_limits = state.rate_limits.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+            loop {
+                interval.tick().await;
+                rate_limits.retain_recent();
+            }
+        });
+    }
+
+    if let Some(url) = &config.event_broker_url {
+        #[cfg(any(feature = "events-nats", feature = "events-mqtt"))]
+        match fossdb::events::EventBroker::connect(url, &config.instance_id).await {
+            Ok(broker) => {
+                info!("Publishing timeline events to {}", url);
+                fossdb::events::spawn_publisher(
+                    broker,
+                    config.event_topic_prefix.clone(),
+                    broadcaster.clone(),
+                );
+            }
+            Err(e) => error!("Failed to connect to event broker {}: {}", url, e),
+        }
+        #[cfg(not(any(feature = "events-nats", feature = "events-mqtt")))]
+        warn!("EVENT_BROKER_URL is set but no broker support is compiled in: {}", url);
+    }
+
+    // Warn users of long unused accounts, then expire the accounts of
+    // those who don't come back
+    match config.retention_policy() {
+        Ok(None) => {}
+        #[cfg(feature = "email")]
+        Ok(Some(policy)) if config.email_enabled => {
+            let retention_db = db.clone();
+            let email_service = email::EmailService::new(config.clone())
+                .expect("Failed to initialize email service");
+            tokio::spawn(async move {
+                loop {
+                    if let Err(e) =
+                        expire_inactive_accounts(&*retention_db, &policy, &email_service).await
+                    {
+                        error!("Failed to expire inactive accounts: {}", e);
+                    }
+                    tokio::time::sleep(tokio::time::Duration::from_secs(24 * 60 * 60)).await;
+                }
+            });
+        }
+        Ok(Some(_)) => {
+            warn!(
+                "INACTIVE_ACCOUNT_MONTHS is set but users can't be warned without email, no accounts will be expired"
+            );
+        }
+        Err(e) => error!("Invalid account retention policy: {}", e),
+    }
+
+    #[cfg(feature = "grpc")]
+    {
+        let addr = std::net::SocketAddr::from(([0, 0, 0, 0], config.grpc_port));
+        let service = fossdb::grpc::PackagesService::new(state.db.clone(), state.broadcaster.clone());
+        tokio::spawn(async move {
+            info!("gRPC server running on {}", addr);
+            if let Err(e) = tonic::transport::Server::builder()
+                .add_service(service.into_server())
+                .serve(addr)
+                .await
+            {
+                error!("gRPC server failed: {}", e);
+            }
+        });
+    }
+
     // Initialize collectors (if not disabled)
     #[cfg(feature = "collector")]
     if !no_collectors {
@@ -155,7 +535,7 @@ async fn start_server(config: Config, no_collectors: bool) -> Result<()> {
         #[cfg(feature = "collector-rust")]
         {
             let client = reqwest::Client::builder().user_agent("fossdb").build()?;
-            let crates_collector = collectors::crates_io::CratesIoCollector::new(client.clone());
+            let crates_collector = collectors::crates_io::CratesIoCollector::new(client.clone(), config.admission());
             collectors.push(Arc::new(crates_collector));
         }
 
@@ -163,7 +543,12 @@ async fn start_server(config: Config, no_collectors: bool) -> Result<()> {
         if let Some(api_key) = config.libraries_io_api_key.clone() {
             let client = reqwest::Client::builder().user_agent("fossdb").build()?;
             let libraries_collector =
-                collectors::libraries_io::LibrariesIoCollector::new(client.clone(), api_key);
+                collectors::libraries_io::LibrariesIoCollector::new(
+                    client.clone(),
+                    api_key,
+                    config.admission(),
+                )
+                .with_statuses(state.collectors.clone());
             collectors.push(Arc::new(libraries_collector));
         } else {
             use anyhow::bail;
@@ -172,13 +557,27 @@ async fn start_server(config: Config, no_collectors: bool) -> Result<()> {
         }
 
         #[cfg(feature = "collector-nixpkgs")]
-        collectors.push(Arc::new(collectors::nixpkgs::NixpkgsCollector {}));
+        collectors.push(Arc::new(collectors::nixpkgs::NixpkgsCollector::new(
+            collectors::nixpkgs::NixLimits {
+                timeout: std::time::Duration::from_secs(config.nix_timeout_seconds),
+                max_output_bytes: config.nix_max_output_mb * 1024 * 1024,
+                max_concurrency: config.nix_max_concurrency,
+            },
+            config.admission(),
+        )));
 
         // Spawn one background task per collector
         for collector in collectors {
             let db = db.clone();
+            let statuses = state.collectors.clone();
+            let broadcaster = state.broadcaster.clone();
             let interval_hours = config.collector_interval_hours;
-            tokio::spawn(async move { run_collector_loop(collector, db, interval_hours).await });
+            let holder = config.instance_id.clone();
+            statuses.register(collector.name());
+            tokio::spawn(async move {
+                run_collector_loop(collector, db, statuses, broadcaster, holder, interval_hours)
+                    .await
+            });
         }
 
         // Initialize notification processor
@@ -213,6 +612,82 @@ async fn start_server(config: Config, no_collectors: bool) -> Result<()> {
             info!("Email disabled, notification processor not started");
         }
 
+        #[cfg(feature = "enrichment")]
+        {
+            let client = reqwest::Client::builder().user_agent("fossdb").build()?;
+            let enricher =
+                fossdb::enrichment::ScoreEnricher::new(client, config.nvd_api_key.clone());
+            let interval_hours = config.enrichment_interval_hours;
+            tokio::spawn(fossdb::enrichment::run_enrichment_loop(
+                enricher,
+                db.clone(),
+                interval_hours,
+            ));
+        }
+
+        // Catch up on advisory links of versions stored by other processes,
+        // like collectors importing advisories
+        let links_db = db.clone();
+        tokio::spawn(async move {
+            loop {
+                match fossdb::vulnerability_links::reconcile(&*links_db) {
+                    Ok(count) => {
+                        if count > 0 {
+                            info!("Linked {} versions to their advisories", count);
+                        }
+                    }
+                    Err(e) => {
+                        error!("Failed to link versions to advisories: {}", e);
+                    }
+                }
+                tokio::time::sleep(tokio::time::Duration::from_secs(24 * 60 * 60)).await;
+            }
+        });
+
+        // Mark packages that look abandoned, checking whether the repositories
+        // of followed packages were archived
+        let maintenance_db = db.clone();
+        #[cfg(feature = "prefetch")]
+        let prefetcher = state.prefetcher.clone();
+        tokio::spawn(async move {
+            loop {
+                #[cfg(feature = "prefetch")]
+                let archived =
+                    fossdb::maintenance::check_archived(&*maintenance_db, &prefetcher).await;
+                #[cfg(not(feature = "prefetch"))]
+                let archived = std::collections::HashMap::new();
+                match fossdb::maintenance::sweep(&*maintenance_db, None, &archived, chrono::Utc::now()) {
+                    Ok(count) => {
+                        if count > 0 {
+                            info!("Updated the status of {} packages", count);
+                        }
+                    }
+                    Err(e) => {
+                        error!("Failed to update package statuses: {}", e);
+                    }
+                }
+                tokio::time::sleep(tokio::time::Duration::from_secs(24 * 60 * 60)).await;
+            }
+        });
+
+        // Rescore packages for the default order of listings
+        let ranking_db = db.clone();
+        tokio::spawn(async move {
+            loop {
+                match fossdb::ranking::recompute(&*ranking_db, chrono::Utc::now()) {
+                    Ok(count) => {
+                        if count > 0 {
+                            info!("Updated the rank of {} packages", count);
+                        }
+                    }
+                    Err(e) => {
+                        error!("Failed to rank packages: {}", e);
+                    }
+                }
+                tokio::time::sleep(tokio::time::Duration::from_secs(24 * 60 * 60)).await;
+            }
+        });
+
         // Spawn timeline event purge task
         let purge_db = db.clone();
         let retention_days = config.timeline_retention_days;
@@ -244,6 +719,10 @@ async fn start_server(config: Config, no_collectors: bool) -> Result<()> {
     // Protected routes that require authentication
     let protected = Router::new()
         .route("/api/packages", post(handlers::packages::create_package))
+        .route(
+            "/api/packages/{id}/versions/{version}/verify",
+            post(handlers::packages::verify_checksum),
+        )
         .route(
             "/api/users/subscriptions",
             get(handlers::users::get_subscriptions),
@@ -260,6 +739,18 @@ async fn start_server(config: Config, no_collectors: bool) -> Result<()> {
             "/api/users/subscriptions/{package_name}/notifications",
             axum::routing::put(handlers::users::update_package_notification),
         )
+        .route(
+            "/api/users/security-report",
+            get(handlers::users::get_security_report),
+        )
+        .route(
+            "/api/users/timeline/unread",
+            get(handlers::users::get_unread_count),
+        )
+        .route(
+            "/api/users/timeline/read",
+            post(handlers::users::mark_timeline_read),
+        )
         .route(
             "/api/users/settings/notifications",
             get(handlers::users::get_notification_settings),
@@ -268,6 +759,137 @@ async fn start_server(config: Config, no_collectors: bool) -> Result<()> {
             "/api/users/settings/notifications",
             axum::routing::put(handlers::users::update_notification_settings),
         )
+        .route(
+            "/api/users/settings/profile",
+            get(handlers::users::get_profile_settings),
+        )
+        .route(
+            "/api/users/settings/profile",
+            axum::routing::put(handlers::users::update_profile_settings),
+        )
+        .route(
+            "/api/users/acknowledgements",
+            get(handlers::users::list_advisory_acks),
+        )
+        .route(
+            "/api/vulnerabilities/{id}/ack",
+            post(handlers::vulnerabilities::acknowledge_vulnerability)
+                .delete(handlers::vulnerabilities::unacknowledge_vulnerability),
+        )
+        .route(
+            "/api/users/settings/timeline",
+            get(handlers::users::get_timeline_preferences),
+        )
+        .route(
+            "/api/users/settings/timeline",
+            axum::routing::put(handlers::users::update_timeline_preferences),
+        )
+        .route(
+            "/api/projects",
+            get(handlers::projects::list_projects).post(handlers::projects::create_project),
+        )
+        .route(
+            "/api/projects/{id}",
+            get(handlers::projects::get_project).delete(handlers::projects::delete_project),
+        )
+        .route(
+            "/api/projects/{id}/lockfile",
+            axum::routing::put(handlers::projects::update_lockfile),
+        );
+
+    #[cfg(feature = "github-app")]
+    let protected = protected
+        .route(
+            "/api/github/installations",
+            get(handlers::github::list_installations).post(handlers::github::add_installation),
+        )
+        .route(
+            "/api/github/installations/{id}",
+            axum::routing::delete(handlers::github::delete_installation),
+        )
+        .route(
+            "/api/projects/{id}/sync",
+            get(handlers::github::get_project_sync)
+                .put(handlers::github::set_project_sync)
+                .delete(handlers::github::delete_project_sync),
+        );
+
+    let protected = protected
+        .layer(axum::middleware::from_fn(middleware::auth_middleware))
+        .with_state(state.clone());
+
+    // Admin routes - auth_middleware is layered last so it runs first
+    let admin = Router::new()
+        .route(
+            "/api/admin/data-quality",
+            get(handlers::admin::get_data_quality),
+        )
+        .route(
+            "/api/admin/collectors",
+            get(handlers::admin::get_collector_status),
+        )
+        .route(
+            "/api/admin/collectors/runs",
+            get(handlers::admin::list_collector_runs),
+        )
+        .route(
+            "/api/admin/cpe-mappings",
+            get(handlers::admin::list_cpe_mappings).post(handlers::admin::set_cpe_mapping),
+        )
+        .route(
+            "/api/admin/cpe-mappings/{id}",
+            axum::routing::delete(handlers::admin::delete_cpe_mapping),
+        )
+        .route(
+            "/api/admin/quarantine",
+            get(handlers::admin::list_quarantined_packages),
+        )
+        .route(
+            "/api/admin/quarantine/{id}",
+            axum::routing::delete(handlers::admin::delete_quarantined_package),
+        )
+        .route(
+            "/api/admin/quarantine/{id}/admit",
+            post(handlers::admin::admit_quarantined_package),
+        )
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            middleware::admin_middleware,
+        ))
+        .layer(axum::middleware::from_fn(middleware::auth_middleware));
+
+    // Vulnerability curation and package review routes for moderators and admins
+    let moderation = Router::new()
+        .route(
+            "/api/vulnerabilities",
+            post(handlers::vulnerabilities::create_vulnerability),
+        )
+        .route(
+            "/api/vulnerabilities/{id}",
+            axum::routing::put(handlers::vulnerabilities::update_vulnerability)
+                .delete(handlers::vulnerabilities::delete_vulnerability),
+        )
+        .route(
+            "/api/moderation/packages",
+            get(handlers::moderation::list_pending_packages),
+        )
+        .route(
+            "/api/moderation/packages/approve",
+            post(handlers::moderation::approve_packages),
+        )
+        .route(
+            "/api/moderation/packages/{id}/approve",
+            post(handlers::moderation::approve_package),
+        )
+        .route(
+            "/api/packages/{id}/replacement",
+            axum::routing::put(handlers::packages::set_package_replacement)
+                .delete(handlers::packages::delete_package_replacement),
+        )
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            middleware::moderator_middleware,
+        ))
         .layer(axum::middleware::from_fn(middleware::auth_middleware))
         .with_state(state.clone());
 
@@ -280,19 +902,87 @@ async fn start_server(config: Config, no_collectors: bool) -> Result<()> {
         ))
         .with_state(state.clone());
 
+    let cors = if dev {
+        info!("Development mode: CORS is permissive");
+        CorsLayer::permissive()
+    } else {
+        middleware::cors_layer(&config)
+    };
+
     let app = Router::new()
         .route("/api/health", get(health_check))
         .route("/api/stats", get(handlers::analytics::get_db_stats))
+        .route("/api/limits", get(handlers::limits::get_limits))
+        .route("/api/meta/languages", get(handlers::meta::get_languages))
+        .route("/api/meta/licenses", get(handlers::meta::get_licenses))
+        .route("/api/users/{username}", get(handlers::users::get_public_profile))
+        .route("/api/platforms", get(handlers::platforms::list_platforms))
+        .route(
+            "/api/platforms/{name}/stats",
+            get(handlers::platforms::get_platform_stats),
+        )
         .route("/api/packages", get(handlers::packages::list_packages))
+        .route(
+            "/api/packages/autocomplete",
+            get(handlers::packages::autocomplete_packages),
+        )
+        .route(
+            "/api/packages/check-name",
+            get(handlers::packages::check_package_name),
+        )
+        .route(
+            "/api/packages/compare",
+            get(handlers::packages::compare_packages),
+        )
+        .route(
+            "/api/packages/by-name/{name}",
+            get(handlers::packages::get_package_by_name),
+        )
         .route("/api/packages/{id}", get(handlers::packages::get_package))
+        .route(
+            "/api/packages/{id}/aliases",
+            get(handlers::packages::get_package_aliases),
+        )
         .route(
             "/api/packages/{id}/versions",
             get(handlers::packages::get_package_versions),
         )
+        .route(
+            "/api/packages/{id}/versions/{from}/diff/{to}",
+            get(handlers::packages::get_version_diff),
+        )
+        .route(
+            "/api/packages/{id}/dependencies",
+            get(handlers::packages::get_dependency_graph),
+        )
         .route(
             "/api/packages/{id}/subscribers",
             get(handlers::packages::get_package_subscriber_count),
         )
+        .route(
+            "/api/packages/{id}/activity",
+            get(handlers::packages::get_package_activity),
+        )
+        .route(
+            "/api/packages/{id}/activity/heatmap",
+            get(handlers::packages::get_package_activity_heatmap),
+        )
+        .route(
+            "/api/packages/{id}/maintainers",
+            get(handlers::maintainers::get_package_maintainers),
+        )
+        .route(
+            "/api/maintainers",
+            get(handlers::maintainers::list_maintainers),
+        )
+        .route(
+            "/api/maintainers/{id}",
+            get(handlers::maintainers::get_maintainer),
+        )
+        .route(
+            "/api/maintainers/{id}/packages",
+            get(handlers::maintainers::get_maintainer_packages),
+        )
         .route("/api/auth/register", post(handlers::auth::register))
         .route(
             "/api/auth/register-form",
@@ -300,7 +990,23 @@ async fn start_server(config: Config, no_collectors: bool) -> Result<()> {
         )
         .route("/api/auth/login", post(handlers::auth::login))
         .route("/api/auth/login-form", post(handlers::auth::login_form))
+        .route(
+            "/api/vulnerabilities",
+            get(handlers::vulnerabilities::list_vulnerabilities),
+        )
+        .route(
+            "/api/vulnerabilities/{id}",
+            get(handlers::vulnerabilities::get_vulnerability),
+        )
         .route("/api/analytics", get(handlers::analytics::get_analytics))
+        .route(
+            "/api/analytics/growth",
+            get(handlers::analytics::get_growth),
+        )
+        .route(
+            "/api/analytics/rollups",
+            get(handlers::analytics::get_rollups),
+        )
         .route(
             "/api/analytics/languages",
             get(handlers::analytics::get_language_trends),
@@ -310,68 +1016,297 @@ async fn start_server(config: Config, no_collectors: bool) -> Result<()> {
             get(handlers::analytics::get_security_report),
         )
         .route("/ws/timeline", get(websocket::timeline_websocket_handler))
+        .route(
+            "/badge/packages/{name}/version.svg",
+            get(handlers::badges::version_badge),
+        )
+        .route(
+            "/badge/packages/{name}/vulnerabilities.svg",
+            get(handlers::badges::vulnerabilities_badge),
+        );
+
+    #[cfg(feature = "artifact-cache")]
+    let app = app.route(
+        "/artifacts/{platform}/{name}/{version}",
+        get(handlers::artifacts::get_artifact),
+    );
+
+    #[cfg(feature = "github-app")]
+    let app = app.route("/api/github/webhook", post(handlers::github::webhook));
+
+    // Operational endpoints get their own listener when one is configured,
+    // so it can stay private while the package API is public
+    let body_limit = RequestBodyLimitLayer::new(config.max_request_body_kb * 1024);
+    let app = match &config.admin_listen_addr {
+        Some(addr) => {
+            let admin = admin
+                .route("/api/health", get(health_check))
+                .layer(DefaultBodyLimit::disable())
+                .layer(body_limit)
+                .layer(cors.clone())
+                .with_state(state.clone());
+            let listener = tokio::net::TcpListener::bind(addr).await?;
+            info!("Admin endpoints on http://{}", addr);
+            tokio::spawn(async move {
+                if let Err(e) = axum::serve(listener, admin).await {
+                    error!("Admin listener failed: {}", e);
+                }
+            });
+            app
+        }
+        None => app.merge(admin),
+    };
+
+    // Embeds are public and read-only, so any site may fetch them regardless
+    // of the configured origins
+    let embed = Router::new()
+        .route("/embed/packages/{file}", get(handlers::embed::get_embed))
+        .layer(middleware::compression_layer())
+        .layer(
+            CorsLayer::new()
+                .allow_origin(Any)
+                .allow_methods([axum::http::Method::GET]),
+        )
+        .with_state(state.clone());
+
+    let app = app
         .merge(timeline_route)
         .merge(protected)
-        .layer(CorsLayer::permissive())
-        .with_state(state);
+        .merge(moderation)
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            idempotency::idempotency_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            fossdb::rate_limit::rate_limit_middleware,
+        ))
+        // One configurable cap instead of axum's per-extractor default
+        .layer(DefaultBodyLimit::disable())
+        .layer(body_limit)
+        .layer(middleware::compression_layer())
+        .layer(cors)
+        .with_state(state)
+        .merge(embed);
+
+    // Serve the bundled frontend for everything that isn't an API route
+    #[cfg(feature = "embed-frontend")]
+    let app = app.fallback(fossdb::frontend::static_handler);
+
+    #[cfg(feature = "systemd")]
+    let inherited = fossdb::systemd::take_listener()?;
+    #[cfg(not(feature = "systemd"))]
+    let inherited: Option<tokio::net::TcpListener> = None;
+
+    let listener = match inherited {
+        Some(listener) => {
+            info!("Server running on socket from systemd ({})", listener.local_addr()?);
+            listener
+        }
+        None => {
+            let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await?;
+            info!("Server running on http://0.0.0.0:3000");
+            listener
+        }
+    };
 
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await?;
-    info!("Server running on http://0.0.0.0:3000");
+    #[cfg(feature = "systemd")]
+    fossdb::systemd::notify_ready();
 
-    axum::serve(listener, app).await?;
+    // Anonymous rate limits are counted per client IP
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .await?;
     Ok(())
 }
 
-async fn health_check() -> Json<Value> {
+/// Liveness, along with what this instance does with client details so
+/// users can check its privacy guarantees
+async fn health_check(State(state): State<AppState>) -> Json<Value> {
+    let privacy_mode = state.rate_limits.privacy_mode();
     Json(json!({
         "status": "healthy",
-        "service": "fossdb"
+        "service": "fossdb",
+        "privacy": {
+            "privacy_mode": privacy_mode,
+            "client_ips": if privacy_mode {
+                "hashed with a key that changes on restart, only to apply rate limits; never logged or stored"
+            } else {
+                "kept in memory only to apply rate limits; never logged or stored"
+            },
+            "user_agents": "never logged or stored",
+        }
     }))
 }
 
 #[cfg(feature = "collector")]
 async fn run_collector_loop(
     collector: Arc<dyn collector_models::Collector + Send + Sync>,
-    db: Arc<Database>,
+    db: Arc<dyn Storage>,
+    statuses: Arc<fossdb::collector_status::CollectorStatusRegistry>,
+    broadcaster: Arc<websocket::TimelineBroadcaster>,
+    holder: String,
     interval_hours: u64,
 ) {
     let collector_name = collector.name();
+    let lease_name = format!("collector:{}", collector_name);
 
     loop {
-        info!("Starting collector: {}", collector_name);
+        if let Err(e) = db.cleanup_expired_leases() {
+            warn!("Failed to clean up expired leases: {}", e);
+        }
 
-        match collector.collect(db.clone()).await {
-            Ok(()) => {
+        info!("Starting collector: {}", collector_name);
+        statuses.started(collector_name);
+        let started_at = chrono::Utc::now();
+        let report = collector_models::RunReport::default();
+
+        // Other processes sharing this database may be running it already
+        let work = collector.collect(db.clone(), &*broadcaster, &report);
+        let interval = std::time::Duration::from_secs(interval_hours * 3600);
+        let result =
+            match fossdb::leases::run_with_lease(db.clone(), &lease_name, &holder, interval, work)
+                .await
+            {
+                Ok(result) => result,
+                Err(e) => Some(Err(e)),
+            };
+
+        match &result {
+            None => {
+                info!("Collector {} is running in another process", collector_name);
+                statuses.skipped(collector_name);
+            }
+            Some(Ok(())) => {
                 info!("Collector {} completed successfully", collector_name);
+                statuses.succeeded(collector_name);
+            }
+            Some(Err(e)) => {
+                let unavailable = e.is::<collector_models::CollectorUnavailable>();
+                if unavailable {
+                    warn!("Collector {} cannot run: {}", collector_name, e);
+                } else {
+                    error!("Collector {} failed: {:#}", collector_name, e);
+                }
+                statuses.failed(collector_name, format!("{:#}", e), unavailable);
             }
-            Err(e) => {
-                error!("Collector {} failed: {}", collector_name, e);
+        }
+
+        if let Some(result) = &result {
+            match fossdb::collector_runs::record(&*db, collector_name, started_at, &report, result) {
+                Ok(run) => statuses.flagged(collector_name, run.anomalies),
+                Err(e) => warn!("Failed to record run of collector {}: {}", collector_name, e),
             }
         }
 
-        let sleep_duration = tokio::time::Duration::from_secs(interval_hours * 3600);
+        statuses.scheduled(
+            collector_name,
+            chrono::Utc::now() + chrono::Duration::hours(interval_hours as i64),
+        );
         info!(
             "Collector {} sleeping for {} hours",
             collector_name, interval_hours
         );
-        tokio::time::sleep(sleep_duration).await;
+        tokio::time::sleep(interval).await;
     }
 }
 
-// Generic export function to avoid code duplication
-fn export_table<T: Serialize>(table_name: &str, data: Vec<T>, output_path: &Path) -> Result<()> {
-    info!("Exporting {}...", table_name);
-    eprintln!(
-        "Exporting {} {} to {}...",
-        data.len(),
-        table_name,
-        output_path.display()
-    );
+#[cfg(any(feature = "collector-crates-io-dump", feature = "collector-libraries-io-dump"))]
+async fn backfill(config: &Config, source: BackfillSource) -> Result<()> {
+    let db = Backend::open(config).await?.storage();
+
+    match source {
+        #[cfg(feature = "collector-crates-io-dump")]
+        BackfillSource::CratesIo { dump, keep_dump } => {
+            use collectors::crates_io_dump;
+
+            let (path, downloaded) = match dump {
+                Some(path) => (path, false),
+                None => {
+                    let path = std::env::temp_dir().join("crates-io-db-dump.tar.gz");
+                    let client = reqwest::Client::builder().user_agent("fossdb").build()?;
+                    eprintln!("Downloading {}...", crates_io_dump::DUMP_URL);
+                    crates_io_dump::download_dump(&client, &path).await?;
+                    (path, true)
+                }
+            };
+
+            eprintln!("Importing {}...", path.display());
+            let import_path = path.clone();
+            let admission = config.admission();
+            let stats = tokio::task::spawn_blocking(move || {
+                crates_io_dump::backfill(&*db, &import_path, &admission)
+            })
+            .await??;
+
+            if downloaded && !keep_dump {
+                std::fs::remove_file(&path)?;
+            }
 
-    let json = serde_json::to_string_pretty(&data)?;
-    std::fs::write(output_path, json)?;
+            eprintln!(
+                "✓ Imported {} new packages ({} updated), {} versions, {} dependencies and {} maintainers",
+                stats.packages_created,
+                stats.packages_updated,
+                stats.versions_created,
+                stats.dependencies,
+                stats.maintainers
+            );
+        }
+        #[cfg(feature = "collector-libraries-io-dump")]
+        BackfillSource::LibrariesIo { path, platforms } => {
+            use collectors::libraries_io_dump;
+
+            eprintln!("Importing {}...", path.display());
+            let admission = config.admission();
+            let stats = tokio::task::spawn_blocking(move || {
+                libraries_io_dump::backfill(&*db, &path, &platforms, &admission)
+            })
+            .await??;
+
+            eprintln!(
+                "✓ Imported {} new packages ({} updated), {} versions and {} dependencies; {} skipped for licensing",
+                stats.packages_created,
+                stats.packages_updated,
+                stats.versions_created,
+                stats.dependencies,
+                stats.skipped_license
+            );
+        }
+    }
 
-    eprintln!("✓ Exported {} {}", data.len(), table_name);
+    Ok(())
+}
+
+/// Write a table as a JSON array with one record per line, streaming records
+/// from `for_each` so the table never has to fit in memory
+fn export_table<T: Serialize>(
+    table_name: &str,
+    output_path: &Path,
+    for_each: impl FnOnce(&mut dyn FnMut(T) -> Result<()>) -> Result<()>,
+) -> Result<()> {
+    use std::io::Write;
+
+    info!("Exporting {}...", table_name);
+    eprintln!("Exporting {} to {}...", table_name, output_path.display());
+
+    let mut output = std::io::BufWriter::new(std::fs::File::create(output_path)?);
+    let mut count = 0;
+    output.write_all(b"[")?;
+    for_each(&mut |record| {
+        output.write_all(if count == 0 { b"\n" } else { b",\n" })?;
+        serde_json::to_writer(&mut output, &record)?;
+        count += 1;
+        if count % 10_000 == 0 {
+            eprint!("\rExported {} {}", count, table_name);
+        }
+        Ok(())
+    })?;
+    output.write_all(b"\n]\n")?;
+    output.flush()?;
+
+    eprintln!("\r✓ Exported {} {}", count, table_name);
     Ok(())
 }
 
@@ -380,7 +1315,7 @@ async fn export_database(
     output_dir: PathBuf,
     table: Option<String>,
 ) -> Result<()> {
-    let db = Database::new(&config.database_path)?;
+    let db = Backend::open(config).await?.storage();
 
     // Create output directory if it doesn't exist
     std::fs::create_dir_all(&output_dir)?;
@@ -398,39 +1333,290 @@ async fn export_database(
     };
 
     for table_name in tables_to_export {
-        let output_path = output_dir.join(format!("{}.json", table_name));
+        export_named(&*db, &table_name, &output_dir)?;
+    }
 
-        match table_name.as_str() {
-            "packages" => export_table("packages", db.get_all_packages()?, &output_path)?,
-            "versions" => export_table("versions", db.get_all_versions()?, &output_path)?,
-            "users" => export_table("users", db.get_all_users()?, &output_path)?,
-            "vulnerabilities" => export_table(
-                "vulnerabilities",
-                db.get_all_vulnerabilities()?,
-                &output_path,
-            )?,
-            "timeline_events" => export_table(
-                "timeline events",
-                db.get_all_timeline_events()?,
-                &output_path,
-            )?,
-            _ => {
-                eprintln!(
-                    "Error: Unknown table '{}'. Valid tables: packages, versions, users, vulnerabilities, timeline_events",
-                    table_name
-                );
-                return Err(anyhow::anyhow!("Unknown table: {}", table_name));
+    eprintln!("\nExport completed successfully!");
+
+    Ok(())
+}
+
+/// Export `table_name` to `<table_name>.json` in `output_dir`
+fn export_named(db: &dyn Storage, table_name: &str, output_dir: &Path) -> Result<()> {
+    let output_path = output_dir.join(format!("{}.json", table_name));
+
+    match table_name {
+        "packages" => export_table("packages", &output_path, |f| db.for_each_package(f)),
+        "versions" => export_table("versions", &output_path, |f| db.for_each_version(f)),
+        "users" => export_table("users", &output_path, |f| db.for_each_user(f)),
+        "vulnerabilities" => export_table("vulnerabilities", &output_path, |f| {
+            db.for_each_vulnerability(f)
+        }),
+        "timeline_events" => export_table("timeline events", &output_path, |f| {
+            db.for_each_timeline_event(f)
+        }),
+        _ => {
+            eprintln!(
+                "Error: Unknown table '{}'. Valid tables: packages, versions, users, vulnerabilities, timeline_events",
+                table_name
+            );
+            Err(anyhow::anyhow!("Unknown table: {}", table_name))
+        }
+    }
+}
+
+async fn query_table(
+    config: &Config,
+    table: &str,
+    query: &Query,
+    format: QueryFormat,
+    columns: &[String],
+) -> Result<()> {
+    let db = Backend::open(config).await?.storage();
+    let rows = fossdb::query::run(&*db, table, query)?;
+
+    match format {
+        QueryFormat::Json => {
+            for row in &rows {
+                println!("{}", row);
             }
         }
+        QueryFormat::Table => {
+            let columns: Vec<&str> = if columns.is_empty() {
+                fossdb::query::default_columns(table).to_vec()
+            } else {
+                columns.iter().map(String::as_str).collect()
+            };
+            println!("{}", fossdb::query::render_table(&rows, &columns));
+            eprintln!("{} row(s)", rows.len());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "import-csv")]
+async fn import_csv(
+    config: &Config,
+    input: PathBuf,
+    map: PathBuf,
+    skip_invalid: bool,
+    backup_first: bool,
+) -> Result<()> {
+    use fossdb::csv_import::{self, Mapping, Target};
+
+    let mapping = Mapping::from_toml(&std::fs::read_to_string(&map)?)?;
+    let backend = Backend::open(config).await?;
+    if backup_first {
+        let table = match mapping.target {
+            Target::Packages => "packages",
+            Target::Versions => "versions",
+        };
+        eprintln!("Backed up to {}", backend.backup(config, table)?.display());
     }
+    let db = backend.storage();
 
-    eprintln!("\nExport completed successfully!");
+    eprintln!("Reading {:?} from: {}", mapping.target, input.display());
+    let report = csv_import::import(&*db, &mapping, std::fs::File::open(&input)?, skip_invalid)?;
 
+    for error in &report.errors {
+        eprintln!("{}:{}: {}", input.display(), error.line, error.message);
+    }
+    if !report.errors.is_empty() && !skip_invalid {
+        return Err(anyhow::anyhow!(
+            "{} invalid rows, nothing imported. Fix them or pass --skip-invalid",
+            report.errors.len()
+        ));
+    }
+
+    eprintln!(
+        "✓ Imported {} records, skipped {} existing, {} invalid",
+        report.imported,
+        report.skipped,
+        report.errors.len()
+    );
+    Ok(())
+}
+
+/// Ask before a destructive change. Without a terminal to ask on, `--yes`
+/// is required.
+fn confirm(prompt: &str, yes: bool) -> Result<()> {
+    use std::io::IsTerminal;
+
+    if yes {
+        return Ok(());
+    }
+    if !std::io::stdin().is_terminal() {
+        return Err(anyhow::anyhow!(
+            "No terminal to confirm on, pass --yes to continue: {}",
+            prompt
+        ));
+    }
+    if dialoguer::Confirm::new()
+        .with_prompt(prompt)
+        .default(false)
+        .interact()?
+    {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("Cancelled"))
+    }
+}
+
+/// Prompt for a new password, or read it from stdin when not on a terminal
+fn read_password() -> Result<String> {
+    use std::io::IsTerminal;
+
+    let password = if std::io::stdin().is_terminal() {
+        dialoguer::Password::new()
+            .with_prompt("Password")
+            .with_confirmation("Repeat password", "Passwords don't match")
+            .interact()?
+    } else {
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line)?;
+        line.trim_end_matches(['\r', '\n']).to_string()
+    };
+    if password.chars().count() < 8 {
+        return Err(anyhow::anyhow!("Password must be at least 8 characters"));
+    }
+    Ok(password)
+}
+
+async fn manage_users(config: &Config, command: UserCommand) -> Result<()> {
+    use fossdb::auth::hash_password;
+    use fossdb::validation::Validate;
+
+    let db = Backend::open(config).await?.storage();
+    let find = |username: &str| -> Result<User> {
+        db.get_user_by_username(username)?
+            .filter(|user| user.username == username)
+            .ok_or_else(|| anyhow::anyhow!("No user named {}", username))
+    };
+
+    match command {
+        UserCommand::Create {
+            username,
+            email,
+            role,
+        } => {
+            if db.get_user_by_username(&username)?.is_some_and(|u| u.username == username) {
+                return Err(anyhow::anyhow!("Username {} is taken", username));
+            }
+            if db.get_user_by_email(&email)?.is_some_and(|u| u.email == email) {
+                return Err(anyhow::anyhow!("Email {} is already registered", email));
+            }
+            let password = read_password()?;
+            let request = fossdb::RegisterRequest {
+                username,
+                email,
+                password,
+            };
+            if let Err(errors) = request.validate() {
+                let errors: Vec<String> = errors
+                    .errors
+                    .iter()
+                    .map(|e| format!("{} {}", e.field, e.message))
+                    .collect();
+                return Err(anyhow::anyhow!("Invalid user: {}", errors.join(", ")));
+            }
+
+            let user = db.insert_user(User {
+                id: 0,
+                username: request.username,
+                email: request.email,
+                password_hash: hash_password(&request.password)?,
+                subscriptions: Vec::new(),
+                created_at: chrono::Utc::now(),
+                is_verified: true,
+                notifications_enabled: true,
+                role,
+                profile: Default::default(),
+                last_read_event_id: None,
+                timeline: Default::default(),
+            })?;
+            eprintln!("✓ Created {} ({:?}) with ID {}", user.username, user.role, user.id);
+        }
+        UserCommand::List => {
+            println!("{:<8} {:<24} {:<32} {:<10} CREATED", "ID", "USERNAME", "EMAIL", "ROLE");
+            db.for_each_user(&mut |user| {
+                println!(
+                    "{:<8} {:<24} {:<32} {:<10} {}",
+                    user.id,
+                    user.username,
+                    user.email,
+                    format!("{:?}", user.role).to_lowercase(),
+                    user.created_at.format("%Y-%m-%d")
+                );
+                Ok(())
+            })?;
+        }
+        UserCommand::SetRole { username, role } => {
+            let user = find(&username)?;
+            db.modify_user(user.id, &mut |user| user.role = role)?;
+            eprintln!("✓ {} is now {:?}", username, role);
+        }
+        UserCommand::ResetPassword { username } => {
+            let user = find(&username)?;
+            let password_hash = hash_password(&read_password()?)?;
+            db.modify_user(user.id, &mut |user| user.password_hash = password_hash.clone())?;
+            eprintln!("✓ Password reset for {}", username);
+        }
+        UserCommand::Delete { username, yes } => {
+            let user = find(&username)?;
+            confirm(
+                &format!(
+                    "Delete {} <{}> with their subscriptions, projects and timeline?",
+                    user.username, user.email
+                ),
+                yes,
+            )?;
+            db.delete_user(user.id)?;
+            eprintln!("✓ Deleted {}", username);
+        }
+    }
+    Ok(())
+}
+
+/// Warn users whose accounts have gone unused, and expire the accounts of
+/// those who were warned long enough ago
+#[cfg(feature = "email")]
+async fn expire_inactive_accounts(
+    db: &dyn Storage,
+    policy: &fossdb::retention::RetentionPolicy,
+    email_service: &email::EmailService,
+) -> Result<()> {
+    use fossdb::retention;
+
+    let now = chrono::Utc::now();
+    let due = retention::due(db, policy, now)?;
+    for (user, expires_at) in due.warn {
+        let expires_on = expires_at.format("%Y-%m-%d").to_string();
+        match email_service
+            .send_inactivity_warning(&user.email, &user.username, &expires_on, policy.action)
+            .await
+        {
+            Ok(()) => retention::mark_warned(db, user.id, now)?,
+            Err(e) => warn!("Failed to warn {} about their inactive account: {}", user.username, e),
+        }
+    }
+    for user in &due.expire {
+        retention::expire(db, user.id, policy.action, now)?;
+    }
+    if !due.expire.is_empty() {
+        info!("Expired {} inactive accounts", due.expire.len());
+    }
     Ok(())
 }
 
-async fn import_database(config: &Config, input: PathBuf, merge: bool) -> Result<()> {
-    let db = Database::new(&config.database_path)?;
+async fn import_database(
+    config: &Config,
+    input: PathBuf,
+    merge: bool,
+    yes: bool,
+    backup_first: bool,
+) -> Result<()> {
+    let backend = Backend::open(config).await?;
+    let db = backend.storage();
 
     // Determine table name from filename
     let table_name = input
@@ -449,9 +1635,10 @@ async fn import_database(config: &Config, input: PathBuf, merge: bool) -> Result
             eprintln!("Found {} {} to import", $data.len(), $type_name);
 
             if !merge {
-                eprintln!("WARNING: This will replace existing {}!", $type_name);
-                eprintln!("Press Ctrl+C within 5 seconds to cancel...");
-                tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+                confirm(&format!("This will replace existing {}. Continue?", $type_name), yes)?;
+            }
+            if backup_first {
+                eprintln!("Backed up to {}", backend.backup(config, table_name)?.display());
             }
 
             let total = $data.len();
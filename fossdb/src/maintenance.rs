@@ -0,0 +1,291 @@
+//! Marks packages that look abandoned, from their release cadence, the
+//! archive status of their repository and informational advisories
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, HashSet};
+
+use crate::storage::Storage;
+use crate::{EventMetadata, EventType, Package, TimelineEvent};
+
+pub const UNMAINTAINED: &str = "unmaintained";
+pub const ARCHIVED: &str = "archived";
+
+/// Packages without a release for this long are considered unmaintained
+pub const STALE_AFTER_DAYS: i64 = 730;
+
+/// What's known about the upkeep of a package
+#[derive(Debug, Clone, Default)]
+pub struct Signals {
+    pub last_release: Option<DateTime<Utc>>,
+    /// None when the repository wasn't checked
+    pub archived: Option<bool>,
+    /// Title of an informational advisory declaring the package unmaintained
+    pub advisory: Option<String>,
+}
+
+/// The status `signals` call for and why, or None for a package that looks
+/// maintained. An archived package stays archived until its repository is
+/// checked again.
+pub fn assess(
+    current: Option<&str>,
+    signals: &Signals,
+    now: DateTime<Utc>,
+) -> Option<(&'static str, String)> {
+    if signals.archived.unwrap_or(current == Some(ARCHIVED)) {
+        return Some((ARCHIVED, "Its repository is archived".to_string()));
+    }
+    if let Some(advisory) = &signals.advisory {
+        return Some((UNMAINTAINED, format!("Advisory: {}", advisory)));
+    }
+    match signals.last_release {
+        Some(last) if now - last > Duration::days(STALE_AFTER_DAYS) => Some((
+            UNMAINTAINED,
+            format!("No release since {}", last.format("%Y-%m-%d")),
+        )),
+        _ => None,
+    }
+}
+
+/// Statuses set here. Others come from registries and are left alone.
+fn managed(status: Option<&str>) -> bool {
+    status.is_none_or(|status| status == UNMAINTAINED || status == ARCHIVED)
+}
+
+fn deprecated(
+    package: &Package,
+    status: &str,
+    reason: String,
+    now: DateTime<Utc>,
+) -> TimelineEvent {
+    TimelineEvent {
+        id: 0,
+        package_id: package.id,
+        user_id: None,
+        event_type: EventType::PackageDeprecated,
+        package_name: package.name.clone(),
+        version: None,
+        message: format!("Marked {}: {}", status, reason),
+        metadata: Some(
+            EventMetadata::Deprecated {
+                status: status.to_string(),
+                reason,
+            }
+            .to_json(),
+        ),
+        created_at: now,
+        notified_at: None,
+    }
+}
+
+/// Check whether the repositories of followed packages are archived, for
+/// [`sweep`]. Repositories that can't be checked are left out.
+#[cfg(feature = "prefetch")]
+pub async fn check_archived(
+    db: &dyn Storage,
+    prefetcher: &crate::prefetch::Prefetcher,
+) -> HashMap<u64, bool> {
+    let mut names = HashSet::new();
+    let result = db.for_each_user(&mut |user| {
+        names.extend(user.subscriptions.into_iter().map(|s| s.package_name));
+        Ok(())
+    });
+    if let Err(e) = result {
+        tracing::error!("Failed to read subscriptions: {}", e);
+    }
+
+    let mut archived = HashMap::new();
+    for name in names {
+        let package = match db.get_package_by_name(&name) {
+            Ok(Some(package)) => package,
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::error!("Failed to read package {}: {}", name, e);
+                continue;
+            }
+        };
+        let Some(repository) = &package.repository else {
+            continue;
+        };
+        match prefetcher.archived(repository).await {
+            Ok(Some(is_archived)) => {
+                archived.insert(package.id, is_archived);
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to check whether {} is archived: {}", repository, e),
+        }
+    }
+    archived
+}
+
+/// Update the status of `packages`, or of every package when None, recording
+/// a PackageDeprecated event for each that became inactive. `archived` holds
+/// the repositories checked since the last sweep. Returns how many changed.
+pub fn sweep(
+    db: &dyn Storage,
+    packages: Option<&HashSet<u64>>,
+    archived: &HashMap<u64, bool>,
+    now: DateTime<Utc>,
+) -> Result<usize> {
+    let wanted = |id: &u64| packages.is_none_or(|packages| packages.contains(id));
+    let mut signals: HashMap<u64, Signals> = HashMap::new();
+    let mut candidates = Vec::new();
+    match packages {
+        Some(packages) => {
+            for &id in packages {
+                let Some(package) = db.get_package(id)? else {
+                    continue;
+                };
+                signals.entry(id).or_default().last_release = db
+                    .get_versions_by_package(id)?
+                    .iter()
+                    .map(|v| v.release_date)
+                    .max();
+                candidates.push(package);
+            }
+        }
+        None => {
+            db.for_each_version(&mut |version| {
+                let last = &mut signals.entry(version.package_id).or_default().last_release;
+                *last = (*last).max(Some(version.release_date));
+                Ok(())
+            })?;
+            db.for_each_package(&mut |package| {
+                candidates.push(package);
+                Ok(())
+            })?;
+        }
+    }
+    db.for_each_vulnerability(&mut |vulnerability| {
+        if vulnerability.informational.as_deref() == Some(UNMAINTAINED) {
+            for affected in vulnerability
+                .affected_packages
+                .iter()
+                .filter(|a| wanted(&a.package_id))
+            {
+                signals
+                    .entry(affected.package_id)
+                    .or_default()
+                    .advisory
+                    .get_or_insert_with(|| vulnerability.title.clone());
+            }
+        }
+        Ok(())
+    })?;
+    for (id, &is_archived) in archived.iter().filter(|(id, _)| wanted(id)) {
+        signals.entry(*id).or_default().archived = Some(is_archived);
+    }
+
+    let mut changed = Vec::new();
+    let mut events = Vec::new();
+    let unknown = Signals::default();
+    for mut package in candidates {
+        if !managed(package.status.as_deref()) {
+            continue;
+        }
+        let signals = signals.get(&package.id).unwrap_or(&unknown);
+        let assessed = assess(package.status.as_deref(), signals, now);
+        let status = assessed.as_ref().map(|(status, _)| status.to_string());
+        if status == package.status {
+            continue;
+        }
+        if let Some((status, reason)) = assessed {
+            events.push(deprecated(&package, status, reason, now));
+        }
+        package.status = status;
+        changed.push(package);
+    }
+
+    let count = changed.len();
+    if count > 0 {
+        db.update_packages(changed)?;
+    }
+    for event in events {
+        tracing::info!("{} {}", event.package_name, event.message);
+        db.insert_timeline_event(event)?;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::fixtures;
+    use crate::{AffectedPackage, PackageVersion, Vulnerability, VulnerabilitySeverity};
+
+    #[test]
+    fn test_sweep() {
+        let db = Database::in_memory().unwrap();
+        let seeded = fixtures::seed(&db).unwrap();
+        // The seeded releases are from 2024
+        let now = Utc::now().max("2027-01-01T00:00:00Z".parse().unwrap());
+        let status = |id| db.get_package(id).unwrap().unwrap().status;
+
+        assert_eq!(sweep(&db, None, &HashMap::new(), now).unwrap(), 3);
+        assert_eq!(status(seeded.serde.id).as_deref(), Some(UNMAINTAINED));
+        let events = db.get_timeline_by_package(seeded.serde.id).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, EventType::PackageDeprecated);
+        assert!(
+            events[0]
+                .message
+                .starts_with("Marked unmaintained: No release since")
+        );
+        // Nothing changes on a second pass
+        assert_eq!(sweep(&db, None, &HashMap::new(), now).unwrap(), 0);
+
+        // A fresh release revives it, unless an advisory says otherwise
+        let tokio = HashSet::from([seeded.tokio.id]);
+        db.insert_version(PackageVersion {
+            release_date: now,
+            ..fixtures::version(seeded.tokio.id, "1.41.0")
+        })
+        .unwrap();
+        db.insert_vulnerability(Vulnerability {
+            id: 0,
+            cve_id: None,
+            title: "serde_json is unmaintained".to_string(),
+            description: String::new(),
+            severity: VulnerabilitySeverity::Low,
+            affected_packages: vec![AffectedPackage {
+                package_id: seeded.serde_json.id,
+                version_range: "*".to_string(),
+            }],
+            discovered_at: now,
+            fixed_in: None,
+            cvss_vector: None,
+            cvss_score: None,
+            epss_score: None,
+            epss_percentile: None,
+            scores_updated_at: None,
+            informational: Some(UNMAINTAINED.to_string()),
+        })
+        .unwrap();
+        assert_eq!(sweep(&db, Some(&tokio), &HashMap::new(), now).unwrap(), 1);
+        assert_eq!(status(seeded.tokio.id), None);
+
+        // Archived repositories stay archived until checked again
+        let archived = HashMap::from([(seeded.serde.id, true)]);
+        sweep(&db, None, &archived, now).unwrap();
+        assert_eq!(status(seeded.serde.id).as_deref(), Some(ARCHIVED));
+        sweep(&db, None, &HashMap::new(), now).unwrap();
+        assert_eq!(status(seeded.serde.id).as_deref(), Some(ARCHIVED));
+        let event = db.get_timeline_by_package(seeded.serde_json.id).unwrap();
+        assert_eq!(
+            event[0].typed_metadata(),
+            Some(EventMetadata::Deprecated {
+                status: UNMAINTAINED.to_string(),
+                reason: "No release since 2024-01-01".to_string(),
+            })
+        );
+
+        // Statuses from registries are left alone
+        db.modify_package(seeded.serde.id, &mut |p| {
+            p.status = Some("Deprecated".to_string())
+        })
+        .unwrap();
+        let archived = HashMap::from([(seeded.serde.id, false)]);
+        sweep(&db, None, &archived, now).unwrap();
+        assert_eq!(status(seeded.serde.id).as_deref(), Some("Deprecated"));
+    }
+}
@@ -1,9 +1,15 @@
 use axum::{
-    extract::Request,
-    http::{StatusCode, header},
+    extract::{Request, State},
+    http::{HeaderName, HeaderValue, Method, StatusCode, header},
     middleware::Next,
     response::Response,
 };
+use tower_http::compression::{CompressionLayer, DefaultPredicate, Predicate};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+use crate::AppState;
+use crate::auth::Claims;
+use crate::config::Config;
 
 pub async fn auth_middleware(mut req: Request, next: Next) -> Result<Response, StatusCode> {
     let auth_header = req
@@ -23,6 +29,55 @@ pub async fn auth_middleware(mut req: Request, next: Next) -> Result<Response, S
     Ok(next.run(req).await)
 }
 
+/// Refresh the token's claims from the stored user, for the rest of the
+/// request too
+fn current_claims(state: &AppState, req: &mut Request) -> Result<Claims, StatusCode> {
+    let claims = req
+        .extensions()
+        .get::<Claims>()
+        .ok_or(StatusCode::UNAUTHORIZED)?
+        .current(&*state.db)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    req.extensions_mut().insert(claims.clone());
+    Ok(claims)
+}
+
+/// Admin middleware - must run after `auth_middleware`.
+/// Only users listed in `ADMIN_USERNAMES` or with the admin role are allowed through.
+pub async fn admin_middleware(
+    State(state): State<AppState>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let claims = current_claims(&state, &mut req)?;
+
+    let config = Config::from_env();
+    if !claims.is_admin(&config) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(next.run(req).await)
+}
+
+/// Moderator middleware - must run after `auth_middleware`.
+/// Users listed in either `MODERATOR_USERNAMES` or `ADMIN_USERNAMES`, or with
+/// the moderator or admin role, are allowed through.
+pub async fn moderator_middleware(
+    State(state): State<AppState>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let claims = current_claims(&state, &mut req)?;
+
+    let config = Config::from_env();
+    if !claims.is_moderator(&config) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(next.run(req).await)
+}
+
 /// Optional auth middleware - doesn't fail if no auth header is present
 /// Use this for endpoints that should work for both authenticated and unauthenticated users
 pub async fn optional_auth_middleware(mut req: Request, next: Next) -> Response {
@@ -39,3 +94,70 @@ pub async fn optional_auth_middleware(mut req: Request, next: Next) -> Response
     // Always proceed, whether auth succeeded or not
     next.run(req).await
 }
+
+/// Build the CORS layer from configuration.
+///
+/// A `*` origin allows any origin, but cannot be combined with credentials.
+pub fn cors_layer(config: &Config) -> CorsLayer {
+    let methods: Vec<Method> = config
+        .cors_allowed_methods
+        .iter()
+        .filter_map(|m| match m.to_uppercase().parse() {
+            Ok(method) => Some(method),
+            Err(_) => {
+                tracing::warn!("Ignoring invalid CORS method: {}", m);
+                None
+            }
+        })
+        .collect();
+
+    let layer = CorsLayer::new()
+        .allow_methods(methods)
+        .allow_headers([
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            HeaderName::from_static(crate::idempotency::IDEMPOTENCY_KEY),
+        ]);
+
+    if config.cors_allowed_origins.iter().any(|o| o == "*") {
+        if config.cors_allow_credentials {
+            tracing::warn!("CORS credentials cannot be used with a wildcard origin, ignoring");
+        }
+        return layer.allow_origin(Any);
+    }
+
+    let origins: Vec<HeaderValue> = config
+        .cors_allowed_origins
+        .iter()
+        .filter_map(|o| match o.parse() {
+            Ok(origin) => Some(origin),
+            Err(_) => {
+                tracing::warn!("Ignoring invalid CORS origin: {}", o);
+                None
+            }
+        })
+        .collect();
+
+    layer
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_credentials(config.cors_allow_credentials)
+}
+
+/// Gzip or brotli compression, as the client accepts, for JSON and text
+/// responses. Artifacts and other binary downloads are sent as they are.
+pub fn compression_layer() -> CompressionLayer<impl Predicate> {
+    let compressible = |_: StatusCode,
+                        _: axum::http::Version,
+                        headers: &axum::http::HeaderMap,
+                        _: &axum::http::Extensions| {
+        headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|content_type| {
+                content_type.starts_with("application/json")
+                    || content_type.starts_with("text/")
+                    || content_type.starts_with("application/javascript")
+            })
+    };
+    CompressionLayer::new().compress_when(DefaultPredicate::new().and(compressible))
+}
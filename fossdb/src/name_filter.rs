@@ -0,0 +1,107 @@
+//! A Bloom filter of package names, so collectors can tell a name is new
+//! without a secondary index lookup. It can answer "maybe" for names that
+//! don't exist, but never "no" for names that do.
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::RwLock;
+
+/// About 1% false positives at capacity
+const BITS_PER_NAME: usize = 10;
+const HASHES: u64 = 7;
+const MIN_CAPACITY: usize = 1024;
+
+/// A fixed size filter. Once full, another twice the size is added rather
+/// than rebuilding, which would need every name again.
+struct Layer {
+    bits: Vec<u64>,
+    capacity: usize,
+    len: usize,
+}
+
+impl Layer {
+    fn new(capacity: usize) -> Self {
+        Self {
+            bits: vec![0; (capacity * BITS_PER_NAME).div_ceil(64)],
+            capacity,
+            len: 0,
+        }
+    }
+
+    /// Bit positions for a name, by double hashing
+    fn positions(&self, hash: u64) -> impl Iterator<Item = usize> {
+        let m = self.bits.len() as u64 * 64;
+        let step = hash.rotate_left(32) | 1;
+        (0..HASHES).map(move |i| (hash.wrapping_add(i.wrapping_mul(step)) % m) as usize)
+    }
+
+    fn contains(&self, hash: u64) -> bool {
+        self.positions(hash)
+            .all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    fn insert(&mut self, hash: u64) {
+        for bit in self.positions(hash).collect::<Vec<_>>() {
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+        self.len += 1;
+    }
+}
+
+pub struct NameFilter {
+    layers: RwLock<Vec<Layer>>,
+}
+
+impl NameFilter {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            layers: RwLock::new(vec![Layer::new(capacity.max(MIN_CAPACITY))]),
+        }
+    }
+
+    /// False only if `name` was never inserted
+    pub fn may_contain(&self, name: &str) -> bool {
+        let hash = hash(name);
+        self.layers.read().unwrap().iter().any(|layer| layer.contains(hash))
+    }
+
+    pub fn insert(&self, name: &str) {
+        let hash = hash(name);
+        let mut layers = self.layers.write().unwrap();
+        if layers.iter().any(|layer| layer.contains(hash)) {
+            return;
+        }
+        let last = layers.last().unwrap();
+        if last.len >= last.capacity {
+            let capacity = last.capacity * 2;
+            layers.push(Layer::new(capacity));
+        }
+        layers.last_mut().unwrap().insert(hash);
+    }
+}
+
+fn hash(name: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    name.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name_filter() {
+        let filter = NameFilter::with_capacity(0);
+        let names: Vec<String> = (0..5000).map(|i| format!("package-{}", i)).collect();
+        for name in &names {
+            filter.insert(name);
+        }
+
+        assert!(names.iter().all(|name| filter.may_contain(name)));
+        assert!(filter.layers.read().unwrap().len() > 1);
+
+        let false_positives = (0..5000)
+            .filter(|i| filter.may_contain(&format!("other-{}", i)))
+            .count();
+        assert!(false_positives < 250, "{} false positives", false_positives);
+    }
+}
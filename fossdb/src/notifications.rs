@@ -2,18 +2,51 @@ use anyhow::Result;
 use chrono::Utc;
 use std::sync::Arc;
 
-use crate::{db::Database, email::EmailService};
+use crate::{EventMetadata, TimelineEvent, email::EmailService, release_diff, storage::Storage};
 
 pub struct NotificationProcessor {
-    db: Arc<Database>,
+    db: Arc<dyn Storage>,
     email: Arc<EmailService>,
 }
 
 impl NotificationProcessor {
-    pub fn new(db: Arc<Database>, email: Arc<EmailService>) -> Self {
+    pub fn new(db: Arc<dyn Storage>, email: Arc<EmailService>) -> Self {
         Self { db, email }
     }
 
+    /// Summary of what changed since the previous release, empty if unknown
+    fn release_changes(&self, package_id: u64, version: &str) -> Vec<String> {
+        let versions = match self.db.get_versions_by_package(package_id) {
+            Ok(versions) => versions,
+            Err(e) => {
+                tracing::warn!("Failed to get versions of package {}: {}", package_id, e);
+                return Vec::new();
+            }
+        };
+        let current = versions.iter().find(|v| v.version == version);
+        match (current, release_diff::previous_release(&versions, version)) {
+            (Some(current), Some(previous)) => release_diff::diff(previous, current).summary(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Whether `event` alerts to an advisory the user acknowledged everywhere
+    fn acknowledged(&self, user_id: u64, event: &TimelineEvent) -> bool {
+        let Some(EventMetadata::Vulnerability {
+            vulnerability_id, ..
+        }) = event.typed_metadata()
+        else {
+            return false;
+        };
+        match self.db.get_advisory_acks_by_user(user_id) {
+            Ok(acks) => acks.iter().any(|ack| ack.covers(vulnerability_id, None)),
+            Err(e) => {
+                tracing::warn!("Failed to get acknowledgements of user {}: {}", user_id, e);
+                false
+            }
+        }
+    }
+
     pub async fn process_new_releases(&self) -> Result<()> {
         tracing::info!("Processing new release notifications...");
 
@@ -68,6 +101,17 @@ impl NotificationProcessor {
                 continue;
             }
 
+            // Advisories the user already dealt with aren't worth an email
+            if self.acknowledged(user.id, &event) {
+                tracing::debug!("User {} acknowledged event {}, skipping", user.id, event.id);
+                event.notified_at = Some(Utc::now());
+                if let Err(e) = self.db.update_timeline_event(event.clone()) {
+                    tracing::error!("Failed to update timeline event {}: {}", event.id, e);
+                }
+                notifications_skipped += 1;
+                continue;
+            }
+
             // Get package details
             let package = match self.db.get_package(event.package_id) {
                 Ok(Some(p)) => p,
@@ -93,6 +137,7 @@ impl NotificationProcessor {
             let version_string = "unknown".to_string();
             let version = event.version.as_ref().unwrap_or(&version_string);
             let release_date = event.created_at.format("%Y-%m-%d %H:%M UTC").to_string();
+            let changes = self.release_changes(event.package_id, version);
 
             // Send email
             match self
@@ -103,6 +148,7 @@ impl NotificationProcessor {
                     version,
                     &release_date,
                     package.description.as_deref(),
+                    &changes,
                 )
                 .await
             {
@@ -0,0 +1,172 @@
+// Fills in metadata for submitted packages from their source repository
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::duplicates::normalize_url;
+
+/// What a repository host knows about a project
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepositoryMetadata {
+    pub name: String,
+    pub description: Option<String>,
+    pub homepage: Option<String>,
+    /// SPDX ID of the license detected from the repository's license file
+    pub license: Option<String>,
+    pub latest_release: Option<Release>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Release {
+    pub version: String,
+    pub published_at: DateTime<Utc>,
+    pub notes: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GithubRepository {
+    name: String,
+    description: Option<String>,
+    homepage: Option<String>,
+    license: Option<GithubLicense>,
+    #[serde(default)]
+    archived: bool,
+}
+
+#[derive(Deserialize)]
+struct GithubLicense {
+    spdx_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    published_at: Option<DateTime<Utc>>,
+    body: Option<String>,
+}
+
+/// Owner and name of a GitHub repository URL
+fn github_repository(url: &str) -> Option<(String, String)> {
+    let url = normalize_url(url);
+    let mut parts = url.strip_prefix("github.com/")?.split('/');
+    let owner = parts.next().filter(|s| !s.is_empty())?;
+    let name = parts.next().filter(|s| !s.is_empty())?;
+    Some((owner.to_string(), name.to_string()))
+}
+
+/// Version number of a release tag such as `v1.2.3`
+fn tag_version(tag: &str) -> &str {
+    match tag.strip_prefix('v') {
+        Some(version) if version.starts_with(|c: char| c.is_ascii_digit()) => version,
+        _ => tag,
+    }
+}
+
+fn present(s: Option<String>) -> Option<String> {
+    s.filter(|s| !s.trim().is_empty())
+}
+
+pub struct Prefetcher {
+    client: reqwest::Client,
+    /// Raises GitHub's rate limit for unauthenticated requests
+    github_token: Option<String>,
+}
+
+impl Prefetcher {
+    pub fn new(client: reqwest::Client, github_token: Option<String>) -> Self {
+        Self {
+            client,
+            github_token,
+        }
+    }
+
+    fn github(&self, path: &str) -> reqwest::RequestBuilder {
+        let request = self
+            .client
+            .get(format!("https://api.github.com/repos/{}", path))
+            .header("Accept", "application/vnd.github+json");
+        match &self.github_token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Look up a repository. None if its host isn't supported.
+    pub async fn fetch(&self, repository: &str) -> Result<Option<RepositoryMetadata>> {
+        let Some((owner, name)) = github_repository(repository) else {
+            return Ok(None);
+        };
+        let path = format!("{}/{}", owner, name);
+
+        let repo: GithubRepository = self
+            .github(&path)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        // Repositories without releases respond with 404
+        let response = self.github(&format!("{}/releases/latest", path)).send().await?;
+        let latest_release = match response.status() {
+            StatusCode::NOT_FOUND => None,
+            _ => {
+                let release: GithubRelease = response.error_for_status()?.json().await?;
+                release.published_at.map(|published_at| Release {
+                    version: tag_version(&release.tag_name).to_string(),
+                    published_at,
+                    notes: present(release.body),
+                })
+            }
+        };
+
+        Ok(Some(RepositoryMetadata {
+            name: repo.name,
+            description: present(repo.description),
+            homepage: present(repo.homepage),
+            // GitHub reports license files it can't identify as NOASSERTION
+            license: present(repo.license.and_then(|l| l.spdx_id)).filter(|id| id != "NOASSERTION"),
+            latest_release,
+        }))
+    }
+
+    /// Whether a repository is archived. None if its host isn't supported.
+    pub async fn archived(&self, repository: &str) -> Result<Option<bool>> {
+        let Some((owner, name)) = github_repository(repository) else {
+            return Ok(None);
+        };
+        let repo: GithubRepository = self
+            .github(&format!("{}/{}", owner, name))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(Some(repo.archived))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_github_repository() {
+        let parsed = |url| github_repository(url);
+        assert_eq!(
+            parsed("https://github.com/fossable/fossdb.git"),
+            Some(("fossable".to_string(), "fossdb".to_string()))
+        );
+        assert_eq!(
+            parsed("https://www.github.com/fossable/fossdb/tree/main"),
+            Some(("fossable".to_string(), "fossdb".to_string()))
+        );
+        assert_eq!(parsed("https://github.com/fossable"), None);
+        assert_eq!(parsed("https://gitlab.com/fossable/fossdb"), None);
+
+        assert_eq!(tag_version("v1.2.3"), "1.2.3");
+        assert_eq!(tag_version("1.2.3"), "1.2.3");
+        assert_eq!(tag_version("vendor-2024"), "vendor-2024");
+    }
+}
@@ -0,0 +1,342 @@
+//! Dependency sets users track through their lockfiles, and which of them a
+//! release or advisory affects
+use anyhow::{Result, anyhow, bail};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+
+use crate::storage::Storage;
+use crate::{
+    EventMetadata, EventType, LockedDependency, ProjectDiff, TimelineEvent, VersionChange,
+    version_range,
+};
+
+/// Read the resolved dependencies from a Cargo.lock, package-lock.json or a
+/// requirements.txt with pinned versions, sorted and without duplicates
+pub fn parse_lockfile(contents: &str) -> Result<Vec<LockedDependency>> {
+    let trimmed = contents.trim_start();
+    let mut dependencies = if trimmed.starts_with('{') {
+        parse_package_lock(trimmed)?
+    } else if contents.lines().any(|line| line.trim() == "[[package]]") {
+        parse_cargo_lock(contents)
+    } else {
+        parse_requirements(contents)?
+    };
+
+    if dependencies.is_empty() {
+        bail!("no dependencies found");
+    }
+    dependencies.sort();
+    dependencies.dedup();
+    Ok(dependencies)
+}
+
+/// Packages from a registry or git, skipping the workspace's own crates
+fn parse_cargo_lock(contents: &str) -> Vec<LockedDependency> {
+    #[derive(Default)]
+    struct Entry {
+        name: Option<String>,
+        version: Option<String>,
+        has_source: bool,
+    }
+
+    let mut entries: Vec<Entry> = Vec::new();
+    for line in contents.lines().map(str::trim) {
+        if line == "[[package]]" {
+            entries.push(Entry::default());
+            continue;
+        }
+        let (Some(entry), Some((key, value))) = (entries.last_mut(), line.split_once('=')) else {
+            continue;
+        };
+        let value = value.trim().trim_matches('"').to_string();
+        match key.trim() {
+            "name" => entry.name = Some(value),
+            "version" => entry.version = Some(value),
+            "source" => entry.has_source = true,
+            _ => {}
+        }
+    }
+
+    entries
+        .into_iter()
+        .filter(|entry| entry.has_source)
+        .filter_map(|entry| {
+            Some(LockedDependency {
+                name: entry.name?,
+                version: entry.version?,
+            })
+        })
+        .collect()
+}
+
+fn parse_package_lock(contents: &str) -> Result<Vec<LockedDependency>> {
+    let lock: Value =
+        serde_json::from_str(contents).map_err(|e| anyhow!("invalid package-lock.json: {}", e))?;
+    let mut dependencies = Vec::new();
+
+    // Version 2 and 3 key every installed package by its path
+    if let Some(packages) = lock["packages"].as_object() {
+        for (path, package) in packages {
+            let Some((_, name)) = path.rsplit_once("node_modules/") else {
+                // The root project
+                continue;
+            };
+            if let Some(version) = package["version"].as_str() {
+                dependencies.push(LockedDependency {
+                    name: name.to_string(),
+                    version: version.to_string(),
+                });
+            }
+        }
+    } else if let Some(tree) = lock["dependencies"].as_object() {
+        collect_npm_tree(tree, &mut dependencies);
+    }
+
+    Ok(dependencies)
+}
+
+/// Walk the nested `dependencies` of a version 1 lockfile
+fn collect_npm_tree(tree: &serde_json::Map<String, Value>, out: &mut Vec<LockedDependency>) {
+    for (name, package) in tree {
+        if let Some(version) = package["version"].as_str() {
+            out.push(LockedDependency {
+                name: name.clone(),
+                version: version.to_string(),
+            });
+        }
+        if let Some(nested) = package["dependencies"].as_object() {
+            collect_npm_tree(nested, out);
+        }
+    }
+}
+
+fn parse_requirements(contents: &str) -> Result<Vec<LockedDependency>> {
+    let mut dependencies = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        let line = line.trim().trim_end_matches('\\').trim();
+        // Options such as `-r other.txt` and pip-compile's `--hash` lines
+        if line.is_empty() || line.starts_with('-') {
+            continue;
+        }
+
+        let requirement = line.split(';').next().unwrap_or_default();
+        let Some((name, version)) = requirement.split_once("==") else {
+            bail!("line {}: `{}` is not pinned with ==", number + 1, line);
+        };
+        let name = name.split('[').next().unwrap_or_default().trim();
+        let version = version.split_whitespace().next().unwrap_or_default();
+        if name.is_empty() || version.is_empty() {
+            bail!("line {}: `{}` is not a valid requirement", number + 1, line);
+        }
+        dependencies.push(LockedDependency {
+            name: name.to_string(),
+            version: version.to_string(),
+        });
+    }
+    Ok(dependencies)
+}
+
+/// Compare two sorted dependency sets. A name locked to a single version on
+/// both sides is reported as changed rather than removed and added.
+pub fn diff(old: &[LockedDependency], new: &[LockedDependency]) -> ProjectDiff {
+    let old: BTreeSet<&LockedDependency> = old.iter().collect();
+    let new: BTreeSet<&LockedDependency> = new.iter().collect();
+    let mut added: Vec<LockedDependency> = new.difference(&old).map(|&d| d.clone()).collect();
+    let mut removed: Vec<LockedDependency> = old.difference(&new).map(|&d| d.clone()).collect();
+
+    let count = |deps: &[LockedDependency]| {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for dep in deps {
+            *counts.entry(dep.name.clone()).or_default() += 1;
+        }
+        counts
+    };
+    let (added_counts, removed_counts) = (count(&added), count(&removed));
+    let is_change =
+        |name: &str| added_counts.get(name) == Some(&1) && removed_counts.get(name) == Some(&1);
+
+    let changed = added
+        .iter()
+        .filter(|dep| is_change(&dep.name))
+        .filter_map(|to| {
+            let from = removed.iter().find(|dep| dep.name == to.name)?;
+            Some(VersionChange {
+                name: to.name.clone(),
+                from: from.version.clone(),
+                to: to.version.clone(),
+            })
+        })
+        .collect();
+    added.retain(|dep| !is_change(&dep.name));
+    removed.retain(|dep| !is_change(&dep.name));
+
+    ProjectDiff {
+        added,
+        removed,
+        changed,
+    }
+}
+
+/// Whether `event` concerns a project locked to `locked`: a release at least
+/// one major version ahead, or an advisory whose range may include it
+fn affects(event: &TimelineEvent, locked: &str) -> bool {
+    match event.event_type {
+        EventType::NewRelease => event
+            .version
+            .as_deref()
+            .is_some_and(|released| is_major_upgrade(locked, released)),
+        EventType::SecurityAlert => {
+            let range = match event.typed_metadata() {
+                Some(EventMetadata::Vulnerability { version_range, .. }) => Some(version_range),
+                _ => None,
+            };
+            // Alerts without a range are about the package as a whole
+            range.is_none_or(|range| version_range::matches(&range, locked) != Some(false))
+        }
+        _ => false,
+    }
+}
+
+/// Before 1.0, a minor bump is the breaking one
+fn is_major_upgrade(from: &str, to: &str) -> bool {
+    let (Some(from), Some(to)) = (
+        version_range::parse_version(from),
+        version_range::parse_version(to),
+    ) else {
+        return false;
+    };
+    if from.major == 0 && to.major == 0 {
+        to.minor > from.minor
+    } else {
+        to.major > from.major
+    }
+}
+
+/// Owners of projects that `event` affects, each listed once
+pub fn affected_owners(db: &dyn Storage, event: &TimelineEvent) -> Result<Vec<u64>> {
+    let mut owners = Vec::new();
+    for project in db.get_projects_depending_on(&event.package_name)? {
+        let affected = project
+            .dependencies
+            .iter()
+            .filter(|dep| dep.name == event.package_name)
+            .any(|dep| affects(event, &dep.version));
+        if affected && !owners.contains(&project.user_id) {
+            owners.push(project.user_id);
+        }
+    }
+    Ok(owners)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dep(name: &str, version: &str) -> LockedDependency {
+        LockedDependency {
+            name: name.to_string(),
+            version: version.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_lockfiles() {
+        let cargo_lock = r#"
+version = 4
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = [
+ "serde",
+]
+
+[[package]]
+name = "serde"
+version = "1.0.210"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "abc"
+"#;
+        assert_eq!(
+            parse_lockfile(cargo_lock).unwrap(),
+            [dep("serde", "1.0.210")]
+        );
+
+        let package_lock = r#"{
+            "lockfileVersion": 3,
+            "packages": {
+                "": { "name": "app", "version": "1.0.0" },
+                "node_modules/react": { "version": "18.3.1" },
+                "node_modules/a/node_modules/@babel/core": { "version": "7.25.0" }
+            }
+        }"#;
+        assert_eq!(
+            parse_lockfile(package_lock).unwrap(),
+            [dep("@babel/core", "7.25.0"), dep("react", "18.3.1")]
+        );
+
+        let requirements = "# pinned\nrequests[socks]==2.32.3 \\\n    --hash=sha256:abc\nidna==3.8 ; python_version >= \"3.6\"\n";
+        assert_eq!(
+            parse_lockfile(requirements).unwrap(),
+            [dep("idna", "3.8"), dep("requests", "2.32.3")]
+        );
+
+        assert!(parse_lockfile("requests>=2\n").is_err());
+        assert!(parse_lockfile("").is_err());
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = [dep("a", "1.0.0"), dep("b", "1.0.0"), dep("c", "1.0.0")];
+        let new = [dep("a", "1.0.0"), dep("b", "2.0.0"), dep("d", "0.1.0")];
+        assert_eq!(
+            diff(&old, &new),
+            ProjectDiff {
+                added: vec![dep("d", "0.1.0")],
+                removed: vec![dep("c", "1.0.0")],
+                changed: vec![VersionChange {
+                    name: "b".to_string(),
+                    from: "1.0.0".to_string(),
+                    to: "2.0.0".to_string(),
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn test_major_upgrade() {
+        assert!(is_major_upgrade("1.4.0", "2.0.0"));
+        assert!(!is_major_upgrade("1.4.0", "1.9.0"));
+        assert!(is_major_upgrade("0.3.2", "0.4.0"));
+        assert!(!is_major_upgrade("0.3.2", "0.3.9"));
+    }
+
+    #[test]
+    fn test_affects() {
+        let alert = |metadata: Option<String>| TimelineEvent {
+            id: 0,
+            package_id: 1,
+            user_id: None,
+            event_type: EventType::SecurityAlert,
+            package_name: "serde".to_string(),
+            version: None,
+            message: String::new(),
+            metadata,
+            created_at: chrono::Utc::now(),
+            notified_at: None,
+        };
+        let typed = EventMetadata::Vulnerability {
+            vulnerability_id: 1,
+            cve_id: None,
+            version_range: "<1.2.0".to_string(),
+        };
+        assert!(affects(&alert(Some(typed.to_json())), "1.1.0"));
+        assert!(!affects(&alert(Some(typed.to_json())), "1.2.0"));
+        // Written before metadata was tagged
+        let legacy = r#"{"vulnerability_id":1,"cve_id":null,"version_range":"<1.2.0"}"#;
+        assert_eq!(EventMetadata::parse(legacy), Some(typed));
+        assert!(!affects(&alert(Some(legacy.to_string())), "1.2.0"));
+        assert!(affects(&alert(None), "1.2.0"));
+    }
+}
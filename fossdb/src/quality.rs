@@ -0,0 +1,210 @@
+// Metadata completeness scoring for packages
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{Package, VulnerabilitySeverity};
+
+/// Points awarded for each present field, out of 100
+const FIELD_WEIGHT: u32 = 20;
+
+/// Source name used for packages that weren't ingested by a collector
+pub const MANUAL_SOURCE: &str = "manual";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PackageQuality {
+    pub package_id: u64,
+    pub name: String,
+    pub source: String,
+    pub score: u32,
+    pub missing: Vec<String>,
+}
+
+/// Aggregated completeness for all packages from one source
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SourceQuality {
+    pub source: String,
+    pub packages: u64,
+    pub average_score: f32,
+    pub missing_description: u64,
+    pub missing_license: u64,
+    pub missing_repository: u64,
+    pub missing_homepage: u64,
+    pub missing_versions: u64,
+}
+
+fn is_present(value: &Option<String>) -> bool {
+    value.as_ref().is_some_and(|v| !v.trim().is_empty())
+}
+
+/// Score a package on the metadata operators care about most
+pub fn score_package(package: &Package, has_versions: bool) -> PackageQuality {
+    let checks = [
+        ("description", is_present(&package.description)),
+        ("license", is_present(&package.license)),
+        ("repository", is_present(&package.repository)),
+        ("homepage", is_present(&package.homepage)),
+        ("versions", has_versions),
+    ];
+
+    let missing: Vec<String> = checks
+        .iter()
+        .filter(|(_, present)| !present)
+        .map(|(field, _)| field.to_string())
+        .collect();
+
+    PackageQuality {
+        package_id: package.id,
+        name: package.name.clone(),
+        source: package
+            .platform
+            .clone()
+            .unwrap_or_else(|| MANUAL_SOURCE.to_string()),
+        score: (checks.len() - missing.len()) as u32 * FIELD_WEIGHT,
+        missing,
+    }
+}
+
+/// Overall health out of 100: half for having no open advisories, 30 for a
+/// release in the last year (15 within two) and 20 for complete metadata
+pub fn health_score(
+    quality: &PackageQuality,
+    latest_release: Option<DateTime<Utc>>,
+    open_advisories: &[VulnerabilitySeverity],
+    now: DateTime<Utc>,
+) -> u32 {
+    let penalty: u32 = open_advisories
+        .iter()
+        .map(|severity| match severity {
+            VulnerabilitySeverity::Critical => 50,
+            VulnerabilitySeverity::High => 30,
+            VulnerabilitySeverity::Medium => 15,
+            VulnerabilitySeverity::Low => 5,
+        })
+        .sum();
+    let security = 50u32.saturating_sub(penalty);
+
+    let activity = match latest_release.map(|released| now - released) {
+        Some(age) if age <= Duration::days(365) => 30,
+        Some(age) if age <= Duration::days(2 * 365) => 15,
+        _ => 0,
+    };
+
+    security + activity + quality.score / 5
+}
+
+/// Group package scores by source, sorted by source name
+pub fn aggregate_by_source(scores: &[PackageQuality]) -> Vec<SourceQuality> {
+    let mut sources: BTreeMap<&str, (SourceQuality, u64)> = BTreeMap::new();
+
+    for score in scores {
+        let (stats, total_score) = sources.entry(&score.source).or_insert_with(|| {
+            (
+                SourceQuality {
+                    source: score.source.clone(),
+                    ..Default::default()
+                },
+                0,
+            )
+        });
+
+        stats.packages += 1;
+        *total_score += score.score as u64;
+
+        for field in &score.missing {
+            match field.as_str() {
+                "description" => stats.missing_description += 1,
+                "license" => stats.missing_license += 1,
+                "repository" => stats.missing_repository += 1,
+                "homepage" => stats.missing_homepage += 1,
+                "versions" => stats.missing_versions += 1,
+                _ => {}
+            }
+        }
+    }
+
+    sources
+        .into_values()
+        .map(|(mut stats, total_score)| {
+            stats.average_score = total_score as f32 / stats.packages as f32;
+            stats
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn package(id: u64, platform: Option<&str>, description: Option<&str>) -> Package {
+        Package {
+            id,
+            name: format!("pkg{}", id),
+            description: description.map(str::to_string),
+            homepage: None,
+            repository: Some("https://example.com/repo".to_string()),
+            license: Some("MIT".to_string()),
+            tags: vec![],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            platform: platform.map(str::to_string),
+            language: None,
+            status: None,
+            dependents_count: None,
+            rank: None,
+        }
+    }
+
+    #[test]
+    fn test_score_package() {
+        let quality = score_package(&package(1, Some("crates.io"), Some("  ")), true);
+        assert_eq!(quality.score, 60);
+        assert_eq!(quality.missing, vec!["description", "homepage"]);
+        assert_eq!(quality.source, "crates.io");
+    }
+
+    #[test]
+    fn test_health_score() {
+        let now = Utc::now();
+        let quality = score_package(&package(1, Some("crates.io"), Some("desc")), true);
+        assert_eq!(quality.score, 80);
+        assert_eq!(health_score(&quality, Some(now), &[], now), 96);
+        assert_eq!(
+            health_score(
+                &quality,
+                Some(now - Duration::days(500)),
+                &[VulnerabilitySeverity::High],
+                now
+            ),
+            51
+        );
+        assert_eq!(
+            health_score(
+                &quality,
+                None,
+                &[VulnerabilitySeverity::Critical, VulnerabilitySeverity::Low],
+                now
+            ),
+            16
+        );
+    }
+
+    #[test]
+    fn test_aggregate_by_source() {
+        let scores = vec![
+            score_package(&package(1, Some("nixpkgs"), Some("desc")), true),
+            score_package(&package(2, Some("nixpkgs"), None), false),
+            score_package(&package(3, None, Some("desc")), true),
+        ];
+
+        let sources = aggregate_by_source(&scores);
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].source, MANUAL_SOURCE);
+        assert_eq!(sources[1].packages, 2);
+        assert_eq!(sources[1].average_score, 60.0);
+        assert_eq!(sources[1].missing_description, 1);
+        assert_eq!(sources[1].missing_versions, 1);
+        assert_eq!(sources[1].missing_homepage, 2);
+    }
+}
@@ -0,0 +1,340 @@
+//! Ad hoc filtering and sorting of whole tables, for `fossdb query`.
+//!
+//! ```sh
+//! fossdb query --table packages --where 'language=rust' --sort -rank --limit 20
+//! ```
+use anyhow::{Result, anyhow, bail};
+use serde::Serialize;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use crate::storage::Storage;
+
+pub const TABLES: &[&str] = &[
+    "packages",
+    "versions",
+    "users",
+    "vulnerabilities",
+    "timeline_events",
+];
+
+/// Fields never shown, whatever the query asks for
+const HIDDEN_FIELDS: &[&str] = &["password_hash"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// Case-insensitive substring
+    Contains,
+}
+
+/// `field<op>value`, e.g. `language=rust`, `rank>=10` or `name~serde`.
+/// Dotted fields reach into nested objects, and list fields match if any
+/// item does.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub field: String,
+    pub op: Op,
+    pub value: String,
+}
+
+impl FromStr for Condition {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (index, op, len) = s
+            .char_indices()
+            .find_map(|(i, c)| {
+                let next = s[i + c.len_utf8()..].chars().next();
+                match (c, next) {
+                    ('!', Some('=')) => Some((i, Op::Ne, 2)),
+                    ('>', Some('=')) => Some((i, Op::Ge, 2)),
+                    ('<', Some('=')) => Some((i, Op::Le, 2)),
+                    ('=', _) => Some((i, Op::Eq, 1)),
+                    ('>', _) => Some((i, Op::Gt, 1)),
+                    ('<', _) => Some((i, Op::Lt, 1)),
+                    ('~', _) => Some((i, Op::Contains, 1)),
+                    _ => None,
+                }
+            })
+            .ok_or_else(|| anyhow!("Expected field=value, !=, <, <=, >, >= or ~ in '{}'", s))?;
+
+        let field = s[..index].trim();
+        if field.is_empty() {
+            bail!("Missing field name in '{}'", s);
+        }
+        Ok(Self {
+            field: field.to_string(),
+            op,
+            value: s[index + len..].trim().to_string(),
+        })
+    }
+}
+
+impl Condition {
+    pub fn matches(&self, record: &Value) -> bool {
+        match (lookup(record, &self.field), self.op) {
+            (Some(Value::Array(items)), Op::Ne) => !items.iter().any(|item| self.test(item, Op::Eq)),
+            (Some(Value::Array(items)), op) => items.iter().any(|item| self.test(item, op)),
+            (Some(value), op) => self.test(value, op),
+            (None, op) => self.test(&Value::Null, op),
+        }
+    }
+
+    fn test(&self, value: &Value, op: Op) -> bool {
+        if op == Op::Contains {
+            return text(value)
+                .to_lowercase()
+                .contains(&self.value.to_lowercase());
+        }
+        let Some(ordering) = self.compare(value) else {
+            return op == Op::Ne;
+        };
+        match op {
+            Op::Eq => ordering == Ordering::Equal,
+            Op::Ne => ordering != Ordering::Equal,
+            Op::Lt => ordering == Ordering::Less,
+            Op::Le => ordering != Ordering::Greater,
+            Op::Gt => ordering == Ordering::Greater,
+            Op::Ge => ordering != Ordering::Less,
+            Op::Contains => unreachable!(),
+        }
+    }
+
+    /// How `value` compares to the condition's value, if they're comparable
+    fn compare(&self, value: &Value) -> Option<Ordering> {
+        match value {
+            Value::Null => (self.value.is_empty() || self.value == "null").then_some(Ordering::Equal),
+            Value::Bool(b) => self.value.parse::<bool>().ok().map(|v| b.cmp(&v)),
+            Value::Number(n) => n.as_f64()?.partial_cmp(&self.value.parse::<f64>().ok()?),
+            Value::String(s) => Some(s.to_lowercase().cmp(&self.value.to_lowercase())),
+            Value::Array(_) | Value::Object(_) => None,
+        }
+    }
+}
+
+fn lookup<'a>(record: &'a Value, field: &str) -> Option<&'a Value> {
+    field.split('.').try_fold(record, |value, key| value.get(key))
+}
+
+/// A value as shown in a table cell
+pub fn text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(text).collect::<Vec<_>>().join(","),
+        other => other.to_string(),
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Query {
+    pub conditions: Vec<Condition>,
+    /// Field to sort by, descending if prefixed with `-`
+    pub sort: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Records of `table` matching `query`, as JSON
+pub fn run(db: &dyn Storage, table: &str, query: &Query) -> Result<Vec<Value>> {
+    let mut rows = Vec::new();
+    let mut visit = |record: Value| {
+        if query.conditions.iter().all(|c| c.matches(&record))
+            // Without sorting, the first matches are the answer
+            && (query.sort.is_some() || query.limit.is_none_or(|limit| rows.len() < limit))
+        {
+            rows.push(record);
+        }
+    };
+
+    fn each<T: Serialize>(visit: &mut dyn FnMut(Value)) -> impl FnMut(T) -> Result<()> {
+        move |record| {
+            let mut value = serde_json::to_value(record)?;
+            if let Value::Object(fields) = &mut value {
+                for field in HIDDEN_FIELDS {
+                    fields.remove(*field);
+                }
+            }
+            visit(value);
+            Ok(())
+        }
+    }
+
+    match table {
+        "packages" => db.for_each_package(&mut each(&mut visit))?,
+        "versions" => db.for_each_version(&mut each(&mut visit))?,
+        "users" => db.for_each_user(&mut each(&mut visit))?,
+        "vulnerabilities" => db.for_each_vulnerability(&mut each(&mut visit))?,
+        "timeline_events" => db.for_each_timeline_event(&mut each(&mut visit))?,
+        _ => bail!("Unknown table '{}'. Valid tables: {}", table, TABLES.join(", ")),
+    }
+
+    if let Some(sort) = &query.sort {
+        let (field, descending) = match sort.strip_prefix('-') {
+            Some(field) => (field, true),
+            None => (sort.as_str(), false),
+        };
+        rows.sort_by(|a, b| {
+            let (a, b) = (lookup(a, field), lookup(b, field));
+            // Missing values last either way
+            match (a.filter(|v| !v.is_null()), b.filter(|v| !v.is_null())) {
+                (Some(a), Some(b)) => {
+                    let ordering = compare_values(a, b);
+                    if descending { ordering.reverse() } else { ordering }
+                }
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }
+        });
+        if let Some(limit) = query.limit {
+            rows.truncate(limit);
+        }
+    }
+    Ok(rows)
+}
+
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        _ => text(a).to_lowercase().cmp(&text(b).to_lowercase()),
+    }
+}
+
+/// Columns shown for a table unless others are asked for
+pub fn default_columns(table: &str) -> &'static [&'static str] {
+    match table {
+        "packages" => &["id", "name", "platform", "language", "license", "rank"],
+        "versions" => &["id", "package_id", "version", "release_date", "license"],
+        "users" => &["id", "username", "email", "role", "created_at"],
+        "vulnerabilities" => &["id", "cve_id", "severity", "cvss_score", "title"],
+        "timeline_events" => &["id", "package_name", "event_type", "version", "user_id", "created_at"],
+        _ => &["id"],
+    }
+}
+
+/// Render rows as an aligned text table, truncating long cells
+pub fn render_table(rows: &[Value], columns: &[&str]) -> String {
+    const MAX_WIDTH: usize = 40;
+
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .map(|column| {
+                    let cell = lookup(row, column).map(text).unwrap_or_default();
+                    let cell = cell.replace(['\n', '\t'], " ");
+                    if cell.chars().count() > MAX_WIDTH {
+                        format!("{}…", cell.chars().take(MAX_WIDTH - 1).collect::<String>())
+                    } else {
+                        cell
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            cells
+                .iter()
+                .map(|row| row[i].chars().count())
+                .chain([column.len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let line = |values: Vec<String>| {
+        values
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{:<width$}", value, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut output = line(columns.iter().map(|c| c.to_uppercase()).collect());
+    for row in cells {
+        output.push('\n');
+        output.push_str(&line(row));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::{Package, Role, fixtures};
+
+    #[test]
+    fn test_query() {
+        let db = Database::in_memory().unwrap();
+        for (name, language, rank) in [
+            ("serde", "rust", Some(20)),
+            ("tokio", "rust", Some(30)),
+            ("left-pad", "javascript", Some(5)),
+            ("unranked", "rust", None),
+        ] {
+            db.insert_package(Package {
+                language: Some(language.to_string()),
+                rank,
+                ..fixtures::package(name)
+            })
+            .unwrap();
+        }
+        db.insert_user(fixtures::user("alice", &[])).unwrap();
+
+        let names = |conditions: &[&str], sort: Option<&str>, limit: Option<usize>| -> Vec<String> {
+            let query = Query {
+                conditions: conditions.iter().map(|c| c.parse().unwrap()).collect(),
+                sort: sort.map(str::to_string),
+                limit,
+            };
+            run(&db, "packages", &query)
+                .unwrap()
+                .iter()
+                .map(|row| text(&row["name"]))
+                .collect()
+        };
+
+        assert_eq!(
+            names(&["language=Rust"], Some("-rank"), None),
+            ["tokio", "serde", "unranked"]
+        );
+        assert_eq!(names(&["rank>=20"], Some("rank"), Some(1)), ["serde"]);
+        assert_eq!(names(&["name~PAD", "tags=crate"], None, None), ["left-pad"]);
+        assert_eq!(names(&["rank="], None, None), ["unranked"]);
+        assert_eq!(names(&["language!=rust"], None, Some(5)), ["left-pad"]);
+
+        let users = run(&db, "users", &Query::default()).unwrap();
+        assert_eq!(users[0]["role"], serde_json::json!(Role::User));
+        assert!(users[0].get("password_hash").is_none());
+
+        assert!("language".parse::<Condition>().is_err());
+        assert_eq!(
+            "rank >= 10".parse::<Condition>().unwrap(),
+            Condition {
+                field: "rank".to_string(),
+                op: Op::Ge,
+                value: "10".to_string()
+            }
+        );
+    }
+}
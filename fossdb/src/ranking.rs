@@ -0,0 +1,254 @@
+//! Orders packages by how prominent they are, combining what every source
+//! knows about them into one score
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+
+use crate::PackageRank;
+use crate::maintenance::STALE_AFTER_DAYS;
+use crate::storage::Storage;
+
+/// Scores are normalized to this
+pub const MAX_SCORE: u32 = 1000;
+
+/// Share of the score each signal carries
+const DEPENDENTS_WEIGHT: f64 = 0.30;
+const DOWNLOADS_WEIGHT: f64 = 0.25;
+const STARS_WEIGHT: f64 = 0.15;
+const SUBSCRIBERS_WEIGHT: f64 = 0.15;
+const RECENCY_WEIGHT: f64 = 0.15;
+
+/// Releases this recent count fully towards recency
+const FRESH_DAYS: i64 = 90;
+
+/// What's known about the reach of a package. Unknown counts are zero.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Signals {
+    pub dependents: u64,
+    pub downloads: u64,
+    pub stars: u64,
+    pub subscribers: u64,
+    pub last_release: Option<DateTime<Utc>>,
+}
+
+impl Signals {
+    /// The highest of each count, which scores are relative to
+    fn max(&self, other: &Signals) -> Signals {
+        Signals {
+            dependents: self.dependents.max(other.dependents),
+            downloads: self.downloads.max(other.downloads),
+            stars: self.stars.max(other.stars),
+            subscribers: self.subscribers.max(other.subscribers),
+            last_release: self.last_release.max(other.last_release),
+        }
+    }
+}
+
+/// `value` relative to the highest seen, on a log scale so a handful of
+/// giants don't flatten everyone else
+fn relative(value: u64, max: u64) -> f64 {
+    if max == 0 {
+        return 0.0;
+    }
+    (value as f64).ln_1p() / (max as f64).ln_1p()
+}
+
+/// Full marks for a release in the last [`FRESH_DAYS`], falling to nothing
+/// once a package would be considered unmaintained
+fn recency(last_release: Option<DateTime<Utc>>, now: DateTime<Utc>) -> f64 {
+    let Some(last_release) = last_release else {
+        return 0.0;
+    };
+    let age = (now - last_release).num_days();
+    if age <= FRESH_DAYS {
+        return 1.0;
+    }
+    (1.0 - (age - FRESH_DAYS) as f64 / (STALE_AFTER_DAYS - FRESH_DAYS) as f64).max(0.0)
+}
+
+/// Score `signals` out of [`MAX_SCORE`], relative to the highest counts
+/// across all packages
+pub fn score(signals: &Signals, maxima: &Signals, now: DateTime<Utc>) -> u32 {
+    let weighted = DEPENDENTS_WEIGHT * relative(signals.dependents, maxima.dependents)
+        + DOWNLOADS_WEIGHT * relative(signals.downloads, maxima.downloads)
+        + STARS_WEIGHT * relative(signals.stars, maxima.stars)
+        + SUBSCRIBERS_WEIGHT * relative(signals.subscribers, maxima.subscribers)
+        + RECENCY_WEIGHT * recency(signals.last_release, now);
+    (weighted * MAX_SCORE as f64).round() as u32
+}
+
+/// Keep the downloads and stars a source reported for a package, leaving
+/// counts it doesn't know about as they were
+pub fn record_popularity(
+    db: &dyn Storage,
+    reported: Vec<(u64, Option<u64>, Option<u32>)>,
+) -> Result<()> {
+    let mut ranks = Vec::new();
+    for (package_id, downloads, stars) in reported {
+        if downloads.is_none() && stars.is_none() {
+            continue;
+        }
+        let mut rank = db.get_package_rank(package_id)?.unwrap_or(PackageRank {
+            package_id,
+            score: 0,
+            downloads: None,
+            stars: None,
+            computed_at: None,
+        });
+        rank.downloads = downloads.or(rank.downloads);
+        rank.stars = stars.or(rank.stars);
+        ranks.push(rank);
+    }
+    if !ranks.is_empty() {
+        db.update_package_ranks(ranks)?;
+    }
+    Ok(())
+}
+
+/// Rescore every package, returning how many scores changed
+pub fn recompute(db: &dyn Storage, now: DateTime<Utc>) -> Result<usize> {
+    let mut ids: HashMap<String, u64> = HashMap::new();
+    let mut signals: HashMap<u64, Signals> = HashMap::new();
+    db.for_each_package(&mut |package| {
+        signals.entry(package.id).or_default().dependents =
+            package.dependents_count.unwrap_or(0) as u64;
+        ids.insert(package.name, package.id);
+        Ok(())
+    })?;
+
+    // Registries that don't report dependents still have them in the catalog
+    let mut dependents: HashMap<u64, HashSet<u64>> = HashMap::new();
+    db.for_each_version(&mut |version| {
+        if let Some(signals) = signals.get_mut(&version.package_id) {
+            signals.last_release = signals.last_release.max(Some(version.release_date));
+        }
+        for dependency in &version.dependencies {
+            if let Some(&id) = ids.get(&dependency.name)
+                && id != version.package_id
+            {
+                dependents.entry(id).or_default().insert(version.package_id);
+            }
+        }
+        Ok(())
+    })?;
+    for (id, found) in dependents {
+        if let Some(signals) = signals.get_mut(&id) {
+            signals.dependents = signals.dependents.max(found.len() as u64);
+        }
+    }
+
+    db.for_each_user(&mut |user| {
+        for subscription in user.subscriptions {
+            if let Some(signals) = ids
+                .get(&subscription.package_name)
+                .and_then(|id| signals.get_mut(id))
+            {
+                signals.subscribers += 1;
+            }
+        }
+        Ok(())
+    })?;
+
+    let mut ranks: HashMap<u64, PackageRank> = db
+        .get_all_package_ranks()?
+        .into_iter()
+        .map(|rank| (rank.package_id, rank))
+        .collect();
+    for rank in ranks.values() {
+        if let Some(signals) = signals.get_mut(&rank.package_id) {
+            signals.downloads = rank.downloads.unwrap_or(0);
+            signals.stars = rank.stars.unwrap_or(0) as u64;
+        }
+    }
+
+    let maxima = signals
+        .values()
+        .fold(Signals::default(), |maxima, signals| maxima.max(signals));
+    let mut changed = Vec::new();
+    for (id, signals) in &signals {
+        let score = score(signals, &maxima, now);
+        let rank = ranks.remove(id).unwrap_or(PackageRank {
+            package_id: *id,
+            score: 0,
+            downloads: None,
+            stars: None,
+            computed_at: None,
+        });
+        if rank.score != score || rank.computed_at.is_none() {
+            changed.push(PackageRank {
+                score,
+                computed_at: Some(now),
+                ..rank
+            });
+        }
+    }
+
+    let count = changed.len();
+    if count > 0 {
+        db.update_package_ranks(changed)?;
+    }
+    Ok(count)
+}
+
+/// Scores of every ranked package, for ordering listings. Packages missing
+/// from it haven't been ranked yet.
+pub fn scores(db: &dyn Storage) -> Result<HashMap<u64, u32>> {
+    Ok(db
+        .get_all_package_ranks()?
+        .into_iter()
+        .map(|rank| (rank.package_id, rank.score))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::fixtures;
+    use crate::storage::Storage;
+
+    #[test]
+    fn test_score() {
+        let now = Utc::now();
+        let maxima = Signals {
+            dependents: 100,
+            downloads: 1_000_000,
+            stars: 5000,
+            subscribers: 10,
+            last_release: Some(now),
+        };
+        assert_eq!(score(&maxima, &maxima, now), MAX_SCORE);
+        assert_eq!(score(&Signals::default(), &maxima, now), 0);
+        assert_eq!(score(&Signals::default(), &Signals::default(), now), 0);
+
+        // Recency fades out over the two years before a package is stale
+        let released = |days| Signals {
+            last_release: Some(now - chrono::Duration::days(days)),
+            ..Default::default()
+        };
+        assert_eq!(score(&released(30), &maxima, now), 150);
+        assert!(score(&released(400), &maxima, now) < 150);
+        assert_eq!(score(&released(STALE_AFTER_DAYS), &maxima, now), 0);
+    }
+
+    #[test]
+    fn test_recompute() {
+        let db = Database::in_memory().unwrap();
+        let seeded = fixtures::seed(&db).unwrap();
+        let now = Utc::now();
+
+        record_popularity(&db, vec![(seeded.tokio.id, Some(5_000_000), None)]).unwrap();
+        record_popularity(&db, vec![(seeded.tokio.id, None, Some(25_000))]).unwrap();
+        assert_eq!(recompute(&db, now).unwrap(), 3);
+        let scores = scores(&db).unwrap();
+        // serde_json depends on serde, and both are followed
+        assert!(scores[&seeded.serde.id] > scores[&seeded.serde_json.id]);
+        assert!(scores[&seeded.tokio.id] > scores[&seeded.serde.id]);
+        let tokio = db.get_package_rank(seeded.tokio.id).unwrap().unwrap();
+        assert_eq!(tokio.downloads, Some(5_000_000));
+        assert_eq!(tokio.stars, Some(25_000));
+
+        // Unchanged scores aren't rewritten
+        assert_eq!(recompute(&db, now).unwrap(), 0);
+    }
+}
@@ -0,0 +1,335 @@
+//! Per-caller request quotas for the API, with a tighter tier for anonymous
+//! callers than for signed in users
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use governor::{
+    DefaultKeyedRateLimiter, Quota, RateLimiter,
+    clock::{Clock, DefaultClock},
+    middleware::StateInformationMiddleware,
+    state::keyed::DefaultKeyedStateStore,
+};
+use std::collections::BTreeMap;
+use std::hash::{BuildHasher, RandomState};
+use std::net::SocketAddr;
+use std::num::NonZeroU32;
+
+use crate::{AppState, RateLimitTier, RateLimitsResponse};
+
+pub const LIMIT_HEADER: &str = "x-ratelimit-limit";
+pub const REMAINING_HEADER: &str = "x-ratelimit-remaining";
+pub const TIER_HEADER: &str = "x-ratelimit-tier";
+
+/// Checksum mismatches a user can report per hour, since each one can raise
+/// a security alert
+pub const CHECKSUM_REPORTS_PER_HOUR: u32 = 10;
+
+type Limiter =
+    RateLimiter<String, DefaultKeyedStateStore<String>, DefaultClock, StateInformationMiddleware>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tier {
+    /// No valid token, counted per client IP
+    Anonymous,
+    /// A valid bearer token, counted per user
+    Authenticated,
+}
+
+impl Tier {
+    pub fn name(self) -> &'static str {
+        match self {
+            Tier::Anonymous => "anonymous",
+            Tier::Authenticated => "authenticated",
+        }
+    }
+}
+
+struct TierLimit {
+    per_minute: u32,
+    /// None when the tier is unlimited
+    limiter: Option<Limiter>,
+}
+
+impl TierLimit {
+    fn new(per_minute: u32) -> Self {
+        Self {
+            per_minute,
+            limiter: NonZeroU32::new(per_minute)
+                .map(|n| RateLimiter::keyed(Quota::per_minute(n)).with_middleware()),
+        }
+    }
+}
+
+enum Outcome {
+    Unlimited,
+    Allowed { limit: u32, remaining: u32 },
+    Limited { limit: u32, retry_after: u64 },
+}
+
+pub struct RateLimits {
+    anonymous: TierLimit,
+    authenticated: TierLimit,
+    /// Take the client IP from `X-Forwarded-For`, for running behind a proxy
+    trust_forwarded: bool,
+    /// Set in privacy mode, so anonymous callers are told apart by a hash
+    /// that's keyed per process instead of by their IP
+    ip_hasher: Option<RandomState>,
+    /// Checksum mismatch reports, counted per user
+    checksum_reports: DefaultKeyedRateLimiter<u64>,
+}
+
+impl RateLimits {
+    /// Quotas are requests per minute, and 0 leaves a tier unlimited
+    pub fn new(
+        anonymous_per_minute: u32,
+        authenticated_per_minute: u32,
+        trust_forwarded: bool,
+    ) -> Self {
+        Self {
+            anonymous: TierLimit::new(anonymous_per_minute),
+            authenticated: TierLimit::new(authenticated_per_minute),
+            trust_forwarded,
+            ip_hasher: None,
+            checksum_reports: RateLimiter::keyed(Quota::per_hour(
+                NonZeroU32::new(CHECKSUM_REPORTS_PER_HOUR).unwrap(),
+            )),
+        }
+    }
+
+    /// Never hold on to client IPs, only to hashes of them that can't be
+    /// matched across restarts
+    pub fn with_privacy_mode(mut self, enabled: bool) -> Self {
+        self.ip_hasher = enabled.then(RandomState::new);
+        self
+    }
+
+    pub fn privacy_mode(&self) -> bool {
+        self.ip_hasher.is_some()
+    }
+
+    fn tier_limit(&self, tier: Tier) -> &TierLimit {
+        match tier {
+            Tier::Anonymous => &self.anonymous,
+            Tier::Authenticated => &self.authenticated,
+        }
+    }
+
+    /// Count a request against its caller's quota
+    fn check(&self, tier: Tier, key: String) -> Outcome {
+        let tier_limit = self.tier_limit(tier);
+        let Some(limiter) = &tier_limit.limiter else {
+            return Outcome::Unlimited;
+        };
+        match limiter.check_key(&key) {
+            Ok(snapshot) => Outcome::Allowed {
+                limit: tier_limit.per_minute,
+                remaining: snapshot.remaining_burst_capacity(),
+            },
+            Err(not_until) => Outcome::Limited {
+                limit: tier_limit.per_minute,
+                retry_after: not_until
+                    .wait_time_from(DefaultClock::default().now())
+                    .as_secs()
+                    .max(1),
+            },
+        }
+    }
+
+    /// Count a checksum mismatch report against `user_id`'s hourly quota
+    pub fn allow_checksum_report(&self, user_id: u64) -> bool {
+        self.checksum_reports.check_key(&user_id).is_ok()
+    }
+
+    /// Forget callers whose quota has refilled, so idle IPs don't pile up
+    pub fn retain_recent(&self) {
+        for tier_limit in [&self.anonymous, &self.authenticated] {
+            if let Some(limiter) = &tier_limit.limiter {
+                limiter.retain_recent();
+                limiter.shrink_to_fit();
+            }
+        }
+        self.checksum_reports.retain_recent();
+        self.checksum_reports.shrink_to_fit();
+    }
+
+    /// Which tier a request falls in, and the key its quota is tracked under
+    pub fn caller(&self, headers: &HeaderMap, peer: Option<SocketAddr>) -> (Tier, String) {
+        if let Some(claims) = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .and_then(|token| crate::auth::verify_jwt(token).ok())
+        {
+            return (Tier::Authenticated, format!("user:{}", claims.sub));
+        }
+
+        let forwarded = self
+            .trust_forwarded
+            .then(|| headers.get("x-forwarded-for"))
+            .flatten()
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.split(',').next())
+            .map(|ip| ip.trim().to_string());
+        let ip = forwarded
+            .or_else(|| peer.map(|addr| addr.ip().to_string()))
+            .unwrap_or_else(|| "unknown".to_string());
+        match &self.ip_hasher {
+            Some(hasher) => (Tier::Anonymous, format!("ip:{:016x}", hasher.hash_one(&ip))),
+            None => (Tier::Anonymous, format!("ip:{}", ip)),
+        }
+    }
+
+    pub fn describe(&self, tier: Tier) -> RateLimitsResponse {
+        let tiers = [
+            (
+                Tier::Anonymous,
+                if self.privacy_mode() { "hashed_ip" } else { "ip" },
+                "Requests without a valid bearer token",
+            ),
+            (
+                Tier::Authenticated,
+                "user",
+                "Requests with a valid bearer token",
+            ),
+        ];
+        RateLimitsResponse {
+            tier: tier.name().to_string(),
+            tiers: tiers
+                .into_iter()
+                .map(|(tier, keyed_by, description)| RateLimitTier {
+                    name: tier.name().to_string(),
+                    requests_per_minute: self.tier_limit(tier).per_minute,
+                    keyed_by: keyed_by.to_string(),
+                    description: description.to_string(),
+                })
+                .collect(),
+            headers: BTreeMap::from([
+                (
+                    LIMIT_HEADER.to_string(),
+                    "Requests allowed per minute in the caller's tier".to_string(),
+                ),
+                (
+                    REMAINING_HEADER.to_string(),
+                    "Requests left before the caller is limited".to_string(),
+                ),
+                (TIER_HEADER.to_string(), "The caller's tier".to_string()),
+                (
+                    header::RETRY_AFTER.to_string(),
+                    "Seconds to wait after a 429 response".to_string(),
+                ),
+            ]),
+        }
+    }
+}
+
+fn insert_number(headers: &mut HeaderMap, name: &'static str, value: impl Into<u64>) {
+    headers.insert(name, HeaderValue::from(value.into()));
+}
+
+/// Hold API requests to their caller's tier, reporting the quota left on
+/// every response. Unlimited tiers get no quota headers.
+pub async fn rate_limit_middleware(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let path = req.uri().path();
+    if !path.starts_with("/api/") || path == "/api/health" {
+        return next.run(req).await;
+    }
+
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0);
+    let (tier, key) = state.rate_limits.caller(req.headers(), peer);
+    let tier_header = HeaderValue::from_static(tier.name());
+
+    match state.rate_limits.check(tier, key) {
+        Outcome::Unlimited => next.run(req).await,
+        Outcome::Allowed { limit, remaining } => {
+            let mut response = next.run(req).await;
+            let headers = response.headers_mut();
+            insert_number(headers, LIMIT_HEADER, limit);
+            insert_number(headers, REMAINING_HEADER, remaining);
+            headers.insert(TIER_HEADER, tier_header);
+            response
+        }
+        Outcome::Limited { limit, retry_after } => {
+            let mut response = (
+                StatusCode::TOO_MANY_REQUESTS,
+                "Rate limit exceeded, see /api/limits",
+            )
+                .into_response();
+            let headers = response.headers_mut();
+            insert_number(headers, LIMIT_HEADER, limit);
+            insert_number(headers, REMAINING_HEADER, 0u32);
+            headers.insert(TIER_HEADER, tier_header);
+            headers.insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+            response
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_caller() {
+        let limits = RateLimits::new(60, 600, false);
+        let peer: SocketAddr = "203.0.113.7:4000".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", HeaderValue::from_static("198.51.100.1"));
+        assert_eq!(
+            limits.caller(&headers, Some(peer)),
+            (Tier::Anonymous, "ip:203.0.113.7".to_string())
+        );
+
+        let proxied = RateLimits::new(60, 600, true);
+        assert_eq!(
+            proxied.caller(&headers, Some(peer)),
+            (Tier::Anonymous, "ip:198.51.100.1".to_string())
+        );
+
+        // Privacy mode tells callers apart without keeping their IPs
+        let private = RateLimits::new(60, 600, true).with_privacy_mode(true);
+        let (tier, key) = private.caller(&headers, Some(peer));
+        assert_eq!(tier, Tier::Anonymous);
+        assert!(!key.contains("198.51.100.1"));
+        assert_eq!(private.caller(&headers, Some(peer)).1, key);
+        headers.insert("x-forwarded-for", HeaderValue::from_static("198.51.100.2"));
+        assert_ne!(private.caller(&headers, Some(peer)).1, key);
+        assert_eq!(private.describe(tier).tiers[0].keyed_by, "hashed_ip");
+    }
+
+    #[test]
+    fn test_check() {
+        let limits = RateLimits::new(2, 0, false);
+        let key = || "ip:203.0.113.7".to_string();
+        assert!(matches!(
+            limits.check(Tier::Anonymous, key()),
+            Outcome::Allowed { remaining: 1, .. }
+        ));
+        assert!(matches!(
+            limits.check(Tier::Anonymous, key()),
+            Outcome::Allowed { remaining: 0, .. }
+        ));
+        assert!(matches!(
+            limits.check(Tier::Anonymous, key()),
+            Outcome::Limited { limit: 2, .. }
+        ));
+        // Other callers have their own quota
+        assert!(matches!(
+            limits.check(Tier::Anonymous, "ip:198.51.100.1".to_string()),
+            Outcome::Allowed { .. }
+        ));
+        assert!(matches!(
+            limits.check(Tier::Authenticated, "user:1".to_string()),
+            Outcome::Unlimited
+        ));
+    }
+}
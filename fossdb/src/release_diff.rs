@@ -0,0 +1,259 @@
+// What changed between two releases of a package
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{Dependency, PackageVersion, version_range};
+
+/// A value that differs between the two versions
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Change<T> {
+    pub from: T,
+    pub to: T,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DependencyChange {
+    pub name: String,
+    pub dependency_type: String,
+    pub version_requirement: Option<Change<String>>,
+    pub optional: Option<Change<bool>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VersionDiff {
+    pub package_id: u64,
+    pub from: String,
+    pub to: String,
+    pub added_dependencies: Vec<Dependency>,
+    pub removed_dependencies: Vec<Dependency>,
+    pub changed_dependencies: Vec<DependencyChange>,
+    /// Set only if the license changed
+    pub license: Option<Change<Option<String>>>,
+    /// Set only if the checksum changed
+    pub checksum: Option<Change<Option<String>>>,
+    pub size: Option<Change<Option<u64>>>,
+    /// Growth in bytes, when both sizes are known
+    pub size_delta: Option<i64>,
+}
+
+impl VersionDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_dependencies.is_empty()
+            && self.removed_dependencies.is_empty()
+            && self.changed_dependencies.is_empty()
+            && self.license.is_none()
+            && self.size.is_none()
+    }
+
+    /// One line per change, for notifications
+    pub fn summary(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if let Some(license) = &self.license {
+            lines.push(format!(
+                "License changed from {} to {}",
+                license.from.as_deref().unwrap_or("unknown"),
+                license.to.as_deref().unwrap_or("unknown")
+            ));
+        }
+        for dependency in &self.added_dependencies {
+            lines.push(format!(
+                "Added dependency {} {}",
+                dependency.name, dependency.version_requirement
+            ));
+        }
+        for dependency in &self.removed_dependencies {
+            lines.push(format!("Removed dependency {}", dependency.name));
+        }
+        for change in &self.changed_dependencies {
+            if let Some(requirement) = &change.version_requirement {
+                lines.push(format!(
+                    "Updated dependency {} from {} to {}",
+                    change.name, requirement.from, requirement.to
+                ));
+            } else if let Some(optional) = &change.optional {
+                let now = if optional.to { "optional" } else { "required" };
+                lines.push(format!("Dependency {} is now {}", change.name, now));
+            }
+        }
+        if let Some(delta) = self.size_delta.filter(|delta| *delta != 0) {
+            lines.push(format!("Size changed by {:+} bytes", delta));
+        }
+        lines
+    }
+}
+
+fn change<T: Clone + PartialEq>(from: &T, to: &T) -> Option<Change<T>> {
+    (from != to).then(|| Change {
+        from: from.clone(),
+        to: to.clone(),
+    })
+}
+
+/// Dependencies keyed by name and kind, since a crate can be both a normal
+/// and a dev dependency
+fn by_key(version: &PackageVersion) -> BTreeMap<(&str, &str), &Dependency> {
+    version
+        .dependencies
+        .iter()
+        .map(|d| ((d.name.as_str(), d.dependency_type.as_str()), d))
+        .collect()
+}
+
+/// Compare two versions of the same package
+pub fn diff(from: &PackageVersion, to: &PackageVersion) -> VersionDiff {
+    let old = by_key(from);
+    let new = by_key(to);
+
+    let added_dependencies = new
+        .iter()
+        .filter(|(key, _)| !old.contains_key(key))
+        .map(|(_, d)| (*d).clone())
+        .collect();
+    let removed_dependencies = old
+        .iter()
+        .filter(|(key, _)| !new.contains_key(key))
+        .map(|(_, d)| (*d).clone())
+        .collect();
+    let changed_dependencies = new
+        .iter()
+        .filter_map(|(key, d)| {
+            let previous = old.get(key)?;
+            let version_requirement = change(&previous.version_requirement, &d.version_requirement);
+            let optional = change(&previous.optional, &d.optional);
+            (version_requirement.is_some() || optional.is_some()).then(|| DependencyChange {
+                name: d.name.clone(),
+                dependency_type: d.dependency_type.clone(),
+                version_requirement,
+                optional,
+            })
+        })
+        .collect();
+
+    let size_delta = match (from.size, to.size) {
+        (Some(from), Some(to)) => Some(to as i64 - from as i64),
+        _ => None,
+    };
+
+    VersionDiff {
+        package_id: to.package_id,
+        from: from.version.clone(),
+        to: to.version.clone(),
+        added_dependencies,
+        removed_dependencies,
+        changed_dependencies,
+        license: change(&from.license, &to.license),
+        checksum: change(&from.checksum, &to.checksum),
+        size: change(&from.size, &to.size),
+        size_delta,
+    }
+}
+
+/// The release that came before `version`: the highest lower version, or the
+/// latest earlier release when versions can't be parsed
+pub fn previous_release<'a>(
+    versions: &'a [PackageVersion],
+    version: &str,
+) -> Option<&'a PackageVersion> {
+    let current = versions.iter().find(|v| v.version == version)?;
+    match version_range::parse_version(version) {
+        Some(parsed) => versions
+            .iter()
+            .filter_map(|v| version_range::parse_version(&v.version).map(|p| (p, v)))
+            .filter(|(p, _)| *p < parsed)
+            .max_by(|a, b| a.0.cmp(&b.0))
+            .map(|(_, v)| v),
+        None => versions
+            .iter()
+            .filter(|v| v.release_date < current.release_date)
+            .max_by_key(|v| v.release_date),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn dependency(name: &str, requirement: &str, dependency_type: &str) -> Dependency {
+        Dependency {
+            name: name.to_string(),
+            version_requirement: requirement.to_string(),
+            dependency_type: dependency_type.to_string(),
+            optional: false,
+        }
+    }
+
+    fn version(
+        version: &str,
+        dependencies: Vec<Dependency>,
+        license: &str,
+        size: u64,
+    ) -> PackageVersion {
+        PackageVersion {
+            id: 0,
+            package_id: 1,
+            version: version.to_string(),
+            release_date: Utc::now(),
+            download_url: None,
+            checksum: Some(format!("sha-{}", version)),
+            dependencies,
+            vulnerabilities: vec![],
+            changelog: None,
+            created_at: Utc::now(),
+            license: Some(license.to_string()),
+            size: Some(size),
+        }
+    }
+
+    #[test]
+    fn test_diff_versions() {
+        let old = version(
+            "1.0.0",
+            vec![
+                dependency("serde", "^1.0", "normal"),
+                dependency("rand", "^0.8", "normal"),
+                dependency("serde", "^1.0", "dev"),
+            ],
+            "MIT",
+            1000,
+        );
+        let new = version(
+            "1.1.0",
+            vec![
+                dependency("serde", "^1.0.100", "normal"),
+                dependency("tokio", "^1", "normal"),
+                dependency("serde", "^1.0", "dev"),
+            ],
+            "MIT OR Apache-2.0",
+            900,
+        );
+
+        let diff = diff(&old, &new);
+        assert_eq!(
+            diff.added_dependencies,
+            [dependency("tokio", "^1", "normal")]
+        );
+        assert_eq!(
+            diff.removed_dependencies,
+            [dependency("rand", "^0.8", "normal")]
+        );
+        assert_eq!(diff.changed_dependencies.len(), 1);
+        assert_eq!(diff.changed_dependencies[0].dependency_type, "normal");
+        assert_eq!(
+            diff.license.as_ref().unwrap().to.as_deref(),
+            Some("MIT OR Apache-2.0")
+        );
+        assert!(diff.checksum.is_some());
+        assert_eq!(diff.size_delta, Some(-100));
+        assert_eq!(diff.summary().len(), 5);
+
+        assert!(super::diff(&old, &old).is_empty());
+
+        let versions = [new.clone(), old.clone(), version("0.9.0", vec![], "MIT", 0)];
+        assert_eq!(
+            previous_release(&versions, "1.1.0").unwrap().version,
+            "1.0.0"
+        );
+        assert!(previous_release(&versions, "0.9.0").is_none());
+    }
+}
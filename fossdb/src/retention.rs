@@ -0,0 +1,270 @@
+//! Expiring accounts that haven't been used for a long time. Users are warned
+//! first, and an account is only anonymized or deleted once the warning has
+//! gone unanswered for the grace period.
+use anyhow::{Result, bail};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, HashSet};
+
+use crate::storage::Storage;
+use crate::{AccountActivity, ProfileSettings, Role, TimelinePreferences, User};
+
+/// What happens to an account once it expires
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RetentionAction {
+    /// Strip everything identifying but keep the account's ID
+    Anonymize,
+    Delete,
+}
+
+impl std::str::FromStr for RetentionAction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "anonymize" => Ok(Self::Anonymize),
+            "delete" => Ok(Self::Delete),
+            _ => bail!("Unknown retention action: {}", s),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RetentionPolicy {
+    /// How long an account can go unused before its user is warned
+    pub inactive_after: Duration,
+    /// How long after the warning the account expires
+    pub grace_period: Duration,
+    pub action: RetentionAction,
+    /// Users granted admin or moderator rights by name. Their accounts are
+    /// kept like those with a privileged role, since freeing the name would
+    /// hand the rights to whoever registers it next.
+    pub exempt_usernames: HashSet<String>,
+}
+
+/// Accounts a sweep found to act on
+#[derive(Debug, Default)]
+pub struct Due {
+    /// Users to warn, with when their account will expire
+    pub warn: Vec<(User, DateTime<Utc>)>,
+    /// Users whose warning went unanswered
+    pub expire: Vec<User>,
+}
+
+/// Note that a user was just seen, withdrawing any warning they were sent
+pub fn record_activity(db: &dyn Storage, user_id: u64, now: DateTime<Utc>) -> Result<()> {
+    db.set_account_activity(AccountActivity {
+        user_id,
+        last_active_at: now,
+        warned_at: None,
+        anonymized_at: None,
+    })
+}
+
+/// Find the accounts `policy` applies to. Admins and moderators are never
+/// expired, and users who haven't been seen since activity was first tracked
+/// count from `now`, so enabling a policy doesn't expire anyone straight away.
+pub fn due(db: &dyn Storage, policy: &RetentionPolicy, now: DateTime<Utc>) -> Result<Due> {
+    let mut activity: HashMap<u64, AccountActivity> = db
+        .get_all_account_activity()?
+        .into_iter()
+        .map(|activity| (activity.user_id, activity))
+        .collect();
+
+    let mut due = Due::default();
+    let mut unseen = Vec::new();
+    db.for_each_user(&mut |user| {
+        let Some(activity) = activity.remove(&user.id) else {
+            unseen.push(user.id);
+            return Ok(());
+        };
+        if user.role >= Role::Moderator
+            || policy.exempt_usernames.contains(&user.username)
+            || activity.anonymized_at.is_some()
+        {
+            return Ok(());
+        }
+        match activity.warned_at {
+            Some(warned_at) if warned_at + policy.grace_period <= now => due.expire.push(user),
+            Some(_) => {}
+            None if activity.last_active_at + policy.inactive_after <= now => {
+                due.warn.push((user, now + policy.grace_period))
+            }
+            None => {}
+        }
+        Ok(())
+    })?;
+
+    for user_id in unseen {
+        record_activity(db, user_id, now)?;
+    }
+    Ok(due)
+}
+
+/// Note that a user was sent a warning, starting their grace period
+pub fn mark_warned(db: &dyn Storage, user_id: u64, now: DateTime<Utc>) -> Result<()> {
+    if let Some(activity) = db.get_account_activity(user_id)? {
+        db.set_account_activity(AccountActivity {
+            warned_at: Some(now),
+            ..activity
+        })?;
+    }
+    Ok(())
+}
+
+/// Anonymize or delete an account along with its subscriptions, projects,
+/// GitHub installations, advisory acknowledgements and personal timeline
+pub fn expire(
+    db: &dyn Storage,
+    user_id: u64,
+    action: RetentionAction,
+    now: DateTime<Utc>,
+) -> Result<()> {
+    match action {
+        RetentionAction::Delete => {
+            db.delete_user(user_id)?;
+        }
+        RetentionAction::Anonymize => {
+            let Some(user) = db.get_user(user_id)? else {
+                return Ok(());
+            };
+            db.anonymize_user(User {
+                id: user_id,
+                email: format!("deleted-{}@invalid", user_id),
+                username: format!("deleted-{}", user_id),
+                password_hash: String::new(),
+                subscriptions: Vec::new(),
+                created_at: user.created_at,
+                is_verified: false,
+                notifications_enabled: false,
+                role: Role::User,
+                profile: ProfileSettings::default(),
+                last_read_event_id: None,
+                timeline: TimelinePreferences::default(),
+            })?;
+            if let Some(activity) = db.get_account_activity(user_id)? {
+                db.set_account_activity(AccountActivity {
+                    anonymized_at: Some(now),
+                    ..activity
+                })?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::fixtures;
+    use crate::{EventType, TimelineEvent};
+
+    #[test]
+    fn test_inactive_accounts_expire() {
+        let db = Database::in_memory().unwrap();
+        let seeded = fixtures::seed(&db).unwrap();
+        db.insert_timeline_event(TimelineEvent {
+            id: 0,
+            package_id: seeded.serde.id,
+            user_id: Some(seeded.alice.id),
+            event_type: EventType::NewRelease,
+            package_name: "serde".to_string(),
+            version: Some("1.1.0".to_string()),
+            message: String::new(),
+            metadata: None,
+            created_at: Utc::now(),
+            notified_at: None,
+        })
+        .unwrap();
+        let project = db
+            .insert_project(crate::Project {
+                id: 0,
+                user_id: seeded.alice.id,
+                name: "app".to_string(),
+                dependencies: Vec::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .unwrap();
+        db.upsert_github_installation(crate::GithubInstallation {
+            id: 7,
+            user_id: seeded.alice.id,
+            account: "alice".to_string(),
+            created_at: Utc::now(),
+        })
+        .unwrap();
+        db.set_project_sync(crate::ProjectSync {
+            project_id: project.id,
+            installation_id: 7,
+            repository: "alice/app".to_string(),
+            path: "Cargo.lock".to_string(),
+            last_commit: None,
+            synced_at: None,
+            error: None,
+        })
+        .unwrap();
+        db.acknowledge_advisory(crate::AdvisoryAck {
+            id: 0,
+            user_id: seeded.alice.id,
+            vulnerability_id: 1,
+            project_id: None,
+            note: None,
+            created_at: Utc::now(),
+        })
+        .unwrap();
+        let policy = RetentionPolicy {
+            inactive_after: Duration::days(365),
+            grace_period: Duration::days(30),
+            action: RetentionAction::Anonymize,
+            exempt_usernames: HashSet::from(["bob".to_string()]),
+        };
+        // Privileged accounts are left alone however long they go unused
+        db.insert_user(fixtures::user("bob", &[])).unwrap();
+        db.insert_user(User {
+            role: Role::Moderator,
+            ..fixtures::user("carol", &[])
+        })
+        .unwrap();
+        let start = Utc::now();
+
+        // Activity is tracked from the first sweep
+        assert!(due(&db, &policy, start).unwrap().warn.is_empty());
+        let later = start + Duration::days(400);
+        let found = due(&db, &policy, later).unwrap();
+        assert_eq!(found.warn.len(), 1);
+        assert_eq!(found.warn[0].1, later + Duration::days(30));
+        mark_warned(&db, seeded.alice.id, later).unwrap();
+
+        // Logging in withdraws the warning
+        record_activity(&db, seeded.alice.id, later + Duration::days(1)).unwrap();
+        let found = due(&db, &policy, later + Duration::days(31)).unwrap();
+        assert!(found.warn.is_empty() && found.expire.is_empty());
+
+        let warned = later + Duration::days(400);
+        mark_warned(&db, seeded.alice.id, warned).unwrap();
+        let found = due(&db, &policy, warned + Duration::days(30)).unwrap();
+        assert_eq!(found.expire.len(), 1);
+        expire(&db, seeded.alice.id, policy.action, warned).unwrap();
+
+        let alice = db.get_user(seeded.alice.id).unwrap().unwrap();
+        assert_eq!(alice.username, format!("deleted-{}", alice.id));
+        assert!(alice.subscriptions.is_empty());
+        assert!(db.get_users_subscribed_to("serde").unwrap().is_empty());
+        assert!(db.get_timeline_events_by_user(alice.id).unwrap().is_empty());
+        assert!(db.get_projects_by_user(alice.id).unwrap().is_empty());
+        assert!(db.get_project_sync(project.id).unwrap().is_none());
+        assert!(
+            db.get_github_installations_by_user(alice.id)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(db.get_advisory_acks_by_user(alice.id).unwrap().is_empty());
+        // Anonymized accounts are left alone from then on
+        let found = due(&db, &policy, warned + Duration::days(1000)).unwrap();
+        assert!(found.warn.is_empty() && found.expire.is_empty());
+
+        expire(&db, alice.id, RetentionAction::Delete, warned).unwrap();
+        assert!(db.get_user(alice.id).unwrap().is_none());
+        assert!(db.get_account_activity(alice.id).unwrap().is_none());
+    }
+}
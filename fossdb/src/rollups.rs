@@ -0,0 +1,321 @@
+//! Daily counts of what happened across the catalog, so charts over long
+//! ranges don't have to scan every package, version and advisory
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use futures::StreamExt;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use crate::storage::Storage;
+use crate::websocket::TimelineBroadcaster;
+use crate::{DailyRollup, EventMetadata, EventType, RollupPoint, TimelineEvent};
+
+/// Longest range a series can cover
+pub const MAX_RANGE_DAYS: i64 = 3660;
+
+/// Width of the buckets a series is split into
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Granularity {
+    #[default]
+    Day,
+    /// Starting on Monday
+    Week,
+    Month,
+}
+
+impl Granularity {
+    /// First day of the bucket `day` falls in
+    fn bucket(self, day: NaiveDate) -> NaiveDate {
+        match self {
+            Granularity::Day => day,
+            Granularity::Week => day - Duration::days(day.weekday().num_days_from_monday() as i64),
+            Granularity::Month => day.with_day(1).unwrap(),
+        }
+    }
+}
+
+impl std::str::FromStr for Granularity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "day" => Ok(Self::Day),
+            "week" => Ok(Self::Week),
+            "month" => Ok(Self::Month),
+            _ => Err(format!("Unknown granularity: {}", s)),
+        }
+    }
+}
+
+/// The day something happened at, as rollups are keyed
+fn day_of(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d").to_string()
+}
+
+/// The rollup for the day of `at`, created empty if needed
+fn day(days: &mut BTreeMap<String, DailyRollup>, at: DateTime<Utc>) -> &mut DailyRollup {
+    days.entry(day_of(at))
+        .or_insert_with_key(|date| DailyRollup {
+            date: date.clone(),
+            ..Default::default()
+        })
+}
+
+/// Recount every day from the packages, versions and advisories stored,
+/// returning how many days had any activity
+pub fn rebuild(db: &dyn Storage) -> Result<usize> {
+    let mut days = BTreeMap::new();
+    db.for_each_package(&mut |package| {
+        day(&mut days, package.created_at).packages_added += 1;
+        Ok(())
+    })?;
+    db.for_each_version(&mut |version| {
+        day(&mut days, version.release_date).versions_released += 1;
+        Ok(())
+    })?;
+    db.for_each_vulnerability(&mut |vulnerability| {
+        day(&mut days, vulnerability.discovered_at).vulnerabilities_published += 1;
+        Ok(())
+    })?;
+
+    let count = days.len();
+    db.replace_daily_rollups(days.into_values().collect())?;
+    Ok(count)
+}
+
+/// How long an advisory is remembered once counted. Its alerts are all
+/// published together when it's created.
+const SEEN_FOR: Duration = Duration::hours(1);
+
+/// What `event` adds to its day, if anything, dated as [`rebuild`] dates
+/// what it's about. An advisory alerts every package it affects, so it's
+/// only counted the first time it's in `seen`.
+fn counted(
+    db: &dyn Storage,
+    event: &TimelineEvent,
+    seen: &mut HashMap<u64, DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Result<Option<DailyRollup>> {
+    let mut rollup = DailyRollup::default();
+    let at = match event.event_type {
+        EventType::PackageAdded => {
+            let Some(package) = db.get_package(event.package_id)? else {
+                return Ok(None);
+            };
+            rollup.packages_added = 1;
+            package.created_at
+        }
+        EventType::NewRelease => {
+            let Some(version) = db
+                .get_versions_by_package(event.package_id)?
+                .into_iter()
+                .find(|v| event.version.as_ref() == Some(&v.version))
+            else {
+                return Ok(None);
+            };
+            rollup.versions_released = 1;
+            version.release_date
+        }
+        EventType::SecurityAlert => {
+            let metadata = event.metadata.as_deref().and_then(EventMetadata::parse);
+            let Some(EventMetadata::Vulnerability {
+                vulnerability_id, ..
+            }) = metadata
+            else {
+                return Ok(None);
+            };
+            seen.retain(|_, counted_at| now - *counted_at < SEEN_FOR);
+            if seen.insert(vulnerability_id, now).is_some() {
+                return Ok(None);
+            }
+            let Some(vulnerability) = db.get_vulnerability(vulnerability_id)? else {
+                return Ok(None);
+            };
+            rollup.vulnerabilities_published = 1;
+            vulnerability.discovered_at
+        }
+        _ => return Ok(None),
+    };
+    rollup.date = day_of(at);
+    Ok(Some(rollup))
+}
+
+/// Count global timeline events into their days as they're broadcast. Events
+/// missed while falling behind are caught up on by the [`rebuild`] at the
+/// next start.
+pub fn spawn_maintainer(db: Arc<dyn Storage>, broadcaster: Arc<TimelineBroadcaster>) {
+    tokio::spawn(async move {
+        let mut seen = HashMap::new();
+        loop {
+            let mut events = Box::pin(broadcaster.global_events());
+            while let Some(event) = events.next().await {
+                let rollup = match counted(&*db, &event, &mut seen, Utc::now()) {
+                    Ok(Some(rollup)) => rollup,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        tracing::warn!("Failed to date event {} for rollups: {}", event.id, e);
+                        continue;
+                    }
+                };
+                if let Err(e) = db.add_daily_rollups(vec![rollup]) {
+                    tracing::warn!("Failed to count event {} into rollups: {}", event.id, e);
+                }
+            }
+            tracing::warn!("Rollup maintainer fell behind, some events were not counted");
+        }
+    });
+}
+
+/// Activity from `from` to `to` inclusive in buckets of `granularity`, empty
+/// ones included. A bucket starting before `from` only counts the days in
+/// the range.
+pub fn series(
+    db: &dyn Storage,
+    from: NaiveDate,
+    to: NaiveDate,
+    granularity: Granularity,
+) -> Result<Vec<RollupPoint>> {
+    let mut buckets: BTreeMap<NaiveDate, RollupPoint> = from
+        .iter_days()
+        .take_while(|day| *day <= to)
+        .map(|day| granularity.bucket(day))
+        .map(|start| {
+            let point = RollupPoint {
+                date: start.format("%Y-%m-%d").to_string(),
+                ..Default::default()
+            };
+            (start, point)
+        })
+        .collect();
+
+    let key = |day: NaiveDate| day.format("%Y-%m-%d").to_string();
+    for rollup in db.get_daily_rollups(&key(from), &key(to))? {
+        let Ok(day) = NaiveDate::parse_from_str(&rollup.date, "%Y-%m-%d") else {
+            continue;
+        };
+        if let Some(point) = buckets.get_mut(&granularity.bucket(day)) {
+            point.packages_added += rollup.packages_added;
+            point.versions_released += rollup.versions_released;
+            point.vulnerabilities_published += rollup.vulnerabilities_published;
+        }
+    }
+    Ok(buckets.into_values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::fixtures;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_rebuild_and_series() {
+        let db = Database::in_memory().unwrap();
+        fixtures::seed(&db).unwrap();
+        assert!(rebuild(&db).unwrap() > 0);
+
+        let all = series(
+            &db,
+            NaiveDate::from_ymd_opt(2000, 1, 1).unwrap(),
+            Utc::now().date_naive(),
+            Granularity::Month,
+        )
+        .unwrap();
+        let total = |f: fn(&RollupPoint) -> u64| all.iter().map(f).sum::<u64>();
+        assert_eq!(total(|p| p.packages_added), 3);
+        assert_eq!(
+            total(|p| p.versions_released) as usize,
+            db.get_all_versions().unwrap().len()
+        );
+        assert!(all.iter().all(|p| p.date.ends_with("-01")));
+
+        // Weeks start on Monday, and the range is covered without gaps
+        let from = NaiveDate::from_ymd_opt(2024, 3, 6).unwrap();
+        let to = NaiveDate::from_ymd_opt(2024, 3, 20).unwrap();
+        let weeks = series(&db, from, to, Granularity::Week).unwrap();
+        let dates: Vec<&str> = weeks.iter().map(|p| p.date.as_str()).collect();
+        assert_eq!(dates, ["2024-03-04", "2024-03-11", "2024-03-18"]);
+        assert_eq!(series(&db, from, to, Granularity::Day).unwrap().len(), 15);
+    }
+
+    #[test]
+    fn test_events_are_counted_once() {
+        let db = Database::in_memory().unwrap();
+        let seeded = fixtures::seed(&db).unwrap();
+        let discovered_at = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let vulnerability = db
+            .insert_vulnerability(crate::Vulnerability {
+                id: 0,
+                cve_id: None,
+                title: "Stack overflow".to_string(),
+                description: String::new(),
+                severity: crate::VulnerabilitySeverity::High,
+                affected_packages: Vec::new(),
+                discovered_at,
+                fixed_in: None,
+                cvss_vector: None,
+                cvss_score: None,
+                epss_score: None,
+                epss_percentile: None,
+                scores_updated_at: None,
+                informational: None,
+            })
+            .unwrap();
+        let alert = |package_id| TimelineEvent {
+            id: 0,
+            package_id,
+            user_id: None,
+            event_type: EventType::SecurityAlert,
+            package_name: String::new(),
+            version: None,
+            message: String::new(),
+            metadata: Some(
+                EventMetadata::Vulnerability {
+                    vulnerability_id: vulnerability.id,
+                    cve_id: None,
+                    version_range: "*".to_string(),
+                }
+                .to_json(),
+            ),
+            created_at: Utc::now(),
+            notified_at: None,
+        };
+        // Announced now, but counted on the day it was released as a rebuild
+        // would
+        let release = TimelineEvent {
+            event_type: EventType::NewRelease,
+            version: Some("1.40.0".to_string()),
+            metadata: None,
+            ..alert(seeded.tokio.id)
+        };
+
+        let now = Utc::now();
+        let mut seen = HashMap::new();
+        let counts: Vec<DailyRollup> = [
+            alert(seeded.serde.id),
+            alert(seeded.tokio.id),
+            release.clone(),
+            release,
+        ]
+        .iter()
+        .filter_map(|event| counted(&db, event, &mut seen, now).unwrap())
+        .collect();
+        assert_eq!(counts.len(), 3);
+        db.add_daily_rollups(counts).unwrap();
+
+        let rollups = db.get_daily_rollups("2024-01-01", "2024-12-31").unwrap();
+        let count = |date: &str| rollups.iter().find(|r| r.date == date).cloned();
+        assert_eq!(count("2024-01-01").unwrap().versions_released, 2);
+        assert_eq!(count("2024-05-01").unwrap().vulnerabilities_published, 1);
+
+        // Advisories are forgotten once their alerts have all gone out
+        let later = now + SEEN_FOR;
+        assert!(
+            counted(&db, &alert(seeded.serde.id), &mut seen, later)
+                .unwrap()
+                .is_some()
+        );
+        assert_eq!(seen.len(), 1);
+    }
+}
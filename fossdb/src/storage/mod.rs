@@ -0,0 +1,294 @@
+//! Persistence used by the server and collectors. [`crate::db::Database`] is
+//! the embedded implementation.
+use anyhow::Result;
+
+#[cfg(feature = "storage-postgres")]
+pub mod postgres;
+
+use crate::{
+    AccountActivity, AdvisoryAck, CollectorRun, CpeMapping, DailyRollup, GithubInstallation, Maintainer, Package, PackageAlias,
+    PackageRank, PackageReplacement, PackageSummary, PackageVersion, PendingPackage, Project, ProjectSync,
+    QuarantinedPackage, TimelineEvent, TimelineFilter, User, Vulnerability,
+};
+
+/// Inserts assign an ID to records whose ID is 0
+pub trait Storage: Send + Sync {
+    // Packages
+    fn insert_package(&self, package: Package) -> Result<Package>;
+    fn insert_packages(&self, packages: Vec<Package>) -> Result<Vec<Package>>;
+    fn get_package(&self, id: u64) -> Result<Option<Package>>;
+    fn get_package_by_name(&self, name: &str) -> Result<Option<Package>>;
+    /// Up to `limit` packages whose name starts with `prefix`, ordered by name
+    fn find_packages_with_prefix(&self, prefix: &str, limit: usize) -> Result<Vec<Package>>;
+    /// Visit every package without loading the table into memory
+    fn for_each_package(&self, f: &mut dyn FnMut(Package) -> Result<()>) -> Result<()>;
+    fn get_all_packages(&self) -> Result<Vec<Package>> {
+        let mut all = Vec::new();
+        self.for_each_package(&mut |record| {
+            all.push(record);
+            Ok(())
+        })?;
+        Ok(all)
+    }
+    /// False only if no package is named `name`, which backends may answer
+    /// without a lookup. Collectors use it to skip lookups for new names.
+    fn may_contain_package(&self, _name: &str) -> bool {
+        true
+    }
+    /// Visit every package as a summary. Backends that can read only those
+    /// fields should, rather than loading whole packages.
+    fn for_each_package_summary(&self, f: &mut dyn FnMut(PackageSummary) -> Result<()>) -> Result<()> {
+        self.for_each_package(&mut |package| f(package.into()))
+    }
+    /// Look up a package by its exact name, or by a name it had before a rename
+    fn find_package(&self, name: &str) -> Result<Option<Package>> {
+        if let Some(package) = self.get_package_by_name(name)? {
+            return Ok(Some(package));
+        }
+        match self.get_package_alias(name)? {
+            Some(alias) => self.get_package(alias.package_id),
+            None => Ok(None),
+        }
+    }
+    fn update_package(&self, package: Package) -> Result<()>;
+    fn update_packages(&self, packages: Vec<Package>) -> Result<()>;
+    /// Apply `f` to a package, retrying if it's changed concurrently. None if
+    /// there is no such package.
+    fn modify_package(&self, id: u64, f: &mut dyn FnMut(&mut Package)) -> Result<Option<Package>>;
+
+    // Aliases
+    /// Record a former name, replacing any alias with the same name
+    fn insert_package_alias(&self, alias: PackageAlias) -> Result<()>;
+    fn get_package_alias(&self, name: &str) -> Result<Option<PackageAlias>>;
+    fn get_package_aliases(&self, package_id: u64) -> Result<Vec<PackageAlias>>;
+
+    // Replacements
+    /// Point users of a package to its successor, replacing any earlier one
+    fn set_package_replacement(&self, replacement: PackageReplacement) -> Result<()>;
+    fn get_package_replacement(&self, package_id: u64) -> Result<Option<PackageReplacement>>;
+    /// Returns false if the package had no replacement
+    fn delete_package_replacement(&self, package_id: u64) -> Result<bool>;
+
+    // Ranking
+    fn get_package_rank(&self, package_id: u64) -> Result<Option<PackageRank>>;
+    fn get_all_package_ranks(&self) -> Result<Vec<PackageRank>>;
+    /// Insert or replace each rank
+    fn update_package_ranks(&self, ranks: Vec<PackageRank>) -> Result<()>;
+
+    // Analytics rollups
+    /// Days from `from` to `to` inclusive, both `YYYY-MM-DD`, oldest first.
+    /// Days nothing happened on are missing.
+    fn get_daily_rollups(&self, from: &str, to: &str) -> Result<Vec<DailyRollup>>;
+    /// Add each rollup's counts to those already recorded for its day
+    fn add_daily_rollups(&self, rollups: Vec<DailyRollup>) -> Result<()>;
+    /// Replace every rollup with a fresh count
+    fn replace_daily_rollups(&self, rollups: Vec<DailyRollup>) -> Result<()>;
+
+    // Versions
+    fn insert_version(&self, version: PackageVersion) -> Result<PackageVersion>;
+    fn insert_versions(&self, versions: Vec<PackageVersion>) -> Result<Vec<PackageVersion>>;
+    fn update_versions(&self, versions: Vec<PackageVersion>) -> Result<()>;
+    fn get_version(&self, id: u64) -> Result<Option<PackageVersion>>;
+    fn get_versions_by_package(&self, package_id: u64) -> Result<Vec<PackageVersion>>;
+    /// Visit every version without loading the table into memory
+    fn for_each_version(&self, f: &mut dyn FnMut(PackageVersion) -> Result<()>) -> Result<()>;
+    fn get_all_versions(&self) -> Result<Vec<PackageVersion>> {
+        let mut all = Vec::new();
+        self.for_each_version(&mut |record| {
+            all.push(record);
+            Ok(())
+        })?;
+        Ok(all)
+    }
+
+    // Users
+    fn insert_user(&self, user: User) -> Result<User>;
+    fn get_user(&self, id: u64) -> Result<Option<User>>;
+    fn get_user_by_email(&self, email: &str) -> Result<Option<User>>;
+    fn get_user_by_username(&self, username: &str) -> Result<Option<User>>;
+    /// Visit every user without loading the table into memory
+    fn for_each_user(&self, f: &mut dyn FnMut(User) -> Result<()>) -> Result<()>;
+    fn get_all_users(&self) -> Result<Vec<User>> {
+        let mut all = Vec::new();
+        self.for_each_user(&mut |record| {
+            all.push(record);
+            Ok(())
+        })?;
+        Ok(all)
+    }
+    fn update_user(&self, user: User) -> Result<()>;
+    /// Apply `f` to a user, retrying if they're changed concurrently. None if
+    /// there is no such user.
+    fn modify_user(&self, id: u64, f: &mut dyn FnMut(&mut User)) -> Result<Option<User>>;
+    /// Remove a user with their subscriptions, projects, GitHub installations
+    /// and personal timeline, returning whether they existed
+    fn delete_user(&self, id: u64) -> Result<bool>;
+    /// Replace a user with an anonymized `user` of the same ID, removing
+    /// everything `delete_user` would except their account activity. Returns
+    /// whether they existed.
+    fn anonymize_user(&self, user: User) -> Result<bool>;
+    fn get_account_activity(&self, user_id: u64) -> Result<Option<AccountActivity>>;
+    fn get_all_account_activity(&self) -> Result<Vec<AccountActivity>>;
+    /// Insert or replace a user's activity
+    fn set_account_activity(&self, activity: AccountActivity) -> Result<()>;
+
+    // Vulnerabilities
+    fn insert_vulnerability(&self, vulnerability: Vulnerability) -> Result<Vulnerability>;
+    fn get_vulnerability(&self, id: u64) -> Result<Option<Vulnerability>>;
+    /// Vulnerabilities with any of `ids`, skipping those that don't exist
+    fn get_vulnerabilities(&self, ids: &[u64]) -> Result<Vec<Vulnerability>>;
+    /// Visit every vulnerability without loading the table into memory
+    fn for_each_vulnerability(&self, f: &mut dyn FnMut(Vulnerability) -> Result<()>) -> Result<()>;
+    fn get_all_vulnerabilities(&self) -> Result<Vec<Vulnerability>> {
+        let mut all = Vec::new();
+        self.for_each_vulnerability(&mut |record| {
+            all.push(record);
+            Ok(())
+        })?;
+        Ok(all)
+    }
+    fn update_vulnerability(&self, vulnerability: Vulnerability) -> Result<()>;
+    /// Delete a vulnerability with its acknowledgements, returning whether
+    /// it existed
+    fn delete_vulnerability(&self, id: u64) -> Result<bool>;
+
+    // Timeline
+    /// Insert an event unless an equivalent one exists, see
+    /// [`TimelineEvent::dedup_key`]. Returns None for duplicates.
+    fn insert_timeline_event(&self, event: TimelineEvent) -> Result<Option<TimelineEvent>>;
+    fn get_timeline_event(&self, id: u64) -> Result<Option<TimelineEvent>>;
+    /// Visit every timeline event without loading the table into memory
+    fn for_each_timeline_event(&self, f: &mut dyn FnMut(TimelineEvent) -> Result<()>) -> Result<()>;
+    fn get_all_timeline_events(&self) -> Result<Vec<TimelineEvent>> {
+        let mut all = Vec::new();
+        self.for_each_timeline_event(&mut |record| {
+            all.push(record);
+            Ok(())
+        })?;
+        Ok(all)
+    }
+    fn get_timeline_by_package(&self, package_id: u64) -> Result<Vec<TimelineEvent>>;
+    fn get_timeline_events_by_user(&self, user_id: u64) -> Result<Vec<TimelineEvent>>;
+    /// Delete a user's personal events, returning how many there were
+    fn delete_timeline_events_by_user(&self, user_id: u64) -> Result<usize>;
+    /// Events matching `filter`, read through the package or user index
+    /// when the filter names one. The event type and `since` are checked in
+    /// memory, which is cheap for one user's or package's timeline but scans
+    /// every event when neither is given. Backends that can filter in the
+    /// query itself override this.
+    fn query_timeline(&self, filter: &TimelineFilter) -> Result<Vec<TimelineEvent>> {
+        let mut events = match (filter.package_id, filter.user_id) {
+            (Some(package_id), _) => self.get_timeline_by_package(package_id)?,
+            (None, Some(user_id)) => self.get_timeline_events_by_user(user_id)?,
+            (None, None) => self.get_all_timeline_events()?,
+        };
+        events.retain(|event| filter.matches(event));
+        Ok(events)
+    }
+    fn update_timeline_event(&self, event: TimelineEvent) -> Result<()>;
+    /// Events that should still be emailed to their recipient
+    fn get_pending_notifications(&self) -> Result<Vec<TimelineEvent>>;
+    /// Returns the number of events deleted
+    fn purge_old_timeline_events(&self, older_than: chrono::Duration) -> Result<usize>;
+
+    // Subscriptions
+    fn get_subscriber_count(&self, package_name: &str) -> Result<u64>;
+    /// Users subscribed to a package with notifications enabled
+    fn get_users_subscribed_to(&self, package_name: &str) -> Result<Vec<u64>>;
+
+    // Projects
+    fn insert_project(&self, project: Project) -> Result<Project>;
+    fn get_project(&self, id: u64) -> Result<Option<Project>>;
+    fn get_projects_by_user(&self, user_id: u64) -> Result<Vec<Project>>;
+    fn update_project(&self, project: Project) -> Result<()>;
+    /// Returns whether the project existed
+    fn delete_project(&self, id: u64) -> Result<bool>;
+    /// Projects with any version of `package_name` among their dependencies
+    fn get_projects_depending_on(&self, package_name: &str) -> Result<Vec<Project>>;
+
+    // GitHub App
+    /// Store an installation, replacing any with the same ID
+    fn upsert_github_installation(&self, installation: GithubInstallation) -> Result<()>;
+    fn get_github_installation(&self, id: u64) -> Result<Option<GithubInstallation>>;
+    fn get_github_installations_by_user(&self, user_id: u64) -> Result<Vec<GithubInstallation>>;
+    /// Remove an installation along with the project syncs through it,
+    /// returning whether it existed
+    fn delete_github_installation(&self, id: u64) -> Result<bool>;
+    /// Store a project's sync, replacing any it had
+    fn set_project_sync(&self, sync: ProjectSync) -> Result<()>;
+    fn get_project_sync(&self, project_id: u64) -> Result<Option<ProjectSync>>;
+    fn get_project_syncs_by_repository(&self, repository: &str) -> Result<Vec<ProjectSync>>;
+    fn delete_project_sync(&self, project_id: u64) -> Result<bool>;
+
+    // Advisory acknowledgements
+    /// Store an acknowledgement, replacing the user's existing one for the
+    /// same vulnerability and project
+    fn acknowledge_advisory(&self, ack: AdvisoryAck) -> Result<AdvisoryAck>;
+    fn get_advisory_acks_by_user(&self, user_id: u64) -> Result<Vec<AdvisoryAck>>;
+    /// Returns whether the acknowledgement existed
+    fn delete_advisory_ack(&self, id: u64) -> Result<bool>;
+
+    // CPE mappings
+    /// Store a mapping, replacing any other for the same `vendor_product`
+    fn insert_cpe_mapping(&self, mapping: CpeMapping) -> Result<CpeMapping>;
+    fn get_cpe_mapping(&self, vendor_product: &str) -> Result<Option<CpeMapping>>;
+    fn get_all_cpe_mappings(&self) -> Result<Vec<CpeMapping>>;
+    fn delete_cpe_mapping(&self, id: u64) -> Result<bool>;
+
+    // Maintainers
+    /// Insert maintainers or update those with a known `handle`, returning
+    /// them with their IDs
+    fn upsert_maintainers(&self, maintainers: Vec<Maintainer>) -> Result<Vec<Maintainer>>;
+    fn get_maintainer(&self, id: u64) -> Result<Option<Maintainer>>;
+    fn get_maintainer_by_handle(&self, handle: &str) -> Result<Option<Maintainer>>;
+    fn get_all_maintainers(&self) -> Result<Vec<Maintainer>>;
+    /// Replace the maintainers of each package
+    fn set_package_maintainers(&self, links: Vec<(u64, Vec<u64>)>) -> Result<()>;
+    fn get_package_maintainers(&self, package_id: u64) -> Result<Vec<Maintainer>>;
+    fn get_packages_by_maintainer(&self, maintainer_id: u64) -> Result<Vec<Package>>;
+
+    // Quarantine
+    /// Record rejected packages. A package already quarantined under the same
+    /// name keeps its ID, `admitted` flag and `first_seen`.
+    fn quarantine_packages(&self, packages: Vec<QuarantinedPackage>) -> Result<()>;
+    fn get_quarantined_package(&self, id: u64) -> Result<Option<QuarantinedPackage>>;
+    fn get_all_quarantined_packages(&self) -> Result<Vec<QuarantinedPackage>>;
+    fn update_quarantined_package(&self, package: QuarantinedPackage) -> Result<()>;
+    fn delete_quarantined_package(&self, id: u64) -> Result<bool>;
+
+    // Moderation
+    /// Hide packages from the public API until they're approved
+    fn hold_packages(&self, package_ids: Vec<u64>) -> Result<()>;
+    /// Insert a package already awaiting approval, so it's never public
+    fn insert_pending_package(&self, package: Package) -> Result<Package>;
+    fn get_pending_packages(&self) -> Result<Vec<PendingPackage>>;
+    fn is_package_pending(&self, package_id: u64) -> Result<bool>;
+    /// Returns how many of the packages were pending
+    fn approve_packages(&self, package_ids: Vec<u64>) -> Result<usize>;
+    /// IDs of packages awaiting approval, for filtering listings
+    fn pending_package_ids(&self) -> Result<std::collections::HashSet<u64>> {
+        Ok(self.get_pending_packages()?.into_iter().map(|p| p.package_id).collect())
+    }
+    /// Hide a looked up package if it's awaiting approval
+    fn published(&self, package: Option<Package>) -> Result<Option<Package>> {
+        match package {
+            Some(package) if self.is_package_pending(package.id)? => Ok(None),
+            package => Ok(package),
+        }
+    }
+
+    // Collector runs
+    fn insert_collector_run(&self, run: CollectorRun) -> Result<CollectorRun>;
+    /// Runs of one collector, oldest first
+    fn get_collector_runs(&self, collector: &str) -> Result<Vec<CollectorRun>>;
+    fn get_all_collector_runs(&self) -> Result<Vec<CollectorRun>>;
+
+    // Leases
+    /// Claim `name` for `ttl`, or extend the claim if `holder` already has it.
+    /// False while another holder's lease is unexpired
+    fn acquire_lease(&self, name: &str, holder: &str, ttl: chrono::Duration) -> Result<bool>;
+    /// Give up a lease early. Does nothing if `holder` no longer has it
+    fn release_lease(&self, name: &str, holder: &str) -> Result<()>;
+    /// Remove expired leases, returning how many were removed
+    fn cleanup_expired_leases(&self) -> Result<usize>;
+}
@@ -0,0 +1,1644 @@
+//! Storage in a shared PostgreSQL database, so several server replicas and
+//! collectors can run against the same data.
+use anyhow::Result;
+use futures::TryStreamExt;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use sqlx::postgres::{PgConnection, PgPool, PgPoolOptions};
+use sqlx::types::Json;
+use std::future::Future;
+
+use super::Storage;
+use crate::{
+    AccountActivity, AdvisoryAck, CollectorRun, CpeMapping, DailyRollup, GithubInstallation, Maintainer, Package, PackageAlias,
+    PackageRank, PackageReplacement, PackageSummary, PackageVersion, PendingPackage, Project, ProjectSync,
+    QuarantinedPackage, TimelineEvent, TimelineFilter, User, Vulnerability,
+};
+
+const MAX_CONNECTIONS: u32 = 16;
+
+/// Tables with an ID sequence
+const TABLES: [&str; 11] = [
+    "packages",
+    "package_versions",
+    "users",
+    "vulnerabilities",
+    "timeline_events",
+    "cpe_mappings",
+    "maintainers",
+    "quarantined_packages",
+    "collector_runs",
+    "projects",
+    "advisory_acks",
+];
+
+/// Run a query from the synchronous [`Storage`] API. Requires the
+/// multi-threaded tokio runtime, which the server and CLI both use.
+fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::task::block_in_place(|| tokio::runtime::Handle::current().block_on(future))
+}
+
+/// A record kept in its own table as JSON
+trait Row: Serialize + DeserializeOwned + Clone + Send + Unpin + 'static {
+    const TABLE: &'static str;
+
+    fn id(&self) -> u64;
+    fn set_id(&mut self, id: u64);
+
+    /// Insert or replace the row, along with anything derived from it
+    async fn upsert(&self, conn: &mut PgConnection) -> Result<()>;
+}
+
+impl Row for Package {
+    const TABLE: &'static str = "packages";
+
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn set_id(&mut self, id: u64) {
+        self.id = id;
+    }
+
+    async fn upsert(&self, conn: &mut PgConnection) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO packages (id, name, data) VALUES ($1, $2, $3)
+             ON CONFLICT (id) DO UPDATE SET name = EXCLUDED.name, data = EXCLUDED.data",
+        )
+        .bind(self.id as i64)
+        .bind(&self.name)
+        .bind(Json(self))
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
+
+impl Row for PackageVersion {
+    const TABLE: &'static str = "package_versions";
+
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn set_id(&mut self, id: u64) {
+        self.id = id;
+    }
+
+    async fn upsert(&self, conn: &mut PgConnection) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO package_versions (id, package_id, data) VALUES ($1, $2, $3)
+             ON CONFLICT (id) DO UPDATE SET package_id = EXCLUDED.package_id, data = EXCLUDED.data",
+        )
+        .bind(self.id as i64)
+        .bind(self.package_id as i64)
+        .bind(Json(self))
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
+
+impl Row for User {
+    const TABLE: &'static str = "users";
+
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn set_id(&mut self, id: u64) {
+        self.id = id;
+    }
+
+    async fn upsert(&self, conn: &mut PgConnection) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO users (id, email, username, data) VALUES ($1, $2, $3, $4)
+             ON CONFLICT (id) DO UPDATE
+             SET email = EXCLUDED.email, username = EXCLUDED.username, data = EXCLUDED.data",
+        )
+        .bind(self.id as i64)
+        .bind(&self.email)
+        .bind(&self.username)
+        .bind(Json(self))
+        .execute(&mut *conn)
+        .await?;
+
+        // Nothing already queued is sent to users who opt out
+        if !self.notifications_enabled {
+            sqlx::query(
+                "UPDATE timeline_events SET awaits_notification = FALSE
+                 WHERE user_id = $1 AND awaits_notification",
+            )
+            .bind(self.id as i64)
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        sqlx::query("DELETE FROM subscriptions WHERE user_id = $1")
+            .bind(self.id as i64)
+            .execute(&mut *conn)
+            .await?;
+        for subscription in &self.subscriptions {
+            sqlx::query(
+                "INSERT INTO subscriptions (user_id, package_name, notifications_enabled)
+                 VALUES ($1, $2, $3)",
+            )
+            .bind(self.id as i64)
+            .bind(&subscription.package_name)
+            .bind(subscription.notifications_enabled)
+            .execute(&mut *conn)
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+impl Row for Vulnerability {
+    const TABLE: &'static str = "vulnerabilities";
+
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn set_id(&mut self, id: u64) {
+        self.id = id;
+    }
+
+    async fn upsert(&self, conn: &mut PgConnection) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO vulnerabilities (id, data) VALUES ($1, $2)
+             ON CONFLICT (id) DO UPDATE SET data = EXCLUDED.data",
+        )
+        .bind(self.id as i64)
+        .bind(Json(self))
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
+
+impl Row for TimelineEvent {
+    const TABLE: &'static str = "timeline_events";
+
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn set_id(&mut self, id: u64) {
+        self.id = id;
+    }
+
+    async fn upsert(&self, conn: &mut PgConnection) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO timeline_events
+                 (id, package_id, user_id, awaits_notification, created_at, data)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (id) DO UPDATE SET
+                 package_id = EXCLUDED.package_id,
+                 user_id = EXCLUDED.user_id,
+                 awaits_notification = EXCLUDED.awaits_notification,
+                 created_at = EXCLUDED.created_at,
+                 data = EXCLUDED.data",
+        )
+        .bind(self.id as i64)
+        .bind(self.package_id as i64)
+        .bind(self.user_id.map(|id| id as i64))
+        .bind(self.awaits_notification())
+        .bind(self.created_at)
+        .bind(Json(self))
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
+
+impl Row for CpeMapping {
+    const TABLE: &'static str = "cpe_mappings";
+
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn set_id(&mut self, id: u64) {
+        self.id = id;
+    }
+
+    async fn upsert(&self, conn: &mut PgConnection) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO cpe_mappings (id, vendor_product, package_id, data) VALUES ($1, $2, $3, $4)
+             ON CONFLICT (id) DO UPDATE SET
+                 vendor_product = EXCLUDED.vendor_product,
+                 package_id = EXCLUDED.package_id,
+                 data = EXCLUDED.data",
+        )
+        .bind(self.id as i64)
+        .bind(&self.vendor_product)
+        .bind(self.package_id as i64)
+        .bind(Json(self))
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
+
+impl Row for Maintainer {
+    const TABLE: &'static str = "maintainers";
+
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn set_id(&mut self, id: u64) {
+        self.id = id;
+    }
+
+    async fn upsert(&self, conn: &mut PgConnection) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO maintainers (id, handle, data) VALUES ($1, $2, $3)
+             ON CONFLICT (id) DO UPDATE SET handle = EXCLUDED.handle, data = EXCLUDED.data",
+        )
+        .bind(self.id as i64)
+        .bind(&self.handle)
+        .bind(Json(self))
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
+
+impl Row for QuarantinedPackage {
+    const TABLE: &'static str = "quarantined_packages";
+
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn set_id(&mut self, id: u64) {
+        self.id = id;
+    }
+
+    async fn upsert(&self, conn: &mut PgConnection) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO quarantined_packages (id, name, data) VALUES ($1, $2, $3)
+             ON CONFLICT (id) DO UPDATE SET name = EXCLUDED.name, data = EXCLUDED.data",
+        )
+        .bind(self.id as i64)
+        .bind(&self.name)
+        .bind(Json(self))
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
+
+impl Row for CollectorRun {
+    const TABLE: &'static str = "collector_runs";
+
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn set_id(&mut self, id: u64) {
+        self.id = id;
+    }
+
+    async fn upsert(&self, conn: &mut PgConnection) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO collector_runs (id, collector, data) VALUES ($1, $2, $3)
+             ON CONFLICT (id) DO UPDATE SET collector = EXCLUDED.collector, data = EXCLUDED.data",
+        )
+        .bind(self.id as i64)
+        .bind(&self.collector)
+        .bind(Json(self))
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
+
+impl Row for Project {
+    const TABLE: &'static str = "projects";
+
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn set_id(&mut self, id: u64) {
+        self.id = id;
+    }
+
+    async fn upsert(&self, conn: &mut PgConnection) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO projects (id, user_id, data) VALUES ($1, $2, $3)
+             ON CONFLICT (id) DO UPDATE SET user_id = EXCLUDED.user_id, data = EXCLUDED.data",
+        )
+        .bind(self.id as i64)
+        .bind(self.user_id as i64)
+        .bind(Json(self))
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query("DELETE FROM project_dependencies WHERE project_id = $1")
+            .bind(self.id as i64)
+            .execute(&mut *conn)
+            .await?;
+        for edge in crate::ProjectDependency::from_project(self) {
+            sqlx::query(
+                "INSERT INTO project_dependencies (project_id, package_name) VALUES ($1, $2)",
+            )
+            .bind(self.id as i64)
+            .bind(&edge.package_name)
+            .execute(&mut *conn)
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+impl Row for AdvisoryAck {
+    const TABLE: &'static str = "advisory_acks";
+
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn set_id(&mut self, id: u64) {
+        self.id = id;
+    }
+
+    async fn upsert(&self, conn: &mut PgConnection) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO advisory_acks (id, user_id, vulnerability_id, project_id, data)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (id) DO UPDATE
+             SET user_id = EXCLUDED.user_id,
+                 vulnerability_id = EXCLUDED.vulnerability_id,
+                 project_id = EXCLUDED.project_id,
+                 data = EXCLUDED.data",
+        )
+        .bind(self.id as i64)
+        .bind(self.user_id as i64)
+        .bind(self.vulnerability_id as i64)
+        .bind(self.project_id.map(|id| id as i64))
+        .bind(Json(self))
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
+
+pub struct PostgresStorage {
+    pool: PgPool,
+}
+
+impl PostgresStorage {
+    /// Connect to `url` and bring the schema up to date
+    pub async fn connect(url: &str) -> Result<Self> {
+        let pool = PgPoolOptions::new()
+            .max_connections(MAX_CONNECTIONS)
+            .connect(url)
+            .await?;
+        sqlx::migrate!().run(&pool).await?;
+
+        // Rows imported with explicit IDs don't advance the sequences
+        for table in TABLES {
+            sqlx::query(&format!(
+                "SELECT setval('{table}_id_seq', GREATEST(used, 1), used > 0) FROM (
+                     SELECT GREATEST(
+                         (SELECT COALESCE(MAX(id), 0) FROM {table}),
+                         (SELECT CASE WHEN is_called THEN last_value ELSE 0 END
+                          FROM {table}_id_seq)
+                     ) AS used
+                 ) AS sequence"
+            ))
+            .execute(&pool)
+            .await?;
+        }
+
+        Ok(Self { pool })
+    }
+
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    async fn next_id<T: Row>(&self) -> Result<u64> {
+        let id: i64 = sqlx::query_scalar(&format!("SELECT nextval('{}_id_seq')", T::TABLE))
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(id as u64)
+    }
+
+    async fn get<T: Row>(&self, id: u64) -> Result<Option<T>> {
+        let row: Option<Json<T>> =
+            sqlx::query_scalar(&format!("SELECT data FROM {} WHERE id = $1", T::TABLE))
+                .bind(id as i64)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(row.map(|Json(record)| record))
+    }
+
+    async fn get_many<T: Row>(&self, ids: &[u64]) -> Result<Vec<T>> {
+        let ids: Vec<i64> = ids.iter().map(|&id| id as i64).collect();
+        let rows: Vec<Json<T>> = sqlx::query_scalar(&format!(
+            "SELECT data FROM {} WHERE id = ANY($1) ORDER BY id",
+            T::TABLE
+        ))
+        .bind(&ids)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(|Json(record)| record).collect())
+    }
+
+    async fn get_all<T: Row>(&self) -> Result<Vec<T>> {
+        let rows: Vec<Json<T>> =
+            sqlx::query_scalar(&format!("SELECT data FROM {} ORDER BY id", T::TABLE))
+                .fetch_all(&self.pool)
+                .await?;
+        Ok(rows.into_iter().map(|Json(record)| record).collect())
+    }
+
+    /// Stream every record in ID order
+    async fn for_each<T: Row>(&self, f: &mut dyn FnMut(T) -> Result<()>) -> Result<()> {
+        let query = format!("SELECT data FROM {} ORDER BY id", T::TABLE);
+        let mut rows = sqlx::query_scalar::<_, Json<T>>(&query).fetch(&self.pool);
+        while let Some(Json(record)) = rows.try_next().await? {
+            f(record)?;
+        }
+        Ok(())
+    }
+
+    /// Records whose BIGINT `column` equals `value`
+    async fn get_by<T: Row>(&self, column: &str, value: i64) -> Result<Vec<T>> {
+        let rows: Vec<Json<T>> = sqlx::query_scalar(&format!(
+            "SELECT data FROM {} WHERE {} = $1 ORDER BY id",
+            T::TABLE,
+            column
+        ))
+        .bind(value)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(|Json(record)| record).collect())
+    }
+
+    async fn insert_all<T: Row>(&self, records: Vec<T>) -> Result<Vec<T>> {
+        let mut saved = Vec::with_capacity(records.len());
+        for mut record in records {
+            if record.id() == 0 {
+                record.set_id(self.next_id::<T>().await?);
+            }
+            saved.push(record);
+        }
+
+        self.update_all(&saved).await?;
+        Ok(saved)
+    }
+
+    async fn update_all<T: Row>(&self, records: &[T]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for record in records {
+            record.upsert(&mut tx).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Apply `f` with the row locked, so concurrent changes wait their turn
+    async fn modify<T: Row + PartialEq>(
+        &self,
+        id: u64,
+        f: &mut dyn FnMut(&mut T),
+    ) -> Result<Option<T>> {
+        let mut tx = self.pool.begin().await?;
+        let row: Option<Json<T>> = sqlx::query_scalar(&format!(
+            "SELECT data FROM {} WHERE id = $1 FOR UPDATE",
+            T::TABLE
+        ))
+        .bind(id as i64)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(Json(current)) = row else {
+            return Ok(None);
+        };
+
+        let mut updated = current.clone();
+        f(&mut updated);
+        if updated != current {
+            updated.upsert(&mut tx).await?;
+        }
+        tx.commit().await?;
+        Ok(Some(updated))
+    }
+
+    async fn insert_timeline_event_async(
+        &self,
+        mut event: TimelineEvent,
+    ) -> Result<Option<TimelineEvent>> {
+        let mut tx = self.pool.begin().await?;
+
+        // Serialise inserts per package so two replicas can't both add the
+        // same release
+        sqlx::query("SELECT pg_advisory_xact_lock($1)")
+            .bind(event.package_id as i64)
+            .execute(&mut *tx)
+            .await?;
+
+        if let Some(version) = &event.version {
+            // Event types are stored as serde names inside the JSON
+            let event_type = serde_json::to_value(&event.event_type)?;
+            let duplicate: bool = sqlx::query_scalar(
+                "SELECT EXISTS (
+                     SELECT 1 FROM timeline_events
+                     WHERE package_id = $1
+                       AND data->>'event_type' = $2
+                       AND data->>'version' = $3
+                       AND user_id IS NOT DISTINCT FROM $4
+                 )",
+            )
+            .bind(event.package_id as i64)
+            .bind(event_type.as_str())
+            .bind(version)
+            .bind(event.user_id.map(|id| id as i64))
+            .fetch_one(&mut *tx)
+            .await?;
+            if duplicate {
+                return Ok(None);
+            }
+        }
+
+        if event.id == 0 {
+            event.id = self.next_id::<TimelineEvent>().await?;
+        }
+        event.upsert(&mut tx).await?;
+        tx.commit().await?;
+        Ok(Some(event))
+    }
+}
+
+impl Storage for PostgresStorage {
+    fn insert_package(&self, package: Package) -> Result<Package> {
+        block_on(self.insert_all(vec![package])).map(|mut saved| saved.remove(0))
+    }
+
+    fn insert_packages(&self, packages: Vec<Package>) -> Result<Vec<Package>> {
+        block_on(self.insert_all(packages))
+    }
+
+    fn get_package(&self, id: u64) -> Result<Option<Package>> {
+        block_on(self.get(id))
+    }
+
+    fn get_package_by_name(&self, name: &str) -> Result<Option<Package>> {
+        block_on(async {
+            let row: Option<Json<Package>> =
+                sqlx::query_scalar("SELECT data FROM packages WHERE name = $1")
+                    .bind(name)
+                    .fetch_optional(&self.pool)
+                    .await?;
+            Ok(row.map(|Json(package)| package))
+        })
+    }
+
+    fn find_packages_with_prefix(&self, prefix: &str, limit: usize) -> Result<Vec<Package>> {
+        block_on(async {
+            let pattern = format!(
+                "{}%",
+                prefix.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+            );
+            // Byte order, like the embedded database
+            let rows: Vec<Json<Package>> = sqlx::query_scalar(
+                "SELECT data FROM packages WHERE name LIKE $1 ORDER BY name COLLATE \"C\" LIMIT $2",
+            )
+            .bind(pattern)
+            .bind(limit as i64)
+            .fetch_all(&self.pool)
+            .await?;
+            Ok(rows.into_iter().map(|Json(package)| package).collect())
+        })
+    }
+
+    fn for_each_package(&self, f: &mut dyn FnMut(Package) -> Result<()>) -> Result<()> {
+        block_on(self.for_each(f))
+    }
+
+    fn for_each_package_summary(&self, f: &mut dyn FnMut(PackageSummary) -> Result<()>) -> Result<()> {
+        block_on(async {
+            let mut rows = sqlx::query_scalar::<_, Json<PackageSummary>>(
+                "SELECT jsonb_build_object(
+                     'id', id,
+                     'name', name,
+                     'description', data->'description',
+                     'repository', data->'repository',
+                     'license', data->'license',
+                     'platform', data->'platform',
+                     'language', data->'language',
+                     'rank', data->'rank'
+                 ) FROM packages ORDER BY id",
+            )
+            .fetch(&self.pool);
+            while let Some(Json(summary)) = rows.try_next().await? {
+                f(summary)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn update_package(&self, package: Package) -> Result<()> {
+        block_on(self.update_all(&[package]))
+    }
+
+    fn update_packages(&self, packages: Vec<Package>) -> Result<()> {
+        block_on(self.update_all(&packages))
+    }
+
+    fn modify_package(&self, id: u64, f: &mut dyn FnMut(&mut Package)) -> Result<Option<Package>> {
+        block_on(self.modify(id, f))
+    }
+
+    fn insert_package_alias(&self, alias: PackageAlias) -> Result<()> {
+        block_on(async {
+            sqlx::query(
+                "INSERT INTO package_aliases (name, package_id, data) VALUES ($1, $2, $3)
+                 ON CONFLICT (name) DO UPDATE
+                 SET package_id = EXCLUDED.package_id, data = EXCLUDED.data",
+            )
+            .bind(&alias.name)
+            .bind(alias.package_id as i64)
+            .bind(Json(&alias))
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        })
+    }
+
+    fn get_package_alias(&self, name: &str) -> Result<Option<PackageAlias>> {
+        block_on(async {
+            let row: Option<Json<PackageAlias>> =
+                sqlx::query_scalar("SELECT data FROM package_aliases WHERE name = $1")
+                    .bind(name)
+                    .fetch_optional(&self.pool)
+                    .await?;
+            Ok(row.map(|Json(alias)| alias))
+        })
+    }
+
+    fn get_package_aliases(&self, package_id: u64) -> Result<Vec<PackageAlias>> {
+        block_on(async {
+            let rows: Vec<Json<PackageAlias>> = sqlx::query_scalar(
+                "SELECT data FROM package_aliases WHERE package_id = $1 ORDER BY name",
+            )
+            .bind(package_id as i64)
+            .fetch_all(&self.pool)
+            .await?;
+            Ok(rows.into_iter().map(|Json(alias)| alias).collect())
+        })
+    }
+
+    fn set_package_replacement(&self, replacement: PackageReplacement) -> Result<()> {
+        block_on(async {
+            sqlx::query(
+                "INSERT INTO package_replacements (package_id, replacement_id, data)
+                 VALUES ($1, $2, $3)
+                 ON CONFLICT (package_id) DO UPDATE
+                 SET replacement_id = EXCLUDED.replacement_id, data = EXCLUDED.data",
+            )
+            .bind(replacement.package_id as i64)
+            .bind(replacement.replacement_id as i64)
+            .bind(Json(&replacement))
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        })
+    }
+
+    fn get_package_replacement(&self, package_id: u64) -> Result<Option<PackageReplacement>> {
+        block_on(async {
+            let row: Option<Json<PackageReplacement>> = sqlx::query_scalar(
+                "SELECT data FROM package_replacements WHERE package_id = $1",
+            )
+            .bind(package_id as i64)
+            .fetch_optional(&self.pool)
+            .await?;
+            Ok(row.map(|Json(replacement)| replacement))
+        })
+    }
+
+    fn delete_package_replacement(&self, package_id: u64) -> Result<bool> {
+        block_on(async {
+            let result = sqlx::query("DELETE FROM package_replacements WHERE package_id = $1")
+                .bind(package_id as i64)
+                .execute(&self.pool)
+                .await?;
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    fn get_package_rank(&self, package_id: u64) -> Result<Option<PackageRank>> {
+        block_on(async {
+            let row: Option<Json<PackageRank>> =
+                sqlx::query_scalar("SELECT data FROM package_ranks WHERE package_id = $1")
+                    .bind(package_id as i64)
+                    .fetch_optional(&self.pool)
+                    .await?;
+            Ok(row.map(|Json(rank)| rank))
+        })
+    }
+
+    fn get_all_package_ranks(&self) -> Result<Vec<PackageRank>> {
+        block_on(async {
+            let rows: Vec<Json<PackageRank>> =
+                sqlx::query_scalar("SELECT data FROM package_ranks ORDER BY package_id")
+                    .fetch_all(&self.pool)
+                    .await?;
+            Ok(rows.into_iter().map(|Json(rank)| rank).collect())
+        })
+    }
+
+    fn update_package_ranks(&self, ranks: Vec<PackageRank>) -> Result<()> {
+        block_on(async {
+            let mut tx = self.pool.begin().await?;
+            for rank in &ranks {
+                sqlx::query(
+                    "INSERT INTO package_ranks (package_id, score, data) VALUES ($1, $2, $3)
+                     ON CONFLICT (package_id) DO UPDATE
+                     SET score = EXCLUDED.score, data = EXCLUDED.data",
+                )
+                .bind(rank.package_id as i64)
+                .bind(rank.score as i32)
+                .bind(Json(rank))
+                .execute(&mut *tx)
+                .await?;
+            }
+            tx.commit().await?;
+            Ok(())
+        })
+    }
+
+    fn get_daily_rollups(&self, from: &str, to: &str) -> Result<Vec<DailyRollup>> {
+        block_on(async {
+            let rows: Vec<(String, i64, i64, i64)> = sqlx::query_as(
+                "SELECT date, packages_added, versions_released, vulnerabilities_published
+                 FROM daily_rollups WHERE date BETWEEN $1 AND $2 ORDER BY date",
+            )
+            .bind(from)
+            .bind(to)
+            .fetch_all(&self.pool)
+            .await?;
+            Ok(rows
+                .into_iter()
+                .map(|(date, packages, versions, vulnerabilities)| DailyRollup {
+                    date,
+                    packages_added: packages as u64,
+                    versions_released: versions as u64,
+                    vulnerabilities_published: vulnerabilities as u64,
+                })
+                .collect())
+        })
+    }
+
+    fn add_daily_rollups(&self, rollups: Vec<DailyRollup>) -> Result<()> {
+        block_on(async {
+            let mut tx = self.pool.begin().await?;
+            for rollup in &rollups {
+                sqlx::query(
+                    "INSERT INTO daily_rollups
+                         (date, packages_added, versions_released, vulnerabilities_published)
+                     VALUES ($1, $2, $3, $4)
+                     ON CONFLICT (date) DO UPDATE SET
+                         packages_added = daily_rollups.packages_added + EXCLUDED.packages_added,
+                         versions_released =
+                             daily_rollups.versions_released + EXCLUDED.versions_released,
+                         vulnerabilities_published = daily_rollups.vulnerabilities_published
+                             + EXCLUDED.vulnerabilities_published",
+                )
+                .bind(&rollup.date)
+                .bind(rollup.packages_added as i64)
+                .bind(rollup.versions_released as i64)
+                .bind(rollup.vulnerabilities_published as i64)
+                .execute(&mut *tx)
+                .await?;
+            }
+            tx.commit().await?;
+            Ok(())
+        })
+    }
+
+    fn replace_daily_rollups(&self, rollups: Vec<DailyRollup>) -> Result<()> {
+        block_on(async {
+            let mut tx = self.pool.begin().await?;
+            sqlx::query("DELETE FROM daily_rollups")
+                .execute(&mut *tx)
+                .await?;
+            for rollup in &rollups {
+                sqlx::query(
+                    "INSERT INTO daily_rollups
+                         (date, packages_added, versions_released, vulnerabilities_published)
+                     VALUES ($1, $2, $3, $4)",
+                )
+                .bind(&rollup.date)
+                .bind(rollup.packages_added as i64)
+                .bind(rollup.versions_released as i64)
+                .bind(rollup.vulnerabilities_published as i64)
+                .execute(&mut *tx)
+                .await?;
+            }
+            tx.commit().await?;
+            Ok(())
+        })
+    }
+
+    fn insert_version(&self, version: PackageVersion) -> Result<PackageVersion> {
+        block_on(self.insert_all(vec![version])).map(|mut saved| saved.remove(0))
+    }
+
+    fn insert_versions(&self, versions: Vec<PackageVersion>) -> Result<Vec<PackageVersion>> {
+        block_on(self.insert_all(versions))
+    }
+
+    fn update_versions(&self, versions: Vec<PackageVersion>) -> Result<()> {
+        block_on(self.update_all(&versions))
+    }
+
+    fn get_version(&self, id: u64) -> Result<Option<PackageVersion>> {
+        block_on(self.get(id))
+    }
+
+    fn get_versions_by_package(&self, package_id: u64) -> Result<Vec<PackageVersion>> {
+        block_on(self.get_by("package_id", package_id as i64))
+    }
+
+    fn for_each_version(&self, f: &mut dyn FnMut(PackageVersion) -> Result<()>) -> Result<()> {
+        block_on(self.for_each(f))
+    }
+
+    fn insert_user(&self, user: User) -> Result<User> {
+        block_on(self.insert_all(vec![user])).map(|mut saved| saved.remove(0))
+    }
+
+    fn get_user(&self, id: u64) -> Result<Option<User>> {
+        block_on(self.get(id))
+    }
+
+    fn get_user_by_email(&self, email: &str) -> Result<Option<User>> {
+        block_on(async {
+            let row: Option<Json<User>> =
+                sqlx::query_scalar("SELECT data FROM users WHERE email = $1")
+                    .bind(email)
+                    .fetch_optional(&self.pool)
+                    .await?;
+            Ok(row.map(|Json(user)| user))
+        })
+    }
+
+    fn get_user_by_username(&self, username: &str) -> Result<Option<User>> {
+        block_on(async {
+            let row: Option<Json<User>> =
+                sqlx::query_scalar("SELECT data FROM users WHERE username = $1")
+                    .bind(username)
+                    .fetch_optional(&self.pool)
+                    .await?;
+            Ok(row.map(|Json(user)| user))
+        })
+    }
+
+    fn for_each_user(&self, f: &mut dyn FnMut(User) -> Result<()>) -> Result<()> {
+        block_on(self.for_each(f))
+    }
+
+    fn update_user(&self, user: User) -> Result<()> {
+        block_on(self.update_all(&[user]))
+    }
+
+    fn modify_user(&self, id: u64, f: &mut dyn FnMut(&mut User)) -> Result<Option<User>> {
+        block_on(self.modify(id, f))
+    }
+
+    fn delete_user(&self, id: u64) -> Result<bool> {
+        block_on(async {
+            let mut tx = self.pool.begin().await?;
+            // Pending notifications are flags on the events
+            sqlx::query("DELETE FROM timeline_events WHERE user_id = $1")
+                .bind(id as i64)
+                .execute(&mut *tx)
+                .await?;
+            // Subscriptions, projects and installations go with the user
+            // through ON DELETE CASCADE
+            let result = sqlx::query("DELETE FROM users WHERE id = $1")
+                .bind(id as i64)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    fn anonymize_user(&self, user: User) -> Result<bool> {
+        block_on(async {
+            let mut tx = self.pool.begin().await?;
+            let exists: Option<i64> =
+                sqlx::query_scalar("SELECT id FROM users WHERE id = $1 FOR UPDATE")
+                    .bind(user.id as i64)
+                    .fetch_optional(&mut *tx)
+                    .await?;
+            if exists.is_none() {
+                return Ok(false);
+            }
+            // Project dependencies and syncs go with these through ON DELETE
+            // CASCADE
+            for table in [
+                "timeline_events",
+                "projects",
+                "github_installations",
+                "advisory_acks",
+            ] {
+                sqlx::query(&format!("DELETE FROM {} WHERE user_id = $1", table))
+                    .bind(user.id as i64)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            user.upsert(&mut tx).await?;
+            tx.commit().await?;
+            Ok(true)
+        })
+    }
+
+    fn get_account_activity(&self, user_id: u64) -> Result<Option<AccountActivity>> {
+        block_on(async {
+            let row: Option<Json<AccountActivity>> =
+                sqlx::query_scalar("SELECT data FROM account_activity WHERE user_id = $1")
+                    .bind(user_id as i64)
+                    .fetch_optional(&self.pool)
+                    .await?;
+            Ok(row.map(|Json(activity)| activity))
+        })
+    }
+
+    fn get_all_account_activity(&self) -> Result<Vec<AccountActivity>> {
+        block_on(async {
+            let rows: Vec<Json<AccountActivity>> =
+                sqlx::query_scalar("SELECT data FROM account_activity ORDER BY user_id")
+                    .fetch_all(&self.pool)
+                    .await?;
+            Ok(rows.into_iter().map(|Json(activity)| activity).collect())
+        })
+    }
+
+    fn set_account_activity(&self, activity: AccountActivity) -> Result<()> {
+        block_on(async {
+            sqlx::query(
+                "INSERT INTO account_activity (user_id, data) VALUES ($1, $2)
+                 ON CONFLICT (user_id) DO UPDATE SET data = EXCLUDED.data",
+            )
+            .bind(activity.user_id as i64)
+            .bind(Json(&activity))
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        })
+    }
+
+    fn insert_vulnerability(&self, vulnerability: Vulnerability) -> Result<Vulnerability> {
+        block_on(self.insert_all(vec![vulnerability])).map(|mut saved| saved.remove(0))
+    }
+
+    fn get_vulnerability(&self, id: u64) -> Result<Option<Vulnerability>> {
+        block_on(self.get(id))
+    }
+
+    fn get_vulnerabilities(&self, ids: &[u64]) -> Result<Vec<Vulnerability>> {
+        block_on(self.get_many(ids))
+    }
+
+    fn for_each_vulnerability(&self, f: &mut dyn FnMut(Vulnerability) -> Result<()>) -> Result<()> {
+        block_on(self.for_each(f))
+    }
+
+    fn update_vulnerability(&self, vulnerability: Vulnerability) -> Result<()> {
+        block_on(self.update_all(&[vulnerability]))
+    }
+
+    fn delete_vulnerability(&self, id: u64) -> Result<bool> {
+        block_on(async {
+            let result = sqlx::query("DELETE FROM vulnerabilities WHERE id = $1")
+                .bind(id as i64)
+                .execute(&self.pool)
+                .await?;
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    fn insert_timeline_event(&self, event: TimelineEvent) -> Result<Option<TimelineEvent>> {
+        block_on(self.insert_timeline_event_async(event))
+    }
+
+    fn get_timeline_event(&self, id: u64) -> Result<Option<TimelineEvent>> {
+        block_on(self.get(id))
+    }
+
+    fn for_each_timeline_event(&self, f: &mut dyn FnMut(TimelineEvent) -> Result<()>) -> Result<()> {
+        block_on(self.for_each(f))
+    }
+
+    fn get_timeline_by_package(&self, package_id: u64) -> Result<Vec<TimelineEvent>> {
+        block_on(self.get_by("package_id", package_id as i64))
+    }
+
+    fn get_timeline_events_by_user(&self, user_id: u64) -> Result<Vec<TimelineEvent>> {
+        block_on(self.get_by("user_id", user_id as i64))
+    }
+
+    fn delete_timeline_events_by_user(&self, user_id: u64) -> Result<usize> {
+        block_on(async {
+            let result = sqlx::query("DELETE FROM timeline_events WHERE user_id = $1")
+                .bind(user_id as i64)
+                .execute(&self.pool)
+                .await?;
+            Ok(result.rows_affected() as usize)
+        })
+    }
+
+    fn update_timeline_event(&self, event: TimelineEvent) -> Result<()> {
+        block_on(self.update_all(&[event]))
+    }
+
+    fn query_timeline(&self, filter: &TimelineFilter) -> Result<Vec<TimelineEvent>> {
+        // Event types are stored as serde names inside the JSON
+        let event_type = filter
+            .event_type
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()?
+            .and_then(|value| value.as_str().map(str::to_string));
+        block_on(async {
+            let rows: Vec<Json<TimelineEvent>> = sqlx::query_scalar(
+                "SELECT data FROM timeline_events
+                 WHERE ($1::BIGINT IS NULL OR package_id = $1)
+                   AND ($2::BIGINT IS NULL OR user_id = $2)
+                   AND ($3::TIMESTAMPTZ IS NULL OR created_at >= $3)
+                   AND ($4::TEXT IS NULL OR data->>'event_type' = $4)
+                 ORDER BY id",
+            )
+            .bind(filter.package_id.map(|id| id as i64))
+            .bind(filter.user_id.map(|id| id as i64))
+            .bind(filter.since)
+            .bind(event_type)
+            .fetch_all(&self.pool)
+            .await?;
+            Ok(rows.into_iter().map(|Json(event)| event).collect())
+        })
+    }
+
+    fn get_pending_notifications(&self) -> Result<Vec<TimelineEvent>> {
+        block_on(async {
+            // Deleted users and users who opted out have nothing to send
+            let rows: Vec<Json<TimelineEvent>> = sqlx::query_scalar(
+                "SELECT t.data FROM timeline_events t
+                 JOIN users u ON u.id = t.user_id
+                 WHERE t.awaits_notification
+                   AND (u.data->>'notifications_enabled')::BOOLEAN
+                 ORDER BY t.id",
+            )
+            .fetch_all(&self.pool)
+            .await?;
+            Ok(rows.into_iter().map(|Json(event)| event).collect())
+        })
+    }
+
+    fn purge_old_timeline_events(&self, older_than: chrono::Duration) -> Result<usize> {
+        let cutoff_time = chrono::Utc::now() - older_than;
+        block_on(async {
+            let result = sqlx::query("DELETE FROM timeline_events WHERE created_at < $1")
+                .bind(cutoff_time)
+                .execute(&self.pool)
+                .await?;
+            Ok(result.rows_affected() as usize)
+        })
+    }
+
+    fn get_subscriber_count(&self, package_name: &str) -> Result<u64> {
+        block_on(async {
+            let count: i64 =
+                sqlx::query_scalar("SELECT COUNT(*) FROM subscriptions WHERE package_name = $1")
+                    .bind(package_name)
+                    .fetch_one(&self.pool)
+                    .await?;
+            Ok(count as u64)
+        })
+    }
+
+    fn get_users_subscribed_to(&self, package_name: &str) -> Result<Vec<u64>> {
+        block_on(async {
+            let user_ids: Vec<i64> = sqlx::query_scalar(
+                "SELECT user_id FROM subscriptions
+                 WHERE package_name = $1 AND notifications_enabled
+                 ORDER BY user_id",
+            )
+            .bind(package_name)
+            .fetch_all(&self.pool)
+            .await?;
+            Ok(user_ids.into_iter().map(|id| id as u64).collect())
+        })
+    }
+
+    fn insert_cpe_mapping(&self, mut mapping: CpeMapping) -> Result<CpeMapping> {
+        block_on(async {
+            let mut tx = self.pool.begin().await?;
+            let existing: Option<i64> = sqlx::query_scalar(
+                "SELECT id FROM cpe_mappings WHERE vendor_product = $1 FOR UPDATE",
+            )
+            .bind(&mapping.vendor_product)
+            .fetch_optional(&mut *tx)
+            .await?;
+            if let Some(existing) = existing {
+                if mapping.id == 0 {
+                    mapping.id = existing as u64;
+                } else if mapping.id != existing as u64 {
+                    sqlx::query("DELETE FROM cpe_mappings WHERE id = $1")
+                        .bind(existing)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+            }
+            if mapping.id == 0 {
+                mapping.id = self.next_id::<CpeMapping>().await?;
+            }
+            mapping.upsert(&mut tx).await?;
+            tx.commit().await?;
+            Ok(mapping)
+        })
+    }
+
+    fn get_cpe_mapping(&self, vendor_product: &str) -> Result<Option<CpeMapping>> {
+        block_on(async {
+            let row: Option<Json<CpeMapping>> =
+                sqlx::query_scalar("SELECT data FROM cpe_mappings WHERE vendor_product = $1")
+                    .bind(vendor_product)
+                    .fetch_optional(&self.pool)
+                    .await?;
+            Ok(row.map(|Json(mapping)| mapping))
+        })
+    }
+
+    fn get_all_cpe_mappings(&self) -> Result<Vec<CpeMapping>> {
+        block_on(self.get_all())
+    }
+
+    fn delete_cpe_mapping(&self, id: u64) -> Result<bool> {
+        block_on(async {
+            let result = sqlx::query("DELETE FROM cpe_mappings WHERE id = $1")
+                .bind(id as i64)
+                .execute(&self.pool)
+                .await?;
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    fn upsert_maintainers(&self, maintainers: Vec<Maintainer>) -> Result<Vec<Maintainer>> {
+        block_on(async {
+            let mut tx = self.pool.begin().await?;
+            let mut saved = Vec::with_capacity(maintainers.len());
+            for mut maintainer in maintainers {
+                let existing: Option<Json<Maintainer>> = sqlx::query_scalar(
+                    "SELECT data FROM maintainers WHERE handle = $1 FOR UPDATE",
+                )
+                .bind(&maintainer.handle)
+                .fetch_optional(&mut *tx)
+                .await?;
+                match existing {
+                    Some(Json(existing)) => {
+                        maintainer.id = existing.id;
+                        maintainer.created_at = existing.created_at;
+                    }
+                    None => maintainer.id = self.next_id::<Maintainer>().await?,
+                }
+                maintainer.upsert(&mut tx).await?;
+                saved.push(maintainer);
+            }
+            tx.commit().await?;
+            Ok(saved)
+        })
+    }
+
+    fn get_maintainer(&self, id: u64) -> Result<Option<Maintainer>> {
+        block_on(self.get(id))
+    }
+
+    fn get_maintainer_by_handle(&self, handle: &str) -> Result<Option<Maintainer>> {
+        block_on(async {
+            let row: Option<Json<Maintainer>> =
+                sqlx::query_scalar("SELECT data FROM maintainers WHERE handle = $1")
+                    .bind(handle)
+                    .fetch_optional(&self.pool)
+                    .await?;
+            Ok(row.map(|Json(maintainer)| maintainer))
+        })
+    }
+
+    fn get_all_maintainers(&self) -> Result<Vec<Maintainer>> {
+        block_on(self.get_all())
+    }
+
+    fn set_package_maintainers(&self, links: Vec<(u64, Vec<u64>)>) -> Result<()> {
+        block_on(async {
+            let mut tx = self.pool.begin().await?;
+            for (package_id, maintainer_ids) in links {
+                sqlx::query("DELETE FROM package_maintainers WHERE package_id = $1")
+                    .bind(package_id as i64)
+                    .execute(&mut *tx)
+                    .await?;
+                for maintainer_id in maintainer_ids {
+                    sqlx::query(
+                        "INSERT INTO package_maintainers (package_id, maintainer_id)
+                         VALUES ($1, $2) ON CONFLICT DO NOTHING",
+                    )
+                    .bind(package_id as i64)
+                    .bind(maintainer_id as i64)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+            }
+            tx.commit().await?;
+            Ok(())
+        })
+    }
+
+    fn get_package_maintainers(&self, package_id: u64) -> Result<Vec<Maintainer>> {
+        block_on(async {
+            let rows: Vec<Json<Maintainer>> = sqlx::query_scalar(
+                "SELECT m.data FROM maintainers m
+                 JOIN package_maintainers pm ON pm.maintainer_id = m.id
+                 WHERE pm.package_id = $1
+                 ORDER BY m.id",
+            )
+            .bind(package_id as i64)
+            .fetch_all(&self.pool)
+            .await?;
+            Ok(rows.into_iter().map(|Json(maintainer)| maintainer).collect())
+        })
+    }
+
+    fn get_packages_by_maintainer(&self, maintainer_id: u64) -> Result<Vec<Package>> {
+        block_on(async {
+            let rows: Vec<Json<Package>> = sqlx::query_scalar(
+                "SELECT p.data FROM packages p
+                 JOIN package_maintainers pm ON pm.package_id = p.id
+                 WHERE pm.maintainer_id = $1
+                 ORDER BY p.id",
+            )
+            .bind(maintainer_id as i64)
+            .fetch_all(&self.pool)
+            .await?;
+            Ok(rows.into_iter().map(|Json(package)| package).collect())
+        })
+    }
+
+    fn quarantine_packages(&self, packages: Vec<QuarantinedPackage>) -> Result<()> {
+        block_on(async {
+            let mut tx = self.pool.begin().await?;
+            for mut package in packages {
+                let existing: Option<Json<QuarantinedPackage>> = sqlx::query_scalar(
+                    "SELECT data FROM quarantined_packages WHERE name = $1 FOR UPDATE",
+                )
+                .bind(&package.name)
+                .fetch_optional(&mut *tx)
+                .await?;
+                match existing {
+                    Some(Json(existing)) => {
+                        package.id = existing.id;
+                        package.admitted = existing.admitted;
+                        package.first_seen = existing.first_seen;
+                    }
+                    None => package.id = self.next_id::<QuarantinedPackage>().await?,
+                }
+                package.upsert(&mut tx).await?;
+            }
+            tx.commit().await?;
+            Ok(())
+        })
+    }
+
+    fn get_quarantined_package(&self, id: u64) -> Result<Option<QuarantinedPackage>> {
+        block_on(self.get(id))
+    }
+
+    fn get_all_quarantined_packages(&self) -> Result<Vec<QuarantinedPackage>> {
+        block_on(self.get_all())
+    }
+
+    fn update_quarantined_package(&self, package: QuarantinedPackage) -> Result<()> {
+        block_on(self.update_all(&[package]))
+    }
+
+    fn delete_quarantined_package(&self, id: u64) -> Result<bool> {
+        block_on(async {
+            let result = sqlx::query("DELETE FROM quarantined_packages WHERE id = $1")
+                .bind(id as i64)
+                .execute(&self.pool)
+                .await?;
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    fn hold_packages(&self, package_ids: Vec<u64>) -> Result<()> {
+        let ids: Vec<i64> = package_ids.into_iter().map(|id| id as i64).collect();
+        block_on(async {
+            sqlx::query(
+                "INSERT INTO pending_packages (package_id, created_at)
+                 SELECT id, now() FROM UNNEST($1::BIGINT[]) AS id
+                 ON CONFLICT (package_id) DO NOTHING",
+            )
+            .bind(&ids)
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        })
+    }
+
+    fn insert_pending_package(&self, mut package: Package) -> Result<Package> {
+        block_on(async {
+            if package.id == 0 {
+                package.id = self.next_id::<Package>().await?;
+            }
+            let mut tx = self.pool.begin().await?;
+            package.upsert(&mut tx).await?;
+            sqlx::query(
+                "INSERT INTO pending_packages (package_id, created_at) VALUES ($1, now())
+                 ON CONFLICT (package_id) DO NOTHING",
+            )
+            .bind(package.id as i64)
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            Ok(package)
+        })
+    }
+
+    fn get_pending_packages(&self) -> Result<Vec<PendingPackage>> {
+        block_on(async {
+            let rows: Vec<(i64, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
+                "SELECT package_id, created_at FROM pending_packages ORDER BY package_id",
+            )
+            .fetch_all(&self.pool)
+            .await?;
+            Ok(rows
+                .into_iter()
+                .map(|(package_id, created_at)| PendingPackage {
+                    package_id: package_id as u64,
+                    created_at,
+                })
+                .collect())
+        })
+    }
+
+    fn is_package_pending(&self, package_id: u64) -> Result<bool> {
+        block_on(async {
+            let pending: bool = sqlx::query_scalar(
+                "SELECT EXISTS (SELECT 1 FROM pending_packages WHERE package_id = $1)",
+            )
+            .bind(package_id as i64)
+            .fetch_one(&self.pool)
+            .await?;
+            Ok(pending)
+        })
+    }
+
+    fn approve_packages(&self, package_ids: Vec<u64>) -> Result<usize> {
+        let ids: Vec<i64> = package_ids.into_iter().map(|id| id as i64).collect();
+        block_on(async {
+            let result = sqlx::query("DELETE FROM pending_packages WHERE package_id = ANY($1)")
+                .bind(&ids)
+                .execute(&self.pool)
+                .await?;
+            Ok(result.rows_affected() as usize)
+        })
+    }
+
+    fn insert_project(&self, project: Project) -> Result<Project> {
+        block_on(self.insert_all(vec![project])).map(|mut saved| saved.remove(0))
+    }
+
+    fn get_project(&self, id: u64) -> Result<Option<Project>> {
+        block_on(self.get(id))
+    }
+
+    fn get_projects_by_user(&self, user_id: u64) -> Result<Vec<Project>> {
+        block_on(self.get_by("user_id", user_id as i64))
+    }
+
+    fn update_project(&self, project: Project) -> Result<()> {
+        block_on(self.update_all(&[project]))
+    }
+
+    fn delete_project(&self, id: u64) -> Result<bool> {
+        block_on(async {
+            let result = sqlx::query("DELETE FROM projects WHERE id = $1")
+                .bind(id as i64)
+                .execute(&self.pool)
+                .await?;
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    fn get_projects_depending_on(&self, package_name: &str) -> Result<Vec<Project>> {
+        block_on(async {
+            let rows: Vec<Json<Project>> = sqlx::query_scalar(
+                "SELECT p.data FROM projects p
+                 JOIN project_dependencies d ON d.project_id = p.id
+                 WHERE d.package_name = $1
+                 ORDER BY p.id",
+            )
+            .bind(package_name)
+            .fetch_all(&self.pool)
+            .await?;
+            Ok(rows.into_iter().map(|Json(project)| project).collect())
+        })
+    }
+
+    fn upsert_github_installation(&self, installation: GithubInstallation) -> Result<()> {
+        block_on(async {
+            sqlx::query(
+                "INSERT INTO github_installations (id, user_id, data) VALUES ($1, $2, $3)
+                 ON CONFLICT (id) DO UPDATE SET user_id = EXCLUDED.user_id, data = EXCLUDED.data",
+            )
+            .bind(installation.id as i64)
+            .bind(installation.user_id as i64)
+            .bind(Json(&installation))
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        })
+    }
+
+    fn get_github_installation(&self, id: u64) -> Result<Option<GithubInstallation>> {
+        block_on(async {
+            let row: Option<Json<GithubInstallation>> =
+                sqlx::query_scalar("SELECT data FROM github_installations WHERE id = $1")
+                    .bind(id as i64)
+                    .fetch_optional(&self.pool)
+                    .await?;
+            Ok(row.map(|Json(installation)| installation))
+        })
+    }
+
+    fn get_github_installations_by_user(&self, user_id: u64) -> Result<Vec<GithubInstallation>> {
+        block_on(async {
+            let rows: Vec<Json<GithubInstallation>> = sqlx::query_scalar(
+                "SELECT data FROM github_installations WHERE user_id = $1 ORDER BY id",
+            )
+            .bind(user_id as i64)
+            .fetch_all(&self.pool)
+            .await?;
+            Ok(rows.into_iter().map(|Json(installation)| installation).collect())
+        })
+    }
+
+    fn delete_github_installation(&self, id: u64) -> Result<bool> {
+        block_on(async {
+            // Project syncs go with it through ON DELETE CASCADE
+            let result = sqlx::query("DELETE FROM github_installations WHERE id = $1")
+                .bind(id as i64)
+                .execute(&self.pool)
+                .await?;
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    fn set_project_sync(&self, sync: ProjectSync) -> Result<()> {
+        block_on(async {
+            sqlx::query(
+                "INSERT INTO project_syncs (project_id, installation_id, repository, data)
+                 VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (project_id) DO UPDATE
+                 SET installation_id = EXCLUDED.installation_id,
+                     repository = EXCLUDED.repository,
+                     data = EXCLUDED.data",
+            )
+            .bind(sync.project_id as i64)
+            .bind(sync.installation_id as i64)
+            .bind(&sync.repository)
+            .bind(Json(&sync))
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        })
+    }
+
+    fn get_project_sync(&self, project_id: u64) -> Result<Option<ProjectSync>> {
+        block_on(async {
+            let row: Option<Json<ProjectSync>> =
+                sqlx::query_scalar("SELECT data FROM project_syncs WHERE project_id = $1")
+                    .bind(project_id as i64)
+                    .fetch_optional(&self.pool)
+                    .await?;
+            Ok(row.map(|Json(sync)| sync))
+        })
+    }
+
+    fn get_project_syncs_by_repository(&self, repository: &str) -> Result<Vec<ProjectSync>> {
+        block_on(async {
+            let rows: Vec<Json<ProjectSync>> = sqlx::query_scalar(
+                "SELECT data FROM project_syncs WHERE repository = $1 ORDER BY project_id",
+            )
+            .bind(repository)
+            .fetch_all(&self.pool)
+            .await?;
+            Ok(rows.into_iter().map(|Json(sync)| sync).collect())
+        })
+    }
+
+    fn delete_project_sync(&self, project_id: u64) -> Result<bool> {
+        block_on(async {
+            let result = sqlx::query("DELETE FROM project_syncs WHERE project_id = $1")
+                .bind(project_id as i64)
+                .execute(&self.pool)
+                .await?;
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    fn acknowledge_advisory(&self, mut ack: AdvisoryAck) -> Result<AdvisoryAck> {
+        block_on(async {
+            let existing: Vec<AdvisoryAck> = self.get_by("user_id", ack.user_id as i64).await?;
+            ack.id = existing
+                .iter()
+                .find(|existing| {
+                    existing.vulnerability_id == ack.vulnerability_id
+                        && existing.project_id == ack.project_id
+                })
+                .map_or(0, |existing| existing.id);
+            self.insert_all(vec![ack]).await.map(|mut saved| saved.remove(0))
+        })
+    }
+
+    fn get_advisory_acks_by_user(&self, user_id: u64) -> Result<Vec<AdvisoryAck>> {
+        block_on(self.get_by("user_id", user_id as i64))
+    }
+
+    fn delete_advisory_ack(&self, id: u64) -> Result<bool> {
+        block_on(async {
+            let result = sqlx::query("DELETE FROM advisory_acks WHERE id = $1")
+                .bind(id as i64)
+                .execute(&self.pool)
+                .await?;
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    fn insert_collector_run(&self, run: CollectorRun) -> Result<CollectorRun> {
+        block_on(self.insert_all(vec![run])).map(|mut saved| saved.remove(0))
+    }
+
+    fn get_collector_runs(&self, collector: &str) -> Result<Vec<CollectorRun>> {
+        block_on(async {
+            let rows: Vec<Json<CollectorRun>> = sqlx::query_scalar(
+                "SELECT data FROM collector_runs WHERE collector = $1 ORDER BY id",
+            )
+            .bind(collector)
+            .fetch_all(&self.pool)
+            .await?;
+            Ok(rows.into_iter().map(|Json(run)| run).collect())
+        })
+    }
+
+    fn get_all_collector_runs(&self) -> Result<Vec<CollectorRun>> {
+        block_on(self.get_all())
+    }
+
+    fn acquire_lease(&self, name: &str, holder: &str, ttl: chrono::Duration) -> Result<bool> {
+        let expires_at = chrono::Utc::now() + ttl;
+        block_on(async {
+            let result = sqlx::query(
+                "INSERT INTO leases (name, holder, expires_at) VALUES ($1, $2, $3)
+                 ON CONFLICT (name) DO UPDATE
+                 SET holder = EXCLUDED.holder, expires_at = EXCLUDED.expires_at
+                 WHERE leases.holder = EXCLUDED.holder OR leases.expires_at <= now()",
+            )
+            .bind(name)
+            .bind(holder)
+            .bind(expires_at)
+            .execute(&self.pool)
+            .await?;
+            Ok(result.rows_affected() == 1)
+        })
+    }
+
+    fn release_lease(&self, name: &str, holder: &str) -> Result<()> {
+        block_on(async {
+            sqlx::query("DELETE FROM leases WHERE name = $1 AND holder = $2")
+                .bind(name)
+                .bind(holder)
+                .execute(&self.pool)
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn cleanup_expired_leases(&self) -> Result<usize> {
+        block_on(async {
+            let result = sqlx::query("DELETE FROM leases WHERE expires_at <= now()")
+                .execute(&self.pool)
+                .await?;
+            Ok(result.rows_affected() as usize)
+        })
+    }
+}
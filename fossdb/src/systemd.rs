@@ -0,0 +1,48 @@
+//! Running as a systemd service: listening on a socket passed in by a
+//! `.socket` unit, and reporting readiness and liveness to a `Type=notify`
+//! unit. Everything here does nothing when not started by systemd.
+use anyhow::{Context, Result};
+use sd_notify::NotifyState;
+use std::os::fd::FromRawFd;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// The first socket systemd passed in, if any
+pub fn take_listener() -> Result<Option<tokio::net::TcpListener>> {
+    let Some(fd) = sd_notify::listen_fds()
+        .context("Invalid LISTEN_FDS from systemd")?
+        .next()
+    else {
+        return Ok(None);
+    };
+
+    // SAFETY: systemd hands over ownership of the descriptors it lists
+    let listener = unsafe { std::net::TcpListener::from_raw_fd(fd) };
+    listener.set_nonblocking(true)?;
+    Ok(Some(tokio::net::TcpListener::from_std(listener)?))
+}
+
+/// Tell systemd startup is complete, then keep its watchdog fed if the unit
+/// sets `WatchdogSec`
+pub fn notify_ready() {
+    if let Err(e) = sd_notify::notify(false, &[NotifyState::Ready]) {
+        warn!("Failed to notify systemd of readiness: {}", e);
+        return;
+    }
+
+    let mut usec = 0;
+    if sd_notify::watchdog_enabled(false, &mut usec) {
+        // Ping at half the timeout, as sd_watchdog_enabled(3) recommends
+        let interval = Duration::from_micros(usec) / 2;
+        info!("systemd watchdog enabled, pinging every {:?}", interval);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = sd_notify::notify(false, &[NotifyState::Watchdog]) {
+                    warn!("Failed to ping systemd watchdog: {}", e);
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,90 @@
+use anyhow::Result;
+
+use crate::storage::Storage;
+use crate::{TimelineEvent, projects};
+
+/// Where published events are sent live
+pub trait Broadcast: Send + Sync {
+    fn broadcast(&self, event: TimelineEvent);
+}
+
+#[cfg(feature = "api-server")]
+impl Broadcast for crate::websocket::TimelineBroadcaster {
+    fn broadcast(&self, event: TimelineEvent) {
+        crate::websocket::TimelineBroadcaster::broadcast(self, event)
+    }
+}
+
+/// Nobody is listening, e.g. in an offline import. Subscribers still get
+/// their copies.
+impl Broadcast for () {
+    fn broadcast(&self, _event: TimelineEvent) {}
+}
+
+/// Fan an event out to everyone subscribed to its package.
+///
+/// A personal copy of `event` is stored and broadcast for each subscriber with
+/// notifications enabled and each owner of a project it affects, then a global copy is broadcast without being stored.
+/// If every subscriber already has the event nothing is broadcast, so replays
+/// of the same release don't reach clients twice. Returns the number of
+/// personal events created.
+pub fn publish(
+    db: &dyn Storage,
+    broadcaster: &dyn Broadcast,
+    event: TimelineEvent,
+) -> Result<usize> {
+    let mut recipients = db.get_users_subscribed_to(&event.package_name)?;
+    for owner in projects::affected_owners(db, &event)? {
+        if !recipients.contains(&owner) {
+            recipients.push(owner);
+        }
+    }
+    let mut created = 0;
+    let mut duplicates = 0;
+
+    for user_id in recipients {
+        let personal = TimelineEvent {
+            id: 0,
+            user_id: Some(user_id),
+            ..event.clone()
+        };
+
+        match db.insert_timeline_event(personal) {
+            Ok(None) => duplicates += 1,
+            Ok(Some(saved_event)) => {
+                broadcaster.broadcast(saved_event);
+                created += 1;
+                tracing::debug!(
+                    "Created {:?} timeline event for user {} for {}",
+                    event.event_type,
+                    user_id,
+                    event.package_name
+                );
+            }
+            Err(e) => {
+                tracing::error!(
+                    "Failed to create timeline event for user {}: {}",
+                    user_id,
+                    e
+                );
+            }
+        }
+    }
+
+    if created == 0 && duplicates > 0 {
+        tracing::debug!(
+            "Skipped duplicate {:?} event for {}",
+            event.event_type,
+            event.package_name
+        );
+        return Ok(0);
+    }
+
+    broadcaster.broadcast(TimelineEvent {
+        id: 0,
+        user_id: None,
+        ..event
+    });
+
+    Ok(created)
+}
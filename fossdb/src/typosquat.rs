@@ -0,0 +1,323 @@
+// Name similarity checks for spotting typosquats
+#[cfg(feature = "db")]
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+#[cfg(feature = "db")]
+use std::sync::RwLock;
+#[cfg(feature = "db")]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "db")]
+use crate::storage::Storage;
+
+/// An existing package name that is dangerously similar to a queried name
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SimilarName {
+    pub name: String,
+    pub platform: Option<String>,
+    pub edit_distance: usize,
+    pub keyboard_distance: f32,
+}
+
+const QWERTY_ROWS: [&str; 4] = ["1234567890-", "qwertyuiop", "asdfghjkl", "zxcvbnm"];
+
+fn key_position(c: char) -> Option<(i32, i32)> {
+    let c = c.to_ascii_lowercase();
+    QWERTY_ROWS.iter().enumerate().find_map(|(row, keys)| {
+        keys.find(c).map(|col| (row as i32, col as i32))
+    })
+}
+
+/// Whether two characters sit next to each other on a QWERTY keyboard
+fn keys_adjacent(a: char, b: char) -> bool {
+    match (key_position(a), key_position(b)) {
+        (Some((r1, c1)), Some((r2, c2))) => (r1 - r2).abs() <= 1 && (c1 - c2).abs() <= 1,
+        _ => false,
+    }
+}
+
+/// Strip case and separator differences (`foo-bar`, `foo_bar`, `Foo.Bar`)
+pub fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, '-' | '_' | '.'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Levenshtein distance, or `None` as soon as it is known to exceed `max`
+pub fn levenshtein_within(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        let mut row_min = curr[0];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+            row_min = row_min.min(curr[j + 1]);
+        }
+        if row_min > max {
+            return None;
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    let distance = prev[b.len()];
+    (distance <= max).then_some(distance)
+}
+
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    levenshtein_within(a, b, usize::MAX).unwrap_or(usize::MAX)
+}
+
+/// Edit distance where substituting an adjacent key only costs half an edit
+pub fn keyboard_distance(a: &str, b: &str) -> f32 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut prev: Vec<f32> = (0..=b.len()).map(|j| j as f32).collect();
+    let mut curr = vec![0.0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = (i + 1) as f32;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca.eq_ignore_ascii_case(cb) {
+                0.0
+            } else if keys_adjacent(*ca, *cb) {
+                0.5
+            } else {
+                1.0
+            };
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1.0).min(curr[j] + 1.0);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+/// Maximum edit distance considered dangerous for a name of this length.
+///
+/// Short names are too dense to flag single edits without drowning in noise.
+fn max_edit_distance(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// Compare `name` against a single existing name
+pub fn compare(name: &str, existing: &str) -> Option<(usize, f32)> {
+    if name == existing {
+        return None;
+    }
+
+    let normalized = normalize(name);
+    let existing_normalized = normalize(existing);
+
+    // Separator and case confusion is always suspicious
+    if normalized == existing_normalized {
+        return Some((levenshtein(name, existing), 0.0));
+    }
+
+    let max = max_edit_distance(normalized.chars().count().min(existing_normalized.chars().count()));
+    let distance = levenshtein_within(&normalized, &existing_normalized, max.max(2))?;
+    let keyboard = keyboard_distance(&normalized, &existing_normalized);
+
+    // Two adjacent-key slips are as likely as one arbitrary typo
+    if distance <= max || (max > 0 && keyboard <= 1.0) {
+        Some((distance, keyboard))
+    } else {
+        None
+    }
+}
+
+/// Index of known names bucketed by normalized length for fast comparison
+#[derive(Default)]
+pub struct NameIndex {
+    by_len: HashMap<usize, Vec<(String, Option<String>)>>,
+}
+
+impl NameIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index every package in the catalog
+    #[cfg(feature = "db")]
+    pub fn load(db: &dyn Storage) -> Result<Self> {
+        let mut index = Self::new();
+        db.for_each_package_summary(&mut |summary| {
+            index.insert(summary.name, summary.platform);
+            Ok(())
+        })?;
+        Ok(index)
+    }
+
+    /// Whether `name` is known exactly, on `platform` if one is given
+    pub fn contains(&self, name: &str, platform: Option<&str>) -> bool {
+        self.by_len
+            .get(&normalize(name).chars().count())
+            .into_iter()
+            .flatten()
+            .any(|(n, p)| n == name && (platform.is_none() || p.as_deref() == platform))
+    }
+
+    pub fn insert(&mut self, name: String, platform: Option<String>) {
+        let len = normalize(&name).chars().count();
+        self.by_len.entry(len).or_default().push((name, platform));
+    }
+
+    /// Find dangerously similar names, most similar first.
+    ///
+    /// When `platform` is given, only names from that platform are considered.
+    pub fn find_similar(&self, name: &str, platform: Option<&str>) -> Vec<SimilarName> {
+        let len = normalize(name).chars().count();
+        let mut similar: Vec<SimilarName> = (len.saturating_sub(2)..=len + 2)
+            .filter_map(|l| self.by_len.get(&l))
+            .flatten()
+            .filter(|(_, p)| platform.is_none() || p.as_deref() == platform)
+            .filter_map(|(existing, p)| {
+                compare(name, existing).map(|(edit_distance, keyboard_distance)| SimilarName {
+                    name: existing.clone(),
+                    platform: p.clone(),
+                    edit_distance,
+                    keyboard_distance,
+                })
+            })
+            .collect();
+
+        similar.sort_by(|a, b| {
+            a.keyboard_distance
+                .total_cmp(&b.keyboard_distance)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        similar
+    }
+}
+
+/// A [`NameIndex`] of the catalog shared between requests. It's loaded on
+/// first use and kept up to date as packages are submitted, and reloaded
+/// after `ttl` to pick up packages added by collectors.
+#[cfg(feature = "db")]
+pub struct SharedNameIndex {
+    ttl: Duration,
+    index: RwLock<Option<(Instant, NameIndex)>>,
+}
+
+#[cfg(feature = "db")]
+impl SharedNameIndex {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            index: RwLock::new(None),
+        }
+    }
+
+    /// Run `f` against the index, loading it first if it's missing or stale
+    pub fn with<T>(&self, db: &dyn Storage, f: impl FnOnce(&NameIndex) -> T) -> Result<T> {
+        if let Some((loaded, index)) = &*self.index.read().unwrap()
+            && loaded.elapsed() < self.ttl
+        {
+            return Ok(f(index));
+        }
+
+        // Load without holding the lock, a racing reload is harmless
+        let index = NameIndex::load(db)?;
+        let result = f(&index);
+        *self.index.write().unwrap() = Some((Instant::now(), index));
+        Ok(result)
+    }
+
+    /// Add a package that was just stored
+    pub fn insert(&self, name: String, platform: Option<String>) {
+        if let Some((_, index)) = &mut *self.index.write().unwrap() {
+            index.insert(name, platform);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("serde", "serde"), 0);
+        assert_eq!(levenshtein("serde", "sedre"), 2);
+        assert_eq!(levenshtein("tokio", "tokioo"), 1);
+        assert_eq!(levenshtein_within("request", "reqwest", 1), Some(1));
+        assert_eq!(levenshtein_within("serde", "anyhow", 2), None);
+    }
+
+    #[test]
+    fn test_keyboard_distance() {
+        // 'r' and 't' are neighbours, 'r' and 'm' are not
+        assert_eq!(keyboard_distance("serde", "setde"), 0.5);
+        assert_eq!(keyboard_distance("serde", "semde"), 1.0);
+    }
+
+    #[test]
+    fn test_find_similar() {
+        let mut index = NameIndex::new();
+        index.insert("serde_json".to_string(), Some("crates.io".to_string()));
+        index.insert("tokio".to_string(), Some("crates.io".to_string()));
+        index.insert("rand".to_string(), Some("crates.io".to_string()));
+        index.insert("requests".to_string(), Some("PyPI".to_string()));
+
+        let similar = index.find_similar("serde-json", None);
+        assert_eq!(similar.len(), 1);
+        assert_eq!(similar[0].name, "serde_json");
+
+        assert_eq!(index.find_similar("tokoi", None)[0].name, "tokio");
+        assert_eq!(index.find_similar("requets", Some("PyPI"))[0].name, "requests");
+        assert!(index.find_similar("requets", Some("crates.io")).is_empty());
+
+        // Very short names need an exact normalized match
+        assert!(index.find_similar("rnd", None).is_empty());
+        assert!(index.find_similar("tokio", None).is_empty());
+
+        assert!(index.contains("tokio", Some("crates.io")));
+        assert!(!index.contains("tokio", Some("PyPI")));
+        assert!(!index.contains("toki", None));
+    }
+
+    #[cfg(feature = "db")]
+    #[test]
+    fn test_shared_index() {
+        use crate::{db::Database, fixtures};
+
+        let db = Database::in_memory().unwrap();
+        fixtures::seed(&db).unwrap();
+        let shared = SharedNameIndex::new(Duration::from_secs(60));
+        assert!(
+            shared
+                .with(&db, |index| index.contains("serde", None))
+                .unwrap()
+        );
+
+        // Stored packages aren't seen until they're added or the index expires
+        db.insert_package(fixtures::package("left-pad")).unwrap();
+        assert!(
+            !shared
+                .with(&db, |index| index.contains("left-pad", None))
+                .unwrap()
+        );
+        shared.insert("left-pad".to_string(), None);
+        assert!(
+            shared
+                .with(&db, |index| index.contains("left-pad", None))
+                .unwrap()
+        );
+    }
+}
@@ -0,0 +1,469 @@
+use axum::{
+    Form, Json,
+    extract::{FromRequest, Request, rejection::FormRejection, rejection::JsonRejection},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use regex::Regex;
+use serde::{Serialize, de::DeserializeOwned};
+use std::sync::LazyLock;
+
+use crate::cpe::Cpe;
+use crate::{
+    AdvisoryAckRequest, CpeMappingRequest, CreatePackageRequest, LockfileRequest, LoginRequest,
+    PackageReplacementRequest, ProjectRequest, ProjectSyncRequest, RegisterRequest,
+    VerifyChecksumRequest, VulnerabilityRequest,
+};
+
+static CVE_ID: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^CVE-\d{4}-\d{4,}$").unwrap());
+
+/// Kinds of informational advisory, as RustSec names them
+const INFORMATIONAL_KINDS: &[&str] = &["notice", "unmaintained", "unsound"];
+
+/// A single invalid field in a request body
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// Field-level validation errors, rendered as a 422 response
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct ValidationErrors {
+    pub errors: Vec<FieldError>,
+}
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an error for a field
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.errors.push(FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        });
+    }
+
+    /// Record an error for a field if the condition does not hold
+    pub fn check(&mut self, condition: bool, field: &str, message: impl Into<String>) {
+        if !condition {
+            self.add(field, message);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Convert into a `Result`, failing if any errors were recorded
+    pub fn into_result(self) -> Result<(), ValidationErrors> {
+        if self.is_empty() { Ok(()) } else { Err(self) }
+    }
+}
+
+impl IntoResponse for ValidationErrors {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(serde_json::json!({
+                "error": "validation_failed",
+                "fields": self.errors,
+            })),
+        )
+            .into_response()
+    }
+}
+
+/// Types that can check their own fields after deserialization
+pub trait Validate {
+    fn validate(&self) -> Result<(), ValidationErrors>;
+}
+
+/// JSON extractor that runs [`Validate`] on the body.
+///
+/// Malformed bodies are reported with the same 422 shape as invalid fields.
+pub struct ValidatedJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ValidationErrors;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|e: JsonRejection| body_error(e.body_text()))?;
+        value.validate()?;
+        Ok(Self(value))
+    }
+}
+
+/// Form extractor that runs [`Validate`] on the body
+pub struct ValidatedForm<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidatedForm<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ValidationErrors;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Form(value) = Form::<T>::from_request(req, state)
+            .await
+            .map_err(|e: FormRejection| body_error(e.body_text()))?;
+        value.validate()?;
+        Ok(Self(value))
+    }
+}
+
+fn body_error(message: String) -> ValidationErrors {
+    let mut errors = ValidationErrors::new();
+    errors.add("body", message);
+    errors
+}
+
+fn is_valid_email(email: &str) -> bool {
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !email.contains(char::is_whitespace)
+        }
+        None => false,
+    }
+}
+
+fn is_valid_url(url: &str) -> bool {
+    (url.starts_with("https://") || url.starts_with("http://")) && !url.contains(char::is_whitespace)
+}
+
+/// Check a package name as accepted by subscriptions and package creation
+pub fn check_package_name(errors: &mut ValidationErrors, field: &str, name: &str) {
+    if name.trim().is_empty() {
+        errors.add(field, "must not be empty");
+    } else if name.len() > 214 {
+        errors.add(field, "must be at most 214 characters");
+    } else if name.contains(char::is_whitespace) {
+        errors.add(field, "must not contain whitespace");
+    }
+}
+
+impl Validate for RegisterRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        let username_len = self.username.chars().count();
+        errors.check(
+            (3..=32).contains(&username_len),
+            "username",
+            "must be between 3 and 32 characters",
+        );
+        errors.check(
+            self.username
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+            "username",
+            "may only contain letters, digits, '-' and '_'",
+        );
+        errors.check(is_valid_email(&self.email), "email", "must be a valid email address");
+        errors.check(
+            self.password.chars().count() >= 8,
+            "password",
+            "must be at least 8 characters",
+        );
+
+        errors.into_result()
+    }
+}
+
+impl Validate for LoginRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.check(!self.email.is_empty(), "email", "must not be empty");
+        errors.check(!self.password.is_empty(), "password", "must not be empty");
+        errors.into_result()
+    }
+}
+
+impl Validate for CreatePackageRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        match &self.name {
+            Some(name) => check_package_name(&mut errors, "name", name),
+            None => errors.check(
+                self.repository.is_some(),
+                "name",
+                "is required without a repository",
+            ),
+        }
+
+        if let Some(homepage) = &self.homepage {
+            errors.check(is_valid_url(homepage), "homepage", "must be an http(s) URL");
+        }
+        if let Some(repository) = &self.repository {
+            errors.check(
+                is_valid_url(repository),
+                "repository",
+                "must be an http(s) URL",
+            );
+        }
+        if let Some(description) = &self.description {
+            errors.check(
+                description.chars().count() <= 2000,
+                "description",
+                "must be at most 2000 characters",
+            );
+        }
+
+        errors.check(self.tags.len() <= 20, "tags", "must contain at most 20 tags");
+        errors.check(
+            self.tags
+                .iter()
+                .all(|t| !t.trim().is_empty() && t.chars().count() <= 50),
+            "tags",
+            "each tag must be between 1 and 50 characters",
+        );
+
+        errors.into_result()
+    }
+}
+
+impl Validate for VulnerabilityRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        if let Some(cve_id) = &self.cve_id {
+            errors.check(
+                CVE_ID.is_match(cve_id),
+                "cve_id",
+                "must look like CVE-YYYY-NNNN",
+            );
+        }
+        errors.check(!self.title.trim().is_empty(), "title", "must not be empty");
+        errors.check(
+            self.title.chars().count() <= 200,
+            "title",
+            "must be at most 200 characters",
+        );
+        errors.check(
+            !self.description.trim().is_empty(),
+            "description",
+            "must not be empty",
+        );
+        errors.check(
+            !self.affected_packages.is_empty(),
+            "affected_packages",
+            "must list at least one package",
+        );
+
+        for (i, affected) in self.affected_packages.iter().enumerate() {
+            if let Err(e) = semver::VersionReq::parse(&affected.version_range) {
+                errors.add(
+                    &format!("affected_packages[{}].version_range", i),
+                    format!("invalid version range: {}", e),
+                );
+            }
+        }
+
+        if let Some(fixed_in) = &self.fixed_in {
+            errors.check(!fixed_in.trim().is_empty(), "fixed_in", "must not be empty");
+        }
+        if let Some(kind) = &self.informational {
+            errors.check(
+                INFORMATIONAL_KINDS.contains(&kind.as_str()),
+                "informational",
+                "must be notice, unmaintained or unsound",
+            );
+        }
+
+        errors.into_result()
+    }
+}
+
+impl Validate for CpeMappingRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.check(
+            Cpe::parse(&self.cpe).is_some(),
+            "cpe",
+            "must be a CPE name with a vendor and product",
+        );
+        errors.into_result()
+    }
+}
+
+impl Validate for VerifyChecksumRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let checksum = self.checksum.trim();
+        errors.check(!checksum.is_empty(), "checksum", "must not be empty");
+        errors.check(
+            checksum.len() <= 256,
+            "checksum",
+            "must be at most 256 characters",
+        );
+        errors.into_result()
+    }
+}
+
+fn check_lockfile(errors: &mut ValidationErrors, lockfile: &str) {
+    errors.check(!lockfile.trim().is_empty(), "lockfile", "must not be empty");
+}
+
+impl Validate for ProjectRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.check(!self.name.trim().is_empty(), "name", "must not be empty");
+        errors.check(
+            self.name.chars().count() <= 100,
+            "name",
+            "must be at most 100 characters",
+        );
+        check_lockfile(&mut errors, &self.lockfile);
+        errors.into_result()
+    }
+}
+
+impl Validate for LockfileRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        check_lockfile(&mut errors, &self.lockfile);
+        errors.into_result()
+    }
+}
+
+impl Validate for ProjectSyncRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let parts: Vec<&str> = self.repository.split('/').collect();
+        errors.check(
+            parts.len() == 2
+                && parts.iter().all(|part| {
+                    !part.is_empty()
+                        && part
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+                }),
+            "repository",
+            "must look like owner/name",
+        );
+        errors.check(!self.path.trim().is_empty(), "path", "must not be empty");
+        errors.check(
+            !self.path.split('/').any(|part| part == ".."),
+            "path",
+            "must stay within the repository",
+        );
+        errors.into_result()
+    }
+}
+
+impl Validate for AdvisoryAckRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.check(
+            self.note.as_ref().is_none_or(|note| note.chars().count() <= 500),
+            "note",
+            "must be at most 500 characters",
+        );
+        errors.into_result()
+    }
+}
+
+impl Validate for PackageReplacementRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.check(
+            self.note.as_ref().is_none_or(|note| note.chars().count() <= 500),
+            "note",
+            "must be at most 500 characters",
+        );
+        errors.into_result()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_request_validation() {
+        let valid = RegisterRequest {
+            username: "alice_01".to_string(),
+            email: "alice@example.com".to_string(),
+            password: "correct horse".to_string(),
+        };
+        assert!(valid.validate().is_ok());
+
+        let invalid = RegisterRequest {
+            username: "a b".to_string(),
+            email: "alice@localhost".to_string(),
+            password: "short".to_string(),
+        };
+        let errors = invalid.validate().unwrap_err();
+        let fields: Vec<&str> = errors.errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["username", "email", "password"]);
+    }
+
+    #[test]
+    fn test_create_package_request_validation() {
+        let mut request = CreatePackageRequest {
+            name: Some("serde".to_string()),
+            description: None,
+            homepage: Some("https://serde.rs".to_string()),
+            repository: None,
+            license: Some("MIT".to_string()),
+            tags: vec!["rust".to_string()],
+        };
+        assert!(request.validate().is_ok());
+
+        request.name = Some("  ".to_string());
+        request.repository = Some("git@github.com:serde-rs/serde".to_string());
+        let errors = request.validate().unwrap_err();
+        assert_eq!(errors.errors.len(), 2);
+        assert_eq!(errors.errors[0].field, "name");
+        assert_eq!(errors.errors[1].field, "repository");
+
+        // The name can come from the repository
+        request.name = None;
+        request.repository = Some("https://github.com/serde-rs/serde".to_string());
+        assert!(request.validate().is_ok());
+        request.repository = None;
+        assert_eq!(request.validate().unwrap_err().errors[0].field, "name");
+    }
+
+    #[test]
+    fn test_vulnerability_request_validation() {
+        let mut request = VulnerabilityRequest {
+            cve_id: Some("CVE-2024-12345".to_string()),
+            title: "Buffer overflow".to_string(),
+            description: "Out of bounds write in parser".to_string(),
+            severity: crate::VulnerabilitySeverity::High,
+            affected_packages: vec![crate::AffectedPackage {
+                package_id: 1,
+                version_range: ">=1.0.0, <1.4.2".to_string(),
+            }],
+            fixed_in: Some("1.4.2".to_string()),
+            informational: None,
+            superseded_by: None,
+        };
+        assert!(request.validate().is_ok());
+
+        request.cve_id = Some("CVE-24-1".to_string());
+        request.affected_packages[0].version_range = "not a range".to_string();
+        request.informational = Some("abandoned".to_string());
+        let errors = request.validate().unwrap_err();
+        let fields: Vec<&str> = errors.errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec!["cve_id", "affected_packages[0].version_range", "informational"]
+        );
+    }
+}
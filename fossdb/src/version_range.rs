@@ -0,0 +1,217 @@
+// Lenient version and range matching for ecosystems that aren't strictly semver
+use semver::{Version, VersionReq};
+
+use crate::PackageVersion;
+
+/// Parse a version string, padding missing components (`1.2` -> `1.2.0`)
+/// and ignoring a leading `v`
+pub fn parse_version(version: &str) -> Option<Version> {
+    let version = version.trim();
+    let version = version.strip_prefix('v').unwrap_or(version);
+
+    if let Ok(parsed) = Version::parse(version) {
+        return Some(parsed);
+    }
+
+    // Split off pre-release/build metadata before padding the numeric part
+    let split = version.find(['-', '+']).unwrap_or(version.len());
+    let (core, rest) = version.split_at(split);
+    let components = core.split('.').count();
+    if components == 0 || components > 3 {
+        return None;
+    }
+
+    let padded = format!("{}{}{}", core, ".0".repeat(3 - components), rest);
+    Version::parse(&padded).ok()
+}
+
+pub fn parse_range(range: &str) -> Option<VersionReq> {
+    VersionReq::parse(range.trim()).ok()
+}
+
+/// Whether `version` falls within `range`.
+///
+/// Returns `None` if either side can't be parsed. Pre-releases are matched on
+/// their numeric core so `1.2.0-rc.1` is considered affected by `<1.3.0`.
+pub fn matches(range: &str, version: &str) -> Option<bool> {
+    let range = parse_range(range)?;
+    let version = parse_version(version)?;
+
+    if range.matches(&version) {
+        return Some(true);
+    }
+
+    let core = Version::new(version.major, version.minor, version.patch);
+    Some(!version.pre.is_empty() && range.matches(&core))
+}
+
+/// Pick the highest version, falling back to the most recent release for
+/// version strings that can't be parsed
+pub fn latest_version(versions: &[PackageVersion]) -> Option<String> {
+    versions
+        .iter()
+        .filter_map(|v| parse_version(&v.version).map(|parsed| (parsed, v)))
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, v)| v.version.clone())
+        .or_else(|| {
+            versions
+                .iter()
+                .max_by_key(|v| v.release_date)
+                .map(|v| v.version.clone())
+        })
+}
+
+/// The highest version that satisfies `range`
+pub fn highest_matching<'a>(
+    range: &str,
+    versions: impl Iterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let range = parse_range(range)?;
+    versions
+        .filter_map(|v| parse_version(v).map(|parsed| (parsed, v)))
+        .filter(|(parsed, _)| range.matches(parsed))
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, v)| v)
+}
+
+/// The lowest of `versions` above `current` that none of `ranges` include,
+/// i.e. the smallest upgrade clear of every known advisory. Pre-releases are
+/// only suggested to those already on one, and ranges that can't be parsed
+/// are ignored.
+pub fn minimal_upgrade<'a>(
+    current: &str,
+    ranges: &[&str],
+    versions: impl Iterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let current = parse_version(current)?;
+    versions
+        .filter_map(|v| parse_version(v).map(|parsed| (parsed, v)))
+        .filter(|(parsed, _)| *parsed > current)
+        .filter(|(parsed, _)| parsed.pre.is_empty() || !current.pre.is_empty())
+        .filter(|(_, v)| !ranges.iter().any(|range| matches(range, v) == Some(true)))
+        .min_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, v)| v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("1.2.3"), Some(Version::new(1, 2, 3)));
+        assert_eq!(parse_version("v1.2"), Some(Version::new(1, 2, 0)));
+        assert_eq!(parse_version("7"), Some(Version::new(7, 0, 0)));
+        assert_eq!(
+            parse_version("1.2-beta.1").map(|v| v.pre.to_string()),
+            Some("beta.1".to_string())
+        );
+        assert_eq!(parse_version("1.2.3.4"), None);
+        assert_eq!(parse_version("unstable-2024-01-01"), None);
+    }
+
+    #[test]
+    fn test_matches() {
+        assert_eq!(matches(">=1.0.0, <1.4.2", "1.3.9"), Some(true));
+        assert_eq!(matches(">=1.0.0, <1.4.2", "1.4.2"), Some(false));
+        assert_eq!(matches("<1.3.0", "1.2.0-rc.1"), Some(true));
+        assert_eq!(matches("not a range", "1.0.0"), None);
+    }
+
+    #[test]
+    fn test_minimal_upgrade() {
+        let versions = ["1.0.0", "1.0.1", "1.1.0-rc.1", "1.1.0", "1.2.0", "2.0.0"];
+        let upgrade =
+            |current, ranges: &[&str]| minimal_upgrade(current, ranges, versions.into_iter());
+        assert_eq!(upgrade("1.0.0", &["<1.0.1"]), Some("1.0.1"));
+        // Clear of every advisory, not just the first
+        assert_eq!(
+            upgrade("1.0.0", &["<1.0.1", ">=1.0.1, <1.1.0"]),
+            Some("1.1.0")
+        );
+        assert_eq!(
+            upgrade("1.1.0-beta.1", &["<1.1.0-rc.1"]),
+            Some("1.1.0-rc.1")
+        );
+        assert_eq!(upgrade("1.0.0", &["<1.1.0", "not a range"]), Some("1.1.0"));
+        assert_eq!(upgrade("1.0.0", &[">=1.0.0"]), None);
+        assert_eq!(upgrade("unstable", &["<2.0.0"]), None);
+    }
+
+    fn version() -> impl Strategy<Value = Version> {
+        let identifier = "[0-9A-Za-z-]{1,8}(\\.[0-9A-Za-z-]{1,8}){0,2}";
+        (
+            0..1000u64,
+            0..1000u64,
+            0..1000u64,
+            proptest::option::of(identifier),
+            proptest::option::of(identifier),
+        )
+            .prop_filter_map("invalid identifier", |(major, minor, patch, pre, build)| {
+                let mut version = Version::new(major, minor, patch);
+                if let Some(pre) = pre {
+                    version.pre = semver::Prerelease::new(&pre).ok()?;
+                }
+                if let Some(build) = build {
+                    version.build = semver::BuildMetadata::new(&build).ok()?;
+                }
+                Some(version)
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn parse_version_round_trips(version in version()) {
+            prop_assert_eq!(parse_version(&version.to_string()), Some(version.clone()));
+            prop_assert_eq!(parse_version(&format!(" v{} ", version)), Some(version));
+        }
+
+        #[test]
+        fn parse_version_pads(major in 0..1000u64, minor in 0..1000u64) {
+            prop_assert_eq!(
+                parse_version(&format!("{}.{}", major, minor)),
+                Some(Version::new(major, minor, 0))
+            );
+            prop_assert_eq!(parse_version(&major.to_string()), Some(Version::new(major, 0, 0)));
+        }
+
+        // Ranges and versions come from registries and advisories
+        #[test]
+        fn parsers_accept_any_input(input in "\\PC*") {
+            let _ = parse_version(&input);
+            let _ = parse_range(&input);
+            let _ = matches(&input, &input);
+        }
+
+        #[test]
+        fn range_round_trips(version in version()) {
+            for op in ["=", ">=", "<=", ">", "<", "^", "~"] {
+                let range = parse_range(&format!("{}{}", op, version)).unwrap();
+                prop_assert_eq!(parse_range(&range.to_string()), Some(range));
+            }
+        }
+
+        #[test]
+        fn bounds_match(version in version()) {
+            let text = version.to_string();
+            prop_assert_eq!(matches(&format!("={}", text), &text), Some(true));
+            prop_assert_eq!(matches(&format!(">={}", text), &text), Some(true));
+            prop_assert_eq!(matches(&format!("<{}", text), &text), Some(false));
+        }
+
+        #[test]
+        fn highest_matching_is_highest(
+            versions in proptest::collection::vec(version(), 0..20),
+            range in version(),
+        ) {
+            let range_text = format!(">={}", range);
+            let texts: Vec<String> = versions.iter().map(|v| v.to_string()).collect();
+            let highest = highest_matching(&range_text, texts.iter().map(String::as_str));
+
+            let req = parse_range(&range_text).unwrap();
+            let expected = versions.iter().filter(|v| req.matches(v)).max();
+            prop_assert_eq!(highest.and_then(parse_version), expected.cloned());
+        }
+    }
+}
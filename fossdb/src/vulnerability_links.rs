@@ -0,0 +1,175 @@
+//! Keeps `PackageVersion::vulnerabilities` in step with the advisories whose
+//! affected range includes each version
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+
+use crate::storage::Storage;
+use crate::{PackageVersion, version_range};
+
+/// Affected ranges by package, as (vulnerability ID, range) pairs
+type Ranges = HashMap<u64, Vec<(u64, String)>>;
+
+/// Ranges of every advisory affecting one of `packages`, or any package
+/// when None
+fn affected_ranges(db: &dyn Storage, packages: Option<&HashSet<u64>>) -> Result<Ranges> {
+    let mut ranges: Ranges = HashMap::new();
+    db.for_each_vulnerability(&mut |vulnerability| {
+        for affected in vulnerability.affected_packages {
+            if packages.is_none_or(|packages| packages.contains(&affected.package_id)) {
+                ranges
+                    .entry(affected.package_id)
+                    .or_default()
+                    .push((vulnerability.id, affected.version_range));
+            }
+        }
+        Ok(())
+    })?;
+    Ok(ranges)
+}
+
+/// IDs of the advisories including `version`, lowest first
+fn linked(ranges: &Ranges, version: &PackageVersion) -> Vec<String> {
+    let mut ids: Vec<u64> = ranges
+        .get(&version.package_id)
+        .into_iter()
+        .flatten()
+        .filter(|(_, range)| version_range::matches(range, &version.version) == Some(true))
+        .map(|(id, _)| *id)
+        .collect();
+    ids.sort_unstable();
+    ids.dedup();
+    ids.into_iter().map(|id| id.to_string()).collect()
+}
+
+/// Store the versions whose links changed. Returns how many did.
+fn relink(
+    db: &dyn Storage,
+    versions: impl IntoIterator<Item = PackageVersion>,
+    ranges: &Ranges,
+) -> Result<usize> {
+    let changed: Vec<PackageVersion> = versions
+        .into_iter()
+        .filter_map(|mut version| {
+            let vulnerabilities = linked(ranges, &version);
+            (version.vulnerabilities != vulnerabilities).then(|| {
+                version.vulnerabilities = vulnerabilities;
+                version
+            })
+        })
+        .collect();
+    let count = changed.len();
+    if count > 0 {
+        db.update_versions(changed)?;
+    }
+    Ok(count)
+}
+
+/// Link a newly stored version to the advisories affecting it
+pub fn link_version(db: &dyn Storage, version: PackageVersion) -> Result<usize> {
+    let packages = HashSet::from([version.package_id]);
+    let ranges = affected_ranges(db, Some(&packages))?;
+    relink(db, [version], &ranges)
+}
+
+/// Relink every version of `packages`, after an advisory affecting them was
+/// added, changed or removed
+pub fn link_packages(db: &dyn Storage, packages: HashSet<u64>) -> Result<usize> {
+    let ranges = affected_ranges(db, Some(&packages))?;
+    let mut count = 0;
+    for package_id in packages {
+        count += relink(db, db.get_versions_by_package(package_id)?, &ranges)?;
+    }
+    Ok(count)
+}
+
+/// Relink every version, catching up on changes made without going through
+/// the API, like advisories imported by a collector
+pub fn reconcile(db: &dyn Storage) -> Result<usize> {
+    let ranges = affected_ranges(db, None)?;
+    let mut stale = Vec::new();
+    db.for_each_version(&mut |version| {
+        if version.vulnerabilities != linked(&ranges, &version) {
+            stale.push(version);
+        }
+        Ok(())
+    })?;
+    relink(db, stale, &ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::fixtures;
+    use crate::{AffectedPackage, Vulnerability, VulnerabilitySeverity};
+
+    fn advisory(package_id: u64, version_range: &str) -> Vulnerability {
+        Vulnerability {
+            id: 0,
+            cve_id: None,
+            title: "Stack overflow".to_string(),
+            description: String::new(),
+            severity: VulnerabilitySeverity::High,
+            affected_packages: vec![AffectedPackage {
+                package_id,
+                version_range: version_range.to_string(),
+            }],
+            discovered_at: chrono::Utc::now(),
+            fixed_in: None,
+            cvss_vector: None,
+            cvss_score: None,
+            epss_score: None,
+            epss_percentile: None,
+            scores_updated_at: None,
+            informational: None,
+        }
+    }
+
+    #[test]
+    fn test_links() {
+        let db = Database::in_memory().unwrap();
+        let seeded = fixtures::seed(&db).unwrap();
+        let serde = seeded.serde.id;
+        let linked_versions = |db: &Database| -> Vec<(String, Vec<String>)> {
+            let mut versions: Vec<_> = db
+                .get_versions_by_package(serde)
+                .unwrap()
+                .into_iter()
+                .map(|v| (v.version, v.vulnerabilities))
+                .collect();
+            versions.sort();
+            versions
+        };
+
+        let old = db.insert_vulnerability(advisory(serde, "<1.0.1")).unwrap();
+        let all = db.insert_vulnerability(advisory(serde, ">=0.1.0")).unwrap();
+        assert!(link_packages(&db, HashSet::from([serde])).unwrap() > 0);
+        let versions = linked_versions(&db);
+        assert!(
+            versions
+                .iter()
+                .all(|(_, ids)| ids.contains(&all.id.to_string()))
+        );
+        assert!(
+            versions
+                .iter()
+                .any(|(_, ids)| ids == &[old.id.to_string(), all.id.to_string()])
+        );
+        // Nothing left to do once linked
+        assert_eq!(reconcile(&db).unwrap(), 0);
+
+        let new = db
+            .insert_version(fixtures::version(serde, "2.0.0"))
+            .unwrap();
+        assert_eq!(link_version(&db, new).unwrap(), 1);
+        assert!(linked_versions(&db).contains(&("2.0.0".to_string(), vec![all.id.to_string()])));
+
+        db.delete_vulnerability(all.id).unwrap();
+        reconcile(&db).unwrap();
+        assert!(
+            linked_versions(&db)
+                .iter()
+                .all(|(_, ids)| !ids.contains(&all.id.to_string()))
+        );
+    }
+}
@@ -2,32 +2,196 @@ use axum::{
     extract::{State, WebSocketUpgrade, ws::WebSocket},
     response::Response,
 };
-use futures::{SinkExt, StreamExt};
-use std::sync::Arc;
+use futures::{SinkExt, Stream, StreamExt};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::broadcast;
 
-/// Broadcaster for timeline events
-#[derive(Clone)]
+use crate::TimelineEvent;
+
+/// Largest batch sent in one frame; a full buffer is flushed immediately
+const MAX_BATCH_SIZE: usize = 500;
+
+/// Batches buffered per connection before it's considered too slow
+const CHANNEL_CAPACITY: usize = 64;
+
+/// Longest a single frame may take to send before the client is dropped
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Recent events kept for clients resuming after a disconnect
+const REPLAY_CAPACITY: usize = 1000;
+
+/// How often the server pings each client
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Silence after which a client is considered gone
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(75);
+
+/// How long a `Resume` sent before `Auth` waits for it, so personal events
+/// aren't skipped over by an anonymous replay
+const RESUME_AUTH_GRACE: Duration = Duration::from_secs(2);
+
+/// An event with its position in the broadcast stream
+#[derive(Debug, Clone)]
+struct Sequenced {
+    seq: u64,
+    event: TimelineEvent,
+}
+
+#[derive(Default)]
+struct Buffers {
+    next_seq: u64,
+    pending: Vec<Sequenced>,
+    recent: VecDeque<Sequenced>,
+}
+
+/// Broadcaster for timeline events.
+///
+/// Events are buffered and sent to clients in batches, either when the
+/// flusher ticks or when a batch fills up. The most recent events are also
+/// kept so reconnecting clients can catch up on what they missed.
 pub struct TimelineBroadcaster {
-    tx: broadcast::Sender<crate::TimelineEvent>,
+    tx: broadcast::Sender<Arc<Vec<Sequenced>>>,
+    buffers: Mutex<Buffers>,
+    /// Distinguishes this process's resume tokens from a previous run's
+    epoch: u64,
+    /// Connected WebSocket clients
+    clients: AtomicUsize,
 }
 
 impl TimelineBroadcaster {
     pub fn new() -> Self {
-        let (tx, _rx) = broadcast::channel(100);
-        Self { tx }
+        let (tx, _rx) = broadcast::channel(CHANNEL_CAPACITY);
+        let epoch = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        Self {
+            tx,
+            buffers: Mutex::new(Buffers {
+                next_seq: 1,
+                ..Default::default()
+            }),
+            epoch,
+            clients: AtomicUsize::new(0),
+        }
+    }
+
+    /// How many WebSocket clients are connected
+    pub fn client_count(&self) -> usize {
+        self.clients.load(Ordering::Relaxed)
     }
 
-    /// Broadcast a timeline event to all connected clients
-    pub fn broadcast(&self, event: crate::TimelineEvent) {
+    /// Queue a timeline event for all connected clients
+    pub fn broadcast(&self, event: TimelineEvent) {
+        let full = {
+            let mut buffers = self.buffers.lock().unwrap();
+            let sequenced = Sequenced {
+                seq: buffers.next_seq,
+                event,
+            };
+            buffers.next_seq += 1;
+
+            if buffers.recent.len() == REPLAY_CAPACITY {
+                buffers.recent.pop_front();
+            }
+            buffers.recent.push_back(sequenced.clone());
+            buffers.pending.push(sequenced);
+            buffers.pending.len() >= MAX_BATCH_SIZE
+        };
+        if full {
+            self.flush();
+        }
+    }
+
+    /// Send everything queued so far
+    pub fn flush(&self) {
+        let events = std::mem::take(&mut self.buffers.lock().unwrap().pending);
+        if events.is_empty() {
+            return;
+        }
         // Ignore send errors - they just mean no receivers are listening
-        let _ = self.tx.send(event);
+        let _ = self.tx.send(Arc::new(events));
     }
 
-    /// Subscribe to timeline events
-    fn subscribe(&self) -> broadcast::Receiver<crate::TimelineEvent> {
+    /// Subscribe to batches of timeline events
+    fn subscribe(&self) -> broadcast::Receiver<Arc<Vec<Sequenced>>> {
         self.tx.subscribe()
     }
+
+    /// Global events as they're flushed, for consumers other than WebSocket
+    /// clients. Ends if the consumer falls too far behind
+    pub fn global_events(&self) -> impl Stream<Item = TimelineEvent> + Send + 'static {
+        futures::stream::unfold(self.subscribe(), |mut rx| async move {
+            let batch = rx.recv().await.ok()?;
+            Some((batch, rx))
+        })
+        .flat_map(|batch| {
+            let events: Vec<TimelineEvent> = batch
+                .iter()
+                .filter(|e| is_visible(None, &e.event))
+                .map(|e| e.event.clone())
+                .collect();
+            futures::stream::iter(events)
+        })
+    }
+
+    fn resume_token(&self, seq: u64) -> String {
+        format!("{:x}-{}", self.epoch, seq)
+    }
+
+    /// Events broadcast after the one a resume token points at, or None for
+    /// tokens from another run, older than the replay buffer or ahead of
+    /// anything broadcast, which can't be honoured
+    fn replay_since(&self, token: &str) -> Option<Vec<Sequenced>> {
+        let buffers = self.buffers.lock().unwrap();
+        let last_seq = token
+            .split_once('-')
+            .filter(|(epoch, _)| u64::from_str_radix(epoch, 16).ok() == Some(self.epoch))
+            .and_then(|(_, seq)| seq.parse::<u64>().ok())?;
+
+        let oldest = buffers.recent.front().map_or(buffers.next_seq, |e| e.seq);
+        if last_seq >= buffers.next_seq || last_seq.saturating_add(1) < oldest {
+            return None;
+        }
+        let events = buffers
+            .recent
+            .iter()
+            .filter(|e| e.seq > last_seq)
+            .cloned()
+            .collect();
+        Some(events)
+    }
+}
+
+impl Default for TimelineBroadcaster {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Periodically flush queued events to clients
+pub fn spawn_flusher(broadcaster: Arc<TimelineBroadcaster>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            broadcaster.flush();
+        }
+    });
+}
+
+/// Whether a connection should receive an event:
+/// - If not authenticated: only global events (user_id = None)
+/// - If authenticated: only events for this user
+fn is_visible(user_id: Option<u64>, event: &TimelineEvent) -> bool {
+    match (user_id, event.user_id) {
+        (None, None) => true,
+        (Some(uid), Some(event_uid)) => uid == event_uid,
+        _ => false,
+    }
 }
 
 /// WebSocket handler for timeline updates
@@ -38,88 +202,213 @@ pub async fn timeline_websocket_handler(
     ws.on_upgrade(move |socket| handle_socket(socket, state.broadcaster))
 }
 
+/// Counts a client as connected for as long as it's held
+struct Connected(Arc<TimelineBroadcaster>);
+
+impl Connected {
+    fn new(broadcaster: &Arc<TimelineBroadcaster>) -> Self {
+        broadcaster.clients.fetch_add(1, Ordering::Relaxed);
+        Self(broadcaster.clone())
+    }
+}
+
+impl Drop for Connected {
+    fn drop(&mut self) {
+        self.0.clients.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+enum ClientRequest {
+    Auth(u64),
+    Ping,
+    Resume(String),
+}
+
+/// What a resuming client missed before the first live batch it received,
+/// followed by `Resumed`, or just `ResyncRequired` if its token can't be
+/// honoured
+fn resume(
+    broadcaster: &TimelineBroadcaster,
+    token: &str,
+    user_id: Option<u64>,
+    live_from: Option<u64>,
+    delivered: &mut u64,
+) -> (Vec<TimelineEvent>, u64, Option<crate::WebSocketMessage>) {
+    let Some(missed) = broadcaster.replay_since(token) else {
+        tracing::debug!("Resume token can't be honoured, asking client to resync");
+        return (Vec::new(), 0, Some(crate::WebSocketMessage::ResyncRequired));
+    };
+    let missed: Vec<Sequenced> = missed
+        .into_iter()
+        .filter(|e| match live_from {
+            Some(from) => e.seq < from,
+            None => e.seq > *delivered,
+        })
+        .collect();
+    if live_from.is_none() {
+        *delivered = missed.last().map_or(*delivered, |e| e.seq);
+    }
+    let events: Vec<TimelineEvent> = missed
+        .into_iter()
+        .filter(|e| is_visible(user_id, &e.event))
+        .map(|e| e.event)
+        .collect();
+    tracing::debug!("Replaying {} timeline events", events.len());
+    (events, *delivered, Some(crate::WebSocketMessage::Resumed))
+}
+
 async fn handle_socket(socket: WebSocket, broadcaster: Arc<TimelineBroadcaster>) {
     tracing::debug!("New WebSocket connection established");
+    let _connected = Connected::new(&broadcaster);
     let (mut sender, mut receiver) = socket.split();
     let mut rx = broadcaster.subscribe();
     let mut user_id: Option<u64> = None;
+    // Highest sequence number already handled, and the first one received
+    // live, so replays and live batches don't overlap
+    let mut delivered: u64 = 0;
+    let mut live_from: Option<u64> = None;
+    // A resume waiting for the client to authenticate, and how long it waits
+    let mut pending_resume: Option<String> = None;
+    let mut resume_deadline = tokio::time::Instant::now();
 
-    // Use channels to communicate from receiver to sender
-    let (auth_tx, mut auth_rx) = tokio::sync::mpsc::channel::<u64>(1);
-    let (ping_tx, mut ping_rx) = tokio::sync::mpsc::channel::<()>(1);
+    // Use a channel to communicate from receiver to sender
+    let (request_tx, mut request_rx) = tokio::sync::mpsc::channel::<ClientRequest>(4);
+    let last_seen = Arc::new(Mutex::new(Instant::now()));
 
     // Spawn a task to receive messages from the client
+    let client_seen = last_seen.clone();
     let mut recv_task = tokio::spawn(async move {
         while let Some(Ok(msg)) = receiver.next().await {
-            if let axum::extract::ws::Message::Text(text) = msg
-                && let Ok(ws_msg) = serde_json::from_str::<crate::WebSocketMessage>(&text)
-            {
-                match ws_msg {
-                    crate::WebSocketMessage::Auth { token } => {
-                        // Verify JWT and extract user_id
-                        if let Ok(claims) = crate::auth::verify_jwt(&token)
-                            && let Ok(uid) = claims.sub.parse::<u64>()
-                        {
-                            let _ = auth_tx.send(uid).await;
-                        }
-                    }
-                    crate::WebSocketMessage::Ping => {
-                        // Notify send task to respond with Pong
-                        let _ = ping_tx.send(()).await;
+            // Any traffic, including pongs, shows the client is alive
+            *client_seen.lock().unwrap() = Instant::now();
+
+            let axum::extract::ws::Message::Text(text) = msg else {
+                continue;
+            };
+            let request = match serde_json::from_str::<crate::WebSocketMessage>(&text) {
+                Ok(crate::WebSocketMessage::Auth { token }) => {
+                    // Verify JWT and extract user_id
+                    match crate::auth::verify_jwt(&token)
+                        .ok()
+                        .and_then(|claims| claims.sub.parse::<u64>().ok())
+                    {
+                        Some(uid) => ClientRequest::Auth(uid),
+                        None => continue,
                     }
-                    _ => {}
                 }
+                Ok(crate::WebSocketMessage::Ping) => ClientRequest::Ping,
+                Ok(crate::WebSocketMessage::Resume { token }) => ClientRequest::Resume(token),
+                _ => continue,
+            };
+            if request_tx.send(request).await.is_err() {
+                break;
             }
         }
     });
 
     // Spawn a task to send messages to the client
     let mut send_task = tokio::spawn(async move {
-        loop {
-            tokio::select! {
-                // Receive timeline events from the broadcaster
-                Ok(db_event) = rx.recv() => {
-                    // Filter events based on authentication:
-                    // - If not authenticated: only send global events (user_id = None)
-                    // - If authenticated: only send events for this user
-                    let should_send = match (user_id, db_event.user_id) {
-                        (None, None) => true,  // Not authenticated, global event
-                        (Some(uid), Some(event_uid)) if uid == event_uid => true,  // Authenticated, personal event
-                        _ => false,  // Don't send
-                    };
+        let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+        heartbeat.tick().await;
 
-                    if should_send {
-                        let msg = crate::WebSocketMessage::TimelineEvent { event: db_event };
-                        let json = serde_json::to_string(&msg).unwrap();
-                        if sender.send(axum::extract::ws::Message::Text(json.into())).await.is_err() {
+        loop {
+            let (events, resume_seq, extra) = tokio::select! {
+                // Receive batches of timeline events from the broadcaster
+                batch = rx.recv() => {
+                    let batch = match batch {
+                        Ok(batch) => batch,
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            tracing::warn!(
+                                "Dropping slow WebSocket client that fell {} batches behind",
+                                skipped
+                            );
                             break;
                         }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    };
+
+                    if let Some(first) = batch.first() {
+                        live_from.get_or_insert(first.seq);
                     }
+                    let last_seq = batch.last().map_or(delivered, |e| e.seq);
+                    let events: Vec<TimelineEvent> = batch
+                        .iter()
+                        .filter(|e| e.seq > delivered && is_visible(user_id, &e.event))
+                        .map(|e| e.event.clone())
+                        .collect();
+                    delivered = delivered.max(last_seq);
+                    (events, delivered, None)
                 }
 
-                // Handle client authentication
-                Some(uid) = auth_rx.recv() => {
-                    user_id = Some(uid);
-                    tracing::debug!("WebSocket authenticated user: {}", uid);
-                    // Note: WebSocketMessage doesn't have an Authenticated variant,
-                    // so we don't send a response. Client knows auth succeeded when they get personal events.
+                Some(request) = request_rx.recv() => match request {
+                    // Handle client authentication
+                    ClientRequest::Auth(uid) => {
+                        user_id = Some(uid);
+                        tracing::debug!("WebSocket authenticated user: {}", uid);
+                        // Note: WebSocketMessage doesn't have an Authenticated variant,
+                        // so we don't send a response. Client knows auth succeeded when they get personal events.
+                        match pending_resume.take() {
+                            Some(token) => {
+                                resume(&broadcaster, &token, user_id, live_from, &mut delivered)
+                            }
+                            None => continue,
+                        }
+                    }
+
+                    // Respond to client ping
+                    ClientRequest::Ping => (Vec::new(), 0, Some(crate::WebSocketMessage::Pong)),
+
+                    // Give a client that resumes first the chance to authenticate
+                    ClientRequest::Resume(token) if user_id.is_none() => {
+                        pending_resume = Some(token);
+                        resume_deadline = tokio::time::Instant::now() + RESUME_AUTH_GRACE;
+                        continue;
+                    }
+
+                    // Replay what the client missed while disconnected
+                    ClientRequest::Resume(token) => {
+                        resume(&broadcaster, &token, user_id, live_from, &mut delivered)
+                    }
+                },
+
+                // Clients that never authenticate resume anonymously
+                _ = tokio::time::sleep_until(resume_deadline), if pending_resume.is_some() => {
+                    let token = pending_resume.take().unwrap_or_default();
+                    resume(&broadcaster, &token, user_id, live_from, &mut delivered)
                 }
 
-                // Respond to client ping
-                Some(()) = ping_rx.recv() => {
-                    let msg = crate::WebSocketMessage::Pong;
-                    let json = serde_json::to_string(&msg).unwrap();
-                    if sender.send(axum::extract::ws::Message::Text(json.into())).await.is_err() {
+                // Ping the client, dropping it if it has stopped answering
+                _ = heartbeat.tick() => {
+                    if last_seen.lock().unwrap().elapsed() > HEARTBEAT_TIMEOUT {
+                        tracing::debug!("WebSocket client missed its heartbeat");
+                        break;
+                    }
+                    let ping = axum::extract::ws::Message::Ping(Default::default());
+                    if sender.send(ping).await.is_err() {
                         break;
                     }
+                    continue;
                 }
+            };
 
-                // Send periodic server-side pings for keepalive
-                _ = tokio::time::sleep(tokio::time::Duration::from_secs(30)) => {
-                    let msg = crate::WebSocketMessage::Ping;
-                    let json = serde_json::to_string(&msg).unwrap();
-                    if sender.send(axum::extract::ws::Message::Text(json.into())).await.is_err() {
-                        break;
+            let mut messages = Vec::new();
+            if !events.is_empty() {
+                messages.push(crate::WebSocketMessage::TimelineEvents {
+                    events,
+                    resume_token: Some(broadcaster.resume_token(resume_seq)),
+                });
+            }
+            messages.extend(extra);
+
+            for msg in messages {
+                let json = serde_json::to_string(&msg).unwrap();
+                let send = sender.send(axum::extract::ws::Message::Text(json.into()));
+                match tokio::time::timeout(SEND_TIMEOUT, send).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(_)) => return,
+                    Err(_) => {
+                        tracing::warn!("Dropping WebSocket client that stopped reading");
+                        return;
                     }
                 }
             }
@@ -133,3 +422,92 @@ async fn handle_socket(socket: WebSocket, broadcaster: Arc<TimelineBroadcaster>)
     }
     tracing::debug!("WebSocket connection closed");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EventType;
+    use chrono::Utc;
+
+    fn event(id: u64) -> TimelineEvent {
+        TimelineEvent {
+            id,
+            package_id: 1,
+            user_id: None,
+            event_type: EventType::NewRelease,
+            package_name: "serde".to_string(),
+            version: Some(format!("1.0.{}", id)),
+            message: String::new(),
+            metadata: None,
+            created_at: Utc::now(),
+            notified_at: None,
+        }
+    }
+
+    #[test]
+    fn test_broadcaster_batches_until_flush() {
+        let broadcaster = TimelineBroadcaster::new();
+        let mut rx = broadcaster.subscribe();
+
+        broadcaster.broadcast(event(1));
+        broadcaster.broadcast(event(2));
+        assert!(rx.try_recv().is_err());
+
+        broadcaster.flush();
+        let batch = rx.try_recv().unwrap();
+        assert_eq!(batch.iter().map(|e| e.event.id).collect::<Vec<_>>(), vec![1, 2]);
+
+        // A full batch goes out without waiting for the flusher
+        for id in 0..MAX_BATCH_SIZE as u64 {
+            broadcaster.broadcast(event(id));
+        }
+        assert_eq!(rx.try_recv().unwrap().len(), MAX_BATCH_SIZE);
+    }
+
+    #[test]
+    fn test_replay_since_token() {
+        let broadcaster = TimelineBroadcaster::new();
+        for id in 1..=3 {
+            broadcaster.broadcast(event(id));
+        }
+
+        let missed = broadcaster
+            .replay_since(&broadcaster.resume_token(1))
+            .unwrap();
+        assert_eq!(
+            missed.iter().map(|e| e.event.id).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+
+        // Tokens from a previous server run can't say what was missed
+        assert!(broadcaster.replay_since("0-1").is_none());
+        assert!(broadcaster.replay_since("garbage").is_none());
+        // Nor can ones for events that were never broadcast
+        let future = broadcaster.resume_token(u64::MAX);
+        assert!(broadcaster.replay_since(&future).is_none());
+
+        // Nor can tokens older than the replay buffer
+        for id in 0..REPLAY_CAPACITY as u64 {
+            broadcaster.broadcast(event(id));
+        }
+        assert!(
+            broadcaster
+                .replay_since(&broadcaster.resume_token(1))
+                .is_none()
+        );
+        let token = broadcaster.resume_token(2);
+        let mut delivered = 0;
+        let (events, _, message) = resume(&broadcaster, &token, None, None, &mut delivered);
+        assert!(events.is_empty());
+        assert!(matches!(
+            message,
+            Some(crate::WebSocketMessage::ResyncRequired)
+        ));
+
+        // The newest token replays nothing but still confirms the resume
+        let token = broadcaster.resume_token(REPLAY_CAPACITY as u64 + 3);
+        let (events, _, message) = resume(&broadcaster, &token, None, None, &mut delivered);
+        assert!(events.is_empty());
+        assert!(matches!(message, Some(crate::WebSocketMessage::Resumed)));
+    }
+}